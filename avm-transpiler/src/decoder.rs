@@ -0,0 +1,352 @@
+use std::fmt;
+
+use acvm::{AcirField, FieldElement};
+
+use crate::instructions::{AvmInstruction, AvmOperand, AvmTypeTag};
+use crate::opcodes::AvmOpcode;
+
+/// An error raised while decoding AVM bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The byte at the start of an instruction isn't a known opcode
+    InvalidOpcode { pc: usize, byte: u8 },
+    /// The type tag of an instruction isn't a known tag
+    InvalidTag { pc: usize, byte: u8 },
+    /// The bytecode ends before the last instruction is complete
+    UnexpectedEnd { pc: usize, opcode: AvmOpcode },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidOpcode { pc, byte } => {
+                write!(f, "Invalid opcode byte {byte:#04x} at AVM PC {pc}")
+            }
+            DecodeError::InvalidTag { pc, byte } => {
+                write!(f, "Invalid type tag {byte} at AVM PC {pc}")
+            }
+            DecodeError::UnexpectedEnd { pc, opcode } => write!(
+                f,
+                "Bytecode ends in the middle of the {} instruction at AVM PC {pc}",
+                opcode.name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes a whole AVM program, returning each instruction along with its AVM PC.
+pub fn decode_avm_bytecode(bytecode: &[u8]) -> Result<Vec<(usize, AvmInstruction)>, DecodeError> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < bytecode.len() {
+        let (instruction, size) = decode_instruction(bytecode, pc)?;
+        instructions.push((pc, instruction));
+        pc += size;
+    }
+    Ok(instructions)
+}
+
+/// Decodes the instruction starting at the given AVM PC, returning it along with its size in
+/// bytes.
+fn decode_instruction(bytecode: &[u8], pc: usize) -> Result<(AvmInstruction, usize), DecodeError> {
+    let byte = bytecode[pc];
+    let opcode = AvmOpcode::from_u8(byte).ok_or(DecodeError::InvalidOpcode { pc, byte })?;
+    let format = instruction_format(opcode);
+
+    let mut reader = BytecodeReader { bytecode, pc, opcode, position: pc + 1 };
+    let indirect = format.indirect.map(|size| reader.read_operand(size)).transpose()?;
+    let operands = format
+        .operands
+        .iter()
+        .map(|size| reader.read_operand(*size))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tag = if format.tag { Some(reader.read_tag()?) } else { None };
+    let immediates = format
+        .immediates
+        .iter()
+        .map(|size| reader.read_operand(*size))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let instruction = AvmInstruction { opcode, indirect, tag, operands, immediates };
+    Ok((instruction, reader.position - pc))
+}
+
+#[derive(Clone, Copy)]
+enum OperandSize {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    FF,
+}
+
+/// The encoding of the bytes following the opcode of an instruction.
+/// Fields are encoded in the same order as in `AvmInstruction::to_bytes`.
+struct InstructionFormat {
+    indirect: Option<OperandSize>,
+    operands: &'static [OperandSize],
+    tag: bool,
+    immediates: &'static [OperandSize],
+}
+
+/// Keep updated with the wire formats of TS and cpp!
+fn instruction_format(opcode: AvmOpcode) -> InstructionFormat {
+    use OperandSize::*;
+
+    fn format(
+        indirect: Option<OperandSize>,
+        operands: &'static [OperandSize],
+        tag: bool,
+        immediates: &'static [OperandSize],
+    ) -> InstructionFormat {
+        InstructionFormat { indirect, operands, tag, immediates }
+    }
+
+    match opcode {
+        // Compute
+        AvmOpcode::ADD_8
+        | AvmOpcode::SUB_8
+        | AvmOpcode::MUL_8
+        | AvmOpcode::DIV_8
+        | AvmOpcode::FDIV_8
+        | AvmOpcode::EQ_8
+        | AvmOpcode::LT_8
+        | AvmOpcode::LTE_8
+        | AvmOpcode::AND_8
+        | AvmOpcode::OR_8
+        | AvmOpcode::XOR_8
+        | AvmOpcode::SHL_8
+        | AvmOpcode::SHR_8 => format(Some(U8), &[U8, U8, U8], false, &[]),
+        AvmOpcode::ADD_16
+        | AvmOpcode::SUB_16
+        | AvmOpcode::MUL_16
+        | AvmOpcode::DIV_16
+        | AvmOpcode::FDIV_16
+        | AvmOpcode::EQ_16
+        | AvmOpcode::LT_16
+        | AvmOpcode::LTE_16
+        | AvmOpcode::AND_16
+        | AvmOpcode::OR_16
+        | AvmOpcode::XOR_16
+        | AvmOpcode::SHL_16
+        | AvmOpcode::SHR_16 => format(Some(U8), &[U16, U16, U16], false, &[]),
+        AvmOpcode::NOT_8 | AvmOpcode::MOV_8 | AvmOpcode::REVERT_8 => {
+            format(Some(U8), &[U8, U8], false, &[])
+        }
+        AvmOpcode::NOT_16 | AvmOpcode::MOV_16 | AvmOpcode::REVERT_16 => {
+            format(Some(U8), &[U16, U16], false, &[])
+        }
+        AvmOpcode::CAST_8 => format(Some(U8), &[U8, U8], true, &[]),
+        AvmOpcode::CAST_16 => format(Some(U8), &[U16, U16], true, &[]),
+
+        // Execution environment
+        AvmOpcode::GETENVVAR_16 => format(Some(U8), &[U16], false, &[U8]),
+        AvmOpcode::RETURNDATASIZE | AvmOpcode::EMITNOTEHASH | AvmOpcode::EMITNULLIFIER => {
+            format(Some(U8), &[U16], false, &[])
+        }
+
+        // Control flow
+        AvmOpcode::JUMP_32 | AvmOpcode::INTERNALCALL => format(None, &[], false, &[U32]),
+        AvmOpcode::JUMPI_32 => format(Some(U8), &[U16], false, &[U32]),
+        AvmOpcode::INTERNALRETURN => format(None, &[], false, &[]),
+
+        // Memory
+        AvmOpcode::SET_8 => format(Some(U8), &[U8], true, &[U8]),
+        AvmOpcode::SET_16 => format(Some(U8), &[U16], true, &[U16]),
+        AvmOpcode::SET_32 => format(Some(U8), &[U16], true, &[U32]),
+        AvmOpcode::SET_64 => format(Some(U8), &[U16], true, &[U64]),
+        AvmOpcode::SET_128 => format(Some(U8), &[U16], true, &[U128]),
+        AvmOpcode::SET_FF => format(Some(U8), &[U16], true, &[FF]),
+
+        // World state, external calls and gadgets
+        AvmOpcode::SLOAD
+        | AvmOpcode::SSTORE
+        | AvmOpcode::EMITUNENCRYPTEDLOG
+        | AvmOpcode::SENDL2TOL1MSG
+        | AvmOpcode::RETURN
+        | AvmOpcode::POSEIDON2
+        | AvmOpcode::KECCAKF1600 => format(Some(U8), &[U16, U16], false, &[]),
+        AvmOpcode::CALLDATACOPY
+        | AvmOpcode::RETURNDATACOPY
+        | AvmOpcode::NOTEHASHEXISTS
+        | AvmOpcode::NULLIFIEREXISTS
+        | AvmOpcode::L1TOL2MSGEXISTS
+        | AvmOpcode::SHA256COMPRESSION => format(Some(U8), &[U16, U16, U16], false, &[]),
+        AvmOpcode::GETCONTRACTINSTANCE => format(Some(U8), &[U16, U16, U16], false, &[U8]),
+        AvmOpcode::DEBUGLOG => format(Some(U8), &[U16, U16, U16], false, &[U16]),
+        AvmOpcode::MSM => format(Some(U8), &[U16, U16, U16, U16], false, &[]),
+        AvmOpcode::CALL | AvmOpcode::STATICCALL | AvmOpcode::TORADIXBE => {
+            format(Some(U16), &[U16, U16, U16, U16, U16], false, &[])
+        }
+        AvmOpcode::ECADD => format(Some(U16), &[U16, U16, U16, U16, U16, U16, U16], false, &[]),
+    }
+}
+
+struct BytecodeReader<'a> {
+    bytecode: &'a [u8],
+    /// PC of the instruction being decoded
+    pc: usize,
+    opcode: AvmOpcode,
+    /// Position of the next byte to read
+    position: usize,
+}
+
+impl<'a> BytecodeReader<'a> {
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let bytes = self
+            .bytecode
+            .get(self.position..self.position + N)
+            .ok_or(DecodeError::UnexpectedEnd { pc: self.pc, opcode: self.opcode })?;
+        self.position += N;
+
+        let mut array = [0; N];
+        array.copy_from_slice(bytes);
+        Ok(array)
+    }
+
+    fn read_operand(&mut self, size: OperandSize) -> Result<AvmOperand, DecodeError> {
+        Ok(match size {
+            OperandSize::U8 => AvmOperand::U8 { value: u8::from_be_bytes(self.read_bytes()?) },
+            OperandSize::U16 => AvmOperand::U16 { value: u16::from_be_bytes(self.read_bytes()?) },
+            OperandSize::U32 => AvmOperand::U32 { value: u32::from_be_bytes(self.read_bytes()?) },
+            OperandSize::U64 => AvmOperand::U64 { value: u64::from_be_bytes(self.read_bytes()?) },
+            OperandSize::U128 => {
+                AvmOperand::U128 { value: u128::from_be_bytes(self.read_bytes()?) }
+            }
+            OperandSize::FF => AvmOperand::FF {
+                value: FieldElement::from_be_bytes_reduce(&self.read_bytes::<32>()?),
+            },
+        })
+    }
+
+    fn read_tag(&mut self) -> Result<AvmTypeTag, DecodeError> {
+        let [byte] = self.read_bytes()?;
+        AvmTypeTag::from_u8(byte).ok_or(DecodeError::InvalidTag { pc: self.pc, byte })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::brillig::{BitSize, HeapVector, IntegerBitSize, MemoryAddress};
+    use acvm::brillig_vm::brillig::{BinaryFieldOp, BinaryIntOp, Opcode as BrilligOpcode};
+
+    use super::*;
+    use crate::transpile::{brillig_to_avm, BrilligToAvmOptions};
+
+    /// Builds an instruction following the format of the opcode, with distinct values in each
+    /// field so that fields which get swapped or misaligned are noticed.
+    fn sample_instruction(opcode: AvmOpcode) -> AvmInstruction {
+        let format = instruction_format(opcode);
+        let mut next_value = 0_u8;
+        let mut sample_operand = |size: &OperandSize| {
+            next_value += 1;
+            let value = next_value;
+            match size {
+                OperandSize::U8 => AvmOperand::U8 { value },
+                OperandSize::U16 => AvmOperand::U16 { value: (u16::from(value) << 8) | 0x01 },
+                OperandSize::U32 => AvmOperand::U32 { value: (u32::from(value) << 24) | 0x0203 },
+                OperandSize::U64 => AvmOperand::U64 { value: (u64::from(value) << 56) | 0x0405 },
+                OperandSize::U128 => {
+                    AvmOperand::U128 { value: (u128::from(value) << 120) | 0x0607 }
+                }
+                OperandSize::FF => AvmOperand::FF { value: -FieldElement::from(u128::from(value)) },
+            }
+        };
+        let indirect = format.indirect.as_ref().map(&mut sample_operand);
+        let operands = format.operands.iter().map(&mut sample_operand).collect();
+        let tag = format.tag.then_some(AvmTypeTag::UINT64);
+        let immediates = format.immediates.iter().map(&mut sample_operand).collect();
+        AvmInstruction { opcode, indirect, tag, operands, immediates }
+    }
+
+    #[test]
+    fn every_opcode_round_trips() {
+        let opcodes: Vec<_> = (0..=u8::MAX).map_while(AvmOpcode::from_u8).collect();
+        assert_eq!(opcodes.len(), 68);
+
+        for (byte, opcode) in opcodes.into_iter().enumerate() {
+            assert_eq!(opcode as usize, byte, "{} is out of order", opcode.name());
+
+            let instruction = sample_instruction(opcode);
+            let bytes = instruction.to_bytes();
+            let decoded = decode_avm_bytecode(&bytes).unwrap();
+            assert_eq!(decoded.len(), 1, "{} decoded to several instructions", opcode.name());
+            let (pc, decoded) = &decoded[0];
+            assert_eq!(*pc, 0);
+            assert_eq!(decoded.to_string(), instruction.to_string());
+            assert_eq!(decoded.to_bytes(), bytes);
+
+            if bytes.len() > 1 {
+                let truncated = decode_avm_bytecode(&bytes[..bytes.len() - 1]);
+                assert_eq!(truncated.unwrap_err(), DecodeError::UnexpectedEnd { pc: 0, opcode });
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_transpiled_programs() {
+        let direct = MemoryAddress::direct;
+        let brillig_bytecode = [
+            BrilligOpcode::Const {
+                destination: direct(0),
+                bit_size: BitSize::Integer(IntegerBitSize::U32),
+                value: FieldElement::from(5_u128),
+            },
+            BrilligOpcode::Const {
+                destination: direct(1000),
+                bit_size: BitSize::Field,
+                value: -FieldElement::from(1_u128),
+            },
+            BrilligOpcode::BinaryIntOp {
+                destination: direct(2),
+                op: BinaryIntOp::Add,
+                bit_size: IntegerBitSize::U32,
+                lhs: direct(0),
+                rhs: direct(0),
+            },
+            BrilligOpcode::BinaryFieldOp {
+                destination: direct(300),
+                op: BinaryFieldOp::Mul,
+                lhs: direct(1000),
+                rhs: direct(1000),
+            },
+            BrilligOpcode::Not {
+                destination: direct(3),
+                source: direct(2),
+                bit_size: IntegerBitSize::U32,
+            },
+            BrilligOpcode::Cast {
+                destination: direct(4),
+                source: direct(3),
+                bit_size: BitSize::Integer(IntegerBitSize::U64),
+            },
+            BrilligOpcode::Mov { destination: direct(5), source: direct(4) },
+            BrilligOpcode::JumpIf { condition: direct(0), location: 9 },
+            BrilligOpcode::Jump { location: 9 },
+            BrilligOpcode::Call { location: 11 },
+            BrilligOpcode::Stop { return_data: HeapVector { pointer: direct(0), size: direct(2) } },
+            BrilligOpcode::Return,
+        ];
+        for peephole in [true, false] {
+            let options = BrilligToAvmOptions { peephole };
+            let (bytecode, _) = brillig_to_avm(&brillig_bytecode, options).unwrap();
+
+            let decoded = decode_avm_bytecode(&bytecode).unwrap();
+            let mut reencoded = Vec::new();
+            for (pc, instruction) in decoded {
+                assert_eq!(pc, reencoded.len());
+                reencoded.extend(instruction.to_bytes());
+            }
+            assert_eq!(reencoded, bytecode);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_opcodes() {
+        let result = decode_avm_bytecode(&[u8::MAX]);
+        assert_eq!(result.unwrap_err(), DecodeError::InvalidOpcode { pc: 0, byte: u8::MAX });
+    }
+}
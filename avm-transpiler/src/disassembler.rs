@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use base64::Engine;
+
+use crate::decoder::decode_avm_bytecode;
+use crate::errors::{find_source_location, SourceLocation};
+use crate::transpile_contract::{
+    AvmContractFunctionArtifact, AvmOrAcirContractFunctionArtifact, TranspiledContractArtifact,
+};
+
+/// Disassembles the AVM functions of a transpiled contract, or only the one with the given name.
+/// Each instruction is printed with its AVM PC, preceded by the Noir source line it comes from
+/// whenever that line changes.
+pub fn disassemble_contract(
+    contract: &TranspiledContractArtifact,
+    function_name: Option<&str>,
+) -> Result<String, String> {
+    let avm_functions = contract.functions.iter().filter_map(|function| match function {
+        AvmOrAcirContractFunctionArtifact::Avm(function)
+            if function_name.map_or(true, |name| name == function.name) =>
+        {
+            Some(function)
+        }
+        _ => None,
+    });
+
+    let mut output = String::new();
+    for function in avm_functions {
+        disassemble_function(&mut output, contract, function)?;
+    }
+    if output.is_empty() {
+        return Err(match function_name {
+            Some(name) => format!("Contract {} has no AVM function named {name}", contract.name),
+            None => format!("Contract {} has no AVM functions", contract.name),
+        });
+    }
+    Ok(output)
+}
+
+fn disassemble_function(
+    output: &mut String,
+    contract: &TranspiledContractArtifact,
+    function: &AvmContractFunctionArtifact,
+) -> Result<(), String> {
+    let function_error = |error: String| {
+        format!("Unable to disassemble {}::{}: {error}", contract.name, function.name)
+    };
+    let bytecode = base64::prelude::BASE64_STANDARD
+        .decode(&function.bytecode)
+        .map_err(|error| function_error(error.to_string()))?;
+    let instructions =
+        decode_avm_bytecode(&bytecode).map_err(|error| function_error(error.to_string()))?;
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    // Writing to a `String` can't fail.
+    let _ = writeln!(output, "{}::{} ({} bytes)", contract.name, function.name, bytecode.len());
+    let mut previous_location: Option<SourceLocation> = None;
+    for (avm_pc, instruction) in instructions {
+        if let Some(location) =
+            find_source_location(avm_pc, &function.debug_symbols, &contract.file_map)
+        {
+            let same_line = previous_location.as_ref().is_some_and(|previous| {
+                previous.path == location.path && previous.line == location.line
+            });
+            if !same_line {
+                let _ = writeln!(
+                    output,
+                    "        // {}:{}: {}",
+                    location.path,
+                    location.line,
+                    location.source_line.trim()
+                );
+            }
+            previous_location = Some(location);
+        }
+        let _ = writeln!(output, "{avm_pc:>6}: {instruction}");
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use acvm::acir::circuit::brillig::BrilligFunctionId;
+use acvm::acir::circuit::BrilligOpcodeLocation;
+use noirc_errors::debug_info::ProgramDebugInfo;
+
+/// The reason why a Brillig program couldn't be transpiled to AVM bytecode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranspileErrorKind {
+    /// The ACIR program doesn't have the shape expected for an AVM function
+    InvalidProgram(String),
+    /// The Brillig opcode has no AVM equivalent
+    UnsupportedOpcode(String),
+    /// The Brillig black box function has no AVM equivalent
+    UnsupportedBlackBox(String),
+    /// The foreign call doesn't correspond to any AVM opcode
+    UnknownForeignCall(String),
+    /// The foreign call doesn't have the inputs or destinations expected by its AVM opcode
+    InvalidForeignCall(String),
+    /// The operands of the instruction don't fit in any of the encodings of its AVM opcode
+    UnsupportedOperandSize { instruction: &'static str, bits_needed: usize },
+    /// An operand of the instruction has a value which can't be transpiled
+    InvalidOperand(String),
+}
+
+impl fmt::Display for TranspileErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranspileErrorKind::InvalidProgram(reason) => {
+                write!(f, "Invalid AVM program: {reason}")
+            }
+            TranspileErrorKind::UnsupportedOpcode(opcode) => {
+                write!(f, "Transpiler doesn't know how to process {opcode} brillig instruction")
+            }
+            TranspileErrorKind::UnsupportedBlackBox(operation) => {
+                write!(f, "Transpiler doesn't know how to process black box function {operation}")
+            }
+            TranspileErrorKind::UnknownForeignCall(function) => {
+                write!(f, "Transpiler doesn't know how to process ForeignCall function {function}")
+            }
+            TranspileErrorKind::InvalidForeignCall(reason) => write!(f, "{reason}"),
+            TranspileErrorKind::UnsupportedOperandSize { instruction, bits_needed } => write!(
+                f,
+                "{instruction} doesn't support an encoding with {bits_needed} bit operands"
+            ),
+            TranspileErrorKind::InvalidOperand(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// A position in a Noir source file, as shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, in characters
+    pub column: usize,
+    /// Text of the source line, without its line terminator
+    pub source_line: String,
+}
+
+/// An error raised while transpiling a contract function, along with where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranspileError {
+    pub kind: TranspileErrorKind,
+    pub contract_name: Option<String>,
+    pub function_name: Option<String>,
+    /// Program counter of the Brillig instruction which couldn't be transpiled
+    pub brillig_pc: Option<usize>,
+    /// Location of the Noir source code which generated that instruction
+    pub location: Option<SourceLocation>,
+}
+
+impl TranspileError {
+    pub fn new(kind: TranspileErrorKind) -> Self {
+        TranspileError {
+            kind,
+            contract_name: None,
+            function_name: None,
+            brillig_pc: None,
+            location: None,
+        }
+    }
+
+    pub fn at_brillig_pc(mut self, brillig_pc: usize) -> Self {
+        self.brillig_pc = Some(brillig_pc);
+        self
+    }
+
+    pub fn in_function(mut self, contract_name: &str, function_name: &str) -> Self {
+        self.contract_name = Some(contract_name.to_string());
+        self.function_name = Some(function_name.to_string());
+        self
+    }
+
+    /// Looks up the Noir source location of the failing Brillig instruction.
+    /// `file_map` is the file map of the contract artifact, mapping file ids to their path and
+    /// source.
+    pub fn with_source_location(
+        mut self,
+        debug_symbols: &ProgramDebugInfo,
+        file_map: &serde_json::Value,
+    ) -> Self {
+        self.location = self
+            .brillig_pc
+            .and_then(|brillig_pc| find_source_location(brillig_pc, debug_symbols, file_map));
+        self
+    }
+}
+
+impl From<TranspileErrorKind> for TranspileError {
+    fn from(kind: TranspileErrorKind) -> Self {
+        TranspileError::new(kind)
+    }
+}
+
+impl fmt::Display for TranspileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.kind)?;
+        if let Some(location) = &self.location {
+            write!(f, "\n  --> {}:{}:{}", location.path, location.line, location.column)?;
+        }
+        match (&self.contract_name, &self.function_name) {
+            (Some(contract_name), Some(function_name)) => {
+                write!(f, "\n  in function {contract_name}::{function_name}")?;
+            }
+            (None, Some(function_name)) => write!(f, "\n  in function {function_name}")?,
+            _ => (),
+        }
+        if let Some(brillig_pc) = self.brillig_pc {
+            write!(f, "\n  at Brillig PC {brillig_pc}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TranspileError {}
+
+/// Finds the innermost source location of an instruction of the (single) unconstrained function
+/// of an AVM program. `pc` is a Brillig PC, or an AVM PC once the debug info has been patched.
+pub(crate) fn find_source_location(
+    pc: usize,
+    debug_symbols: &ProgramDebugInfo,
+    file_map: &serde_json::Value,
+) -> Option<SourceLocation> {
+    let call_stack = debug_symbols
+        .debug_infos
+        .first()?
+        .brillig_locations
+        .get(&BrilligFunctionId(0))?
+        .get(&BrilligOpcodeLocation(pc))?;
+    let location = call_stack.last()?;
+
+    let file = file_map.get(location.file.as_usize().to_string())?;
+    let path = file.get("path")?.as_str()?;
+    let source = file.get("source")?.as_str()?;
+
+    let preceding_source = source.get(..location.span.start() as usize)?;
+    let line_start = preceding_source.rfind('\n').map_or(0, |newline| newline + 1);
+    let line_end = source[line_start..].find('\n').map_or(source.len(), |end| line_start + end);
+    Some(SourceLocation {
+        path: path.to_string(),
+        line: preceding_source.matches('\n').count() + 1,
+        column: preceding_source[line_start..].chars().count() + 1,
+        source_line: source[line_start..line_end].trim_end_matches('\r').to_string(),
+    })
+}
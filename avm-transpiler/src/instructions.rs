@@ -106,7 +106,7 @@ impl Default for AvmInstruction {
 
 /// AVM instructions may include a type tag
 #[allow(clippy::upper_case_acronyms, dead_code)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AvmTypeTag {
     FIELD,
     UINT1,
@@ -118,6 +118,23 @@ pub enum AvmTypeTag {
     INVALID,
 }
 
+impl AvmTypeTag {
+    /// Decodes the tag from its byte value in the bytecode.
+    pub fn from_u8(byte: u8) -> Option<AvmTypeTag> {
+        match byte {
+            0 => Some(AvmTypeTag::FIELD),
+            1 => Some(AvmTypeTag::UINT1),
+            2 => Some(AvmTypeTag::UINT8),
+            3 => Some(AvmTypeTag::UINT16),
+            4 => Some(AvmTypeTag::UINT32),
+            5 => Some(AvmTypeTag::UINT64),
+            6 => Some(AvmTypeTag::UINT128),
+            7 => Some(AvmTypeTag::INVALID),
+            _ => None,
+        }
+    }
+}
+
 /// Operands are usually 8, 16 and 32 bits (offsets)
 /// Immediates (as used by the SET instruction) can have different sizes
 #[allow(non_camel_case_types)]
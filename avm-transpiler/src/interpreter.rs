@@ -0,0 +1,1221 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use acvm::blackbox_solver::{keccakf1600, sha256_compression, BlackBoxFunctionSolver};
+use acvm::{AcirField, FieldElement};
+use log::debug;
+
+use crate::decoder::{decode_avm_bytecode, DecodeError};
+use crate::instructions::{AvmInstruction, AvmOperand, AvmTypeTag};
+use crate::opcodes::AvmOpcode;
+use crate::world_state::{ExecutionEnvironment, MockWorldState};
+
+/// How the execution of an AVM program ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionResult {
+    Returned(Vec<FieldElement>),
+    Reverted(Vec<FieldElement>),
+}
+
+/// The reason why the execution of an AVM program failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionErrorKind {
+    /// The bytecode couldn't be decoded
+    InvalidBytecode(DecodeError),
+    /// A memory value doesn't have the tag expected by the instruction
+    TagMismatch {
+        offset: u32,
+        expected: AvmTypeTag,
+        found: AvmTypeTag,
+    },
+    /// A memory value has a tag on which the instruction doesn't operate
+    UnsupportedTag {
+        offset: u32,
+        found: AvmTypeTag,
+    },
+    DivisionByZero,
+    /// A jump or internal call targets a PC which isn't the start of an instruction
+    InvalidJumpDestination(u32),
+    /// INTERNALRETURN was executed outside of an internal call
+    EmptyInternalCallStack,
+    /// The instruction modifies the world state during a static call
+    StaticCallModification(AvmOpcode),
+    /// The nullifier was already emitted by the contract
+    DuplicateNullifier(FieldElement),
+    /// An operand of the instruction has a value which can't be executed
+    InvalidOperand(String),
+    /// A black box function solver failed
+    BlackBoxFailure(String),
+    /// The mocked world state has no support for the opcode
+    UnsupportedOpcode(AvmOpcode),
+    /// Execution went past the last instruction without returning or reverting
+    MissingReturn,
+    /// Execution didn't halt within the maximum number of executed instructions
+    StepLimitExceeded(usize),
+}
+
+impl fmt::Display for ExecutionErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionErrorKind::InvalidBytecode(error) => write!(f, "{error}"),
+            ExecutionErrorKind::TagMismatch { offset, expected, found } => write!(
+                f,
+                "Expected tag {expected:?} at memory offset {offset}, found tag {found:?}"
+            ),
+            ExecutionErrorKind::UnsupportedTag { offset, found } => {
+                write!(f, "Unsupported tag {found:?} at memory offset {offset}")
+            }
+            ExecutionErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            ExecutionErrorKind::InvalidJumpDestination(location) => {
+                write!(f, "AVM PC {location} isn't the start of an instruction")
+            }
+            ExecutionErrorKind::EmptyInternalCallStack => {
+                write!(f, "INTERNALRETURN executed with an empty internal call stack")
+            }
+            ExecutionErrorKind::StaticCallModification(opcode) => {
+                write!(f, "{} can't modify the world state during a static call", opcode.name())
+            }
+            ExecutionErrorKind::DuplicateNullifier(nullifier) => {
+                write!(f, "Nullifier {nullifier} already exists")
+            }
+            ExecutionErrorKind::InvalidOperand(reason) => write!(f, "{reason}"),
+            ExecutionErrorKind::BlackBoxFailure(reason) => write!(f, "{reason}"),
+            ExecutionErrorKind::UnsupportedOpcode(opcode) => {
+                write!(f, "The mocked world state doesn't support {}", opcode.name())
+            }
+            ExecutionErrorKind::MissingReturn => {
+                write!(f, "Execution reached the end of the bytecode without returning")
+            }
+            ExecutionErrorKind::StepLimitExceeded(max_steps) => {
+                write!(f, "Execution didn't halt after executing {max_steps} instructions")
+            }
+        }
+    }
+}
+
+/// An error raised while executing an AVM program, along with where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionError {
+    pub kind: ExecutionErrorKind,
+    /// PC of the instruction which failed
+    pub avm_pc: Option<usize>,
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.kind)?;
+        if let Some(avm_pc) = self.avm_pc {
+            write!(f, "\n  at AVM PC {avm_pc}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// Default maximum number of instructions executed before giving up on a program.
+pub const DEFAULT_MAX_STEPS: usize = 10_000_000;
+
+/// Executes an AVM program against a mocked world state.
+/// The world state is left untouched unless the program returns.
+/// External calls aren't supported: CALL and STATICCALL fail the execution.
+/// Execution fails once `max_steps` instructions were executed without halting.
+pub fn execute_avm_bytecode<B: BlackBoxFunctionSolver<FieldElement>>(
+    bytecode: &[u8],
+    environment: &ExecutionEnvironment,
+    world_state: &mut MockWorldState,
+    black_box_solver: &B,
+    max_steps: usize,
+) -> Result<ExecutionResult, ExecutionError> {
+    let instructions = decode_avm_bytecode(bytecode).map_err(|error| ExecutionError {
+        kind: ExecutionErrorKind::InvalidBytecode(error),
+        avm_pc: None,
+    })?;
+
+    let initial_world_state = world_state.clone();
+    let mut machine = AvmMachine {
+        environment,
+        world_state,
+        black_box_solver,
+        memory: HashMap::new(),
+        last_written: None,
+        internal_call_stack: Vec::new(),
+    };
+    let result = machine.run(&instructions, max_steps);
+    if !matches!(result, Ok(ExecutionResult::Returned(_))) {
+        *world_state = initial_world_state;
+    }
+    result
+}
+
+/// A value stored in AVM memory, along with its type tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MemoryValue {
+    tag: AvmTypeTag,
+    value: FieldElement,
+}
+
+/// What to execute after an instruction.
+enum Step {
+    Next,
+    /// Jump to an AVM PC
+    Jump(u32),
+    /// Continue at an instruction index, after an internal return
+    Resume(usize),
+    Halt(ExecutionResult),
+}
+
+type StepResult = Result<Step, ExecutionErrorKind>;
+
+struct AvmMachine<'a, B> {
+    environment: &'a ExecutionEnvironment,
+    world_state: &'a mut MockWorldState,
+    black_box_solver: &'a B,
+    /// Memory values which have been written. Any other offset holds a zero field.
+    memory: HashMap<u32, MemoryValue>,
+    /// Highest memory offset which has been written
+    last_written: Option<u32>,
+    /// Indices of the instructions to resume at after each pending INTERNALRETURN
+    internal_call_stack: Vec<usize>,
+}
+
+impl<'a, B: BlackBoxFunctionSolver<FieldElement>> AvmMachine<'a, B> {
+    fn run(
+        &mut self,
+        instructions: &[(usize, AvmInstruction)],
+        max_steps: usize,
+    ) -> Result<ExecutionResult, ExecutionError> {
+        let pc_to_index: HashMap<usize, usize> =
+            instructions.iter().enumerate().map(|(index, (avm_pc, _))| (*avm_pc, index)).collect();
+
+        let mut index = 0;
+        for _ in 0..max_steps {
+            let Some((avm_pc, instruction)) = instructions.get(index) else {
+                return Err(ExecutionError {
+                    kind: ExecutionErrorKind::MissingReturn,
+                    avm_pc: None,
+                });
+            };
+            let error = |kind| ExecutionError { kind, avm_pc: Some(*avm_pc) };
+            match self.execute_instruction(instruction, index).map_err(error)? {
+                Step::Next => index += 1,
+                Step::Jump(location) => {
+                    index = *pc_to_index.get(&(location as usize)).ok_or_else(|| {
+                        error(ExecutionErrorKind::InvalidJumpDestination(location))
+                    })?;
+                }
+                Step::Resume(resume_index) => index = resume_index,
+                Step::Halt(result) => return Ok(result),
+            }
+        }
+        Err(ExecutionError {
+            kind: ExecutionErrorKind::StepLimitExceeded(max_steps),
+            avm_pc: instructions.get(index).map(|(avm_pc, _)| *avm_pc),
+        })
+    }
+
+    fn execute_instruction(&mut self, instr: &AvmInstruction, index: usize) -> StepResult {
+        match instr.opcode {
+            AvmOpcode::ADD_8 | AvmOpcode::ADD_16 => self.binary_op(instr, BinaryOp::Add),
+            AvmOpcode::SUB_8 | AvmOpcode::SUB_16 => self.binary_op(instr, BinaryOp::Sub),
+            AvmOpcode::MUL_8 | AvmOpcode::MUL_16 => self.binary_op(instr, BinaryOp::Mul),
+            AvmOpcode::DIV_8 | AvmOpcode::DIV_16 => self.binary_op(instr, BinaryOp::Div),
+            AvmOpcode::FDIV_8 | AvmOpcode::FDIV_16 => self.binary_op(instr, BinaryOp::FieldDiv),
+            AvmOpcode::EQ_8 | AvmOpcode::EQ_16 => self.binary_op(instr, BinaryOp::Eq),
+            AvmOpcode::LT_8 | AvmOpcode::LT_16 => self.binary_op(instr, BinaryOp::Lt),
+            AvmOpcode::LTE_8 | AvmOpcode::LTE_16 => self.binary_op(instr, BinaryOp::Lte),
+            AvmOpcode::AND_8 | AvmOpcode::AND_16 => self.binary_op(instr, BinaryOp::And),
+            AvmOpcode::OR_8 | AvmOpcode::OR_16 => self.binary_op(instr, BinaryOp::Or),
+            AvmOpcode::XOR_8 | AvmOpcode::XOR_16 => self.binary_op(instr, BinaryOp::Xor),
+            AvmOpcode::SHL_8 | AvmOpcode::SHL_16 => self.binary_op(instr, BinaryOp::Shl),
+            AvmOpcode::SHR_8 | AvmOpcode::SHR_16 => self.binary_op(instr, BinaryOp::Shr),
+            AvmOpcode::NOT_8 | AvmOpcode::NOT_16 => {
+                let [a_offset, dst_offset] = self.resolve_operands(instr)?;
+                let a = self.read(a_offset);
+                let mask = integral_mask(a_offset, a.tag)?;
+                self.write(dst_offset, a.tag, (!a.value.to_u128() & mask).into());
+                Ok(Step::Next)
+            }
+            AvmOpcode::CAST_8 | AvmOpcode::CAST_16 => {
+                let [src_offset, dst_offset] = self.resolve_operands(instr)?;
+                let tag = instruction_tag(instr)?;
+                let value = truncate(self.read(src_offset).value, tag);
+                self.write(dst_offset, tag, value);
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::GETENVVAR_16 => {
+                let [dst_offset] = self.resolve_operands(instr)?;
+                let (tag, value) = self.environment_variable(immediate_u32(instr, 0)?)?;
+                self.write(dst_offset, tag, value);
+                Ok(Step::Next)
+            }
+            AvmOpcode::CALLDATACOPY | AvmOpcode::RETURNDATACOPY => {
+                let [start_offset, size_offset, dst_offset] = self.resolve_operands(instr)?;
+                let start = self.read_u32(start_offset)? as usize;
+                let size = self.read_u32(size_offset)? as usize;
+                // Nested calls aren't supported, so the return data is always empty.
+                let data: &[FieldElement] = match instr.opcode {
+                    AvmOpcode::CALLDATACOPY => &self.environment.calldata,
+                    _ => &[],
+                };
+                let copied = data.iter().skip(start).take(size).copied().collect::<Vec<_>>();
+                self.write_slice(dst_offset, AvmTypeTag::FIELD, &copied)?;
+                Ok(Step::Next)
+            }
+            AvmOpcode::RETURNDATASIZE => {
+                let [dst_offset] = self.resolve_operands(instr)?;
+                self.write(dst_offset, AvmTypeTag::UINT32, FieldElement::zero());
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::JUMP_32 => Ok(Step::Jump(immediate_u32(instr, 0)?)),
+            AvmOpcode::JUMPI_32 => {
+                let [condition_offset] = self.resolve_operands(instr)?;
+                if self.read(condition_offset).value.is_zero() {
+                    Ok(Step::Next)
+                } else {
+                    Ok(Step::Jump(immediate_u32(instr, 0)?))
+                }
+            }
+            AvmOpcode::INTERNALCALL => {
+                self.internal_call_stack.push(index + 1);
+                Ok(Step::Jump(immediate_u32(instr, 0)?))
+            }
+            AvmOpcode::INTERNALRETURN => self
+                .internal_call_stack
+                .pop()
+                .map(Step::Resume)
+                .ok_or(ExecutionErrorKind::EmptyInternalCallStack),
+
+            AvmOpcode::SET_8
+            | AvmOpcode::SET_16
+            | AvmOpcode::SET_32
+            | AvmOpcode::SET_64
+            | AvmOpcode::SET_128
+            | AvmOpcode::SET_FF => {
+                let [dst_offset] = self.resolve_operands(instr)?;
+                let tag = instruction_tag(instr)?;
+                self.write(dst_offset, tag, truncate(operand_value(immediate(instr, 0)?), tag));
+                Ok(Step::Next)
+            }
+            AvmOpcode::MOV_8 | AvmOpcode::MOV_16 => {
+                let [src_offset, dst_offset] = self.resolve_operands(instr)?;
+                let value = self.read(src_offset);
+                self.memory.insert(dst_offset, value);
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::SLOAD => {
+                let [slot_offset, dst_offset] = self.resolve_operands(instr)?;
+                let slot = self.read_tagged(slot_offset, AvmTypeTag::FIELD)?;
+                let value = self.world_state.storage_read(self.environment.address, slot);
+                self.write(dst_offset, AvmTypeTag::FIELD, value);
+                Ok(Step::Next)
+            }
+            AvmOpcode::SSTORE => {
+                self.check_not_static(instr.opcode)?;
+                let [src_offset, slot_offset] = self.resolve_operands(instr)?;
+                let value = self.read_tagged(src_offset, AvmTypeTag::FIELD)?;
+                let slot = self.read_tagged(slot_offset, AvmTypeTag::FIELD)?;
+                self.world_state.storage_write(self.environment.address, slot, value);
+                Ok(Step::Next)
+            }
+            AvmOpcode::NOTEHASHEXISTS => {
+                let [note_hash_offset, leaf_index_offset, exists_offset] =
+                    self.resolve_operands(instr)?;
+                let note_hash = self.read_tagged(note_hash_offset, AvmTypeTag::FIELD)?;
+                let leaf_index = self.read_tagged(leaf_index_offset, AvmTypeTag::FIELD)?;
+                let exists = self.world_state.note_hash_exists(note_hash, leaf_index);
+                self.write(exists_offset, AvmTypeTag::UINT1, exists.into());
+                Ok(Step::Next)
+            }
+            AvmOpcode::EMITNOTEHASH => {
+                self.check_not_static(instr.opcode)?;
+                let [note_hash_offset] = self.resolve_operands(instr)?;
+                let note_hash = self.read_tagged(note_hash_offset, AvmTypeTag::FIELD)?;
+                self.world_state.emit_note_hash(self.environment.address, note_hash);
+                Ok(Step::Next)
+            }
+            AvmOpcode::NULLIFIEREXISTS => {
+                let [nullifier_offset, address_offset, exists_offset] =
+                    self.resolve_operands(instr)?;
+                let nullifier = self.read_tagged(nullifier_offset, AvmTypeTag::FIELD)?;
+                let address = self.read_tagged(address_offset, AvmTypeTag::FIELD)?;
+                let exists = self.world_state.nullifier_exists(address, nullifier);
+                self.write(exists_offset, AvmTypeTag::UINT1, exists.into());
+                Ok(Step::Next)
+            }
+            AvmOpcode::EMITNULLIFIER => {
+                self.check_not_static(instr.opcode)?;
+                let [nullifier_offset] = self.resolve_operands(instr)?;
+                let nullifier = self.read_tagged(nullifier_offset, AvmTypeTag::FIELD)?;
+                if !self.world_state.emit_nullifier(self.environment.address, nullifier) {
+                    return Err(ExecutionErrorKind::DuplicateNullifier(nullifier));
+                }
+                Ok(Step::Next)
+            }
+            AvmOpcode::L1TOL2MSGEXISTS => {
+                let [message_hash_offset, leaf_index_offset, exists_offset] =
+                    self.resolve_operands(instr)?;
+                let message_hash = self.read_tagged(message_hash_offset, AvmTypeTag::FIELD)?;
+                let leaf_index = self.read_tagged(leaf_index_offset, AvmTypeTag::FIELD)?;
+                let exists = self.world_state.l1_to_l2_message_exists(message_hash, leaf_index);
+                self.write(exists_offset, AvmTypeTag::UINT1, exists.into());
+                Ok(Step::Next)
+            }
+            AvmOpcode::GETCONTRACTINSTANCE => {
+                let [address_offset, dst_offset, exists_offset] = self.resolve_operands(instr)?;
+                let address = self.read_tagged(address_offset, AvmTypeTag::FIELD)?;
+                let instance = self.world_state.contract_instance(address);
+                let value = match (immediate_u32(instr, 0)?, instance) {
+                    (member @ 3.., _) => {
+                        return Err(ExecutionErrorKind::InvalidOperand(format!(
+                            "Invalid contract instance member {member}"
+                        )))
+                    }
+                    (_, None) => FieldElement::zero(),
+                    (0, Some(instance)) => instance.deployer,
+                    (1, Some(instance)) => instance.class_id,
+                    (_, Some(instance)) => instance.initialization_hash,
+                };
+                let exists = instance.is_some();
+                self.write(dst_offset, AvmTypeTag::FIELD, value);
+                self.write(exists_offset, AvmTypeTag::UINT1, exists.into());
+                Ok(Step::Next)
+            }
+            AvmOpcode::EMITUNENCRYPTEDLOG => {
+                self.check_not_static(instr.opcode)?;
+                let [log_offset, log_size_offset] = self.resolve_operands(instr)?;
+                let log_size = self.read_u32(log_size_offset)?;
+                let fields = self.read_slice(log_offset, log_size, AvmTypeTag::FIELD)?;
+                self.world_state.emit_unencrypted_log(self.environment.address, fields);
+                Ok(Step::Next)
+            }
+            AvmOpcode::SENDL2TOL1MSG => {
+                self.check_not_static(instr.opcode)?;
+                let [recipient_offset, content_offset] = self.resolve_operands(instr)?;
+                let recipient = self.read_tagged(recipient_offset, AvmTypeTag::FIELD)?;
+                let content = self.read_tagged(content_offset, AvmTypeTag::FIELD)?;
+                self.world_state.send_l2_to_l1_message(
+                    self.environment.address,
+                    recipient,
+                    content,
+                );
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::CALL | AvmOpcode::STATICCALL => {
+                Err(ExecutionErrorKind::UnsupportedOpcode(instr.opcode))
+            }
+            AvmOpcode::RETURN | AvmOpcode::REVERT_8 | AvmOpcode::REVERT_16 => {
+                let [data_offset, size_offset] = self.resolve_operands(instr)?;
+                let size = self.read_u32(size_offset)?;
+                self.check_written_range(data_offset, size)?;
+                let data = (0..size)
+                    .map(|i| Ok(self.read(offset_add(data_offset, i)?).value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Step::Halt(match instr.opcode {
+                    AvmOpcode::RETURN => ExecutionResult::Returned(data),
+                    _ => ExecutionResult::Reverted(data),
+                }))
+            }
+
+            AvmOpcode::DEBUGLOG => {
+                let [message_offset, fields_offset, fields_size_offset] =
+                    self.resolve_operands(instr)?;
+                let fields_size = self.read_u32(fields_size_offset)?;
+                let message =
+                    self.read_slice(message_offset, immediate_u32(instr, 0)?, AvmTypeTag::UINT8)?;
+                let fields = self.read_slice(fields_offset, fields_size, AvmTypeTag::FIELD)?;
+                let message: String =
+                    message.iter().map(|byte| char::from(byte.to_u128() as u8)).collect();
+                debug!("{message} {fields:?}");
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::POSEIDON2 => {
+                let [input_offset, output_offset] = self.resolve_operands(instr)?;
+                let input = self.read_slice(input_offset, 4, AvmTypeTag::FIELD)?;
+                let output = self
+                    .black_box_solver
+                    .poseidon2_permutation(&input, 4)
+                    .map_err(|error| ExecutionErrorKind::BlackBoxFailure(error.to_string()))?;
+                self.write_slice(output_offset, AvmTypeTag::FIELD, &output)?;
+                Ok(Step::Next)
+            }
+            AvmOpcode::SHA256COMPRESSION => {
+                let [output_offset, state_offset, inputs_offset] = self.resolve_operands(instr)?;
+                let mut state = [0; 8];
+                for (word, value) in
+                    state.iter_mut().zip(self.read_slice(state_offset, 8, AvmTypeTag::UINT32)?)
+                {
+                    *word = value.to_u128() as u32;
+                }
+                let mut inputs = [0; 16];
+                for (word, value) in
+                    inputs.iter_mut().zip(self.read_slice(inputs_offset, 16, AvmTypeTag::UINT32)?)
+                {
+                    *word = value.to_u128() as u32;
+                }
+                sha256_compression(&mut state, &inputs);
+                let output = state.map(FieldElement::from);
+                self.write_slice(output_offset, AvmTypeTag::UINT32, &output)?;
+                Ok(Step::Next)
+            }
+            AvmOpcode::KECCAKF1600 => {
+                let [dst_offset, input_offset] = self.resolve_operands(instr)?;
+                let mut state = [0; 25];
+                for (lane, value) in
+                    state.iter_mut().zip(self.read_slice(input_offset, 25, AvmTypeTag::UINT64)?)
+                {
+                    *lane = value.to_u128() as u64;
+                }
+                let output = keccakf1600(state)
+                    .map_err(|error| ExecutionErrorKind::BlackBoxFailure(error.to_string()))?
+                    .map(FieldElement::from);
+                self.write_slice(dst_offset, AvmTypeTag::UINT64, &output)?;
+                Ok(Step::Next)
+            }
+            AvmOpcode::ECADD => {
+                let [p1_x, p1_y, p1_infinite, p2_x, p2_y, p2_infinite, dst_offset] =
+                    self.resolve_operands(instr)?;
+                let (x, y, infinite) = self
+                    .black_box_solver
+                    .ec_add(
+                        &self.read_tagged(p1_x, AvmTypeTag::FIELD)?,
+                        &self.read_tagged(p1_y, AvmTypeTag::FIELD)?,
+                        &self.read_tagged(p1_infinite, AvmTypeTag::UINT1)?,
+                        &self.read_tagged(p2_x, AvmTypeTag::FIELD)?,
+                        &self.read_tagged(p2_y, AvmTypeTag::FIELD)?,
+                        &self.read_tagged(p2_infinite, AvmTypeTag::UINT1)?,
+                    )
+                    .map_err(|error| ExecutionErrorKind::BlackBoxFailure(error.to_string()))?;
+                self.write_point(dst_offset, x, y, infinite)?;
+                Ok(Step::Next)
+            }
+            AvmOpcode::MSM => {
+                let [points_offset, scalars_offset, output_offset, points_length_offset] =
+                    self.resolve_operands(instr)?;
+                let points_length = self.read_u32(points_length_offset)?;
+                if points_length % 3 != 0 {
+                    return Err(ExecutionErrorKind::InvalidOperand(format!(
+                        "MSM points vector length should be a multiple of 3, was {points_length}"
+                    )));
+                }
+                let mut points = Vec::new();
+                for i in 0..points_length {
+                    let tag = if i % 3 == 2 { AvmTypeTag::UINT1 } else { AvmTypeTag::FIELD };
+                    points.push(self.read_tagged(offset_add(points_offset, i)?, tag)?);
+                }
+                let scalars =
+                    self.read_slice(scalars_offset, points_length / 3 * 2, AvmTypeTag::FIELD)?;
+                let scalars_lo = scalars.iter().step_by(2).copied().collect::<Vec<_>>();
+                let scalars_hi = scalars.iter().skip(1).step_by(2).copied().collect::<Vec<_>>();
+                let (x, y, infinite) = self
+                    .black_box_solver
+                    .multi_scalar_mul(&points, &scalars_lo, &scalars_hi)
+                    .map_err(|error| ExecutionErrorKind::BlackBoxFailure(error.to_string()))?;
+                self.write_point(output_offset, x, y, infinite)?;
+                Ok(Step::Next)
+            }
+
+            AvmOpcode::TORADIXBE => {
+                let [src_offset, radix_offset, num_limbs_offset, output_bits_offset, dst_offset] =
+                    self.resolve_operands(instr)?;
+                let value = self.read_tagged(src_offset, AvmTypeTag::FIELD)?;
+                let radix = self.read_u32(radix_offset)?;
+                let num_limbs = self.read_u32(num_limbs_offset)?;
+                let output_bits = self.read_tagged(output_bits_offset, AvmTypeTag::UINT1)?;
+                if num_limbs == 0 {
+                    return Err(ExecutionErrorKind::InvalidOperand(
+                        "TORADIXBE should output at least one limb".to_string(),
+                    ));
+                }
+                if !(2..=256).contains(&radix) {
+                    return Err(ExecutionErrorKind::InvalidOperand(format!(
+                        "TORADIXBE radix should be between 2 and 256, was {radix}"
+                    )));
+                }
+                let tag = if output_bits.is_zero() { AvmTypeTag::UINT8 } else { AvmTypeTag::UINT1 };
+                let limbs = to_radix_be(value, radix, num_limbs as usize);
+                self.write_slice(dst_offset, tag, &limbs)?;
+                Ok(Step::Next)
+            }
+        }
+    }
+
+    fn binary_op(&mut self, instr: &AvmInstruction, op: BinaryOp) -> StepResult {
+        let [a_offset, b_offset, dst_offset] = self.resolve_operands(instr)?;
+        let a = self.read(a_offset);
+        let b = self.read(b_offset);
+        if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+            // Brillig shifts by a value of the same bit size as the shifted value, so any
+            // integral tag is accepted for the shift amount.
+            integral_mask(b_offset, b.tag)?;
+        } else if a.tag != b.tag {
+            return Err(ExecutionErrorKind::TagMismatch {
+                offset: b_offset,
+                expected: a.tag,
+                found: b.tag,
+            });
+        }
+
+        let (tag, value) = match op {
+            BinaryOp::Eq => (AvmTypeTag::UINT1, (a.value == b.value).into()),
+            BinaryOp::Lt => (AvmTypeTag::UINT1, (a.value < b.value).into()),
+            BinaryOp::Lte => (AvmTypeTag::UINT1, (a.value <= b.value).into()),
+            _ if a.tag == AvmTypeTag::FIELD => (a.tag, field_op(op, a_offset, a.value, b.value)?),
+            _ => (a.tag, integral_op(op, a_offset, a.tag, a.value.to_u128(), b.value.to_u128())?),
+        };
+        self.write(dst_offset, tag, value);
+        Ok(Step::Next)
+    }
+
+    fn environment_variable(
+        &self,
+        variable: u32,
+    ) -> Result<(AvmTypeTag, FieldElement), ExecutionErrorKind> {
+        let environment = self.environment;
+        // Keep the order in sync with the EnvironmentVariable enum of the TS simulator.
+        let value = match variable {
+            0 => environment.address,
+            1 => environment.sender,
+            2 => environment.transaction_fee,
+            3 => environment.chain_id,
+            4 => environment.version,
+            5 => environment.block_number,
+            6 => return Ok((AvmTypeTag::UINT64, environment.timestamp.into())),
+            7 => environment.fee_per_l2_gas,
+            8 => environment.fee_per_da_gas,
+            9 => environment.is_static_call.into(),
+            10 => environment.l2_gas_left.into(),
+            11 => environment.da_gas_left.into(),
+            _ => {
+                return Err(ExecutionErrorKind::InvalidOperand(format!(
+                    "Invalid environment variable {variable}"
+                )))
+            }
+        };
+        Ok((AvmTypeTag::FIELD, value))
+    }
+
+    fn check_not_static(&self, opcode: AvmOpcode) -> Result<(), ExecutionErrorKind> {
+        if self.environment.is_static_call {
+            return Err(ExecutionErrorKind::StaticCallModification(opcode));
+        }
+        Ok(())
+    }
+
+    /// Resolves the memory offsets of the operands of an instruction, applying their relative
+    /// and indirect addressing modes.
+    fn resolve_operands<const N: usize>(
+        &self,
+        instr: &AvmInstruction,
+    ) -> Result<[u32; N], ExecutionErrorKind> {
+        let flags = instr.indirect.as_ref().map_or(Ok(0), operand_u32)?;
+        if instr.operands.len() != N {
+            return Err(ExecutionErrorKind::InvalidOperand(format!(
+                "{} expects {N} operands, found {}",
+                instr.opcode.name(),
+                instr.operands.len()
+            )));
+        }
+        let mut resolved = [0; N];
+        for (i, offset) in resolved.iter_mut().enumerate() {
+            *offset = operand_u32(&instr.operands[i])?;
+            if flags & (1 << (N + i)) != 0 {
+                *offset = offset_add(self.read_u32(0)?, *offset)?;
+            }
+            if flags & (1 << i) != 0 {
+                *offset = self.read_u32(*offset)?;
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn read(&self, offset: u32) -> MemoryValue {
+        self.memory
+            .get(&offset)
+            .copied()
+            .unwrap_or(MemoryValue { tag: AvmTypeTag::FIELD, value: FieldElement::zero() })
+    }
+
+    fn read_tagged(
+        &self,
+        offset: u32,
+        tag: AvmTypeTag,
+    ) -> Result<FieldElement, ExecutionErrorKind> {
+        let value = self.read(offset);
+        if value.tag != tag {
+            return Err(ExecutionErrorKind::TagMismatch {
+                offset,
+                expected: tag,
+                found: value.tag,
+            });
+        }
+        Ok(value.value)
+    }
+
+    fn read_u32(&self, offset: u32) -> Result<u32, ExecutionErrorKind> {
+        Ok(self.read_tagged(offset, AvmTypeTag::UINT32)?.to_u128() as u32)
+    }
+
+    fn read_slice(
+        &self,
+        offset: u32,
+        size: u32,
+        tag: AvmTypeTag,
+    ) -> Result<Vec<FieldElement>, ExecutionErrorKind> {
+        self.check_written_range(offset, size)?;
+        (0..size).map(|i| self.read_tagged(offset_add(offset, i)?, tag)).collect()
+    }
+
+    /// Checks that `size` values starting at `offset` don't extend past the highest memory
+    /// offset written so far, so that a corrupted size can't make us read billions of values.
+    fn check_written_range(&self, offset: u32, size: u32) -> Result<(), ExecutionErrorKind> {
+        if size == 0 {
+            return Ok(());
+        }
+        let last_offset = offset_add(offset, size - 1)?;
+        if self.last_written.map_or(true, |last_written| last_offset > last_written) {
+            return Err(ExecutionErrorKind::InvalidOperand(format!(
+                "Reading {size} values at memory offset {offset} goes past the written memory"
+            )));
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, tag: AvmTypeTag, value: FieldElement) {
+        self.memory.insert(offset, MemoryValue { tag, value });
+        self.last_written = self.last_written.max(Some(offset));
+    }
+
+    fn write_slice(
+        &mut self,
+        offset: u32,
+        tag: AvmTypeTag,
+        values: &[FieldElement],
+    ) -> Result<(), ExecutionErrorKind> {
+        for (i, value) in values.iter().enumerate() {
+            self.write(offset_add(offset, i as u32)?, tag, *value);
+        }
+        Ok(())
+    }
+
+    /// Writes an elliptic curve point as its x and y coordinates followed by its infinity flag.
+    fn write_point(
+        &mut self,
+        offset: u32,
+        x: FieldElement,
+        y: FieldElement,
+        infinite: FieldElement,
+    ) -> Result<(), ExecutionErrorKind> {
+        self.write_slice(offset, AvmTypeTag::FIELD, &[x, y])?;
+        self.write(offset_add(offset, 2)?, AvmTypeTag::UINT1, infinite);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FieldDiv,
+    Eq,
+    Lt,
+    Lte,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+fn field_op(
+    op: BinaryOp,
+    offset: u32,
+    a: FieldElement,
+    b: FieldElement,
+) -> Result<FieldElement, ExecutionErrorKind> {
+    match op {
+        BinaryOp::Add => Ok(a + b),
+        BinaryOp::Sub => Ok(a - b),
+        BinaryOp::Mul => Ok(a * b),
+        BinaryOp::FieldDiv if b.is_zero() => Err(ExecutionErrorKind::DivisionByZero),
+        BinaryOp::FieldDiv => Ok(a / b),
+        _ => Err(ExecutionErrorKind::UnsupportedTag { offset, found: AvmTypeTag::FIELD }),
+    }
+}
+
+/// Applies an operation to two unsigned integers of the bit size of `tag`, wrapping around on
+/// overflow.
+fn integral_op(
+    op: BinaryOp,
+    offset: u32,
+    tag: AvmTypeTag,
+    a: u128,
+    b: u128,
+) -> Result<FieldElement, ExecutionErrorKind> {
+    let mask = integral_mask(offset, tag)?;
+    let bit_size = u128::from(mask.count_ones());
+    let result = match op {
+        BinaryOp::Add => a.wrapping_add(b),
+        BinaryOp::Sub => a.wrapping_sub(b),
+        BinaryOp::Mul => a.wrapping_mul(b),
+        BinaryOp::Div if b == 0 => return Err(ExecutionErrorKind::DivisionByZero),
+        BinaryOp::Div => a / b,
+        BinaryOp::And => a & b,
+        BinaryOp::Or => a | b,
+        BinaryOp::Xor => a ^ b,
+        BinaryOp::Shl if b >= bit_size => 0,
+        BinaryOp::Shl => a << b,
+        BinaryOp::Shr if b >= bit_size => 0,
+        BinaryOp::Shr => a >> b,
+        BinaryOp::FieldDiv | BinaryOp::Eq | BinaryOp::Lt | BinaryOp::Lte => {
+            return Err(ExecutionErrorKind::UnsupportedTag { offset, found: tag });
+        }
+    };
+    Ok((result & mask).into())
+}
+
+/// Returns the mask of the bits of an integral tag.
+fn bit_mask(tag: AvmTypeTag) -> Option<u128> {
+    match tag {
+        AvmTypeTag::UINT1 => Some(1),
+        AvmTypeTag::UINT8 => Some(u8::MAX.into()),
+        AvmTypeTag::UINT16 => Some(u16::MAX.into()),
+        AvmTypeTag::UINT32 => Some(u32::MAX.into()),
+        AvmTypeTag::UINT64 => Some(u64::MAX.into()),
+        AvmTypeTag::UINT128 => Some(u128::MAX),
+        AvmTypeTag::FIELD | AvmTypeTag::INVALID => None,
+    }
+}
+
+/// Returns the mask of the bits of the tag of the value at a memory offset, which must be
+/// integral.
+fn integral_mask(offset: u32, tag: AvmTypeTag) -> Result<u128, ExecutionErrorKind> {
+    bit_mask(tag).ok_or(ExecutionErrorKind::UnsupportedTag { offset, found: tag })
+}
+
+/// Truncates a value to the bit size of a tag, as done by CAST and SET.
+fn truncate(value: FieldElement, tag: AvmTypeTag) -> FieldElement {
+    let Some(mask) = bit_mask(tag) else {
+        return value;
+    };
+    let bytes = value.to_be_bytes();
+    let mut low_bytes = [0; 16];
+    low_bytes.copy_from_slice(&bytes[bytes.len() - 16..]);
+    (u128::from_be_bytes(low_bytes) & mask).into()
+}
+
+/// Decomposes a field into `num_limbs` limbs in the given radix, most significant limb first.
+/// Higher limbs are dropped if the value doesn't fit.
+fn to_radix_be(value: FieldElement, radix: u32, num_limbs: usize) -> Vec<FieldElement> {
+    let mut digits = value.to_be_bytes();
+    let mut limbs = vec![FieldElement::zero(); num_limbs];
+    for limb in limbs.iter_mut().rev() {
+        // Long division of the big-endian digits by the radix.
+        let mut remainder = 0;
+        for digit in digits.iter_mut() {
+            let current = (remainder << 8) | u32::from(*digit);
+            *digit = (current / radix) as u8;
+            remainder = current % radix;
+        }
+        *limb = remainder.into();
+    }
+    limbs
+}
+
+fn instruction_tag(instr: &AvmInstruction) -> Result<AvmTypeTag, ExecutionErrorKind> {
+    match instr.tag {
+        Some(AvmTypeTag::INVALID) | None => Err(ExecutionErrorKind::InvalidOperand(format!(
+            "{} has an invalid tag",
+            instr.opcode.name()
+        ))),
+        Some(tag) => Ok(tag),
+    }
+}
+
+fn offset_add(offset: u32, increment: u32) -> Result<u32, ExecutionErrorKind> {
+    offset.checked_add(increment).ok_or_else(|| {
+        ExecutionErrorKind::InvalidOperand(format!(
+            "Memory offset {offset} + {increment} is out of bounds"
+        ))
+    })
+}
+
+/// Memory offsets, addressing flags and jump locations are at most 32 bits.
+fn operand_u32(operand: &AvmOperand) -> Result<u32, ExecutionErrorKind> {
+    match operand {
+        AvmOperand::U8 { value } => Ok((*value).into()),
+        AvmOperand::U16 { value } => Ok((*value).into()),
+        AvmOperand::U32 { value } => Ok(*value),
+        _ => Err(ExecutionErrorKind::InvalidOperand(format!(
+            "Operand {operand} doesn't fit in 32 bits"
+        ))),
+    }
+}
+
+fn immediate(instr: &AvmInstruction, index: usize) -> Result<&AvmOperand, ExecutionErrorKind> {
+    instr.immediates.get(index).ok_or_else(|| {
+        ExecutionErrorKind::InvalidOperand(format!(
+            "{} is missing immediate operand {index}",
+            instr.opcode.name()
+        ))
+    })
+}
+
+fn immediate_u32(instr: &AvmInstruction, index: usize) -> Result<u32, ExecutionErrorKind> {
+    operand_u32(immediate(instr, index)?)
+}
+
+fn operand_value(operand: &AvmOperand) -> FieldElement {
+    match operand {
+        AvmOperand::U8 { value } => u128::from(*value).into(),
+        AvmOperand::U16 { value } => u128::from(*value).into(),
+        AvmOperand::U32 { value } => u128::from(*value).into(),
+        AvmOperand::U64 { value } => u128::from(*value).into(),
+        AvmOperand::U128 { value } => (*value).into(),
+        AvmOperand::FF { value } => *value,
+        AvmOperand::BRILLIG_LOCATION { brillig_pc } => u128::from(*brillig_pc).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::brillig::{
+        BinaryFieldOp, BinaryIntOp, BitSize, HeapVector, IntegerBitSize, MemoryAddress,
+        Opcode as BrilligOpcode,
+    };
+    use acvm::brillig_vm::{VMStatus, VM};
+    use bn254_blackbox_solver::Bn254BlackBoxSolver;
+
+    use super::*;
+    use crate::transpile::{brillig_to_avm, BrilligToAvmOptions};
+
+    const TEST_MAX_STEPS: usize = 1000;
+
+    fn set(tag: AvmTypeTag, destination: u16, value: u32) -> AvmInstruction {
+        AvmInstruction {
+            opcode: AvmOpcode::SET_32,
+            indirect: Some(AvmOperand::U8 { value: 0 }),
+            tag: Some(tag),
+            operands: vec![AvmOperand::U16 { value: destination }],
+            immediates: vec![AvmOperand::U32 { value }],
+        }
+    }
+
+    fn binary(opcode: AvmOpcode, a: u8, b: u8, destination: u8) -> AvmInstruction {
+        AvmInstruction {
+            opcode,
+            indirect: Some(AvmOperand::U8 { value: 0 }),
+            operands: vec![
+                AvmOperand::U8 { value: a },
+                AvmOperand::U8 { value: b },
+                AvmOperand::U8 { value: destination },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn control_flow(opcode: AvmOpcode, avm_pc: u32) -> AvmInstruction {
+        AvmInstruction {
+            opcode,
+            immediates: vec![AvmOperand::U32 { value: avm_pc }],
+            ..Default::default()
+        }
+    }
+
+    fn halt(opcode: AvmOpcode, data_offset: u16, size_offset: u16) -> AvmInstruction {
+        AvmInstruction {
+            opcode,
+            indirect: Some(AvmOperand::U8 { value: 0 }),
+            operands: vec![
+                AvmOperand::U16 { value: data_offset },
+                AvmOperand::U16 { value: size_offset },
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// AVM PC of the instruction at `index`
+    fn avm_pc(instructions: &[AvmInstruction], index: usize) -> u32 {
+        instructions[..index].iter().map(AvmInstruction::size).sum::<usize>() as u32
+    }
+
+    fn execute(instructions: &[AvmInstruction]) -> Result<ExecutionResult, ExecutionError> {
+        let bytecode = instructions.iter().flat_map(AvmInstruction::to_bytes).collect::<Vec<_>>();
+        execute_avm_bytecode(
+            &bytecode,
+            &ExecutionEnvironment::default(),
+            &mut MockWorldState::default(),
+            &Bn254BlackBoxSolver,
+            TEST_MAX_STEPS,
+        )
+    }
+
+    fn execution_error(instructions: &[AvmInstruction]) -> ExecutionError {
+        execute(instructions).expect_err("Expected execution to fail")
+    }
+
+    #[test]
+    fn integer_arithmetic_wraps_around() {
+        let result = execute(&[
+            set(AvmTypeTag::UINT8, 0, 200),
+            set(AvmTypeTag::UINT8, 1, 100),
+            binary(AvmOpcode::ADD_8, 0, 1, 2),
+            binary(AvmOpcode::SUB_8, 1, 0, 3),
+            set(AvmTypeTag::UINT32, 4, 2),
+            halt(AvmOpcode::RETURN, 2, 4),
+        ]);
+        assert_eq!(
+            result,
+            Ok(ExecutionResult::Returned(vec![
+                FieldElement::from(44_u128),
+                FieldElement::from(156_u128)
+            ]))
+        );
+    }
+
+    #[test]
+    fn rejects_operands_with_different_tags() {
+        let instructions = [
+            set(AvmTypeTag::UINT8, 0, 1),
+            set(AvmTypeTag::UINT16, 1, 1),
+            binary(AvmOpcode::ADD_8, 0, 1, 2),
+        ];
+        let error = execution_error(&instructions);
+        assert_eq!(
+            error.kind,
+            ExecutionErrorKind::TagMismatch {
+                offset: 1,
+                expected: AvmTypeTag::UINT8,
+                found: AvmTypeTag::UINT16
+            }
+        );
+        assert_eq!(error.avm_pc, Some(avm_pc(&instructions, 2) as usize));
+    }
+
+    #[test]
+    fn rejects_integer_division_by_zero() {
+        let error = execution_error(&[
+            set(AvmTypeTag::UINT32, 0, 1),
+            set(AvmTypeTag::UINT32, 1, 0),
+            binary(AvmOpcode::DIV_8, 0, 1, 2),
+        ]);
+        assert_eq!(error.kind, ExecutionErrorKind::DivisionByZero);
+    }
+
+    #[test]
+    fn internal_calls_resume_after_the_call() {
+        let mut instructions = vec![
+            control_flow(AvmOpcode::INTERNALCALL, 0),
+            set(AvmTypeTag::UINT32, 1, 1),
+            halt(AvmOpcode::RETURN, 0, 1),
+            set(AvmTypeTag::FIELD, 0, 7),
+            AvmInstruction { opcode: AvmOpcode::INTERNALRETURN, ..Default::default() },
+        ];
+        instructions[0] = control_flow(AvmOpcode::INTERNALCALL, avm_pc(&instructions, 3));
+        assert_eq!(
+            execute(&instructions),
+            Ok(ExecutionResult::Returned(vec![FieldElement::from(7_u128)]))
+        );
+    }
+
+    #[test]
+    fn rejects_internal_returns_outside_of_internal_calls() {
+        let error = execution_error(&[AvmInstruction {
+            opcode: AvmOpcode::INTERNALRETURN,
+            ..Default::default()
+        }]);
+        assert_eq!(error.kind, ExecutionErrorKind::EmptyInternalCallStack);
+    }
+
+    #[test]
+    fn rejects_jumps_into_the_middle_of_an_instruction() {
+        let error = execution_error(&[control_flow(AvmOpcode::JUMP_32, 1)]);
+        assert_eq!(error.kind, ExecutionErrorKind::InvalidJumpDestination(1));
+    }
+
+    #[test]
+    fn stops_infinite_loops() {
+        let error =
+            execution_error(&[set(AvmTypeTag::UINT1, 0, 1), control_flow(AvmOpcode::JUMP_32, 0)]);
+        assert_eq!(error.kind, ExecutionErrorKind::StepLimitExceeded(TEST_MAX_STEPS));
+    }
+
+    #[test]
+    fn rejects_missing_returns() {
+        let error = execution_error(&[set(AvmTypeTag::UINT1, 0, 1)]);
+        assert_eq!(error.kind, ExecutionErrorKind::MissingReturn);
+    }
+
+    #[test]
+    fn reverts_with_the_revert_data() {
+        let result = execute(&[
+            set(AvmTypeTag::FIELD, 0, 3),
+            set(AvmTypeTag::UINT32, 1, 1),
+            halt(AvmOpcode::REVERT_16, 0, 1),
+        ]);
+        assert_eq!(result, Ok(ExecutionResult::Reverted(vec![FieldElement::from(3_u128)])));
+    }
+
+    #[test]
+    fn rejects_return_data_past_the_written_memory() {
+        let error =
+            execution_error(&[set(AvmTypeTag::UINT32, 0, u32::MAX), halt(AvmOpcode::RETURN, 0, 0)]);
+        assert!(matches!(error.kind, ExecutionErrorKind::InvalidOperand(_)));
+
+        let error =
+            execution_error(&[set(AvmTypeTag::UINT32, 0, 2), halt(AvmOpcode::RETURN, u16::MAX, 0)]);
+        assert!(matches!(error.kind, ExecutionErrorKind::InvalidOperand(_)));
+    }
+
+    /// Executes a Brillig program in the Brillig VM, returning its return data.
+    fn execute_brillig(
+        bytecode: &[BrilligOpcode<FieldElement>],
+        calldata: &[FieldElement],
+    ) -> Vec<FieldElement> {
+        let mut vm = VM::new(calldata.to_vec(), bytecode, vec![], &Bn254BlackBoxSolver, false);
+        match vm.process_opcodes() {
+            VMStatus::Finished { return_data_offset, return_data_size } => vm.get_memory()
+                [return_data_offset..return_data_offset + return_data_size]
+                .iter()
+                .map(|value| value.to_field())
+                .collect(),
+            status => panic!("Brillig execution didn't finish: {status:?}"),
+        }
+    }
+
+    /// Checks that a Brillig program returns the same data when run in the Brillig VM and
+    /// when transpiled to AVM bytecode, with and without the peephole optimizations.
+    fn assert_same_execution(bytecode: &[BrilligOpcode<FieldElement>], calldata: &[FieldElement]) {
+        let expected = execute_brillig(bytecode, calldata);
+        for peephole in [true, false] {
+            let (avm_bytecode, _) = brillig_to_avm(bytecode, BrilligToAvmOptions { peephole })
+                .expect("Failed to transpile the Brillig program");
+            let environment =
+                ExecutionEnvironment { calldata: calldata.to_vec(), ..Default::default() };
+            let result = execute_avm_bytecode(
+                &avm_bytecode,
+                &environment,
+                &mut MockWorldState::default(),
+                &Bn254BlackBoxSolver,
+                DEFAULT_MAX_STEPS,
+            );
+            assert_eq!(
+                result,
+                Ok(ExecutionResult::Returned(expected.clone())),
+                "AVM execution differs from Brillig (peephole: {peephole})"
+            );
+        }
+    }
+
+    fn u32_const(destination: usize, value: u128) -> BrilligOpcode<FieldElement> {
+        BrilligOpcode::Const {
+            destination: MemoryAddress::direct(destination),
+            bit_size: BitSize::Integer(IntegerBitSize::U32),
+            value: FieldElement::from(value),
+        }
+    }
+
+    fn int_op(
+        op: BinaryIntOp,
+        destination: usize,
+        lhs: usize,
+        rhs: usize,
+    ) -> BrilligOpcode<FieldElement> {
+        BrilligOpcode::BinaryIntOp {
+            destination: MemoryAddress::direct(destination),
+            op,
+            bit_size: IntegerBitSize::U32,
+            lhs: MemoryAddress::direct(lhs),
+            rhs: MemoryAddress::direct(rhs),
+        }
+    }
+
+    fn field_op(
+        op: BinaryFieldOp,
+        destination: usize,
+        lhs: usize,
+        rhs: usize,
+    ) -> BrilligOpcode<FieldElement> {
+        BrilligOpcode::BinaryFieldOp {
+            destination: MemoryAddress::direct(destination),
+            op,
+            lhs: MemoryAddress::direct(lhs),
+            rhs: MemoryAddress::direct(rhs),
+        }
+    }
+
+    /// Copies `size` calldata fields to memory offset 3, using offsets 1 and 2 as scratch.
+    fn copy_calldata(size: u128) -> Vec<BrilligOpcode<FieldElement>> {
+        vec![
+            u32_const(1, 0),
+            u32_const(2, size),
+            BrilligOpcode::CalldataCopy {
+                destination_address: MemoryAddress::direct(3),
+                size_address: MemoryAddress::direct(2),
+                offset_address: MemoryAddress::direct(1),
+            },
+        ]
+    }
+
+    fn stop(pointer: usize, size: usize) -> BrilligOpcode<FieldElement> {
+        BrilligOpcode::Stop {
+            return_data: HeapVector {
+                pointer: MemoryAddress::direct(pointer),
+                size: MemoryAddress::direct(size),
+            },
+        }
+    }
+
+    #[test]
+    fn field_arithmetic_matches_brillig() {
+        let mut bytecode = copy_calldata(2);
+        bytecode.extend([
+            // a * b + a, a - b, a / b
+            field_op(BinaryFieldOp::Mul, 10, 3, 4),
+            field_op(BinaryFieldOp::Add, 10, 10, 3),
+            field_op(BinaryFieldOp::Sub, 11, 3, 4),
+            field_op(BinaryFieldOp::Div, 12, 3, 4),
+            u32_const(20, 10),
+            u32_const(21, 3),
+            stop(20, 21),
+        ]);
+        assert_same_execution(&bytecode, &[FieldElement::from(5_u128), FieldElement::from(9_u128)]);
+        assert_same_execution(&bytecode, &[-FieldElement::one(), FieldElement::from(2_u128)]);
+    }
+
+    #[test]
+    fn integer_loop_matches_brillig() {
+        // Sums the squares of the u32s below the first calldata field, wrapping around.
+        let mut bytecode = copy_calldata(1);
+        bytecode.extend([
+            BrilligOpcode::Cast {
+                destination: MemoryAddress::direct(4),
+                source: MemoryAddress::direct(3),
+                bit_size: BitSize::Integer(IntegerBitSize::U32),
+            },
+            u32_const(5, 0),
+            u32_const(6, 0),
+            u32_const(7, 1),
+            // Loop header, at Brillig PC 7
+            int_op(BinaryIntOp::LessThan, 8, 5, 4),
+            BrilligOpcode::JumpIf { condition: MemoryAddress::direct(8), location: 10 },
+            BrilligOpcode::Jump { location: 14 },
+            int_op(BinaryIntOp::Mul, 9, 5, 5),
+            int_op(BinaryIntOp::Add, 6, 6, 9),
+            int_op(BinaryIntOp::Add, 5, 5, 7),
+            BrilligOpcode::Jump { location: 7 },
+            u32_const(10, 6),
+            stop(10, 7),
+        ]);
+        for n in [0_u128, 5, 3000] {
+            assert_same_execution(&bytecode, &[FieldElement::from(n)]);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use acvm::acir::brillig::Opcode as BrilligOpcode;
+use acvm::acir::circuit::brillig::{BrilligFunctionId, BrilligInputs, BrilligOutputs};
+use acvm::acir::circuit::{BrilligOpcodeLocation, Opcode, Program};
+use acvm::acir::native_types::{Expression, Witness};
+use acvm::{AcirField, FieldElement};
+use noirc_errors::debug_info::DebugInfo;
+
+use crate::errors::{TranspileError, TranspileErrorKind};
+
+/// The unconstrained functions called by the ACIR wrapper of an AVM program, linked into a
+/// single Brillig program.
+pub struct LinkedBrilligProgram {
+    pub bytecode: Vec<BrilligOpcode<FieldElement>>,
+    /// The unconstrained function and PC each instruction was copied from.
+    /// Instructions added by the linker have no origin.
+    origins: Vec<Option<(BrilligFunctionId, usize)>>,
+}
+
+/// Extract the Brillig program from its `Program` wrapper.
+/// Noir entry point unconstrained functions are compiled to their own list contained
+/// as part of a full program. Function calls are then accessed through a function
+/// pointer opcode in ACIR that fetches those unconstrained functions from the main list.
+///
+/// The ACIR function must only contain `BrilligCall`s. When it has a single one, the called
+/// function is extracted as is. Otherwise the called functions are laid out one after the other
+/// behind a prologue which `Call`s each of them in order, and jumps to the last one. All but the
+/// last function then `Return` to the prologue instead of stopping, so the program stops with the
+/// return data of the last function.
+///
+/// The AVM has no witnesses: every called function reads its inputs from the start of the shared
+/// calldata and the program returns the return data of the last one. Every call must therefore
+/// take exactly the parameters of the ACIR function in order, as plain witnesses, and the last
+/// call must output exactly the return values of the ACIR function in order.
+pub fn link_brillig_from_acir_program(
+    program: &Program<FieldElement>,
+) -> Result<LinkedBrilligProgram, TranspileError> {
+    let invalid_program =
+        |reason: String| TranspileError::from(TranspileErrorKind::InvalidProgram(reason));
+    if program.functions.len() != 1 {
+        return Err(invalid_program(
+            "An AVM program should have only a single ACIR function wrapping its `BrilligCall`s"
+                .to_string(),
+        ));
+    }
+    let main_function = &program.functions[0];
+
+    let mut calls = Vec::new();
+    for opcode in &main_function.opcodes {
+        let Opcode::BrilligCall { id, inputs, outputs, predicate } = opcode else {
+            return Err(invalid_program("Tried to extract a Brillig program from its ACIR wrapper, but the wrapper contains opcodes other than `BrilligCall`".to_string()));
+        };
+        if predicate.as_ref().is_some_and(|predicate| !is_one(predicate)) {
+            return Err(invalid_program(format!("The `BrilligCall` to function {id} has a predicate, which AVM programs don't support")));
+        }
+        if id.as_usize() >= program.unconstrained_functions.len() {
+            return Err(invalid_program(format!(
+                "The `BrilligCall` to function {id} doesn't match any `Brillig` function"
+            )));
+        }
+        calls.push((*id, inputs, outputs));
+    }
+
+    let (last_call, first_calls) = calls.split_last().ok_or_else(|| {
+        invalid_program("An AVM program should have at least one `BrilligCall`".to_string())
+    })?;
+
+    let parameters: BTreeSet<Witness> = main_function
+        .private_parameters
+        .iter()
+        .chain(main_function.public_parameters.0.iter())
+        .copied()
+        .collect();
+    for (id, inputs, _) in &calls {
+        let input_witnesses = call_input_witnesses(*id, inputs)?;
+        if !input_witnesses.iter().eq(parameters.iter()) {
+            return Err(invalid_program(format!("The `BrilligCall` to function {id} must take exactly the parameters of the ACIR function, in order")));
+        }
+    }
+    let last_outputs = last_call.2.iter().flat_map(|output| match output {
+        BrilligOutputs::Simple(witness) => vec![*witness],
+        BrilligOutputs::Array(witnesses) => witnesses.clone(),
+    });
+    if !last_outputs.eq(main_function.return_values.0.iter().copied()) {
+        return Err(invalid_program(format!("The last `BrilligCall`, to function {}, must output exactly the return values of the ACIR function, in order", last_call.0)));
+    }
+
+    if first_calls.is_empty() {
+        let bytecode = &program.unconstrained_functions[last_call.0.as_usize()].bytecode;
+        let origins = (0..bytecode.len()).map(|pc| Some((last_call.0, pc))).collect();
+        return Ok(LinkedBrilligProgram { bytecode: bytecode.clone(), origins });
+    }
+
+    // Each function called before the last one is laid out once, in order of first call. The last
+    // function gets its own copy, which stops the program.
+    let mut returning_functions: Vec<BrilligFunctionId> = Vec::new();
+    for (id, _, _) in first_calls {
+        if !returning_functions.contains(id) {
+            returning_functions.push(*id);
+        }
+    }
+    let layout = returning_functions
+        .iter()
+        .map(|id| (*id, true))
+        .chain(std::iter::once((last_call.0, false)))
+        .collect::<Vec<_>>();
+
+    let mut function_starts = Vec::with_capacity(layout.len());
+    let mut next_start = calls.len();
+    for (id, _) in &layout {
+        function_starts.push(next_start);
+        next_start += program.unconstrained_functions[id.as_usize()].bytecode.len();
+    }
+    let start_of = |id: BrilligFunctionId| {
+        let position = returning_functions.iter().position(|returning| *returning == id);
+        function_starts[position.expect("called functions are laid out")]
+    };
+
+    let mut bytecode: Vec<BrilligOpcode<FieldElement>> = first_calls
+        .iter()
+        .map(|(id, _, _)| BrilligOpcode::Call { location: start_of(*id) })
+        .collect();
+    bytecode.push(BrilligOpcode::Jump { location: *function_starts.last().unwrap() });
+    let mut origins = vec![None; bytecode.len()];
+
+    for ((id, returns), start) in layout.into_iter().zip(function_starts) {
+        let function_bytecode = &program.unconstrained_functions[id.as_usize()].bytecode;
+        for (pc, opcode) in function_bytecode.iter().enumerate() {
+            let opcode = relocate_opcode(opcode, start, function_bytecode.len(), returns)
+                .map_err(|kind| TranspileError::new(kind).at_brillig_pc(start + pc))?;
+            bytecode.push(opcode);
+            origins.push(Some((id, pc)));
+        }
+    }
+    Ok(LinkedBrilligProgram { bytecode, origins })
+}
+
+/// Moves the Brillig locations of the debug infos of the linked functions to the PCs of the
+/// linked program, as if it was the single unconstrained function of the program.
+pub fn link_debug_infos(
+    mut debug_infos: Vec<DebugInfo>,
+    program: &LinkedBrilligProgram,
+) -> Vec<DebugInfo> {
+    for debug_info in debug_infos.iter_mut() {
+        let mut linked_locations = BTreeMap::new();
+        for (linked_pc, origin) in program.origins.iter().enumerate() {
+            let Some((id, pc)) = origin else {
+                continue;
+            };
+            let locations = debug_info
+                .brillig_locations
+                .get(id)
+                .and_then(|locations| locations.get(&BrilligOpcodeLocation(*pc)));
+            if let Some(locations) = locations {
+                linked_locations.insert(BrilligOpcodeLocation(linked_pc), locations.clone());
+            }
+        }
+        debug_info.brillig_locations = BTreeMap::from([(BrilligFunctionId(0), linked_locations)]);
+    }
+    debug_infos
+}
+
+/// Moves the jump locations of an instruction of a function laid out at `start`, and turns
+/// `Stop` into `Return` if the function is called by the prologue.
+fn relocate_opcode(
+    opcode: &BrilligOpcode<FieldElement>,
+    start: usize,
+    function_size: usize,
+    returns: bool,
+) -> Result<BrilligOpcode<FieldElement>, TranspileErrorKind> {
+    let relocate = |location: usize| {
+        if location >= function_size {
+            return Err(TranspileErrorKind::InvalidOperand(format!(
+                "Jump location {location} is outside of the function, which has {function_size} instructions"
+            )));
+        }
+        Ok(start + location)
+    };
+    Ok(match opcode {
+        BrilligOpcode::Jump { location } => BrilligOpcode::Jump { location: relocate(*location)? },
+        BrilligOpcode::JumpIf { condition, location } => {
+            BrilligOpcode::JumpIf { condition: *condition, location: relocate(*location)? }
+        }
+        BrilligOpcode::JumpIfNot { condition, location } => {
+            BrilligOpcode::JumpIfNot { condition: *condition, location: relocate(*location)? }
+        }
+        BrilligOpcode::Call { location } => BrilligOpcode::Call { location: relocate(*location)? },
+        BrilligOpcode::Stop { .. } if returns => BrilligOpcode::Return,
+        _ => opcode.clone(),
+    })
+}
+
+fn is_one(expression: &Expression<FieldElement>) -> bool {
+    expression.to_const().is_some_and(|constant| constant.is_one())
+}
+
+/// Returns the witnesses passed to a `BrilligCall`, which must all be plain witnesses.
+fn call_input_witnesses(
+    id: BrilligFunctionId,
+    inputs: &[BrilligInputs<FieldElement>],
+) -> Result<Vec<Witness>, TranspileError> {
+    let invalid_program =
+        |reason: String| TranspileError::from(TranspileErrorKind::InvalidProgram(reason));
+    let expressions = inputs.iter().map(|input| match input {
+        BrilligInputs::Single(expression) => Ok(std::slice::from_ref(expression)),
+        BrilligInputs::Array(expressions) => Ok(expressions.as_slice()),
+        BrilligInputs::MemoryArray(_) => Err(invalid_program(format!(
+            "The `BrilligCall` to function {id} reads an ACIR memory block, which can't be linked"
+        ))),
+    });
+    let mut witnesses = Vec::new();
+    for expressions in expressions {
+        for expression in expressions? {
+            let witness = expression.to_witness().ok_or_else(|| {
+                invalid_program(format!("The `BrilligCall` to function {id} takes inputs computed by the ACIR function, which can't be linked"))
+            })?;
+            witnesses.push(witness);
+        }
+    }
+    Ok(witnesses)
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::brillig::{HeapVector, MemoryAddress};
+    use acvm::acir::circuit::brillig::BrilligBytecode;
+    use acvm::acir::circuit::{Circuit, PublicInputs};
+
+    use super::*;
+
+    fn stop() -> BrilligOpcode<FieldElement> {
+        BrilligOpcode::Stop {
+            return_data: HeapVector {
+                pointer: MemoryAddress::direct(0),
+                size: MemoryAddress::direct(1),
+            },
+        }
+    }
+
+    fn call(
+        id: u32,
+        inputs: Vec<BrilligInputs<FieldElement>>,
+        outputs: Vec<BrilligOutputs>,
+    ) -> Opcode<FieldElement> {
+        Opcode::BrilligCall { id: BrilligFunctionId(id), inputs, outputs, predicate: None }
+    }
+
+    fn witness_input(index: u32) -> BrilligInputs<FieldElement> {
+        BrilligInputs::Single(Witness(index).into())
+    }
+
+    /// A program taking witnesses 0 and 1 and returning witness 2, whose unconstrained
+    /// functions each have a jump followed by a stop.
+    fn program(opcodes: Vec<Opcode<FieldElement>>) -> Program<FieldElement> {
+        let main_function = Circuit {
+            opcodes,
+            private_parameters: BTreeSet::from([Witness(0), Witness(1)]),
+            return_values: PublicInputs(BTreeSet::from([Witness(2)])),
+            ..Default::default()
+        };
+        let function =
+            BrilligBytecode { bytecode: vec![BrilligOpcode::Jump { location: 1 }, stop()] };
+        Program {
+            functions: vec![main_function],
+            unconstrained_functions: vec![function.clone(), function],
+        }
+    }
+
+    fn link_error(program: &Program<FieldElement>) -> TranspileErrorKind {
+        match link_brillig_from_acir_program(program) {
+            Ok(_) => panic!("Expected linking to fail"),
+            Err(error) => error.kind,
+        }
+    }
+
+    #[test]
+    fn extracts_a_single_call() {
+        let program = program(vec![call(
+            1,
+            vec![witness_input(0), witness_input(1)],
+            vec![BrilligOutputs::Simple(Witness(2))],
+        )]);
+        let linked = link_brillig_from_acir_program(&program).unwrap();
+        assert_eq!(linked.bytecode, program.unconstrained_functions[1].bytecode);
+    }
+
+    #[test]
+    fn accepts_array_inputs_and_outputs() {
+        let program = program(vec![call(
+            0,
+            vec![BrilligInputs::Array(vec![Witness(0).into(), Witness(1).into()])],
+            vec![BrilligOutputs::Array(vec![Witness(2)])],
+        )]);
+        assert!(link_brillig_from_acir_program(&program).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_single_call_which_does_not_take_all_parameters() {
+        let missing_parameter = program(vec![call(
+            0,
+            vec![witness_input(0)],
+            vec![BrilligOutputs::Simple(Witness(2))],
+        )]);
+        assert!(matches!(link_error(&missing_parameter), TranspileErrorKind::InvalidProgram(_)));
+
+        let swapped_parameters = program(vec![call(
+            0,
+            vec![witness_input(1), witness_input(0)],
+            vec![BrilligOutputs::Simple(Witness(2))],
+        )]);
+        assert!(matches!(link_error(&swapped_parameters), TranspileErrorKind::InvalidProgram(_)));
+    }
+
+    #[test]
+    fn rejects_computed_inputs() {
+        let sum = &Expression::from(Witness(0)) + &Expression::from(Witness(1));
+        let program = program(vec![call(
+            0,
+            vec![BrilligInputs::Single(sum), witness_input(1)],
+            vec![BrilligOutputs::Simple(Witness(2))],
+        )]);
+        assert!(matches!(link_error(&program), TranspileErrorKind::InvalidProgram(_)));
+    }
+
+    #[test]
+    fn rejects_outputs_which_are_not_the_return_values() {
+        let program = program(vec![call(
+            0,
+            vec![witness_input(0), witness_input(1)],
+            vec![BrilligOutputs::Simple(Witness(2)), BrilligOutputs::Simple(Witness(3))],
+        )]);
+        assert!(matches!(link_error(&program), TranspileErrorKind::InvalidProgram(_)));
+    }
+
+    #[test]
+    fn links_several_calls_behind_a_prologue() {
+        let program = program(vec![
+            call(
+                0,
+                vec![witness_input(0), witness_input(1)],
+                vec![BrilligOutputs::Simple(Witness(3))],
+            ),
+            call(
+                1,
+                vec![witness_input(0), witness_input(1)],
+                vec![BrilligOutputs::Simple(Witness(2))],
+            ),
+        ]);
+        let linked = link_brillig_from_acir_program(&program).unwrap();
+        assert_eq!(
+            linked.bytecode,
+            vec![
+                BrilligOpcode::Call { location: 2 },
+                BrilligOpcode::Jump { location: 4 },
+                BrilligOpcode::Jump { location: 3 },
+                BrilligOpcode::Return,
+                BrilligOpcode::Jump { location: 5 },
+                stop(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_several_calls_which_take_a_subset_of_the_parameters() {
+        // Each linked function would read the calldata from its start, so the first call would
+        // read witness 0 instead of witness 1.
+        let program = program(vec![
+            call(0, vec![witness_input(1)], vec![BrilligOutputs::Simple(Witness(3))]),
+            call(
+                1,
+                vec![witness_input(0), witness_input(1)],
+                vec![BrilligOutputs::Simple(Witness(2))],
+            ),
+        ]);
+        assert!(matches!(link_error(&program), TranspileErrorKind::InvalidProgram(_)));
+    }
+
+    #[test]
+    fn rejects_several_calls_when_an_earlier_one_computes_the_return_values() {
+        let program = program(vec![
+            call(
+                0,
+                vec![witness_input(0), witness_input(1)],
+                vec![BrilligOutputs::Simple(Witness(2))],
+            ),
+            call(1, vec![witness_input(0), witness_input(1)], vec![]),
+        ]);
+        assert!(matches!(link_error(&program), TranspileErrorKind::InvalidProgram(_)));
+    }
+}
@@ -1,60 +1,282 @@
 #![warn(clippy::semicolon_if_nothing_returned)]
 #![cfg_attr(not(test), warn(unused_crate_dependencies, unused_extern_crates))]
 
+use acvm::FieldElement;
+use base64::Engine;
+use bn254_blackbox_solver::Bn254BlackBoxSolver;
 use log::warn;
 use std::env;
 use std::fs;
 use std::path::Path;
+use std::process;
 
 mod bit_traits;
+mod decoder;
+mod disassembler;
+mod errors;
 mod instructions;
+mod interpreter;
+mod linker;
 mod opcodes;
+mod peephole;
+mod report;
 mod transpile;
 mod transpile_contract;
 mod utils;
+mod world_state;
 
-use transpile_contract::{CompiledAcirContractArtifact, TranspiledContractArtifact};
+use disassembler::disassemble_contract;
+use interpreter::{execute_avm_bytecode, ExecutionResult, DEFAULT_MAX_STEPS};
+use report::{build_transpile_report, check_size_budget, count_brillig_instructions};
+use transpile::BrilligToAvmOptions;
+use transpile_contract::{
+    AvmOrAcirContractFunctionArtifact, CompiledAcirContractArtifact, TranspiledContractArtifact,
+};
+use world_state::{ExecutionEnvironment, MockWorldState};
+
+const USAGE: &str = "Usage:
+    avm-transpiler <contract-artifact-json> <transpiled-output-json> [--report <report-json>] [--max-bytecode-size <bytes>] [--no-peephole]
+    avm-transpiler disassemble <transpiled-artifact-json> [<function-name>]
+    avm-transpiler execute <transpiled-artifact-json> <function-name> [<calldata-field>...]";
 
 fn main() {
     env_logger::init();
 
     let args: Vec<String> = env::args().collect();
-    let in_contract_artifact_path = &args[1];
-    let out_transpiled_artifact_path = &args[2];
-    let json_parse_error = format!(
-        "Unable to parse json for: {in_contract_artifact_path}
+    let result = match args.iter().skip(1).map(String::as_str).collect::<Vec<_>>()[..] {
+        ["disassemble", transpiled_artifact_path] => {
+            print_disassembly(transpiled_artifact_path, None)
+        }
+        ["disassemble", transpiled_artifact_path, function_name] => {
+            print_disassembly(transpiled_artifact_path, Some(function_name))
+        }
+        ["execute", transpiled_artifact_path, function_name, ref calldata @ ..] => {
+            print_execution(transpiled_artifact_path, function_name, calldata)
+        }
+        [in_contract_artifact_path, out_transpiled_artifact_path, ref options @ ..] => {
+            parse_transpile_options(options).and_then(|options| {
+                transpile_artifact(in_contract_artifact_path, out_transpiled_artifact_path, options)
+            })
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{error}");
+        process::exit(1);
+    }
+}
+
+/// Prints the AVM instructions of a transpiled contract artifact, along with their source code.
+fn print_disassembly(
+    transpiled_artifact_path: &str,
+    function_name: Option<&str>,
+) -> Result<(), String> {
+    let contract = read_transpiled_artifact(transpiled_artifact_path)?;
+    print!("{}", disassemble_contract(&contract, function_name)?);
+    Ok(())
+}
+
+/// Executes an AVM function of a transpiled contract artifact against an empty mocked world
+/// state, then prints its output and the side effects it produced.
+fn print_execution(
+    transpiled_artifact_path: &str,
+    function_name: &str,
+    calldata: &[&str],
+) -> Result<(), String> {
+    let contract = read_transpiled_artifact(transpiled_artifact_path)?;
+    let function = contract
+        .functions
+        .iter()
+        .find_map(|function| match function {
+            AvmOrAcirContractFunctionArtifact::Avm(function) if function.name == function_name => {
+                Some(function)
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            format!("Contract {} has no AVM function named {function_name}", contract.name)
+        })?;
+    let bytecode = base64::prelude::BASE64_STANDARD
+        .decode(&function.bytecode)
+        .map_err(|error| format!("Unable to decode bytecode of {function_name} ({error})"))?;
+    let calldata = calldata
+        .iter()
+        .map(|field| {
+            FieldElement::try_from_str(field)
+                .ok_or_else(|| format!("Invalid calldata field: {field}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let environment = ExecutionEnvironment { calldata, ..Default::default() };
+    let mut world_state = MockWorldState::default();
+    let result = execute_avm_bytecode(
+        &bytecode,
+        &environment,
+        &mut world_state,
+        &Bn254BlackBoxSolver,
+        DEFAULT_MAX_STEPS,
+    )
+    .map_err(|error| error.to_string())?;
+
+    match result {
+        ExecutionResult::Returned(output) => println!("Returned: {}", format_fields(&output)),
+        ExecutionResult::Reverted(output) => println!("Reverted: {}", format_fields(&output)),
+    }
+    let mut storage = world_state.public_storage.iter().collect::<Vec<_>>();
+    storage.sort();
+    for ((_, slot), value) in storage {
+        println!("Storage write: {slot} = {value}");
+    }
+    for (_, note_hash) in &world_state.new_note_hashes {
+        println!("Note hash: {note_hash}");
+    }
+    for (_, nullifier) in &world_state.new_nullifiers {
+        println!("Nullifier: {nullifier}");
+    }
+    for log in &world_state.unencrypted_logs {
+        println!("Unencrypted log from {}: {}", log.contract_address, format_fields(&log.fields));
+    }
+    for message in &world_state.l2_to_l1_messages {
+        println!(
+            "L2 to L1 message from {} to {}: {}",
+            message.contract_address, message.recipient, message.content
+        );
+    }
+    Ok(())
+}
+
+fn format_fields(fields: &[FieldElement]) -> String {
+    let fields = fields.iter().map(FieldElement::to_string).collect::<Vec<_>>();
+    format!("[{}]", fields.join(", "))
+}
+
+fn read_transpiled_artifact(
+    transpiled_artifact_path: &str,
+) -> Result<TranspiledContractArtifact, String> {
+    let contract_json = fs::read_to_string(Path::new(transpiled_artifact_path))
+        .map_err(|error| format!("Unable to read file: {transpiled_artifact_path} ({error})"))?;
+    serde_json::from_str(&contract_json)
+        .map_err(|error| format!("Unable to parse json for: {transpiled_artifact_path} ({error})"))
+}
+
+/// Removes the transpiled artifact written by a previous run, if any.
+fn remove_stale_output(out_transpiled_artifact_path: &str) -> Result<(), String> {
+    let path = Path::new(out_transpiled_artifact_path);
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(path).map_err(|error| {
+        format!("Unable to remove stale file: {out_transpiled_artifact_path} ({error})")
+    })
+}
+
+/// Optional outputs and checks of a transpilation.
+#[derive(Default)]
+struct TranspileOptions<'a> {
+    /// Where to write the JSON report of the transpiled functions
+    report_path: Option<&'a str>,
+    /// Size in bytes that the bytecode of each function must not exceed
+    max_bytecode_size: Option<usize>,
+    /// Disables the peephole optimizations, to find out whether they cause a miscompilation
+    no_peephole: bool,
+}
+
+fn parse_transpile_options<'a>(options: &[&'a str]) -> Result<TranspileOptions<'a>, String> {
+    let mut parsed = TranspileOptions::default();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        if *option == "--no-peephole" {
+            parsed.no_peephole = true;
+            continue;
+        }
+        if !["--report", "--max-bytecode-size"].contains(option) {
+            return Err(format!("Unknown option: {option}\n{USAGE}"));
+        }
+        let value =
+            *options.next().ok_or_else(|| format!("Missing value for {option}\n{USAGE}"))?;
+        if *option == "--report" {
+            parsed.report_path = Some(value);
+        } else {
+            let size = value.parse().map_err(|_| format!("Invalid bytecode size: {value}"))?;
+            parsed.max_bytecode_size = Some(size);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Transpiles the public functions of a contract artifact, writing the transpiled artifact to
+/// the output path. Returns the message to show to the user on failure.
+/// If a function exceeds the size budget, the report is written but the transpiled artifact
+/// isn't, and the output left by a previous run is removed (after being backed up) so that it
+/// can't be mistaken for the result of this one.
+fn transpile_artifact(
+    in_contract_artifact_path: &str,
+    out_transpiled_artifact_path: &str,
+    options: TranspileOptions,
+) -> Result<(), String> {
+    let json_parse_error = |error: serde_json::Error| {
+        format!(
+            "Unable to parse json for: {in_contract_artifact_path} ({error})
     This is probably a stale json file with a different wire format.
     You might need to recompile the contract or delete the json file"
-    );
+        )
+    };
 
     // Parse original (pre-transpile) contract.
     let contract_json = fs::read_to_string(Path::new(in_contract_artifact_path))
-        .unwrap_or_else(|_| panic!("Unable to read file: {in_contract_artifact_path}"));
+        .map_err(|error| format!("Unable to read file: {in_contract_artifact_path} ({error})"))?;
     let raw_json_obj: serde_json::Value =
-        serde_json::from_str(&contract_json).expect(&json_parse_error);
+        serde_json::from_str(&contract_json).map_err(json_parse_error)?;
 
     // Skip if contract has "transpiled: true" flag!
     if let Some(serde_json::Value::Bool(true)) = raw_json_obj.get("transpiled") {
         warn!("Contract already transpiled. Skipping.");
-        return;
+        return Ok(());
     }
 
     // Backup the output file if it already exists.
     if Path::new(out_transpiled_artifact_path).exists() {
         std::fs::copy(
             Path::new(out_transpiled_artifact_path),
-            Path::new(&(out_transpiled_artifact_path.clone() + ".bak")),
+            Path::new(&format!("{out_transpiled_artifact_path}.bak")),
         )
-        .unwrap_or_else(|_| panic!("Unable to backup file: {out_transpiled_artifact_path}"));
+        .map_err(|error| {
+            format!("Unable to backup file: {out_transpiled_artifact_path} ({error})")
+        })?;
     }
 
     // Parse json into contract object
     let contract: CompiledAcirContractArtifact =
-        serde_json::from_str(&contract_json).expect(&json_parse_error);
+        serde_json::from_str(&contract_json).map_err(json_parse_error)?;
+
+    let needs_report = options.report_path.is_some() || options.max_bytecode_size.is_some();
+    let brillig_instruction_counts =
+        needs_report.then(|| count_brillig_instructions(&contract)).transpose()?;
 
     // Transpile contract to AVM bytecode
-    let transpiled_contract = TranspiledContractArtifact::from(contract);
-    let transpiled_json =
-        serde_json::to_string(&transpiled_contract).expect("Unable to serialize json");
-    fs::write(out_transpiled_artifact_path, transpiled_json).expect("Unable to write file");
+    let transpile_options = BrilligToAvmOptions { peephole: !options.no_peephole };
+    let transpiled_contract = TranspiledContractArtifact::transpile(contract, transpile_options)
+        .map_err(|error| error.to_string())?;
+
+    if let Some(brillig_instruction_counts) = brillig_instruction_counts {
+        let report = build_transpile_report(&transpiled_contract, &brillig_instruction_counts)?;
+        if let Some(report_path) = options.report_path {
+            let report_json = serde_json::to_string_pretty(&report)
+                .map_err(|error| format!("Unable to serialize json ({error})"))?;
+            fs::write(report_path, report_json)
+                .map_err(|error| format!("Unable to write file: {report_path} ({error})"))?;
+        }
+        if let Some(max_bytecode_size) = options.max_bytecode_size {
+            if let Err(error) = check_size_budget(&report, max_bytecode_size) {
+                remove_stale_output(out_transpiled_artifact_path)?;
+                return Err(error);
+            }
+        }
+    }
+
+    let transpiled_json = serde_json::to_string(&transpiled_contract)
+        .map_err(|error| format!("Unable to serialize json ({error})"))?;
+    fs::write(out_transpiled_artifact_path, transpiled_json)
+        .map_err(|error| format!("Unable to write file: {out_transpiled_artifact_path} ({error})"))
 }
@@ -82,7 +82,84 @@ pub enum AvmOpcode {
     TORADIXBE,
 }
 
+/// All AVM opcodes, in the order of their byte values.
+const ALL_OPCODES: [AvmOpcode; 68] = [
+    AvmOpcode::ADD_8,
+    AvmOpcode::ADD_16,
+    AvmOpcode::SUB_8,
+    AvmOpcode::SUB_16,
+    AvmOpcode::MUL_8,
+    AvmOpcode::MUL_16,
+    AvmOpcode::DIV_8,
+    AvmOpcode::DIV_16,
+    AvmOpcode::FDIV_8,
+    AvmOpcode::FDIV_16,
+    AvmOpcode::EQ_8,
+    AvmOpcode::EQ_16,
+    AvmOpcode::LT_8,
+    AvmOpcode::LT_16,
+    AvmOpcode::LTE_8,
+    AvmOpcode::LTE_16,
+    AvmOpcode::AND_8,
+    AvmOpcode::AND_16,
+    AvmOpcode::OR_8,
+    AvmOpcode::OR_16,
+    AvmOpcode::XOR_8,
+    AvmOpcode::XOR_16,
+    AvmOpcode::NOT_8,
+    AvmOpcode::NOT_16,
+    AvmOpcode::SHL_8,
+    AvmOpcode::SHL_16,
+    AvmOpcode::SHR_8,
+    AvmOpcode::SHR_16,
+    AvmOpcode::CAST_8,
+    AvmOpcode::CAST_16,
+    AvmOpcode::GETENVVAR_16,
+    AvmOpcode::CALLDATACOPY,
+    AvmOpcode::RETURNDATASIZE,
+    AvmOpcode::RETURNDATACOPY,
+    AvmOpcode::JUMP_32,
+    AvmOpcode::JUMPI_32,
+    AvmOpcode::INTERNALCALL,
+    AvmOpcode::INTERNALRETURN,
+    AvmOpcode::SET_8,
+    AvmOpcode::SET_16,
+    AvmOpcode::SET_32,
+    AvmOpcode::SET_64,
+    AvmOpcode::SET_128,
+    AvmOpcode::SET_FF,
+    AvmOpcode::MOV_8,
+    AvmOpcode::MOV_16,
+    AvmOpcode::SLOAD,
+    AvmOpcode::SSTORE,
+    AvmOpcode::NOTEHASHEXISTS,
+    AvmOpcode::EMITNOTEHASH,
+    AvmOpcode::NULLIFIEREXISTS,
+    AvmOpcode::EMITNULLIFIER,
+    AvmOpcode::L1TOL2MSGEXISTS,
+    AvmOpcode::GETCONTRACTINSTANCE,
+    AvmOpcode::EMITUNENCRYPTEDLOG,
+    AvmOpcode::SENDL2TOL1MSG,
+    AvmOpcode::CALL,
+    AvmOpcode::STATICCALL,
+    AvmOpcode::RETURN,
+    AvmOpcode::REVERT_8,
+    AvmOpcode::REVERT_16,
+    AvmOpcode::DEBUGLOG,
+    AvmOpcode::POSEIDON2,
+    AvmOpcode::SHA256COMPRESSION,
+    AvmOpcode::KECCAKF1600,
+    AvmOpcode::ECADD,
+    AvmOpcode::MSM,
+    AvmOpcode::TORADIXBE,
+];
+
 impl AvmOpcode {
+    /// Decodes the opcode from its byte value in the bytecode.
+    pub fn from_u8(byte: u8) -> Option<AvmOpcode> {
+        ALL_OPCODES.get(byte as usize).copied()
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             // Compute
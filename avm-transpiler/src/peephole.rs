@@ -0,0 +1,493 @@
+use std::collections::{HashMap, HashSet};
+
+use acvm::FieldElement;
+use log::debug;
+
+use crate::instructions::{AvmInstruction, AvmOperand, AvmTypeTag};
+use crate::opcodes::AvmOpcode;
+
+/// Removes or simplifies redundant AVM instructions generated from a Brillig program:
+/// - `MOV`s of a value to itself, or to a memory location which already holds it
+/// - `SET`s of a constant to a memory location which already holds it
+/// - `CAST`s of a value which already has the target tag, which become `MOV`s
+/// - jumps to the next instruction
+///
+/// Jump locations must not be resolved yet, as they are Brillig PCs.
+/// `brillig_pcs_to_avm_indices` maps each Brillig PC to the index of its first AVM instruction,
+/// and is updated to point to the first remaining instruction at or after it.
+pub fn optimize_avm_instructions(
+    mut avm_instrs: Vec<AvmInstruction>,
+    brillig_pcs_to_avm_indices: &mut [usize],
+) -> Vec<AvmInstruction> {
+    let num_instructions = avm_instrs.len();
+
+    let jump_targets = jump_targets(&avm_instrs, brillig_pcs_to_avm_indices);
+    let keep = simplify_memory_instructions(&mut avm_instrs, &jump_targets);
+    avm_instrs = remove_instructions(avm_instrs, &keep, brillig_pcs_to_avm_indices);
+
+    // Removing instructions can turn other jumps into jumps to the next instruction.
+    loop {
+        let keep = avm_instrs
+            .iter()
+            .enumerate()
+            .map(|(index, instr)| !is_jump_to_next(index, instr, brillig_pcs_to_avm_indices))
+            .collect::<Vec<_>>();
+        if keep.iter().all(|keep| *keep) {
+            break;
+        }
+        avm_instrs = remove_instructions(avm_instrs, &keep, brillig_pcs_to_avm_indices);
+    }
+
+    debug!(
+        "Peephole optimizations removed {} of {} AVM instructions",
+        num_instructions - avm_instrs.len(),
+        num_instructions
+    );
+    avm_instrs
+}
+
+/// Simplifies `CAST`s and finds redundant `MOV`s and `SET`s, by tracking the tags and values
+/// known to be in memory since the start of the current basic block.
+/// Returns whether each instruction should be kept.
+fn simplify_memory_instructions(
+    avm_instrs: &mut [AvmInstruction],
+    jump_targets: &HashSet<usize>,
+) -> Vec<bool> {
+    let mut known_memory = KnownMemory::default();
+    let mut keep = vec![true; avm_instrs.len()];
+    for (index, instr) in avm_instrs.iter_mut().enumerate() {
+        if jump_targets.contains(&index) {
+            known_memory.clear();
+        }
+
+        if matches!(instr.opcode, AvmOpcode::CAST_8 | AvmOpcode::CAST_16) {
+            let source_tag = direct_location(instr, 0)
+                .and_then(|source| known_memory.get(source))
+                .map(|source| source.tag);
+            if source_tag.is_some() && source_tag == instr.tag {
+                instr.opcode = if instr.opcode == AvmOpcode::CAST_8 {
+                    AvmOpcode::MOV_8
+                } else {
+                    AvmOpcode::MOV_16
+                };
+                instr.tag = None;
+            }
+        }
+
+        let is_redundant = match instr.opcode {
+            AvmOpcode::MOV_8 | AvmOpcode::MOV_16 => {
+                let source = direct_location(instr, 0).and_then(|source| known_memory.get(source));
+                let destination =
+                    direct_location(instr, 1).and_then(|destination| known_memory.get(destination));
+                let is_move_to_itself = operand_mode(instr, 0)
+                    .is_some_and(|source_mode| operand_mode(instr, 1) == Some(source_mode));
+                is_move_to_itself
+                    || (source.is_some_and(|source| source.value.is_some())
+                        && source == destination)
+            }
+            AvmOpcode::SET_8
+            | AvmOpcode::SET_16
+            | AvmOpcode::SET_32
+            | AvmOpcode::SET_64
+            | AvmOpcode::SET_128
+            | AvmOpcode::SET_FF => {
+                let destination = direct_location(instr, 0);
+                destination.zip(set_value(instr)).is_some_and(|(destination, value)| {
+                    known_memory.get(destination) == Some(&value)
+                })
+            }
+            _ => false,
+        };
+        if is_redundant {
+            keep[index] = false;
+        } else {
+            known_memory.record_write(instr);
+        }
+    }
+    keep
+}
+
+/// What is known about the content of a memory location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KnownValue {
+    tag: AvmTypeTag,
+    value: Option<FieldElement>,
+}
+
+/// A memory location addressed directly, which may be relative to the stack pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MemoryLocation {
+    relative: bool,
+    offset: u32,
+}
+
+#[derive(Default)]
+struct KnownMemory {
+    values: HashMap<MemoryLocation, KnownValue>,
+}
+
+impl KnownMemory {
+    fn get(&self, location: MemoryLocation) -> Option<&KnownValue> {
+        self.values.get(&location)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Updates the known memory with the effects of an instruction.
+    fn record_write(&mut self, instr: &AvmInstruction) {
+        let operand_index = match memory_write(instr.opcode) {
+            MemoryWrite::Nothing => return,
+            MemoryWrite::Operand(operand_index) => operand_index,
+            MemoryWrite::Unknown => return self.clear(),
+        };
+        let Some(destination) = direct_location(instr, operand_index) else {
+            // An indirect write could be to any location.
+            return self.clear();
+        };
+
+        let value = match instr.opcode {
+            AvmOpcode::SET_8
+            | AvmOpcode::SET_16
+            | AvmOpcode::SET_32
+            | AvmOpcode::SET_64
+            | AvmOpcode::SET_128
+            | AvmOpcode::SET_FF => set_value(instr),
+            AvmOpcode::CAST_8 | AvmOpcode::CAST_16 => {
+                instr.tag.map(|tag| KnownValue { tag, value: None })
+            }
+            AvmOpcode::MOV_8 | AvmOpcode::MOV_16 => {
+                direct_location(instr, 0).and_then(|source| self.get(source)).cloned()
+            }
+            AvmOpcode::EQ_8
+            | AvmOpcode::EQ_16
+            | AvmOpcode::LT_8
+            | AvmOpcode::LT_16
+            | AvmOpcode::LTE_8
+            | AvmOpcode::LTE_16
+            | AvmOpcode::NOTEHASHEXISTS
+            | AvmOpcode::NULLIFIEREXISTS
+            | AvmOpcode::L1TOL2MSGEXISTS => {
+                Some(KnownValue { tag: AvmTypeTag::UINT1, value: None })
+            }
+            _ => None,
+        };
+
+        // Absolute and relative locations may alias each other.
+        self.values.retain(|location, _| location.relative == destination.relative);
+        match value {
+            Some(value) => self.values.insert(destination, value),
+            None => self.values.remove(&destination),
+        };
+    }
+}
+
+/// How an instruction writes to memory.
+enum MemoryWrite {
+    Nothing,
+    /// The instruction writes to the memory location of the operand with this index
+    Operand(usize),
+    /// The instruction may write to any memory location
+    Unknown,
+}
+
+fn memory_write(opcode: AvmOpcode) -> MemoryWrite {
+    match opcode {
+        AvmOpcode::ADD_8
+        | AvmOpcode::ADD_16
+        | AvmOpcode::SUB_8
+        | AvmOpcode::SUB_16
+        | AvmOpcode::MUL_8
+        | AvmOpcode::MUL_16
+        | AvmOpcode::DIV_8
+        | AvmOpcode::DIV_16
+        | AvmOpcode::FDIV_8
+        | AvmOpcode::FDIV_16
+        | AvmOpcode::EQ_8
+        | AvmOpcode::EQ_16
+        | AvmOpcode::LT_8
+        | AvmOpcode::LT_16
+        | AvmOpcode::LTE_8
+        | AvmOpcode::LTE_16
+        | AvmOpcode::AND_8
+        | AvmOpcode::AND_16
+        | AvmOpcode::OR_8
+        | AvmOpcode::OR_16
+        | AvmOpcode::XOR_8
+        | AvmOpcode::XOR_16
+        | AvmOpcode::SHL_8
+        | AvmOpcode::SHL_16
+        | AvmOpcode::SHR_8
+        | AvmOpcode::SHR_16
+        | AvmOpcode::NOTEHASHEXISTS
+        | AvmOpcode::NULLIFIEREXISTS
+        | AvmOpcode::L1TOL2MSGEXISTS => MemoryWrite::Operand(2),
+        AvmOpcode::NOT_8
+        | AvmOpcode::NOT_16
+        | AvmOpcode::CAST_8
+        | AvmOpcode::CAST_16
+        | AvmOpcode::MOV_8
+        | AvmOpcode::MOV_16
+        | AvmOpcode::SLOAD => MemoryWrite::Operand(1),
+        AvmOpcode::SET_8
+        | AvmOpcode::SET_16
+        | AvmOpcode::SET_32
+        | AvmOpcode::SET_64
+        | AvmOpcode::SET_128
+        | AvmOpcode::SET_FF
+        | AvmOpcode::GETENVVAR_16
+        | AvmOpcode::RETURNDATASIZE => MemoryWrite::Operand(0),
+        AvmOpcode::JUMP_32
+        | AvmOpcode::JUMPI_32
+        | AvmOpcode::INTERNALRETURN
+        | AvmOpcode::SSTORE
+        | AvmOpcode::EMITNOTEHASH
+        | AvmOpcode::EMITNULLIFIER
+        | AvmOpcode::EMITUNENCRYPTEDLOG
+        | AvmOpcode::SENDL2TOL1MSG
+        | AvmOpcode::RETURN
+        | AvmOpcode::REVERT_8
+        | AvmOpcode::REVERT_16
+        | AvmOpcode::DEBUGLOG => MemoryWrite::Nothing,
+        // Internal and external calls, copies and gadgets write to several locations, some of
+        // them through pointers.
+        _ => MemoryWrite::Unknown,
+    }
+}
+
+/// Returns the (indirect, relative, offset) addressing of a memory offset operand.
+fn operand_mode(instr: &AvmInstruction, operand_index: usize) -> Option<(bool, bool, u32)> {
+    let flags = match instr.indirect {
+        Some(AvmOperand::U8 { value }) => value as u32,
+        Some(AvmOperand::U16 { value }) => value as u32,
+        _ => return None,
+    };
+    let offset = match instr.operands.get(operand_index)? {
+        AvmOperand::U8 { value } => *value as u32,
+        AvmOperand::U16 { value } => *value as u32,
+        AvmOperand::U32 { value } => *value,
+        _ => return None,
+    };
+    let indirect = flags & (1 << operand_index) != 0;
+    let relative = flags & (1 << (instr.operands.len() + operand_index)) != 0;
+    Some((indirect, relative, offset))
+}
+
+/// Returns the memory location of a memory offset operand, unless it's indirect.
+fn direct_location(instr: &AvmInstruction, operand_index: usize) -> Option<MemoryLocation> {
+    match operand_mode(instr, operand_index)? {
+        (false, relative, offset) => Some(MemoryLocation { relative, offset }),
+        (true, _, _) => None,
+    }
+}
+
+fn set_value(instr: &AvmInstruction) -> Option<KnownValue> {
+    let value = match instr.immediates.first()? {
+        AvmOperand::U8 { value } => FieldElement::from(*value as u128),
+        AvmOperand::U16 { value } => FieldElement::from(*value as u128),
+        AvmOperand::U32 { value } => FieldElement::from(*value as u128),
+        AvmOperand::U64 { value } => FieldElement::from(*value as u128),
+        AvmOperand::U128 { value } => FieldElement::from(*value),
+        AvmOperand::FF { value } => *value,
+        AvmOperand::BRILLIG_LOCATION { .. } => return None,
+    };
+    Some(KnownValue { tag: instr.tag?, value: Some(value) })
+}
+
+/// Returns the Brillig PC an instruction jumps to, if it's a jump or an internal call.
+fn jump_location(instr: &AvmInstruction) -> Option<usize> {
+    match instr.opcode {
+        AvmOpcode::JUMP_32 | AvmOpcode::JUMPI_32 | AvmOpcode::INTERNALCALL => {
+            match instr.immediates.first()? {
+                AvmOperand::BRILLIG_LOCATION { brillig_pc } => Some(*brillig_pc as usize),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the indices of the instructions which can be jumped to.
+fn jump_targets(
+    avm_instrs: &[AvmInstruction],
+    brillig_pcs_to_avm_indices: &[usize],
+) -> HashSet<usize> {
+    avm_instrs
+        .iter()
+        .filter_map(jump_location)
+        .map(|brillig_pc| brillig_pcs_to_avm_indices[brillig_pc])
+        .collect()
+}
+
+fn is_jump_to_next(
+    index: usize,
+    instr: &AvmInstruction,
+    brillig_pcs_to_avm_indices: &[usize],
+) -> bool {
+    matches!(instr.opcode, AvmOpcode::JUMP_32 | AvmOpcode::JUMPI_32)
+        && jump_location(instr)
+            .is_some_and(|brillig_pc| brillig_pcs_to_avm_indices[brillig_pc] == index + 1)
+}
+
+/// Removes the instructions which aren't kept, updating `brillig_pcs_to_avm_indices` so that
+/// each Brillig PC maps to the first remaining instruction at or after its original one.
+fn remove_instructions(
+    avm_instrs: Vec<AvmInstruction>,
+    keep: &[bool],
+    brillig_pcs_to_avm_indices: &mut [usize],
+) -> Vec<AvmInstruction> {
+    // The new index of each instruction, and of the end of the program, is the number of
+    // instructions kept before it.
+    let mut new_indices = Vec::with_capacity(keep.len() + 1);
+    let mut num_kept = 0;
+    for keep in keep {
+        new_indices.push(num_kept);
+        if *keep {
+            num_kept += 1;
+        }
+    }
+    new_indices.push(num_kept);
+
+    for avm_index in brillig_pcs_to_avm_indices.iter_mut() {
+        *avm_index = new_indices[*avm_index];
+    }
+    avm_instrs.into_iter().zip(keep).filter_map(|(instr, keep)| keep.then_some(instr)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::brillig::MemoryAddress;
+
+    use super::*;
+    use crate::instructions::AddressingModeBuilder;
+
+    fn set(destination: MemoryAddress, value: u8) -> AvmInstruction {
+        AvmInstruction {
+            opcode: AvmOpcode::SET_8,
+            indirect: Some(AddressingModeBuilder::default().direct_operand(&destination).build()),
+            tag: Some(AvmTypeTag::UINT8),
+            operands: vec![AvmOperand::U8 { value: destination.to_usize() as u8 }],
+            immediates: vec![AvmOperand::U8 { value }],
+        }
+    }
+
+    fn mov(source: MemoryAddress, destination: MemoryAddress) -> AvmInstruction {
+        AvmInstruction {
+            opcode: AvmOpcode::MOV_8,
+            indirect: Some(
+                AddressingModeBuilder::default()
+                    .direct_operand(&source)
+                    .direct_operand(&destination)
+                    .build(),
+            ),
+            operands: vec![
+                AvmOperand::U8 { value: source.to_usize() as u8 },
+                AvmOperand::U8 { value: destination.to_usize() as u8 },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn control_flow(opcode: AvmOpcode, brillig_pc: u32) -> AvmInstruction {
+        AvmInstruction {
+            opcode,
+            immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc }],
+            ..Default::default()
+        }
+    }
+
+    /// Optimizes a program where each Brillig instruction was transpiled to one AVM instruction,
+    /// returning the remaining instructions and the updated mapping from Brillig PCs.
+    fn optimize(avm_instrs: Vec<AvmInstruction>) -> (Vec<String>, Vec<usize>) {
+        let mut brillig_pcs_to_avm_indices: Vec<_> = (0..=avm_instrs.len()).collect();
+        let avm_instrs = optimize_avm_instructions(avm_instrs, &mut brillig_pcs_to_avm_indices);
+        (to_strings(&avm_instrs), brillig_pcs_to_avm_indices)
+    }
+
+    fn to_strings(avm_instrs: &[AvmInstruction]) -> Vec<String> {
+        avm_instrs.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn removes_redundant_moves_and_sets() {
+        let d0 = MemoryAddress::direct(0);
+        let d1 = MemoryAddress::direct(1);
+        let (optimized, _) =
+            optimize(vec![set(d0, 1), set(d0, 1), mov(d0, d1), mov(d0, d1), mov(d1, d1)]);
+        assert_eq!(optimized, to_strings(&[set(d0, 1), mov(d0, d1)]));
+    }
+
+    #[test]
+    fn forgets_known_values_at_jump_targets() {
+        let d0 = MemoryAddress::direct(0);
+        let program = vec![
+            set(d0, 1),
+            // PC 1 can be reached from the jump below, where d0 may hold another value
+            set(d0, 1),
+            set(d0, 2),
+            control_flow(AvmOpcode::JUMPI_32, 1),
+        ];
+        let (optimized, _) = optimize(program);
+        assert_eq!(optimized.len(), 4);
+
+        // Known values are forgotten from the jump target onwards, not before it
+        let program =
+            vec![set(d0, 1), set(d0, 1), set(d0, 1), control_flow(AvmOpcode::JUMPI_32, 2)];
+        let (optimized, _) = optimize(program);
+        assert_eq!(
+            optimized,
+            to_strings(&[set(d0, 1), set(d0, 1), control_flow(AvmOpcode::JUMPI_32, 2)])
+        );
+    }
+
+    #[test]
+    fn internal_calls_clobber_memory() {
+        let d0 = MemoryAddress::direct(0);
+        let program = vec![
+            set(d0, 1),
+            control_flow(AvmOpcode::INTERNALCALL, 4),
+            // The called function may have overwritten d0
+            set(d0, 1),
+            AvmInstruction { opcode: AvmOpcode::INTERNALRETURN, ..Default::default() },
+            set(d0, 2),
+            AvmInstruction { opcode: AvmOpcode::INTERNALRETURN, ..Default::default() },
+        ];
+        let (optimized, _) = optimize(program);
+        assert_eq!(optimized.len(), 6);
+    }
+
+    #[test]
+    fn relative_and_absolute_locations_may_alias() {
+        let direct = MemoryAddress::direct(0);
+        let relative = MemoryAddress::relative(0);
+        let (optimized, _) = optimize(vec![set(direct, 1), set(relative, 2), set(direct, 1)]);
+        assert_eq!(optimized.len(), 3);
+
+        let (optimized, _) = optimize(vec![set(relative, 1), set(direct, 2), set(relative, 1)]);
+        assert_eq!(optimized.len(), 3);
+
+        // Writes with the same addressing don't alias different offsets
+        let other = MemoryAddress::relative(1);
+        let (optimized, _) = optimize(vec![set(relative, 1), set(other, 2), set(relative, 1)]);
+        assert_eq!(optimized, to_strings(&[set(relative, 1), set(other, 2)]));
+    }
+
+    #[test]
+    fn remaps_brillig_pcs_after_removing_instructions() {
+        let d0 = MemoryAddress::direct(0);
+        let d1 = MemoryAddress::direct(1);
+        let program = vec![
+            set(d0, 1),
+            set(d0, 1),
+            // Becomes a jump to the next instruction once the redundant SET is removed
+            control_flow(AvmOpcode::JUMP_32, 4),
+            set(d0, 1),
+            mov(d0, d1),
+        ];
+        let (optimized, brillig_pcs_to_avm_indices) = optimize(program);
+        // Removed instructions map to the next remaining one
+        assert_eq!(brillig_pcs_to_avm_indices, vec![0, 1, 1, 1, 1, 2]);
+        assert_eq!(optimized, to_strings(&[set(d0, 1), mov(d0, d1)]));
+    }
+}
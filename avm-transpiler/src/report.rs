@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap};
+
+use base64::Engine;
+use serde::Serialize;
+
+use crate::decoder::decode_avm_bytecode;
+use crate::errors::find_source_location;
+use crate::linker::link_brillig_from_acir_program;
+use crate::transpile_contract::{
+    AvmContractFunctionArtifact, AvmOrAcirContractFunctionArtifact, CompiledAcirContractArtifact,
+    TranspiledContractArtifact,
+};
+
+/// Number of source lines listed in the report of each function.
+const TOP_SOURCE_LOCATIONS: usize = 10;
+
+/// Machine-readable statistics about the AVM bytecode of a transpiled contract.
+#[derive(Debug, Serialize)]
+pub struct TranspileReport {
+    pub contract_name: String,
+    pub functions: Vec<FunctionReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionReport {
+    pub name: String,
+    /// Size of the AVM bytecode, in bytes
+    pub bytecode_size: usize,
+    pub brillig_instructions: usize,
+    pub avm_instructions: usize,
+    /// Number of AVM instructions emitted per Brillig instruction
+    pub expansion_ratio: f64,
+    /// Number of instructions of each AVM opcode
+    pub opcode_histogram: BTreeMap<&'static str, usize>,
+    /// The source lines which emitted the most bytes, largest first
+    pub top_source_locations: Vec<SourceLocationReport>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceLocationReport {
+    pub path: String,
+    pub line: usize,
+    /// Size of the AVM instructions emitted for the line, in bytes
+    pub bytes: usize,
+}
+
+/// Counts the instructions of the Brillig program of each public function, before it's
+/// transpiled.
+pub fn count_brillig_instructions(
+    contract: &CompiledAcirContractArtifact,
+) -> Result<HashMap<String, usize>, String> {
+    contract
+        .functions
+        .iter()
+        .filter(|function| function.custom_attributes.contains(&"public".to_string()))
+        .map(|function| {
+            let brillig_program =
+                link_brillig_from_acir_program(&function.bytecode).map_err(|error| {
+                    format!("Unable to report on {}::{}: {error}", contract.name, function.name)
+                })?;
+            Ok((function.name.clone(), brillig_program.bytecode.len()))
+        })
+        .collect()
+}
+
+/// Builds the report of the AVM functions of a transpiled contract.
+/// `brillig_instruction_counts` holds the number of Brillig instructions of each function.
+pub fn build_transpile_report(
+    contract: &TranspiledContractArtifact,
+    brillig_instruction_counts: &HashMap<String, usize>,
+) -> Result<TranspileReport, String> {
+    let functions = contract
+        .functions
+        .iter()
+        .filter_map(|function| match function {
+            AvmOrAcirContractFunctionArtifact::Avm(function) => Some(function),
+            AvmOrAcirContractFunctionArtifact::Acir(_) => None,
+        })
+        .map(|function| {
+            let brillig_instructions =
+                brillig_instruction_counts.get(&function.name).copied().unwrap_or_default();
+            build_function_report(contract, function, brillig_instructions)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TranspileReport { contract_name: contract.name.clone(), functions })
+}
+
+fn build_function_report(
+    contract: &TranspiledContractArtifact,
+    function: &AvmContractFunctionArtifact,
+    brillig_instructions: usize,
+) -> Result<FunctionReport, String> {
+    let function_error = |error: String| {
+        format!("Unable to report on {}::{}: {error}", contract.name, function.name)
+    };
+    let bytecode = base64::prelude::BASE64_STANDARD
+        .decode(&function.bytecode)
+        .map_err(|error| function_error(error.to_string()))?;
+    let instructions =
+        decode_avm_bytecode(&bytecode).map_err(|error| function_error(error.to_string()))?;
+
+    let mut opcode_histogram = BTreeMap::new();
+    let mut bytes_per_line: HashMap<(String, usize), usize> = HashMap::new();
+    // Only the first AVM instruction emitted for a Brillig instruction has a location, so the
+    // following ones are attributed to the last location found.
+    let mut current_line = None;
+    for (index, (avm_pc, instruction)) in instructions.iter().enumerate() {
+        *opcode_histogram.entry(instruction.opcode.name()).or_default() += 1;
+
+        if let Some(location) =
+            find_source_location(*avm_pc, &function.debug_symbols, &contract.file_map)
+        {
+            current_line = Some((location.path, location.line));
+        }
+        let next_pc = instructions.get(index + 1).map_or(bytecode.len(), |(next_pc, _)| *next_pc);
+        if let Some(line) = &current_line {
+            *bytes_per_line.entry(line.clone()).or_default() += next_pc - avm_pc;
+        }
+    }
+
+    let mut top_source_locations = bytes_per_line
+        .into_iter()
+        .map(|((path, line), bytes)| SourceLocationReport { path, line, bytes })
+        .collect::<Vec<_>>();
+    top_source_locations.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.cmp(b)));
+    top_source_locations.truncate(TOP_SOURCE_LOCATIONS);
+
+    let expansion_ratio = if brillig_instructions == 0 {
+        0.0
+    } else {
+        instructions.len() as f64 / brillig_instructions as f64
+    };
+    Ok(FunctionReport {
+        name: function.name.clone(),
+        bytecode_size: bytecode.len(),
+        brillig_instructions,
+        avm_instructions: instructions.len(),
+        expansion_ratio,
+        opcode_histogram,
+        top_source_locations,
+    })
+}
+
+/// Checks that the bytecode of every function of the report fits in `max_bytecode_size` bytes.
+pub fn check_size_budget(report: &TranspileReport, max_bytecode_size: usize) -> Result<(), String> {
+    let over_budget = report
+        .functions
+        .iter()
+        .filter(|function| function.bytecode_size > max_bytecode_size)
+        .map(|function| {
+            format!(
+                "\n    {}::{} is {} bytes ({} bytes over budget)",
+                report.contract_name,
+                function.name,
+                function.bytecode_size,
+                function.bytecode_size - max_bytecode_size
+            )
+        })
+        .collect::<String>();
+    if over_budget.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "error: AVM bytecode exceeds the size budget of {max_bytecode_size} bytes:{over_budget}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::brillig::Opcode as BrilligOpcode;
+    use acvm::acir::circuit::brillig::{BrilligBytecode, BrilligFunctionId};
+    use acvm::acir::circuit::{BrilligOpcodeLocation, Circuit, Opcode, Program};
+    use acvm::FieldElement;
+    use noirc_errors::debug_info::{DebugInfo, ProgramDebugInfo};
+    use noirc_errors::Location;
+
+    use super::*;
+    use crate::instructions::{AvmInstruction, AvmOperand, AvmTypeTag};
+    use crate::opcodes::AvmOpcode;
+    use crate::transpile_contract::AcirContractFunctionArtifact;
+
+    fn set(destination: u8) -> AvmInstruction {
+        AvmInstruction {
+            opcode: AvmOpcode::SET_8,
+            indirect: Some(AvmOperand::U8 { value: 0 }),
+            tag: Some(AvmTypeTag::UINT8),
+            operands: vec![AvmOperand::U8 { value: destination }],
+            immediates: vec![AvmOperand::U8 { value: 1 }],
+        }
+    }
+
+    fn return_instruction() -> AvmInstruction {
+        AvmInstruction {
+            opcode: AvmOpcode::RETURN,
+            indirect: Some(AvmOperand::U8 { value: 0 }),
+            operands: vec![AvmOperand::U16 { value: 0 }, AvmOperand::U16 { value: 1 }],
+            ..Default::default()
+        }
+    }
+
+    fn avm_function(
+        name: &str,
+        instructions: &[AvmInstruction],
+    ) -> AvmOrAcirContractFunctionArtifact {
+        let bytecode = instructions.iter().flat_map(AvmInstruction::to_bytes).collect::<Vec<_>>();
+        // Every instruction is attributed to the first line of the file
+        let debug_info = DebugInfo {
+            brillig_locations: BTreeMap::from([(
+                BrilligFunctionId(0),
+                BTreeMap::from([(BrilligOpcodeLocation(0), vec![Location::dummy()])]),
+            )]),
+            ..Default::default()
+        };
+        AvmOrAcirContractFunctionArtifact::Avm(AvmContractFunctionArtifact {
+            name: name.to_string(),
+            is_unconstrained: true,
+            custom_attributes: vec!["public".to_string()],
+            abi: serde_json::Value::Null,
+            bytecode: base64::prelude::BASE64_STANDARD.encode(bytecode),
+            debug_symbols: ProgramDebugInfo { debug_infos: vec![debug_info] },
+            brillig_names: vec![],
+        })
+    }
+
+    fn transpiled_contract(
+        functions: Vec<AvmOrAcirContractFunctionArtifact>,
+    ) -> TranspiledContractArtifact {
+        TranspiledContractArtifact {
+            transpiled: true,
+            noir_version: String::new(),
+            name: "Test".to_string(),
+            functions,
+            outputs: serde_json::Value::Null,
+            file_map: serde_json::json!({ "0": { "path": "main.nr", "source": "fn main() {}" } }),
+        }
+    }
+
+    fn acir_function(name: &str, program: Program<FieldElement>) -> AcirContractFunctionArtifact {
+        AcirContractFunctionArtifact {
+            name: name.to_string(),
+            is_unconstrained: true,
+            custom_attributes: vec!["public".to_string()],
+            abi: serde_json::Value::Null,
+            bytecode: program,
+            debug_symbols: ProgramDebugInfo::default(),
+            brillig_names: vec![],
+        }
+    }
+
+    fn compiled_contract(
+        functions: Vec<AcirContractFunctionArtifact>,
+    ) -> CompiledAcirContractArtifact {
+        CompiledAcirContractArtifact {
+            noir_version: String::new(),
+            name: "Test".to_string(),
+            functions,
+            outputs: serde_json::Value::Null,
+            file_map: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn reports_the_size_and_opcodes_of_each_function() {
+        let instructions = [set(0), set(1), return_instruction()];
+        let bytecode_size = instructions.iter().map(AvmInstruction::size).sum::<usize>();
+        let contract = transpiled_contract(vec![avm_function("foo", &instructions)]);
+        let brillig_instruction_counts = HashMap::from([("foo".to_string(), 2)]);
+
+        let report = build_transpile_report(&contract, &brillig_instruction_counts).unwrap();
+        assert_eq!(report.contract_name, "Test");
+        let [function] = report.functions.as_slice() else {
+            panic!("Expected a single function report");
+        };
+        assert_eq!(function.name, "foo");
+        assert_eq!(function.bytecode_size, bytecode_size);
+        assert_eq!(function.brillig_instructions, 2);
+        assert_eq!(function.avm_instructions, 3);
+        assert_eq!(function.expansion_ratio, 1.5);
+        assert_eq!(function.opcode_histogram, BTreeMap::from([("RETURN", 1), ("SET_8", 2)]));
+        assert_eq!(
+            function.top_source_locations,
+            vec![SourceLocationReport {
+                path: "main.nr".to_string(),
+                line: 1,
+                bytes: bytecode_size
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_undecodable_bytecode() {
+        let mut function = avm_function("foo", &[set(0)]);
+        if let AvmOrAcirContractFunctionArtifact::Avm(function) = &mut function {
+            function.bytecode = base64::prelude::BASE64_STANDARD.encode([u8::MAX]);
+        }
+        let contract = transpiled_contract(vec![function]);
+        assert!(build_transpile_report(&contract, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn checks_the_size_budget() {
+        let instructions = [set(0), return_instruction()];
+        let bytecode_size = instructions.iter().map(AvmInstruction::size).sum::<usize>();
+        let contract = transpiled_contract(vec![
+            avm_function("small", &instructions[1..]),
+            avm_function("large", &instructions),
+        ]);
+        let report = build_transpile_report(&contract, &HashMap::new()).unwrap();
+
+        assert!(check_size_budget(&report, bytecode_size).is_ok());
+        let error = check_size_budget(&report, bytecode_size - 1).unwrap_err();
+        assert!(error.contains("Test::large is"));
+        assert!(error.contains("(1 bytes over budget)"));
+        assert!(!error.contains("Test::small"));
+    }
+
+    #[test]
+    fn counts_the_brillig_instructions_of_public_functions() {
+        let main_function = Circuit {
+            opcodes: vec![Opcode::BrilligCall {
+                id: BrilligFunctionId(0),
+                inputs: vec![],
+                outputs: vec![],
+                predicate: None,
+            }],
+            ..Default::default()
+        };
+        let unconstrained_function =
+            BrilligBytecode { bytecode: vec![BrilligOpcode::Jump { location: 0 }; 3] };
+        let program = Program {
+            functions: vec![main_function],
+            unconstrained_functions: vec![unconstrained_function],
+        };
+        let mut private_function = acir_function("private", Program::default());
+        private_function.custom_attributes.clear();
+        let contract = compiled_contract(vec![acir_function("foo", program), private_function]);
+
+        assert_eq!(
+            count_brillig_instructions(&contract),
+            Ok(HashMap::from([("foo".to_string(), 3)]))
+        );
+    }
+
+    #[test]
+    fn reports_functions_which_can_not_be_linked() {
+        let contract = compiled_contract(vec![acir_function("foo", Program::default())]);
+        let error = count_brillig_instructions(&contract).unwrap_err();
+        assert!(error.contains("Test::foo"));
+    }
+}
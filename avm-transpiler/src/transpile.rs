@@ -9,339 +9,68 @@ use acvm::FieldElement;
 use noirc_errors::debug_info::DebugInfo;
 
 use crate::bit_traits::{bits_needed_for, BitsQueryable};
+use crate::errors::{TranspileError, TranspileErrorKind};
 use crate::instructions::{AddressingModeBuilder, AvmInstruction, AvmOperand, AvmTypeTag};
 use crate::opcodes::AvmOpcode;
+use crate::peephole::optimize_avm_instructions;
 use crate::utils::{dbg_print_avm_program, dbg_print_brillig_program, make_operand};
 
-/// Transpile a Brillig program to AVM bytecode
+/// Options controlling how Brillig programs are transpiled.
+#[derive(Debug, Clone, Copy)]
+pub struct BrilligToAvmOptions {
+    /// Whether to run the peephole optimizations over the transpiled instructions.
+    /// Disabling them helps finding out whether they cause a miscompilation.
+    pub peephole: bool,
+}
+
+impl Default for BrilligToAvmOptions {
+    fn default() -> Self {
+        BrilligToAvmOptions { peephole: true }
+    }
+}
+
+/// Transpile a Brillig program to AVM bytecode, removing redundant instructions unless disabled
+/// by the options.
 /// Returns the bytecode and a mapping from Brillig program counter to AVM program counter.
-pub fn brillig_to_avm(brillig_bytecode: &[BrilligOpcode<FieldElement>]) -> (Vec<u8>, Vec<usize>) {
+pub fn brillig_to_avm(
+    brillig_bytecode: &[BrilligOpcode<FieldElement>],
+    options: BrilligToAvmOptions,
+) -> Result<(Vec<u8>, Vec<usize>), TranspileError> {
     dbg_print_brillig_program(brillig_bytecode);
 
     let mut avm_instrs: Vec<AvmInstruction> = Vec::new();
-    let mut brillig_pcs_to_avm_pcs: Vec<usize> = [0_usize].to_vec();
-    let mut current_avm_pc: usize = 0;
+    let mut brillig_pcs_to_avm_indices: Vec<usize> = [0_usize].to_vec();
 
     // Transpile a Brillig instruction to one or more AVM instructions
-    for brillig_instr in brillig_bytecode {
-        let current_avm_instr_index = avm_instrs.len();
-
-        match brillig_instr {
-            BrilligOpcode::BinaryFieldOp { destination, op, lhs, rhs } => {
-                let bits_needed =
-                    [*lhs, *rhs, *destination].iter().map(bits_needed_for).max().unwrap();
-
-                assert!(
-                    bits_needed == 8 || bits_needed == 16,
-                    "BinaryFieldOp only support 8 or 16 bit encodings, got: {}",
-                    bits_needed
-                );
-
-                let avm_opcode = match op {
-                    BinaryFieldOp::Add => match bits_needed {
-                        8 => AvmOpcode::ADD_8,
-                        16 => AvmOpcode::ADD_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::Sub => match bits_needed {
-                        8 => AvmOpcode::SUB_8,
-                        16 => AvmOpcode::SUB_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::Mul => match bits_needed {
-                        8 => AvmOpcode::MUL_8,
-                        16 => AvmOpcode::MUL_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::Div => match bits_needed {
-                        8 => AvmOpcode::FDIV_8,
-                        16 => AvmOpcode::FDIV_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::IntegerDiv => match bits_needed {
-                        8 => AvmOpcode::DIV_8,
-                        16 => AvmOpcode::DIV_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::Equals => match bits_needed {
-                        8 => AvmOpcode::EQ_8,
-                        16 => AvmOpcode::EQ_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::LessThan => match bits_needed {
-                        8 => AvmOpcode::LT_8,
-                        16 => AvmOpcode::LT_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryFieldOp::LessThanEquals => match bits_needed {
-                        8 => AvmOpcode::LTE_8,
-                        16 => AvmOpcode::LTE_16,
-                        _ => unreachable!(),
-                    },
-                };
-
-                avm_instrs.push(AvmInstruction {
-                    opcode: avm_opcode,
-                    indirect: Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(lhs)
-                            .direct_operand(rhs)
-                            .direct_operand(destination)
-                            .build(),
-                    ),
-                    operands: vec![
-                        make_operand(bits_needed, &lhs.to_usize()),
-                        make_operand(bits_needed, &rhs.to_usize()),
-                        make_operand(bits_needed, &destination.to_usize()),
-                    ],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::BinaryIntOp { destination, op, lhs, rhs, .. } => {
-                let bits_needed =
-                    [*lhs, *rhs, *destination].iter().map(bits_needed_for).max().unwrap();
-                assert!(
-                    bits_needed == 8 || bits_needed == 16,
-                    "BinaryIntOp only support 8 or 16 bit encodings, got: {}",
-                    bits_needed
-                );
-
-                let avm_opcode = match op {
-                    BinaryIntOp::Add => match bits_needed {
-                        8 => AvmOpcode::ADD_8,
-                        16 => AvmOpcode::ADD_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Sub => match bits_needed {
-                        8 => AvmOpcode::SUB_8,
-                        16 => AvmOpcode::SUB_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Mul => match bits_needed {
-                        8 => AvmOpcode::MUL_8,
-                        16 => AvmOpcode::MUL_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Div => match bits_needed {
-                        8 => AvmOpcode::DIV_8,
-                        16 => AvmOpcode::DIV_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::And => match bits_needed {
-                        8 => AvmOpcode::AND_8,
-                        16 => AvmOpcode::AND_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Or => match bits_needed {
-                        8 => AvmOpcode::OR_8,
-                        16 => AvmOpcode::OR_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Xor => match bits_needed {
-                        8 => AvmOpcode::XOR_8,
-                        16 => AvmOpcode::XOR_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Shl => match bits_needed {
-                        8 => AvmOpcode::SHL_8,
-                        16 => AvmOpcode::SHL_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Shr => match bits_needed {
-                        8 => AvmOpcode::SHR_8,
-                        16 => AvmOpcode::SHR_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::Equals => match bits_needed {
-                        8 => AvmOpcode::EQ_8,
-                        16 => AvmOpcode::EQ_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::LessThan => match bits_needed {
-                        8 => AvmOpcode::LT_8,
-                        16 => AvmOpcode::LT_16,
-                        _ => unreachable!(),
-                    },
-                    BinaryIntOp::LessThanEquals => match bits_needed {
-                        8 => AvmOpcode::LTE_8,
-                        16 => AvmOpcode::LTE_16,
-                        _ => unreachable!(),
-                    },
-                };
-                avm_instrs.push(AvmInstruction {
-                    opcode: avm_opcode,
-                    indirect: Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(lhs)
-                            .direct_operand(rhs)
-                            .direct_operand(destination)
-                            .build(),
-                    ),
-                    operands: vec![
-                        make_operand(bits_needed, &lhs.to_usize()),
-                        make_operand(bits_needed, &rhs.to_usize()),
-                        make_operand(bits_needed, &destination.to_usize()),
-                    ],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::Not { destination, source, .. } => {
-                let bits_needed =
-                    [*source, *destination].iter().map(bits_needed_for).max().unwrap();
-                assert!(
-                    bits_needed == 8 || bits_needed == 16,
-                    "Not only support 8 or 16 bit encodings, got: {}",
-                    bits_needed
-                );
-
-                avm_instrs.push(AvmInstruction {
-                    opcode: if bits_needed == 8 { AvmOpcode::NOT_8 } else { AvmOpcode::NOT_16 },
-                    indirect: Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(source)
-                            .direct_operand(destination)
-                            .build(),
-                    ),
-                    operands: vec![
-                        make_operand(bits_needed, &source.to_usize()),
-                        make_operand(bits_needed, &destination.to_usize()),
-                    ],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::CalldataCopy { destination_address, size_address, offset_address } => {
-                avm_instrs.push(AvmInstruction {
-                    opcode: AvmOpcode::CALLDATACOPY,
-                    indirect: Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(offset_address)
-                            .direct_operand(size_address)
-                            .direct_operand(destination_address)
-                            .build(),
-                    ),
-                    operands: vec![
-                        AvmOperand::U16 {
-                            value: offset_address.to_usize() as u16, // cdOffset (calldata offset)
-                        },
-                        AvmOperand::U16 { value: size_address.to_usize() as u16 }, // sizeOffset
-                        AvmOperand::U16 {
-                            value: destination_address.to_usize() as u16, // dstOffset
-                        },
-                    ],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::Jump { location } => {
-                assert!(location.num_bits() <= 32);
-                avm_instrs.push(AvmInstruction {
-                    opcode: AvmOpcode::JUMP_32,
-                    immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::JumpIf { condition, location } => {
-                assert!(location.num_bits() <= 32);
-                avm_instrs.push(AvmInstruction {
-                    opcode: AvmOpcode::JUMPI_32,
-                    indirect: Some(
-                        AddressingModeBuilder::default().direct_operand(condition).build(),
-                    ),
-                    operands: vec![make_operand(16, &condition.to_usize())],
-                    immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::Const { destination, value, bit_size } => {
-                handle_const(&mut avm_instrs, destination, value, bit_size, false);
-            }
-            BrilligOpcode::IndirectConst { destination_pointer, value, bit_size } => {
-                handle_const(&mut avm_instrs, destination_pointer, value, bit_size, true);
-            }
-            BrilligOpcode::Mov { destination, source } => {
-                avm_instrs.push(generate_mov_instruction(
-                    Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(source)
-                            .direct_operand(destination)
-                            .build(),
-                    ),
-                    source.to_usize() as u32,
-                    destination.to_usize() as u32,
-                ));
-            }
-            BrilligOpcode::Load { destination, source_pointer } => {
-                avm_instrs.push(generate_mov_instruction(
-                    Some(
-                        AddressingModeBuilder::default()
-                            .indirect_operand(source_pointer)
-                            .direct_operand(destination)
-                            .build(),
-                    ),
-                    source_pointer.to_usize() as u32,
-                    destination.to_usize() as u32,
-                ));
-            }
-            BrilligOpcode::Store { destination_pointer, source } => {
-                avm_instrs.push(generate_mov_instruction(
-                    Some(
-                        AddressingModeBuilder::default()
-                            .direct_operand(source)
-                            .indirect_operand(destination_pointer)
-                            .build(),
-                    ),
-                    source.to_usize() as u32,
-                    destination_pointer.to_usize() as u32,
-                ));
-            }
-            BrilligOpcode::Call { location } => {
-                assert!(location.num_bits() <= 32);
-                avm_instrs.push(AvmInstruction {
-                    opcode: AvmOpcode::INTERNALCALL,
-                    immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
-                    ..Default::default()
-                });
-            }
-            BrilligOpcode::Return {} => avm_instrs
-                .push(AvmInstruction { opcode: AvmOpcode::INTERNALRETURN, ..Default::default() }),
-            BrilligOpcode::Stop { return_data } => {
-                generate_return_instruction(
-                    &mut avm_instrs,
-                    &return_data.pointer,
-                    &return_data.size,
-                );
-            }
-            BrilligOpcode::Trap { revert_data } => {
-                generate_revert_instruction(
-                    &mut avm_instrs,
-                    &revert_data.pointer,
-                    &revert_data.size,
-                );
-            }
-            BrilligOpcode::Cast { destination, source, bit_size } => {
-                handle_cast(&mut avm_instrs, source, destination, *bit_size);
-            }
-            BrilligOpcode::ForeignCall {
-                function,
-                destinations,
-                inputs,
-                destination_value_types: _,
-                input_value_types: _,
-            } => {
-                handle_foreign_call(&mut avm_instrs, function, destinations, inputs);
-            }
-            BrilligOpcode::BlackBox(operation) => {
-                handle_black_box_function(&mut avm_instrs, operation);
-            }
-            _ => panic!(
-                "Transpiler doesn't know how to process {:?} brillig instruction",
-                brillig_instr
-            ),
-        }
+    for (brillig_pc, brillig_instr) in brillig_bytecode.iter().enumerate() {
+        transpile_brillig_instruction(&mut avm_instrs, brillig_instr, brillig_bytecode.len())
+            .map_err(|kind| TranspileError::new(kind).at_brillig_pc(brillig_pc))?;
+        brillig_pcs_to_avm_indices.push(avm_instrs.len());
+    }
 
-        // Increment the AVM program counter.
-        current_avm_pc +=
-            avm_instrs.iter().skip(current_avm_instr_index).map(|i| i.size()).sum::<usize>();
-        brillig_pcs_to_avm_pcs.push(current_avm_pc);
+    let avm_instrs = if options.peephole {
+        optimize_avm_instructions(avm_instrs, &mut brillig_pcs_to_avm_indices)
+    } else {
+        avm_instrs
+    };
+
+    // Compute the AVM program counter of each instruction, and of the end of the program.
+    let mut avm_pcs: Vec<usize> = Vec::with_capacity(avm_instrs.len() + 1);
+    let mut current_avm_pc: usize = 0;
+    for instruction in &avm_instrs {
+        avm_pcs.push(current_avm_pc);
+        current_avm_pc += instruction.size();
+    }
+    avm_pcs.push(current_avm_pc);
+    let brillig_pcs_to_avm_pcs: Vec<usize> =
+        brillig_pcs_to_avm_indices.iter().map(|avm_index| avm_pcs[*avm_index]).collect();
+
+    // Jump locations were checked to be within the program, so they only need to fit in 32 bits.
+    if current_avm_pc.num_bits() > 32 {
+        return Err(TranspileErrorKind::InvalidProgram(format!(
+            "AVM program is too large: its size is {current_avm_pc} bytes"
+        ))
+        .into());
     }
 
     // Now that we have the general structure of the AVM program, we need to resolve the
@@ -356,7 +85,6 @@ pub fn brillig_to_avm(brillig_bytecode: &[BrilligOpcode<FieldElement>]) -> (Vec<
                     .map(|o| match o {
                         AvmOperand::BRILLIG_LOCATION { brillig_pc } => {
                             let avm_pc = brillig_pcs_to_avm_pcs[brillig_pc as usize];
-                            assert!(avm_pc.num_bits() <= 32, "Oops! AVM PC is too large!");
                             AvmOperand::U32 { value: avm_pc as u32 }
                         }
                         _ => o,
@@ -387,7 +115,248 @@ pub fn brillig_to_avm(brillig_bytecode: &[BrilligOpcode<FieldElement>]) -> (Vec<
         bytecode.extend_from_slice(&instruction.to_bytes());
     }
 
-    (bytecode, brillig_pcs_to_avm_pcs)
+    Ok((bytecode, brillig_pcs_to_avm_pcs))
+}
+
+/// Transpile a single Brillig instruction, appending the resulting AVM instructions.
+/// `program_size` is the number of instructions of the Brillig program, used to check
+/// jump locations.
+fn transpile_brillig_instruction(
+    avm_instrs: &mut Vec<AvmInstruction>,
+    brillig_instr: &BrilligOpcode<FieldElement>,
+    program_size: usize,
+) -> Result<(), TranspileErrorKind> {
+    match brillig_instr {
+        BrilligOpcode::BinaryFieldOp { destination, op, lhs, rhs } => {
+            let bits_needed = [*lhs, *rhs, *destination].iter().map(bits_needed_for).max().unwrap();
+
+            let (opcode_8, opcode_16) = match op {
+                BinaryFieldOp::Add => (AvmOpcode::ADD_8, AvmOpcode::ADD_16),
+                BinaryFieldOp::Sub => (AvmOpcode::SUB_8, AvmOpcode::SUB_16),
+                BinaryFieldOp::Mul => (AvmOpcode::MUL_8, AvmOpcode::MUL_16),
+                BinaryFieldOp::Div => (AvmOpcode::FDIV_8, AvmOpcode::FDIV_16),
+                BinaryFieldOp::IntegerDiv => (AvmOpcode::DIV_8, AvmOpcode::DIV_16),
+                BinaryFieldOp::Equals => (AvmOpcode::EQ_8, AvmOpcode::EQ_16),
+                BinaryFieldOp::LessThan => (AvmOpcode::LT_8, AvmOpcode::LT_16),
+                BinaryFieldOp::LessThanEquals => (AvmOpcode::LTE_8, AvmOpcode::LTE_16),
+            };
+            let avm_opcode =
+                select_8_or_16_bit_opcode("BinaryFieldOp", bits_needed, opcode_8, opcode_16)?;
+
+            avm_instrs.push(AvmInstruction {
+                opcode: avm_opcode,
+                indirect: Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(lhs)
+                        .direct_operand(rhs)
+                        .direct_operand(destination)
+                        .build(),
+                ),
+                operands: vec![
+                    make_operand(bits_needed, &lhs.to_usize())?,
+                    make_operand(bits_needed, &rhs.to_usize())?,
+                    make_operand(bits_needed, &destination.to_usize())?,
+                ],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::BinaryIntOp { destination, op, lhs, rhs, .. } => {
+            let bits_needed = [*lhs, *rhs, *destination].iter().map(bits_needed_for).max().unwrap();
+
+            let (opcode_8, opcode_16) = match op {
+                BinaryIntOp::Add => (AvmOpcode::ADD_8, AvmOpcode::ADD_16),
+                BinaryIntOp::Sub => (AvmOpcode::SUB_8, AvmOpcode::SUB_16),
+                BinaryIntOp::Mul => (AvmOpcode::MUL_8, AvmOpcode::MUL_16),
+                BinaryIntOp::Div => (AvmOpcode::DIV_8, AvmOpcode::DIV_16),
+                BinaryIntOp::And => (AvmOpcode::AND_8, AvmOpcode::AND_16),
+                BinaryIntOp::Or => (AvmOpcode::OR_8, AvmOpcode::OR_16),
+                BinaryIntOp::Xor => (AvmOpcode::XOR_8, AvmOpcode::XOR_16),
+                BinaryIntOp::Shl => (AvmOpcode::SHL_8, AvmOpcode::SHL_16),
+                BinaryIntOp::Shr => (AvmOpcode::SHR_8, AvmOpcode::SHR_16),
+                BinaryIntOp::Equals => (AvmOpcode::EQ_8, AvmOpcode::EQ_16),
+                BinaryIntOp::LessThan => (AvmOpcode::LT_8, AvmOpcode::LT_16),
+                BinaryIntOp::LessThanEquals => (AvmOpcode::LTE_8, AvmOpcode::LTE_16),
+            };
+            let avm_opcode =
+                select_8_or_16_bit_opcode("BinaryIntOp", bits_needed, opcode_8, opcode_16)?;
+
+            avm_instrs.push(AvmInstruction {
+                opcode: avm_opcode,
+                indirect: Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(lhs)
+                        .direct_operand(rhs)
+                        .direct_operand(destination)
+                        .build(),
+                ),
+                operands: vec![
+                    make_operand(bits_needed, &lhs.to_usize())?,
+                    make_operand(bits_needed, &rhs.to_usize())?,
+                    make_operand(bits_needed, &destination.to_usize())?,
+                ],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::Not { destination, source, .. } => {
+            let bits_needed = [*source, *destination].iter().map(bits_needed_for).max().unwrap();
+            let avm_opcode =
+                select_8_or_16_bit_opcode("Not", bits_needed, AvmOpcode::NOT_8, AvmOpcode::NOT_16)?;
+
+            avm_instrs.push(AvmInstruction {
+                opcode: avm_opcode,
+                indirect: Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(source)
+                        .direct_operand(destination)
+                        .build(),
+                ),
+                operands: vec![
+                    make_operand(bits_needed, &source.to_usize())?,
+                    make_operand(bits_needed, &destination.to_usize())?,
+                ],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::CalldataCopy { destination_address, size_address, offset_address } => {
+            avm_instrs.push(AvmInstruction {
+                opcode: AvmOpcode::CALLDATACOPY,
+                indirect: Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(offset_address)
+                        .direct_operand(size_address)
+                        .direct_operand(destination_address)
+                        .build(),
+                ),
+                operands: vec![
+                    AvmOperand::U16 {
+                        value: offset_address.to_usize() as u16, // cdOffset (calldata offset)
+                    },
+                    AvmOperand::U16 { value: size_address.to_usize() as u16 }, // sizeOffset
+                    AvmOperand::U16 {
+                        value: destination_address.to_usize() as u16, // dstOffset
+                    },
+                ],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::Jump { location } => {
+            check_jump_location(*location, program_size)?;
+            avm_instrs.push(AvmInstruction {
+                opcode: AvmOpcode::JUMP_32,
+                immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::JumpIf { condition, location } => {
+            check_jump_location(*location, program_size)?;
+            avm_instrs.push(AvmInstruction {
+                opcode: AvmOpcode::JUMPI_32,
+                indirect: Some(AddressingModeBuilder::default().direct_operand(condition).build()),
+                operands: vec![make_operand(16, &condition.to_usize())?],
+                immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::Const { destination, value, bit_size } => {
+            handle_const(avm_instrs, destination, value, bit_size, false)?;
+        }
+        BrilligOpcode::IndirectConst { destination_pointer, value, bit_size } => {
+            handle_const(avm_instrs, destination_pointer, value, bit_size, true)?;
+        }
+        BrilligOpcode::Mov { destination, source } => {
+            avm_instrs.push(generate_mov_instruction(
+                Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(source)
+                        .direct_operand(destination)
+                        .build(),
+                ),
+                source.to_usize() as u32,
+                destination.to_usize() as u32,
+            )?);
+        }
+        BrilligOpcode::Load { destination, source_pointer } => {
+            avm_instrs.push(generate_mov_instruction(
+                Some(
+                    AddressingModeBuilder::default()
+                        .indirect_operand(source_pointer)
+                        .direct_operand(destination)
+                        .build(),
+                ),
+                source_pointer.to_usize() as u32,
+                destination.to_usize() as u32,
+            )?);
+        }
+        BrilligOpcode::Store { destination_pointer, source } => {
+            avm_instrs.push(generate_mov_instruction(
+                Some(
+                    AddressingModeBuilder::default()
+                        .direct_operand(source)
+                        .indirect_operand(destination_pointer)
+                        .build(),
+                ),
+                source.to_usize() as u32,
+                destination_pointer.to_usize() as u32,
+            )?);
+        }
+        BrilligOpcode::Call { location } => {
+            check_jump_location(*location, program_size)?;
+            avm_instrs.push(AvmInstruction {
+                opcode: AvmOpcode::INTERNALCALL,
+                immediates: vec![AvmOperand::BRILLIG_LOCATION { brillig_pc: *location as u32 }],
+                ..Default::default()
+            });
+        }
+        BrilligOpcode::Return {} => avm_instrs
+            .push(AvmInstruction { opcode: AvmOpcode::INTERNALRETURN, ..Default::default() }),
+        BrilligOpcode::Stop { return_data } => {
+            generate_return_instruction(avm_instrs, &return_data.pointer, &return_data.size);
+        }
+        BrilligOpcode::Trap { revert_data } => {
+            generate_revert_instruction(avm_instrs, &revert_data.pointer, &revert_data.size)?;
+        }
+        BrilligOpcode::Cast { destination, source, bit_size } => {
+            handle_cast(avm_instrs, source, destination, *bit_size)?;
+        }
+        BrilligOpcode::ForeignCall {
+            function,
+            destinations,
+            inputs,
+            destination_value_types: _,
+            input_value_types: _,
+        } => {
+            handle_foreign_call(avm_instrs, function, destinations, inputs)?;
+        }
+        BrilligOpcode::BlackBox(operation) => {
+            handle_black_box_function(avm_instrs, operation)?;
+        }
+        _ => return Err(TranspileErrorKind::UnsupportedOpcode(format!("{brillig_instr:?}"))),
+    }
+    Ok(())
+}
+
+/// Picks the 8 or 16 bit encoding of an instruction, which must fit its operands.
+fn select_8_or_16_bit_opcode(
+    instruction: &'static str,
+    bits_needed: usize,
+    opcode_8: AvmOpcode,
+    opcode_16: AvmOpcode,
+) -> Result<AvmOpcode, TranspileErrorKind> {
+    match bits_needed {
+        8 => Ok(opcode_8),
+        16 => Ok(opcode_16),
+        _ => Err(TranspileErrorKind::UnsupportedOperandSize { instruction, bits_needed }),
+    }
+}
+
+/// Checks that a jump or call targets an instruction of the program, or its end.
+fn check_jump_location(location: usize, program_size: usize) -> Result<(), TranspileErrorKind> {
+    if location > program_size {
+        return Err(TranspileErrorKind::InvalidOperand(format!(
+            "Jump location {location} is outside of the program, which has {program_size} instructions"
+        )));
+    }
+    Ok(())
 }
 
 /// Handle brillig foreign calls
@@ -401,14 +370,14 @@ fn handle_foreign_call(
     function: &str,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
+) -> Result<(), TranspileErrorKind> {
     match function {
         "avmOpcodeCall" => handle_external_call(avm_instrs, destinations, inputs, AvmOpcode::CALL),
         "avmOpcodeStaticCall" => {
-            handle_external_call(avm_instrs, destinations, inputs, AvmOpcode::STATICCALL);
+            handle_external_call(avm_instrs, destinations, inputs, AvmOpcode::STATICCALL)
         }
         "avmOpcodeEmitUnencryptedLog" => {
-            handle_emit_unencrypted_log(avm_instrs, destinations, inputs);
+            handle_emit_unencrypted_log(avm_instrs, destinations, inputs)
         }
         "avmOpcodeNoteHashExists" => handle_note_hash_exists(avm_instrs, destinations, inputs),
         "avmOpcodeEmitNoteHash" | "avmOpcodeEmitNullifier" => handle_emit_note_hash_or_nullifier(
@@ -430,17 +399,39 @@ fn handle_foreign_call(
         "debugLog" => handle_debug_log(avm_instrs, destinations, inputs),
         // Getters.
         _ if inputs.is_empty() && destinations.len() == 1 => {
-            handle_getter_instruction(avm_instrs, function, destinations, inputs);
+            handle_getter_instruction(avm_instrs, function, destinations)
         }
         // Get contract instance variations.
         _ if function.starts_with("avmOpcodeGetContractInstance") => {
-            handle_get_contract_instance(avm_instrs, function, destinations, inputs);
+            handle_get_contract_instance(avm_instrs, function, destinations, inputs)
         }
         // Anything else.
-        _ => panic!("Transpiler doesn't know how to process ForeignCall function {}", function),
+        _ => Err(TranspileErrorKind::UnknownForeignCall(function.to_string())),
     }
 }
 
+/// Checks the number of destinations and inputs of a foreign call.
+fn check_foreign_call_arity(
+    name: &str,
+    destinations: &[ValueOrArray],
+    inputs: &[ValueOrArray],
+    expected_destinations: usize,
+    expected_inputs: usize,
+) -> Result<(), TranspileErrorKind> {
+    if destinations.len() != expected_destinations || inputs.len() != expected_inputs {
+        return Err(TranspileErrorKind::InvalidForeignCall(format!(
+            "Transpiler expects ForeignCall::{name} to have {expected_destinations} destinations and {expected_inputs} inputs, got {} and {}",
+            destinations.len(),
+            inputs.len()
+        )));
+    }
+    Ok(())
+}
+
+fn invalid_foreign_call(reason: impl Into<String>) -> TranspileErrorKind {
+    TranspileErrorKind::InvalidForeignCall(reason.into())
+}
+
 /// Handle an AVM CALL
 /// (an external 'call' brillig foreign call was encountered)
 /// Adds the new instruction to the avm instructions list.
@@ -455,28 +446,27 @@ fn handle_external_call(
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
     opcode: AvmOpcode,
-) {
-    if destinations.len() != 1 || inputs.len() != 4 {
-        panic!(
-            "Transpiler expects ForeignCall (Static)Call to have 1 destinations and 4 inputs, got {} and {}.",
-            destinations.len(),
-            inputs.len()
-        );
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("(STATIC)CALL", destinations, inputs, 1, 4)?;
 
     let gas_offset_ptr = match &inputs[0] {
-        ValueOrArray::HeapArray(HeapArray { pointer, size }) => {
-            assert!(
-                *size == 2,
-                "Call instruction's gas input should be a HeapArray of size 2 (`[l2Gas, daGas]`)"
-            );
-            pointer
+        ValueOrArray::HeapArray(HeapArray { pointer, size: 2 }) => pointer,
+        ValueOrArray::HeapArray(_) => {
+            return Err(invalid_foreign_call(
+                "Call instruction's gas input should be a HeapArray of size 2 (`[l2Gas, daGas]`)",
+            ))
+        }
+        _ => {
+            return Err(invalid_foreign_call("Call instruction's gas input should be a HeapArray"))
         }
-        _ => panic!("Call instruction's gas input should be a HeapArray"),
     };
     let address_offset = match &inputs[1] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("Call instruction's target address input should be a basic MemoryAddress",),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Call instruction's target address input should be a basic MemoryAddress",
+            ))
+        }
     };
     // The args are a slice, and this is represented as a (Field, HeapVector).
     // The field is the length (memory address) and the HeapVector has the data and length again.
@@ -485,12 +475,20 @@ fn handle_external_call(
     let args = &inputs[3];
     let (args_offset_ptr, args_size_offset) = match args {
         ValueOrArray::HeapVector(HeapVector { pointer, size }) => (pointer, size),
-        _ => panic!("Call instruction's args input should be a HeapVector input"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Call instruction's args input should be a HeapVector input",
+            ))
+        }
     };
 
     let success_offset = match &destinations[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("Call instruction's success destination should be a basic MemoryAddress",),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Call instruction's success destination should be a basic MemoryAddress",
+            ))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode,
@@ -512,6 +510,7 @@ fn handle_external_call(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 fn handle_cast(
@@ -519,9 +518,10 @@ fn handle_cast(
     source: &MemoryAddress,
     destination: &MemoryAddress,
     bit_size: BitSize,
-) {
+) -> Result<(), TranspileErrorKind> {
     let tag = tag_from_bit_size(bit_size);
-    avm_instrs.push(generate_cast_instruction(source, false, destination, false, tag));
+    avm_instrs.push(generate_cast_instruction(source, false, destination, false, tag)?);
+    Ok(())
 }
 
 /// Handle an AVM NOTEHASHEXISTS instruction
@@ -530,21 +530,21 @@ fn handle_note_hash_exists(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
+) -> Result<(), TranspileErrorKind> {
     let (note_hash_offset_operand, leaf_index_offset_operand) = match &inputs[..] {
         [
             ValueOrArray::MemoryAddress(nh_offset),
             ValueOrArray::MemoryAddress(li_offset)
         ] => (nh_offset, li_offset),
-        _ => panic!(
+        _ => return Err(invalid_foreign_call(format!(
             "Transpiler expects ForeignCall::NOTEHASHEXISTS to have 2 inputs of type MemoryAddress, got {:?}", inputs
-        ),
+        ))),
     };
     let exists_offset_operand = match &destinations[..] {
         [ValueOrArray::MemoryAddress(offset)] => offset,
-        _ => panic!(
+        _ => return Err(invalid_foreign_call(format!(
             "Transpiler expects ForeignCall::NOTEHASHEXISTS to have 1 output of type MemoryAddress, got {:?}", destinations
-        ),
+        ))),
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::NOTEHASHEXISTS,
@@ -562,26 +562,26 @@ fn handle_note_hash_exists(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 fn handle_emit_unencrypted_log(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    if !destinations.is_empty() || inputs.len() != 2 {
-        panic!(
-            "Transpiler expects ForeignCall::EMITUNENCRYPTEDLOG to have 0 destinations and 2 inputs, got {} and {}",
-            destinations.len(),
-            inputs.len()
-        );
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("EMITUNENCRYPTEDLOG", destinations, inputs, 0, 2)?;
 
     // The fields are a slice, and this is represented as a (length: Field, slice: HeapVector).
     // The length field is redundant and we skipt it.
     let (message_offset, message_size_offset) = match &inputs[1] {
         ValueOrArray::HeapVector(vec) => (vec.pointer, vec.size),
-        _ => panic!("Unexpected inputs for ForeignCall::EMITUNENCRYPTEDLOG: {:?}", inputs),
+        _ => {
+            return Err(invalid_foreign_call(format!(
+                "Unexpected inputs for ForeignCall::EMITUNENCRYPTEDLOG: {:?}",
+                inputs
+            )))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::EMITUNENCRYPTEDLOG,
@@ -598,6 +598,7 @@ fn handle_emit_unencrypted_log(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Handle an AVM EMITNOTEHASH or EMITNULLIFIER instruction
@@ -608,23 +609,17 @@ fn handle_emit_note_hash_or_nullifier(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
+) -> Result<(), TranspileErrorKind> {
     let function_name = if is_nullifier { "EMITNULLIFIER" } else { "EMITNOTEHASH" };
 
-    if !destinations.is_empty() || inputs.len() != 1 {
-        panic!(
-            "Transpiler expects ForeignCall::{} to have 0 destinations and 1 input, got {} and {}",
-            function_name,
-            destinations.len(),
-            inputs.len()
-        );
-    }
+    check_foreign_call_arity(function_name, destinations, inputs, 0, 1)?;
     let offset_operand = match &inputs[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::{} with HeapArray/Vector inputs",
-            function_name
-        ),
+        _ => {
+            return Err(invalid_foreign_call(format!(
+                "Transpiler does not know how to handle ForeignCall::{function_name} with HeapArray/Vector inputs"
+            )))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: if is_nullifier { AvmOpcode::EMITNULLIFIER } else { AvmOpcode::EMITNOTEHASH },
@@ -632,6 +627,7 @@ fn handle_emit_note_hash_or_nullifier(
         operands: vec![AvmOperand::U16 { value: offset_operand.to_usize() as u16 }],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Handle an AVM NULLIFIEREXISTS instruction
@@ -641,21 +637,31 @@ fn handle_nullifier_exists(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    if destinations.len() != 1 || inputs.len() != 2 {
-        panic!("Transpiler expects ForeignCall::CHECKNULLIFIEREXISTS to have 1 destinations and 2 inputs, got {} and {}", destinations.len(), inputs.len());
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("CHECKNULLIFIEREXISTS", destinations, inputs, 1, 2)?;
     let nullifier_offset_operand = match &inputs[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("Transpiler does not know how to handle ForeignCall::EMITNOTEHASH with HeapArray/Vector inputs"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::CHECKNULLIFIEREXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     let address_offset_operand = match &inputs[1] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("Transpiler does not know how to handle ForeignCall::EMITNOTEHASH with HeapArray/Vector inputs"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::CHECKNULLIFIEREXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     let exists_offset_operand = match &destinations[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("Transpiler does not know how to handle ForeignCall::EMITNOTEHASH with HeapArray/Vector inputs"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::CHECKNULLIFIEREXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::NULLIFIEREXISTS,
@@ -673,6 +679,7 @@ fn handle_nullifier_exists(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Handle an AVM L1TOL2MSGEXISTS instruction
@@ -682,31 +689,31 @@ fn handle_l1_to_l2_msg_exists(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    if destinations.len() != 1 || inputs.len() != 2 {
-        panic!(
-            "Transpiler expects ForeignCall::L1TOL2MSGEXISTS to have 1 destinations and 2 input, got {} and {}",
-            destinations.len(),
-            inputs.len()
-        );
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("L1TOL2MSGEXISTS", destinations, inputs, 1, 2)?;
     let msg_hash_offset_operand = match &inputs[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
-        ),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     let msg_leaf_index_offset_operand = match &inputs[1] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
-        ),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     let exists_offset_operand = match &destinations[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
-        ),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::L1TOL2MSGEXISTS with HeapArray/Vector inputs",
+            ))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::L1TOL2MSGEXISTS,
@@ -724,6 +731,7 @@ fn handle_l1_to_l2_msg_exists(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Handle an AVM SENDL2TOL1MSG
@@ -733,25 +741,23 @@ fn handle_send_l2_to_l1_msg(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    if !destinations.is_empty() || inputs.len() != 2 {
-        panic!(
-            "Transpiler expects ForeignCall::SENDL2TOL1MSG to have 0 destinations and 2 inputs, got {} and {}",
-            destinations.len(),
-            inputs.len()
-        );
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("SENDL2TOL1MSG", destinations, inputs, 0, 2)?;
     let recipient_offset_operand = match &inputs[0] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::SENDL2TOL1MSG with HeapArray/Vector inputs",
-        ),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::SENDL2TOL1MSG with HeapArray/Vector inputs",
+            ))
+        }
     };
     let content_offset_operand = match &inputs[1] {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!(
-            "Transpiler does not know how to handle ForeignCall::SENDL2TOL1MSG with HeapArray/Vector inputs",
-        ),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Transpiler does not know how to handle ForeignCall::SENDL2TOL1MSG with HeapArray/Vector inputs",
+            ))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::SENDL2TOL1MSG,
@@ -767,6 +773,7 @@ fn handle_send_l2_to_l1_msg(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Getter Instructions are instructions that take NO inputs, and return information
@@ -781,8 +788,7 @@ fn handle_getter_instruction(
     avm_instrs: &mut Vec<AvmInstruction>,
     function: &str,
     destinations: &Vec<ValueOrArray>,
-    inputs: &Vec<ValueOrArray>,
-) {
+) -> Result<(), TranspileErrorKind> {
     enum EnvironmentVariable {
         ADDRESS,
         SENDER,
@@ -798,14 +804,15 @@ fn handle_getter_instruction(
         DAGASLEFT,
     }
 
-    // For the foreign calls we want to handle, we do not want inputs, as they are getters
-    assert!(inputs.is_empty());
-    assert!(destinations.len() == 1);
-
+    // The caller only treats foreign calls with no inputs and a single destination as getters
     let dest_offset_maybe = destinations[0];
     let dest_offset = match dest_offset_maybe {
         ValueOrArray::MemoryAddress(dest_offset) => dest_offset,
-        _ => panic!("ForeignCall address destination should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "ForeignCall address destination should be a single value",
+            ))
+        }
     };
 
     let var_idx = match function {
@@ -821,7 +828,7 @@ fn handle_getter_instruction(
         "avmOpcodeL2GasLeft" => EnvironmentVariable::L2GASLEFT,
         "avmOpcodeDaGasLeft" => EnvironmentVariable::DAGASLEFT,
         "avmOpcodeIsStaticCall" => EnvironmentVariable::ISSTATICCALL,
-        _ => panic!("Transpiler doesn't know how to process getter {:?}", function),
+        _ => return Err(TranspileErrorKind::UnknownForeignCall(function.to_string())),
     };
 
     avm_instrs.push(AvmInstruction {
@@ -831,6 +838,7 @@ fn handle_getter_instruction(
         immediates: vec![AvmOperand::U8 { value: var_idx as u8 }],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Handles Brillig's CONST opcode.
@@ -840,9 +848,10 @@ fn handle_const(
     value: &FieldElement,
     bit_size: &BitSize,
     indirect: bool,
-) {
+) -> Result<(), TranspileErrorKind> {
     let tag = tag_from_bit_size(*bit_size);
-    avm_instrs.push(generate_set_instruction(tag, destination, value, indirect));
+    avm_instrs.push(generate_set_instruction(tag, destination, value, indirect)?);
+    Ok(())
 }
 
 /// Generates an AVM SET instruction.
@@ -851,10 +860,15 @@ fn generate_set_instruction(
     dest: &MemoryAddress,
     value: &FieldElement,
     indirect: bool,
-) -> AvmInstruction {
+) -> Result<AvmInstruction, TranspileErrorKind> {
     let bits_needed_val = bits_needed_for(value);
     let bits_needed_mem = if bits_needed_val >= 16 { 16 } else { bits_needed_for(dest) };
-    assert!(bits_needed_mem <= 16);
+    if bits_needed_mem > 16 {
+        return Err(TranspileErrorKind::UnsupportedOperandSize {
+            instruction: "SET",
+            bits_needed: bits_needed_mem,
+        });
+    }
     let bits_needed_opcode = bits_needed_val.max(bits_needed_mem);
 
     let set_opcode = match bits_needed_opcode {
@@ -864,10 +878,15 @@ fn generate_set_instruction(
         64 => AvmOpcode::SET_64,
         128 => AvmOpcode::SET_128,
         254 => AvmOpcode::SET_FF,
-        _ => panic!("Invalid bits needed for opcode: {}", bits_needed_opcode),
+        _ => {
+            return Err(TranspileErrorKind::UnsupportedOperandSize {
+                instruction: "SET",
+                bits_needed: bits_needed_opcode,
+            })
+        }
     };
 
-    AvmInstruction {
+    Ok(AvmInstruction {
         opcode: set_opcode,
         indirect: if indirect {
             Some(AddressingModeBuilder::default().indirect_operand(dest).build())
@@ -875,9 +894,9 @@ fn generate_set_instruction(
             Some(AddressingModeBuilder::default().direct_operand(dest).build())
         },
         tag: Some(tag),
-        operands: vec![make_operand(bits_needed_mem, &(dest.to_usize()))],
-        immediates: vec![make_operand(bits_needed_opcode, value)],
-    }
+        operands: vec![make_operand(bits_needed_mem, &(dest.to_usize()))?],
+        immediates: vec![make_operand(bits_needed_opcode, value)?],
+    })
 }
 
 /// Generates an AVM CAST instruction.
@@ -887,13 +906,10 @@ fn generate_cast_instruction(
     destination: &MemoryAddress,
     destination_indirect: bool,
     dst_tag: AvmTypeTag,
-) -> AvmInstruction {
+) -> Result<AvmInstruction, TranspileErrorKind> {
     let bits_needed = bits_needed_for(source).max(bits_needed_for(destination));
-    let avm_opcode = match bits_needed {
-        8 => AvmOpcode::CAST_8,
-        16 => AvmOpcode::CAST_16,
-        _ => panic!("CAST only supports 8 and 16 bit encodings, needed {}", bits_needed),
-    };
+    let avm_opcode =
+        select_8_or_16_bit_opcode("CAST", bits_needed, AvmOpcode::CAST_8, AvmOpcode::CAST_16)?;
     let mut indirect_flags = AddressingModeBuilder::default();
     indirect_flags = if source_indirect {
         indirect_flags.indirect_operand(source)
@@ -907,16 +923,16 @@ fn generate_cast_instruction(
         indirect_flags.direct_operand(destination)
     };
 
-    AvmInstruction {
+    Ok(AvmInstruction {
         opcode: avm_opcode,
         indirect: Some(indirect_flags.build()),
         tag: Some(dst_tag),
         operands: vec![
-            make_operand(bits_needed, &(source.to_usize())),
-            make_operand(bits_needed, &(destination.to_usize())),
+            make_operand(bits_needed, &(source.to_usize()))?,
+            make_operand(bits_needed, &(destination.to_usize()))?,
         ],
         ..Default::default()
-    }
+    })
 }
 
 /// Generates an AVM REVERT instruction.
@@ -924,14 +940,15 @@ fn generate_revert_instruction(
     avm_instrs: &mut Vec<AvmInstruction>,
     revert_data_pointer: &MemoryAddress,
     revert_data_size_offset: &MemoryAddress,
-) {
+) -> Result<(), TranspileErrorKind> {
     let bits_needed =
         *[revert_data_pointer, revert_data_size_offset].map(bits_needed_for).iter().max().unwrap();
-    let avm_opcode = match bits_needed {
-        8 => AvmOpcode::REVERT_8,
-        16 => AvmOpcode::REVERT_16,
-        _ => panic!("REVERT only support 8 or 16 bit encodings, got: {}", bits_needed),
-    };
+    let avm_opcode = select_8_or_16_bit_opcode(
+        "REVERT",
+        bits_needed,
+        AvmOpcode::REVERT_8,
+        AvmOpcode::REVERT_16,
+    )?;
     avm_instrs.push(AvmInstruction {
         opcode: avm_opcode,
         indirect: Some(
@@ -941,11 +958,12 @@ fn generate_revert_instruction(
                 .build(),
         ),
         operands: vec![
-            make_operand(bits_needed, &revert_data_pointer.to_usize()),
-            make_operand(bits_needed, &revert_data_size_offset.to_usize()),
+            make_operand(bits_needed, &revert_data_pointer.to_usize())?,
+            make_operand(bits_needed, &revert_data_size_offset.to_usize())?,
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Generates an AVM RETURN instruction.
@@ -975,26 +993,25 @@ fn generate_mov_instruction(
     indirect: Option<AvmOperand>,
     source: u32,
     dest: u32,
-) -> AvmInstruction {
+) -> Result<AvmInstruction, TranspileErrorKind> {
     let bits_needed = [source, dest].iter().map(bits_needed_for).max().unwrap();
+    let mov_opcode =
+        select_8_or_16_bit_opcode("MOV", bits_needed, AvmOpcode::MOV_8, AvmOpcode::MOV_16)?;
 
-    let mov_opcode = match bits_needed {
-        8 => AvmOpcode::MOV_8,
-        16 => AvmOpcode::MOV_16,
-        _ => panic!("MOV operands must fit in 16 bits but needed {}", bits_needed),
-    };
-
-    AvmInstruction {
+    Ok(AvmInstruction {
         opcode: mov_opcode,
         indirect,
-        operands: vec![make_operand(bits_needed, &source), make_operand(bits_needed, &dest)],
+        operands: vec![make_operand(bits_needed, &source)?, make_operand(bits_needed, &dest)?],
         ..Default::default()
-    }
+    })
 }
 
 /// Black box functions
 /// (array goes in -> field element comes out)
-fn handle_black_box_function(avm_instrs: &mut Vec<AvmInstruction>, operation: &BlackBoxOp) {
+fn handle_black_box_function(
+    avm_instrs: &mut Vec<AvmInstruction>,
+    operation: &BlackBoxOp,
+) -> Result<(), TranspileErrorKind> {
     match operation {
         BlackBoxOp::Sha256Compression { input, hash_values, output } => {
             let inputs_offset = input.pointer.to_usize();
@@ -1024,7 +1041,7 @@ fn handle_black_box_function(avm_instrs: &mut Vec<AvmInstruction>, operation: &B
             len: _, // we don't use this.
         } => {
             // We'd love to validate the input size, but it's not known at compile time.
-            assert_eq!(output.size, 4, "Poseidon2Permutation output size must be 4!");
+            check_black_box_array_size("Poseidon2Permutation output", output, 4)?;
             let input_state_offset = message.pointer.to_usize();
             let output_state_offset = output.pointer.to_usize();
 
@@ -1045,9 +1062,9 @@ fn handle_black_box_function(avm_instrs: &mut Vec<AvmInstruction>, operation: &B
         }
         BlackBoxOp::Keccakf1600 { input, output } => {
             let input_offset = input.pointer.to_usize();
-            assert_eq!(input.size, 25, "Keccakf1600 input size must be 25!");
+            check_black_box_array_size("Keccakf1600 input", input, 25)?;
             let dest_offset = output.pointer.to_usize();
-            assert_eq!(output.size, 25, "Keccakf1600 output size must be 25!");
+            check_black_box_array_size("Keccakf1600 output", output, 25)?;
 
             avm_instrs.push(AvmInstruction {
                 opcode: AvmOpcode::KECCAKF1600,
@@ -1134,7 +1151,7 @@ fn handle_black_box_function(avm_instrs: &mut Vec<AvmInstruction>, operation: &B
             let num_points = points.size.to_usize();
             let scalars_offset = scalars.pointer.to_usize();
             // Output array is fixed to 3
-            assert_eq!(outputs.size, 3, "Output array size must be equal to 3");
+            check_black_box_array_size("MultiScalarMul output", outputs, 3)?;
             let outputs_offset = outputs.pointer.to_usize();
             avm_instrs.push(AvmInstruction {
                 opcode: AvmOpcode::MSM,
@@ -1155,31 +1172,48 @@ fn handle_black_box_function(avm_instrs: &mut Vec<AvmInstruction>, operation: &B
                 ..Default::default()
             });
         }
-        _ => panic!("Transpiler doesn't know how to process {:?}", operation),
+        _ => return Err(TranspileErrorKind::UnsupportedBlackBox(format!("{operation:?}"))),
     }
+    Ok(())
+}
+
+fn check_black_box_array_size(
+    name: &str,
+    array: &HeapArray,
+    expected_size: usize,
+) -> Result<(), TranspileErrorKind> {
+    if array.size != expected_size {
+        return Err(TranspileErrorKind::InvalidOperand(format!(
+            "{name} size must be {expected_size}, got {}",
+            array.size
+        )));
+    }
+    Ok(())
 }
 
 fn handle_debug_log(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    if !destinations.is_empty() || inputs.len() != 3 {
-        panic!(
-            "Transpiler expects ForeignCall::DEBUGLOG to have 0 destinations and 3 inputs, got {} and {}",
-            destinations.len(),
-            inputs.len()
-        );
-    }
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("DEBUGLOG", destinations, inputs, 0, 3)?;
     let (message_offset, message_size) = match &inputs[0] {
         ValueOrArray::HeapArray(HeapArray { pointer, size }) => (pointer, *size as u32),
-        _ => panic!("Message for ForeignCall::DEBUGLOG should be a HeapArray."),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Message for ForeignCall::DEBUGLOG should be a HeapArray.",
+            ))
+        }
     };
     // The fields are a slice, and this is represented as a (length: Field, slice: HeapVector).
     // The length field is redundant and we skipt it.
     let (fields_offset_ptr, fields_size_ptr) = match &inputs[2] {
         ValueOrArray::HeapVector(HeapVector { pointer, size }) => (pointer, size),
-        _ => panic!("List of fields for ForeignCall::DEBUGLOG should be a HeapVector (slice)."),
+        _ => {
+            return Err(invalid_foreign_call(
+                "List of fields for ForeignCall::DEBUGLOG should be a HeapVector (slice).",
+            ))
+        }
     };
     avm_instrs.push(AvmInstruction {
         opcode: AvmOpcode::DEBUGLOG,
@@ -1203,6 +1237,7 @@ fn handle_debug_log(
         immediates: vec![AvmOperand::U16 { value: message_size as u16 }],
         ..Default::default()
     });
+    Ok(())
 }
 
 // #[oracle(avmOpcodeCalldataCopy)]
@@ -1211,23 +1246,22 @@ fn handle_calldata_copy(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 2);
-    assert!(destinations.len() == 1);
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("CALLDATACOPY", destinations, inputs, 1, 2)?;
 
     let cd_offset = match inputs[0] {
         ValueOrArray::MemoryAddress(address) => address,
-        _ => panic!("CalldataCopy offset should be a memory address"),
+        _ => return Err(invalid_foreign_call("CalldataCopy offset should be a memory address")),
     };
 
     let copy_size_offset = match inputs[1] {
         ValueOrArray::MemoryAddress(address) => address,
-        _ => panic!("CalldataCopy size should be a memory address"),
+        _ => return Err(invalid_foreign_call("CalldataCopy size should be a memory address")),
     };
 
     let (dest_offset, ..) = match destinations[0] {
         ValueOrArray::HeapArray(HeapArray { pointer, size }) => (pointer, size),
-        _ => panic!("CalldataCopy destination should be an array"),
+        _ => return Err(invalid_foreign_call("CalldataCopy destination should be an array")),
     };
 
     avm_instrs.push(AvmInstruction {
@@ -1246,6 +1280,7 @@ fn handle_calldata_copy(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 // #[oracle(avmOpcodeReturndataSize)]
@@ -1254,13 +1289,16 @@ fn handle_returndata_size(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 0);
-    assert!(destinations.len() == 1);
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("RETURNDATASIZE", destinations, inputs, 1, 0)?;
 
     let dest_offset = match destinations[0] {
         ValueOrArray::MemoryAddress(address) => address,
-        _ => panic!("ReturndataSize destination should be a memory location"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "ReturndataSize destination should be a memory location",
+            ))
+        }
     };
 
     avm_instrs.push(AvmInstruction {
@@ -1269,6 +1307,7 @@ fn handle_returndata_size(
         operands: vec![AvmOperand::U16 { value: dest_offset.to_usize() as u16 }],
         ..Default::default()
     });
+    Ok(())
 }
 
 // #[oracle(avmOpcodeReturndataCopy)]
@@ -1277,24 +1316,27 @@ fn handle_returndata_copy(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 2);
-    assert!(destinations.len() == 2);
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("RETURNDATACOPY", destinations, inputs, 2, 2)?;
 
     let cd_offset = match inputs[0] {
         ValueOrArray::MemoryAddress(address) => address,
-        _ => panic!("ReturndataCopy offset should be a memory address"),
+        _ => return Err(invalid_foreign_call("ReturndataCopy offset should be a memory address")),
     };
 
     let copy_size_offset = match inputs[1] {
         ValueOrArray::MemoryAddress(address) => address,
-        _ => panic!("ReturndataCopy size should be a memory address"),
+        _ => return Err(invalid_foreign_call("ReturndataCopy size should be a memory address")),
     };
 
     // We skip the first destination, which is the size of the slice.
     let (dest_offset, write_size_here_offset) = match destinations[1] {
         ValueOrArray::HeapVector(HeapVector { pointer, size }) => (pointer, size),
-        _ => panic!("ReturndataCopy destination should be a vector (slice)"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "ReturndataCopy destination should be a vector (slice)",
+            ))
+        }
     };
 
     avm_instrs.extend([
@@ -1325,8 +1367,9 @@ fn handle_returndata_copy(
             ),
             copy_size_offset.to_usize() as u32,
             write_size_here_offset.to_usize() as u32,
-        ),
+        )?,
     ]);
+    Ok(())
 }
 
 // #[oracle(avmOpcodeReturn)]
@@ -1335,17 +1378,21 @@ fn handle_return(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 2);
-    assert!(destinations.len() == 0);
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("RETURN", destinations, inputs, 0, 2)?;
 
     // First arg is the size, which is ignored because it's redundant.
     let (return_data_offset, return_data_size) = match inputs[1] {
         ValueOrArray::HeapVector(HeapVector { pointer, size }) => (pointer, size),
-        _ => panic!("Revert instruction's args input should be a HeapVector"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Return instruction's args input should be a HeapVector",
+            ))
+        }
     };
 
     generate_return_instruction(avm_instrs, &return_data_offset, &return_data_size);
+    Ok(())
 }
 
 // #[oracle(avmOpcodeRevert)]
@@ -1354,17 +1401,20 @@ fn handle_revert(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 2);
-    assert!(destinations.len() == 0);
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("REVERT", destinations, inputs, 0, 2)?;
 
     // First arg is the size, which is ignored because it's redundant.
     let (revert_data_offset, revert_data_size_offset) = match inputs[1] {
         ValueOrArray::HeapVector(HeapVector { pointer, size }) => (pointer, size),
-        _ => panic!("Revert instruction's args input should be a HeapVector"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "Revert instruction's args input should be a HeapVector",
+            ))
+        }
     };
 
-    generate_revert_instruction(avm_instrs, &revert_data_offset, &revert_data_size_offset);
+    generate_revert_instruction(avm_instrs, &revert_data_offset, &revert_data_size_offset)
 }
 
 /// Emit a storage write opcode
@@ -1373,20 +1423,25 @@ fn handle_storage_write(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 2);
-    assert!(destinations.is_empty());
+) -> Result<(), TranspileErrorKind> {
+    check_foreign_call_arity("SSTORE", destinations, inputs, 0, 2)?;
 
     let slot_offset_maybe = inputs[0];
     let slot_offset = match slot_offset_maybe {
         ValueOrArray::MemoryAddress(slot_offset) => slot_offset,
-        _ => panic!("ForeignCall address destination should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "ForeignCall address destination should be a single value",
+            ))
+        }
     };
 
     let src_offset_maybe = inputs[1];
     let src_offset = match src_offset_maybe {
         ValueOrArray::MemoryAddress(src_offset) => src_offset,
-        _ => panic!("ForeignCall address source should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call("ForeignCall address source should be a single value"))
+        }
     };
 
     avm_instrs.push(AvmInstruction {
@@ -1403,6 +1458,7 @@ fn handle_storage_write(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Emit a GETCONTRACTINSTANCE opcode
@@ -1411,7 +1467,7 @@ fn handle_get_contract_instance(
     function: &str,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
+) -> Result<(), TranspileErrorKind> {
     #[allow(non_camel_case_types)]
     enum ContractInstanceMember {
         DEPLOYER,
@@ -1419,32 +1475,43 @@ fn handle_get_contract_instance(
         INIT_HASH,
     }
 
-    assert!(inputs.len() == 1);
-    assert!(destinations.len() == 2);
+    check_foreign_call_arity("GETCONTRACTINSTANCE", destinations, inputs, 2, 1)?;
 
     let member_idx = match function {
         "avmOpcodeGetContractInstanceDeployer" => ContractInstanceMember::DEPLOYER,
         "avmOpcodeGetContractInstanceClassId" => ContractInstanceMember::CLASS_ID,
         "avmOpcodeGetContractInstanceInitializationHash" => ContractInstanceMember::INIT_HASH,
-        _ => panic!("Transpiler doesn't know how to process function {:?}", function),
+        _ => return Err(TranspileErrorKind::UnknownForeignCall(function.to_string())),
     };
 
     let address_offset_maybe = inputs[0];
     let address_offset = match address_offset_maybe {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("GETCONTRACTINSTANCE address should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "GETCONTRACTINSTANCE address should be a single value",
+            ))
+        }
     };
 
     let dest_offset_maybe = destinations[0];
     let dest_offset = match dest_offset_maybe {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("GETCONTRACTINSTANCE dst destination should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "GETCONTRACTINSTANCE dst destination should be a single value",
+            ))
+        }
     };
 
     let exists_offset_maybe = destinations[1];
     let exists_offset = match exists_offset_maybe {
         ValueOrArray::MemoryAddress(offset) => offset,
-        _ => panic!("GETCONTRACTINSTANCE exists destination should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "GETCONTRACTINSTANCE exists destination should be a single value",
+            ))
+        }
     };
 
     avm_instrs.push(AvmInstruction {
@@ -1464,6 +1531,7 @@ fn handle_get_contract_instance(
         immediates: vec![AvmOperand::U8 { value: member_idx as u8 }],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Emit a storage read opcode
@@ -1472,20 +1540,26 @@ fn handle_storage_read(
     avm_instrs: &mut Vec<AvmInstruction>,
     destinations: &Vec<ValueOrArray>,
     inputs: &Vec<ValueOrArray>,
-) {
-    assert!(inputs.len() == 1); // output
-    assert!(destinations.len() == 1); // return value
+) -> Result<(), TranspileErrorKind> {
+    // A slot input and a return value
+    check_foreign_call_arity("SLOAD", destinations, inputs, 1, 1)?;
 
     let slot_offset_maybe = inputs[0];
     let slot_offset = match slot_offset_maybe {
         ValueOrArray::MemoryAddress(slot_offset) => slot_offset,
-        _ => panic!("ForeignCall address input should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call("ForeignCall address input should be a single value"))
+        }
     };
 
     let dest_offset_maybe = destinations[0];
     let dest_offset = match dest_offset_maybe {
         ValueOrArray::MemoryAddress(dest_offset) => dest_offset,
-        _ => panic!("ForeignCall address destination should be a single value"),
+        _ => {
+            return Err(invalid_foreign_call(
+                "ForeignCall address destination should be a single value",
+            ))
+        }
     };
 
     avm_instrs.push(AvmInstruction {
@@ -1502,6 +1576,7 @@ fn handle_storage_read(
         ],
         ..Default::default()
     });
+    Ok(())
 }
 
 /// Patch a Noir function's debug info with updated PCs since transpilation injects extra
@@ -1543,3 +1618,44 @@ fn tag_from_bit_size(bit_size: BitSize) -> AvmTypeTag {
         BitSize::Field => AvmTypeTag::FIELD,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transpile_error(brillig_bytecode: &[BrilligOpcode<FieldElement>]) -> TranspileError {
+        brillig_to_avm(brillig_bytecode, BrilligToAvmOptions::default())
+            .expect_err("Expected transpilation to fail")
+    }
+
+    #[test]
+    fn rejects_out_of_range_jumps() {
+        let error = transpile_error(&[BrilligOpcode::Jump { location: 2 }]);
+        assert_eq!(error.brillig_pc, Some(0));
+    }
+
+    #[test]
+    fn rejects_operands_which_do_not_fit_their_encoding() {
+        // JUMPI only has a 16 bit encoding for its condition
+        let error = transpile_error(&[
+            BrilligOpcode::Const {
+                destination: MemoryAddress::direct(0),
+                bit_size: BitSize::Integer(IntegerBitSize::U1),
+                value: FieldElement::from(1_u128),
+            },
+            BrilligOpcode::JumpIf { condition: MemoryAddress::direct(1 << 16), location: 0 },
+        ]);
+        assert_eq!(error.brillig_pc, Some(1));
+        assert!(matches!(error.kind, TranspileErrorKind::InvalidOperand(_)));
+    }
+
+    #[test]
+    fn rejects_addresses_which_do_not_fit_any_encoding() {
+        let error = transpile_error(&[BrilligOpcode::Mov {
+            destination: MemoryAddress::direct(1 << 20),
+            source: MemoryAddress::direct(0),
+        }]);
+        assert_eq!(error.brillig_pc, Some(0));
+        assert!(matches!(error.kind, TranspileErrorKind::UnsupportedOperandSize { .. }));
+    }
+}
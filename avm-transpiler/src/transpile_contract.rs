@@ -6,8 +6,9 @@ use serde::{Deserialize, Serialize};
 use acvm::acir::circuit::Program;
 use noirc_errors::debug_info::ProgramDebugInfo;
 
-use crate::transpile::{brillig_to_avm, patch_debug_info_pcs};
-use crate::utils::extract_brillig_from_acir_program;
+use crate::errors::TranspileError;
+use crate::linker::{link_brillig_from_acir_program, link_debug_infos};
+use crate::transpile::{brillig_to_avm, patch_debug_info_pcs, BrilligToAvmOptions};
 
 /// Representation of a contract with some transpiled functions
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,21 +83,48 @@ pub enum AvmOrAcirContractFunctionArtifact {
 
 /// Transpilation is performed when a TranspiledContract
 /// is constructed from a CompiledAcirContract
-impl From<CompiledAcirContractArtifact> for TranspiledContractArtifact {
-    fn from(contract: CompiledAcirContractArtifact) -> Self {
+impl TryFrom<CompiledAcirContractArtifact> for TranspiledContractArtifact {
+    type Error = TranspileError;
+
+    fn try_from(contract: CompiledAcirContractArtifact) -> Result<Self, TranspileError> {
+        TranspiledContractArtifact::transpile(contract, BrilligToAvmOptions::default())
+    }
+}
+
+impl TranspiledContractArtifact {
+    /// Transpiles the public functions of a contract with the given options.
+    pub fn transpile(
+        contract: CompiledAcirContractArtifact,
+        options: BrilligToAvmOptions,
+    ) -> Result<Self, TranspileError> {
         let mut functions: Vec<AvmOrAcirContractFunctionArtifact> = Vec::new();
 
         for function in contract.functions {
             if function.custom_attributes.contains(&"public".to_string()) {
                 // if function.name == "public_dispatch" {
                 info!("Transpiling AVM function {} on contract {}", function.name, contract.name);
-                // Extract Brillig Opcodes from acir
+                // Extract Brillig Opcodes from acir, linking the called unconstrained functions
                 let acir_program = function.bytecode;
-                let brillig_bytecode = extract_brillig_from_acir_program(&acir_program);
-                info!("Extracted Brillig program has {} instructions", brillig_bytecode.len());
+                let brillig_program = link_brillig_from_acir_program(&acir_program)
+                    .map_err(|error| error.in_function(&contract.name, &function.name))?;
+                info!(
+                    "Extracted Brillig program has {} instructions",
+                    brillig_program.bytecode.len()
+                );
+                let debug_symbols = ProgramDebugInfo {
+                    debug_infos: link_debug_infos(
+                        function.debug_symbols.debug_infos,
+                        &brillig_program,
+                    ),
+                };
 
                 // Transpile to AVM
-                let (avm_bytecode, brillig_pcs_to_avm_pcs) = brillig_to_avm(brillig_bytecode);
+                let (avm_bytecode, brillig_pcs_to_avm_pcs) =
+                    brillig_to_avm(&brillig_program.bytecode, options).map_err(|error| {
+                        error
+                            .in_function(&contract.name, &function.name)
+                            .with_source_location(&debug_symbols, &contract.file_map)
+                    })?;
 
                 log::info!(
                     "{}::{}: bytecode is {} bytes",
@@ -106,10 +134,8 @@ impl From<CompiledAcirContractArtifact> for TranspiledContractArtifact {
                 );
 
                 // Patch the debug infos with updated PCs
-                let debug_infos = patch_debug_info_pcs(
-                    function.debug_symbols.debug_infos,
-                    &brillig_pcs_to_avm_pcs,
-                );
+                let debug_infos =
+                    patch_debug_info_pcs(debug_symbols.debug_infos, &brillig_pcs_to_avm_pcs);
 
                 // Push modified function entry to ABI
                 functions.push(AvmOrAcirContractFunctionArtifact::Avm(
@@ -128,13 +154,13 @@ impl From<CompiledAcirContractArtifact> for TranspiledContractArtifact {
                 functions.push(AvmOrAcirContractFunctionArtifact::Acir(function));
             }
         }
-        TranspiledContractArtifact {
+        Ok(TranspiledContractArtifact {
             transpiled: true,
             noir_version: contract.noir_version,
             name: contract.name,
             functions, // some acir, some transpiled avm functions
             outputs: contract.outputs,
             file_map: contract.file_map,
-        }
+        })
     }
 }
@@ -1,42 +1,12 @@
-use acvm::acir::circuit::brillig::BrilligFunctionId;
 use acvm::{AcirField, FieldElement};
 use log::{debug, info, trace};
 
 use acvm::acir::brillig::Opcode as BrilligOpcode;
-use acvm::acir::circuit::{Opcode, Program};
 
+use crate::errors::TranspileErrorKind;
 use crate::instructions::{AvmInstruction, AvmOperand};
 use crate::opcodes::AvmOpcode;
 
-/// Extract the Brillig program from its `Program` wrapper.
-/// Noir entry point unconstrained functions are compiled to their own list contained
-/// as part of a full program. Function calls are then accessed through a function
-/// pointer opcode in ACIR that fetches those unconstrained functions from the main list.
-/// This function just extracts Brillig bytecode, with the assumption that the
-/// 0th unconstrained function in the full `Program` structure.
-pub fn extract_brillig_from_acir_program(
-    program: &Program<FieldElement>,
-) -> &[BrilligOpcode<FieldElement>] {
-    assert_eq!(
-        program.functions.len(),
-        1,
-        "An AVM program should have only a single ACIR function with a 'BrilligCall'"
-    );
-    let main_function = &program.functions[0];
-    let opcodes = &main_function.opcodes;
-    assert_eq!(opcodes.len(), 1, "An AVM program should only have a single `BrilligCall`");
-    match opcodes[0] {
-        Opcode::BrilligCall { id, .. } => assert_eq!(id, BrilligFunctionId(0), "The ID of the `BrilligCall` must be 0 as we have a single `Brillig` function"),
-        _ => panic!("Tried to extract a Brillig program from its ACIR wrapper opcode, but the opcode doesn't contain Brillig!"),
-    }
-    assert_eq!(
-        program.unconstrained_functions.len(),
-        1,
-        "An AVM program should be contained entirely in only a single `Brillig` function"
-    );
-    &program.unconstrained_functions[0].bytecode
-}
-
 /// Print inputs, outputs, and instructions in a Brillig program
 pub fn dbg_print_brillig_program(brillig_bytecode: &[BrilligOpcode<FieldElement>]) {
     trace!("Printing Brillig program...");
@@ -64,15 +34,68 @@ pub fn dbg_print_avm_program(avm_program: &[AvmInstruction]) {
     }
 }
 
-pub fn make_operand<T: Into<FieldElement> + Clone>(bits: usize, value: &T) -> AvmOperand {
+/// Builds an operand of the given size in bits, failing if the value doesn't fit in it.
+pub fn make_operand<T: Into<FieldElement> + Clone>(
+    bits: usize,
+    value: &T,
+) -> Result<AvmOperand, TranspileErrorKind> {
     let field: FieldElement = value.clone().into();
-    match bits {
-        8 => AvmOperand::U8 { value: field.try_to_u32().unwrap() as u8 },
-        16 => AvmOperand::U16 { value: field.try_to_u32().unwrap() as u16 },
-        32 => AvmOperand::U32 { value: field.try_to_u32().unwrap() },
-        64 => AvmOperand::U64 { value: field.try_to_u64().unwrap() },
-        128 => AvmOperand::U128 { value: field.try_into_u128().unwrap() },
+    let too_large = || {
+        TranspileErrorKind::InvalidOperand(format!("Operand {field} doesn't fit in {bits} bits"))
+    };
+    let operand = match bits {
+        8 => AvmOperand::U8 {
+            value: field.try_to_u32().and_then(|v| v.try_into().ok()).ok_or_else(too_large)?,
+        },
+        16 => AvmOperand::U16 {
+            value: field.try_to_u32().and_then(|v| v.try_into().ok()).ok_or_else(too_large)?,
+        },
+        32 => AvmOperand::U32 { value: field.try_to_u32().ok_or_else(too_large)? },
+        64 => AvmOperand::U64 { value: field.try_to_u64().ok_or_else(too_large)? },
+        128 => AvmOperand::U128 { value: field.try_into_u128().ok_or_else(too_large)? },
         254 => AvmOperand::FF { value: field },
-        _ => panic!("Invalid operand size for bits: {}", bits),
+        _ => {
+            return Err(TranspileErrorKind::InvalidOperand(format!(
+                "Invalid operand size of {bits} bits"
+            )))
+        }
+    };
+    Ok(operand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operand_string(bits: usize, value: impl Into<FieldElement> + Clone) -> String {
+        make_operand(bits, &value).unwrap().to_string()
+    }
+
+    #[test]
+    fn makes_operands_of_each_size() {
+        assert_eq!(operand_string(8, 255_u32), " U8:255");
+        assert_eq!(operand_string(16, 65535_u32), " U16:65535");
+        assert_eq!(operand_string(32, u32::MAX), format!(" U32:{}", u32::MAX));
+        assert_eq!(operand_string(64, u128::from(u64::MAX)), format!(" U64:{}", u64::MAX));
+        assert_eq!(operand_string(128, u128::MAX), format!(" U128:{}", u128::MAX));
+        let field = -FieldElement::one();
+        assert_eq!(operand_string(254, field), format!(" FF:{field}"));
+    }
+
+    #[test]
+    fn rejects_values_which_do_not_fit() {
+        assert!(make_operand(8, &256_u32).is_err());
+        assert!(make_operand(16, &65536_u32).is_err());
+        assert!(make_operand(32, &(u128::from(u32::MAX) + 1)).is_err());
+        assert!(make_operand(64, &(u128::from(u64::MAX) + 1)).is_err());
+        assert!(make_operand(128, &-FieldElement::one()).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert_eq!(
+            make_operand(12, &1_u32).err(),
+            Some(TranspileErrorKind::InvalidOperand("Invalid operand size of 12 bits".to_string()))
+        );
     }
 }
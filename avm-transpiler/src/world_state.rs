@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use acvm::{AcirField, FieldElement};
+
+/// The environment a public function is executed in.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionEnvironment {
+    pub address: FieldElement,
+    pub sender: FieldElement,
+    pub transaction_fee: FieldElement,
+    pub chain_id: FieldElement,
+    pub version: FieldElement,
+    pub block_number: FieldElement,
+    pub timestamp: u64,
+    pub fee_per_l2_gas: FieldElement,
+    pub fee_per_da_gas: FieldElement,
+    pub is_static_call: bool,
+    pub l2_gas_left: u32,
+    pub da_gas_left: u32,
+    pub calldata: Vec<FieldElement>,
+}
+
+/// A deployed contract, as returned by the GETCONTRACTINSTANCE opcode.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContractInstance {
+    pub deployer: FieldElement,
+    pub class_id: FieldElement,
+    pub initialization_hash: FieldElement,
+}
+
+/// An unencrypted log emitted by a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnencryptedLog {
+    pub contract_address: FieldElement,
+    pub fields: Vec<FieldElement>,
+}
+
+/// A message sent by a contract to L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L2ToL1Message {
+    pub contract_address: FieldElement,
+    pub recipient: FieldElement,
+    pub content: FieldElement,
+}
+
+/// An in-memory stand-in for the world state that public functions read and write.
+/// Nothing is siloed or hashed: the contract address is kept next to each value instead.
+#[derive(Debug, Clone, Default)]
+pub struct MockWorldState {
+    /// Public storage values, keyed by contract address and slot. Missing slots read as zero.
+    pub public_storage: HashMap<(FieldElement, FieldElement), FieldElement>,
+    /// Leaves of the note hash tree, in leaf index order
+    pub note_hashes: Vec<FieldElement>,
+    /// Existing and newly emitted nullifiers, along with the address of their contract
+    pub nullifiers: HashSet<(FieldElement, FieldElement)>,
+    /// Leaves of the L1 to L2 message tree, in leaf index order
+    pub l1_to_l2_messages: Vec<FieldElement>,
+    /// Deployed contracts, keyed by address
+    pub contract_instances: HashMap<FieldElement, ContractInstance>,
+
+    /// Note hashes emitted during execution, along with the address of their contract
+    pub new_note_hashes: Vec<(FieldElement, FieldElement)>,
+    /// Nullifiers emitted during execution, along with the address of their contract
+    pub new_nullifiers: Vec<(FieldElement, FieldElement)>,
+    pub unencrypted_logs: Vec<UnencryptedLog>,
+    pub l2_to_l1_messages: Vec<L2ToL1Message>,
+}
+
+impl MockWorldState {
+    pub fn storage_read(&self, contract_address: FieldElement, slot: FieldElement) -> FieldElement {
+        self.public_storage.get(&(contract_address, slot)).copied().unwrap_or_default()
+    }
+
+    pub fn storage_write(
+        &mut self,
+        contract_address: FieldElement,
+        slot: FieldElement,
+        value: FieldElement,
+    ) {
+        self.public_storage.insert((contract_address, slot), value);
+    }
+
+    pub fn note_hash_exists(&self, note_hash: FieldElement, leaf_index: FieldElement) -> bool {
+        leaf_value(&self.note_hashes, leaf_index) == Some(note_hash)
+    }
+
+    pub fn emit_note_hash(&mut self, contract_address: FieldElement, note_hash: FieldElement) {
+        self.new_note_hashes.push((contract_address, note_hash));
+    }
+
+    pub fn nullifier_exists(
+        &self,
+        contract_address: FieldElement,
+        nullifier: FieldElement,
+    ) -> bool {
+        self.nullifiers.contains(&(contract_address, nullifier))
+    }
+
+    /// Inserts a new nullifier. Returns false if the nullifier already exists.
+    pub fn emit_nullifier(
+        &mut self,
+        contract_address: FieldElement,
+        nullifier: FieldElement,
+    ) -> bool {
+        if !self.nullifiers.insert((contract_address, nullifier)) {
+            return false;
+        }
+        self.new_nullifiers.push((contract_address, nullifier));
+        true
+    }
+
+    pub fn l1_to_l2_message_exists(
+        &self,
+        message_hash: FieldElement,
+        leaf_index: FieldElement,
+    ) -> bool {
+        leaf_value(&self.l1_to_l2_messages, leaf_index) == Some(message_hash)
+    }
+
+    pub fn emit_unencrypted_log(
+        &mut self,
+        contract_address: FieldElement,
+        fields: Vec<FieldElement>,
+    ) {
+        self.unencrypted_logs.push(UnencryptedLog { contract_address, fields });
+    }
+
+    pub fn send_l2_to_l1_message(
+        &mut self,
+        contract_address: FieldElement,
+        recipient: FieldElement,
+        content: FieldElement,
+    ) {
+        self.l2_to_l1_messages.push(L2ToL1Message { contract_address, recipient, content });
+    }
+
+    pub fn contract_instance(&self, address: FieldElement) -> Option<&ContractInstance> {
+        self.contract_instances.get(&address)
+    }
+}
+
+fn leaf_value(leaves: &[FieldElement], leaf_index: FieldElement) -> Option<FieldElement> {
+    let leaf_index = usize::try_from(leaf_index.try_to_u64()?).ok()?;
+    leaves.get(leaf_index).copied()
+}
@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use itertools::Itertools;
+use powdr_ast::analyzed::{
+    AlgebraicBinaryOperation, AlgebraicBinaryOperator, AlgebraicExpression, AlgebraicReference,
+    AlgebraicUnaryOperation, Analyzed, Identity, PolyID, PolynomialType,
+};
+use powdr_number::{DegreeType, FieldElement};
+
+use crate::expression_evaluation::{
+    fold_expression, get_alias_expressions_in_order, get_alias_polys_in_order, recurse_expression,
+    ExpressionFolder,
+};
+use crate::relation_builder::{get_subrelations, group_relations_per_file};
+use crate::utils::sanitize_name;
+
+/// Relation Degree
+///
+/// The maximum degree of the identities of a relation file, and what drives it
+#[derive(Debug)]
+pub struct RelationDegree {
+    pub relation: String,
+    pub max_degree: DegreeType,
+    /// Sub-relation index of the identity of maximum degree
+    pub subrelation: usize,
+    pub label: Option<String>,
+    /// Aliases of degree above one used by the identity of maximum degree, highest degree first
+    pub inflating_aliases: Vec<(String, DegreeType)>,
+}
+
+impl fmt::Display for RelationDegree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: degree {}, driven by subrelation {}",
+            self.relation, self.max_degree, self.subrelation
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, " ({label})")?;
+        }
+        if !self.inflating_aliases.is_empty() {
+            let aliases = self
+                .inflating_aliases
+                .iter()
+                .map(|(name, degree)| format!("{name} (degree {degree})"))
+                .join(", ");
+            write!(f, ", through aliases {aliases}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Analyze Degrees
+///
+/// Reports the maximum degree of the polynomial identities of each relation file, sorted by
+/// relation name. Degrees are the ones of the generated relations, before any splitting.
+pub fn analyze_degrees<F: FieldElement>(analyzed: &Analyzed<F>) -> Vec<RelationDegree> {
+    let mut analyzed_identities = analyzed.identities.clone();
+    analyzed_identities.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let alias_polys_in_order = get_alias_polys_in_order(analyzed);
+    let alias_degrees = get_alias_expressions_in_order(&alias_polys_in_order)
+        .into_iter()
+        .map(|(name, degree, _)| (name, degree))
+        .collect::<HashMap<_, _>>();
+    let indexed_aliases = alias_polys_in_order
+        .into_iter()
+        .map(|(sym, expr)| (&sym.absolute_name, expr))
+        .collect::<HashMap<_, _>>();
+
+    group_relations_per_file(&analyzed_identities)
+        .iter()
+        .sorted_by_key(|(relation, _)| *relation)
+        .filter_map(|(relation, identities)| {
+            // The first identity of maximum degree drives the degree of the relation.
+            let (subrelation, identity, degree, aliases) = get_subrelations(identities)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(subrelation, identity)| {
+                    let selector = identity.left.selector.as_ref()?;
+                    let (degree, _, aliases) =
+                        recurse_expression(selector, &indexed_aliases, false);
+                    Some((subrelation, identity, degree, aliases))
+                })
+                .rev()
+                .max_by_key(|(_, _, degree, _)| *degree)?;
+
+            let inflating_aliases = aliases
+                .into_iter()
+                .filter_map(|alias| {
+                    let degree = *alias_degrees.get(&alias)?;
+                    (degree > 1).then_some((alias, degree))
+                })
+                .sorted_by(|(a_name, a_degree), (b_name, b_degree)| {
+                    b_degree.cmp(a_degree).then_with(|| a_name.cmp(b_name))
+                })
+                .collect();
+            Some(RelationDegree {
+                relation: relation.clone(),
+                max_degree: degree,
+                subrelation,
+                label: identity.attribute.clone(),
+                inflating_aliases,
+            })
+        })
+        .collect()
+}
+
+/// Split Identities
+///
+/// The identities of the ast once split to bound their degree, along with the committed columns
+/// introduced by the split
+pub struct SplitIdentities<F> {
+    pub identities: Vec<Identity<AlgebraicExpression<F>>>,
+    /// Sanitized names of the introduced columns
+    pub columns: Vec<String>,
+}
+
+/// Split High Degree Identities
+///
+/// Rewrites the polynomial identities of degree above `max_degree`, moving subexpressions into new
+/// committed columns `{relation}_degree_split_{n}`. Each new column is constrained to be equal to
+/// its subexpression by a new identity of the same relation, which comes after the existing ones so
+/// that their sub-relation indices don't change.
+///
+/// Identities within the bound are kept as they are. The split ones have their aliases inlined.
+/// No witness generation is emitted for the new columns: the trace builder has to fill them with
+/// the values of their subexpressions, which is why the split is experimental.
+///
+/// Returns an error if `max_degree` is below 2, as products of two columns can't be split, or if an
+/// identity uses an unsupported expression.
+pub fn split_high_degree_identities<F: FieldElement>(
+    analyzed: &Analyzed<F>,
+    max_degree: DegreeType,
+) -> Result<SplitIdentities<F>, String> {
+    if max_degree < 2 {
+        return Err(format!(
+            "Identities can't be split below degree 2, got a maximum degree of {max_degree}"
+        ));
+    }
+
+    let inlined_identities = analyzed
+        .identities_with_inlined_intermediate_polynomials()
+        .into_iter()
+        .map(|identity| (identity.id, identity))
+        .collect::<HashMap<_, _>>();
+
+    let mut analyzed_identities = analyzed.identities.clone();
+    analyzed_identities.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut splitter = DegreeSplitter {
+        max_degree,
+        next_poly_id: analyzed.commitment_count() as u64,
+        columns: Vec::new(),
+    };
+    let mut next_identity_id = analyzed_identities
+        .last()
+        .map_or(0, |identity| identity.id + 1);
+    let mut definitions = Vec::new();
+
+    let grouped_relations = group_relations_per_file(&analyzed_identities);
+    let mut identities = Vec::new();
+    for (relation, relation_identities) in grouped_relations.iter().sorted_by_key(|(name, _)| *name)
+    {
+        let subrelation_ids = get_subrelations(relation_identities)
+            .iter()
+            .map(|identity| identity.id)
+            .collect_vec();
+        for identity in relation_identities {
+            let inlined = &inlined_identities[&identity.id];
+            let selector = match &inlined.left.selector {
+                Some(selector) if subrelation_ids.contains(&identity.id) => selector,
+                _ => {
+                    identities.push(identity.clone());
+                    continue;
+                }
+            };
+            if expression_degree(selector)? <= max_degree {
+                identities.push(identity.clone());
+                continue;
+            }
+
+            let first_column = splitter.columns.len();
+            let (split_selector, _) = splitter.split(selector, relation, &mut definitions)?;
+            log::info!(
+                "Split identity {} of relation {relation} with {} new columns",
+                identity.id,
+                splitter.columns.len() - first_column
+            );
+            let mut split_identity = inlined.clone();
+            split_identity.left.selector = Some(split_selector);
+            identities.push(split_identity);
+
+            for definition in definitions.drain(..) {
+                let mut definition_identity = inlined.clone();
+                definition_identity.id = next_identity_id;
+                definition_identity.attribute = None;
+                definition_identity.left.selector = Some(definition);
+                identities.push(definition_identity);
+                next_identity_id += 1;
+            }
+        }
+    }
+
+    Ok(SplitIdentities {
+        identities,
+        columns: splitter.columns,
+    })
+}
+
+struct DegreeSplitter {
+    max_degree: DegreeType,
+    next_poly_id: u64,
+    columns: Vec<String>,
+}
+
+impl DegreeSplitter {
+    /// Returns an expression equivalent to `expression` of degree at most `max_degree`, along with
+    /// its degree. Pushes the identities defining the new columns to `definitions`.
+    fn split<F: FieldElement>(
+        &mut self,
+        expression: &AlgebraicExpression<F>,
+        relation: &str,
+        definitions: &mut Vec<AlgebraicExpression<F>>,
+    ) -> Result<(AlgebraicExpression<F>, DegreeType), String> {
+        Ok(match expression {
+            AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation { left, op, right }) => {
+                let (mut left, mut left_degree) = self.split(left, relation, definitions)?;
+                let (mut right, mut right_degree) = self.split(right, relation, definitions)?;
+                let degree = match op {
+                    AlgebraicBinaryOperator::Mul => {
+                        // Commit to the operand of highest degree until the product fits.
+                        while left_degree + right_degree > self.max_degree {
+                            if left_degree >= right_degree {
+                                left = self.commit(left, relation, definitions);
+                                left_degree = 1;
+                            } else {
+                                right = self.commit(right, relation, definitions);
+                                right_degree = 1;
+                            }
+                        }
+                        left_degree + right_degree
+                    }
+                    AlgebraicBinaryOperator::Add | AlgebraicBinaryOperator::Sub => {
+                        std::cmp::max(left_degree, right_degree)
+                    }
+                    _ => return Err(format!("Unsupported operator {op:?}")),
+                };
+                let expression = AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation {
+                    left: Box::new(left),
+                    op: *op,
+                    right: Box::new(right),
+                });
+                (expression, degree)
+            }
+            AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation { op, expr }) => {
+                let (expr, degree) = self.split(expr, relation, definitions)?;
+                let expression = AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation {
+                    op: *op,
+                    expr: Box::new(expr),
+                });
+                (expression, degree)
+            }
+            _ => (expression.clone(), expression_degree(expression)?),
+        })
+    }
+
+    /// Introduces a new committed column constrained to be equal to `expression`.
+    fn commit<F: FieldElement>(
+        &mut self,
+        expression: AlgebraicExpression<F>,
+        relation: &str,
+        definitions: &mut Vec<AlgebraicExpression<F>>,
+    ) -> AlgebraicExpression<F> {
+        let name = format!("{relation}.degree_split_{}", self.columns.len());
+        let column = AlgebraicExpression::Reference(AlgebraicReference {
+            name: name.clone(),
+            poly_id: PolyID {
+                id: self.next_poly_id,
+                ptype: PolynomialType::Committed,
+            },
+            next: false,
+        });
+        self.next_poly_id += 1;
+        self.columns.push(sanitize_name(&name));
+
+        definitions.push(AlgebraicExpression::BinaryOperation(
+            AlgebraicBinaryOperation {
+                left: Box::new(column.clone()),
+                op: AlgebraicBinaryOperator::Sub,
+                right: Box::new(expression),
+            },
+        ));
+        column
+    }
+}
+
+/// Degree of an expression whose aliases are inlined.
+fn expression_degree<F: FieldElement>(
+    expression: &AlgebraicExpression<F>,
+) -> Result<DegreeType, String> {
+    fold_expression(expression, &mut DegreeFolder)
+}
+
+struct DegreeFolder;
+
+impl<F> ExpressionFolder<F> for DegreeFolder {
+    type Output = DegreeType;
+
+    fn number(&mut self, _number: &F) -> Result<DegreeType, String> {
+        Ok(0)
+    }
+
+    fn reference(&mut self, _reference: &AlgebraicReference) -> Result<DegreeType, String> {
+        Ok(1)
+    }
+
+    fn add(&mut self, left: DegreeType, right: DegreeType) -> DegreeType {
+        std::cmp::max(left, right)
+    }
+
+    fn sub(&mut self, left: DegreeType, right: DegreeType) -> DegreeType {
+        std::cmp::max(left, right)
+    }
+
+    fn mul(&mut self, left: DegreeType, right: DegreeType) -> DegreeType {
+        left + right
+    }
+
+    fn neg(&mut self, expression: DegreeType) -> DegreeType {
+        expression
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use powdr_ast::analyzed::AlgebraicUnaryOperator;
+    use powdr_number::Bn254Field;
+
+    use super::*;
+
+    fn column(name: &str, id: u64) -> AlgebraicExpression<Bn254Field> {
+        AlgebraicExpression::Reference(AlgebraicReference {
+            name: name.to_string(),
+            poly_id: PolyID {
+                id,
+                ptype: PolynomialType::Committed,
+            },
+            next: false,
+        })
+    }
+
+    fn binary(
+        left: AlgebraicExpression<Bn254Field>,
+        op: AlgebraicBinaryOperator,
+        right: AlgebraicExpression<Bn254Field>,
+    ) -> AlgebraicExpression<Bn254Field> {
+        AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    fn product(columns: &[&str]) -> AlgebraicExpression<Bn254Field> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(id, name)| column(name, id as u64))
+            .reduce(|left, right| binary(left, AlgebraicBinaryOperator::Mul, right))
+            .unwrap()
+    }
+
+    fn splitter() -> DegreeSplitter {
+        DegreeSplitter {
+            max_degree: 2,
+            next_poly_id: 10,
+            columns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn computes_expression_degrees() {
+        let number = AlgebraicExpression::Number(Bn254Field::zero());
+        assert_eq!(expression_degree(&number), Ok(0));
+        assert_eq!(expression_degree(&product(&["a", "b", "c"])), Ok(3));
+
+        let sum = binary(
+            product(&["a", "b"]),
+            AlgebraicBinaryOperator::Sub,
+            column("c", 2),
+        );
+        let negated = AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation {
+            op: AlgebraicUnaryOperator::Minus,
+            expr: Box::new(sum),
+        });
+        assert_eq!(expression_degree(&negated), Ok(2));
+    }
+
+    #[test]
+    fn rejects_unsupported_operators() {
+        let power = binary(column("a", 0), AlgebraicBinaryOperator::Pow, column("b", 1));
+        assert!(expression_degree(&power).is_err());
+
+        let mut definitions = Vec::new();
+        assert!(splitter().split(&power, "main", &mut definitions).is_err());
+    }
+
+    #[test]
+    fn keeps_expressions_within_the_bound() {
+        let expression = product(&["a", "b"]);
+        let mut splitter = splitter();
+        let mut definitions = Vec::new();
+        let (split, degree) = splitter
+            .split(&expression, "main", &mut definitions)
+            .unwrap();
+
+        assert_eq!(split, expression);
+        assert_eq!(degree, 2);
+        assert!(splitter.columns.is_empty());
+        assert!(definitions.is_empty());
+    }
+
+    #[test]
+    fn commits_subexpressions_above_the_bound() {
+        let expression = product(&["a", "b", "c", "d"]);
+        let mut splitter = splitter();
+        let mut definitions = Vec::new();
+        let (split, degree) = splitter
+            .split(&expression, "main", &mut definitions)
+            .unwrap();
+
+        assert_eq!(degree, 2);
+        assert_eq!(expression_degree(&split), Ok(2));
+        assert_eq!(
+            splitter.columns,
+            vec!["main_degree_split_0", "main_degree_split_1"]
+        );
+        assert_eq!(splitter.next_poly_id, 12);
+        // Each new column is constrained to be equal to a subexpression within the bound
+        assert_eq!(definitions.len(), 2);
+        for definition in &definitions {
+            assert_eq!(expression_degree(definition), Ok(2));
+        }
+    }
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 
 use powdr_ast::analyzed::{
-    AlgebraicBinaryOperation, AlgebraicBinaryOperator, AlgebraicExpression,
+    AlgebraicBinaryOperation, AlgebraicBinaryOperator, AlgebraicExpression, AlgebraicReference,
     AlgebraicUnaryOperation, AlgebraicUnaryOperator, Analyzed, Symbol,
 };
 use powdr_number::FieldElement;
@@ -132,3 +132,55 @@ pub fn recurse_expression<F: FieldElement>(
         AlgebraicExpression::Challenge(_) => unimplemented!("{:?}", top_expr),
     }
 }
+
+/// Expression Folder
+///
+/// Computes a value bottom-up over an algebraic expression, for the operations that the
+/// generated relations support: numbers, column references, addition, subtraction,
+/// multiplication and negation.
+pub(crate) trait ExpressionFolder<F> {
+    type Output;
+
+    fn number(&mut self, number: &F) -> Result<Self::Output, String>;
+    fn reference(&mut self, reference: &AlgebraicReference) -> Result<Self::Output, String>;
+    fn add(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+    fn sub(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+    fn mul(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+    fn neg(&mut self, expression: Self::Output) -> Self::Output;
+}
+
+/// Fold Expression
+///
+/// Walks an algebraic expression with a folder. Returns an error on the operators and
+/// expressions that the generated relations don't support.
+pub(crate) fn fold_expression<F: FieldElement, V: ExpressionFolder<F>>(
+    expression: &AlgebraicExpression<F>,
+    folder: &mut V,
+) -> Result<V::Output, String> {
+    match expression {
+        AlgebraicExpression::Number(n) => folder.number(n),
+        AlgebraicExpression::Reference(polyref) => folder.reference(polyref),
+        AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation { left, op, right }) => {
+            let left = fold_expression(left, folder)?;
+            let right = fold_expression(right, folder)?;
+            match op {
+                AlgebraicBinaryOperator::Add => Ok(folder.add(left, right)),
+                AlgebraicBinaryOperator::Sub => Ok(folder.sub(left, right)),
+                AlgebraicBinaryOperator::Mul => Ok(folder.mul(left, right)),
+                _ => Err(format!("Unsupported operator {op:?}")),
+            }
+        }
+        AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation {
+            op: AlgebraicUnaryOperator::Minus,
+            expr,
+        }) => {
+            let expr = fold_expression(expr, folder)?;
+            Ok(folder.neg(expr))
+        }
+        // Public references are not currently used, and challenges are not being used in our
+        // current pil construction
+        AlgebraicExpression::PublicReference(_) | AlgebraicExpression::Challenge(_) => {
+            Err(format!("Unsupported expression {expression:?}"))
+        }
+    }
+}
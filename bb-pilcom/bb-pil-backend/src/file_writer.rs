@@ -1,11 +1,23 @@
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// The clang-format used to format the generated cpp files, as in barretenberg's `format.sh`.
+/// Can be overridden with the `CLANG_FORMAT` environment variable.
+const CLANG_FORMAT: &str = "clang-format-16";
 
 pub struct BBFiles {
     pub vm_name: String,
     pub base_dir: String,
     pub relations: String,
+    /// The generated files, keyed by their path relative to the base directory.
+    /// Nothing is written to disk until `write_changed_files` is called.
+    files: RefCell<BTreeMap<PathBuf, String>>,
 }
 
 impl BBFiles {
@@ -24,23 +36,234 @@ impl BBFiles {
             vm_name: vm_name.to_owned(),
             base_dir,
             relations,
+            files: RefCell::new(BTreeMap::new()),
         }
     }
 
-    pub fn remove_generated_dir(&self) {
-        let path = Path::new(&self.base_dir);
-        if path.exists() {
-            std::fs::remove_dir_all(path).unwrap();
+    pub fn write_file(&self, folder: Option<&str>, filename: &str, contents: &str) {
+        let path = Path::new(folder.unwrap_or("")).join(filename);
+        self.files.borrow_mut().insert(path, contents.to_owned());
+    }
+
+    /// Format Cpp Files
+    ///
+    /// Formats the generated cpp files in memory with clang-format, using the `.clang-format` of
+    /// the directory they're generated in, so that they compare equal to the formatted files on disk
+    pub fn format_cpp_files(&self) -> io::Result<()> {
+        let clang_format = env::var("CLANG_FORMAT").unwrap_or_else(|_| CLANG_FORMAT.to_owned());
+        for (path, contents) in self.files.borrow_mut().iter_mut() {
+            if !matches!(
+                path.extension().and_then(|extension| extension.to_str()),
+                Some("cpp" | "hpp")
+            ) {
+                continue;
+            }
+            let full_path = Path::new(&self.base_dir).join(path);
+            *contents = clang_format_file(&clang_format, &full_path, contents)?;
         }
+        Ok(())
     }
 
-    pub fn write_file(&self, folder: Option<&str>, filename: &str, contents: &str) {
-        // attempt to create dir
-        let base_path = Path::new(&self.base_dir).join(folder.unwrap_or(""));
-        let _ = std::fs::create_dir_all(&base_path);
+    /// Changed Files
+    ///
+    /// The generated files which are missing from the base directory or differ from their version
+    /// on disk, relative to the base directory
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let base_path = Path::new(&self.base_dir);
+        self.files
+            .borrow()
+            .iter()
+            .filter(|(path, contents)| {
+                fs::read_to_string(base_path.join(path)).map_or(true, |old| &old != *contents)
+            })
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Stale Files
+    ///
+    /// The files of the base directory which are not generated anymore, relative to the base
+    /// directory
+    pub fn stale_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut stale_files = Vec::new();
+        let base_path = Path::new(&self.base_dir);
+        if base_path.exists() {
+            self.collect_stale_files(base_path, Path::new(""), &mut stale_files)?;
+        }
+        stale_files.sort();
+        Ok(stale_files)
+    }
+
+    fn collect_stale_files(
+        &self,
+        base_path: &Path,
+        folder: &Path,
+        stale_files: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(base_path.join(folder))? {
+            let entry = entry?;
+            let path = folder.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                self.collect_stale_files(base_path, &path, stale_files)?;
+            } else if !self.files.borrow().contains_key(&path) {
+                stale_files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write Changed Files
+    ///
+    /// Writes the generated files whose contents changed, leaving the others untouched so that
+    /// their timestamps don't trigger rebuilds. Returns the paths of the written files.
+    pub fn write_changed_files(&self) -> io::Result<Vec<PathBuf>> {
+        let changed_files = self.changed_files();
+        let files = self.files.borrow();
+        for path in &changed_files {
+            let full_path = Path::new(&self.base_dir).join(path);
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(full_path, &files[path])?;
+        }
+        Ok(changed_files)
+    }
+
+    pub fn remove_files(&self, paths: &[PathBuf]) -> io::Result<()> {
+        for path in paths {
+            fs::remove_file(Path::new(&self.base_dir).join(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the contents of a file as if they were at `path`, so that clang-format finds the
+/// configuration of its directory.
+fn clang_format_file(clang_format: &str, path: &Path, contents: &str) -> io::Result<String> {
+    let mut child = Command::new(clang_format)
+        .arg(format!("--assume-filename={}", path.display()))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| {
+            io::Error::new(
+                error.kind(),
+                format!("Unable to run {clang_format}: {error}"),
+            )
+        })?;
+
+    // Write from another thread so that a full output pipe can't block us.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = contents.to_owned();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer
+        .join()
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "Unable to write to clang-format"))??;
+
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "{clang_format} failed on {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    /// Returns an empty directory for the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("bb_pil_file_writer_{name}_{}", process::id()));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn bb_files(dir: &Path) -> BBFiles {
+        BBFiles::new("test_vm", Some(dir.to_str().unwrap()), None)
+    }
+
+    #[test]
+    fn writes_only_changed_files() {
+        let dir = test_dir("changed");
+        fs::write(dir.join("same.hpp"), "same").unwrap();
+        fs::write(dir.join("changed.hpp"), "old").unwrap();
+
+        let files = bb_files(&dir);
+        files.write_file(None, "same.hpp", "same");
+        files.write_file(None, "changed.hpp", "new");
+        files.write_file(Some("relations"), "added.hpp", "added");
+
+        let changed = vec![
+            PathBuf::from("changed.hpp"),
+            Path::new("relations").join("added.hpp"),
+        ];
+        assert_eq!(files.changed_files(), changed);
+        assert_eq!(files.write_changed_files().unwrap(), changed);
+        assert_eq!(fs::read_to_string(dir.join("changed.hpp")).unwrap(), "new");
+        assert_eq!(
+            fs::read_to_string(dir.join("relations").join("added.hpp")).unwrap(),
+            "added"
+        );
+        assert!(files.changed_files().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removes_stale_files() {
+        let dir = test_dir("stale");
+        fs::create_dir_all(dir.join("relations")).unwrap();
+        fs::write(dir.join("kept.hpp"), "").unwrap();
+        fs::write(dir.join("stale.cpp"), "").unwrap();
+        fs::write(dir.join("relations").join("stale.hpp"), "").unwrap();
+
+        let files = bb_files(&dir);
+        files.write_file(None, "kept.hpp", "");
+
+        let stale = vec![
+            Path::new("relations").join("stale.hpp"),
+            PathBuf::from("stale.cpp"),
+        ];
+        assert_eq!(files.stale_files().unwrap(), stale);
+        files.remove_files(&stale).unwrap();
+        assert!(files.stale_files().unwrap().is_empty());
+        assert!(dir.join("kept.hpp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_file_is_changed_in_a_missing_directory() {
+        let dir = test_dir("missing");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let files = bb_files(&dir);
+        files.write_file(None, "flavor.hpp", "");
+        assert!(files.stale_files().unwrap().is_empty());
+        assert_eq!(files.changed_files(), vec![PathBuf::from("flavor.hpp")]);
+    }
 
-        let joined = base_path.join(filename);
-        let mut file = File::create(joined).unwrap();
-        file.write_all(contents.as_bytes()).unwrap();
+    /// `analyzed_to_cpp` writes unformatted files when clang-format isn't installed.
+    #[test]
+    fn reports_missing_clang_format_as_not_found() {
+        env::set_var("CLANG_FORMAT", "clang-format-which-does-not-exist");
+        let files = bb_files(Path::new("unused"));
+        files.write_file(None, "flavor.hpp", "");
+        let error = files.format_cpp_files().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
     }
 }
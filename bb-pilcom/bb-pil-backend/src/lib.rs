@@ -1,12 +1,16 @@
 mod circuit_builder;
 mod composer_builder;
+pub mod degree_analysis;
 mod expression_evaluation;
 mod file_writer;
 mod flavor_builder;
+pub mod lints;
 pub mod lookup_builder;
 pub mod permutation_builder;
 mod prover_builder;
 mod relation_builder;
+mod rust_builder;
+pub mod trace_checker;
 mod utils;
 mod verifier_builder;
 pub mod vm_builder;
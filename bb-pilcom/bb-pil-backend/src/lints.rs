@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use itertools::Itertools;
+use powdr_ast::analyzed::{
+    AlgebraicBinaryOperation, AlgebraicExpression, AlgebraicUnaryOperation, Analyzed, Identity,
+    IdentityKind,
+};
+use powdr_number::FieldElement;
+
+use crate::relation_builder::{get_shifted_polys, get_subrelations, group_relations_per_file};
+use crate::utils::sanitize_name;
+
+/// A suspicious construct of a PIL program.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lint {
+    /// A witness column which appears in no identity, lookup or permutation
+    UnconstrainedColumn(String),
+    /// A column shifted in a lookup or permutation, where shifts are ignored, but never in a
+    /// relation
+    UnusedShift(String),
+    /// A lookup or permutation whose selector appears in no relation, so nothing constrains it
+    UntoggledSelector { identity: String, selector: String },
+    /// An identity equal to an earlier one
+    DuplicatedIdentity { identity: String, original: String },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::UnconstrainedColumn(column) => {
+                write!(f, "Witness column {column} is never constrained")
+            }
+            Lint::UnusedShift(column) => write!(
+                f,
+                "Column {column} is shifted, but its shift is never used by a relation"
+            ),
+            Lint::UntoggledSelector { identity, selector } => write!(
+                f,
+                "The selector {selector} of {identity} is not toggled by any relation"
+            ),
+            Lint::DuplicatedIdentity { identity, original } => {
+                write!(f, "{identity} duplicates {original}")
+            }
+        }
+    }
+}
+
+/// Checks the identities of a PIL program for unconstrained columns, unused shifts, untoggled
+/// selectors and duplicated identities. Aliases are inlined, so a column used by an alias counts as
+/// used by the identities using the alias.
+pub fn lint<F: FieldElement>(analyzed: &Analyzed<F>) -> Vec<Lint> {
+    let mut identities = analyzed.identities_with_inlined_intermediate_polynomials();
+    identities.sort_by(|a, b| a.id.cmp(&b.id));
+    let names = get_identity_names(&identities);
+
+    let mut lints = Vec::new();
+
+    // Witness columns which appear in no identity
+    let mut used_columns = HashSet::new();
+    for identity in &identities {
+        for expression in identity_expressions(identity) {
+            collect_columns(expression, &mut used_columns);
+        }
+    }
+    lints.extend(
+        analyzed
+            .committed_polys_in_source_order()
+            .into_iter()
+            .map(|(sym, _)| &sym.absolute_name)
+            .filter(|name| !used_columns.contains(*name))
+            .map(|name| Lint::UnconstrainedColumn(sanitize_name(name))),
+    );
+
+    // Shifts only used where they are ignored. Lookups and permutations read their selectors and
+    // tuples unshifted, so only the relations read shifts.
+    let relation_selectors = identities
+        .iter()
+        .filter(|identity| identity.kind == IdentityKind::Polynomial)
+        .filter_map(|identity| identity.left.selector.clone())
+        .collect_vec();
+    let relation_shifts: HashSet<String> = get_shifted_polys(relation_selectors.clone())
+        .into_iter()
+        .collect();
+    let all_shifts = get_shifted_polys(
+        identities
+            .iter()
+            .flat_map(identity_expressions)
+            .cloned()
+            .collect_vec(),
+    );
+    lints.extend(
+        all_shifts
+            .into_iter()
+            .filter(|name| !relation_shifts.contains(name))
+            .sorted()
+            .map(|name| Lint::UnusedShift(sanitize_name(&name))),
+    );
+
+    // Selectors of lookups and permutations which no relation constrains. Selectors made of fixed
+    // columns are set by the fixed columns themselves.
+    let mut relation_columns = HashSet::new();
+    for selector in &relation_selectors {
+        collect_columns(selector, &mut relation_columns);
+    }
+    let committed_columns: HashSet<&String> = analyzed
+        .committed_polys_in_source_order()
+        .into_iter()
+        .map(|(sym, _)| &sym.absolute_name)
+        .collect();
+    for identity in &identities {
+        if !matches!(
+            identity.kind,
+            IdentityKind::Plookup | IdentityKind::Permutation
+        ) {
+            continue;
+        }
+        for selector in [&identity.left.selector, &identity.right.selector]
+            .into_iter()
+            .flatten()
+        {
+            let mut selector_columns = HashSet::new();
+            collect_columns(selector, &mut selector_columns);
+            let untoggled = selector_columns
+                .iter()
+                .all(|column| committed_columns.contains(column))
+                && selector_columns.is_disjoint(&relation_columns);
+            if untoggled && !selector_columns.is_empty() {
+                lints.push(Lint::UntoggledSelector {
+                    identity: names[&identity.id].clone(),
+                    selector: selector_columns
+                        .iter()
+                        .map(|column| sanitize_name(column))
+                        .sorted()
+                        .join(", "),
+                });
+            }
+        }
+    }
+
+    // Identities equal to an earlier one
+    let mut originals: HashMap<String, u64> = HashMap::new();
+    for identity in &identities {
+        let key = format!(
+            "{:?} {:?} {:?}",
+            identity.kind, identity.left, identity.right
+        );
+        match originals.get(&key) {
+            Some(original) => lints.push(Lint::DuplicatedIdentity {
+                identity: names[&identity.id].clone(),
+                original: names[original].clone(),
+            }),
+            None => {
+                originals.insert(key, identity.id);
+            }
+        }
+    }
+
+    lints
+}
+
+/// Names identities the way the generated code does: polynomial identities by relation and
+/// sub-relation index, lookups and permutations by their attribute.
+fn get_identity_names<F: FieldElement>(
+    identities: &[Identity<AlgebraicExpression<F>>],
+) -> HashMap<u64, String> {
+    let mut names = HashMap::new();
+    for (relation, relation_identities) in group_relations_per_file(identities) {
+        for (subrelation, identity) in get_subrelations(&relation_identities)
+            .into_iter()
+            .enumerate()
+        {
+            let mut name = format!("subrelation {subrelation} of relation {relation}");
+            if let Some(label) = &identity.attribute {
+                name = format!("{name} ({label})");
+            }
+            names.insert(identity.id, name);
+        }
+    }
+    for identity in identities {
+        let kind = match identity.kind {
+            IdentityKind::Plookup => "lookup",
+            IdentityKind::Permutation => "permutation",
+            _ => "identity",
+        };
+        let name = match &identity.attribute {
+            Some(attribute) => format!("{kind} {}", attribute.to_lowercase()),
+            None => format!("{kind} #{}", identity.id),
+        };
+        names.entry(identity.id).or_insert(name);
+    }
+    names
+}
+
+fn identity_expressions<F: FieldElement>(
+    identity: &Identity<AlgebraicExpression<F>>,
+) -> impl Iterator<Item = &AlgebraicExpression<F>> {
+    [&identity.left, &identity.right]
+        .into_iter()
+        .flat_map(|side| side.selector.iter().chain(side.expressions.iter()))
+}
+
+/// Collects the names of the columns referenced by an expression, shifted or not.
+fn collect_columns<F: FieldElement>(
+    expression: &AlgebraicExpression<F>,
+    columns: &mut HashSet<String>,
+) {
+    match expression {
+        AlgebraicExpression::Reference(polyref) => {
+            columns.insert(polyref.name.clone());
+        }
+        AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation { left, right, .. }) => {
+            collect_columns(left, columns);
+            collect_columns(right, columns);
+        }
+        AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation { expr, .. }) => {
+            collect_columns(expr, columns);
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use powdr_number::Bn254Field;
+    use powdr_pil_analyzer::analyze_file;
+
+    use super::*;
+
+    /// A relation where `b` accumulates `a` while `sel` is set, along with a lookup of `a` in a
+    /// table. It has no lints.
+    const PIL: &str = "
+namespace main(4);
+    pol commit sel;
+    pol commit a;
+    pol commit b;
+    pol commit sel_table;
+    pol commit table;
+
+    sel * (1 - sel) = 0;
+    sel * (b' - b - a) = 0;
+    sel_table * (1 - sel_table) = 0;
+
+    #[LOOKUP_A]
+    sel {a} in sel_table {table};
+";
+
+    /// Lints `PIL` followed by `extra`, from a file named `main.pil` so that its relation is named
+    /// `main`.
+    fn lint_with(test_name: &str, extra: &str) -> Vec<Lint> {
+        let dir = env::temp_dir().join(format!("bb_pil_lints_{test_name}_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.pil");
+        fs::write(&path, format!("{PIL}{extra}")).unwrap();
+        let analyzed = analyze_file::<Bn254Field>(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        lint(&analyzed)
+    }
+
+    #[test]
+    fn finds_no_lints() {
+        assert_eq!(lint_with("none", ""), vec![]);
+    }
+
+    #[test]
+    fn finds_unconstrained_column() {
+        assert_eq!(
+            lint_with("unconstrained", "    pol commit unused;\n"),
+            vec![Lint::UnconstrainedColumn("main_unused".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_shift_in_lookup_selector() {
+        assert_eq!(
+            lint_with(
+                "shift_in_selector",
+                "    #[LOOKUP_A_NEXT_SEL]\n    sel' {a} in sel_table {table};\n"
+            ),
+            vec![Lint::UnusedShift("main_sel".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_shift_in_lookup_tuple() {
+        assert_eq!(
+            lint_with(
+                "shift_in_lookup",
+                "    #[LOOKUP_NEXT_A]\n    sel {a'} in sel_table {table};\n"
+            ),
+            vec![Lint::UnusedShift("main_a".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_shift_in_permutation_tuple() {
+        assert_eq!(
+            lint_with(
+                "shift_in_permutation",
+                "    #[PERM_NEXT_A]\n    sel {a'} is sel_table {table};\n"
+            ),
+            vec![Lint::UnusedShift("main_a".to_string())]
+        );
+    }
+
+    #[test]
+    fn ignores_shift_in_lookup_tuple_used_by_relation() {
+        assert_eq!(
+            lint_with(
+                "shift_used",
+                "    #[LOOKUP_NEXT_B]\n    sel {b'} in sel_table {table};\n"
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn finds_untoggled_selector() {
+        assert_eq!(
+            lint_with(
+                "untoggled",
+                "    pol commit sel_c;\n    pol commit c;\n    #[PERM_C_A]\n    sel_c {c} is sel {a};\n"
+            ),
+            vec![Lint::UntoggledSelector {
+                identity: "permutation perm_c_a".to_string(),
+                selector: "main_sel_c".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_duplicated_identity() {
+        assert_eq!(
+            lint_with(
+                "duplicated",
+                "    #[SEL_BOOLEAN_AGAIN]\n    sel * (1 - sel) = 0;\n"
+            ),
+            vec![Lint::DuplicatedIdentity {
+                identity: "subrelation 3 of relation main (SEL_BOOLEAN_AGAIN)".to_string(),
+                original: "subrelation 0 of relation main".to_string(),
+            }]
+        );
+    }
+}
@@ -38,10 +38,13 @@ pub trait RelationBuilder {
     /// Relation output is passed back to the caller as the prover requires both:
     /// - The shifted polys
     /// - The names of the relations files created
+    ///
+    /// The identities are usually the ones of the ast, unless they were split to bound their degree
     fn create_relations<F: FieldElement>(
         &self,
         root_name: &str,
         analyzed: &Analyzed<F>,
+        identities: &[Identity<AlgebraicExpression<F>>],
     ) -> Vec<String>;
 
     /// Create Relation
@@ -68,9 +71,10 @@ impl RelationBuilder for BBFiles {
         &self,
         file_name: &str,
         analyzed: &Analyzed<F>,
+        identities: &[Identity<AlgebraicExpression<F>>],
     ) -> Vec<String> {
         // These identities' terminal objects are either fields, columns, or alias expressions.
-        let mut analyzed_identities = analyzed.identities.clone();
+        let mut analyzed_identities = identities.to_vec();
         analyzed_identities.sort_by(|a, b| a.id.cmp(&b.id));
 
         let alias_polys_in_order = get_alias_polys_in_order(analyzed);
@@ -194,7 +198,7 @@ impl RelationBuilder for BBFiles {
 /// }
 ///
 /// This allows us to generate a relation.hpp file containing ONLY the relations for that .pil file
-fn group_relations_per_file<F: FieldElement>(
+pub(crate) fn group_relations_per_file<F: FieldElement>(
     identities: &[Identity<AlgebraicExpression<F>>],
 ) -> HashMap<String, Vec<Identity<AlgebraicExpression<F>>>> {
     identities.iter().cloned().into_group_map_by(|identity| {
@@ -209,6 +213,20 @@ fn group_relations_per_file<F: FieldElement>(
     })
 }
 
+/// Get Subrelations
+///
+/// Returns the polynomial identities of a relation file, in the order of their sub-relation index
+/// in the generated relation. The `skippable_if` condition is not a sub-relation.
+pub(crate) fn get_subrelations<F: FieldElement>(
+    identities: &[Identity<AlgebraicExpression<F>>],
+) -> Vec<&Identity<AlgebraicExpression<F>>> {
+    identities
+        .iter()
+        .filter(|identity| identity.kind == IdentityKind::Polynomial)
+        .filter(|identity| identity.attribute.as_deref() != Some("skippable_if"))
+        .collect()
+}
+
 fn create_identity<F: FieldElement>(
     expression: &SelectedExpressions<AlgebraicExpression<F>>,
     collected_aliases: &mut HashSet<String>,
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+
+use handlebars::Handlebars;
+use itertools::Itertools;
+use num_bigint::BigInt;
+use powdr_ast::analyzed::{AlgebraicExpression, AlgebraicReference, Analyzed, Identity};
+use powdr_number::FieldElement;
+use serde_json::{json, Value};
+
+use crate::expression_evaluation::{fold_expression, get_alias_polys_in_order, ExpressionFolder};
+use crate::file_writer::BBFiles;
+use crate::relation_builder::{get_subrelations, group_relations_per_file};
+use crate::utils::{sanitize_name, snake_case};
+
+pub trait RustBuilder {
+    /// Create Rust Trace
+    ///
+    /// Creates a rust module with a `Row` struct holding every column, including shifts, inverses
+    /// and lookup counts, the index of each column, and an evaluation function for each relation.
+    /// Columns are in the same order as in `columns.hpp`, so rust tooling can build and check
+    /// traces with the same schema as the cpp code.
+    ///
+    /// Returns an error if a relation uses an expression which can't be evaluated in rust.
+    fn create_rust_trace<F: FieldElement>(
+        &self,
+        name: &str,
+        analyzed: &Analyzed<F>,
+        identities: &[Identity<AlgebraicExpression<F>>],
+        all_cols_and_shifts: &[String],
+        to_be_shifted: &[String],
+    ) -> Result<(), String>;
+}
+
+impl RustBuilder for BBFiles {
+    fn create_rust_trace<F: FieldElement>(
+        &self,
+        name: &str,
+        analyzed: &Analyzed<F>,
+        identities: &[Identity<AlgebraicExpression<F>>],
+        all_cols_and_shifts: &[String],
+        to_be_shifted: &[String],
+    ) -> Result<(), String> {
+        let aliases = get_alias_polys_in_order(analyzed)
+            .into_iter()
+            .map(|(sym, expr)| (&sym.absolute_name, expr))
+            .collect::<HashMap<_, _>>();
+
+        let mut sorted_identities = identities.to_vec();
+        sorted_identities.sort_by(|a, b| a.id.cmp(&b.id));
+        let relations = group_relations_per_file(&sorted_identities)
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(relation_name, relation_identities)| {
+                relation_json(&relation_name, &relation_identities, &aliases)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let trace_rs = render_rust_trace(name, &relations, all_cols_and_shifts, to_be_shifted);
+        self.write_file(Some("rust"), "trace.rs", &trace_rs);
+        Ok(())
+    }
+}
+
+/// The name, sub-relation evaluations and labels of a relation, as rendered by the template.
+fn relation_json<F: FieldElement>(
+    relation_name: &str,
+    relation_identities: &[Identity<AlgebraicExpression<F>>],
+    aliases: &HashMap<&String, &AlgebraicExpression<F>>,
+) -> Result<Value, String> {
+    let subrelations = get_subrelations(relation_identities);
+    let expressions = subrelations
+        .iter()
+        .map(|identity| {
+            let selector = identity
+                .left
+                .selector
+                .as_ref()
+                .expect("identities have a selector");
+            rust_expression(selector, aliases)
+                .map_err(|error| format!("{error} in relation {relation_name}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(json!({
+        "name": snake_case(relation_name),
+        "subrelations": expressions,
+        "labels": subrelations.iter().enumerate().filter_map(|(index, identity)| {
+            identity.attribute.as_ref().map(|label| json!({
+                "index": index,
+                "name": label,
+            }))
+        }).collect_vec(),
+    }))
+}
+
+/// Renders the rust trace module from the evaluations of the sub-relations of each relation.
+fn render_rust_trace(
+    name: &str,
+    relations: &[Value],
+    all_cols_and_shifts: &[String],
+    to_be_shifted: &[String],
+) -> String {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(|s| s.to_string()); // No escaping
+
+    let data = &json!({
+        "name": name,
+        "all_cols_and_shifts": all_cols_and_shifts,
+        "to_be_shifted": to_be_shifted,
+        "relations": relations,
+    });
+
+    handlebars
+        .register_template_string(
+            "trace.rs",
+            std::str::from_utf8(include_bytes!("../templates/trace.rs.hbs")).unwrap(),
+        )
+        .unwrap();
+
+    handlebars.render("trace.rs", data).unwrap()
+}
+
+/// Renders an expression as rust code over a `row: &Row<FF>`, with its aliases inlined.
+fn rust_expression<F: FieldElement>(
+    expr: &AlgebraicExpression<F>,
+    aliases: &HashMap<&String, &AlgebraicExpression<F>>,
+) -> Result<String, String> {
+    fold_expression(expr, &mut RustExpressionFolder { aliases })
+}
+
+struct RustExpressionFolder<'a, F> {
+    aliases: &'a HashMap<&'a String, &'a AlgebraicExpression<F>>,
+}
+
+impl<F: FieldElement> ExpressionFolder<F> for RustExpressionFolder<'_, F> {
+    type Output = String;
+
+    fn number(&mut self, number: &F) -> Result<String, String> {
+        Ok(rust_field(number))
+    }
+
+    fn reference(&mut self, polyref: &AlgebraicReference) -> Result<String, String> {
+        let aliases = self.aliases;
+        if let Some(alias_expr) = aliases.get(&polyref.name) {
+            return fold_expression(*alias_expr, self);
+        }
+        let mut poly_name = sanitize_name(&polyref.name);
+        if polyref.next {
+            poly_name = format!("{}_shift", poly_name);
+        }
+        Ok(format!("row.{}.clone()", poly_name))
+    }
+
+    fn add(&mut self, left: String, right: String) -> String {
+        format!("({} + {})", left, right)
+    }
+
+    fn sub(&mut self, left: String, right: String) -> String {
+        format!("({} - {})", left, right)
+    }
+
+    fn mul(&mut self, left: String, right: String) -> String {
+        format!("({} * {})", left, right)
+    }
+
+    fn neg(&mut self, expression: String) -> String {
+        format!("(-{})", expression)
+    }
+}
+
+fn rust_field<F: FieldElement>(n: &F) -> String {
+    let number: BigInt = BigInt::from_bytes_le(num_bigint::Sign::Plus, &n.to_bytes_le());
+    if number.bits() < 64 {
+        format!("FF::from({}u64)", number)
+    } else {
+        format!("FF::from_hex(\"{}\")", number.to_str_radix(16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use powdr_ast::analyzed::{
+        AlgebraicBinaryOperation, AlgebraicBinaryOperator, AlgebraicUnaryOperation,
+        AlgebraicUnaryOperator, PolyID, PolynomialType,
+    };
+    use powdr_number::Bn254Field;
+
+    use super::*;
+
+    /// Appended to the generated module to check it with a small prime field.
+    const GENERATED_TRACE_TEST: &str = r#"
+#[cfg(test)]
+mod generated_trace_test {
+    use super::*;
+
+    const P: u64 = 101;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Fp(u64);
+
+    impl From<u64> for Fp {
+        fn from(value: u64) -> Fp {
+            Fp(value % P)
+        }
+    }
+
+    impl Add for Fp {
+        type Output = Fp;
+        fn add(self, other: Fp) -> Fp {
+            Fp((self.0 + other.0) % P)
+        }
+    }
+
+    impl Sub for Fp {
+        type Output = Fp;
+        fn sub(self, other: Fp) -> Fp {
+            Fp((self.0 + P - other.0) % P)
+        }
+    }
+
+    impl Mul for Fp {
+        type Output = Fp;
+        fn mul(self, other: Fp) -> Fp {
+            Fp(self.0 * other.0 % P)
+        }
+    }
+
+    impl Neg for Fp {
+        type Output = Fp;
+        fn neg(self) -> Fp {
+            Fp((P - self.0) % P)
+        }
+    }
+
+    impl Field for Fp {
+        fn from_hex(hex: &str) -> Fp {
+            Fp(u64::from_str_radix(hex, 16).unwrap() % P)
+        }
+    }
+
+    fn row(sel: u64, a: u64, b: u64) -> Row<Fp> {
+        Row { main_sel: Fp(sel), main_a: Fp(a), main_b: Fp(b), ..Default::default() }
+    }
+
+    #[test]
+    fn evaluates_the_relations() {
+        assert_eq!(COLUMN_NAMES, ["main_sel", "main_a", "main_b", "main_b_shift"]);
+        assert_eq!(TO_BE_SHIFTED, [(MAIN_B, MAIN_B_SHIFT)]);
+        assert_eq!(main::SUBRELATION_LABELS, [(1, "SEL_BOOLEAN")]);
+
+        let mut rows = vec![row(1, 1, 1), row(1, 1, 2), row(0, 2, 3)];
+        set_shifts(&mut rows);
+        assert_eq!(rows[0].main_b_shift, Fp(2));
+        assert_eq!(rows[2].main_b_shift, Fp(0));
+        assert_eq!(rows[1].as_vector(), vec![Fp(1), Fp(1), Fp(2), Fp(3)]);
+        for row in &rows {
+            assert_eq!(evaluate_relation("main", row), Some(vec![Fp(0); 3]));
+        }
+
+        let mut failing_row = rows[1].clone();
+        *failing_row.get_column_mut(MAIN_B_SHIFT) = Fp(7);
+        assert_eq!(evaluate_relation("main", &failing_row), Some(vec![Fp(4), Fp(0), Fp(0)]));
+        assert_eq!(evaluate_relation("unknown", &failing_row), None);
+    }
+}
+"#;
+
+    fn reference(name: &str, id: u64, next: bool) -> AlgebraicExpression<Bn254Field> {
+        AlgebraicExpression::Reference(AlgebraicReference {
+            name: name.to_string(),
+            poly_id: PolyID {
+                id,
+                ptype: PolynomialType::Committed,
+            },
+            next,
+        })
+    }
+
+    fn binary(
+        left: AlgebraicExpression<Bn254Field>,
+        op: AlgebraicBinaryOperator,
+        right: AlgebraicExpression<Bn254Field>,
+    ) -> AlgebraicExpression<Bn254Field> {
+        AlgebraicExpression::BinaryOperation(AlgebraicBinaryOperation {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    /// Renders the trace of a VM whose `main.b` column is the sum of `main.a` and `main.b` at the
+    /// previous row, when `main.sel` is set.
+    fn render_test_trace() -> String {
+        let sel = || reference("main.sel", 0, false);
+        let a = || reference("main.a", 1, false);
+        let b = || reference("main.b", 2, false);
+        let sum_name = "main.sum".to_string();
+        let sum = binary(a(), AlgebraicBinaryOperator::Add, b());
+        let aliases = HashMap::from([(&sum_name, &sum)]);
+
+        let next_b = binary(
+            reference("main.b", 2, true),
+            AlgebraicBinaryOperator::Sub,
+            reference("main.sum", 3, false),
+        );
+        let sel_boolean = binary(
+            binary(sel(), AlgebraicBinaryOperator::Mul, sel()),
+            AlgebraicBinaryOperator::Sub,
+            sel(),
+        );
+        let negation = binary(
+            binary(
+                AlgebraicExpression::UnaryOperation(AlgebraicUnaryOperation {
+                    op: AlgebraicUnaryOperator::Minus,
+                    expr: Box::new(a()),
+                }),
+                AlgebraicBinaryOperator::Add,
+                a(),
+            ),
+            AlgebraicBinaryOperator::Add,
+            AlgebraicExpression::Number(Bn254Field::zero()),
+        );
+        let subrelations = [
+            binary(sel(), AlgebraicBinaryOperator::Mul, next_b),
+            sel_boolean,
+            negation,
+        ]
+        .iter()
+        .map(|expression| rust_expression(expression, &aliases).unwrap())
+        .collect_vec();
+        let relations = [json!({
+            "name": "main",
+            "subrelations": subrelations,
+            "labels": [{ "index": 1, "name": "SEL_BOOLEAN" }],
+        })];
+
+        let columns = ["main_sel", "main_a", "main_b", "main_b_shift"].map(String::from);
+        render_rust_trace("Test", &relations, &columns, &["main_b".to_string()])
+    }
+
+    #[test]
+    fn renders_expressions_with_inlined_aliases() {
+        let sum_name = "main.sum".to_string();
+        let sum = binary(
+            reference("main.a", 0, false),
+            AlgebraicBinaryOperator::Mul,
+            reference("main.b", 1, true),
+        );
+        let aliases = HashMap::from([(&sum_name, &sum)]);
+        let expression = binary(
+            reference("main.sum", 2, false),
+            AlgebraicBinaryOperator::Sub,
+            AlgebraicExpression::Number(Bn254Field::zero()),
+        );
+        assert_eq!(
+            rust_expression(&expression, &aliases),
+            Ok("((row.main_a.clone() * row.main_b_shift.clone()) - FF::from(0u64))".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_operators() {
+        let power = binary(
+            reference("main.a", 0, false),
+            AlgebraicBinaryOperator::Pow,
+            reference("main.b", 1, false),
+        );
+        assert!(rust_expression(&power, &HashMap::new()).is_err());
+    }
+
+    /// Compiles the generated trace module along with tests using it, and runs them.
+    #[test]
+    fn generated_trace_compiles_and_evaluates_relations() {
+        let dir = std::env::temp_dir().join(format!("bb_pil_rust_trace_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("trace.rs");
+        let binary = dir.join("trace_test");
+        fs::write(&source, render_test_trace() + GENERATED_TRACE_TEST).unwrap();
+
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let compiled = Command::new(rustc)
+            .args(["--edition", "2021", "--test", "-o"])
+            .arg(&binary)
+            .arg(&source)
+            .status()
+            .unwrap();
+        assert!(compiled.success(), "The generated trace doesn't compile");
+        let tested = Command::new(&binary).status().unwrap();
+        assert!(tested.success(), "The tests of the generated trace fail");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
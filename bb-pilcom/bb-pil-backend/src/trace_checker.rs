@@ -0,0 +1,697 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use itertools::Itertools;
+use powdr_ast::analyzed::{
+    AlgebraicExpression, AlgebraicReference, Analyzed, Identity, IdentityKind,
+};
+use powdr_ast::parsed::SelectedExpressions;
+use powdr_number::FieldElement;
+
+use crate::expression_evaluation::{fold_expression, ExpressionFolder};
+use crate::relation_builder::{get_subrelations, group_relations_per_file};
+use crate::utils::sanitize_name;
+
+/// Number of failing rows listed for each failing relation
+const MAX_REPORTED_ROWS: usize = 10;
+
+/// A witness trace: the values of each column, indexed by row.
+/// Column names are sanitized the same way as in the generated C++ code, e.g. `main_sel_op_add`.
+pub struct Trace<F> {
+    column_indices: HashMap<String, usize>,
+    columns: Vec<Vec<F>>,
+    num_rows: usize,
+}
+
+impl<F: FieldElement> Trace<F> {
+    pub fn new(columns: Vec<(String, Vec<F>)>) -> Result<Self, String> {
+        let num_rows = columns.first().map_or(0, |(_, values)| values.len());
+        if let Some((name, values)) = columns.iter().find(|(_, values)| values.len() != num_rows) {
+            return Err(format!(
+                "Column {} has {} rows, but column {} has {num_rows}",
+                name,
+                values.len(),
+                columns[0].0
+            ));
+        }
+        let column_indices = columns
+            .iter()
+            .enumerate()
+            .map(|(index, (name, _))| (sanitize_name(name), index))
+            .collect();
+        let columns = columns.into_iter().map(|(_, values)| values).collect();
+        Ok(Trace {
+            column_indices,
+            columns,
+            num_rows,
+        })
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Value of a column at a row, or of the next row if `next` is set.
+    /// The shift of the last row is zero, as for shifted polynomials in barretenberg.
+    fn value(&self, column: usize, row: usize, next: bool) -> F {
+        let row = if next { row + 1 } else { row };
+        self.columns[column]
+            .get(row)
+            .copied()
+            .unwrap_or_else(F::zero)
+    }
+}
+
+/// Reads a trace from a JSON file holding an object mapping column names to arrays of values, or
+/// from a CSV file with a header row of column names. Values are decimal or `0x`-prefixed hex.
+pub fn read_trace<F: FieldElement>(path: &Path) -> Result<Trace<F>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Unable to read {}: {error}", path.display()))?;
+    let columns = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        parse_json_trace(&contents)?
+    } else {
+        parse_csv_trace(&contents)?
+    };
+    Trace::new(columns)
+}
+
+fn parse_json_trace<F: FieldElement>(contents: &str) -> Result<Vec<(String, Vec<F>)>, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(contents).map_err(|error| format!("Invalid JSON trace: {error}"))?;
+    let object = json
+        .as_object()
+        .ok_or("A JSON trace should be an object mapping column names to values")?;
+    object
+        .iter()
+        .map(|(name, values)| {
+            let values = values
+                .as_array()
+                .ok_or_else(|| format!("Column {name} should be an array of values"))?
+                .iter()
+                .map(|value| match value {
+                    serde_json::Value::String(value) => parse_field(value),
+                    serde_json::Value::Number(value) => parse_field(&value.to_string()),
+                    _ => Err(format!("Invalid value in column {name}: {value}")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((name.clone(), values))
+        })
+        .collect()
+}
+
+fn parse_csv_trace<F: FieldElement>(contents: &str) -> Result<Vec<(String, Vec<F>)>, String> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or("A CSV trace should start with a header of column names")?;
+    let mut columns = header
+        .split(',')
+        .map(|name| (name.trim().to_string(), Vec::new()))
+        .collect_vec();
+    for (row, line) in lines.enumerate() {
+        let values = line.split(',').collect_vec();
+        if values.len() != columns.len() {
+            return Err(format!(
+                "Row {row} of the CSV trace has {} values, but there are {} columns",
+                values.len(),
+                columns.len()
+            ));
+        }
+        for ((_, column), value) in columns.iter_mut().zip(values) {
+            column.push(parse_field(value.trim())?);
+        }
+    }
+    Ok(columns)
+}
+
+fn parse_field<F: FieldElement>(value: &str) -> Result<F, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => F::from_str_radix(hex, 16),
+        None => F::from_str_radix(value, 10),
+    }
+    .map_err(|error| format!("Invalid field element {value}: {error}"))
+}
+
+/// A relation which doesn't hold on a trace.
+#[derive(Debug)]
+pub enum TraceFailure {
+    /// A polynomial identity isn't zero
+    Relation {
+        /// Name of the relation, i.e. the name of the PIL file declaring the identity
+        relation: String,
+        /// Index of the identity within the relation, as in the generated C++ code
+        subrelation: usize,
+        label: Option<String>,
+        rows: FailingRows,
+    },
+    /// Values of the left side of a lookup aren't found on its right side
+    Lookup { name: String, rows: FailingRows },
+    /// Values of one side of a permutation have no match on the other side
+    Permutation {
+        name: String,
+        unmatched_left_rows: FailingRows,
+        unmatched_right_rows: FailingRows,
+    },
+}
+
+/// The first rows at which a relation fails, along with the number of failing rows.
+#[derive(Debug, Default)]
+pub struct FailingRows {
+    pub first_rows: Vec<usize>,
+    pub count: usize,
+}
+
+impl FailingRows {
+    fn push(&mut self, row: usize) {
+        if self.first_rows.len() < MAX_REPORTED_ROWS {
+            self.first_rows.push(row);
+        }
+        self.count += 1;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+impl fmt::Display for FailingRows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first_rows.iter().join(", "))?;
+        if self.count > self.first_rows.len() {
+            write!(f, " and {} more", self.count - self.first_rows.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TraceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceFailure::Relation {
+                relation,
+                subrelation,
+                label,
+                rows,
+            } => {
+                write!(f, "Relation {relation}, subrelation {subrelation}")?;
+                if let Some(label) = label {
+                    write!(f, " ({label})")?;
+                }
+                write!(f, " fails at rows {rows}")
+            }
+            TraceFailure::Lookup { name, rows } => write!(
+                f,
+                "Lookup {name} fails at rows {rows}: the values aren't in the looked up columns"
+            ),
+            TraceFailure::Permutation {
+                name,
+                unmatched_left_rows,
+                unmatched_right_rows,
+            } => {
+                let mut unmatched_sides = Vec::new();
+                if !unmatched_left_rows.is_empty() {
+                    unmatched_sides.push(format!(
+                        "rows {unmatched_left_rows} of the left side have no match"
+                    ));
+                }
+                if !unmatched_right_rows.is_empty() {
+                    unmatched_sides.push(format!(
+                        "rows {unmatched_right_rows} of the right side have no match"
+                    ));
+                }
+                write!(
+                    f,
+                    "Permutation {name} fails: {}",
+                    unmatched_sides.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Checks every polynomial identity, lookup and permutation of a PIL program on a trace.
+/// Returns the relations which don't hold, or an error if the trace doesn't contain a column
+/// used by the program.
+pub fn check_trace<F: FieldElement>(
+    analyzed: &Analyzed<F>,
+    trace: &Trace<F>,
+) -> Result<Vec<TraceFailure>, String> {
+    // Intermediate polynomials are inlined, so identities only refer to columns of the trace.
+    let mut identities = analyzed.identities_with_inlined_intermediate_polynomials();
+    identities.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut failures = Vec::new();
+    let grouped_relations = group_relations_per_file(&identities);
+    for (relation, relation_identities) in grouped_relations.iter().sorted_by_key(|(name, _)| *name)
+    {
+        for (subrelation, identity) in get_subrelations(relation_identities)
+            .into_iter()
+            .enumerate()
+        {
+            let selector = identity
+                .left
+                .selector
+                .as_ref()
+                .expect("identities have a selector");
+            let expression = CompiledExpression::new(selector, trace)?;
+            let mut rows = FailingRows::default();
+            for row in 0..trace.num_rows() {
+                if expression.evaluate(trace, row) != F::zero() {
+                    rows.push(row);
+                }
+            }
+            if !rows.is_empty() {
+                failures.push(TraceFailure::Relation {
+                    relation: relation.clone(),
+                    subrelation,
+                    label: identity.attribute.clone(),
+                    rows,
+                });
+            }
+        }
+    }
+
+    for identity in &identities {
+        match identity.kind {
+            IdentityKind::Plookup => failures.extend(check_lookup(identity, trace)?),
+            IdentityKind::Permutation => failures.extend(check_permutation(identity, trace)?),
+            _ => (),
+        }
+    }
+    Ok(failures)
+}
+
+fn check_lookup<F: FieldElement>(
+    identity: &Identity<AlgebraicExpression<F>>,
+    trace: &Trace<F>,
+) -> Result<Option<TraceFailure>, String> {
+    let left = CompiledSide::new(&identity.left, None, trace)?;
+    let right = CompiledSide::new(&identity.right, None, trace)?;
+
+    let looked_up_values = right
+        .active_rows(trace)
+        .map(|(_, values)| values)
+        .collect::<HashSet<_>>();
+    let mut rows = FailingRows::default();
+    for (row, values) in left.active_rows(trace) {
+        if !looked_up_values.contains(&values) {
+            rows.push(row);
+        }
+    }
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(TraceFailure::Lookup {
+        name: identity_name(identity),
+        rows,
+    }))
+}
+
+fn check_permutation<F: FieldElement>(
+    identity: &Identity<AlgebraicExpression<F>>,
+    trace: &Trace<F>,
+) -> Result<Option<TraceFailure>, String> {
+    let left = CompiledSide::new(&identity.left, None, trace)?;
+    // As in the generated code, the right side defaults to the selector of the left side.
+    let right = CompiledSide::new(&identity.right, identity.left.selector.as_ref(), trace)?;
+
+    let mut unmatched_right: HashMap<Vec<Vec<u8>>, VecDeque<usize>> = HashMap::new();
+    for (row, values) in right.active_rows(trace) {
+        unmatched_right.entry(values).or_default().push_back(row);
+    }
+    let mut unmatched_left_rows = FailingRows::default();
+    for (row, values) in left.active_rows(trace) {
+        if unmatched_right
+            .get_mut(&values)
+            .and_then(VecDeque::pop_front)
+            .is_none()
+        {
+            unmatched_left_rows.push(row);
+        }
+    }
+    let mut unmatched_right_rows = FailingRows::default();
+    for row in unmatched_right.into_values().flatten().sorted() {
+        unmatched_right_rows.push(row);
+    }
+
+    if unmatched_left_rows.is_empty() && unmatched_right_rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(TraceFailure::Permutation {
+        name: identity_name(identity),
+        unmatched_left_rows,
+        unmatched_right_rows,
+    }))
+}
+
+fn identity_name<F>(identity: &Identity<AlgebraicExpression<F>>) -> String {
+    identity
+        .attribute
+        .clone()
+        .unwrap_or_else(|| format!("#{}", identity.id))
+        .to_lowercase()
+}
+
+/// One side of a lookup or permutation, with its columns resolved in the trace.
+struct CompiledSide<F> {
+    selector: Option<CompiledExpression<F>>,
+    expressions: Vec<CompiledExpression<F>>,
+}
+
+impl<F: FieldElement> CompiledSide<F> {
+    fn new(
+        side: &SelectedExpressions<AlgebraicExpression<F>>,
+        default_selector: Option<&AlgebraicExpression<F>>,
+        trace: &Trace<F>,
+    ) -> Result<Self, String> {
+        let selector = side
+            .selector
+            .as_ref()
+            .or(default_selector)
+            .map(|selector| CompiledExpression::new(selector, trace))
+            .transpose()?;
+        let expressions = side
+            .expressions
+            .iter()
+            .map(|expression| CompiledExpression::new(expression, trace))
+            .collect::<Result<_, _>>()?;
+        Ok(CompiledSide {
+            selector,
+            expressions,
+        })
+    }
+
+    /// Returns the rows at which the selector is set, along with the values of the side at these
+    /// rows, as bytes so that they can be hashed.
+    fn active_rows<'a>(
+        &'a self,
+        trace: &'a Trace<F>,
+    ) -> impl Iterator<Item = (usize, Vec<Vec<u8>>)> + 'a {
+        (0..trace.num_rows())
+            .filter(move |row| {
+                self.selector
+                    .as_ref()
+                    .map_or(true, |selector| selector.evaluate(trace, *row) != F::zero())
+            })
+            .map(move |row| {
+                let values = self
+                    .expressions
+                    .iter()
+                    .map(|expression| expression.evaluate(trace, row).to_bytes_le())
+                    .collect();
+                (row, values)
+            })
+    }
+}
+
+/// An algebraic expression whose column references are resolved to trace columns.
+enum CompiledExpression<F> {
+    Number(F),
+    Column { index: usize, next: bool },
+    Add(Box<CompiledExpression<F>>, Box<CompiledExpression<F>>),
+    Sub(Box<CompiledExpression<F>>, Box<CompiledExpression<F>>),
+    Mul(Box<CompiledExpression<F>>, Box<CompiledExpression<F>>),
+    Neg(Box<CompiledExpression<F>>),
+}
+
+impl<F: FieldElement> CompiledExpression<F> {
+    fn new(expression: &AlgebraicExpression<F>, trace: &Trace<F>) -> Result<Self, String> {
+        fold_expression(expression, &mut ExpressionCompiler { trace })
+    }
+
+    fn evaluate(&self, trace: &Trace<F>, row: usize) -> F {
+        match self {
+            CompiledExpression::Number(n) => *n,
+            CompiledExpression::Column { index, next } => trace.value(*index, row, *next),
+            CompiledExpression::Add(left, right) => {
+                left.evaluate(trace, row) + right.evaluate(trace, row)
+            }
+            CompiledExpression::Sub(left, right) => {
+                left.evaluate(trace, row) - right.evaluate(trace, row)
+            }
+            CompiledExpression::Mul(left, right) => {
+                left.evaluate(trace, row) * right.evaluate(trace, row)
+            }
+            CompiledExpression::Neg(expression) => -expression.evaluate(trace, row),
+        }
+    }
+}
+
+/// Resolves the column references of an expression to the columns of a trace.
+struct ExpressionCompiler<'a, F> {
+    trace: &'a Trace<F>,
+}
+
+impl<F: FieldElement> ExpressionFolder<F> for ExpressionCompiler<'_, F> {
+    type Output = CompiledExpression<F>;
+
+    fn number(&mut self, number: &F) -> Result<CompiledExpression<F>, String> {
+        Ok(CompiledExpression::Number(*number))
+    }
+
+    fn reference(&mut self, polyref: &AlgebraicReference) -> Result<CompiledExpression<F>, String> {
+        let name = sanitize_name(&polyref.name);
+        let index = *self
+            .trace
+            .column_indices
+            .get(&name)
+            .ok_or_else(|| format!("Column {name} is missing from the trace"))?;
+        Ok(CompiledExpression::Column {
+            index,
+            next: polyref.next,
+        })
+    }
+
+    fn add(
+        &mut self,
+        left: CompiledExpression<F>,
+        right: CompiledExpression<F>,
+    ) -> CompiledExpression<F> {
+        CompiledExpression::Add(Box::new(left), Box::new(right))
+    }
+
+    fn sub(
+        &mut self,
+        left: CompiledExpression<F>,
+        right: CompiledExpression<F>,
+    ) -> CompiledExpression<F> {
+        CompiledExpression::Sub(Box::new(left), Box::new(right))
+    }
+
+    fn mul(
+        &mut self,
+        left: CompiledExpression<F>,
+        right: CompiledExpression<F>,
+    ) -> CompiledExpression<F> {
+        CompiledExpression::Mul(Box::new(left), Box::new(right))
+    }
+
+    fn neg(&mut self, expression: CompiledExpression<F>) -> CompiledExpression<F> {
+        CompiledExpression::Neg(Box::new(expression))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use powdr_number::Bn254Field;
+    use powdr_pil_analyzer::analyze_file;
+
+    use super::*;
+
+    /// A relation where `b` accumulates `a` while `sel` is set, along with a lookup of `a` in a
+    /// table and a permutation between `c` and `d`.
+    const PIL: &str = "
+namespace main(4);
+    pol commit sel;
+    pol commit a;
+    pol commit b;
+    pol commit sel_table;
+    pol commit table;
+    pol commit sel_c;
+    pol commit c;
+    pol commit sel_d;
+    pol commit d;
+
+    #[SEL_BOOLEAN]
+    sel * (1 - sel) = 0;
+    #[B_ACCUMULATES_A]
+    sel * (b' - b - a) = 0;
+
+    #[LOOKUP_A]
+    sel {a} in sel_table {table};
+
+    #[PERM_C_D]
+    sel_c {c} is sel_d {d};
+";
+
+    /// Analyzes `PIL`, from a file named `main.pil` so that its relation is named `main`.
+    fn analyze(test_name: &str) -> Analyzed<Bn254Field> {
+        let dir = env::temp_dir().join(format!(
+            "bb_pil_trace_checker_{test_name}_{}",
+            process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.pil");
+        fs::write(&path, PIL).unwrap();
+        let analyzed = analyze_file(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        analyzed
+    }
+
+    /// The columns of a trace on which every relation of `PIL` holds.
+    fn passing_columns() -> Vec<(&'static str, [u64; 4])> {
+        vec![
+            ("main.sel", [1, 1, 1, 0]),
+            ("main.a", [1, 2, 0, 0]),
+            ("main.b", [0, 1, 3, 3]),
+            ("main.sel_table", [1, 1, 1, 0]),
+            ("main.table", [0, 1, 2, 5]),
+            ("main.sel_c", [1, 1, 0, 0]),
+            ("main.c", [4, 7, 0, 0]),
+            ("main.sel_d", [0, 1, 1, 0]),
+            ("main.d", [0, 4, 7, 0]),
+        ]
+    }
+
+    fn fields(values: &[u64]) -> Vec<Bn254Field> {
+        values.iter().copied().map(Bn254Field::from).collect()
+    }
+
+    fn trace(columns: Vec<(&str, [u64; 4])>) -> Trace<Bn254Field> {
+        let columns = columns
+            .into_iter()
+            .map(|(name, values)| (name.to_string(), fields(&values)))
+            .collect();
+        Trace::new(columns).unwrap()
+    }
+
+    /// Checks `PIL` on the passing trace with the value of `column` at `row` changed to `value`,
+    /// and returns the failures.
+    fn failures_with_value(test_name: &str, column: &str, row: usize, value: u64) -> Vec<String> {
+        let mut columns = passing_columns();
+        let (_, values) = columns
+            .iter_mut()
+            .find(|(name, _)| *name == column)
+            .unwrap();
+        values[row] = value;
+        check_trace(&analyze(test_name), &trace(columns))
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_passing_trace() {
+        let failures = check_trace(&analyze("passing"), &trace(passing_columns())).unwrap();
+        assert!(failures.is_empty(), "{failures:?}");
+    }
+
+    #[test]
+    fn reports_failing_identities() {
+        assert_eq!(
+            failures_with_value("identity", "main.b", 1, 2),
+            ["Relation main, subrelation 1 (B_ACCUMULATES_A) fails at rows 0, 1"]
+        );
+        assert_eq!(
+            failures_with_value("boolean", "main.sel", 3, 2),
+            [
+                "Relation main, subrelation 0 (SEL_BOOLEAN) fails at rows 3",
+                "Relation main, subrelation 1 (B_ACCUMULATES_A) fails at rows 3",
+            ]
+        );
+    }
+
+    /// The shift of the last row is zero, so `b` can't accumulate anything on the last row.
+    #[test]
+    fn reports_failing_shifts_at_the_last_row() {
+        assert_eq!(
+            failures_with_value("shift", "main.sel", 3, 1),
+            ["Relation main, subrelation 1 (B_ACCUMULATES_A) fails at rows 3"]
+        );
+    }
+
+    #[test]
+    fn reports_values_missing_from_a_lookup_table() {
+        assert_eq!(
+            failures_with_value("lookup", "main.table", 2, 9),
+            ["Lookup lookup_a fails at rows 1: the values aren't in the looked up columns"]
+        );
+        // Rows of the table which aren't selected can't be looked up
+        assert_eq!(
+            failures_with_value("lookup_selector", "main.sel_table", 0, 0),
+            ["Lookup lookup_a fails at rows 2: the values aren't in the looked up columns"]
+        );
+    }
+
+    #[test]
+    fn reports_unmatched_rows_of_a_permutation() {
+        assert_eq!(
+            failures_with_value("permutation", "main.d", 2, 8),
+            ["Permutation perm_c_d fails: rows 1 of the left side have no match, rows 2 of the right side have no match"]
+        );
+        // Each row of one side matches a single row of the other side
+        assert_eq!(
+            failures_with_value("permutation_count", "main.sel_d", 0, 1),
+            ["Permutation perm_c_d fails: rows 0 of the right side have no match"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_columns() {
+        let mut columns = passing_columns();
+        columns.retain(|(name, _)| *name != "main.d");
+        let error = check_trace(&analyze("missing"), &trace(columns)).unwrap_err();
+        assert_eq!(error, "Column main_d is missing from the trace");
+    }
+
+    #[test]
+    fn parses_csv_traces() {
+        let columns = parse_csv_trace::<Bn254Field>("main.sel, main_a\n1, 0x10\n\n0,2\n").unwrap();
+        assert_eq!(
+            columns,
+            [
+                ("main.sel".to_string(), fields(&[1, 0])),
+                ("main_a".to_string(), fields(&[16, 2])),
+            ]
+        );
+
+        let error = parse_csv_trace::<Bn254Field>("main_sel,main_a\n1\n").unwrap_err();
+        assert_eq!(
+            error,
+            "Row 0 of the CSV trace has 1 values, but there are 2 columns"
+        );
+        assert!(parse_csv_trace::<Bn254Field>("main_sel\nfoo\n").is_err());
+    }
+
+    #[test]
+    fn parses_json_traces() {
+        let columns =
+            parse_json_trace::<Bn254Field>(r#"{ "main_sel": [1, "0x10", "2"] }"#).unwrap();
+        assert_eq!(columns, [("main_sel".to_string(), fields(&[1, 16, 2]))]);
+
+        assert!(parse_json_trace::<Bn254Field>(r#"[[1, 2]]"#).is_err());
+        assert!(parse_json_trace::<Bn254Field>(r#"{ "main_sel": 1 }"#).is_err());
+        assert!(parse_json_trace::<Bn254Field>(r#"{ "main_sel": [true] }"#).is_err());
+    }
+
+    #[test]
+    fn rejects_columns_of_different_lengths() {
+        let columns = vec![
+            ("main_a".to_string(), fields(&[1])),
+            ("main_b".to_string(), vec![]),
+        ];
+        assert_eq!(
+            Trace::new(columns).err(),
+            Some("Column main_b has 0 rows, but column main_a has 1".to_string())
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use std::io;
+
 use crate::circuit_builder::CircuitBuilder;
 use crate::composer_builder::ComposerBuilder;
+use crate::degree_analysis::{split_high_degree_identities, SplitIdentities};
 use crate::file_writer::BBFiles;
 use crate::flavor_builder::FlavorBuilder;
 use crate::lookup_builder::{
@@ -8,6 +11,7 @@ use crate::lookup_builder::{
 use crate::permutation_builder::{get_inverses_from_permutations, Permutation, PermutationBuilder};
 use crate::prover_builder::ProverBuilder;
 use crate::relation_builder::{get_shifted_polys, RelationBuilder};
+use crate::rust_builder::RustBuilder;
 use crate::utils::{flatten, sanitize_name, snake_case, sort_cols};
 use crate::verifier_builder::VerifierBuilder;
 
@@ -45,29 +49,129 @@ struct ColumnGroups {
 /// Analyzed to cpp
 ///
 /// Converts an analyzed pil AST into a set of cpp files that can be used to generate a proof
+///
+/// If `max_degree` is set, identities of higher degree are split with new committed columns. This
+/// is experimental: no witness generation is emitted for the new columns.
+/// If `emit_rust` is set, a rust module with the schema of the trace is generated as well.
+///
+/// The generated cpp files are formatted with clang-format before being compared to the files on
+/// disk. If clang-format isn't installed they are written unformatted, with a warning, but `check`
+/// mode requires it. Only the files whose contents changed are written, and the files of the
+/// generated directory which are not generated anymore are removed, after a confirmation prompt
+/// unless `delete_stale` is set. In `check` mode nothing is written; instead, returns false if the
+/// generated directory is not up to date. Returns an error if the identities can't be split, or if
+/// the generated files can't be formatted, read or written.
 pub fn analyzed_to_cpp<F: FieldElement>(
     analyzed: &Analyzed<F>,
     generated_dir: Option<&str>,
     vm_name: &str,
-    delete_dir: bool,
-) {
+    delete_stale: bool,
+    max_degree: Option<u64>,
+    emit_rust: bool,
+    check: bool,
+) -> Result<bool, String> {
     let mut bb_files = BBFiles::new(&snake_case(&vm_name), generated_dir, None);
+    generate_files(&mut bb_files, analyzed, vm_name, max_degree, emit_rust)?;
+    match bb_files.format_cpp_files() {
+        Ok(()) => {}
+        // Unformatted files would always differ from the formatted ones on disk
+        Err(error) if !check && error.kind() == io::ErrorKind::NotFound => {
+            eprintln!("warning: {error}, the generated files are written unformatted");
+        }
+        Err(error) => return Err(format!("Unable to format the generated files: {error}")),
+    }
+
+    let stale_files = bb_files
+        .stale_files()
+        .map_err(|error| format!("Unable to list the files of {}: {error}", bb_files.base_dir))?;
+    if check {
+        let changed_files = bb_files.changed_files();
+        for path in &changed_files {
+            println!("Out of date: {}", path.display());
+        }
+        for path in &stale_files {
+            println!("Stale: {}", path.display());
+        }
+        return Ok(changed_files.is_empty() && stale_files.is_empty());
+    }
 
-    // Remove the generated directory if it exists.
+    let written_files = bb_files.write_changed_files().map_err(|error| {
+        format!(
+            "Unable to write the generated files to {}: {error}",
+            bb_files.base_dir
+        )
+    })?;
+    println!(
+        "Wrote {} changed files to {}",
+        written_files.len(),
+        bb_files.base_dir
+    );
+
+    // Remove the files which are not generated anymore.
     // Pass `-y` as parameter if you want to skip the confirmation prompt.
-    let confirmation = delete_dir
-        || Confirm::new()
-            .with_prompt(format!("Going to remove: {}. OK?", bb_files.base_dir))
-            .default(true)
-            .interact()
-            .unwrap();
-    if confirmation {
-        println!("Removing generated directory: {}", bb_files.base_dir);
-        bb_files.remove_generated_dir();
+    if !stale_files.is_empty() {
+        let stale_list = stale_files
+            .iter()
+            .map(|path| format!("\n  {}", path.display()))
+            .collect::<String>();
+        let confirmation = delete_stale
+            || Confirm::new()
+                .with_prompt(format!(
+                    "Going to remove stale files from {}:{}\nOK?",
+                    bb_files.base_dir, stale_list
+                ))
+                .default(true)
+                .interact()
+                .map_err(|error| format!("Unable to confirm the removal: {error}"))?;
+        if confirmation {
+            println!("Removing {} stale files", stale_files.len());
+            bb_files.remove_files(&stale_files).map_err(|error| {
+                format!(
+                    "Unable to remove the stale files of {}: {error}",
+                    bb_files.base_dir
+                )
+            })?;
+        }
     }
 
+    println!("Done with generation.");
+    Ok(true)
+}
+
+/// Generate Files
+///
+/// Generates the contents of every file of the VM, in memory
+fn generate_files<F: FieldElement>(
+    bb_files: &mut BBFiles,
+    analyzed: &Analyzed<F>,
+    vm_name: &str,
+    max_degree: Option<u64>,
+    emit_rust: bool,
+) -> Result<(), String> {
+    // ----------------------- Split High Degree Identities -----------------------
+    let SplitIdentities {
+        identities,
+        columns: split_columns,
+    } = match max_degree {
+        Some(max_degree) => {
+            let split = split_high_degree_identities(analyzed, max_degree)?;
+            if !split.columns.is_empty() {
+                println!(
+                    "Added {} columns to bound the degree of the relations to {}. The trace builder must fill them, as no witness generation is emitted for them.",
+                    split.columns.len(),
+                    max_degree
+                );
+            }
+            split
+        }
+        None => SplitIdentities {
+            identities: analyzed.identities.clone(),
+            columns: vec![],
+        },
+    };
+
     // ----------------------- Handle Standard Relation Identities -----------------------
-    let relations = bb_files.create_relations(vm_name, analyzed);
+    let relations = bb_files.create_relations(vm_name, analyzed, &identities);
 
     // ----------------------- Handle Lookup / Permutation Relation Identities -----------------------
     let permutations = bb_files.create_permutation_files(analyzed, vm_name);
@@ -90,7 +194,7 @@ pub fn analyzed_to_cpp<F: FieldElement>(
         all_cols_with_shifts,
         inverses,
         public_inputs,
-    } = get_all_col_names(analyzed, &permutations, &lookups);
+    } = get_all_col_names(analyzed, &permutations, &lookups, &split_columns);
 
     // ----------------------- Create the full row files -----------------------
     bb_files.create_full_row_hpp(vm_name, &all_cols);
@@ -138,6 +242,17 @@ pub fn analyzed_to_cpp<F: FieldElement>(
         &all_cols_with_shifts,
     );
 
+    // ----------------------- Create the Rust trace -----------------------
+    if emit_rust {
+        bb_files.create_rust_trace(
+            vm_name,
+            analyzed,
+            &identities,
+            &all_cols_with_shifts,
+            &to_be_shifted,
+        )?;
+    }
+
     // ----------------------- Create the Verifier files -----------------------
     bb_files.create_verifier_cpp(vm_name, &public_inputs);
     bb_files.create_verifier_hpp(vm_name);
@@ -148,7 +263,7 @@ pub fn analyzed_to_cpp<F: FieldElement>(
 
     if vm_name == "Avm2" {
         println!("Skipping the creation of the composer, circuit builder and recursive verifier for Avm2.");
-        return;
+        return Ok(());
     }
 
     // ----------------------- Create the circuit builder files -----------------------
@@ -161,14 +276,14 @@ pub fn analyzed_to_cpp<F: FieldElement>(
 
     // ----------------------- Create the recursive verifier -----------------------
     bb_files.create_recursive_verifier_cpp(vm_name, &public_inputs);
-
-    println!("Done with generation.");
+    Ok(())
 }
 
 fn get_all_col_names<F: FieldElement>(
     analyzed: &Analyzed<F>,
     permutations: &[Permutation],
     lookups: &[Lookup],
+    split_columns: &[String],
 ) -> ColumnGroups {
     let constant = sort_cols(
         &analyzed
@@ -184,6 +299,7 @@ fn get_all_col_names<F: FieldElement>(
             .iter()
             .map(|(sym, _)| sym.absolute_name.clone())
             .map(|n| sanitize_name(&n))
+            .chain(split_columns.iter().cloned())
             .collect_vec(),
     );
     let public = analyzed
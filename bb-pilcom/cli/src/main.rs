@@ -1,5 +1,8 @@
-use std::{io, path::Path};
+use std::{io, path::Path, process};
 
+use bb_pil_backend::degree_analysis::analyze_degrees;
+use bb_pil_backend::lints::lint;
+use bb_pil_backend::trace_checker::{check_trace, read_trace};
 use bb_pil_backend::vm_builder::analyzed_to_cpp;
 use clap::Parser;
 use powdr_ast::analyzed::Analyzed;
@@ -17,23 +20,119 @@ struct Cli {
     output_directory: Option<String>,
 
     /// BBerg: Name of the VM
-    #[arg(long)]
+    #[arg(long, required_unless_present_any = ["check_trace", "degree_report", "lint", "deny_lints"])]
     name: Option<String>,
 
-    /// Delete the output directory if it already exists
+    /// Remove the files of the output directory which are not generated anymore without asking
     #[arg(short, long)]
     #[arg(default_value_t = false)]
     yes: bool,
+
+    /// Check that the output directory is up to date instead of writing to it (requires
+    /// clang-format-16, or the clang-format set with `CLANG_FORMAT`)
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    check: bool,
+
+    /// Check the relations on a witness trace (JSON or CSV columns) instead of generating code
+    #[arg(long)]
+    check_trace: Option<String>,
+
+    /// Print the maximum degree of each relation, and what drives it
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    degree_report: bool,
+
+    /// Experimental: split the identities of higher degree by adding committed columns. No witness
+    /// generation is emitted for these columns, so the trace builder has to fill them.
+    #[arg(long = "experimental-max-degree", value_parser = clap::value_parser!(u64).range(2..))]
+    max_degree: Option<u64>,
+
+    /// Also generate a rust module with the columns of the trace and the evaluation of the relations
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    rust: bool,
+
+    /// Warn about unconstrained columns, unused shifts, untoggled selectors and duplicated identities
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    lint: bool,
+
+    /// Like --lint, but exit with an error if there are warnings
+    #[arg(long)]
+    #[arg(default_value_t = false)]
+    deny_lints: bool,
 }
 
 fn main() -> Result<(), io::Error> {
     let args = Cli::parse();
 
     let file_name = args.file;
-    let name = args.name.unwrap();
     let analyzed: Analyzed<Bn254Field> = analyze_file(Path::new(&file_name));
 
-    analyzed_to_cpp(&analyzed, args.output_directory.as_deref(), &name, args.yes);
+    if args.degree_report {
+        for relation_degree in analyze_degrees(&analyzed) {
+            println!("{relation_degree}");
+        }
+    }
+
+    if args.lint || args.deny_lints {
+        let lints = lint(&analyzed);
+        for warning in &lints {
+            println!("warning: {warning}");
+        }
+        if args.deny_lints && !lints.is_empty() {
+            eprintln!("error: {} lint warnings", lints.len());
+            process::exit(1);
+        }
+    }
+
+    if args.name.is_none() && args.check_trace.is_none() {
+        return Ok(());
+    }
+
+    if let Some(trace_path) = args.check_trace {
+        let failures = read_trace(Path::new(&trace_path))
+            .and_then(|trace| check_trace(&analyzed, &trace).map(|failures| (trace, failures)));
+        match failures {
+            Ok((trace, failures)) if failures.is_empty() => {
+                println!(
+                    "All relations hold on the {} rows of the trace",
+                    trace.num_rows()
+                );
+            }
+            Ok((_, failures)) => {
+                for failure in failures {
+                    println!("{failure}");
+                }
+                process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("error: {error}");
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let name = args.name.unwrap();
+    let up_to_date = analyzed_to_cpp(
+        &analyzed,
+        args.output_directory.as_deref(),
+        &name,
+        args.yes,
+        args.max_degree,
+        args.rust,
+        args.check,
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        process::exit(1);
+    });
+    if !up_to_date {
+        eprintln!("error: the generated files are out of date");
+        process::exit(1);
+    }
 
     Ok(())
 }
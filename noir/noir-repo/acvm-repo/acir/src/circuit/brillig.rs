@@ -1,5 +1,7 @@
+use super::opcodes::write_witnesses;
 use super::opcodes::BlockId;
 use crate::native_types::{Expression, Witness};
+use acir_field::AcirField;
 use brillig::Opcode as BrilligOpcode;
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +14,25 @@ pub enum BrilligInputs<F> {
     MemoryArray(BlockId),
 }
 
+impl<F: AcirField> std::fmt::Display for BrilligInputs<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrilligInputs::Single(expr) => write!(f, "{expr}"),
+            BrilligInputs::Array(exprs) => {
+                write!(f, "[")?;
+                for (index, expr) in exprs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{expr}")?;
+                }
+                write!(f, "]")
+            }
+            BrilligInputs::MemoryArray(block_id) => write!(f, "MEM {}", block_id.0),
+        }
+    }
+}
+
 /// Outputs for the Brillig VM. Once the VM has completed
 /// execution, this will be the object that is returned.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Hash)]
@@ -20,6 +41,15 @@ pub enum BrilligOutputs {
     Array(Vec<Witness>),
 }
 
+impl std::fmt::Display for BrilligOutputs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrilligOutputs::Simple(witness) => write!(f, "_{}", witness.witness_index()),
+            BrilligOutputs::Array(witnesses) => write_witnesses(f, witnesses),
+        }
+    }
+}
+
 /// This is purely a wrapper struct around a list of Brillig opcode's which represents
 /// a full Brillig function to be executed by the Brillig VM.
 /// This is stored separately on a program and accessed through a [BrilligPointer].
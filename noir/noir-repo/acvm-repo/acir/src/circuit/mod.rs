@@ -322,9 +322,24 @@ impl<F: AcirField> std::fmt::Display for Circuit<F> {
         write!(f, "return value indices : ")?;
         write_witness_indices(f, &self.return_values.indices())?;
 
+        writeln!(f, "expression width : {:?}", self.expression_width)?;
+
         for opcode in &self.opcodes {
             writeln!(f, "{opcode}")?;
         }
+        for (location, payload) in &self.assert_messages {
+            write!(f, "ASSERT at {location}: selector {}, payload: [", payload.error_selector)?;
+            for (index, item) in payload.payload.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                match item {
+                    ExpressionOrMemory::Expression(expr) => write!(f, "{expr}")?,
+                    ExpressionOrMemory::Memory(block_id) => write!(f, "MEM {}", block_id.0)?,
+                }
+            }
+            writeln!(f, "]")?;
+        }
         Ok(())
     }
 }
@@ -343,7 +358,9 @@ impl<F: AcirField> std::fmt::Display for Program<F> {
         }
         for (func_index, function) in self.unconstrained_functions.iter().enumerate() {
             writeln!(f, "unconstrained func {}", func_index)?;
-            writeln!(f, "{:?}", function.bytecode)?;
+            for opcode in &function.bytecode {
+                writeln!(f, "{opcode:?}")?;
+            }
         }
         Ok(())
     }
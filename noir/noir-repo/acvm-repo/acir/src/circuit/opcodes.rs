@@ -158,7 +158,11 @@ impl<F: AcirField> std::fmt::Display for Opcode<F> {
                 } else if is_write {
                     write!(f, "(id: {}, write {} at: {}) ", block_id.0, op.value, op.index)
                 } else {
-                    write!(f, "(id: {}, op {} at: {}) ", block_id.0, op.operation, op.index)
+                    write!(
+                        f,
+                        "(id: {}, op {} at: {}, value: {}) ",
+                        block_id.0, op.operation, op.index, op.value
+                    )
                 }
             }
             Opcode::MemoryInit { block_id, init, block_type: databus } => {
@@ -167,7 +171,9 @@ impl<F: AcirField> std::fmt::Display for Opcode<F> {
                     BlockType::CallData(id) => write!(f, "INIT CALLDATA {} ", id)?,
                     BlockType::ReturnData => write!(f, "INIT RETURNDATA ")?,
                 }
-                write!(f, "(id: {}, len: {}) ", block_id.0, init.len())
+                write!(f, "(id: {}, len: {}, witnesses: ", block_id.0, init.len())?;
+                write_witnesses(f, init)?;
+                write!(f, ")")
             }
             // We keep the display for a BrilligCall and circuit Call separate as they
             // are distinct in their functionality and we should maintain this separation for debugging.
@@ -176,21 +182,51 @@ impl<F: AcirField> std::fmt::Display for Opcode<F> {
                 if let Some(pred) = predicate {
                     writeln!(f, "PREDICATE = {pred}")?;
                 }
-                write!(f, "inputs: {:?}, ", inputs)?;
-                write!(f, "outputs: {:?}", outputs)
+                write!(f, "inputs: [")?;
+                for (index, input) in inputs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{input}")?;
+                }
+                write!(f, "], outputs: [")?;
+                for (index, output) in outputs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{output}")?;
+                }
+                write!(f, "]")
             }
             Opcode::Call { id, inputs, outputs, predicate } => {
                 write!(f, "CALL func {}: ", id)?;
                 if let Some(pred) = predicate {
                     writeln!(f, "PREDICATE = {pred}")?;
                 }
-                write!(f, "inputs: {:?}, ", inputs)?;
-                write!(f, "outputs: {:?}", outputs)
+                write!(f, "inputs: ")?;
+                write_witnesses(f, inputs)?;
+                write!(f, ", outputs: ")?;
+                write_witnesses(f, outputs)
             }
         }
     }
 }
 
+/// Writes a list of witnesses as `[_0, _1, ...]`.
+pub(crate) fn write_witnesses(
+    f: &mut std::fmt::Formatter<'_>,
+    witnesses: &[Witness],
+) -> std::fmt::Result {
+    write!(f, "[")?;
+    for (index, witness) in witnesses.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "_{}", witness.witness_index())?;
+    }
+    write!(f, "]")
+}
+
 impl<F: AcirField> std::fmt::Debug for Opcode<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self, f)
@@ -73,8 +73,12 @@ impl<F: AcirField> FunctionInput<F> {
 impl<F: std::fmt::Display> std::fmt::Display for FunctionInput<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.input {
-            ConstantOrWitnessEnum::Constant(constant) => write!(f, "{constant}"),
-            ConstantOrWitnessEnum::Witness(witness) => write!(f, "{}", witness.0),
+            ConstantOrWitnessEnum::Constant(constant) => {
+                write!(f, "({constant}, {})", self.num_bits)
+            }
+            ConstantOrWitnessEnum::Witness(witness) => {
+                write!(f, "(_{}, {})", witness.0, self.num_bits)
+            }
         }
     }
 }
@@ -375,55 +379,108 @@ impl<F: Copy> BlackBoxFuncCall<F> {
     }
 }
 
-const ABBREVIATION_LIMIT: usize = 5;
-
-fn get_inputs_string<F: std::fmt::Display>(inputs: &[FunctionInput<F>]) -> String {
-    // Once a vectors length gets above this limit,
-    // instead of listing all of their elements, we use ellipses
-    // to abbreviate them
-    let should_abbreviate_inputs = inputs.len() <= ABBREVIATION_LIMIT;
-
-    if should_abbreviate_inputs {
-        let mut result = String::new();
-        for (index, inp) in inputs.iter().enumerate() {
-            result += &format!("({})", inp);
-            // Add a comma, unless it is the last entry
-            if index != inputs.len() - 1 {
-                result += ", ";
-            }
-        }
-        result
-    } else {
-        let first = inputs.first().unwrap();
-        let last = inputs.last().unwrap();
-
-        let mut result = String::new();
-        result += &format!("({})...({})", first, last,);
-
-        result
-    }
+/// A field of a black box function call, displayed as `name: value`.
+enum DisplayedField<'a, F> {
+    Input(&'a FunctionInput<F>),
+    Inputs(&'a [FunctionInput<F>]),
+    Output(Witness),
+    Witnesses(Vec<Witness>),
+    Integer(u32),
+    Bytes(&'a [u8]),
 }
 
-fn get_outputs_string(outputs: &[Witness]) -> String {
-    let should_abbreviate_outputs = outputs.len() <= ABBREVIATION_LIMIT;
-
-    if should_abbreviate_outputs {
-        let mut result = String::new();
-        for (index, output) in outputs.iter().enumerate() {
-            result += &format!("_{}", output.witness_index());
-            // Add a comma, unless it is the last entry
-            if index != outputs.len() - 1 {
-                result += ", ";
+impl<F: Copy> BlackBoxFuncCall<F> {
+    /// Returns the fields of the call in the order in which they are displayed.
+    fn displayed_fields(&self) -> Vec<(&'static str, DisplayedField<'_, F>)> {
+        use DisplayedField::{Bytes, Input, Inputs, Integer, Output, Witnesses};
+        match self {
+            BlackBoxFuncCall::AES128Encrypt { inputs, iv, key, outputs } => vec![
+                ("inputs", Inputs(inputs)),
+                ("iv", Inputs(iv.as_slice())),
+                ("key", Inputs(key.as_slice())),
+                ("outputs", Witnesses(outputs.clone())),
+            ],
+            BlackBoxFuncCall::AND { lhs, rhs, output }
+            | BlackBoxFuncCall::XOR { lhs, rhs, output } => {
+                vec![("lhs", Input(lhs)), ("rhs", Input(rhs)), ("output", Output(*output))]
+            }
+            BlackBoxFuncCall::RANGE { input } => vec![("input", Input(input))],
+            BlackBoxFuncCall::Blake2s { inputs, outputs }
+            | BlackBoxFuncCall::Blake3 { inputs, outputs } => {
+                vec![("inputs", Inputs(inputs)), ("outputs", Witnesses(outputs.to_vec()))]
+            }
+            BlackBoxFuncCall::EcdsaSecp256k1 {
+                public_key_x,
+                public_key_y,
+                signature,
+                hashed_message,
+                output,
             }
+            | BlackBoxFuncCall::EcdsaSecp256r1 {
+                public_key_x,
+                public_key_y,
+                signature,
+                hashed_message,
+                output,
+            } => vec![
+                ("public_key_x", Inputs(public_key_x.as_slice())),
+                ("public_key_y", Inputs(public_key_y.as_slice())),
+                ("signature", Inputs(signature.as_slice())),
+                ("hashed_message", Inputs(hashed_message.as_slice())),
+                ("output", Output(*output)),
+            ],
+            BlackBoxFuncCall::MultiScalarMul { points, scalars, outputs } => vec![
+                ("points", Inputs(points)),
+                ("scalars", Inputs(scalars)),
+                ("outputs", Witnesses(vec![outputs.0, outputs.1, outputs.2])),
+            ],
+            BlackBoxFuncCall::EmbeddedCurveAdd { input1, input2, outputs } => vec![
+                ("input1", Inputs(input1.as_slice())),
+                ("input2", Inputs(input2.as_slice())),
+                ("outputs", Witnesses(vec![outputs.0, outputs.1, outputs.2])),
+            ],
+            BlackBoxFuncCall::Keccakf1600 { inputs, outputs } => vec![
+                ("inputs", Inputs(inputs.as_slice())),
+                ("outputs", Witnesses(outputs.to_vec())),
+            ],
+            BlackBoxFuncCall::RecursiveAggregation {
+                verification_key,
+                proof,
+                public_inputs,
+                key_hash,
+                proof_type,
+            } => vec![
+                ("verification_key", Inputs(verification_key)),
+                ("proof", Inputs(proof)),
+                ("public_inputs", Inputs(public_inputs)),
+                ("key_hash", Input(key_hash)),
+                ("proof_type", Integer(*proof_type)),
+            ],
+            BlackBoxFuncCall::BigIntAdd { lhs, rhs, output }
+            | BlackBoxFuncCall::BigIntSub { lhs, rhs, output }
+            | BlackBoxFuncCall::BigIntMul { lhs, rhs, output }
+            | BlackBoxFuncCall::BigIntDiv { lhs, rhs, output } => {
+                vec![("lhs", Integer(*lhs)), ("rhs", Integer(*rhs)), ("output", Integer(*output))]
+            }
+            BlackBoxFuncCall::BigIntFromLeBytes { inputs, modulus, output } => vec![
+                ("inputs", Inputs(inputs)),
+                ("modulus", Bytes(modulus)),
+                ("output", Integer(*output)),
+            ],
+            BlackBoxFuncCall::BigIntToLeBytes { input, outputs } => {
+                vec![("input", Integer(*input)), ("outputs", Witnesses(outputs.clone()))]
+            }
+            BlackBoxFuncCall::Poseidon2Permutation { inputs, outputs, len } => vec![
+                ("inputs", Inputs(inputs)),
+                ("outputs", Witnesses(outputs.clone())),
+                ("len", Integer(*len)),
+            ],
+            BlackBoxFuncCall::Sha256Compression { inputs, hash_values, outputs } => vec![
+                ("inputs", Inputs(inputs.as_slice())),
+                ("hash_values", Inputs(hash_values.as_slice())),
+                ("outputs", Witnesses(outputs.to_vec())),
+            ],
         }
-        result
-    } else {
-        let first = outputs.first().unwrap();
-        let last = outputs.last().unwrap();
-
-        let mut result = String::new();
-        result += &format!("(_{},...,_{})", first.witness_index(), last.witness_index());
-        result
     }
 }
 
@@ -431,24 +488,32 @@ impl<F: std::fmt::Display + Copy> std::fmt::Display for BlackBoxFuncCall<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let uppercase_name = self.name().to_uppercase();
         write!(f, "BLACKBOX::{uppercase_name} ")?;
-        // INPUTS
-        write!(f, "[")?;
-
-        let inputs_str = get_inputs_string(&self.get_inputs_vec());
-
-        write!(f, "{inputs_str}")?;
-        write!(f, "] ")?;
-
-        // OUTPUTS
-        write!(f, "[ ")?;
-
-        let outputs_str = get_outputs_string(&self.get_outputs_vec());
 
-        write!(f, "{outputs_str}")?;
-
-        write!(f, "]")?;
-
-        write!(f, "")
+        // All fields are displayed in full, so that the call can be parsed back
+        for (index, (name, field)) in self.displayed_fields().into_iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{name}: ")?;
+            match field {
+                DisplayedField::Input(input) => write!(f, "{input}")?,
+                DisplayedField::Inputs(inputs) => {
+                    write!(f, "[")?;
+                    for (index, input) in inputs.iter().enumerate() {
+                        if index > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{input}")?;
+                    }
+                    write!(f, "]")?;
+                }
+                DisplayedField::Output(witness) => write!(f, "_{}", witness.witness_index())?,
+                DisplayedField::Witnesses(witnesses) => super::write_witnesses(f, &witnesses)?,
+                DisplayedField::Integer(integer) => write!(f, "{integer}")?,
+                DisplayedField::Bytes(bytes) => write!(f, "{bytes:?}")?,
+            }
+        }
+        Ok(())
     }
 }
 
@@ -7,12 +7,14 @@
 
 pub mod circuit;
 pub mod native_types;
+mod parser;
 
 pub use acir_field;
 pub use acir_field::{AcirField, FieldElement};
 pub use brillig;
 pub use circuit::black_box_functions::BlackBoxFunc;
 pub use circuit::opcodes::InvalidInputBitSize;
+pub use parser::AcirParserErrorWithSource;
 
 #[cfg(test)]
 mod reflection {
@@ -0,0 +1,174 @@
+use std::str::CharIndices;
+
+use thiserror::Error;
+
+use super::token::{Span, SpannedToken, Token};
+
+pub(crate) struct Lexer<'a> {
+    chars: CharIndices<'a>,
+    source_length: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Lexer { chars: source.char_indices(), source_length: source.len() }
+    }
+
+    pub(crate) fn next_token(&mut self) -> SpannedTokenResult {
+        self.eat_while(None, |char| char.is_ascii_whitespace());
+
+        let start = self.offset();
+        let Some(char) = self.next_char() else {
+            return Ok(SpannedToken::new(Token::Eof, Span::new(start, start)));
+        };
+        let token = match char {
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '.' => Token::Dot,
+            '%' => Token::Percent,
+            '=' => Token::Assign,
+            '-' => Token::Dash,
+            '"' => Token::Str(self.eat_string(start)?),
+            '_' if self.peek_char().is_some_and(|char| char.is_ascii_digit()) => {
+                let index = self.eat_while(None, |char| char.is_ascii_digit());
+                let index = index.parse().map_err(|_| LexerError::InvalidWitnessIndex {
+                    span: Span::new(start, self.offset()),
+                    found: index,
+                })?;
+                Token::Witness(index)
+            }
+            char if char.is_ascii_digit() => {
+                Token::Int(self.eat_while(Some(char), |char| char.is_ascii_digit()))
+            }
+            char if char.is_ascii_alphabetic() || char == '_' => Token::Ident(
+                self.eat_while(Some(char), |char| char.is_ascii_alphanumeric() || char == '_'),
+            ),
+            char => {
+                return Err(LexerError::UnexpectedCharacter {
+                    char,
+                    span: Span::single_char(start),
+                })
+            }
+        };
+        Ok(SpannedToken::new(token, Span::new(start, self.offset())))
+    }
+
+    /// Reads a string literal as printed by `Debug`, after its opening quote.
+    fn eat_string(&mut self, start: usize) -> Result<String, LexerError> {
+        let mut string = String::new();
+        loop {
+            let char = match self.next_char() {
+                Some('"') => break,
+                Some('\\') => match self.next_char() {
+                    Some('r') => '\r',
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some('"') => '"',
+                    Some('\'') => '\'',
+                    Some('\\') => '\\',
+                    Some('u') => self.eat_unicode_escape(start)?,
+                    Some(escaped) => {
+                        let span = Span::new(start, self.offset());
+                        return Err(LexerError::InvalidEscape { escaped, span });
+                    }
+                    None => {
+                        let span = Span::new(start, self.offset());
+                        return Err(LexerError::UnterminatedStringLiteral { span });
+                    }
+                },
+                Some(other) => other,
+                None => {
+                    let span = Span::new(start, self.offset());
+                    return Err(LexerError::UnterminatedStringLiteral { span });
+                }
+            };
+            string.push(char);
+        }
+        Ok(string)
+    }
+
+    /// Reads a `\u{...}` escape sequence, after its `u`.
+    fn eat_unicode_escape(&mut self, start: usize) -> Result<char, LexerError> {
+        let invalid_escape = |lexer: &Self| LexerError::InvalidEscape {
+            escaped: 'u',
+            span: Span::new(start, lexer.offset()),
+        };
+        if self.next_char() != Some('{') {
+            return Err(invalid_escape(self));
+        }
+        let digits = self.eat_while(None, |char| char.is_ascii_hexdigit());
+        if self.next_char() != Some('}') {
+            return Err(invalid_escape(self));
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| invalid_escape(self))
+    }
+
+    fn eat_while<F: Fn(char) -> bool>(
+        &mut self,
+        initial_char: Option<char>,
+        predicate: F,
+    ) -> String {
+        let mut word = String::new();
+        if let Some(init_char) = initial_char {
+            word.push(init_char);
+        }
+
+        while let Some(peek_char) = self.peek_char() {
+            if !predicate(peek_char) {
+                break;
+            }
+            word.push(peek_char);
+            self.next_char();
+        }
+
+        word
+    }
+
+    /// The position of the next character to be read.
+    fn offset(&self) -> usize {
+        self.chars.clone().next().map_or(self.source_length, |(position, _)| position)
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, char)| char)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.chars.clone().next().map(|(_, char)| char)
+    }
+}
+
+type SpannedTokenResult = Result<SpannedToken, LexerError>;
+
+#[derive(Debug, Error)]
+pub(crate) enum LexerError {
+    #[error("Unexpected character: {char:?}")]
+    UnexpectedCharacter { char: char, span: Span },
+    #[error("Invalid witness index {found}")]
+    InvalidWitnessIndex { span: Span, found: String },
+    #[error("Unterminated string literal")]
+    UnterminatedStringLiteral { span: Span },
+    #[error("'\\{escaped}' is not a valid escape sequence")]
+    InvalidEscape { escaped: char, span: Span },
+}
+
+impl LexerError {
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            LexerError::UnexpectedCharacter { span, .. }
+            | LexerError::InvalidWitnessIndex { span, .. }
+            | LexerError::UnterminatedStringLiteral { span }
+            | LexerError::InvalidEscape { span, .. } => *span,
+        }
+    }
+}
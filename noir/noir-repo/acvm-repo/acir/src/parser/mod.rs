@@ -0,0 +1,1267 @@
+//! Parser for the textual form of ACIR programs, which is the output of their `Display`
+//! implementation. This makes it possible to write ACIR by hand, for example in tests, and to
+//! turn a printed program back into bytecode.
+//!
+//! ACIR opcodes are printed in full, while Brillig opcodes are printed using their `Debug`
+//! representation, one per line.
+
+use std::str::FromStr;
+
+use acir_field::AcirField;
+use brillig::{
+    BinaryFieldOp, BinaryIntOp, BitSize, BlackBoxOp, HeapArray, HeapValueType, HeapVector,
+    IntegerBitSize, MemoryAddress, Opcode as BrilligOpcode, ValueOrArray,
+};
+use thiserror::Error;
+
+use crate::circuit::brillig::{BrilligBytecode, BrilligFunctionId, BrilligInputs, BrilligOutputs};
+use crate::circuit::opcodes::{
+    AcirFunctionId, BlackBoxFuncCall, BlockId, BlockType, ConstantOrWitnessEnum, FunctionInput,
+    MemOp,
+};
+use crate::circuit::{
+    AssertionPayload, Circuit, ExpressionOrMemory, ExpressionWidth, Opcode, OpcodeLocation,
+    Program, PublicInputs,
+};
+use crate::native_types::{Expression, Witness};
+use crate::{BlackBoxFunc, InvalidInputBitSize};
+use lexer::{Lexer, LexerError};
+use token::{Span, SpannedToken, Token};
+
+mod lexer;
+mod tests;
+mod token;
+
+impl<F: AcirField> FromStr for Program<F> {
+    type Err = AcirParserErrorWithSource;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Parser::new(src)
+            .and_then(|mut parser| parser.parse_program())
+            .map_err(|error| AcirParserErrorWithSource::new(error, src))
+    }
+}
+
+impl<F: AcirField> FromStr for Circuit<F> {
+    type Err = AcirParserErrorWithSource;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Parser::new(src)
+            .and_then(|mut parser| {
+                let circuit = parser.parse_circuit()?;
+                parser.eat_or_error(Token::Eof)?;
+                Ok(circuit)
+            })
+            .map_err(|error| AcirParserErrorWithSource::new(error, src))
+    }
+}
+
+/// An error found while parsing ACIR, which is displayed along with the line of the source
+/// where it occurred.
+pub struct AcirParserErrorWithSource {
+    src: String,
+    error: ParserError,
+}
+
+impl AcirParserErrorWithSource {
+    fn new(error: ParserError, src: &str) -> Self {
+        Self { src: src.to_string(), error }
+    }
+}
+
+impl std::fmt::Display for AcirParserErrorWithSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.error.span();
+
+        let mut line_start: usize = 0;
+        for (line_index, line) in self.src.lines().enumerate() {
+            let line_end = line_start + line.len();
+            if line_start <= span.start && span.start <= line_end {
+                let offset = span.start - line_start;
+                let length = span.end.min(line_end).saturating_sub(span.start).max(1);
+                writeln!(f, "{} at line {}", self.error, line_index + 1)?;
+                writeln!(f, "{line}")?;
+                return write!(f, "{}{}", " ".repeat(offset), "^".repeat(length));
+            }
+            line_start = line_end + 1; // "+ 1" for the newline
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::fmt::Debug for AcirParserErrorWithSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for AcirParserErrorWithSource {}
+
+type ParseResult<T> = Result<T, ParserError>;
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    token: SpannedToken,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> ParseResult<Self> {
+        let lexer = Lexer::new(source);
+        let mut parser = Self { lexer, token: eof_spanned_token() };
+        parser.token = parser.read_token_internal()?;
+        Ok(parser)
+    }
+
+    fn parse_program<F: AcirField>(&mut self) -> ParseResult<Program<F>> {
+        let mut functions = Vec::new();
+        while self.eat_keyword("func")? {
+            self.parse_function_index(functions.len())?;
+            functions.push(self.parse_circuit()?);
+        }
+
+        let mut unconstrained_functions = Vec::new();
+        while self.eat_keyword("unconstrained")? {
+            self.eat_keyword_or_error("func")?;
+            self.parse_function_index(unconstrained_functions.len())?;
+            let mut bytecode = Vec::new();
+            while !self.at_keyword("unconstrained") && !self.at(Token::Eof) {
+                bytecode.push(self.parse_brillig_opcode()?);
+            }
+            unconstrained_functions.push(BrilligBytecode { bytecode });
+        }
+
+        self.eat_or_error(Token::Eof)?;
+        Ok(Program { functions, unconstrained_functions })
+    }
+
+    /// Functions are printed in order, so their indices are only checked.
+    fn parse_function_index(&mut self, expected: usize) -> ParseResult<()> {
+        let span = self.token.to_span();
+        let found = self.parse_integer()?;
+        if found == expected {
+            Ok(())
+        } else {
+            Err(ParserError::UnexpectedFunctionIndex { expected, found, span })
+        }
+    }
+
+    fn parse_circuit<F: AcirField>(&mut self) -> ParseResult<Circuit<F>> {
+        self.eat_keywords_or_error(&["current", "witness", "index"])?;
+        self.eat_or_error(Token::Colon)?;
+        let current_witness_index = self.parse_integer()?;
+
+        self.eat_keywords_or_error(&["private", "parameters", "indices"])?;
+        self.eat_or_error(Token::Colon)?;
+        let private_parameters = self.parse_list(Self::parse_witness_index)?.into_iter().collect();
+
+        self.eat_keywords_or_error(&["public", "parameters", "indices"])?;
+        self.eat_or_error(Token::Colon)?;
+        let public_parameters =
+            PublicInputs(self.parse_list(Self::parse_witness_index)?.into_iter().collect());
+
+        self.eat_keywords_or_error(&["return", "value", "indices"])?;
+        self.eat_or_error(Token::Colon)?;
+        let return_values =
+            PublicInputs(self.parse_list(Self::parse_witness_index)?.into_iter().collect());
+
+        self.eat_keywords_or_error(&["expression", "width"])?;
+        self.eat_or_error(Token::Colon)?;
+        let expression_width = self.parse_expression_width()?;
+
+        let mut opcodes = Vec::new();
+        while let Some(opcode) = self.parse_opcode()? {
+            opcodes.push(opcode);
+        }
+
+        let mut assert_messages = Vec::new();
+        while self.eat_keyword("ASSERT")? {
+            assert_messages.push(self.parse_assert_message()?);
+        }
+
+        Ok(Circuit {
+            current_witness_index,
+            opcodes,
+            expression_width,
+            private_parameters,
+            public_parameters,
+            return_values,
+            assert_messages,
+        })
+    }
+
+    fn parse_witness_index(&mut self) -> ParseResult<Witness> {
+        Ok(Witness(self.parse_integer()?))
+    }
+
+    fn parse_expression_width(&mut self) -> ParseResult<ExpressionWidth> {
+        if self.eat_keyword("Unbounded")? {
+            Ok(ExpressionWidth::Unbounded)
+        } else if self.eat_keyword("Bounded")? {
+            let width =
+                self.parse_struct(|parser| parser.parse_named("width", Self::parse_integer))?;
+            Ok(ExpressionWidth::Bounded { width })
+        } else {
+            self.expected_one_of_keywords(&["Unbounded", "Bounded"])
+        }
+    }
+
+    fn parse_opcode<F: AcirField>(&mut self) -> ParseResult<Option<Opcode<F>>> {
+        let Token::Ident(keyword) = self.token.token() else {
+            return Ok(None);
+        };
+        let opcode = match keyword.clone().as_str() {
+            "EXPR" => {
+                self.bump()?;
+                Opcode::AssertZero(self.parse_expression_terms()?)
+            }
+            "BLACKBOX" => {
+                self.bump()?;
+                Opcode::BlackBoxFuncCall(self.parse_black_box_func_call()?)
+            }
+            "MEM" => {
+                self.bump()?;
+                self.parse_memory_op()?
+            }
+            "INIT" => {
+                self.bump()?;
+                self.parse_memory_init()?
+            }
+            "BRILLIG" => {
+                self.bump()?;
+                self.parse_brillig_call()?
+            }
+            "CALL" => {
+                self.bump()?;
+                self.parse_call()?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(opcode))
+    }
+
+    /// Parses the terms of an expression as printed by an `AssertZero` opcode, that is
+    /// `[ (q_M, _i, _j)... (q_i, _i)... q_c ]`.
+    fn parse_expression_terms<F: AcirField>(&mut self) -> ParseResult<Expression<F>> {
+        self.eat_or_error(Token::LeftBracket)?;
+        let mut expression = Expression::default();
+        while self.eat(Token::LeftParen)? {
+            let coefficient = self.parse_field()?;
+            self.eat_or_error(Token::Comma)?;
+            let lhs = self.eat_witness_or_error()?;
+            if self.eat(Token::Comma)? {
+                let rhs = self.eat_witness_or_error()?;
+                expression.push_multiplication_term(coefficient, lhs, rhs);
+            } else {
+                expression.push_addition_term(coefficient, lhs);
+            }
+            self.eat_or_error(Token::RightParen)?;
+        }
+        expression.q_c = self.parse_field()?;
+        self.eat_or_error(Token::RightBracket)?;
+        Ok(expression)
+    }
+
+    /// Parses an expression used as an operand, which is printed as `xN` if it is a single
+    /// witness or as `%EXPR [ ... ]%` otherwise.
+    fn parse_expression<F: AcirField>(&mut self) -> ParseResult<Expression<F>> {
+        if self.eat(Token::Percent)? {
+            self.eat_keyword_or_error("EXPR")?;
+            let expression = self.parse_expression_terms()?;
+            self.eat_or_error(Token::Percent)?;
+            return Ok(expression);
+        }
+
+        if let Token::Ident(ident) = self.token.token() {
+            if let Some(index) = ident.strip_prefix('x').and_then(|index| index.parse().ok()) {
+                self.bump()?;
+                return Ok(Witness(index).into());
+            }
+        }
+
+        self.expected_expression()
+    }
+
+    fn parse_predicate<F: AcirField>(&mut self) -> ParseResult<Option<Expression<F>>> {
+        if self.eat_keyword("PREDICATE")? {
+            self.eat_or_error(Token::Assign)?;
+            Ok(Some(self.parse_expression()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_memory_op<F: AcirField>(&mut self) -> ParseResult<Opcode<F>> {
+        let predicate = self.parse_predicate()?;
+        self.eat_or_error(Token::LeftParen)?;
+        let block_id = self.parse_named("id", Self::parse_block_id)?;
+
+        let op = if self.eat_keyword("read")? {
+            let index = self.parse_named("at", Self::parse_expression)?;
+            let value = self.parse_named("value", Self::parse_expression)?;
+            MemOp { operation: Expression::zero(), index, value }
+        } else if self.eat_keyword("write")? {
+            let value = self.parse_expression()?;
+            let index = self.parse_named("at", Self::parse_expression)?;
+            MemOp { operation: Expression::one(), index, value }
+        } else if self.eat_keyword("op")? {
+            let operation = self.parse_expression()?;
+            let index = self.parse_named("at", Self::parse_expression)?;
+            let value = self.parse_named("value", Self::parse_expression)?;
+            MemOp { operation, index, value }
+        } else {
+            return self.expected_one_of_keywords(&["read", "write", "op"]);
+        };
+
+        self.eat_or_error(Token::RightParen)?;
+        Ok(Opcode::MemoryOp { block_id, op, predicate })
+    }
+
+    fn parse_memory_init<F: AcirField>(&mut self) -> ParseResult<Opcode<F>> {
+        let block_type = if self.eat_keyword("CALLDATA")? {
+            BlockType::CallData(self.parse_integer()?)
+        } else if self.eat_keyword("RETURNDATA")? {
+            BlockType::ReturnData
+        } else {
+            BlockType::Memory
+        };
+
+        self.eat_or_error(Token::LeftParen)?;
+        let block_id = self.parse_named("id", Self::parse_block_id)?;
+        let len: usize = self.parse_named("len", Self::parse_integer)?;
+        self.eat_keyword_or_error("witnesses")?;
+        self.eat_or_error(Token::Colon)?;
+        let span = self.token.to_span();
+        let init = self.parse_list(Self::eat_witness_or_error)?;
+        if init.len() != len {
+            return Err(ParserError::InvalidLength { expected: len, found: init.len(), span });
+        }
+        self.eat_or_error(Token::RightParen)?;
+
+        Ok(Opcode::MemoryInit { block_id, init, block_type })
+    }
+
+    fn parse_brillig_call<F: AcirField>(&mut self) -> ParseResult<Opcode<F>> {
+        self.eat_keywords_or_error(&["CALL", "func"])?;
+        let id = BrilligFunctionId(self.parse_integer()?);
+        self.eat_or_error(Token::Colon)?;
+        let predicate = self.parse_predicate()?;
+        let inputs =
+            self.parse_named("inputs", |parser| parser.parse_list(Self::parse_brillig_input))?;
+        let outputs =
+            self.parse_named("outputs", |parser| parser.parse_list(Self::parse_brillig_output))?;
+        Ok(Opcode::BrilligCall { id, inputs, outputs, predicate })
+    }
+
+    fn parse_brillig_input<F: AcirField>(&mut self) -> ParseResult<BrilligInputs<F>> {
+        if self.at(Token::LeftBracket) {
+            Ok(BrilligInputs::Array(self.parse_list(Self::parse_expression)?))
+        } else if self.eat_keyword("MEM")? {
+            Ok(BrilligInputs::MemoryArray(self.parse_block_id()?))
+        } else {
+            Ok(BrilligInputs::Single(self.parse_expression()?))
+        }
+    }
+
+    fn parse_brillig_output(&mut self) -> ParseResult<BrilligOutputs> {
+        if self.at(Token::LeftBracket) {
+            Ok(BrilligOutputs::Array(self.parse_list(Self::eat_witness_or_error)?))
+        } else {
+            Ok(BrilligOutputs::Simple(self.eat_witness_or_error()?))
+        }
+    }
+
+    fn parse_call<F: AcirField>(&mut self) -> ParseResult<Opcode<F>> {
+        self.eat_keyword_or_error("func")?;
+        let id = AcirFunctionId(self.parse_integer()?);
+        self.eat_or_error(Token::Colon)?;
+        let predicate = self.parse_predicate()?;
+        let inputs =
+            self.parse_named("inputs", |parser| parser.parse_list(Self::eat_witness_or_error))?;
+        let outputs =
+            self.parse_named("outputs", |parser| parser.parse_list(Self::eat_witness_or_error))?;
+        Ok(Opcode::Call { id, inputs, outputs, predicate })
+    }
+
+    fn parse_block_id(&mut self) -> ParseResult<BlockId> {
+        Ok(BlockId(self.parse_integer()?))
+    }
+
+    fn parse_assert_message<F: AcirField>(
+        &mut self,
+    ) -> ParseResult<(OpcodeLocation, AssertionPayload<F>)> {
+        self.eat_keyword_or_error("at")?;
+        let acir_index = self.parse_integer()?;
+        let location = if self.eat(Token::Dot)? {
+            OpcodeLocation::Brillig { acir_index, brillig_index: self.parse_integer()? }
+        } else {
+            OpcodeLocation::Acir(acir_index)
+        };
+        self.eat_or_error(Token::Colon)?;
+
+        self.eat_keyword_or_error("selector")?;
+        let error_selector = self.parse_integer()?;
+        self.eat_or_error(Token::Comma)?;
+        let payload = self.parse_named("payload", |parser| {
+            parser.parse_list(|parser| {
+                if parser.eat_keyword("MEM")? {
+                    Ok(ExpressionOrMemory::Memory(parser.parse_block_id()?))
+                } else {
+                    Ok(ExpressionOrMemory::Expression(parser.parse_expression()?))
+                }
+            })
+        })?;
+
+        Ok((location, AssertionPayload { error_selector, payload }))
+    }
+
+    fn parse_black_box_func_call<F: AcirField>(&mut self) -> ParseResult<BlackBoxFuncCall<F>> {
+        self.eat_or_error(Token::Colon)?;
+        self.eat_or_error(Token::Colon)?;
+        let (name, span) = self.eat_ident_or_error()?;
+        let Some(func) = BlackBoxFunc::lookup(&name.to_lowercase()) else {
+            return Err(ParserError::UnknownVariant { kind: "black box function", name, span });
+        };
+
+        let call = match func {
+            BlackBoxFunc::AES128Encrypt => BlackBoxFuncCall::AES128Encrypt {
+                inputs: self.parse_named("inputs", Self::parse_function_inputs)?,
+                iv: self.parse_named("iv", Self::parse_function_input_array)?,
+                key: self.parse_named("key", Self::parse_function_input_array)?,
+                outputs: self.parse_named("outputs", Self::parse_witnesses)?,
+            },
+            BlackBoxFunc::AND => BlackBoxFuncCall::AND {
+                lhs: self.parse_named("lhs", Self::parse_function_input)?,
+                rhs: self.parse_named("rhs", Self::parse_function_input)?,
+                output: self.parse_named("output", Self::eat_witness_or_error)?,
+            },
+            BlackBoxFunc::XOR => BlackBoxFuncCall::XOR {
+                lhs: self.parse_named("lhs", Self::parse_function_input)?,
+                rhs: self.parse_named("rhs", Self::parse_function_input)?,
+                output: self.parse_named("output", Self::eat_witness_or_error)?,
+            },
+            BlackBoxFunc::RANGE => BlackBoxFuncCall::RANGE {
+                input: self.parse_named("input", Self::parse_function_input)?,
+            },
+            BlackBoxFunc::Blake2s => BlackBoxFuncCall::Blake2s {
+                inputs: self.parse_named("inputs", Self::parse_function_inputs)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_array)?,
+            },
+            BlackBoxFunc::Blake3 => BlackBoxFuncCall::Blake3 {
+                inputs: self.parse_named("inputs", Self::parse_function_inputs)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_array)?,
+            },
+            BlackBoxFunc::EcdsaSecp256k1 => BlackBoxFuncCall::EcdsaSecp256k1 {
+                public_key_x: self.parse_named("public_key_x", Self::parse_function_input_array)?,
+                public_key_y: self.parse_named("public_key_y", Self::parse_function_input_array)?,
+                signature: self.parse_named("signature", Self::parse_function_input_array)?,
+                hashed_message: self
+                    .parse_named("hashed_message", Self::parse_function_input_array)?,
+                output: self.parse_named("output", Self::eat_witness_or_error)?,
+            },
+            BlackBoxFunc::EcdsaSecp256r1 => BlackBoxFuncCall::EcdsaSecp256r1 {
+                public_key_x: self.parse_named("public_key_x", Self::parse_function_input_array)?,
+                public_key_y: self.parse_named("public_key_y", Self::parse_function_input_array)?,
+                signature: self.parse_named("signature", Self::parse_function_input_array)?,
+                hashed_message: self
+                    .parse_named("hashed_message", Self::parse_function_input_array)?,
+                output: self.parse_named("output", Self::eat_witness_or_error)?,
+            },
+            BlackBoxFunc::MultiScalarMul => BlackBoxFuncCall::MultiScalarMul {
+                points: self.parse_named("points", Self::parse_function_inputs)?,
+                scalars: self.parse_named("scalars", Self::parse_function_inputs)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_triple)?,
+            },
+            BlackBoxFunc::EmbeddedCurveAdd => BlackBoxFuncCall::EmbeddedCurveAdd {
+                input1: self.parse_named("input1", Self::parse_function_input_array)?,
+                input2: self.parse_named("input2", Self::parse_function_input_array)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_triple)?,
+            },
+            BlackBoxFunc::Keccakf1600 => BlackBoxFuncCall::Keccakf1600 {
+                inputs: self.parse_named("inputs", Self::parse_function_input_array)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_array)?,
+            },
+            BlackBoxFunc::RecursiveAggregation => BlackBoxFuncCall::RecursiveAggregation {
+                verification_key: self
+                    .parse_named("verification_key", Self::parse_function_inputs)?,
+                proof: self.parse_named("proof", Self::parse_function_inputs)?,
+                public_inputs: self.parse_named("public_inputs", Self::parse_function_inputs)?,
+                key_hash: self.parse_named("key_hash", Self::parse_function_input)?,
+                proof_type: self.parse_named("proof_type", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntAdd => BlackBoxFuncCall::BigIntAdd {
+                lhs: self.parse_named("lhs", Self::parse_integer)?,
+                rhs: self.parse_named("rhs", Self::parse_integer)?,
+                output: self.parse_named("output", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntSub => BlackBoxFuncCall::BigIntSub {
+                lhs: self.parse_named("lhs", Self::parse_integer)?,
+                rhs: self.parse_named("rhs", Self::parse_integer)?,
+                output: self.parse_named("output", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntMul => BlackBoxFuncCall::BigIntMul {
+                lhs: self.parse_named("lhs", Self::parse_integer)?,
+                rhs: self.parse_named("rhs", Self::parse_integer)?,
+                output: self.parse_named("output", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntDiv => BlackBoxFuncCall::BigIntDiv {
+                lhs: self.parse_named("lhs", Self::parse_integer)?,
+                rhs: self.parse_named("rhs", Self::parse_integer)?,
+                output: self.parse_named("output", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntFromLeBytes => BlackBoxFuncCall::BigIntFromLeBytes {
+                inputs: self.parse_named("inputs", Self::parse_function_inputs)?,
+                modulus: self
+                    .parse_named("modulus", |parser| parser.parse_list(Self::parse_integer))?,
+                output: self.parse_named("output", Self::parse_integer)?,
+            },
+            BlackBoxFunc::BigIntToLeBytes => BlackBoxFuncCall::BigIntToLeBytes {
+                input: self.parse_named("input", Self::parse_integer)?,
+                outputs: self.parse_named("outputs", Self::parse_witnesses)?,
+            },
+            BlackBoxFunc::Poseidon2Permutation => BlackBoxFuncCall::Poseidon2Permutation {
+                inputs: self.parse_named("inputs", Self::parse_function_inputs)?,
+                outputs: self.parse_named("outputs", Self::parse_witnesses)?,
+                len: self.parse_named("len", Self::parse_integer)?,
+            },
+            BlackBoxFunc::Sha256Compression => BlackBoxFuncCall::Sha256Compression {
+                inputs: self.parse_named("inputs", Self::parse_function_input_array)?,
+                hash_values: self.parse_named("hash_values", Self::parse_function_input_array)?,
+                outputs: self.parse_named("outputs", Self::parse_witness_array)?,
+            },
+        };
+        Ok(call)
+    }
+
+    /// Parses a black box function input, printed as `(_N, num_bits)` for a witness or as
+    /// `(value, num_bits)` for a constant.
+    fn parse_function_input<F: AcirField>(&mut self) -> ParseResult<FunctionInput<F>> {
+        self.eat_or_error(Token::LeftParen)?;
+        let span = self.token.to_span();
+        let input = match self.eat_witness()? {
+            Some(witness) => ConstantOrWitnessEnum::Witness(witness),
+            None => ConstantOrWitnessEnum::Constant(self.parse_field()?),
+        };
+        self.eat_or_error(Token::Comma)?;
+        let num_bits = self.parse_integer()?;
+        self.eat_or_error(Token::RightParen)?;
+
+        match input {
+            ConstantOrWitnessEnum::Witness(witness) => {
+                Ok(FunctionInput::witness(witness, num_bits))
+            }
+            ConstantOrWitnessEnum::Constant(constant) => {
+                FunctionInput::constant(constant, num_bits)
+                    .map_err(|error| ParserError::InvalidInputBitSize { error, span })
+            }
+        }
+    }
+
+    fn parse_function_inputs<F: AcirField>(&mut self) -> ParseResult<Vec<FunctionInput<F>>> {
+        self.parse_list(Self::parse_function_input)
+    }
+
+    fn parse_function_input_array<F: AcirField, const N: usize>(
+        &mut self,
+    ) -> ParseResult<Box<[FunctionInput<F>; N]>> {
+        let span = self.token.to_span();
+        let inputs = self.parse_function_inputs()?;
+        let found = inputs.len();
+        inputs.try_into().map_err(|_| ParserError::InvalidLength { expected: N, found, span })
+    }
+
+    fn parse_witnesses(&mut self) -> ParseResult<Vec<Witness>> {
+        self.parse_list(Self::eat_witness_or_error)
+    }
+
+    fn parse_witness_array<const N: usize>(&mut self) -> ParseResult<Box<[Witness; N]>> {
+        let span = self.token.to_span();
+        let witnesses = self.parse_witnesses()?;
+        let found = witnesses.len();
+        witnesses.try_into().map_err(|_| ParserError::InvalidLength { expected: N, found, span })
+    }
+
+    fn parse_witness_triple(&mut self) -> ParseResult<(Witness, Witness, Witness)> {
+        let [first, second, third] = *self.parse_witness_array::<3>()?;
+        Ok((first, second, third))
+    }
+
+    fn parse_brillig_opcode<F: AcirField>(&mut self) -> ParseResult<BrilligOpcode<F>> {
+        let (name, span) = self.eat_ident_or_error()?;
+        let opcode = match name.as_str() {
+            "BinaryFieldOp" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::BinaryFieldOp {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    op: parser.parse_named("op", Self::parse_binary_field_op)?,
+                    lhs: parser.parse_named("lhs", Self::parse_memory_address)?,
+                    rhs: parser.parse_named("rhs", Self::parse_memory_address)?,
+                })
+            })?,
+            "BinaryIntOp" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::BinaryIntOp {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    op: parser.parse_named("op", Self::parse_binary_int_op)?,
+                    bit_size: parser.parse_named("bit_size", Self::parse_integer_bit_size)?,
+                    lhs: parser.parse_named("lhs", Self::parse_memory_address)?,
+                    rhs: parser.parse_named("rhs", Self::parse_memory_address)?,
+                })
+            })?,
+            "Not" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Not {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    source: parser.parse_named("source", Self::parse_memory_address)?,
+                    bit_size: parser.parse_named("bit_size", Self::parse_integer_bit_size)?,
+                })
+            })?,
+            "Cast" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Cast {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    source: parser.parse_named("source", Self::parse_memory_address)?,
+                    bit_size: parser.parse_named("bit_size", Self::parse_bit_size)?,
+                })
+            })?,
+            "JumpIfNot" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::JumpIfNot {
+                    condition: parser.parse_named("condition", Self::parse_memory_address)?,
+                    location: parser.parse_named("location", Self::parse_integer)?,
+                })
+            })?,
+            "JumpIf" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::JumpIf {
+                    condition: parser.parse_named("condition", Self::parse_memory_address)?,
+                    location: parser.parse_named("location", Self::parse_integer)?,
+                })
+            })?,
+            "Jump" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Jump {
+                    location: parser.parse_named("location", Self::parse_integer)?,
+                })
+            })?,
+            "CalldataCopy" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::CalldataCopy {
+                    destination_address: parser
+                        .parse_named("destination_address", Self::parse_memory_address)?,
+                    size_address: parser.parse_named("size_address", Self::parse_memory_address)?,
+                    offset_address: parser
+                        .parse_named("offset_address", Self::parse_memory_address)?,
+                })
+            })?,
+            "Call" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Call {
+                    location: parser.parse_named("location", Self::parse_integer)?,
+                })
+            })?,
+            "Const" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Const {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    bit_size: parser.parse_named("bit_size", Self::parse_bit_size)?,
+                    value: parser.parse_named("value", Self::parse_field)?,
+                })
+            })?,
+            "IndirectConst" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::IndirectConst {
+                    destination_pointer: parser
+                        .parse_named("destination_pointer", Self::parse_memory_address)?,
+                    bit_size: parser.parse_named("bit_size", Self::parse_bit_size)?,
+                    value: parser.parse_named("value", Self::parse_field)?,
+                })
+            })?,
+            "Return" => BrilligOpcode::Return,
+            "ForeignCall" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::ForeignCall {
+                    function: parser.parse_named("function", Self::eat_str_or_error)?,
+                    destinations: parser.parse_named("destinations", |parser| {
+                        parser.parse_list(Self::parse_value_or_array)
+                    })?,
+                    destination_value_types: parser
+                        .parse_named("destination_value_types", |parser| {
+                            parser.parse_list(Self::parse_heap_value_type)
+                        })?,
+                    inputs: parser.parse_named("inputs", |parser| {
+                        parser.parse_list(Self::parse_value_or_array)
+                    })?,
+                    input_value_types: parser.parse_named("input_value_types", |parser| {
+                        parser.parse_list(Self::parse_heap_value_type)
+                    })?,
+                })
+            })?,
+            "Mov" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Mov {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    source: parser.parse_named("source", Self::parse_memory_address)?,
+                })
+            })?,
+            "ConditionalMov" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::ConditionalMov {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    source_a: parser.parse_named("source_a", Self::parse_memory_address)?,
+                    source_b: parser.parse_named("source_b", Self::parse_memory_address)?,
+                    condition: parser.parse_named("condition", Self::parse_memory_address)?,
+                })
+            })?,
+            "Load" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Load {
+                    destination: parser.parse_named("destination", Self::parse_memory_address)?,
+                    source_pointer: parser
+                        .parse_named("source_pointer", Self::parse_memory_address)?,
+                })
+            })?,
+            "Store" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Store {
+                    destination_pointer: parser
+                        .parse_named("destination_pointer", Self::parse_memory_address)?,
+                    source: parser.parse_named("source", Self::parse_memory_address)?,
+                })
+            })?,
+            "BlackBox" => BrilligOpcode::BlackBox(self.parse_tuple(Self::parse_black_box_op)?),
+            "Trap" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Trap {
+                    revert_data: parser.parse_named("revert_data", Self::parse_heap_vector)?,
+                })
+            })?,
+            "Stop" => self.parse_struct(|parser| {
+                Ok(BrilligOpcode::Stop {
+                    return_data: parser.parse_named("return_data", Self::parse_heap_vector)?,
+                })
+            })?,
+            _ => return Err(ParserError::UnknownVariant { kind: "Brillig opcode", name, span }),
+        };
+        Ok(opcode)
+    }
+
+    fn parse_black_box_op(&mut self) -> ParseResult<BlackBoxOp> {
+        let (name, span) = self.eat_ident_or_error()?;
+        let op = match name.as_str() {
+            "AES128Encrypt" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::AES128Encrypt {
+                    inputs: parser.parse_named("inputs", Self::parse_heap_vector)?,
+                    iv: parser.parse_named("iv", Self::parse_heap_array)?,
+                    key: parser.parse_named("key", Self::parse_heap_array)?,
+                    outputs: parser.parse_named("outputs", Self::parse_heap_vector)?,
+                })
+            })?,
+            "Blake2s" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::Blake2s {
+                    message: parser.parse_named("message", Self::parse_heap_vector)?,
+                    output: parser.parse_named("output", Self::parse_heap_array)?,
+                })
+            })?,
+            "Blake3" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::Blake3 {
+                    message: parser.parse_named("message", Self::parse_heap_vector)?,
+                    output: parser.parse_named("output", Self::parse_heap_array)?,
+                })
+            })?,
+            "Keccakf1600" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::Keccakf1600 {
+                    input: parser.parse_named("input", Self::parse_heap_array)?,
+                    output: parser.parse_named("output", Self::parse_heap_array)?,
+                })
+            })?,
+            "EcdsaSecp256k1" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::EcdsaSecp256k1 {
+                    hashed_msg: parser.parse_named("hashed_msg", Self::parse_heap_vector)?,
+                    public_key_x: parser.parse_named("public_key_x", Self::parse_heap_array)?,
+                    public_key_y: parser.parse_named("public_key_y", Self::parse_heap_array)?,
+                    signature: parser.parse_named("signature", Self::parse_heap_array)?,
+                    result: parser.parse_named("result", Self::parse_memory_address)?,
+                })
+            })?,
+            "EcdsaSecp256r1" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::EcdsaSecp256r1 {
+                    hashed_msg: parser.parse_named("hashed_msg", Self::parse_heap_vector)?,
+                    public_key_x: parser.parse_named("public_key_x", Self::parse_heap_array)?,
+                    public_key_y: parser.parse_named("public_key_y", Self::parse_heap_array)?,
+                    signature: parser.parse_named("signature", Self::parse_heap_array)?,
+                    result: parser.parse_named("result", Self::parse_memory_address)?,
+                })
+            })?,
+            "MultiScalarMul" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::MultiScalarMul {
+                    points: parser.parse_named("points", Self::parse_heap_vector)?,
+                    scalars: parser.parse_named("scalars", Self::parse_heap_vector)?,
+                    outputs: parser.parse_named("outputs", Self::parse_heap_array)?,
+                })
+            })?,
+            "EmbeddedCurveAdd" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::EmbeddedCurveAdd {
+                    input1_x: parser.parse_named("input1_x", Self::parse_memory_address)?,
+                    input1_y: parser.parse_named("input1_y", Self::parse_memory_address)?,
+                    input1_infinite: parser
+                        .parse_named("input1_infinite", Self::parse_memory_address)?,
+                    input2_x: parser.parse_named("input2_x", Self::parse_memory_address)?,
+                    input2_y: parser.parse_named("input2_y", Self::parse_memory_address)?,
+                    input2_infinite: parser
+                        .parse_named("input2_infinite", Self::parse_memory_address)?,
+                    result: parser.parse_named("result", Self::parse_heap_array)?,
+                })
+            })?,
+            "BigIntAdd" | "BigIntSub" | "BigIntMul" | "BigIntDiv" => {
+                let (lhs, rhs, output) = self.parse_struct(|parser| {
+                    Ok((
+                        parser.parse_named("lhs", Self::parse_memory_address)?,
+                        parser.parse_named("rhs", Self::parse_memory_address)?,
+                        parser.parse_named("output", Self::parse_memory_address)?,
+                    ))
+                })?;
+                match name.as_str() {
+                    "BigIntAdd" => BlackBoxOp::BigIntAdd { lhs, rhs, output },
+                    "BigIntSub" => BlackBoxOp::BigIntSub { lhs, rhs, output },
+                    "BigIntMul" => BlackBoxOp::BigIntMul { lhs, rhs, output },
+                    _ => BlackBoxOp::BigIntDiv { lhs, rhs, output },
+                }
+            }
+            "BigIntFromLeBytes" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::BigIntFromLeBytes {
+                    inputs: parser.parse_named("inputs", Self::parse_heap_vector)?,
+                    modulus: parser.parse_named("modulus", Self::parse_heap_vector)?,
+                    output: parser.parse_named("output", Self::parse_memory_address)?,
+                })
+            })?,
+            "BigIntToLeBytes" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::BigIntToLeBytes {
+                    input: parser.parse_named("input", Self::parse_memory_address)?,
+                    output: parser.parse_named("output", Self::parse_heap_vector)?,
+                })
+            })?,
+            "Poseidon2Permutation" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::Poseidon2Permutation {
+                    message: parser.parse_named("message", Self::parse_heap_vector)?,
+                    output: parser.parse_named("output", Self::parse_heap_array)?,
+                    len: parser.parse_named("len", Self::parse_memory_address)?,
+                })
+            })?,
+            "Sha256Compression" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::Sha256Compression {
+                    input: parser.parse_named("input", Self::parse_heap_array)?,
+                    hash_values: parser.parse_named("hash_values", Self::parse_heap_array)?,
+                    output: parser.parse_named("output", Self::parse_heap_array)?,
+                })
+            })?,
+            "ToRadix" => self.parse_struct(|parser| {
+                Ok(BlackBoxOp::ToRadix {
+                    input: parser.parse_named("input", Self::parse_memory_address)?,
+                    radix: parser.parse_named("radix", Self::parse_memory_address)?,
+                    output_pointer: parser
+                        .parse_named("output_pointer", Self::parse_memory_address)?,
+                    num_limbs: parser.parse_named("num_limbs", Self::parse_memory_address)?,
+                    output_bits: parser.parse_named("output_bits", Self::parse_memory_address)?,
+                })
+            })?,
+            _ => return Err(ParserError::UnknownVariant { kind: "Brillig black box", name, span }),
+        };
+        Ok(op)
+    }
+
+    fn parse_memory_address(&mut self) -> ParseResult<MemoryAddress> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "Direct" => Ok(MemoryAddress::Direct(self.parse_tuple(Self::parse_integer)?)),
+            "Relative" => Ok(MemoryAddress::Relative(self.parse_tuple(Self::parse_integer)?)),
+            _ => Err(ParserError::UnknownVariant { kind: "memory address", name, span }),
+        }
+    }
+
+    fn parse_heap_array(&mut self) -> ParseResult<HeapArray> {
+        self.eat_keyword_or_error("HeapArray")?;
+        self.parse_struct(|parser| {
+            Ok(HeapArray {
+                pointer: parser.parse_named("pointer", Self::parse_memory_address)?,
+                size: parser.parse_named("size", Self::parse_integer)?,
+            })
+        })
+    }
+
+    fn parse_heap_vector(&mut self) -> ParseResult<HeapVector> {
+        self.eat_keyword_or_error("HeapVector")?;
+        self.parse_struct(|parser| {
+            Ok(HeapVector {
+                pointer: parser.parse_named("pointer", Self::parse_memory_address)?,
+                size: parser.parse_named("size", Self::parse_memory_address)?,
+            })
+        })
+    }
+
+    fn parse_value_or_array(&mut self) -> ParseResult<ValueOrArray> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "MemoryAddress" => {
+                Ok(ValueOrArray::MemoryAddress(self.parse_tuple(Self::parse_memory_address)?))
+            }
+            "HeapArray" => Ok(ValueOrArray::HeapArray(self.parse_tuple(Self::parse_heap_array)?)),
+            "HeapVector" => {
+                Ok(ValueOrArray::HeapVector(self.parse_tuple(Self::parse_heap_vector)?))
+            }
+            _ => Err(ParserError::UnknownVariant { kind: "value or array", name, span }),
+        }
+    }
+
+    fn parse_heap_value_type(&mut self) -> ParseResult<HeapValueType> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "Simple" => Ok(HeapValueType::Simple(self.parse_tuple(Self::parse_bit_size)?)),
+            "Array" => self.parse_struct(|parser| {
+                Ok(HeapValueType::Array {
+                    value_types: parser.parse_named("value_types", |parser| {
+                        parser.parse_list(Self::parse_heap_value_type)
+                    })?,
+                    size: parser.parse_named("size", Self::parse_integer)?,
+                })
+            }),
+            "Vector" => self.parse_struct(|parser| {
+                Ok(HeapValueType::Vector {
+                    value_types: parser.parse_named("value_types", |parser| {
+                        parser.parse_list(Self::parse_heap_value_type)
+                    })?,
+                })
+            }),
+            _ => Err(ParserError::UnknownVariant { kind: "heap value type", name, span }),
+        }
+    }
+
+    fn parse_bit_size(&mut self) -> ParseResult<BitSize> {
+        if self.eat_keyword("Field")? {
+            Ok(BitSize::Field)
+        } else if self.eat_keyword("Integer")? {
+            Ok(BitSize::Integer(self.parse_tuple(Self::parse_integer_bit_size)?))
+        } else {
+            self.expected_one_of_keywords(&["Field", "Integer"])
+        }
+    }
+
+    fn parse_integer_bit_size(&mut self) -> ParseResult<IntegerBitSize> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "U1" => Ok(IntegerBitSize::U1),
+            "U8" => Ok(IntegerBitSize::U8),
+            "U16" => Ok(IntegerBitSize::U16),
+            "U32" => Ok(IntegerBitSize::U32),
+            "U64" => Ok(IntegerBitSize::U64),
+            "U128" => Ok(IntegerBitSize::U128),
+            _ => Err(ParserError::UnknownVariant { kind: "integer bit size", name, span }),
+        }
+    }
+
+    fn parse_binary_field_op(&mut self) -> ParseResult<BinaryFieldOp> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "Add" => Ok(BinaryFieldOp::Add),
+            "Sub" => Ok(BinaryFieldOp::Sub),
+            "Mul" => Ok(BinaryFieldOp::Mul),
+            "Div" => Ok(BinaryFieldOp::Div),
+            "IntegerDiv" => Ok(BinaryFieldOp::IntegerDiv),
+            "Equals" => Ok(BinaryFieldOp::Equals),
+            "LessThan" => Ok(BinaryFieldOp::LessThan),
+            "LessThanEquals" => Ok(BinaryFieldOp::LessThanEquals),
+            _ => Err(ParserError::UnknownVariant { kind: "binary field operation", name, span }),
+        }
+    }
+
+    fn parse_binary_int_op(&mut self) -> ParseResult<BinaryIntOp> {
+        let (name, span) = self.eat_ident_or_error()?;
+        match name.as_str() {
+            "Add" => Ok(BinaryIntOp::Add),
+            "Sub" => Ok(BinaryIntOp::Sub),
+            "Mul" => Ok(BinaryIntOp::Mul),
+            "Div" => Ok(BinaryIntOp::Div),
+            "Equals" => Ok(BinaryIntOp::Equals),
+            "LessThan" => Ok(BinaryIntOp::LessThan),
+            "LessThanEquals" => Ok(BinaryIntOp::LessThanEquals),
+            "And" => Ok(BinaryIntOp::And),
+            "Or" => Ok(BinaryIntOp::Or),
+            "Xor" => Ok(BinaryIntOp::Xor),
+            "Shl" => Ok(BinaryIntOp::Shl),
+            "Shr" => Ok(BinaryIntOp::Shr),
+            _ => Err(ParserError::UnknownVariant { kind: "binary integer operation", name, span }),
+        }
+    }
+
+    /// Parses `name: value`, followed by an optional comma.
+    fn parse_named<T>(
+        &mut self,
+        name: &str,
+        parse_value: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        self.eat_keyword_or_error(name)?;
+        self.eat_or_error(Token::Colon)?;
+        let value = parse_value(self)?;
+        self.eat(Token::Comma)?;
+        Ok(value)
+    }
+
+    /// Parses `[item, item, ...]`.
+    fn parse_list<T>(
+        &mut self,
+        parse_item: impl Fn(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<Vec<T>> {
+        self.eat_or_error(Token::LeftBracket)?;
+        let mut items = Vec::new();
+        while !self.at(Token::RightBracket) {
+            items.push(parse_item(self)?);
+            if !self.eat(Token::Comma)? {
+                break;
+            }
+        }
+        self.eat_or_error(Token::RightBracket)?;
+        Ok(items)
+    }
+
+    /// Parses `{ fields }`, as printed by `Debug` for structs.
+    fn parse_struct<T>(
+        &mut self,
+        parse_fields: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        self.eat_or_error(Token::LeftBrace)?;
+        let value = parse_fields(self)?;
+        self.eat_or_error(Token::RightBrace)?;
+        Ok(value)
+    }
+
+    /// Parses `(value)`, as printed by `Debug` for tuple structs and variants.
+    fn parse_tuple<T>(
+        &mut self,
+        parse_value: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        self.eat_or_error(Token::LeftParen)?;
+        let value = parse_value(self)?;
+        self.eat_or_error(Token::RightParen)?;
+        Ok(value)
+    }
+
+    fn parse_field<F: AcirField>(&mut self) -> ParseResult<F> {
+        let negative = self.eat(Token::Dash)?;
+        let Token::Int(digits) = self.token.token() else {
+            return self.expected_int();
+        };
+        let ten = F::from(10_u128);
+        let value = digits
+            .bytes()
+            .fold(F::zero(), |value, digit| value * ten + F::from(u128::from(digit - b'0')));
+        self.bump()?;
+        Ok(if negative { -value } else { value })
+    }
+
+    fn parse_integer<T: FromStr>(&mut self) -> ParseResult<T> {
+        let Token::Int(digits) = self.token.token() else {
+            return self.expected_int();
+        };
+        let span = self.token.to_span();
+        let value = digits
+            .parse()
+            .map_err(|_| ParserError::IntegerOutOfRange { value: digits.clone(), span })?;
+        self.bump()?;
+        Ok(value)
+    }
+
+    fn eat_witness(&mut self) -> ParseResult<Option<Witness>> {
+        if let Token::Witness(index) = self.token.token() {
+            let witness = Witness(*index);
+            self.bump()?;
+            Ok(Some(witness))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn eat_witness_or_error(&mut self) -> ParseResult<Witness> {
+        if let Some(witness) = self.eat_witness()? {
+            Ok(witness)
+        } else {
+            self.expected_witness()
+        }
+    }
+
+    fn eat_str_or_error(&mut self) -> ParseResult<String> {
+        if let Token::Str(string) = self.token.token() {
+            let string = string.clone();
+            self.bump()?;
+            Ok(string)
+        } else {
+            self.expected_string()
+        }
+    }
+
+    fn eat_ident_or_error(&mut self) -> ParseResult<(String, Span)> {
+        if let Token::Ident(ident) = self.token.token() {
+            let ident = ident.clone();
+            let span = self.token.to_span();
+            self.bump()?;
+            Ok((ident, span))
+        } else {
+            self.expected_identifier()
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> ParseResult<bool> {
+        if self.at_keyword(keyword) {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_keyword_or_error(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword)? {
+            Ok(())
+        } else {
+            self.expected_keyword(keyword)
+        }
+    }
+
+    fn eat_keywords_or_error(&mut self, keywords: &[&str]) -> ParseResult<()> {
+        for keyword in keywords {
+            self.eat_keyword_or_error(keyword)?;
+        }
+        Ok(())
+    }
+
+    fn eat(&mut self, token: Token) -> ParseResult<bool> {
+        if self.token.token() == &token {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_or_error(&mut self, token: Token) -> ParseResult<()> {
+        if self.eat(token.clone())? {
+            Ok(())
+        } else {
+            self.expected_token(token)
+        }
+    }
+
+    fn at(&self, token: Token) -> bool {
+        self.token.token() == &token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.token.token(), Token::Ident(ident) if ident == keyword)
+    }
+
+    fn bump(&mut self) -> ParseResult<SpannedToken> {
+        let token = self.read_token_internal()?;
+        Ok(std::mem::replace(&mut self.token, token))
+    }
+
+    fn read_token_internal(&mut self) -> ParseResult<SpannedToken> {
+        self.lexer.next_token().map_err(ParserError::LexerError)
+    }
+
+    fn expected_identifier<T>(&mut self) -> ParseResult<T> {
+        Err(ParserError::ExpectedIdentifier {
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_int<T>(&mut self) -> ParseResult<T> {
+        Err(ParserError::ExpectedInt {
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_witness<T>(&mut self) -> ParseResult<T> {
+        Err(ParserError::ExpectedWitness {
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_expression<T>(&mut self) -> ParseResult<T> {
+        Err(ParserError::ExpectedExpression {
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_string<T>(&mut self) -> ParseResult<T> {
+        Err(ParserError::ExpectedString {
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_keyword<T>(&mut self, keyword: &str) -> ParseResult<T> {
+        Err(ParserError::ExpectedKeyword {
+            keyword: keyword.to_string(),
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_one_of_keywords<T>(&mut self, keywords: &[&str]) -> ParseResult<T> {
+        Err(ParserError::ExpectedOneOfKeywords {
+            keywords: keywords.iter().map(ToString::to_string).collect(),
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+
+    fn expected_token<T>(&mut self, token: Token) -> ParseResult<T> {
+        Err(ParserError::ExpectedToken {
+            token,
+            found: self.token.token().clone(),
+            span: self.token.to_span(),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum ParserError {
+    #[error("{0}")]
+    LexerError(LexerError),
+    #[error("Expected '{token}', found '{found}'")]
+    ExpectedToken { token: Token, found: Token, span: Span },
+    #[error("Expected '{keyword}', found '{found}'")]
+    ExpectedKeyword { keyword: String, found: Token, span: Span },
+    #[error("Expected one of {keywords:?}, found '{found}'")]
+    ExpectedOneOfKeywords { keywords: Vec<String>, found: Token, span: Span },
+    #[error("Expected an identifier, found '{found}'")]
+    ExpectedIdentifier { found: Token, span: Span },
+    #[error("Expected an integer, found '{found}'")]
+    ExpectedInt { found: Token, span: Span },
+    #[error("Expected a witness, found '{found}'")]
+    ExpectedWitness { found: Token, span: Span },
+    #[error("Expected an expression, found '{found}'")]
+    ExpectedExpression { found: Token, span: Span },
+    #[error("Expected a string literal, found '{found}'")]
+    ExpectedString { found: Token, span: Span },
+    #[error("Integer {value} is out of range")]
+    IntegerOutOfRange { value: String, span: Span },
+    #[error("Unknown {kind} '{name}'")]
+    UnknownVariant { kind: &'static str, name: String, span: Span },
+    #[error("Expected {expected} elements, found {found}")]
+    InvalidLength { expected: usize, found: usize, span: Span },
+    #[error("{error}")]
+    InvalidInputBitSize { error: InvalidInputBitSize, span: Span },
+    #[error("Expected function {expected}, found function {found}")]
+    UnexpectedFunctionIndex { expected: usize, found: usize, span: Span },
+}
+
+impl ParserError {
+    fn span(&self) -> Span {
+        match self {
+            ParserError::LexerError(error) => error.span(),
+            ParserError::ExpectedToken { span, .. }
+            | ParserError::ExpectedKeyword { span, .. }
+            | ParserError::ExpectedOneOfKeywords { span, .. }
+            | ParserError::ExpectedIdentifier { span, .. }
+            | ParserError::ExpectedInt { span, .. }
+            | ParserError::ExpectedWitness { span, .. }
+            | ParserError::ExpectedExpression { span, .. }
+            | ParserError::ExpectedString { span, .. }
+            | ParserError::IntegerOutOfRange { span, .. }
+            | ParserError::UnknownVariant { span, .. }
+            | ParserError::InvalidLength { span, .. }
+            | ParserError::InvalidInputBitSize { span, .. }
+            | ParserError::UnexpectedFunctionIndex { span, .. } => *span,
+        }
+    }
+}
+
+fn eof_spanned_token() -> SpannedToken {
+    SpannedToken::new(Token::Eof, Default::default())
+}
@@ -0,0 +1,652 @@
+#![cfg(test)]
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use acir_field::{AcirField, FieldElement};
+use brillig::{
+    BinaryFieldOp, BinaryIntOp, BitSize, BlackBoxOp, HeapArray, HeapValueType, HeapVector,
+    IntegerBitSize, MemoryAddress, Opcode as BrilligOpcode, ValueOrArray,
+};
+
+use crate::circuit::brillig::{BrilligBytecode, BrilligFunctionId, BrilligInputs, BrilligOutputs};
+use crate::circuit::opcodes::{
+    AcirFunctionId, BlackBoxFuncCall, BlockId, BlockType, FunctionInput, MemOp,
+};
+use crate::circuit::{
+    AssertionPayload, Circuit, ExpressionOrMemory, ExpressionWidth, Opcode, OpcodeLocation,
+    Program, PublicInputs,
+};
+use crate::native_types::{Expression, Witness};
+use crate::BlackBoxFunc;
+use strum::IntoEnumIterator;
+
+/// Trims each line and drops empty ones, as circuits are followed by an empty line and some
+/// opcodes by a trailing space.
+fn trim_lines(src: &str) -> String {
+    src.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+fn assert_program_roundtrip(src: &str) {
+    let program = Program::<FieldElement>::from_str(src).unwrap();
+    let printed = trim_lines(&program.to_string());
+    let src = trim_lines(src);
+    assert_eq!(printed, src);
+}
+
+#[test]
+fn roundtrips_circuit_with_arithmetic_opcodes() {
+    let src = "
+        func 0
+        current witness index : 3
+        private parameters indices : [0, 1]
+        public parameters indices : [2]
+        return value indices : [3]
+        expression width : Bounded { width: 4 }
+        EXPR [ (1, _0, _1) (-1, _3) 0 ]
+        EXPR [ (2, _1) (1, _2) (-1, _3) 5 ]
+        EXPR [ 0 ]
+        ";
+    assert_program_roundtrip(src);
+}
+
+#[test]
+fn roundtrips_memory_opcodes_and_calls() {
+    let src = "
+        func 0
+        current witness index : 6
+        private parameters indices : [0, 1, 2]
+        public parameters indices : []
+        return value indices : [6]
+        expression width : Unbounded
+        INIT (id: 0, len: 2, witnesses: [_0, _1])
+        INIT CALLDATA 1 (id: 1, len: 1, witnesses: [_2])
+        INIT RETURNDATA (id: 2, len: 0, witnesses: [])
+        MEM (id: 0, read at: x2, value: x3)
+        MEM PREDICATE = x1
+        (id: 0, write %EXPR [ (1, _3) 1 ]% at: x2)
+        CALL func 1: PREDICATE = %EXPR [ (-1, _1) 1 ]%
+        inputs: [_0, _3], outputs: [_4]
+        BRILLIG CALL func 0: inputs: [x0, [x1, %EXPR [ (2, _2) 0 ]%], MEM 0], outputs: [_5, [_6]]
+        ASSERT at 4: selector 12, payload: [x4, MEM 0]
+        ASSERT at 5.3: selector 18446744073709551615, payload: []
+        func 1
+        current witness index : 2
+        private parameters indices : [0, 1]
+        public parameters indices : []
+        return value indices : [2]
+        expression width : Unbounded
+        EXPR [ (1, _0) (1, _1) (-1, _2) 0 ]
+        unconstrained func 0
+        Const { destination: Direct(0), bit_size: Integer(U32), value: 2 }
+        CalldataCopy { destination_address: Direct(1), size_address: Direct(0), offset_address: Relative(3) }
+        BinaryFieldOp { destination: Direct(2), op: Add, lhs: Direct(1), rhs: Direct(1) }
+        JumpIf { condition: Direct(2), location: 5 }
+        ForeignCall { function: \"print\", destinations: [], destination_value_types: [], inputs: [MemoryAddress(Direct(2)), HeapArray(HeapArray { pointer: Direct(3), size: 2 })], input_value_types: [Simple(Field), Array { value_types: [Simple(Integer(U8))], size: 2 }] }
+        Trap { revert_data: HeapVector { pointer: Direct(0), size: Direct(1) } }
+        Stop { return_data: HeapVector { pointer: Direct(2), size: Direct(0) } }
+        ";
+    assert_program_roundtrip(src);
+}
+
+#[test]
+fn roundtrips_black_box_function_calls() {
+    let src = "
+        func 0
+        current witness index : 10
+        private parameters indices : [0, 1, 2]
+        public parameters indices : []
+        return value indices : []
+        expression width : Unbounded
+        BLACKBOX::RANGE input: (_0, 8)
+        BLACKBOX::AND lhs: (_0, 8), rhs: (255, 8), output: _3
+        BLACKBOX::EMBEDDED_CURVE_ADD input1: [(_0, 254), (_1, 254), (0, 1)], input2: [(_0, 254), (_1, 254), (0, 1)], outputs: [_4, _5, _6]
+        BLACKBOX::POSEIDON2_PERMUTATION inputs: [(_0, 254), (-1, 254)], outputs: [_7, _8], len: 2
+        BLACKBOX::BIGINT_FROM_LE_BYTES inputs: [(_0, 8), (_1, 8)], modulus: [1, 2, 3], output: 0
+        BLACKBOX::BIGINT_TO_LE_BYTES input: 0, outputs: [_9, _10]
+        ";
+    assert_program_roundtrip(src);
+}
+
+#[test]
+fn roundtrips_program_built_from_opcodes() {
+    let witnesses = |range: std::ops::Range<u32>| range.map(Witness).collect::<Vec<_>>();
+    let inputs = |range: std::ops::Range<u32>| {
+        range.map(|index| FunctionInput::witness(Witness(index), 8)).collect::<Vec<_>>()
+    };
+
+    let mut expression = Expression::default();
+    expression.push_multiplication_term(FieldElement::from(3_u128), Witness(1), Witness(2));
+    expression.push_addition_term(-FieldElement::one(), Witness(3));
+    expression.q_c = FieldElement::from(7_u128);
+
+    let opcodes = vec![
+        Opcode::AssertZero(expression.clone()),
+        Opcode::BlackBoxFuncCall(BlackBoxFuncCall::Sha256Compression {
+            inputs: Box::new(inputs(0..16).try_into().unwrap()),
+            hash_values: Box::new(inputs(16..24).try_into().unwrap()),
+            outputs: Box::new(witnesses(24..32).try_into().unwrap()),
+        }),
+        Opcode::BlackBoxFuncCall(BlackBoxFuncCall::Keccakf1600 {
+            inputs: Box::new(inputs(0..25).try_into().unwrap()),
+            outputs: Box::new(witnesses(32..57).try_into().unwrap()),
+        }),
+        Opcode::BlackBoxFuncCall(BlackBoxFuncCall::MultiScalarMul {
+            points: inputs(0..3),
+            scalars: vec![
+                FunctionInput::constant(FieldElement::from(2_u128), 254).unwrap(),
+                FunctionInput::witness(Witness(4), 254),
+            ],
+            outputs: (Witness(57), Witness(58), Witness(59)),
+        }),
+        Opcode::BlackBoxFuncCall(BlackBoxFuncCall::RecursiveAggregation {
+            verification_key: inputs(0..2),
+            proof: inputs(2..4),
+            public_inputs: vec![],
+            key_hash: FunctionInput::witness(Witness(5), 254),
+            proof_type: 1,
+        }),
+        Opcode::MemoryInit {
+            block_id: BlockId(0),
+            init: witnesses(0..3),
+            block_type: BlockType::Memory,
+        },
+        Opcode::MemoryOp {
+            block_id: BlockId(0),
+            op: MemOp {
+                operation: Witness(6).into(),
+                index: expression.clone(),
+                value: Witness(7).into(),
+            },
+            predicate: Some(Expression::one()),
+        },
+        Opcode::BrilligCall {
+            id: BrilligFunctionId(0),
+            inputs: vec![BrilligInputs::Single(expression), BrilligInputs::MemoryArray(BlockId(0))],
+            outputs: vec![BrilligOutputs::Array(witnesses(60..62))],
+            predicate: None,
+        },
+        Opcode::Call {
+            id: AcirFunctionId(0),
+            inputs: witnesses(0..2),
+            outputs: witnesses(62..63),
+            predicate: None,
+        },
+    ];
+
+    let circuit = Circuit {
+        current_witness_index: 62,
+        opcodes,
+        expression_width: ExpressionWidth::Bounded { width: 3 },
+        private_parameters: witnesses(0..6).into_iter().collect(),
+        public_parameters: PublicInputs::default(),
+        return_values: PublicInputs(witnesses(62..63).into_iter().collect()),
+        assert_messages: vec![(
+            OpcodeLocation::Brillig { acir_index: 7, brillig_index: 2 },
+            AssertionPayload {
+                error_selector: 42,
+                payload: vec![ExpressionOrMemory::Expression(Witness(1).into())],
+            },
+        )],
+    };
+
+    let value_types = vec![HeapValueType::Vector {
+        value_types: vec![HeapValueType::Simple(BitSize::Integer(IntegerBitSize::U1))],
+    }];
+    let brillig = BrilligBytecode {
+        bytecode: vec![
+            BrilligOpcode::BinaryIntOp {
+                destination: MemoryAddress::Direct(0),
+                op: BinaryIntOp::Shr,
+                bit_size: IntegerBitSize::U128,
+                lhs: MemoryAddress::Relative(1),
+                rhs: MemoryAddress::Relative(2),
+            },
+            BrilligOpcode::BinaryFieldOp {
+                destination: MemoryAddress::Direct(3),
+                op: BinaryFieldOp::LessThanEquals,
+                lhs: MemoryAddress::Direct(0),
+                rhs: MemoryAddress::Direct(1),
+            },
+            BrilligOpcode::IndirectConst {
+                destination_pointer: MemoryAddress::Direct(4),
+                bit_size: BitSize::Field,
+                value: -FieldElement::from(5_u128),
+            },
+            BrilligOpcode::ForeignCall {
+                function: "oracle \"with\" quotes\n".to_string(),
+                destinations: vec![ValueOrArray::HeapVector(HeapVector {
+                    pointer: MemoryAddress::Direct(0),
+                    size: MemoryAddress::Direct(1),
+                })],
+                destination_value_types: value_types.clone(),
+                inputs: vec![ValueOrArray::MemoryAddress(MemoryAddress::Direct(2))],
+                input_value_types: value_types,
+            },
+            BrilligOpcode::BlackBox(BlackBoxOp::ToRadix {
+                input: MemoryAddress::Direct(0),
+                radix: MemoryAddress::Direct(1),
+                output_pointer: MemoryAddress::Direct(2),
+                num_limbs: MemoryAddress::Direct(3),
+                output_bits: MemoryAddress::Direct(4),
+            }),
+            BrilligOpcode::BlackBox(BlackBoxOp::Keccakf1600 {
+                input: HeapArray { pointer: MemoryAddress::Direct(0), size: 25 },
+                output: HeapArray { pointer: MemoryAddress::Direct(1), size: 25 },
+            }),
+            BrilligOpcode::Return,
+        ],
+    };
+
+    let program = Program { functions: vec![circuit], unconstrained_functions: vec![brillig] };
+    let printed = program.to_string();
+    let parsed = Program::from_str(&printed).unwrap();
+    assert_eq!(parsed, program);
+    assert_eq!(parsed.to_string(), printed);
+}
+
+fn witnesses(range: std::ops::Range<u32>) -> Vec<Witness> {
+    range.map(Witness).collect()
+}
+
+fn witness_inputs(range: std::ops::Range<u32>) -> Vec<FunctionInput<FieldElement>> {
+    range.map(|index| FunctionInput::witness(Witness(index), 8)).collect()
+}
+
+fn witness_input_array<const N: usize>(start: u32) -> Box<[FunctionInput<FieldElement>; N]> {
+    Box::new(std::array::from_fn(|index| FunctionInput::witness(Witness(start + index as u32), 8)))
+}
+
+fn witness_array<const N: usize>(start: u32) -> Box<[Witness; N]> {
+    Box::new(std::array::from_fn(|index| Witness(start + index as u32)))
+}
+
+/// Checks that printing then parsing a program made of `opcodes` gives it back.
+fn assert_opcodes_roundtrip(
+    opcodes: Vec<Opcode<FieldElement>>,
+    unconstrained_functions: Vec<BrilligBytecode<FieldElement>>,
+) {
+    let circuit = Circuit { current_witness_index: 100, opcodes, ..Circuit::default() };
+    let program = Program { functions: vec![circuit], unconstrained_functions };
+    let printed = program.to_string();
+    let parsed = Program::from_str(&printed).unwrap();
+    assert_eq!(parsed, program, "{printed}");
+}
+
+#[test]
+fn roundtrips_every_opcode() {
+    let expression = Expression {
+        mul_terms: vec![(FieldElement::from(2_u128), Witness(0), Witness(1))],
+        linear_combinations: vec![(-FieldElement::one(), Witness(2))],
+        q_c: FieldElement::from(3_u128),
+    };
+    let read = MemOp::read_at_mem_index(expression.clone(), Witness(3));
+    let write = MemOp::write_to_mem_index(Witness(4).into(), expression.clone());
+
+    let opcodes = vec![
+        Opcode::AssertZero(expression.clone()),
+        Opcode::AssertZero(Expression::default()),
+        Opcode::BlackBoxFuncCall(BlackBoxFuncCall::RANGE {
+            input: FunctionInput::witness(Witness(0), 32),
+        }),
+        Opcode::MemoryInit {
+            block_id: BlockId(0),
+            init: witnesses(0..3),
+            block_type: BlockType::Memory,
+        },
+        Opcode::MemoryInit {
+            block_id: BlockId(1),
+            init: witnesses(3..4),
+            block_type: BlockType::CallData(2),
+        },
+        Opcode::MemoryInit {
+            block_id: BlockId(2),
+            init: vec![],
+            block_type: BlockType::ReturnData,
+        },
+        Opcode::MemoryOp { block_id: BlockId(0), op: read, predicate: None },
+        Opcode::MemoryOp { block_id: BlockId(0), op: write, predicate: Some(Witness(5).into()) },
+        Opcode::MemoryOp {
+            block_id: BlockId(0),
+            op: MemOp {
+                operation: expression.clone(),
+                index: Witness(1).into(),
+                value: Witness(2).into(),
+            },
+            predicate: Some(expression.clone()),
+        },
+        Opcode::BrilligCall {
+            id: BrilligFunctionId(0),
+            inputs: vec![
+                BrilligInputs::Single(expression.clone()),
+                BrilligInputs::Array(vec![Witness(0).into(), expression.clone()]),
+                BrilligInputs::Array(vec![]),
+                BrilligInputs::MemoryArray(BlockId(0)),
+            ],
+            outputs: vec![
+                BrilligOutputs::Simple(Witness(6)),
+                BrilligOutputs::Array(witnesses(7..9)),
+            ],
+            predicate: None,
+        },
+        Opcode::BrilligCall {
+            id: BrilligFunctionId(0),
+            inputs: vec![],
+            outputs: vec![],
+            predicate: Some(expression.clone()),
+        },
+        Opcode::Call {
+            id: AcirFunctionId(1),
+            inputs: witnesses(0..2),
+            outputs: witnesses(9..10),
+            predicate: None,
+        },
+        Opcode::Call {
+            id: AcirFunctionId(2),
+            inputs: vec![],
+            outputs: vec![],
+            predicate: Some(expression),
+        },
+    ];
+    let brillig = BrilligBytecode { bytecode: vec![BrilligOpcode::Return] };
+    assert_opcodes_roundtrip(opcodes, vec![brillig]);
+}
+
+#[test]
+fn roundtrips_every_black_box_function_call() {
+    let constant = |value: u128| FunctionInput::constant(FieldElement::from(value), 254).unwrap();
+    let calls = vec![
+        BlackBoxFuncCall::AES128Encrypt {
+            inputs: witness_inputs(0..3),
+            iv: witness_input_array(3),
+            key: witness_input_array(19),
+            outputs: witnesses(35..51),
+        },
+        BlackBoxFuncCall::AND {
+            lhs: FunctionInput::witness(Witness(0), 8),
+            rhs: constant(255),
+            output: Witness(1),
+        },
+        BlackBoxFuncCall::XOR {
+            lhs: constant(0),
+            rhs: FunctionInput::witness(Witness(0), 8),
+            output: Witness(1),
+        },
+        BlackBoxFuncCall::RANGE { input: FunctionInput::witness(Witness(0), 1) },
+        BlackBoxFuncCall::Blake2s { inputs: witness_inputs(0..2), outputs: witness_array(2) },
+        BlackBoxFuncCall::Blake3 { inputs: vec![], outputs: witness_array(2) },
+        BlackBoxFuncCall::EcdsaSecp256k1 {
+            public_key_x: witness_input_array(0),
+            public_key_y: witness_input_array(32),
+            signature: witness_input_array(64),
+            hashed_message: witness_input_array(128),
+            output: Witness(160),
+        },
+        BlackBoxFuncCall::EcdsaSecp256r1 {
+            public_key_x: witness_input_array(0),
+            public_key_y: witness_input_array(32),
+            signature: witness_input_array(64),
+            hashed_message: witness_input_array(128),
+            output: Witness(160),
+        },
+        BlackBoxFuncCall::MultiScalarMul {
+            points: witness_inputs(0..3),
+            scalars: vec![constant(1), FunctionInput::witness(Witness(3), 254)],
+            outputs: (Witness(4), Witness(5), Witness(6)),
+        },
+        BlackBoxFuncCall::EmbeddedCurveAdd {
+            input1: witness_input_array(0),
+            input2: Box::new([constant(1), constant(2), constant(0)]),
+            outputs: (Witness(3), Witness(4), Witness(5)),
+        },
+        BlackBoxFuncCall::Keccakf1600 {
+            inputs: witness_input_array(0),
+            outputs: witness_array(25),
+        },
+        BlackBoxFuncCall::RecursiveAggregation {
+            verification_key: witness_inputs(0..2),
+            proof: vec![],
+            public_inputs: witness_inputs(2..3),
+            key_hash: constant(7),
+            proof_type: 2,
+        },
+        BlackBoxFuncCall::BigIntAdd { lhs: 0, rhs: 1, output: 2 },
+        BlackBoxFuncCall::BigIntSub { lhs: 0, rhs: 1, output: 2 },
+        BlackBoxFuncCall::BigIntMul { lhs: 0, rhs: 1, output: 2 },
+        BlackBoxFuncCall::BigIntDiv { lhs: 0, rhs: 1, output: 2 },
+        BlackBoxFuncCall::BigIntFromLeBytes {
+            inputs: witness_inputs(0..2),
+            modulus: vec![0, 255],
+            output: 3,
+        },
+        BlackBoxFuncCall::BigIntToLeBytes { input: 3, outputs: witnesses(0..4) },
+        BlackBoxFuncCall::Poseidon2Permutation {
+            inputs: witness_inputs(0..4),
+            outputs: witnesses(4..8),
+            len: 4,
+        },
+        BlackBoxFuncCall::Sha256Compression {
+            inputs: witness_input_array(0),
+            hash_values: witness_input_array(16),
+            outputs: witness_array(24),
+        },
+    ];
+
+    // Every black box function is covered
+    let covered: HashSet<_> = calls.iter().map(BlackBoxFuncCall::get_black_box_func).collect();
+    assert_eq!(covered, BlackBoxFunc::iter().collect());
+
+    assert_opcodes_roundtrip(calls.into_iter().map(Opcode::BlackBoxFuncCall).collect(), vec![]);
+}
+
+#[test]
+fn roundtrips_every_brillig_opcode() {
+    let (a, b, c) =
+        (MemoryAddress::Direct(0), MemoryAddress::Relative(1), MemoryAddress::Direct(2));
+    let array = HeapArray { pointer: a, size: 3 };
+    let vector = HeapVector { pointer: b, size: c };
+
+    let bit_sizes = [
+        IntegerBitSize::U1,
+        IntegerBitSize::U8,
+        IntegerBitSize::U16,
+        IntegerBitSize::U32,
+        IntegerBitSize::U64,
+        IntegerBitSize::U128,
+    ];
+    let field_ops = [
+        BinaryFieldOp::Add,
+        BinaryFieldOp::Sub,
+        BinaryFieldOp::Mul,
+        BinaryFieldOp::Div,
+        BinaryFieldOp::IntegerDiv,
+        BinaryFieldOp::Equals,
+        BinaryFieldOp::LessThan,
+        BinaryFieldOp::LessThanEquals,
+    ];
+    let int_ops = [
+        BinaryIntOp::Add,
+        BinaryIntOp::Sub,
+        BinaryIntOp::Mul,
+        BinaryIntOp::Div,
+        BinaryIntOp::Equals,
+        BinaryIntOp::LessThan,
+        BinaryIntOp::LessThanEquals,
+        BinaryIntOp::And,
+        BinaryIntOp::Or,
+        BinaryIntOp::Xor,
+        BinaryIntOp::Shl,
+        BinaryIntOp::Shr,
+    ];
+    let black_box_ops = vec![
+        BlackBoxOp::AES128Encrypt { inputs: vector, iv: array, key: array, outputs: vector },
+        BlackBoxOp::Blake2s { message: vector, output: array },
+        BlackBoxOp::Blake3 { message: vector, output: array },
+        BlackBoxOp::Keccakf1600 { input: array, output: array },
+        BlackBoxOp::EcdsaSecp256k1 {
+            hashed_msg: vector,
+            public_key_x: array,
+            public_key_y: array,
+            signature: array,
+            result: a,
+        },
+        BlackBoxOp::EcdsaSecp256r1 {
+            hashed_msg: vector,
+            public_key_x: array,
+            public_key_y: array,
+            signature: array,
+            result: b,
+        },
+        BlackBoxOp::MultiScalarMul { points: vector, scalars: vector, outputs: array },
+        BlackBoxOp::EmbeddedCurveAdd {
+            input1_x: a,
+            input1_y: b,
+            input1_infinite: c,
+            input2_x: a,
+            input2_y: b,
+            input2_infinite: c,
+            result: array,
+        },
+        BlackBoxOp::BigIntAdd { lhs: a, rhs: b, output: c },
+        BlackBoxOp::BigIntSub { lhs: a, rhs: b, output: c },
+        BlackBoxOp::BigIntMul { lhs: a, rhs: b, output: c },
+        BlackBoxOp::BigIntDiv { lhs: a, rhs: b, output: c },
+        BlackBoxOp::BigIntFromLeBytes { inputs: vector, modulus: vector, output: a },
+        BlackBoxOp::BigIntToLeBytes { input: a, output: vector },
+        BlackBoxOp::Poseidon2Permutation { message: vector, output: array, len: c },
+        BlackBoxOp::Sha256Compression { input: array, hash_values: array, output: array },
+        BlackBoxOp::ToRadix { input: a, radix: b, output_pointer: c, num_limbs: a, output_bits: b },
+    ];
+    let value_types = vec![
+        HeapValueType::field(),
+        HeapValueType::Simple(BitSize::Integer(IntegerBitSize::U64)),
+        HeapValueType::Array { value_types: vec![HeapValueType::field()], size: 2 },
+        HeapValueType::Vector {
+            value_types: vec![HeapValueType::Array { value_types: vec![], size: 0 }],
+        },
+    ];
+
+    let mut bytecode = Vec::new();
+    for op in field_ops {
+        bytecode.push(BrilligOpcode::BinaryFieldOp { destination: a, op, lhs: b, rhs: c });
+    }
+    for (op, bit_size) in int_ops.into_iter().zip(bit_sizes.into_iter().cycle()) {
+        bytecode.push(BrilligOpcode::BinaryIntOp { destination: a, op, bit_size, lhs: b, rhs: c });
+    }
+    for bit_size in bit_sizes {
+        bytecode.push(BrilligOpcode::Not { destination: a, source: b, bit_size });
+        bytecode.push(BrilligOpcode::Cast {
+            destination: a,
+            source: b,
+            bit_size: BitSize::Integer(bit_size),
+        });
+    }
+    bytecode.extend([
+        BrilligOpcode::Cast { destination: a, source: b, bit_size: BitSize::Field },
+        BrilligOpcode::JumpIfNot { condition: a, location: 1 },
+        BrilligOpcode::JumpIf { condition: b, location: 2 },
+        BrilligOpcode::Jump { location: 3 },
+        BrilligOpcode::CalldataCopy { destination_address: a, size_address: b, offset_address: c },
+        BrilligOpcode::Call { location: 4 },
+        BrilligOpcode::Const {
+            destination: a,
+            bit_size: BitSize::Field,
+            value: -FieldElement::from(2_u128),
+        },
+        BrilligOpcode::Const {
+            destination: b,
+            bit_size: BitSize::Integer(IntegerBitSize::U32),
+            value: FieldElement::from(7_u128),
+        },
+        BrilligOpcode::IndirectConst {
+            destination_pointer: c,
+            bit_size: BitSize::Integer(IntegerBitSize::U8),
+            value: FieldElement::zero(),
+        },
+        BrilligOpcode::Return,
+        BrilligOpcode::ForeignCall {
+            function: "foo".to_string(),
+            destinations: vec![
+                ValueOrArray::MemoryAddress(a),
+                ValueOrArray::HeapArray(array),
+                ValueOrArray::HeapVector(vector),
+            ],
+            destination_value_types: value_types.clone(),
+            inputs: vec![],
+            input_value_types: value_types,
+        },
+        BrilligOpcode::Mov { destination: a, source: b },
+        BrilligOpcode::ConditionalMov { destination: a, source_a: b, source_b: c, condition: a },
+        BrilligOpcode::Load { destination: a, source_pointer: b },
+        BrilligOpcode::Store { destination_pointer: a, source: b },
+        BrilligOpcode::Trap { revert_data: vector },
+        BrilligOpcode::Stop { return_data: vector },
+    ]);
+    bytecode.extend(black_box_ops.into_iter().map(BrilligOpcode::BlackBox));
+
+    let call = Opcode::BrilligCall {
+        id: BrilligFunctionId(0),
+        inputs: vec![],
+        outputs: vec![],
+        predicate: None,
+    };
+    assert_opcodes_roundtrip(vec![call], vec![BrilligBytecode { bytecode }]);
+}
+
+#[test]
+fn reports_function_input_which_is_neither_a_witness_nor_a_constant() {
+    let src = "
+        func 0
+        current witness index : 0
+        private parameters indices : []
+        public parameters indices : []
+        return value indices : []
+        expression width : Unbounded
+        BLACKBOX::RANGE input: (foo, 8)
+        ";
+    let error = Program::<FieldElement>::from_str(src).unwrap_err().to_string();
+    assert!(error.starts_with("Expected an integer, found 'foo' at line 8"), "{error}");
+}
+
+#[test]
+fn parses_a_single_circuit() {
+    let src = "
+        current witness index : 1
+        private parameters indices : [0]
+        public parameters indices : []
+        return value indices : [1]
+        expression width : Unbounded
+        EXPR [ (1, _0) (-1, _1) 0 ]
+        ";
+    let circuit = Circuit::<FieldElement>::from_str(src).unwrap();
+    assert_eq!(circuit.opcodes.len(), 1);
+    assert_eq!(circuit.return_values.0.len(), 1);
+}
+
+#[test]
+fn reports_invalid_black_box_input_length() {
+    let src = "
+        func 0
+        current witness index : 0
+        private parameters indices : []
+        public parameters indices : []
+        return value indices : []
+        expression width : Unbounded
+        BLACKBOX::BLAKE3 inputs: [(_0, 8)], outputs: [_1]
+        ";
+    let error = Program::<FieldElement>::from_str(src).unwrap_err().to_string();
+    assert!(error.starts_with("Expected 32 elements, found 1 at line 8"));
+}
+
+#[test]
+fn reports_unknown_opcode_with_source_line() {
+    let src = "func 0
+current witness index : 0
+private parameters indices : []
+public parameters indices : []
+return value indices : []
+expression width : Unbounded
+FOO [ 0 ]";
+    let error = Program::<FieldElement>::from_str(src).unwrap_err().to_string();
+    assert_eq!(error, "Expected '(end of stream)', found 'FOO' at line 7\nFOO [ 0 ]\n^^^");
+}
@@ -0,0 +1,98 @@
+use std::fmt::Display;
+
+/// A range of bytes in the parsed source, with an exclusive end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Span {
+    pub(crate) fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    pub(crate) fn single_char(position: usize) -> Span {
+        Span { start: position, end: position + 1 }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SpannedToken {
+    token: Token,
+    span: Span,
+}
+
+impl SpannedToken {
+    pub(crate) fn new(token: Token, span: Span) -> SpannedToken {
+        SpannedToken { token, span }
+    }
+
+    pub(crate) fn to_span(&self) -> Span {
+        self.span
+    }
+
+    pub(crate) fn token(&self) -> &Token {
+        &self.token
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    Ident(String),
+    /// An unsigned decimal integer. Its digits are kept as they may not fit in any integer type,
+    /// for example if it is a field element.
+    Int(String),
+    Str(String),
+    /// _N
+    Witness(u32),
+    /// (
+    LeftParen,
+    /// )
+    RightParen,
+    /// {
+    LeftBrace,
+    /// }
+    RightBrace,
+    /// [
+    LeftBracket,
+    /// ]
+    RightBracket,
+    /// ,
+    Comma,
+    /// :
+    Colon,
+    /// .
+    Dot,
+    /// %
+    Percent,
+    /// =
+    Assign,
+    /// -
+    Dash,
+    Eof,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "{ident}"),
+            Token::Int(int) => write!(f, "{int}"),
+            Token::Str(string) => write!(f, "{string:?}"),
+            Token::Witness(index) => write!(f, "_{index}"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBrace => write!(f, "{{"),
+            Token::RightBrace => write!(f, "}}"),
+            Token::LeftBracket => write!(f, "["),
+            Token::RightBracket => write!(f, "]"),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+            Token::Dot => write!(f, "."),
+            Token::Percent => write!(f, "%"),
+            Token::Assign => write!(f, "="),
+            Token::Dash => write!(f, "-"),
+            Token::Eof => write!(f, "(end of stream)"),
+        }
+    }
+}
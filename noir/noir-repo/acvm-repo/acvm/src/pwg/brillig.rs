@@ -34,6 +34,12 @@ pub struct BrilligSolver<'b, F, B: BlackBoxFunctionSolver<F>> {
     pub function_id: BrilligFunctionId,
 }
 
+impl<F: Clone, B: BlackBoxFunctionSolver<F>> Clone for BrilligSolver<'_, F, B> {
+    fn clone(&self) -> Self {
+        Self { vm: self.vm.clone(), acir_index: self.acir_index, function_id: self.function_id }
+    }
+}
+
 impl<'b, B: BlackBoxFunctionSolver<F>, F: AcirField> BrilligSolver<'b, F, B> {
     /// Assigns the zero value to all outputs of the given [`Brillig`] bytecode.
     pub(super) fn zero_out_brillig_outputs(
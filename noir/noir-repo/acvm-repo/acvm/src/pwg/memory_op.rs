@@ -14,7 +14,7 @@ use super::{ErrorLocation, OpcodeResolutionError};
 type MemoryIndex = u32;
 
 /// Maintains the state for solving [`MemoryInit`][`acir::circuit::Opcode::MemoryInit`] and [`MemoryOp`][`acir::circuit::Opcode::MemoryOp`] opcodes.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(crate) struct MemoryOpSolver<F> {
     pub(super) block_value: HashMap<MemoryIndex, F>,
     pub(super) block_len: u32,
@@ -71,7 +71,7 @@ pub struct BrilligProfilingSample {
     pub call_stack: Vec<usize>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq)]
 /// VM encapsulates the state of the Brillig VM during execution.
 pub struct VM<'a, F, B: BlackBoxFunctionSolver<F>> {
     /// Calldata to the brillig function
@@ -102,6 +102,27 @@ pub struct VM<'a, F, B: BlackBoxFunctionSolver<F>> {
     profiling_samples: BrilligProfilingSamples,
 }
 
+// Implemented manually, as deriving `Clone` would require the black box solver to implement it
+// even if only a reference to it is held.
+impl<F: Clone, B: BlackBoxFunctionSolver<F>> Clone for VM<'_, F, B> {
+    fn clone(&self) -> Self {
+        Self {
+            calldata: self.calldata.clone(),
+            program_counter: self.program_counter,
+            foreign_call_counter: self.foreign_call_counter,
+            foreign_call_results: self.foreign_call_results.clone(),
+            bytecode: self.bytecode,
+            status: self.status.clone(),
+            memory: self.memory.clone(),
+            call_stack: self.call_stack.clone(),
+            black_box_solver: self.black_box_solver,
+            bigint_solver: self.bigint_solver.clone(),
+            profiling_active: self.profiling_active,
+            profiling_samples: self.profiling_samples.clone(),
+        }
+    }
+}
+
 impl<'a, F: AcirField, B: BlackBoxFunctionSolver<F>> VM<'a, F, B> {
     /// Constructs a new VM instance
     pub fn new(
@@ -6,15 +6,19 @@
 use abi_gen::{abi_type_from_hir_type, value_from_hir_expression};
 use acvm::acir::circuit::ExpressionWidth;
 use acvm::compiler::MIN_EXPRESSION_WIDTH;
+use acvm::FieldElement;
 use clap::Args;
 use fm::{FileId, FileManager};
 use iter_extended::vecmap;
+use noirc_abi::input_parser::Format;
 use noirc_abi::{AbiParameter, AbiType, AbiValue};
 use noirc_errors::{CustomDiagnostic, DiagnosticKind, FileDiagnostic};
 use noirc_evaluator::create_program;
 use noirc_evaluator::errors::RuntimeError;
 use noirc_evaluator::ssa::{SsaLogging, SsaProgramArtifact};
+use noirc_frontend::ast::Visibility;
 use noirc_frontend::debug::build_debug_crate_file;
+use noirc_frontend::hir::def_collector::dc_crate::CompilationError;
 use noirc_frontend::hir::def_map::{Contract, CrateDefMap};
 use noirc_frontend::hir::Context;
 use noirc_frontend::monomorphization::{
@@ -22,7 +26,8 @@ use noirc_frontend::monomorphization::{
 };
 use noirc_frontend::node_interner::FuncId;
 use noirc_frontend::token::SecondaryAttribute;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 mod abi_gen;
@@ -150,6 +155,11 @@ pub struct CompileOptions {
     /// A lower value keeps the original program if it was smaller, even if it has more jumps.
     #[arg(long, hide = true, allow_hyphen_values = true)]
     pub max_bytecode_increase_percent: Option<i32>,
+
+    /// Interpret the SSA with the inputs in the given file (e.g. `Prover.toml`) after every
+    /// SSA pass, and panic if a pass changes the result of the program
+    #[arg(long, hide = true)]
+    pub check_ssa_passes: Option<PathBuf>,
 }
 
 pub fn parse_expression_width(input: &str) -> Result<ExpressionWidth, std::io::Error> {
@@ -172,6 +182,7 @@ pub fn parse_expression_width(input: &str) -> Result<ExpressionWidth, std::io::E
 pub enum CompileError {
     MonomorphizationError(MonomorphizationError),
     RuntimeError(RuntimeError),
+    InvalidSsaCheckInputs { path: PathBuf, reason: String },
 }
 
 impl From<MonomorphizationError> for CompileError {
@@ -191,6 +202,10 @@ impl From<CompileError> for FileDiagnostic {
         match error {
             CompileError::RuntimeError(err) => err.into(),
             CompileError::MonomorphizationError(err) => err.into(),
+            CompileError::InvalidSsaCheckInputs { path, reason } => {
+                let message = format!("Invalid inputs in {}: {reason}", path.display());
+                CustomDiagnostic::from_message(&message).in_file(FileId::dummy())
+            }
         }
     }
 }
@@ -314,7 +329,44 @@ pub fn check_crate(
         options.debug_comptime_in_file.as_deref(),
         error_on_unused_imports,
     );
-    let warnings_and_errors: Vec<FileDiagnostic> = diagnostics
+    let warnings_and_errors = to_file_diagnostics(diagnostics, options);
+
+    if has_errors(&warnings_and_errors, options.deny_warnings) {
+        Err(warnings_and_errors)
+    } else {
+        Ok(((), warnings_and_errors))
+    }
+}
+
+/// Run the lexing, parsing, name resolution, and type checking passes on the dependencies of a
+/// crate, including the stdlib, but not on the crate itself.
+///
+/// A later [check_crate] of the crate skips the dependencies which were already checked, so
+/// their results can be checked once and reused while only the crate's own files change.
+/// This returns the warnings and errors found in the dependencies.
+pub fn check_crate_dependencies(
+    context: &mut Context,
+    crate_id: CrateId,
+    options: &CompileOptions,
+) -> Vec<FileDiagnostic> {
+    let error_on_unused_imports = false;
+    let mut diagnostics = Vec::new();
+    for dependency in context.crate_graph[crate_id].dependencies.clone() {
+        diagnostics.extend(CrateDefMap::collect_defs(
+            dependency.crate_id,
+            context,
+            options.debug_comptime_in_file.as_deref(),
+            error_on_unused_imports,
+        ));
+    }
+    to_file_diagnostics(diagnostics, options)
+}
+
+fn to_file_diagnostics(
+    diagnostics: Vec<(CompilationError, FileId)>,
+    options: &CompileOptions,
+) -> Vec<FileDiagnostic> {
+    diagnostics
         .into_iter()
         .map(|(error, file_id)| {
             let diagnostic = CustomDiagnostic::from(&error);
@@ -324,13 +376,7 @@ pub fn check_crate(
             // We filter out any warnings if they're going to be ignored later on to free up memory.
             !options.silence_warnings || diagnostic.diagnostic.kind != DiagnosticKind::Warning
         })
-        .collect();
-
-    if has_errors(&warnings_and_errors, options.deny_warnings) {
-        Err(warnings_and_errors)
-    } else {
-        Ok(((), warnings_and_errors))
-    }
+        .collect()
 }
 
 pub fn compute_function_abi(
@@ -616,6 +662,12 @@ pub fn compile_no_check(
     }
 
     let return_visibility = program.return_visibility;
+    let interpreter_check_inputs = match &options.check_ssa_passes {
+        Some(inputs_file) => {
+            Some(read_ssa_check_inputs(context, &main_function, return_visibility, inputs_file)?)
+        }
+        None => None,
+    };
     let ssa_evaluator_options = noirc_evaluator::ssa::SsaEvaluatorOptions {
         ssa_logging: match &options.show_ssa_pass_name {
             Some(string) => SsaLogging::Contains(string.clone()),
@@ -640,10 +692,17 @@ pub fn compile_no_check(
         skip_brillig_constraints_check: options.skip_brillig_constraints_check,
         inliner_aggressiveness: options.inliner_aggressiveness,
         max_bytecode_increase_percent: options.max_bytecode_increase_percent,
+        interpreter_check_inputs,
     };
 
     let SsaProgramArtifact { program, debug, warnings, names, brillig_names, error_types, .. } =
-        create_program(program, &ssa_evaluator_options)?;
+        create_program(program, &ssa_evaluator_options).map_err(|error| match error {
+            RuntimeError::InvalidSsaCheckInputs { reason, .. } => {
+                let path = options.check_ssa_passes.clone().unwrap_or_default();
+                CompileError::InvalidSsaCheckInputs { path, reason }
+            }
+            error => error.into(),
+        })?;
 
     let abi = abi_gen::gen_abi(context, &main_function, return_visibility, error_types);
     let file_map = filter_relevant_files(&debug, &context.file_manager);
@@ -660,3 +719,28 @@ pub fn compile_no_check(
         brillig_names,
     })
 }
+
+/// Reads the inputs to `main` from a `Prover.toml` (or JSON) file, flattened into the
+/// field elements of their witnesses.
+fn read_ssa_check_inputs(
+    context: &Context,
+    main_function: &FuncId,
+    return_visibility: Visibility,
+    inputs_file: &Path,
+) -> Result<Vec<FieldElement>, CompileError> {
+    let invalid = |reason: String| CompileError::InvalidSsaCheckInputs {
+        path: inputs_file.to_path_buf(),
+        reason,
+    };
+
+    let abi = abi_gen::gen_abi(context, main_function, return_visibility, BTreeMap::new());
+    let format = match inputs_file.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => Format::Json,
+        _ => Format::Toml,
+    };
+    let input_string =
+        std::fs::read_to_string(inputs_file).map_err(|error| invalid(error.to_string()))?;
+    let inputs = format.parse(&input_string, &abi).map_err(|error| invalid(error.to_string()))?;
+    let witness_map = abi.encode(&inputs, None).map_err(|error| invalid(error.to_string()))?;
+    Ok(witness_map.into_iter().map(|(_, value)| value).collect())
+}
@@ -61,6 +61,10 @@ pub enum RuntimeError {
     UnconstrainedOracleReturnToConstrained { call_stack: CallStack },
     #[error("Could not resolve some references to the array. All references must be resolved at compile time")]
     UnknownReference { call_stack: CallStack },
+    /// The inputs given to check the SSA passes don't match the parameters of `main`.
+    /// This has no location, so the call stack is always empty.
+    #[error("Invalid inputs to check the SSA passes: {reason}")]
+    InvalidSsaCheckInputs { reason: String, call_stack: CallStack },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
@@ -173,7 +177,8 @@ impl RuntimeError {
             | RuntimeError::BigIntModulus { call_stack, .. }
             | RuntimeError::UnconstrainedSliceReturnToConstrained { call_stack }
             | RuntimeError::UnconstrainedOracleReturnToConstrained { call_stack }
-            | RuntimeError::UnknownReference { call_stack } => call_stack,
+            | RuntimeError::UnknownReference { call_stack }
+            | RuntimeError::InvalidSsaCheckInputs { call_stack, .. } => call_stack,
         }
     }
 }
@@ -198,6 +203,11 @@ impl RuntimeError {
                     noirc_errors::Span::inclusive(0, 0)
                 )
             }
+            RuntimeError::InvalidSsaCheckInputs { .. } => Diagnostic::simple_error(
+                self.to_string(),
+                String::new(),
+                noirc_errors::Span::inclusive(0, 0),
+            ),
             RuntimeError::UnknownLoopBound { .. } => {
                 let primary_message = self.to_string();
                 let location =
@@ -26,9 +26,10 @@ use acvm::{
     FieldElement,
 };
 
-use ir::instruction::ErrorType;
+use ir::{call_stack::CallStack, instruction::ErrorType};
 use noirc_errors::debug_info::{DebugFunctions, DebugInfo, DebugTypes, DebugVariables};
 
+use interpreter::InterpreterCheck;
 use noirc_frontend::ast::Visibility;
 use noirc_frontend::{hir_def::function::FunctionSignature, monomorphization::ast::Program};
 use ssa_gen::Ssa;
@@ -38,12 +39,14 @@ use crate::acir::{Artifacts, GeneratedAcir};
 
 mod checks;
 pub(super) mod function_builder;
+mod interpreter;
 pub mod ir;
 mod opt;
-#[cfg(test)]
 pub(crate) mod parser;
 pub mod ssa_gen;
 
+pub use parser::SsaErrorWithSource;
+
 #[derive(Debug, Clone)]
 pub enum SsaLogging {
     None,
@@ -82,6 +85,10 @@ pub struct SsaEvaluatorOptions {
     /// When `None` the size increase check is skipped altogether and any decrease in the SSA
     /// instruction count is accepted.
     pub max_bytecode_increase_percent: Option<i32>,
+
+    /// The flattened inputs to `main`. When set, the SSA is interpreted with these inputs
+    /// after every pass, panicking if a pass changes the program's result.
+    pub interpreter_check_inputs: Option<Vec<FieldElement>>,
 }
 
 pub(crate) struct ArtifactsAndWarnings(Artifacts, Vec<SsaReport>);
@@ -104,6 +111,10 @@ pub(crate) fn optimize_into_acir(
         options.print_codegen_timings,
         &options.emit_ssa,
     )?;
+    let builder = match &options.interpreter_check_inputs {
+        Some(inputs) => builder.with_interpreter_check(inputs)?,
+        None => builder,
+    };
 
     let mut ssa = optimize_all(builder, options)?;
 
@@ -127,6 +138,14 @@ pub(crate) fn optimize_into_acir(
 
     drop(ssa_gen_span_guard);
 
+    let artifacts = lower_into_acir(ssa, options)?;
+
+    Ok(ArtifactsAndWarnings(artifacts, ssa_level_warnings))
+}
+
+/// Converts an optimized SSA program into Brillig for its unconstrained functions
+/// and ACIR for its constrained functions.
+fn lower_into_acir(ssa: Ssa, options: &SsaEvaluatorOptions) -> Result<Artifacts, RuntimeError> {
     let brillig = time("SSA to Brillig", options.print_codegen_timings, || {
         ssa.to_brillig(options.enable_brillig_logging)
     });
@@ -138,6 +157,7 @@ pub(crate) fn optimize_into_acir(
         ssa,
         ssa_logging: options.ssa_logging.clone(),
         print_codegen_timings: options.print_codegen_timings,
+        interpreter_check: None,
     }
     .run_pass(|ssa| ssa.fold_constants_with_brillig(&brillig), "Inlining Brillig Calls Inlining")
     .run_pass(Ssa::dead_instruction_elimination, "Dead Instruction Elimination (2nd)")
@@ -145,55 +165,92 @@ pub(crate) fn optimize_into_acir(
 
     drop(ssa_gen_span_guard);
 
-    let artifacts = time("SSA to ACIR", options.print_codegen_timings, || {
+    time("SSA to ACIR", options.print_codegen_timings, || {
         ssa.into_acir(&brillig, options.expression_width)
-    })?;
-
-    Ok(ArtifactsAndWarnings(artifacts, ssa_level_warnings))
+    })
 }
 
 /// Run all SSA passes.
 fn optimize_all(builder: SsaBuilder, options: &SsaEvaluatorOptions) -> Result<Ssa, RuntimeError> {
-    Ok(builder
-        .run_pass(Ssa::defunctionalize, "Defunctionalization")
-        .run_pass(Ssa::remove_paired_rc, "Removing Paired rc_inc & rc_decs")
-        .run_pass(Ssa::separate_runtime, "Runtime Separation")
-        .run_pass(Ssa::resolve_is_unconstrained, "Resolving IsUnconstrained")
-        .run_pass(|ssa| ssa.inline_functions(options.inliner_aggressiveness), "Inlining (1st)")
+    Ok(builder.run_passes(&primary_passes(options))?.finish())
+}
+
+/// An SSA pass together with the name it is reported under in timings and `--show-ssa` output.
+pub struct SsaPass<'a> {
+    run: Box<dyn Fn(Ssa) -> Result<Ssa, RuntimeError> + 'a>,
+    msg: &'static str,
+}
+
+impl<'a> SsaPass<'a> {
+    fn new<F>(pass: F, msg: &'static str) -> Self
+    where
+        F: Fn(Ssa) -> Ssa + 'a,
+    {
+        Self::new_try(move |ssa| Ok(pass(ssa)), msg)
+    }
+
+    fn new_try<F>(pass: F, msg: &'static str) -> Self
+    where
+        F: Fn(Ssa) -> Result<Ssa, RuntimeError> + 'a,
+    {
+        Self { run: Box::new(pass), msg }
+    }
+
+    /// The name of this pass, as printed by `--show-ssa`.
+    pub fn msg(&self) -> &'static str {
+        self.msg
+    }
+
+    pub fn run(&self, ssa: Ssa) -> Result<Ssa, RuntimeError> {
+        (self.run)(ssa)
+    }
+}
+
+/// The SSA passes run by `optimize_all`, in the order they are run.
+pub fn primary_passes(options: &SsaEvaluatorOptions) -> Vec<SsaPass<'_>> {
+    vec![
+        SsaPass::new(Ssa::defunctionalize, "Defunctionalization"),
+        SsaPass::new(Ssa::remove_paired_rc, "Removing Paired rc_inc & rc_decs"),
+        SsaPass::new(Ssa::separate_runtime, "Runtime Separation"),
+        SsaPass::new(Ssa::resolve_is_unconstrained, "Resolving IsUnconstrained"),
+        SsaPass::new(
+            move |ssa| ssa.inline_functions(options.inliner_aggressiveness),
+            "Inlining (1st)",
+        ),
         // Run mem2reg with the CFG separated into blocks
-        .run_pass(Ssa::mem2reg, "Mem2Reg (1st)")
-        .run_pass(Ssa::simplify_cfg, "Simplifying (1st)")
-        .run_pass(Ssa::as_slice_optimization, "`as_slice` optimization")
-        .try_run_pass(
+        SsaPass::new(Ssa::mem2reg, "Mem2Reg (1st)"),
+        SsaPass::new(Ssa::simplify_cfg, "Simplifying (1st)"),
+        SsaPass::new(Ssa::as_slice_optimization, "`as_slice` optimization"),
+        SsaPass::new_try(
             Ssa::evaluate_static_assert_and_assert_constant,
             "`static_assert` and `assert_constant`",
-        )?
-        .run_pass(Ssa::loop_invariant_code_motion, "Loop Invariant Code Motion")
-        .try_run_pass(
-            |ssa| ssa.unroll_loops_iteratively(options.max_bytecode_increase_percent),
+        ),
+        SsaPass::new(Ssa::loop_invariant_code_motion, "Loop Invariant Code Motion"),
+        SsaPass::new_try(
+            move |ssa| ssa.unroll_loops_iteratively(options.max_bytecode_increase_percent),
             "Unrolling",
-        )?
-        .run_pass(Ssa::simplify_cfg, "Simplifying (2nd)")
-        .run_pass(Ssa::flatten_cfg, "Flattening")
-        .run_pass(Ssa::remove_bit_shifts, "After Removing Bit Shifts")
+        ),
+        SsaPass::new(Ssa::simplify_cfg, "Simplifying (2nd)"),
+        SsaPass::new(Ssa::flatten_cfg, "Flattening"),
+        SsaPass::new(Ssa::remove_bit_shifts, "After Removing Bit Shifts"),
         // Run mem2reg once more with the flattened CFG to catch any remaining loads/stores
-        .run_pass(Ssa::mem2reg, "Mem2Reg (2nd)")
+        SsaPass::new(Ssa::mem2reg, "Mem2Reg (2nd)"),
         // Run the inlining pass again to handle functions with `InlineType::NoPredicates`.
         // Before flattening is run, we treat functions marked with the `InlineType::NoPredicates` as an entry point.
         // This pass must come immediately following `mem2reg` as the succeeding passes
         // may create an SSA which inlining fails to handle.
-        .run_pass(
-            |ssa| ssa.inline_functions_with_no_predicates(options.inliner_aggressiveness),
+        SsaPass::new(
+            move |ssa| ssa.inline_functions_with_no_predicates(options.inliner_aggressiveness),
             "Inlining (2nd)",
-        )
-        .run_pass(Ssa::remove_if_else, "Remove IfElse")
-        .run_pass(Ssa::fold_constants, "Constant Folding")
-        .run_pass(Ssa::remove_enable_side_effects, "EnableSideEffectsIf removal")
-        .run_pass(Ssa::fold_constants_using_constraints, "Constraint Folding")
-        .run_pass(Ssa::dead_instruction_elimination, "Dead Instruction Elimination (1st)")
-        .run_pass(Ssa::simplify_cfg, "Simplifying:")
-        .run_pass(Ssa::array_set_optimization, "Array Set Optimizations")
-        .finish())
+        ),
+        SsaPass::new(Ssa::remove_if_else, "Remove IfElse"),
+        SsaPass::new(Ssa::fold_constants, "Constant Folding"),
+        SsaPass::new(Ssa::remove_enable_side_effects, "EnableSideEffectsIf removal"),
+        SsaPass::new(Ssa::fold_constants_using_constraints, "Constraint Folding"),
+        SsaPass::new(Ssa::dead_instruction_elimination, "Dead Instruction Elimination (1st)"),
+        SsaPass::new(Ssa::simplify_cfg, "Simplifying:"),
+        SsaPass::new(Ssa::array_set_optimization, "Array Set Optimizations"),
+    ]
 }
 
 // Helper to time SSA passes
@@ -304,7 +361,7 @@ pub fn create_program(
     for (acir, func_sig) in generated_acirs.into_iter().zip(func_sigs) {
         let circuit_artifact = convert_generated_acir_into_circuit(
             acir,
-            func_sig,
+            Some(func_sig),
             // TODO: get rid of these clones
             debug_variables.clone(),
             debug_functions.clone(),
@@ -318,6 +375,40 @@ pub fn create_program(
     Ok(program_artifact)
 }
 
+/// Compiles an already optimized [`Ssa`] program into [`ACIR`][acvm::acir::circuit::Program].
+///
+/// SSA carries no ABI information, so unlike [`create_program`] every input of the generated
+/// circuits is private and the debug info contains no variables.
+pub fn create_program_from_ssa(
+    ssa: Ssa,
+    options: &SsaEvaluatorOptions,
+) -> Result<SsaProgramArtifact, RuntimeError> {
+    let (generated_acirs, generated_brillig, brillig_function_names, error_types) =
+        lower_into_acir(ssa, options)?;
+
+    let error_types = error_types
+        .into_iter()
+        .map(|(selector, hir_type)| (selector, ErrorType::Dynamic(hir_type)))
+        .collect();
+
+    let mut program_artifact = SsaProgramArtifact::new(generated_brillig, error_types);
+    let mut is_main = true;
+    for acir in generated_acirs {
+        let circuit_artifact = convert_generated_acir_into_circuit(
+            acir,
+            None,
+            DebugVariables::default(),
+            DebugFunctions::default(),
+            DebugTypes::default(),
+        );
+        program_artifact.add_circuit(circuit_artifact, is_main);
+        is_main = false;
+    }
+    program_artifact.brillig_names = brillig_function_names;
+
+    Ok(program_artifact)
+}
+
 pub struct SsaCircuitArtifact {
     name: String,
     circuit: Circuit<FieldElement>,
@@ -330,7 +421,7 @@ pub struct SsaCircuitArtifact {
 
 fn convert_generated_acir_into_circuit(
     mut generated_acir: GeneratedAcir<FieldElement>,
-    func_sig: FunctionSignature,
+    func_sig: Option<FunctionSignature>,
     debug_variables: DebugVariables,
     debug_functions: DebugFunctions,
     debug_types: DebugTypes,
@@ -349,8 +440,10 @@ fn convert_generated_acir_into_circuit(
         ..
     } = generated_acir;
 
-    let (public_parameter_witnesses, private_parameters) =
-        split_public_and_private_inputs(&func_sig, &input_witnesses);
+    let (public_parameter_witnesses, private_parameters) = match &func_sig {
+        Some(func_sig) => split_public_and_private_inputs(func_sig, &input_witnesses),
+        None => (BTreeSet::new(), input_witnesses.iter().copied().collect()),
+    };
 
     let public_parameters = PublicInputs(public_parameter_witnesses);
     let return_values = PublicInputs(return_witnesses.iter().copied().collect());
@@ -445,6 +538,9 @@ struct SsaBuilder {
     ssa: Ssa,
     ssa_logging: SsaLogging,
     print_codegen_timings: bool,
+
+    /// When set, the program is interpreted after every pass to check that its behavior is unchanged
+    interpreter_check: Option<InterpreterCheck>,
 }
 
 impl SsaBuilder {
@@ -465,7 +561,18 @@ impl SsaBuilder {
             let ssa_path = emit_ssa.with_extension("ssa.json");
             write_to_file(&serde_json::to_vec(&ssa).unwrap(), &ssa_path);
         }
-        Ok(SsaBuilder { ssa_logging, print_codegen_timings, ssa }.print("Initial SSA:"))
+        Ok(SsaBuilder { ssa_logging, print_codegen_timings, ssa, interpreter_check: None }
+            .print("Initial SSA:"))
+    }
+
+    /// Interprets the program with the given flattened inputs after every subsequent pass,
+    /// panicking if a pass changes its result.
+    fn with_interpreter_check(mut self, inputs: &[FieldElement]) -> Result<Self, RuntimeError> {
+        let check = InterpreterCheck::new(&self.ssa, inputs).map_err(|reason| {
+            RuntimeError::InvalidSsaCheckInputs { reason, call_stack: CallStack::new() }
+        })?;
+        self.interpreter_check = Some(check);
+        Ok(self)
     }
 
     fn finish(self) -> Ssa {
@@ -478,6 +585,7 @@ impl SsaBuilder {
         F: FnOnce(Ssa) -> Ssa,
     {
         self.ssa = time(msg, self.print_codegen_timings, || pass(self.ssa));
+        self.check_behavior(msg);
         self.print(msg)
     }
 
@@ -487,9 +595,24 @@ impl SsaBuilder {
         F: FnOnce(Ssa) -> Result<Ssa, RuntimeError>,
     {
         self.ssa = time(msg, self.print_codegen_timings, || pass(self.ssa))?;
+        self.check_behavior(msg);
         Ok(self.print(msg))
     }
 
+    /// Runs each of the given passes in order.
+    fn run_passes(mut self, passes: &[SsaPass]) -> Result<Self, RuntimeError> {
+        for pass in passes {
+            self = self.try_run_pass(|ssa| pass.run(ssa), pass.msg)?;
+        }
+        Ok(self)
+    }
+
+    fn check_behavior(&self, msg: &str) {
+        if let Some(check) = &self.interpreter_check {
+            check.check(&self.ssa, msg);
+        }
+    }
+
     fn print(mut self, msg: &str) -> Self {
         let print_ssa_pass = match &self.ssa_logging {
             SsaLogging::None => false,
@@ -0,0 +1,97 @@
+use thiserror::Error;
+
+use crate::ssa::ir::{
+    basic_block::BasicBlockId, function::FunctionId, instruction::BinaryOp, types::Type,
+    value::ValueId,
+};
+
+/// Errors raised while interpreting an SSA program.
+///
+/// These fall into two groups: failures that the program itself would also trigger when
+/// executed by the ACVM or Brillig VM (failed constraints, out of bounds indices, overflows),
+/// and malformed SSA which can only be caused by a bug in the compiler.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub(crate) enum InterpreterError {
+    #[error("Constraint failed in {function}: {lhs} != {rhs}{}", display_message(.message))]
+    ConstrainEqFailed { function: FunctionId, lhs: String, rhs: String, message: Option<String> },
+    #[error("Range check of {value} to {max_bit_size} bits failed in {function}{}", display_message(.message))]
+    RangeCheckFailed {
+        function: FunctionId,
+        value: String,
+        max_bit_size: u32,
+        message: Option<String>,
+    },
+    #[error("Attempt to {operator} with overflow in {function}: {lhs} {operator} {rhs}")]
+    Overflow { function: FunctionId, operator: BinaryOp, lhs: String, rhs: String },
+    #[error("Attempt to divide by zero in {function}")]
+    DivisionByZero { function: FunctionId },
+    #[error("Index {index} is out of bounds for array of length {length} in {function}")]
+    IndexOutOfBounds { function: FunctionId, index: u128, length: usize },
+    #[error("Attempt to remove an element from an empty slice in {function}")]
+    PopFromEmptySlice { function: FunctionId },
+    #[error("Loaded from {address} in {function} before anything was stored to it")]
+    UninitializedLoad { function: FunctionId, address: ValueId },
+    #[error("static_assert failed in {function}: {message}")]
+    StaticAssertFailed { function: FunctionId, message: String },
+    #[error("Black box function `{name}` failed: {reason}")]
+    BlackBoxFailed { name: String, reason: String },
+
+    // The errors below come from features the interpreter doesn't support
+    #[error("Called unknown foreign function `{name}`")]
+    UnknownForeignFunction { name: String },
+    #[error("Black box function `{name}` is not supported by the SSA interpreter")]
+    UnsupportedBlackBox { name: String },
+    #[error("Exceeded the maximum call depth of {max_depth}")]
+    CallDepthExceeded { max_depth: usize },
+
+    // The errors below indicate malformed SSA rather than a failure of the program
+    #[error("ICE: {value} in {function} was used before being defined")]
+    UndefinedValue { function: FunctionId, value: ValueId },
+    #[error("ICE: expected a value of type {expected} but found `{found}` in {function}")]
+    TypeError { function: FunctionId, expected: String, found: String },
+    #[error("ICE: {function} expects {expected} arguments but was called with {found}")]
+    ArgumentCountMismatch { function: FunctionId, expected: usize, found: usize },
+    #[error("ICE: block {block} in {function} has no terminator")]
+    MissingTerminator { function: FunctionId, block: BasicBlockId },
+    #[error("ICE: called {function} which does not exist in the program")]
+    UnknownFunction { function: FunctionId },
+    #[error("ICE: `{name}` expects {expected} elements for {input} but was given {found}")]
+    BlackBoxInputLength { name: String, input: &'static str, expected: usize, found: usize },
+    #[error("ICE: cannot {operator} values of type {typ}")]
+    UnsupportedOperator { operator: BinaryOp, typ: Type },
+}
+
+impl InterpreterError {
+    /// Whether this error says nothing about the behavior of the program, as it comes from
+    /// malformed SSA or from a feature the interpreter doesn't support.
+    pub(crate) fn is_inconclusive(&self) -> bool {
+        match self {
+            InterpreterError::ConstrainEqFailed { .. }
+            | InterpreterError::RangeCheckFailed { .. }
+            | InterpreterError::Overflow { .. }
+            | InterpreterError::DivisionByZero { .. }
+            | InterpreterError::IndexOutOfBounds { .. }
+            | InterpreterError::PopFromEmptySlice { .. }
+            | InterpreterError::UninitializedLoad { .. }
+            | InterpreterError::StaticAssertFailed { .. }
+            | InterpreterError::BlackBoxFailed { .. } => false,
+            InterpreterError::UnknownForeignFunction { .. }
+            | InterpreterError::UnsupportedBlackBox { .. }
+            | InterpreterError::CallDepthExceeded { .. }
+            | InterpreterError::UndefinedValue { .. }
+            | InterpreterError::TypeError { .. }
+            | InterpreterError::ArgumentCountMismatch { .. }
+            | InterpreterError::MissingTerminator { .. }
+            | InterpreterError::UnknownFunction { .. }
+            | InterpreterError::BlackBoxInputLength { .. }
+            | InterpreterError::UnsupportedOperator { .. } => true,
+        }
+    }
+}
+
+fn display_message(message: &Option<String>) -> String {
+    match message {
+        Some(message) => format!(": {message}"),
+        None => String::new(),
+    }
+}
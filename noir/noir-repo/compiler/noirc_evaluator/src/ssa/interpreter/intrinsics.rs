@@ -0,0 +1,474 @@
+use acvm::{
+    acir::{AcirField, BlackBoxFunc},
+    BlackBoxFunctionSolver, BlackBoxResolutionError, FieldElement,
+};
+use bn254_blackbox_solver::derive_generators;
+use iter_extended::{try_vecmap, vecmap};
+use num_bigint::BigUint;
+
+use crate::ssa::ir::{
+    function::Function,
+    instruction::{Endian, Hint, Intrinsic},
+    types::{NumericType, Type},
+    value::ValueId,
+};
+
+use super::{truncate_field, IResult, Interpreter, InterpreterError, Value};
+
+impl Interpreter<'_> {
+    /// Executes a call to the given intrinsic, returning its results.
+    ///
+    /// `results` are the ids of the call's results, used to recover result types which
+    /// can't be derived from the arguments (e.g. the number of limbs of `to_le_bits`).
+    pub(super) fn call_intrinsic(
+        &self,
+        function: &Function,
+        intrinsic: Intrinsic,
+        args: Vec<Value>,
+        results: &[ValueId],
+    ) -> IResult<Vec<Value>> {
+        let result_type = |index: usize| function.dfg.type_of_value(results[index]);
+
+        match intrinsic {
+            Intrinsic::ArrayLen => match &args[0] {
+                Value::ArrayOrSlice(array) => Ok(vec![Value::u32(array.len() as u32)]),
+                // Slices are passed as a `(length, contents)` pair
+                length @ Value::Numeric(_) => Ok(vec![length.clone()]),
+                other => Err(self.type_error("an array or slice", other)),
+            },
+            Intrinsic::ArrayAsStrUnchecked | Intrinsic::StrAsBytes => Ok(vec![args[0].clone()]),
+            Intrinsic::Hint(Hint::BlackBox) => Ok(args),
+            Intrinsic::AsSlice => {
+                let array = self.expect_array(&args[0])?;
+                let elements = array.elements.borrow().clone();
+                let length = Value::u32(array.len() as u32);
+                Ok(vec![length, Value::slice(elements, array.element_types.clone())])
+            }
+            Intrinsic::AssertConstant | Intrinsic::AsWitness => Ok(Vec::new()),
+            Intrinsic::StaticAssert => {
+                if self.expect_bool(&args[0])? {
+                    Ok(Vec::new())
+                } else {
+                    let message =
+                        super::try_value_to_string(&args[1]).unwrap_or_else(|| args[1].to_string());
+                    Err(InterpreterError::StaticAssertFailed { function: function.id(), message })
+                }
+            }
+            Intrinsic::SlicePushBack => {
+                let (length, slice) = self.expect_slice(&args[0], &args[1])?;
+                let mut elements = slice;
+                elements.extend(args[2..].iter().cloned());
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                Ok(vec![Value::u32(length + 1), Value::slice(elements, element_types)])
+            }
+            Intrinsic::SlicePushFront => {
+                let (length, slice) = self.expect_slice(&args[0], &args[1])?;
+                let mut elements = args[2..].to_vec();
+                elements.extend(slice);
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                Ok(vec![Value::u32(length + 1), Value::slice(elements, element_types)])
+            }
+            Intrinsic::SlicePopBack => {
+                let (length, mut elements) = self.expect_slice(&args[0], &args[1])?;
+                if length == 0 {
+                    return self.pop_from_empty_slice(function, results);
+                }
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                let popped = elements.split_off(elements.len() - element_types.len());
+                let mut results =
+                    vec![Value::u32(length - 1), Value::slice(elements, element_types)];
+                results.extend(popped);
+                Ok(results)
+            }
+            Intrinsic::SlicePopFront => {
+                let (length, mut elements) = self.expect_slice(&args[0], &args[1])?;
+                if length == 0 {
+                    return self.pop_from_empty_slice(function, results);
+                }
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                let rest = elements.split_off(element_types.len());
+                let mut results = elements;
+                results.push(Value::u32(length - 1));
+                results.push(Value::slice(rest, element_types));
+                Ok(results)
+            }
+            Intrinsic::SliceInsert => {
+                let (length, mut elements) = self.expect_slice(&args[0], &args[1])?;
+                let index = self.expect_u32(&args[2])?;
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                if index > length {
+                    return self.slice_index_out_of_bounds(function, index, length, results);
+                }
+                let position = index as usize * element_types.len();
+                elements.splice(position..position, args[3..].iter().cloned());
+                Ok(vec![Value::u32(length + 1), Value::slice(elements, element_types)])
+            }
+            Intrinsic::SliceRemove => {
+                let (length, mut elements) = self.expect_slice(&args[0], &args[1])?;
+                let index = self.expect_u32(&args[2])?;
+                let element_types = self.expect_array(&args[1])?.element_types.clone();
+                if index >= length {
+                    return self.slice_index_out_of_bounds(function, index, length, results);
+                }
+                let position = index as usize * element_types.len();
+                let removed: Vec<_> =
+                    elements.drain(position..position + element_types.len()).collect();
+                let mut results =
+                    vec![Value::u32(length - 1), Value::slice(elements, element_types)];
+                results.extend(removed);
+                Ok(results)
+            }
+            Intrinsic::ApplyRangeConstraint => {
+                let value = self.expect_numeric(&args[0])?;
+                let max_bit_size = self.expect_u32(&args[1])?;
+                if value.num_bits() > max_bit_size {
+                    return Err(InterpreterError::RangeCheckFailed {
+                        function: function.id(),
+                        value: value.to_string(),
+                        max_bit_size,
+                        message: Some("call to assert_max_bit_size".to_owned()),
+                    });
+                }
+                Ok(Vec::new())
+            }
+            Intrinsic::ToBits(endian) => {
+                let field = self.expect_numeric(&args[0])?;
+                self.to_radix(function, endian, field, 2, result_type(0), NumericType::bool())
+            }
+            Intrinsic::ToRadix(endian) => {
+                let field = self.expect_numeric(&args[0])?;
+                let radix = self.expect_u32(&args[1])?;
+                let limb_type = NumericType::Unsigned { bit_size: 8 };
+                self.to_radix(function, endian, field, radix, result_type(0), limb_type)
+            }
+            Intrinsic::BlackBox(black_box) => {
+                let result = self.call_black_box(black_box, args, &result_type(0))?;
+                Ok(vec![result])
+            }
+            Intrinsic::FromField => {
+                let field = self.expect_numeric(&args[0])?;
+                let typ = result_type(0).unwrap_numeric();
+                Ok(vec![Value::numeric(truncate_field(field, typ.bit_size()), typ)])
+            }
+            Intrinsic::AsField => {
+                let value = self.expect_numeric(&args[0])?;
+                Ok(vec![Value::field(value)])
+            }
+            Intrinsic::IsUnconstrained => Ok(vec![Value::bool(function.runtime().is_brillig())]),
+            Intrinsic::DerivePedersenGenerators => {
+                let Type::Array(_, num_generators) = result_type(0) else {
+                    unreachable!("ICE: derive_pedersen_generators must return an array")
+                };
+                let domain_separator = self.expect_bytes(&args[0])?;
+                let starting_index = self.expect_u32(&args[1])?;
+                let generators =
+                    derive_generators(&domain_separator, num_generators, starting_index);
+
+                let mut elements = Vec::with_capacity(generators.len() * 3);
+                for generator in generators {
+                    let x_big: BigUint = generator.x.into();
+                    let y_big: BigUint = generator.y.into();
+                    elements.push(Value::field(FieldElement::from_be_bytes_reduce(
+                        &x_big.to_bytes_be(),
+                    )));
+                    elements.push(Value::field(FieldElement::from_be_bytes_reduce(
+                        &y_big.to_bytes_be(),
+                    )));
+                    elements.push(Value::bool(false));
+                }
+                let element_types = vec![Type::field(), Type::field(), Type::bool()];
+                Ok(vec![Value::array(elements, element_types)])
+            }
+            Intrinsic::FieldLessThan => {
+                let lhs = self.expect_numeric(&args[0])?;
+                let rhs = self.expect_numeric(&args[1])?;
+                Ok(vec![Value::bool(lhs < rhs)])
+            }
+            Intrinsic::ArrayRefCount | Intrinsic::SliceRefCount => {
+                // Reference counts are only tracked in Brillig, ACIR always reports 0
+                let rc = if function.runtime().is_brillig() {
+                    let array = args
+                        .iter()
+                        .find_map(Value::as_array_or_slice)
+                        .ok_or_else(|| self.type_error("an array or slice", &args[0]))?;
+                    *array.rc.borrow()
+                } else {
+                    0
+                };
+                Ok(vec![Value::u32(rc)])
+            }
+        }
+    }
+
+    fn to_radix(
+        &self,
+        function: &Function,
+        endian: Endian,
+        field: FieldElement,
+        radix: u32,
+        result_type: Type,
+        limb_type: NumericType,
+    ) -> IResult<Vec<Value>> {
+        let Type::Array(_, limb_count) = result_type else {
+            unreachable!("ICE: to_radix must return an array")
+        };
+
+        let value = BigUint::from_bytes_be(&field.to_be_bytes());
+        let digits = value.to_radix_le(radix);
+        if digits.len() > limb_count as usize && self.side_effects_enabled() {
+            return Err(InterpreterError::RangeCheckFailed {
+                function: function.id(),
+                value: field.to_string(),
+                max_bit_size: limb_count * (u32::BITS - (radix - 1).leading_zeros()),
+                message: Some(format!(
+                    "Field failed to decompose into specified {limb_count} limbs"
+                )),
+            });
+        }
+
+        let mut limbs = vecmap(0..limb_count as usize, |i| {
+            let digit = digits.get(i).copied().unwrap_or_default();
+            Value::numeric(FieldElement::from(digit as u128), limb_type)
+        });
+        if endian == Endian::Big {
+            limbs.reverse();
+        }
+        Ok(vec![Value::array(limbs, vec![Type::Numeric(limb_type)])])
+    }
+
+    fn call_black_box(
+        &self,
+        black_box: BlackBoxFunc,
+        args: Vec<Value>,
+        result_type: &Type,
+    ) -> IResult<Value> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "bn254")] {
+                let solver = bn254_blackbox_solver::Bn254BlackBoxSolver;
+            } else {
+                let solver = acvm::blackbox_solver::StubbedBlackBoxSolver;
+            }
+        };
+
+        // Slices are passed as `(length, contents)`, the black box functions only need the contents.
+        let args = strip_slice_lengths(args);
+        let failed = |error: BlackBoxResolutionError| InterpreterError::BlackBoxFailed {
+            name: black_box.name().to_string(),
+            reason: error.to_string(),
+        };
+
+        let outputs: Vec<FieldElement> = match black_box {
+            BlackBoxFunc::Blake2s => {
+                let inputs = self.expect_bytes(&args[0])?;
+                let hash = acvm::blackbox_solver::blake2s(&inputs).map_err(failed)?;
+                vecmap(hash, |byte| FieldElement::from(byte as u128))
+            }
+            BlackBoxFunc::Blake3 => {
+                let inputs = self.expect_bytes(&args[0])?;
+                let hash = acvm::blackbox_solver::blake3(&inputs).map_err(failed)?;
+                vecmap(hash, |byte| FieldElement::from(byte as u128))
+            }
+            BlackBoxFunc::Keccakf1600 => {
+                let inputs = try_vecmap(self.expect_fields(&args[0])?, |field| {
+                    field.try_to_u64().ok_or_else(|| self.type_error("a u64", &Value::field(field)))
+                })?;
+                let inputs = fixed_length(black_box, "its input", inputs)?;
+                let state = acvm::blackbox_solver::keccakf1600(inputs).map_err(failed)?;
+                vecmap(state, |word| FieldElement::from(word as u128))
+            }
+            BlackBoxFunc::Sha256Compression => {
+                let inputs = self.expect_u32_array(&args[0])?;
+                let mut state: [u32; 8] =
+                    fixed_length(black_box, "its state", self.expect_u32_array(&args[1])?)?;
+                let inputs = fixed_length(black_box, "its input", inputs)?;
+                acvm::blackbox_solver::sha256_compression(&mut state, &inputs);
+                vecmap(state, |word| FieldElement::from(word as u128))
+            }
+            BlackBoxFunc::EcdsaSecp256k1 | BlackBoxFunc::EcdsaSecp256r1 => {
+                let public_key_x: [u8; 32] =
+                    fixed_length(black_box, "the public key", self.expect_bytes(&args[0])?)?;
+                let public_key_y: [u8; 32] =
+                    fixed_length(black_box, "the public key", self.expect_bytes(&args[1])?)?;
+                let signature: [u8; 64] =
+                    fixed_length(black_box, "the signature", self.expect_bytes(&args[2])?)?;
+                let hashed_message = self.expect_bytes(&args[3])?;
+                let verify = if black_box == BlackBoxFunc::EcdsaSecp256k1 {
+                    acvm::blackbox_solver::ecdsa_secp256k1_verify
+                } else {
+                    acvm::blackbox_solver::ecdsa_secp256r1_verify
+                };
+                let valid = verify(&hashed_message, &public_key_x, &public_key_y, &signature)
+                    .map_err(failed)?;
+                return Ok(Value::bool(valid));
+            }
+            BlackBoxFunc::Poseidon2Permutation => {
+                let inputs = self.expect_fields(&args[0])?;
+                let length = self.expect_u32(&args[1])?;
+                solver.poseidon2_permutation(&inputs, length).map_err(failed)?
+            }
+            BlackBoxFunc::EmbeddedCurveAdd => {
+                let inputs = try_vecmap(&args[0..6], |arg| self.expect_numeric(arg))?;
+                let (x, y, is_infinite) = solver
+                    .ec_add(&inputs[0], &inputs[1], &inputs[2], &inputs[3], &inputs[4], &inputs[5])
+                    .map_err(failed)?;
+                vec![x, y, is_infinite]
+            }
+            BlackBoxFunc::MultiScalarMul => {
+                let points = self.expect_fields(&args[0])?;
+                let scalars = self.expect_fields(&args[1])?;
+                let (scalars_lo, scalars_hi): (Vec<_>, Vec<_>) =
+                    scalars.chunks(2).map(|chunk| (chunk[0], chunk[1])).unzip();
+                let (x, y, is_infinite) =
+                    solver.multi_scalar_mul(&points, &scalars_lo, &scalars_hi).map_err(failed)?;
+                vec![x, y, is_infinite]
+            }
+            BlackBoxFunc::AND | BlackBoxFunc::XOR | BlackBoxFunc::RANGE => {
+                unreachable!(
+                    "ICE: `{black_box}` calls should have been transformed into instructions"
+                )
+            }
+            BlackBoxFunc::AES128Encrypt
+            | BlackBoxFunc::RecursiveAggregation
+            | BlackBoxFunc::BigIntAdd
+            | BlackBoxFunc::BigIntSub
+            | BlackBoxFunc::BigIntMul
+            | BlackBoxFunc::BigIntDiv
+            | BlackBoxFunc::BigIntFromLeBytes
+            | BlackBoxFunc::BigIntToLeBytes => {
+                return Err(InterpreterError::UnsupportedBlackBox {
+                    name: black_box.name().to_string(),
+                });
+            }
+        };
+
+        Ok(make_array_of_type(outputs, result_type))
+    }
+
+    fn pop_from_empty_slice(
+        &self,
+        function: &Function,
+        results: &[ValueId],
+    ) -> IResult<Vec<Value>> {
+        if self.side_effects_enabled() {
+            Err(InterpreterError::PopFromEmptySlice { function: function.id() })
+        } else {
+            Ok(vecmap(results, |result| Value::uninitialized(&function.dfg.type_of_value(*result))))
+        }
+    }
+
+    fn slice_index_out_of_bounds(
+        &self,
+        function: &Function,
+        index: u32,
+        length: u32,
+        results: &[ValueId],
+    ) -> IResult<Vec<Value>> {
+        if self.side_effects_enabled() {
+            Err(InterpreterError::IndexOutOfBounds {
+                function: function.id(),
+                index: index as u128,
+                length: length as usize,
+            })
+        } else {
+            Ok(vecmap(results, |result| Value::uninitialized(&function.dfg.type_of_value(*result))))
+        }
+    }
+
+    fn expect_numeric(&self, value: &Value) -> IResult<FieldElement> {
+        match value {
+            Value::Numeric(numeric) => Ok(numeric.value),
+            other => Err(self.type_error("a numeric value", other)),
+        }
+    }
+
+    fn expect_bool(&self, value: &Value) -> IResult<bool> {
+        Ok(!self.expect_numeric(value)?.is_zero())
+    }
+
+    fn expect_u32(&self, value: &Value) -> IResult<u32> {
+        let field = self.expect_numeric(value)?;
+        field.try_to_u32().ok_or_else(|| self.type_error("a u32", value))
+    }
+
+    fn expect_array(&self, value: &Value) -> IResult<super::ArrayValue> {
+        match value {
+            Value::ArrayOrSlice(array) => Ok(array.clone()),
+            other => Err(self.type_error("an array or slice", other)),
+        }
+    }
+
+    /// Returns the length and the elements of the `(length, contents)` pair representing a slice.
+    /// Any elements past the length (left over from the slice's capacity) are dropped.
+    fn expect_slice(&self, length: &Value, slice: &Value) -> IResult<(u32, Vec<Value>)> {
+        let length = self.expect_u32(length)?;
+        let slice = self.expect_array(slice)?;
+        let element_size = slice.element_types.len();
+        let mut elements = slice.elements.borrow().clone();
+        elements.truncate(length as usize * element_size);
+        Ok((length, elements))
+    }
+
+    fn expect_fields(&self, value: &Value) -> IResult<Vec<FieldElement>> {
+        let array = self.expect_array(value)?;
+        let elements = array.elements.borrow();
+        try_vecmap(elements.iter(), |element| self.expect_numeric(element))
+    }
+
+    fn expect_bytes(&self, value: &Value) -> IResult<Vec<u8>> {
+        try_vecmap(self.expect_fields(value)?, |field| {
+            field
+                .try_to_u32()
+                .filter(|byte| *byte <= 0xFF)
+                .map(|byte| byte as u8)
+                .ok_or_else(|| self.type_error("a u8", &Value::field(field)))
+        })
+    }
+
+    fn expect_u32_array(&self, value: &Value) -> IResult<Vec<u32>> {
+        try_vecmap(self.expect_fields(value)?, |field| {
+            field.try_to_u32().ok_or_else(|| self.type_error("a u32", &Value::field(field)))
+        })
+    }
+}
+
+/// Converts the inputs of a black box function into the fixed size array it expects.
+fn fixed_length<T, const N: usize>(
+    black_box: BlackBoxFunc,
+    input: &'static str,
+    values: Vec<T>,
+) -> IResult<[T; N]> {
+    let found = values.len();
+    values.try_into().map_err(|_| InterpreterError::BlackBoxInputLength {
+        name: black_box.name().to_string(),
+        input,
+        expected: N,
+        found,
+    })
+}
+
+/// Removes the length argument preceding each slice argument.
+fn strip_slice_lengths(args: Vec<Value>) -> Vec<Value> {
+    let mut stripped: Vec<Value> = Vec::with_capacity(args.len());
+    for arg in args {
+        let is_slice = matches!(&arg, Value::ArrayOrSlice(array) if array.is_slice);
+        if is_slice && matches!(stripped.last(), Some(Value::Numeric(_))) {
+            stripped.pop();
+        }
+        stripped.push(arg);
+    }
+    stripped
+}
+
+/// Creates an array of the given type out of a flat list of fields,
+/// giving each element the numeric type of its position in the array's element types.
+fn make_array_of_type(fields: Vec<FieldElement>, typ: &Type) -> Value {
+    let element_types = match typ {
+        Type::Array(element_types, _) | Type::Slice(element_types) => element_types.clone(),
+        other => unreachable!("ICE: expected black box to return an array, found {other}"),
+    };
+    let elements = vecmap(fields.into_iter().enumerate(), |(i, field)| {
+        let typ = element_types[i % element_types.len()].unwrap_numeric();
+        Value::numeric(field, typ)
+    });
+    Value::array(elements, element_types.as_ref().clone())
+}
@@ -0,0 +1,744 @@
+//! A reference interpreter for the SSA IR.
+//!
+//! The interpreter executes an [`Ssa`] program directly on concrete values, without lowering
+//! it to ACIR or Brillig first. Its purpose is differential testing of the optimization passes:
+//! running the same inputs through the program before and after a pass must produce the
+//! same results, so any difference points directly at a miscompilation in that pass.
+//!
+//! The semantics follow those of the generated code as closely as possible:
+//! - `enable_side_effects` disables failures of instructions which acir-gen predicates
+//!   (division, out of bounds array accesses and array writes, calls).
+//! - `inc_rc`/`dec_rc` are tracked in Brillig functions and a non-mutable `array_set` on an
+//!   array with a reference count of 1 mutates it in place, as the Brillig VM would.
+//! - Unsigned arithmetic which overflows is a failure, while signed arithmetic wraps since
+//!   its overflow checks are made explicit in the SSA.
+use std::{cell::RefCell, rc::Rc};
+
+use acvm::{acir::AcirField, FieldElement};
+use fxhash::FxHashMap as HashMap;
+use iter_extended::vecmap;
+use num_bigint::BigUint;
+
+use super::{
+    ir::{
+        basic_block::BasicBlockId,
+        function::{Function, FunctionId},
+        instruction::{
+            Binary, BinaryOp, ConstrainError, Instruction, InstructionId, TerminatorInstruction,
+        },
+        types::{NumericType, Type},
+        value::{Value as SsaValue, ValueId},
+    },
+    Ssa,
+};
+
+mod errors;
+mod intrinsics;
+mod tests;
+pub(crate) mod value;
+
+pub(crate) use errors::InterpreterError;
+use value::{ArrayValue, NumericValue, ReferenceValue, Value};
+
+/// The maximum depth of nested calls before execution is aborted.
+/// This keeps accidental infinite recursion from overflowing the native stack.
+const MAX_CALL_DEPTH: usize = 1000;
+
+type IResult<T> = Result<T, InterpreterError>;
+
+impl Ssa {
+    /// Interprets the `main` function of this program with the given arguments,
+    /// returning the values it returns.
+    pub(crate) fn interpret(&self, args: Vec<Value>) -> IResult<Vec<Value>> {
+        self.interpret_function(self.main_id, args)
+    }
+
+    /// Interprets the given function of this program with the given arguments.
+    pub(crate) fn interpret_function(
+        &self,
+        function: FunctionId,
+        args: Vec<Value>,
+    ) -> IResult<Vec<Value>> {
+        let mut interpreter = Interpreter::new(self);
+        interpreter.call_function(function, args)
+    }
+}
+
+/// Checks that an SSA pass preserves the behavior of a program.
+///
+/// The program is interpreted once with the given inputs when the check is created, and
+/// `check` then asserts that interpreting it again with the same inputs gives the same result.
+pub(crate) struct InterpreterCheck {
+    inputs: Vec<Value>,
+    expected: IResult<Vec<Value>>,
+}
+
+impl InterpreterCheck {
+    /// Creates a check for `main` called with `inputs`, flattened in the same order as
+    /// `main`'s witnesses: one field per numeric parameter or array element.
+    ///
+    /// Returns an error if `inputs` don't match the parameters of `main`.
+    pub(crate) fn new(ssa: &Ssa, inputs: &[FieldElement]) -> Result<Self, String> {
+        let main = ssa.main();
+        let parameter_types =
+            vecmap(main.parameters(), |parameter| main.dfg.type_of_value(*parameter));
+        let mut fields = inputs.iter().copied();
+        let inputs = parameter_types
+            .iter()
+            .map(|typ| unflatten_input(typ, &mut fields))
+            .collect::<Result<Vec<_>, _>>()?;
+        if fields.next().is_some() {
+            return Err("more inputs were given than `main` takes".to_string());
+        }
+
+        let expected = ssa.interpret(vecmap(&inputs, Value::snapshot));
+        if let Err(error) = &expected {
+            if error.is_inconclusive() {
+                eprintln!(
+                    "Warning: SSA passes are not checked, as interpreting the initial SSA failed with: {error}"
+                );
+            }
+        }
+        Ok(Self { inputs, expected })
+    }
+
+    /// Panics if interpreting `ssa` no longer gives the expected result.
+    ///
+    /// Only the kind of error is compared for failing programs since passes such as inlining
+    /// legitimately change which function an error is reported in. Nothing is checked when the
+    /// initial program failed with an inconclusive error.
+    pub(crate) fn check(&self, ssa: &Ssa, pass: &str) {
+        if matches!(&self.expected, Err(error) if error.is_inconclusive()) {
+            return;
+        }
+        let result = ssa.interpret(vecmap(&self.inputs, Value::snapshot));
+        let preserved = match (&self.expected, &result) {
+            (Ok(expected), Ok(result)) => {
+                expected.len() == result.len()
+                    && expected.iter().zip(result).all(|(lhs, rhs)| lhs.structurally_eq(rhs))
+            }
+            (Err(expected), Err(result)) => {
+                std::mem::discriminant(expected) == std::mem::discriminant(result)
+            }
+            _ => false,
+        };
+        if !preserved {
+            panic!(
+                "{pass} changed the behavior of the program:\nexpected: {}\nfound: {}\n{ssa}",
+                display_result(&self.expected),
+                display_result(&result)
+            );
+        }
+    }
+}
+
+/// Builds a value of type `typ` from the next fields of a flattened input.
+fn unflatten_input(
+    typ: &Type,
+    fields: &mut impl Iterator<Item = FieldElement>,
+) -> Result<Value, String> {
+    match typ {
+        Type::Numeric(numeric_type) => {
+            let field = fields
+                .next()
+                .ok_or_else(|| "fewer inputs were given than `main` takes".to_string())?;
+            Ok(Value::numeric(field, *numeric_type))
+        }
+        Type::Array(element_types, length) => {
+            let mut elements = Vec::new();
+            for _ in 0..*length {
+                for typ in element_types.iter() {
+                    elements.push(unflatten_input(typ, fields)?);
+                }
+            }
+            Ok(Value::array(elements, element_types.to_vec()))
+        }
+        _ => Err(format!("`main` cannot take a parameter of type {typ}")),
+    }
+}
+
+fn display_result(result: &IResult<Vec<Value>>) -> String {
+    match result {
+        Ok(values) => vecmap(values, ToString::to_string).join(", "),
+        Err(error) => error.to_string(),
+    }
+}
+
+struct Interpreter<'ssa> {
+    ssa: &'ssa Ssa,
+    call_stack: Vec<CallContext>,
+}
+
+/// The state of a single function call
+struct CallContext {
+    function: FunctionId,
+
+    /// The value of every instruction result and block parameter defined so far
+    scope: HashMap<ValueId, Value>,
+
+    /// Whether side effects were enabled in the caller at the point of this call.
+    /// An `enable_side_effects` inside the callee can only further restrict this.
+    side_effects_enabled_by_caller: bool,
+
+    /// Whether side effects are enabled at the current instruction
+    side_effects_enabled: bool,
+}
+
+impl CallContext {
+    fn new(function: FunctionId, side_effects_enabled: bool) -> Self {
+        Self {
+            function,
+            scope: HashMap::default(),
+            side_effects_enabled_by_caller: side_effects_enabled,
+            side_effects_enabled,
+        }
+    }
+}
+
+impl<'ssa> Interpreter<'ssa> {
+    fn new(ssa: &'ssa Ssa) -> Self {
+        Self { ssa, call_stack: Vec::new() }
+    }
+
+    fn current_context(&self) -> &CallContext {
+        self.call_stack.last().expect("ICE: interpreter should always be inside a function")
+    }
+
+    fn current_context_mut(&mut self) -> &mut CallContext {
+        self.call_stack.last_mut().expect("ICE: interpreter should always be inside a function")
+    }
+
+    fn current_function(&self) -> FunctionId {
+        self.current_context().function
+    }
+
+    fn side_effects_enabled(&self) -> bool {
+        self.current_context().side_effects_enabled
+    }
+
+    fn call_function(&mut self, id: FunctionId, args: Vec<Value>) -> IResult<Vec<Value>> {
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(InterpreterError::CallDepthExceeded { max_depth: MAX_CALL_DEPTH });
+        }
+
+        let ssa = self.ssa;
+        let function =
+            ssa.functions.get(&id).ok_or(InterpreterError::UnknownFunction { function: id })?;
+
+        let side_effects_enabled = self.call_stack.last().map_or(true, |c| c.side_effects_enabled);
+        self.call_stack.push(CallContext::new(id, side_effects_enabled));
+        let result = self.run_function(function, args);
+        self.call_stack.pop();
+        result
+    }
+
+    fn run_function(&mut self, function: &Function, mut args: Vec<Value>) -> IResult<Vec<Value>> {
+        let mut block_id = function.entry_block();
+
+        loop {
+            let block = &function.dfg[block_id];
+            self.define_block_parameters(function, block_id, args)?;
+
+            for instruction in block.instructions() {
+                self.interpret_instruction(function, *instruction)?;
+            }
+
+            match block.terminator() {
+                Some(TerminatorInstruction::Jmp { destination, arguments, .. }) => {
+                    // Evaluate the arguments before jumping since they may refer to
+                    // the parameters of the destination block, as loops do.
+                    args = self.lookup_all(function, arguments)?;
+                    block_id = *destination;
+                }
+                Some(TerminatorInstruction::JmpIf {
+                    condition,
+                    then_destination,
+                    else_destination,
+                    ..
+                }) => {
+                    let condition = self.lookup_bool(function, *condition)?;
+                    block_id = if condition { *then_destination } else { *else_destination };
+                    args = Vec::new();
+                }
+                Some(TerminatorInstruction::Return { return_values, .. }) => {
+                    return self.lookup_all(function, return_values);
+                }
+                None => {
+                    let function = function.id();
+                    return Err(InterpreterError::MissingTerminator { function, block: block_id });
+                }
+            }
+        }
+    }
+
+    fn define_block_parameters(
+        &mut self,
+        function: &Function,
+        block: BasicBlockId,
+        args: Vec<Value>,
+    ) -> IResult<()> {
+        let parameters = function.dfg.block_parameters(block);
+        if parameters.len() != args.len() {
+            return Err(InterpreterError::ArgumentCountMismatch {
+                function: function.id(),
+                expected: parameters.len(),
+                found: args.len(),
+            });
+        }
+
+        for (parameter, arg) in parameters.iter().zip(args) {
+            self.define(*parameter, arg);
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, id: ValueId, value: Value) {
+        self.current_context_mut().scope.insert(id, value);
+    }
+
+    fn lookup(&self, function: &Function, id: ValueId) -> IResult<Value> {
+        // Values which were replaced by another keep a copy of the replacement's data,
+        // so we key the scope by the instruction result or block parameter the data points to.
+        let key = match &function.dfg[id] {
+            SsaValue::NumericConstant { constant, typ } => {
+                return Ok(Value::numeric(*constant, *typ));
+            }
+            SsaValue::Function(id) => return Ok(Value::Function(*id)),
+            SsaValue::Intrinsic(intrinsic) => return Ok(Value::Intrinsic(*intrinsic)),
+            SsaValue::ForeignFunction(name) => return Ok(Value::ForeignFunction(name.clone())),
+            SsaValue::Instruction { instruction, position, .. } => {
+                function.dfg.instruction_results(*instruction)[*position]
+            }
+            SsaValue::Param { block, position, .. } => {
+                function.dfg.block_parameters(*block)[*position]
+            }
+        };
+
+        self.current_context()
+            .scope
+            .get(&key)
+            .cloned()
+            .ok_or(InterpreterError::UndefinedValue { function: function.id(), value: id })
+    }
+
+    fn lookup_all(&self, function: &Function, ids: &[ValueId]) -> IResult<Vec<Value>> {
+        ids.iter().map(|id| self.lookup(function, *id)).collect()
+    }
+
+    fn lookup_numeric(&self, function: &Function, id: ValueId) -> IResult<NumericValue> {
+        let value = self.lookup(function, id)?;
+        value.as_numeric().ok_or_else(|| self.type_error("a numeric value", &value))
+    }
+
+    fn lookup_bool(&self, function: &Function, id: ValueId) -> IResult<bool> {
+        let value = self.lookup_numeric(function, id)?;
+        Ok(!value.value.is_zero())
+    }
+
+    fn lookup_u128(&self, function: &Function, id: ValueId) -> IResult<u128> {
+        let value = self.lookup_numeric(function, id)?;
+        value.value.try_into_u128().ok_or_else(|| self.type_error("an integer", &value.into()))
+    }
+
+    fn lookup_array(&self, function: &Function, id: ValueId) -> IResult<ArrayValue> {
+        let value = self.lookup(function, id)?;
+        match value {
+            Value::ArrayOrSlice(array) => Ok(array),
+            other => Err(self.type_error("an array or slice", &other)),
+        }
+    }
+
+    fn lookup_reference(&self, function: &Function, id: ValueId) -> IResult<ReferenceValue> {
+        let value = self.lookup(function, id)?;
+        match value {
+            Value::Reference(reference) => Ok(reference),
+            other => Err(self.type_error("a reference", &other)),
+        }
+    }
+
+    fn type_error(&self, expected: &str, found: &Value) -> InterpreterError {
+        InterpreterError::TypeError {
+            function: self.current_function(),
+            expected: expected.to_string(),
+            found: found.to_string(),
+        }
+    }
+
+    fn interpret_instruction(
+        &mut self,
+        function: &Function,
+        instruction_id: InstructionId,
+    ) -> IResult<()> {
+        let results = function.dfg.instruction_results(instruction_id);
+
+        match &function.dfg[instruction_id] {
+            Instruction::Binary(binary) => {
+                let result = self.interpret_binary(function, binary)?;
+                self.define(results[0], result);
+            }
+            Instruction::Cast(value, typ) => {
+                let value = self.lookup_numeric(function, *value)?;
+                self.define(results[0], Value::numeric(value.value, *typ));
+            }
+            Instruction::Not(value) => {
+                let value = self.lookup_numeric(function, *value)?;
+                let result = match value.typ {
+                    NumericType::Signed { bit_size } | NumericType::Unsigned { bit_size } => {
+                        let mask = mask(bit_size);
+                        let value = value.value.to_u128();
+                        FieldElement::from(!value & mask)
+                    }
+                    NumericType::NativeField => {
+                        return Err(self.type_error("an integer", &Value::Numeric(value)));
+                    }
+                };
+                self.define(results[0], Value::numeric(result, value.typ));
+            }
+            Instruction::Truncate { value, bit_size, max_bit_size: _ } => {
+                let value = self.lookup_numeric(function, *value)?;
+                let truncated = truncate_field(value.value, *bit_size);
+                self.define(results[0], Value::numeric(truncated, value.typ));
+            }
+            Instruction::Constrain(lhs_id, rhs_id, error) => {
+                let lhs = self.lookup(function, *lhs_id)?;
+                let rhs = self.lookup(function, *rhs_id)?;
+                if !lhs.structurally_eq(&rhs) {
+                    let message = match error {
+                        Some(error) => Some(self.constrain_error_message(function, error)?),
+                        None => None,
+                    };
+                    return Err(InterpreterError::ConstrainEqFailed {
+                        function: function.id(),
+                        lhs: lhs.to_string(),
+                        rhs: rhs.to_string(),
+                        message,
+                    });
+                }
+            }
+            Instruction::RangeCheck { value, max_bit_size, assert_message } => {
+                let value = self.lookup_numeric(function, *value)?;
+                if value.value.num_bits() > *max_bit_size {
+                    return Err(InterpreterError::RangeCheckFailed {
+                        function: function.id(),
+                        value: value.to_string(),
+                        max_bit_size: *max_bit_size,
+                        message: assert_message.clone(),
+                    });
+                }
+            }
+            Instruction::Call { func, arguments } => {
+                let func = self.lookup(function, *func)?;
+                let arguments = self.lookup_all(function, arguments)?;
+                let result_values = match func {
+                    Value::Function(id) => self.call_function(id, arguments)?,
+                    Value::Intrinsic(intrinsic) => {
+                        self.call_intrinsic(function, intrinsic, arguments, results)?
+                    }
+                    Value::ForeignFunction(name) => self.call_foreign_function(&name)?,
+                    other => return Err(self.type_error("a function", &other)),
+                };
+
+                if result_values.len() != results.len() {
+                    return Err(InterpreterError::TypeError {
+                        function: function.id(),
+                        expected: format!("{} results", results.len()),
+                        found: format!("{} results", result_values.len()),
+                    });
+                }
+                for (result, value) in results.iter().zip(result_values) {
+                    self.define(*result, value);
+                }
+            }
+            Instruction::Allocate => {
+                let element_type = match function.dfg.type_of_value(results[0]) {
+                    Type::Reference(element_type) => element_type,
+                    other => unreachable!("ICE: allocate should return a reference, not {other}"),
+                };
+                let element = Rc::new(RefCell::new(None));
+                self.define(results[0], Value::Reference(ReferenceValue { element, element_type }));
+            }
+            Instruction::Load { address } => {
+                let reference = self.lookup_reference(function, *address)?;
+                let element = reference.element.borrow().clone();
+                let element = element.ok_or(InterpreterError::UninitializedLoad {
+                    function: function.id(),
+                    address: *address,
+                })?;
+                self.define(results[0], element);
+            }
+            Instruction::Store { address, value } => {
+                let reference = self.lookup_reference(function, *address)?;
+                let value = self.lookup(function, *value)?;
+                *reference.element.borrow_mut() = Some(value);
+            }
+            Instruction::EnableSideEffectsIf { condition } => {
+                let condition = self.lookup_bool(function, *condition)?;
+                let context = self.current_context_mut();
+                context.side_effects_enabled = context.side_effects_enabled_by_caller && condition;
+            }
+            Instruction::ArrayGet { array, index } => {
+                let array = self.lookup_array(function, *array)?;
+                let index = self.lookup_u128(function, *index)?;
+                let elements = array.elements.borrow();
+                let element = match elements.get(index as usize) {
+                    Some(element) => element.clone(),
+                    None if self.side_effects_enabled() => {
+                        return Err(InterpreterError::IndexOutOfBounds {
+                            function: function.id(),
+                            index,
+                            length: elements.len(),
+                        });
+                    }
+                    // The result of a disabled out of bounds read is never observed
+                    None => Value::uninitialized(&function.dfg.type_of_value(results[0])),
+                };
+                drop(elements);
+                self.define(results[0], element);
+            }
+            Instruction::ArraySet { array, index, value, mutable } => {
+                let array = self.lookup_array(function, *array)?;
+                let index = self.lookup_u128(function, *index)?;
+                let value = self.lookup(function, *value)?;
+
+                let result = if !self.side_effects_enabled() {
+                    array
+                } else {
+                    let length = array.elements.borrow().len();
+                    if index as usize >= length {
+                        return Err(InterpreterError::IndexOutOfBounds {
+                            function: function.id(),
+                            index,
+                            length,
+                        });
+                    }
+
+                    // Brillig reuses the array's memory whenever nothing else refers to it.
+                    let in_place =
+                        *mutable || (function.runtime().is_brillig() && *array.rc.borrow() == 1);
+                    let result = if in_place { array } else { array.deep_copy() };
+                    result.elements.borrow_mut()[index as usize] = value;
+                    result
+                };
+                self.define(results[0], Value::ArrayOrSlice(result));
+            }
+            Instruction::IncrementRc { value } => {
+                if function.runtime().is_brillig() {
+                    if let Value::ArrayOrSlice(array) = self.lookup(function, *value)? {
+                        *array.rc.borrow_mut() += 1;
+                    }
+                }
+            }
+            Instruction::DecrementRc { value } => {
+                if function.runtime().is_brillig() {
+                    if let Value::ArrayOrSlice(array) = self.lookup(function, *value)? {
+                        let mut rc = array.rc.borrow_mut();
+                        *rc = rc.saturating_sub(1);
+                    }
+                }
+            }
+            Instruction::IfElse { then_condition, then_value, else_condition: _, else_value } => {
+                let condition = self.lookup_bool(function, *then_condition)?;
+                let result = if condition {
+                    self.lookup(function, *then_value)?
+                } else {
+                    self.lookup(function, *else_value)?
+                };
+                self.define(results[0], result);
+            }
+            Instruction::MakeArray { elements, typ } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.lookup(function, *element))
+                    .collect::<IResult<Vec<_>>>()?;
+                let array = match typ {
+                    Type::Array(element_types, _) => {
+                        Value::array(elements, element_types.as_ref().clone())
+                    }
+                    Type::Slice(element_types) => Value::slice(elements, element_types.clone()),
+                    other => unreachable!("ICE: make_array with non-array type {other}"),
+                };
+                self.define(results[0], array);
+            }
+        }
+        Ok(())
+    }
+
+    fn interpret_binary(&self, function: &Function, binary: &Binary) -> IResult<Value> {
+        let lhs = self.lookup_numeric(function, binary.lhs)?;
+        let rhs = self.lookup_numeric(function, binary.rhs)?;
+        let operator = binary.operator;
+        let typ = lhs.typ;
+
+        let overflow = || InterpreterError::Overflow {
+            function: function.id(),
+            operator,
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+        };
+        let unsupported =
+            || InterpreterError::UnsupportedOperator { operator, typ: Type::Numeric(typ) };
+
+        if matches!(operator, BinaryOp::Div | BinaryOp::Mod) && rhs.value.is_zero() {
+            if self.side_effects_enabled() {
+                return Err(InterpreterError::DivisionByZero { function: function.id() });
+            }
+            // A disabled division by zero is replaced by a division by one in acir-gen.
+            return Ok(Value::numeric(lhs.value, typ));
+        }
+
+        let result = match typ {
+            NumericType::NativeField => match operator {
+                BinaryOp::Add => lhs.value + rhs.value,
+                BinaryOp::Sub => lhs.value - rhs.value,
+                BinaryOp::Mul => lhs.value * rhs.value,
+                BinaryOp::Div => lhs.value / rhs.value,
+                BinaryOp::Eq => return Ok(Value::bool(lhs.value == rhs.value)),
+                BinaryOp::Lt => return Ok(Value::bool(lhs.value < rhs.value)),
+                BinaryOp::Mod
+                | BinaryOp::And
+                | BinaryOp::Or
+                | BinaryOp::Xor
+                | BinaryOp::Shl
+                | BinaryOp::Shr => return Err(unsupported()),
+            },
+            NumericType::Unsigned { bit_size } => {
+                let lhs_value = lhs.value.to_u128();
+                let rhs_value = rhs.value.to_u128();
+                let result = match operator {
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul => {
+                        let result = match operator {
+                            BinaryOp::Add => lhs_value.checked_add(rhs_value),
+                            BinaryOp::Sub => lhs_value.checked_sub(rhs_value),
+                            _ => lhs_value.checked_mul(rhs_value),
+                        };
+                        match result.filter(|result| fits_in_bits(*result, bit_size)) {
+                            Some(result) => result,
+                            None if self.side_effects_enabled() => return Err(overflow()),
+                            // Overflow checks are predicated in acir-gen as well,
+                            // and the result of a disabled operation is never observed.
+                            None => 0,
+                        }
+                    }
+                    BinaryOp::Div => lhs_value / rhs_value,
+                    BinaryOp::Mod => lhs_value % rhs_value,
+                    BinaryOp::Eq => return Ok(Value::bool(lhs_value == rhs_value)),
+                    BinaryOp::Lt => return Ok(Value::bool(lhs_value < rhs_value)),
+                    BinaryOp::And => lhs_value & rhs_value,
+                    BinaryOp::Or => lhs_value | rhs_value,
+                    BinaryOp::Xor => lhs_value ^ rhs_value,
+                    BinaryOp::Shl => {
+                        lhs_value.checked_shl(rhs_value as u32).unwrap_or(0) & mask(bit_size)
+                    }
+                    BinaryOp::Shr => lhs_value.checked_shr(rhs_value as u32).unwrap_or(0),
+                };
+                FieldElement::from(result)
+            }
+            NumericType::Signed { bit_size } => {
+                let lhs_value = to_signed(lhs.value, bit_size);
+                let rhs_value = to_signed(rhs.value, bit_size);
+                // Overflow checks for signed integers are explicit in the SSA,
+                // so the operations themselves wrap around.
+                let result = match operator {
+                    BinaryOp::Add => lhs_value.wrapping_add(rhs_value),
+                    BinaryOp::Sub => lhs_value.wrapping_sub(rhs_value),
+                    BinaryOp::Mul => lhs_value.wrapping_mul(rhs_value),
+                    BinaryOp::Div => lhs_value.wrapping_div(rhs_value),
+                    BinaryOp::Mod => lhs_value.wrapping_rem(rhs_value),
+                    BinaryOp::Eq => return Ok(Value::bool(lhs_value == rhs_value)),
+                    BinaryOp::Lt => return Ok(Value::bool(lhs_value < rhs_value)),
+                    BinaryOp::And => lhs_value & rhs_value,
+                    BinaryOp::Or => lhs_value | rhs_value,
+                    BinaryOp::Xor => lhs_value ^ rhs_value,
+                    BinaryOp::Shl => lhs_value.checked_shl(rhs.value.to_u128() as u32).unwrap_or(0),
+                    BinaryOp::Shr => {
+                        let shift = (rhs.value.to_u128() as u32).min(127);
+                        lhs_value >> shift
+                    }
+                };
+                from_signed(result, bit_size)
+            }
+        };
+
+        Ok(Value::numeric(result, typ))
+    }
+
+    fn constrain_error_message(
+        &self,
+        function: &Function,
+        error: &ConstrainError,
+    ) -> IResult<String> {
+        match error {
+            ConstrainError::StaticString(message) => Ok(message.clone()),
+            ConstrainError::Dynamic(_, is_string, values) => {
+                let values = self.lookup_all(function, values)?;
+                if *is_string && values.len() == 1 {
+                    if let Some(string) = try_value_to_string(&values[0]) {
+                        return Ok(string);
+                    }
+                }
+                Ok(vecmap(values, |value| value.to_string()).join(", "))
+            }
+        }
+    }
+
+    fn call_foreign_function(&self, name: &str) -> IResult<Vec<Value>> {
+        match name {
+            // Printing has no observable effect on the program's results
+            "print" => Ok(Vec::new()),
+            _ => Err(InterpreterError::UnknownForeignFunction { name: name.to_string() }),
+        }
+    }
+}
+
+/// Returns a mask with the lowest `bit_size` bits set
+fn mask(bit_size: u32) -> u128 {
+    if bit_size >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << bit_size) - 1
+    }
+}
+
+fn fits_in_bits(value: u128, bit_size: u32) -> bool {
+    bit_size >= 128 || value < (1u128 << bit_size)
+}
+
+/// Returns `field` modulo `2^bit_size`
+fn truncate_field(field: FieldElement, bit_size: u32) -> FieldElement {
+    let value = BigUint::from_bytes_be(&field.to_be_bytes());
+    let truncated = value % (BigUint::from(1u8) << bit_size);
+    FieldElement::from_be_bytes_reduce(&truncated.to_bytes_be())
+}
+
+/// Interprets the lowest `bit_size` bits of `field` as a two's complement integer
+fn to_signed(field: FieldElement, bit_size: u32) -> i128 {
+    let value = field.to_u128() & mask(bit_size);
+    if bit_size < 128 && value >= (1u128 << (bit_size - 1)) {
+        value as i128 - (1i128 << bit_size)
+    } else {
+        value as i128
+    }
+}
+
+/// Encodes `value` as a two's complement integer of `bit_size` bits
+fn from_signed(value: i128, bit_size: u32) -> FieldElement {
+    FieldElement::from((value as u128) & mask(bit_size))
+}
+
+fn try_value_to_string(value: &Value) -> Option<String> {
+    let array = value.as_array_or_slice()?;
+    let elements = array.elements.borrow();
+    elements
+        .iter()
+        .map(|element| {
+            let byte = element.as_numeric()?.value.try_to_u32()?;
+            char::from_u32(byte)
+        })
+        .collect()
+}
+
+impl From<NumericValue> for Value {
+    fn from(value: NumericValue) -> Self {
+        Value::Numeric(value)
+    }
+}
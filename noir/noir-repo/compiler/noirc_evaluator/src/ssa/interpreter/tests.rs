@@ -0,0 +1,432 @@
+#![cfg(test)]
+
+use acvm::FieldElement;
+
+use crate::ssa::{
+    ir::types::{NumericType, Type},
+    Ssa, SsaBuilder, SsaLogging,
+};
+
+use super::{value::Value, InterpreterCheck, InterpreterError};
+
+fn interpret(src: &str, args: Vec<Value>) -> Result<Vec<Value>, InterpreterError> {
+    let ssa = Ssa::from_str(src).unwrap();
+    ssa.interpret(args)
+}
+
+fn expect_values(src: &str, args: Vec<Value>) -> Vec<Value> {
+    interpret(src, args).unwrap_or_else(|error| panic!("Interpreter failed: {error}"))
+}
+
+fn expect_error(src: &str, args: Vec<Value>) -> InterpreterError {
+    match interpret(src, args) {
+        Ok(values) => panic!("Expected an error but the program returned {values:?}"),
+        Err(error) => error,
+    }
+}
+
+fn field(value: u128) -> Value {
+    Value::field(FieldElement::from(value))
+}
+
+fn u8(value: u128) -> Value {
+    Value::numeric(FieldElement::from(value), NumericType::unsigned(8))
+}
+
+fn assert_values_eq(actual: &[Value], expected: &[Value]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!(actual.structurally_eq(expected), "expected {expected} but found {actual}");
+    }
+}
+
+#[test]
+fn field_arithmetic() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field, v1: Field):
+            v2 = add v0, v1
+            v3 = mul v2, Field 3
+            v4 = sub v3, Field 1
+            return v4
+        }
+        ";
+    let values = expect_values(src, vec![field(2), field(5)]);
+    assert_values_eq(&values, &[field(20)]);
+}
+
+#[test]
+fn loop_with_block_parameters() {
+    // Sums 0..v0
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: u32):
+            jmp b1(u32 0, u32 0)
+          b1(v1: u32, v2: u32):
+            v3 = lt v1, v0
+            jmpif v3 then: b2, else: b3
+          b2():
+            v4 = add v2, v1
+            v5 = add v1, u32 1
+            jmp b1(v5, v4)
+          b3():
+            return v2
+        }
+        ";
+    let values = expect_values(src, vec![Value::u32(5)]);
+    assert_values_eq(&values, &[Value::u32(10)]);
+}
+
+#[test]
+fn unsigned_overflow_is_an_error() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: u8):
+            v1 = add v0, u8 1
+            return v1
+        }
+        ";
+    assert!(matches!(expect_error(src, vec![u8(255)]), InterpreterError::Overflow { .. }));
+    assert_values_eq(&expect_values(src, vec![u8(254)]), &[u8(255)]);
+}
+
+#[test]
+fn signed_arithmetic_wraps() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: i8):
+            v1 = add v0, i8 1
+            return v1
+        }
+        ";
+    let max = Value::numeric(FieldElement::from(127u128), NumericType::signed(8));
+    let min = Value::numeric(FieldElement::from(128u128), NumericType::signed(8));
+    assert_values_eq(&expect_values(src, vec![max]), &[min]);
+}
+
+#[test]
+fn failing_constrain_reports_message() {
+    let src = r#"
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            constrain v0 == Field 1, "Oh no!"
+            return
+        }
+        "#;
+    expect_values(src, vec![field(1)]);
+    match expect_error(src, vec![field(2)]) {
+        InterpreterError::ConstrainEqFailed { message, .. } => {
+            assert_eq!(message.as_deref(), Some("Oh no!"));
+        }
+        other => panic!("Expected a failed constraint, got {other}"),
+    }
+}
+
+#[test]
+fn disabled_side_effects_prevent_division_by_zero() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: u1, v1: u32):
+            enable_side_effects v0
+            v2 = div u32 10, v1
+            enable_side_effects u1 1
+            return v2
+        }
+        ";
+    expect_values(src, vec![Value::bool(false), Value::u32(0)]);
+    let error = expect_error(src, vec![Value::bool(true), Value::u32(0)]);
+    assert!(matches!(error, InterpreterError::DivisionByZero { .. }));
+}
+
+#[test]
+fn array_get_out_of_bounds() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: u32):
+            v1 = make_array [Field 1, Field 2] : [Field; 2]
+            v2 = array_get v1, index v0 -> Field
+            return v2
+        }
+        ";
+    assert_values_eq(&expect_values(src, vec![Value::u32(1)]), &[field(2)]);
+    let error = expect_error(src, vec![Value::u32(2)]);
+    assert!(matches!(error, InterpreterError::IndexOutOfBounds { index: 2, length: 2, .. }));
+}
+
+#[test]
+fn non_mutable_array_set_copies_in_acir() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0():
+            v0 = make_array [Field 1, Field 2] : [Field; 2]
+            v1 = array_set v0, index u32 0, value Field 5
+            return v0, v1
+        }
+        ";
+    let values = expect_values(src, vec![]);
+    let array_type = vec![Type::field()];
+    let original = Value::array(vec![field(1), field(2)], array_type.clone());
+    let modified = Value::array(vec![field(5), field(2)], array_type);
+    assert_values_eq(&values, &[original, modified]);
+}
+
+#[test]
+fn array_set_with_rc_of_one_mutates_in_brillig() {
+    // Without the `inc_rc` the array is uniquely owned so Brillig mutates it in place,
+    // which is visible through `v0` as well.
+    let src = "
+        brillig(inline) fn main f0 {
+          b0():
+            v0 = make_array [Field 1, Field 2] : [Field; 2]
+            v1 = array_set v0, index u32 0, value Field 5
+            return v0
+        }
+        ";
+    let values = expect_values(src, vec![]);
+    let expected = Value::array(vec![field(5), field(2)], vec![Type::field()]);
+    assert_values_eq(&values, &[expected]);
+
+    let src = "
+        brillig(inline) fn main f0 {
+          b0():
+            v0 = make_array [Field 1, Field 2] : [Field; 2]
+            inc_rc v0
+            v1 = array_set v0, index u32 0, value Field 5
+            return v0
+        }
+        ";
+    let values = expect_values(src, vec![]);
+    let expected = Value::array(vec![field(1), field(2)], vec![Type::field()]);
+    assert_values_eq(&values, &[expected]);
+}
+
+#[test]
+fn references() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            v1 = allocate -> &mut Field
+            store v0 at v1
+            v2 = load v1 -> Field
+            v3 = add v2, Field 1
+            store v3 at v1
+            v4 = load v1 -> Field
+            return v4
+        }
+        ";
+    assert_values_eq(&expect_values(src, vec![field(4)]), &[field(5)]);
+}
+
+#[test]
+fn load_before_store_is_an_error() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0():
+            v0 = allocate -> &mut Field
+            v1 = load v0 -> Field
+            return v1
+        }
+        ";
+    assert!(matches!(expect_error(src, vec![]), InterpreterError::UninitializedLoad { .. }));
+}
+
+#[test]
+fn calls() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            v2, v3 = call f1(v0) -> (Field, Field)
+            v4 = add v2, v3
+            return v4
+        }
+        acir(inline) fn double_and_triple f1 {
+          b0(v0: Field):
+            v1 = mul v0, Field 2
+            v2 = mul v0, Field 3
+            return v1, v2
+        }
+        ";
+    assert_values_eq(&expect_values(src, vec![field(2)]), &[field(10)]);
+}
+
+#[test]
+fn slice_push_back() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            v1 = make_array [Field 1] : [Field]
+            v4, v5 = call slice_push_back(u32 1, v1, v0) -> (u32, [Field])
+            return v4, v5
+        }
+        ";
+    let values = expect_values(src, vec![field(7)]);
+    let slice = Value::slice(vec![field(1), field(7)], std::sync::Arc::new(vec![Type::field()]));
+    assert_values_eq(&values, &[Value::u32(2), slice]);
+}
+
+#[test]
+fn black_box_input_of_wrong_length() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: [u64; 24]):
+            v1 = call keccakf1600(v0) -> [u64; 25]
+            return v1
+        }
+        ";
+    let input = Value::array(
+        vec![Value::numeric(FieldElement::from(1_u128), NumericType::unsigned(64)); 24],
+        vec![Type::unsigned(64)],
+    );
+    let error = expect_error(src, vec![input]);
+    assert!(matches!(error, InterpreterError::BlackBoxInputLength { expected: 25, found: 24, .. }));
+}
+
+#[test]
+fn interpreter_check_unflattens_inputs() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: [(u8, Field); 2], v1: u32):
+            return v0, v1
+        }
+        ";
+    let ssa = Ssa::from_str(src).unwrap();
+    let inputs: Vec<FieldElement> = (1..=5_u128).map(FieldElement::from).collect();
+    let check = InterpreterCheck::new(&ssa, &inputs).unwrap();
+
+    let array = Value::array(
+        vec![u8(1), field(2), u8(3), field(4)],
+        vec![Type::unsigned(8), Type::field()],
+    );
+    assert_values_eq(&check.inputs, &[array.snapshot(), Value::u32(5)]);
+    assert_values_eq(&check.expected.unwrap(), &[array, Value::u32(5)]);
+}
+
+#[test]
+fn passes_preserve_behavior() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: u1, v1: Field):
+            v2 = allocate -> &mut Field
+            store Field 0 at v2
+            jmpif v0 then: b1, else: b2
+          b1():
+            store v1 at v2
+            jmp b2()
+          b2():
+            v3 = load v2 -> Field
+            return v3
+        }
+        ";
+    for condition in [false, true] {
+        let ssa = Ssa::from_str(src).unwrap();
+        let builder = SsaBuilder {
+            ssa,
+            ssa_logging: SsaLogging::None,
+            print_codegen_timings: false,
+            interpreter_check: None,
+        };
+        builder
+            .with_interpreter_check(&[FieldElement::from(condition), FieldElement::from(3_u128)])
+            .unwrap()
+            .run_pass(Ssa::mem2reg, "Mem2Reg")
+            .run_pass(Ssa::flatten_cfg, "Flattening")
+            .run_pass(Ssa::mem2reg, "Mem2Reg (2nd)")
+            .run_pass(Ssa::fold_constants, "Constant Folding")
+            .run_pass(Ssa::dead_instruction_elimination, "Dead Instruction Elimination");
+    }
+}
+
+#[test]
+#[should_panic(expected = "Miscompilation changed the behavior of the program")]
+fn check_detects_changed_behavior() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            return v0
+        }
+        ";
+    let replacement = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            return Field 0
+        }
+        ";
+    let ssa = Ssa::from_str(src).unwrap();
+    let builder = SsaBuilder {
+        ssa,
+        ssa_logging: SsaLogging::None,
+        print_codegen_timings: false,
+        interpreter_check: None,
+    };
+    builder
+        .with_interpreter_check(&[FieldElement::one()])
+        .unwrap()
+        .run_pass(|_| Ssa::from_str(replacement).unwrap(), "Miscompilation");
+}
+
+#[test]
+fn interpreter_check_rejects_inputs_which_do_not_match_main() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: [Field; 2]):
+            return v0
+        }
+        ";
+    let ssa = Ssa::from_str(src).unwrap();
+    assert!(InterpreterCheck::new(&ssa, &[FieldElement::one()]).is_err());
+    assert!(InterpreterCheck::new(&ssa, &[FieldElement::one(); 3]).is_err());
+    assert!(InterpreterCheck::new(&ssa, &[FieldElement::one(); 2]).is_ok());
+}
+
+/// Runs a pass replacing the whole program, with the interpreter check enabled.
+fn replace_program(src: &str, replacement: &str, inputs: &[FieldElement]) {
+    let ssa = Ssa::from_str(src).unwrap();
+    let builder = SsaBuilder {
+        ssa,
+        ssa_logging: SsaLogging::None,
+        print_codegen_timings: false,
+        interpreter_check: None,
+    };
+    builder
+        .with_interpreter_check(inputs)
+        .unwrap()
+        .run_pass(|_| Ssa::from_str(replacement).unwrap(), "Miscompilation");
+}
+
+#[test]
+#[should_panic(expected = "Miscompilation changed the behavior of the program")]
+fn check_detects_changed_error() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            constrain v0 == Field 1
+            return
+        }
+        ";
+    let replacement = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            v1 = div Field 1, Field 0
+            return
+        }
+        ";
+    replace_program(src, replacement, &[FieldElement::from(2_u128)]);
+}
+
+#[test]
+fn check_is_skipped_when_the_initial_program_is_not_supported() {
+    let src = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            v1 = call bigint_add(v0, v0) -> [u32; 5]
+            return v0
+        }
+        ";
+    let replacement = "
+        acir(inline) fn main f0 {
+          b0(v0: Field):
+            return Field 0
+        }
+        ";
+    replace_program(src, replacement, &[FieldElement::one()]);
+}
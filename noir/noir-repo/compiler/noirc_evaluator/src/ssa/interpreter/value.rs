@@ -0,0 +1,218 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use acvm::{acir::AcirField, FieldElement};
+use iter_extended::vecmap;
+
+use crate::ssa::ir::{
+    function::FunctionId,
+    instruction::Intrinsic,
+    types::{CompositeType, NumericType, Type},
+};
+
+/// A concrete value produced while interpreting an SSA program.
+///
+/// Arrays and references are shared through `Rc`s so that aliasing behaves the same way
+/// it does in the generated code: storing through one reference is visible through every
+/// other copy of it, and a `mut` array_set is visible to every holder of that array.
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    Numeric(NumericValue),
+    Reference(ReferenceValue),
+    ArrayOrSlice(ArrayValue),
+    Function(FunctionId),
+    Intrinsic(Intrinsic),
+    ForeignFunction(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NumericValue {
+    pub(crate) value: FieldElement,
+    pub(crate) typ: NumericType,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ReferenceValue {
+    /// The contents of the allocation. `None` until the first store.
+    pub(crate) element: Rc<RefCell<Option<Value>>>,
+
+    /// The type of the allocation, used when printing and when creating default values.
+    pub(crate) element_type: Arc<Type>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ArrayValue {
+    /// The flattened elements of this array. Arrays of composite types store
+    /// each field of each element sequentially.
+    pub(crate) elements: Rc<RefCell<Vec<Value>>>,
+
+    /// The reference count of this array, mirroring the one Brillig keeps at runtime.
+    pub(crate) rc: Rc<RefCell<u32>>,
+
+    pub(crate) element_types: Arc<CompositeType>,
+    pub(crate) is_slice: bool,
+}
+
+impl Value {
+    pub(crate) fn numeric(value: FieldElement, typ: NumericType) -> Self {
+        Value::Numeric(NumericValue { value, typ })
+    }
+
+    pub(crate) fn field(value: FieldElement) -> Self {
+        Self::numeric(value, NumericType::NativeField)
+    }
+
+    pub(crate) fn bool(value: bool) -> Self {
+        Self::numeric(value.into(), NumericType::bool())
+    }
+
+    pub(crate) fn u32(value: u32) -> Self {
+        Self::numeric((value as u128).into(), NumericType::length_type())
+    }
+
+    /// Creates a new array with a reference count of 1.
+    pub(crate) fn array(elements: Vec<Value>, element_types: Vec<Type>) -> Self {
+        Value::ArrayOrSlice(ArrayValue::new(elements, Arc::new(element_types), false))
+    }
+
+    /// Creates a new slice with a reference count of 1.
+    pub(crate) fn slice(elements: Vec<Value>, element_types: Arc<CompositeType>) -> Self {
+        Value::ArrayOrSlice(ArrayValue::new(elements, element_types, true))
+    }
+
+    /// Creates a zeroed value of the given type.
+    ///
+    /// This is used for results of instructions which were disabled by
+    /// `enable_side_effects`, where the result is never meaningfully observed.
+    pub(crate) fn uninitialized(typ: &Type) -> Value {
+        match typ {
+            Type::Numeric(typ) => Value::numeric(FieldElement::zero(), *typ),
+            Type::Reference(element_type) => Value::Reference(ReferenceValue {
+                element: Rc::new(RefCell::new(None)),
+                element_type: element_type.clone(),
+            }),
+            Type::Array(element_types, length) => {
+                let elements = (0..*length)
+                    .flat_map(|_| element_types.iter().map(Value::uninitialized))
+                    .collect();
+                Value::ArrayOrSlice(ArrayValue::new(elements, element_types.clone(), false))
+            }
+            Type::Slice(element_types) => Value::slice(Vec::new(), element_types.clone()),
+            // There is no function to point to, so calling this value will fail with an unknown foreign call.
+            Type::Function => Value::ForeignFunction("<uninitialized>".to_string()),
+        }
+    }
+
+    pub(crate) fn as_numeric(&self) -> Option<NumericValue> {
+        match self {
+            Value::Numeric(numeric) => Some(*numeric),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array_or_slice(&self) -> Option<&ArrayValue> {
+        match self {
+            Value::ArrayOrSlice(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Returns a copy of this value which shares no arrays or references with the original.
+    ///
+    /// This allows the same inputs to be passed to several executions without one
+    /// execution's in-place array mutations being visible to the next.
+    pub(crate) fn snapshot(&self) -> Value {
+        match self {
+            Value::Reference(reference) => {
+                let element = reference.element.borrow().as_ref().map(Value::snapshot);
+                Value::Reference(ReferenceValue {
+                    element: Rc::new(RefCell::new(element)),
+                    element_type: reference.element_type.clone(),
+                })
+            }
+            Value::ArrayOrSlice(array) => {
+                let elements = vecmap(array.elements.borrow().iter(), Value::snapshot);
+                Value::ArrayOrSlice(ArrayValue::new(
+                    elements,
+                    array.element_types.clone(),
+                    array.is_slice,
+                ))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Structural equality: arrays are compared by their contents and references by identity.
+    ///
+    /// Reference counts are deliberately ignored since optimizations are free to change them
+    /// as long as the observable contents stay the same.
+    pub(crate) fn structurally_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Numeric(lhs), Value::Numeric(rhs)) => lhs == rhs,
+            (Value::Reference(lhs), Value::Reference(rhs)) => {
+                Rc::ptr_eq(&lhs.element, &rhs.element)
+            }
+            (Value::ArrayOrSlice(lhs), Value::ArrayOrSlice(rhs)) => {
+                let lhs_elements = lhs.elements.borrow();
+                let rhs_elements = rhs.elements.borrow();
+                lhs.is_slice == rhs.is_slice
+                    && lhs_elements.len() == rhs_elements.len()
+                    && lhs_elements
+                        .iter()
+                        .zip(rhs_elements.iter())
+                        .all(|(l, r)| l.structurally_eq(r))
+            }
+            (Value::Function(lhs), Value::Function(rhs)) => lhs == rhs,
+            (Value::Intrinsic(lhs), Value::Intrinsic(rhs)) => lhs == rhs,
+            (Value::ForeignFunction(lhs), Value::ForeignFunction(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl ArrayValue {
+    fn new(elements: Vec<Value>, element_types: Arc<CompositeType>, is_slice: bool) -> Self {
+        Self {
+            elements: Rc::new(RefCell::new(elements)),
+            rc: Rc::new(RefCell::new(1)),
+            element_types,
+            is_slice,
+        }
+    }
+
+    /// Returns a copy of this array which shares no storage with the original,
+    /// as a non-mutable `array_set` would create.
+    pub(crate) fn deep_copy(&self) -> Self {
+        Self::new(self.elements.borrow().clone(), self.element_types.clone(), self.is_slice)
+    }
+
+    /// The number of (possibly composite) elements in this array.
+    pub(crate) fn len(&self) -> usize {
+        self.elements.borrow().len() / self.element_types.len().max(1)
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Numeric(numeric) => write!(f, "{numeric}"),
+            Value::Reference(reference) => match &*reference.element.borrow() {
+                Some(element) => write!(f, "&mut {element}"),
+                None => write!(f, "&mut <uninitialized>"),
+            },
+            Value::ArrayOrSlice(array) => {
+                let elements = vecmap(array.elements.borrow().iter(), ToString::to_string);
+                let prefix = if array.is_slice { "&" } else { "" };
+                write!(f, "{prefix}[{}]", elements.join(", "))
+            }
+            Value::Function(id) => write!(f, "{id}"),
+            Value::Intrinsic(intrinsic) => write!(f, "{intrinsic}"),
+            Value::ForeignFunction(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl std::fmt::Display for NumericValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.typ, self.value)
+    }
+}
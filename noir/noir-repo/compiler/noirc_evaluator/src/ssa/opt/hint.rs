@@ -22,6 +22,7 @@ mod tests {
             skip_brillig_constraints_check: true,
             inliner_aggressiveness: 0,
             max_bytecode_increase_percent: None,
+            interpreter_check_inputs: None,
         };
 
         let builder = SsaBuilder {
@@ -36,7 +36,7 @@ impl Ssa {
     /// Note that the resulting Ssa might not be exactly the same as the given string.
     /// This is because, internally, the Ssa is built using a `FunctionBuilder`, so
     /// some instructions might be simplified while they are inserted.
-    pub(crate) fn from_str(src: &str) -> Result<Ssa, SsaErrorWithSource> {
+    pub fn from_str(src: &str) -> Result<Ssa, SsaErrorWithSource> {
         let mut parser =
             Parser::new(src).map_err(|err| SsaErrorWithSource::parse_error(err, src))?;
         let parsed_ssa =
@@ -45,7 +45,7 @@ impl Ssa {
     }
 }
 
-pub(crate) struct SsaErrorWithSource {
+pub struct SsaErrorWithSource {
     src: String,
     error: SsaError,
 }
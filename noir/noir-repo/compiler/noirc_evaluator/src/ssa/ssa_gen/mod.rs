@@ -3,7 +3,7 @@ mod program;
 mod value;
 
 use noirc_frontend::token::FmtStrFragment;
-pub(crate) use program::Ssa;
+pub use program::Ssa;
 
 use context::SharedContext;
 use iter_extended::{try_vecmap, vecmap};
@@ -14,7 +14,7 @@ use noirc_frontend::hir_def::types::Type as HirType;
 /// Contains the entire SSA representation of the program.
 #[serde_as]
 #[derive(Serialize, Deserialize)]
-pub(crate) struct Ssa {
+pub struct Ssa {
     #[serde_as(as = "Vec<(_, _)>")]
     pub(crate) functions: BTreeMap<FunctionId, Function>,
     pub(crate) main_id: FunctionId,
@@ -60,7 +60,7 @@ pub type DefMaps = BTreeMap<CrateId, CrateDefMap>;
 /// Map of all modules and scopes defined within a crate.
 ///
 /// The definitions of the crate are accessible indirectly via the scopes of each module.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CrateDefMap {
     pub(crate) root: LocalModuleId,
 
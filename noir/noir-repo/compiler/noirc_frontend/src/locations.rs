@@ -12,7 +12,7 @@ use crate::{
 };
 use petgraph::prelude::NodeIndex as PetGraphIndex;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct LocationIndices {
     map_file_to_range: HashMap<FileId, RangeMap<u32, PetGraphIndex>>,
 }
@@ -32,6 +32,18 @@ impl LocationIndices {
         let range_map = self.map_file_to_range.get(&location.file)?;
         Some(*range_map.get(&location.span.start())?)
     }
+
+    /// Returns the nodes of a file together with their span, ordered by span.
+    pub(crate) fn get_nodes_in_file(
+        &self,
+        file: FileId,
+    ) -> impl Iterator<Item = (Span, PetGraphIndex)> + '_ {
+        self.map_file_to_range
+            .get(&file)
+            .into_iter()
+            .flat_map(|range_map| range_map.iter())
+            .map(|(range, node_index)| (Span::from(range.clone()), *node_index))
+    }
 }
 
 pub struct ReferencesTracker<'a> {
@@ -268,6 +280,21 @@ impl NodeInterner {
         }
     }
 
+    // Returns every reference and definition in the given file, ordered by span, together with
+    // the node they refer to (a definition refers to itself) and whether they are a definition.
+    pub fn find_all_referenced_in_file(&self, file: FileId) -> Vec<(Span, ReferenceId, bool)> {
+        self.location_indices
+            .get_nodes_in_file(file)
+            .filter_map(|(span, node_index)| match self.reference_graph[node_index] {
+                ReferenceId::Reference(..) => {
+                    let referenced_index = self.referenced_index(node_index)?;
+                    Some((span, self.reference_graph[referenced_index], false))
+                }
+                referenced => Some((span, referenced, true)),
+            })
+            .collect()
+    }
+
     // Given a referenced node index, find all references to it and return their locations, optionally together
     // with the reference node's location if `include_referenced` is true.
     // If `include_self_type_name` is true, references where "Self" is written are returned,
@@ -49,7 +49,7 @@ use crate::{Shared, TypeAlias, TypeBindings, TypeVariable, TypeVariableId};
 /// This is needed to stop recursing for cases such as `impl<T> Foo for T where T: Eq`
 const IMPL_SEARCH_RECURSION_LIMIT: u32 = 10;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ModuleAttributes {
     pub name: String,
     pub location: Location,
@@ -65,7 +65,11 @@ type StructAttributes = Vec<SecondaryAttribute>;
 /// each definition or struct, etc. Because it is used on the Hir, the NodeInterner is
 /// useful in passes where the Hir is used - name resolution, type checking, and
 /// monomorphization - and it is not useful afterward.
-#[derive(Debug)]
+///
+/// Cloning the interner shares its `Shared` values, such as struct types and trait impls,
+/// with the clone. This lets the LSP elaborate the dependencies of a crate once and reuse them,
+/// as those values are only mutated while elaborating the crate they belong to.
+#[derive(Debug, Clone)]
 pub struct NodeInterner {
     pub(crate) nodes: Arena<Node>,
     pub(crate) func_meta: HashMap<FuncId, FuncMeta>,
@@ -917,6 +921,11 @@ impl NodeInterner {
             .map(|(func_id, _meta)| *func_id)
     }
 
+    /// Returns the IDs of all the functions with metadata, including methods and trait methods.
+    pub fn function_ids(&self) -> impl Iterator<Item = FuncId> + '_ {
+        self.func_meta.keys().copied()
+    }
+
     ///Interns a function's metadata.
     ///
     /// Note that the FuncId has been created already.
@@ -1240,6 +1249,11 @@ impl NodeInterner {
         self.traits.get(&id)
     }
 
+    /// Returns all the traits, in no particular order.
+    pub fn get_traits(&self) -> impl Iterator<Item = &Trait> {
+        self.traits.values()
+    }
+
     pub fn get_type_alias(&self, id: TypeAliasId) -> Shared<TypeAlias> {
         self.type_aliases[id.0].clone()
     }
@@ -1433,6 +1447,11 @@ impl NodeInterner {
         self.trait_implementations[&id].clone()
     }
 
+    /// Returns all the trait implementations, in no particular order.
+    pub fn get_trait_implementations(&self) -> impl Iterator<Item = &Shared<TraitImpl>> {
+        self.trait_implementations.values()
+    }
+
     /// If the given function belongs to a trait impl, return its trait method id.
     /// Otherwise, return None.
     pub fn get_trait_method_id(&self, function: FuncId) -> Option<TraitMethodId> {
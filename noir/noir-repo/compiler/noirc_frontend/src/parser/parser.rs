@@ -3,7 +3,7 @@ use modifiers::Modifiers;
 use noirc_errors::Span;
 
 use crate::{
-    ast::{Ident, ItemVisibility},
+    ast::{Expression, Ident, ItemVisibility},
     lexer::{Lexer, SpannedTokenResult},
     token::{FmtStrFragment, IntType, Keyword, SpannedToken, Token, TokenKind, Tokens},
 };
@@ -54,6 +54,11 @@ pub fn parse_program(source_program: &str) -> (ParsedModule, Vec<ParserError>) {
     (program, errors)
 }
 
+/// Parses a single expression, for example one entered by the user of a debugger.
+pub fn parse_expression(source: &str) -> Result<Expression, Vec<ParserError>> {
+    Parser::for_str(source).parse_result(Parser::parse_expression_or_error)
+}
+
 enum TokenStream<'a> {
     Lexer(Lexer<'a>),
     Tokens(Tokens),
@@ -0,0 +1,412 @@
+//! Options refining what happens when execution reaches a breakpoint.
+//!
+//! A breakpoint may carry:
+//! - a condition over the source variables in scope, such as `x > 3 && flags[1]`,
+//! - a hit condition over the number of times it was reached with its condition holding,
+//! - a log message, which turns it into a logpoint that prints the message instead of stopping.
+//!
+//! Conditions and the expressions interpolated into log messages are Noir expressions, evaluated
+//! by the same evaluator as the `eval` command against the current stack frame of the debug
+//! variables tracked by the instrumented program.
+use acvm::FieldElement;
+use noirc_artifacts::debug::StackFrame;
+use thiserror::Error;
+
+use crate::evaluation::{ExpressionError, ParsedExpression};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum BreakpointOptionsError {
+    #[error("Invalid condition: {0}")]
+    InvalidCondition(ExpressionError),
+
+    #[error("Invalid hit condition `{0}`, expected a count such as `3`, `== 3`, `> 3` or `% 3`")]
+    InvalidHitCondition(String),
+
+    #[error("Invalid log message `{message}`: {reason}")]
+    InvalidLogMessage { message: String, reason: String },
+}
+
+/// The options of a breakpoint, as provided by the user.
+#[derive(Debug, Clone, Default)]
+pub(super) struct BreakpointOptions {
+    pub(super) condition: Option<Condition>,
+    pub(super) hit_condition: Option<HitCondition>,
+    pub(super) log_message: Option<LogMessage>,
+}
+
+impl BreakpointOptions {
+    /// Parses the options of a breakpoint. Empty strings are treated as absent options.
+    pub(super) fn parse(
+        condition: Option<&str>,
+        hit_condition: Option<&str>,
+        log_message: Option<&str>,
+    ) -> Result<Self, BreakpointOptionsError> {
+        let non_empty = |option: Option<&str>| option.filter(|option| !option.trim().is_empty());
+        Ok(Self {
+            condition: non_empty(condition).map(Condition::parse).transpose()?,
+            hit_condition: non_empty(hit_condition).map(HitCondition::parse).transpose()?,
+            log_message: non_empty(log_message).map(LogMessage::parse).transpose()?,
+        })
+    }
+
+    pub(super) fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+}
+
+impl std::fmt::Display for BreakpointOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut separator = "";
+        if let Some(condition) = &self.condition {
+            write!(f, "if {condition}")?;
+            separator = ", ";
+        }
+        if let Some(hit_condition) = &self.hit_condition {
+            write!(f, "{separator}hits {hit_condition}")?;
+            separator = ", ";
+        }
+        if let Some(log_message) = &self.log_message {
+            write!(f, "{separator}log \"{log_message}\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// What should happen after reaching a breakpoint.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum BreakpointHit {
+    /// Execution should continue as if there was no breakpoint
+    Ignored,
+    /// Execution should stop at the breakpoint
+    Stop,
+    /// Execution should continue after printing the given message
+    Log(String),
+}
+
+/// A breakpoint along with the number of times it was hit.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Breakpoint {
+    pub(super) options: BreakpointOptions,
+    hit_count: usize,
+}
+
+impl Breakpoint {
+    pub(super) fn new(options: BreakpointOptions) -> Self {
+        Self { options, hit_count: 0 }
+    }
+
+    /// The number of times the breakpoint was reached with its condition holding.
+    pub(super) fn hit_count(&self) -> usize {
+        self.hit_count
+    }
+
+    /// Records that execution reached the breakpoint and determines what to do next.
+    pub(super) fn hit(
+        &mut self,
+        frame: Option<&StackFrame<FieldElement>>,
+    ) -> Result<BreakpointHit, ExpressionError> {
+        if let Some(condition) = &self.options.condition {
+            if !condition.evaluate(frame)? {
+                return Ok(BreakpointHit::Ignored);
+            }
+        }
+
+        self.hit_count += 1;
+        if let Some(hit_condition) = &self.options.hit_condition {
+            if !hit_condition.is_met(self.hit_count) {
+                return Ok(BreakpointHit::Ignored);
+            }
+        }
+
+        match &self.options.log_message {
+            Some(log_message) => Ok(BreakpointHit::Log(log_message.format(frame))),
+            None => Ok(BreakpointHit::Stop),
+        }
+    }
+}
+
+/// A boolean Noir expression over the source variables in scope, such as `x > 3 & flags[1]`.
+#[derive(Debug, Clone)]
+pub(super) struct Condition(ParsedExpression);
+
+impl Condition {
+    pub(super) fn parse(source: &str) -> Result<Self, BreakpointOptionsError> {
+        ParsedExpression::parse(source)
+            .map(Condition)
+            .map_err(BreakpointOptionsError::InvalidCondition)
+    }
+
+    pub(super) fn evaluate(
+        &self,
+        frame: Option<&StackFrame<FieldElement>>,
+    ) -> Result<bool, ExpressionError> {
+        self.0.evaluate_bool(frame)
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A condition over the number of times a breakpoint was hit.
+///
+/// A bare count `N` is met from the `N`th hit onwards, while `%N` is met on every `N`th hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum HitCondition {
+    Equal(usize),
+    Greater(usize),
+    GreaterOrEqual(usize),
+    Less(usize),
+    LessOrEqual(usize),
+    Multiple(usize),
+}
+
+impl HitCondition {
+    pub(super) fn parse(source: &str) -> Result<Self, BreakpointOptionsError> {
+        let source = source.trim();
+        let error = || BreakpointOptionsError::InvalidHitCondition(source.to_string());
+
+        let operators: [(&str, fn(usize) -> HitCondition); 6] = [
+            ("==", HitCondition::Equal),
+            (">=", HitCondition::GreaterOrEqual),
+            ("<=", HitCondition::LessOrEqual),
+            (">", HitCondition::Greater),
+            ("<", HitCondition::Less),
+            ("%", HitCondition::Multiple),
+        ];
+        let (constructor, count) = operators
+            .iter()
+            .find_map(|(operator, constructor)| {
+                source.strip_prefix(operator).map(|count| (*constructor, count))
+            })
+            .unwrap_or((HitCondition::GreaterOrEqual, source));
+
+        let count: usize = count.trim().parse().map_err(|_| error())?;
+        let hit_condition = constructor(count);
+        if hit_condition == HitCondition::Multiple(0) {
+            return Err(error());
+        }
+        Ok(hit_condition)
+    }
+
+    pub(super) fn is_met(&self, hit_count: usize) -> bool {
+        match *self {
+            HitCondition::Equal(count) => hit_count == count,
+            HitCondition::Greater(count) => hit_count > count,
+            HitCondition::GreaterOrEqual(count) => hit_count >= count,
+            HitCondition::Less(count) => hit_count < count,
+            HitCondition::LessOrEqual(count) => hit_count <= count,
+            HitCondition::Multiple(count) => hit_count % count == 0,
+        }
+    }
+}
+
+impl std::fmt::Display for HitCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HitCondition::Equal(count) => write!(f, "== {count}"),
+            HitCondition::Greater(count) => write!(f, "> {count}"),
+            HitCondition::GreaterOrEqual(count) => write!(f, ">= {count}"),
+            HitCondition::Less(count) => write!(f, "< {count}"),
+            HitCondition::LessOrEqual(count) => write!(f, "<= {count}"),
+            HitCondition::Multiple(count) => write!(f, "% {count}"),
+        }
+    }
+}
+
+/// A message printed by a logpoint, where `{expression}` is replaced by the value of the expression
+/// and `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone)]
+pub(super) struct LogMessage {
+    source: String,
+    segments: Vec<LogSegment>,
+}
+
+#[derive(Debug, Clone)]
+enum LogSegment {
+    Text(String),
+    Expression(ParsedExpression),
+}
+
+impl LogMessage {
+    pub(super) fn parse(source: &str) -> Result<Self, BreakpointOptionsError> {
+        let invalid = |reason: &str| BreakpointOptionsError::InvalidLogMessage {
+            message: source.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut expression = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(char) => expression.push(char),
+                            None => return Err(invalid("unterminated `{`")),
+                        }
+                    }
+                    let expression = ParsedExpression::parse(&expression)
+                        .map_err(|error| invalid(&error.to_string()))?;
+
+                    if !text.is_empty() {
+                        segments.push(LogSegment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(LogSegment::Expression(expression));
+                }
+                '}' => return Err(invalid("unmatched `}`")),
+                _ => text.push(char),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(LogSegment::Text(text));
+        }
+
+        Ok(Self { source: source.to_string(), segments })
+    }
+
+    /// Formats the message, replacing expressions which can't be evaluated with the reason why.
+    pub(super) fn format(&self, frame: Option<&StackFrame<FieldElement>>) -> String {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                LogSegment::Text(text) => output.push_str(text),
+                LogSegment::Expression(expression) => match expression.evaluate(frame) {
+                    Ok(value) => output.push_str(&value.to_string()),
+                    Err(error) => output.push_str(&format!("<{error}>")),
+                },
+            }
+        }
+        output
+    }
+}
+
+impl std::fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use noirc_printable_type::{PrintableType, PrintableValue};
+
+    fn field(value: u128) -> PrintableValue<FieldElement> {
+        PrintableValue::Field(FieldElement::from(value))
+    }
+
+    fn evaluate(
+        condition: &str,
+        frame: &StackFrame<FieldElement>,
+    ) -> Result<bool, ExpressionError> {
+        Condition::parse(condition).unwrap().evaluate(Some(frame))
+    }
+
+    #[test]
+    fn evaluates_conditions_over_variables() {
+        let x = field(5);
+        let minus_one = field(0xff);
+        let flags =
+            PrintableValue::Vec { array_elements: vec![field(0), field(1)], is_slice: false };
+        let point = PrintableValue::Struct(BTreeMap::from([
+            ("x".to_string(), field(1)),
+            ("y".to_string(), field(2)),
+        ]));
+
+        let u32_type = PrintableType::UnsignedInteger { width: 32 };
+        let i8_type = PrintableType::SignedInteger { width: 8 };
+        let flags_type = PrintableType::Array { length: 2, typ: Box::new(PrintableType::Boolean) };
+        let point_type = PrintableType::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                ("x".to_string(), PrintableType::Field),
+                ("y".to_string(), PrintableType::Field),
+            ],
+        };
+        let frame = StackFrame {
+            function_name: "main",
+            function_params: vec!["x"],
+            variables: vec![
+                ("x", &x, &u32_type),
+                ("m", &minus_one, &i8_type),
+                ("flags", &flags, &flags_type),
+                ("point", &point, &point_type),
+            ],
+        };
+
+        assert_eq!(evaluate("x == 5", &frame), Ok(true));
+        assert_eq!(evaluate("(x > 5) | (x < 0x06)", &frame), Ok(true));
+        assert_eq!(evaluate("(m < 0) & (m == -1)", &frame), Ok(true));
+        assert_eq!(evaluate("!flags[0] & flags[1]", &frame), Ok(true));
+        assert_eq!(evaluate("point.y != point.x", &frame), Ok(true));
+        assert_eq!(evaluate("x + 1 >= 7", &frame), Ok(false));
+
+        assert_eq!(evaluate("y == 1", &frame), Err(ExpressionError::UnknownVariable("y".into())));
+        assert!(matches!(
+            evaluate("flags[2]", &frame),
+            Err(ExpressionError::IndexOutOfBounds { .. })
+        ));
+        assert_eq!(evaluate("x", &frame), Err(ExpressionError::NotABoolean("x".into())));
+        assert!(Condition::parse("x ==").is_err());
+        assert!(Condition::parse("x == 1 1").is_err());
+    }
+
+    #[test]
+    fn hit_conditions() {
+        let hits_met = |condition: &str| {
+            let condition = HitCondition::parse(condition).unwrap();
+            (1..=6).filter(|hit_count| condition.is_met(*hit_count)).collect::<Vec<_>>()
+        };
+        assert_eq!(hits_met("4"), vec![4, 5, 6]);
+        assert_eq!(hits_met("== 2"), vec![2]);
+        assert_eq!(hits_met("<3"), vec![1, 2]);
+        assert_eq!(hits_met("%3"), vec![3, 6]);
+        assert!(HitCondition::parse("%0").is_err());
+        assert!(HitCondition::parse("often").is_err());
+    }
+
+    #[test]
+    fn logpoints_interpolate_variables() {
+        let x = field(7);
+        let u8_type = PrintableType::UnsignedInteger { width: 8 };
+        let frame = StackFrame {
+            function_name: "main",
+            function_params: vec![],
+            variables: vec![("x", &x, &u8_type)],
+        };
+
+        let options = BreakpointOptions::parse(
+            None,
+            Some("2"),
+            Some("x is {x}, {{y}} is {y}, x + 1 is {x + 1}"),
+        );
+        let mut breakpoint = Breakpoint::new(options.unwrap());
+        assert_eq!(breakpoint.hit(Some(&frame)), Ok(BreakpointHit::Ignored));
+        assert_eq!(
+            breakpoint.hit(Some(&frame)),
+            Ok(BreakpointHit::Log(
+                "x is 7, {y} is <Variable `y` is not in scope>, x + 1 is 8".to_string()
+            ))
+        );
+        assert_eq!(breakpoint.hit_count(), 2);
+
+        assert!(LogMessage::parse("unterminated {x").is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::foreign_calls::DebugForeignCallExecutor;
+use crate::breakpoints::{Breakpoint, BreakpointHit, BreakpointOptions};
+use crate::evaluation::{evaluate_expression, EvaluatedValue, ExpressionError};
+use crate::foreign_calls::{DebugForeignCall, DebugForeignCallExecutor};
+use crate::recording::{ExecutionRecording, ExecutionSnapshot, RecordingError};
+use crate::watchpoints::{WatchTarget, WatchedValue, Watchpoint, WatchpointHit, WatchpointId};
 use acvm::acir::brillig::BitSize;
 use acvm::acir::circuit::brillig::{BrilligBytecode, BrilligFunctionId};
 use acvm::acir::circuit::{Circuit, Opcode, OpcodeLocation};
@@ -19,8 +23,7 @@ use noirc_driver::DebugFile;
 
 use thiserror::Error;
 
-use std::collections::BTreeMap;
-use std::collections::{hash_set::Iter, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
 /// A Noir program is composed by
 /// `n` ACIR circuits
@@ -224,6 +227,7 @@ pub(super) enum DebugCommandResult {
     Done,
     Ok,
     BreakpointReached(DebugLocation),
+    WatchpointTriggered(Vec<WatchpointHit>),
     Error(NargoError<FieldElement>),
 }
 
@@ -232,6 +236,12 @@ pub struct ExecutionFrame<'a, B: BlackBoxFunctionSolver<FieldElement>> {
     acvm: ACVM<'a, FieldElement, B>,
 }
 
+impl<B: BlackBoxFunctionSolver<FieldElement>> Clone for ExecutionFrame<'_, B> {
+    fn clone(&self) -> Self {
+        Self { circuit_id: self.circuit_id, acvm: self.acvm.clone() }
+    }
+}
+
 pub(super) struct DebugContext<'a, B: BlackBoxFunctionSolver<FieldElement>> {
     acvm: ACVM<'a, FieldElement, B>,
     current_circuit_id: u32,
@@ -244,13 +254,23 @@ pub(super) struct DebugContext<'a, B: BlackBoxFunctionSolver<FieldElement>> {
     foreign_call_executor: Box<dyn DebugForeignCallExecutor + 'a>,
 
     debug_artifact: &'a DebugArtifact,
-    breakpoints: HashSet<DebugLocation>,
+    breakpoints: HashMap<DebugLocation, Breakpoint>,
+    /// Messages printed by logpoints or reporting breakpoint conditions which failed to evaluate,
+    /// which haven't been displayed yet
+    log_messages: Vec<String>,
+    watchpoints: BTreeMap<WatchpointId, Watchpoint>,
+    next_watchpoint_id: WatchpointId,
     source_to_locations: BTreeMap<FileId, Vec<(usize, DebugLocation)>>,
 
     circuits: &'a [Circuit<FieldElement>],
     unconstrained_functions: &'a [BrilligBytecode<FieldElement>],
 
     acir_opcode_addresses: AddressMap,
+
+    recording: ExecutionRecording<'a, B>,
+    /// Set while replaying recorded steps to go back in the execution, during which breakpoints
+    /// are ignored
+    replaying: bool,
 }
 
 impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
@@ -266,7 +286,7 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         let current_circuit_id: u32 = 0;
         let initial_circuit = &circuits[current_circuit_id as usize];
         let acir_opcode_addresses = AddressMap::new(circuits, unconstrained_functions);
-        Self {
+        let mut context = Self {
             acvm: ACVM::new(
                 blackbox_solver,
                 &initial_circuit.opcodes,
@@ -281,12 +301,19 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
             backend: blackbox_solver,
             foreign_call_executor,
             debug_artifact,
-            breakpoints: HashSet::new(),
+            breakpoints: HashMap::new(),
+            log_messages: Vec::new(),
+            watchpoints: BTreeMap::new(),
+            next_watchpoint_id: 1,
             source_to_locations: source_to_opcodes,
             circuits,
             unconstrained_functions,
             acir_opcode_addresses,
-        }
+            recording: ExecutionRecording::new(None),
+            replaying: false,
+        };
+        context.recording = ExecutionRecording::new(context.get_current_debug_location());
+        context
     }
 
     pub(super) fn get_opcodes(&self) -> &[Opcode<FieldElement>] {
@@ -306,7 +333,11 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         witness: Witness,
         value: FieldElement,
     ) -> Option<FieldElement> {
-        self.acvm.overwrite_witness(witness, value)
+        let previous_value = self.acvm.overwrite_witness(witness, value);
+        // Values written by the user shouldn't trigger watchpoints
+        self.observe_watchpoints();
+        self.discard_recorded_future();
+        previous_value
     }
 
     pub(super) fn get_current_debug_location(&self) -> Option<DebugLocation> {
@@ -543,7 +574,19 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         &mut self,
         foreign_call: ForeignCallWaitInfo<FieldElement>,
     ) -> DebugCommandResult {
-        let foreign_call_result = self.foreign_call_executor.execute(&foreign_call);
+        let foreign_call_result = if DebugForeignCall::lookup(&foreign_call.function).is_some() {
+            // Debug instrumentation calls only update the debug variables, which are restored
+            // along with the rest of the state when going back, so they are always executed
+            self.foreign_call_executor.execute(&foreign_call)
+        } else if let Some(recorded_result) = self.recording.replay_foreign_call() {
+            Ok(recorded_result)
+        } else {
+            let result = self.foreign_call_executor.execute(&foreign_call);
+            if let Ok(result) = &result {
+                self.recording.record_foreign_call(result.clone());
+            }
+            result
+        };
         match foreign_call_result {
             Ok(foreign_call_result) => {
                 if let Some(mut solver) = self.brillig_solver.take() {
@@ -643,17 +686,29 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
     }
 
     pub(super) fn step_into_opcode(&mut self) -> DebugCommandResult {
-        if self.brillig_solver.is_some() {
-            return self.step_brillig_opcode();
-        }
+        let result = self.execute_step();
+        self.check_watchpoints(result)
+    }
 
-        match self.acvm.step_into_brillig() {
-            StepResult::IntoBrillig(solver) => {
-                self.brillig_solver = Some(solver);
-                self.step_brillig_opcode()
-            }
-            StepResult::Status(status) => self.handle_acvm_status(status),
+    /// Executes a single ACIR or Brillig opcode, recording the step.
+    fn execute_step(&mut self) -> DebugCommandResult {
+        if self.recording.needs_snapshot() {
+            let snapshot = self.take_snapshot();
+            self.recording.add_snapshot(snapshot);
         }
+        let result = if self.brillig_solver.is_some() {
+            self.step_brillig_opcode()
+        } else {
+            match self.acvm.step_into_brillig() {
+                StepResult::IntoBrillig(solver) => {
+                    self.brillig_solver = Some(solver);
+                    self.step_brillig_opcode()
+                }
+                StepResult::Status(status) => self.handle_acvm_status(status),
+            }
+        };
+        self.recording.record_step(self.get_current_debug_location());
+        result
     }
 
     fn get_current_acir_index(&self) -> Option<usize> {
@@ -707,8 +762,8 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         if self.is_executing_brillig() {
             self.step_out_of_brillig_opcode()
         } else {
-            let status = self.acvm.solve_opcode();
-            self.handle_acvm_status(status)
+            // Outside of Brillig, stepping into an opcode solves it entirely
+            self.step_into_opcode()
         }
     }
 
@@ -768,6 +823,180 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         }
     }
 
+    /// Returns the number of steps executed since the start to reach the current state.
+    pub(super) fn current_step(&self) -> usize {
+        self.recording.current_step()
+    }
+
+    pub(super) fn can_step_back(&self) -> bool {
+        self.recording.current_step() > self.recording.first_step()
+    }
+
+    fn take_snapshot(&self) -> ExecutionSnapshot<'a, B> {
+        ExecutionSnapshot {
+            acvm: self.acvm.clone(),
+            current_circuit_id: self.current_circuit_id,
+            brillig_solver: self.brillig_solver.clone(),
+            witness_stack: self.witness_stack.clone(),
+            acvm_stack: self.acvm_stack.clone(),
+            debug_vars: self.foreign_call_executor.debug_vars_snapshot(),
+            foreign_call_position: self.recording.foreign_call_position(),
+        }
+    }
+
+    /// Discards the recorded steps after the current one, since they can't be replayed after the
+    /// user modified the state of the execution.
+    fn discard_recorded_future(&mut self) {
+        self.recording.truncate();
+        let snapshot = self.take_snapshot();
+        self.recording.add_snapshot(snapshot);
+    }
+
+    /// Moves execution to the state reached after the given number of steps, restoring the closest
+    /// snapshot and replaying the steps after it. Returns the result of the last replayed step.
+    pub(super) fn goto_step(&mut self, step: usize) -> Result<DebugCommandResult, RecordingError> {
+        if step == self.recording.current_step() {
+            return Ok(DebugCommandResult::Ok);
+        }
+        self.recording.check_step(step)?;
+
+        let (snapshot_step, snapshot) = self
+            .recording
+            .closest_snapshot(step)
+            .expect("A snapshot is kept at the first recorded step");
+        self.acvm = snapshot.acvm.clone();
+        self.current_circuit_id = snapshot.current_circuit_id;
+        self.brillig_solver = snapshot.brillig_solver.clone();
+        self.witness_stack = snapshot.witness_stack.clone();
+        self.acvm_stack = snapshot.acvm_stack.clone();
+        if let Some(debug_vars) = &snapshot.debug_vars {
+            self.foreign_call_executor.restore_debug_vars(debug_vars.clone());
+        }
+        self.recording.rewind_to_snapshot(snapshot_step);
+
+        self.replaying = true;
+        let mut result = DebugCommandResult::Ok;
+        while self.recording.current_step() < step {
+            result = self.execute_step();
+            if !matches!(result, DebugCommandResult::Ok) {
+                break;
+            }
+        }
+        self.replaying = false;
+
+        // Changes made while going back shouldn't trigger watchpoints
+        self.observe_watchpoints();
+        Ok(result)
+    }
+
+    /// Goes back to the state before the last executed opcode.
+    pub(super) fn step_back_opcode(&mut self) -> Result<DebugCommandResult, RecordingError> {
+        let step = self.recording.current_step().saturating_sub(1).max(self.recording.first_step());
+        self.goto_step(step)
+    }
+
+    fn get_source_location_at_step(&self, step: usize) -> Option<Vec<Location>> {
+        self.recording
+            .location_at(step)
+            .map(|debug_location| self.get_source_location_for_debug_location(&debug_location))
+            .filter(|locations| !locations.is_empty())
+    }
+
+    /// Goes back to the first opcode of the previous source location, so that `next_into` would
+    /// return to the current one.
+    pub(super) fn next_back(&mut self) -> Result<DebugCommandResult, RecordingError> {
+        let first_step = self.recording.first_step();
+        let start_location = self.get_current_source_location();
+        let mut step = self.recording.current_step();
+        let previous_location = loop {
+            if step <= first_step {
+                return self.goto_step(first_step);
+            }
+            step -= 1;
+            let location = self.get_source_location_at_step(step);
+            if location.is_some() && location != start_location {
+                break location;
+            }
+        };
+        // Move back to where execution entered the previous source location, skipping over
+        // opcodes which are not mapped to any
+        let mut target_step = step;
+        while step > first_step {
+            step -= 1;
+            match self.get_source_location_at_step(step) {
+                None => continue,
+                location if location == previous_location => target_step = step,
+                _ => break,
+            }
+        }
+        self.goto_step(target_step)
+    }
+
+    /// Goes back to the last point of the execution where a breakpoint would have stopped it, or
+    /// to the start of the recording if there is none. Logpoints and hit conditions are ignored.
+    pub(super) fn reverse_cont(&mut self) -> Result<DebugCommandResult, RecordingError> {
+        let first_step = self.recording.first_step();
+        let mut step = self.recording.current_step();
+        while step > first_step {
+            step -= 1;
+            let Some(location) = self.recording.location_at(step) else {
+                continue;
+            };
+            let Some(options) = self.breakpoints.get(&location).map(|bp| bp.options.clone()) else {
+                continue;
+            };
+            if options.is_logpoint() {
+                continue;
+            }
+            let Some(condition) = &options.condition else {
+                self.goto_step(step)?;
+                return Ok(DebugCommandResult::BreakpointReached(location));
+            };
+            // Conditions depend on the variables in scope at that point
+            self.goto_step(step)?;
+            match condition.evaluate(self.current_stack_frame().as_ref()) {
+                Ok(false) => continue,
+                Ok(true) => {}
+                Err(error) => self.log_messages.push(format!(
+                    "Failed to evaluate condition of breakpoint at {location}: {error}"
+                )),
+            }
+            return Ok(DebugCommandResult::BreakpointReached(location));
+        }
+        self.goto_step(first_step)
+    }
+
+    /// Returns the last step before the current one where the given opcode was about to be
+    /// executed, if any.
+    pub(super) fn find_past_step_at(&self, location: &DebugLocation) -> Option<usize> {
+        (self.recording.first_step()..self.recording.current_step()).rev().find(|step| {
+            self.recording
+                .location_at(*step)
+                .is_some_and(|step_location| is_same_opcode_location(&step_location, location))
+        })
+    }
+
+    /// Goes to the last point of the execution where the given opcode was about to be executed,
+    /// or, if it was never reached, continues execution until reaching it.
+    pub(super) fn goto_location(
+        &mut self,
+        location: &DebugLocation,
+    ) -> Result<DebugCommandResult, RecordingError> {
+        if let Some(step) = self.find_past_step_at(location) {
+            return self.goto_step(step);
+        }
+        loop {
+            let result = self.step_into_opcode();
+            if !matches!(result, DebugCommandResult::Ok) {
+                return Ok(result);
+            }
+            let current_location = self.get_current_debug_location();
+            if current_location.is_some_and(|current| is_same_opcode_location(&current, location)) {
+                return Ok(DebugCommandResult::Ok);
+            }
+        }
+    }
+
     pub(super) fn get_brillig_memory(&self) -> Option<&[MemoryValue<FieldElement>]> {
         self.brillig_solver.as_ref().map(|solver| solver.get_memory())
     }
@@ -785,6 +1014,9 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
                     .expect("Invalid value for the given bit size"),
             );
         }
+        // Values written by the user shouldn't trigger watchpoints
+        self.observe_watchpoints();
+        self.discard_recorded_future();
     }
 
     pub(super) fn get_variables(&self) -> Vec<StackFrame<FieldElement>> {
@@ -795,14 +1027,60 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         return self.foreign_call_executor.current_stack_frame();
     }
 
-    fn breakpoint_reached(&self) -> bool {
-        if let Some(location) = self.get_current_debug_location() {
-            self.breakpoints.contains(&location)
-        } else {
-            false
+    /// Evaluates an expression over the source variables in scope at the current location,
+    /// or in the given stack frame if any, counting frames from the outermost one.
+    pub(super) fn evaluate(
+        &self,
+        expression: &str,
+        frame_index: Option<usize>,
+    ) -> Result<EvaluatedValue, ExpressionError> {
+        match frame_index {
+            Some(frame_index) => {
+                let frames = self.get_variables();
+                let frame =
+                    frames.get(frame_index).ok_or(ExpressionError::UnknownFrame(frame_index))?;
+                evaluate_expression(expression, Some(frame))
+            }
+            None => evaluate_expression(expression, self.current_stack_frame().as_ref()),
+        }
+    }
+
+    /// Checks whether execution should stop at the current location, evaluating the options of
+    /// any breakpoint set there. Logpoints don't stop execution, but queue their message instead.
+    fn breakpoint_reached(&mut self) -> bool {
+        if self.replaying {
+            return false;
+        }
+        let Some(location) = self.get_current_debug_location() else {
+            return false;
+        };
+        let Some(breakpoint) = self.breakpoints.get_mut(&location) else {
+            return false;
+        };
+
+        let frame = self.foreign_call_executor.current_stack_frame();
+        match breakpoint.hit(frame.as_ref()) {
+            Ok(BreakpointHit::Ignored) => false,
+            Ok(BreakpointHit::Stop) => true,
+            Ok(BreakpointHit::Log(message)) => {
+                self.log_messages.push(message);
+                false
+            }
+            Err(error) => {
+                // Stop so that the user gets a chance to fix the condition
+                self.log_messages.push(format!(
+                    "Failed to evaluate condition of breakpoint at {location}: {error}"
+                ));
+                true
+            }
         }
     }
 
+    /// Returns the messages printed by logpoints since the last call.
+    pub(super) fn take_log_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log_messages)
+    }
+
     pub(super) fn is_valid_debug_location(&self, location: &DebugLocation) -> bool {
         if location.circuit_id as usize >= self.circuits.len() {
             return false;
@@ -827,18 +1105,42 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
     }
 
     pub(super) fn is_breakpoint_set(&self, location: &DebugLocation) -> bool {
-        self.breakpoints.contains(location)
+        self.breakpoints.contains_key(location)
     }
 
     pub(super) fn add_breakpoint(&mut self, location: DebugLocation) -> bool {
-        self.breakpoints.insert(location)
+        self.add_breakpoint_with_options(location, BreakpointOptions::default())
+    }
+
+    /// Adds a breakpoint with the given options, returning `false` if one was already set at
+    /// the location, in which case it is left unchanged.
+    pub(super) fn add_breakpoint_with_options(
+        &mut self,
+        location: DebugLocation,
+        options: BreakpointOptions,
+    ) -> bool {
+        if self.breakpoints.contains_key(&location) {
+            return false;
+        }
+        self.breakpoints.insert(location, Breakpoint::new(options));
+        true
+    }
+
+    /// Returns the options of the breakpoint at the given location so they can be updated.
+    pub(super) fn get_breakpoint_options_mut(
+        &mut self,
+        location: &DebugLocation,
+    ) -> Option<&mut BreakpointOptions> {
+        self.breakpoints.get_mut(location).map(|breakpoint| &mut breakpoint.options)
     }
 
     pub(super) fn delete_breakpoint(&mut self, location: &DebugLocation) -> bool {
-        self.breakpoints.remove(location)
+        self.breakpoints.remove(location).is_some()
     }
 
-    pub(super) fn iterate_breakpoints(&self) -> Iter<'_, DebugLocation> {
+    pub(super) fn iterate_breakpoints(
+        &self,
+    ) -> impl Iterator<Item = (&DebugLocation, &Breakpoint)> + '_ {
         self.breakpoints.iter()
     }
 
@@ -846,6 +1148,129 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
         self.breakpoints.clear();
     }
 
+    /// Returns the witness map of the innermost frame executing the given circuit, if any.
+    fn get_witness_map_of_circuit(&self, circuit_id: u32) -> Option<&WitnessMap<FieldElement>> {
+        if circuit_id == self.current_circuit_id {
+            return Some(self.acvm.witness_map());
+        }
+        self.acvm_stack
+            .iter()
+            .rev()
+            .find(|frame| frame.circuit_id == circuit_id)
+            .map(|frame| frame.acvm.witness_map())
+    }
+
+    /// Returns the current value of a watch target, or `None` if it has no value at this point,
+    /// such as an unsolved witness or a variable which is not in scope.
+    fn get_watched_value(&self, target: &WatchTarget) -> Option<WatchedValue> {
+        match target {
+            WatchTarget::Witness { circuit_id, witness, .. } => self
+                .get_witness_map_of_circuit(*circuit_id)?
+                .get(witness)
+                .map(|value| WatchedValue::Field(*value)),
+            WatchTarget::BrilligMemory { address } => {
+                self.get_brillig_memory()?.get(*address).map(|value| WatchedValue::Memory(*value))
+            }
+            WatchTarget::Variable { name, frame_index } => {
+                let frames = self.get_variables();
+                let frame = frames.get(*frame_index)?;
+                frame
+                    .variables
+                    .iter()
+                    .find(|(var_name, ..)| *var_name == name.as_str())
+                    .map(|(_, value, typ)| WatchedValue::Variable((*value).clone(), (*typ).clone()))
+            }
+        }
+    }
+
+    pub(super) fn add_watchpoint(&mut self, target: WatchTarget) -> WatchpointId {
+        self.add_watchpoint_with_options(target, BreakpointOptions::default())
+    }
+
+    pub(super) fn add_watchpoint_with_options(
+        &mut self,
+        target: WatchTarget,
+        options: BreakpointOptions,
+    ) -> WatchpointId {
+        let id = self.next_watchpoint_id;
+        self.next_watchpoint_id += 1;
+        let current_value = self.get_watched_value(&target);
+        self.watchpoints.insert(id, Watchpoint::new(target, options, current_value));
+        id
+    }
+
+    pub(super) fn delete_watchpoint(&mut self, id: WatchpointId) -> bool {
+        self.watchpoints.remove(&id).is_some()
+    }
+
+    pub(super) fn iterate_watchpoints(
+        &self,
+    ) -> impl Iterator<Item = (&WatchpointId, &Watchpoint)> + '_ {
+        self.watchpoints.iter()
+    }
+
+    pub(super) fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Records the current value of every watch target, returning the watchpoints triggered
+    /// since the previous observation.
+    fn observe_watchpoints(&mut self) -> Vec<WatchpointHit> {
+        if self.watchpoints.is_empty() {
+            return Vec::new();
+        }
+        let values: Vec<_> = self
+            .watchpoints
+            .values()
+            .map(|watchpoint| self.get_watched_value(&watchpoint.target))
+            .collect();
+        self.watchpoints
+            .iter_mut()
+            .zip(values)
+            .filter_map(|((id, watchpoint), value)| watchpoint.observe(*id, value))
+            .collect()
+    }
+
+    /// Stops execution after a step which triggered a watchpoint. Results other than `Ok` take
+    /// precedence, but the watched values are still observed so that the changes made by the step
+    /// aren't reported by the next one.
+    fn check_watchpoints(&mut self, result: DebugCommandResult) -> DebugCommandResult {
+        let hits = self.observe_watchpoints();
+        if !matches!(result, DebugCommandResult::Ok) || hits.is_empty() {
+            return result;
+        }
+        let hits: Vec<_> = hits.into_iter().filter(|hit| self.watchpoint_stops(hit)).collect();
+        if hits.is_empty() {
+            result
+        } else {
+            DebugCommandResult::WatchpointTriggered(hits)
+        }
+    }
+
+    /// Evaluates the options of a triggered watchpoint, deciding whether execution should stop.
+    fn watchpoint_stops(&mut self, hit: &WatchpointHit) -> bool {
+        let frame = self.foreign_call_executor.current_stack_frame();
+        let Some(watchpoint) = self.watchpoints.get_mut(&hit.id) else {
+            return false;
+        };
+        match watchpoint.breakpoint.hit(frame.as_ref()) {
+            Ok(BreakpointHit::Ignored) => false,
+            Ok(BreakpointHit::Stop) => true,
+            Ok(BreakpointHit::Log(message)) => {
+                self.log_messages.push(message);
+                false
+            }
+            Err(error) => {
+                // Stop so that the user gets a chance to fix the condition
+                self.log_messages.push(format!(
+                    "Failed to evaluate condition of watchpoint #{}: {error}",
+                    hit.id
+                ));
+                true
+            }
+        }
+    }
+
     pub(super) fn is_solved(&self) -> bool {
         matches!(self.acvm.get_status(), ACVMStatus::Solved)
     }
@@ -857,6 +1282,12 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> DebugContext<'a, B> {
     }
 }
 
+/// Compares two locations ignoring their Brillig function, which is not specified in locations
+/// given by users.
+fn is_same_opcode_location(lhs: &DebugLocation, rhs: &DebugLocation) -> bool {
+    lhs.circuit_id == rhs.circuit_id && lhs.opcode_location == rhs.opcode_location
+}
+
 fn is_debug_file_in_debug_crate(debug_file: &DebugFile) -> bool {
     debug_file.path.starts_with("__debug/")
 }
@@ -964,7 +1395,8 @@ mod tests {
         },
         blackbox_solver::StubbedBlackBoxSolver,
         brillig_vm::brillig::{
-            BinaryFieldOp, HeapValueType, MemoryAddress, Opcode as BrilligOpcode, ValueOrArray,
+            BinaryFieldOp, BinaryIntOp, HeapValueType, MemoryAddress, Opcode as BrilligOpcode,
+            ValueOrArray,
         },
     };
     use nargo::PrintOutput;
@@ -1238,6 +1670,336 @@ mod tests {
         assert_eq!(context.get_current_debug_location(), None);
     }
 
+    /// Builds a program made of a single Brillig call, which increments a counter stored at
+    /// memory address 0 from 0 to 3 in a loop whose body starts at Brillig opcode 3.
+    fn build_counter_loop_program(
+    ) -> (Vec<Circuit<FieldElement>>, Vec<BrilligBytecode<FieldElement>>) {
+        let counter = MemoryAddress::direct(0);
+        let one = MemoryAddress::direct(1);
+        let limit = MemoryAddress::direct(2);
+        let condition = MemoryAddress::direct(3);
+        let zero = MemoryAddress::direct(4);
+        let u32_const = |destination, value: u64| BrilligOpcode::Const {
+            destination,
+            bit_size: BitSize::Integer(IntegerBitSize::U32),
+            value: FieldElement::from(value),
+        };
+
+        let brillig_bytecode = BrilligBytecode {
+            bytecode: vec![
+                u32_const(counter, 0),
+                u32_const(one, 1),
+                u32_const(limit, 3),
+                BrilligOpcode::BinaryIntOp {
+                    destination: counter,
+                    op: BinaryIntOp::Add,
+                    bit_size: IntegerBitSize::U32,
+                    lhs: counter,
+                    rhs: one,
+                },
+                BrilligOpcode::BinaryIntOp {
+                    destination: condition,
+                    op: BinaryIntOp::LessThan,
+                    bit_size: IntegerBitSize::U32,
+                    lhs: counter,
+                    rhs: limit,
+                },
+                BrilligOpcode::JumpIf { condition, location: 3 },
+                u32_const(zero, 0),
+                BrilligOpcode::Stop { return_data: HeapVector { pointer: zero, size: zero } },
+            ],
+        };
+        let opcodes = vec![Opcode::BrilligCall {
+            id: BrilligFunctionId(0),
+            inputs: vec![],
+            outputs: vec![],
+            predicate: None,
+        }];
+        let circuit = Circuit { opcodes, ..Circuit::default() };
+        (vec![circuit], vec![brillig_bytecode])
+    }
+
+    #[test]
+    fn test_breakpoint_hit_conditions_and_logpoints() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+
+        let brillig_location = |brillig_index| DebugLocation {
+            circuit_id: 0,
+            opcode_location: OpcodeLocation::Brillig { acir_index: 0, brillig_index },
+            brillig_function_id: Some(BrilligFunctionId(0)),
+        };
+
+        // the loop body is reached three times, but we only want to stop on the second time
+        let options = BreakpointOptions::parse(None, Some("%2"), None).unwrap();
+        assert!(context.add_breakpoint_with_options(brillig_location(3), options));
+
+        // the logpoint should print on each iteration without stopping
+        let options = BreakpointOptions::parse(None, None, Some("checking counter")).unwrap();
+        assert!(context.add_breakpoint_with_options(brillig_location(4), options));
+
+        let result = context.cont();
+        assert!(matches!(result, DebugCommandResult::BreakpointReached(_)));
+        assert_eq!(context.get_current_debug_location(), Some(brillig_location(3)));
+        assert_eq!(context.take_log_messages(), vec!["checking counter"]);
+
+        let result = context.cont();
+        assert!(matches!(result, DebugCommandResult::Done));
+        assert_eq!(context.take_log_messages(), vec!["checking counter", "checking counter"]);
+
+        let hit_counts: Vec<_> = context
+            .iterate_breakpoints()
+            .map(|(location, breakpoint)| (*location, breakpoint.hit_count()))
+            .collect();
+        assert_eq!(hit_counts.len(), 2);
+        assert!(hit_counts.contains(&(brillig_location(3), 3)));
+        assert!(hit_counts.contains(&(brillig_location(4), 3)));
+    }
+
+    #[test]
+    fn test_brillig_memory_watchpoint() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+
+        let id = context.add_watchpoint(WatchTarget::BrilligMemory { address: 0 });
+
+        // the counter is initialized and then incremented three times
+        for expected_value in 0..=3 {
+            let DebugCommandResult::WatchpointTriggered(hits) = context.cont() else {
+                panic!("Expected the watchpoint to be triggered");
+            };
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].id, id);
+            assert_eq!(
+                hits[0].new_value,
+                WatchedValue::Memory(MemoryValue::new_integer(expected_value, IntegerBitSize::U32))
+            );
+        }
+
+        // the Brillig memory going away when the call finishes doesn't trigger the watchpoint
+        let result = context.cont();
+        assert!(matches!(result, DebugCommandResult::Done));
+    }
+
+    #[test]
+    fn test_watchpoint_options() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+
+        // only every other change of the counter stops execution
+        let options = BreakpointOptions::parse(None, Some("% 2"), None).unwrap();
+        let every_other =
+            context.add_watchpoint_with_options(WatchTarget::BrilligMemory { address: 0 }, options);
+        // a false condition never stops execution, even though the counter changes
+        let options = BreakpointOptions::parse(Some("1 == 2"), None, None).unwrap();
+        context.add_watchpoint_with_options(WatchTarget::BrilligMemory { address: 0 }, options);
+
+        for expected_value in [1, 3] {
+            let DebugCommandResult::WatchpointTriggered(hits) = context.cont() else {
+                panic!("Expected the watchpoint to be triggered");
+            };
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].id, every_other);
+            assert_eq!(
+                hits[0].new_value,
+                WatchedValue::Memory(MemoryValue::new_integer(expected_value, IntegerBitSize::U32))
+            );
+        }
+        assert_eq!(context.watchpoints[&every_other].breakpoint.hit_count(), 4);
+
+        let result = context.cont();
+        assert!(matches!(result, DebugCommandResult::Done));
+    }
+
+    #[test]
+    fn test_watchpoint_condition_errors_stop_execution() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+
+        // there are no source variables in this program
+        let options = BreakpointOptions::parse(Some("counter == 2"), None, None).unwrap();
+        let id =
+            context.add_watchpoint_with_options(WatchTarget::BrilligMemory { address: 0 }, options);
+
+        let DebugCommandResult::WatchpointTriggered(hits) = context.cont() else {
+            panic!("Expected the watchpoint to be triggered");
+        };
+        assert_eq!(hits[0].id, id);
+        let messages = context.take_log_messages();
+        assert_eq!(messages.len(), 1);
+        assert!(
+            messages[0].starts_with(&format!("Failed to evaluate condition of watchpoint #{id}"))
+        );
+    }
+
+    #[test]
+    fn test_step_back_and_reverse_continue() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+
+        let brillig_location = |brillig_index| DebugLocation {
+            circuit_id: 0,
+            opcode_location: OpcodeLocation::Brillig { acir_index: 0, brillig_index },
+            brillig_function_id: Some(BrilligFunctionId(0)),
+        };
+        let counter = |context: &DebugContext<StubbedBlackBoxSolver>| {
+            context.get_brillig_memory().map(|memory| memory[0])
+        };
+        let u32_value = |value| Some(MemoryValue::new_integer(value, IntegerBitSize::U32));
+
+        // stop at the start of the loop body for the second time
+        assert!(context.add_breakpoint(brillig_location(3)));
+        assert!(matches!(context.cont(), DebugCommandResult::BreakpointReached(_)));
+        assert!(matches!(context.cont(), DebugCommandResult::BreakpointReached(_)));
+        assert_eq!(context.current_step(), 6);
+        assert_eq!(counter(&context), u32_value(1));
+
+        // stepping back restores the state before the jump
+        assert!(matches!(context.step_back_opcode().unwrap(), DebugCommandResult::Ok));
+        assert_eq!(context.get_current_debug_location(), Some(brillig_location(5)));
+        assert_eq!(counter(&context), u32_value(1));
+
+        // reverse continue stops at the previous time the breakpoint was reached
+        let result = context.reverse_cont().unwrap();
+        assert!(matches!(result, DebugCommandResult::BreakpointReached(_)));
+        assert_eq!(context.current_step(), 3);
+        assert_eq!(counter(&context), u32_value(0));
+
+        // and goes back to the start when there are no more breakpoints
+        assert!(matches!(context.reverse_cont().unwrap(), DebugCommandResult::Ok));
+        assert!(!context.can_step_back());
+        assert_eq!(context.get_brillig_memory(), None);
+
+        // execution can be resumed from there
+        assert!(matches!(context.cont(), DebugCommandResult::BreakpointReached(_)));
+        assert!(context.delete_breakpoint(&brillig_location(3)));
+        assert!(matches!(
+            context.goto_location(&brillig_location(6)).unwrap(),
+            DebugCommandResult::Ok
+        ));
+        assert_eq!(counter(&context), u32_value(3));
+        assert!(matches!(context.cont(), DebugCommandResult::Done));
+
+        // going back from the end of the execution
+        assert!(matches!(context.step_back_opcode().unwrap(), DebugCommandResult::Ok));
+        assert_eq!(context.get_current_debug_location(), Some(brillig_location(7)));
+        assert!(matches!(
+            context.goto_location(&brillig_location(4)).unwrap(),
+            DebugCommandResult::Ok
+        ));
+        assert_eq!(counter(&context), u32_value(3));
+    }
+
+    #[test]
+    fn test_recording_evicts_old_snapshots() {
+        let (circuits, brillig_funcs) = &build_counter_loop_program();
+        let debug_artifact = &DebugArtifact { debug_symbols: vec![], file_map: BTreeMap::new() };
+
+        let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
+            PrintOutput::Stdout,
+            debug_artifact,
+        ));
+        let mut context = DebugContext::new(
+            &StubbedBlackBoxSolver,
+            circuits,
+            debug_artifact,
+            WitnessMap::default(),
+            foreign_call_executor,
+            brillig_funcs,
+        );
+        context.recording.set_snapshot_limits(2, 2);
+
+        assert!(matches!(context.cont(), DebugCommandResult::Done));
+        let last_step = context.current_step();
+        let first_step = context.recording.first_step();
+        assert!(first_step > 0);
+        assert!(last_step - first_step <= 4);
+
+        assert_eq!(
+            context.goto_step(0).unwrap_err(),
+            RecordingError::Evicted { step: 0, first_step }
+        );
+        assert_eq!(
+            context.goto_step(last_step + 1).unwrap_err(),
+            RecordingError::NotReached { step: last_step + 1, last_step }
+        );
+
+        // the steps still recorded can be gone back to
+        let first_location = context.recording.location_at(first_step);
+        while context.can_step_back() {
+            assert!(matches!(context.step_back_opcode().unwrap(), DebugCommandResult::Ok));
+        }
+        assert_eq!(context.current_step(), first_step);
+        assert_eq!(context.get_current_debug_location(), first_location);
+        assert!(matches!(context.cont(), DebugCommandResult::Done));
+        assert_eq!(context.current_step(), last_step);
+    }
+
     #[test]
     fn test_address_debug_location_mapping() {
         let brillig_one =
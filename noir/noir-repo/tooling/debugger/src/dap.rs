@@ -3,27 +3,32 @@ use std::io::{Read, Write};
 
 use acvm::acir::circuit::brillig::BrilligBytecode;
 use acvm::acir::circuit::Circuit;
-use acvm::acir::native_types::WitnessMap;
+use acvm::acir::native_types::{Witness, WitnessMap};
+use acvm::brillig_vm::MemoryValue;
+use acvm::AcirField;
 use acvm::{BlackBoxFunctionSolver, FieldElement};
 use nargo::PrintOutput;
 
+use crate::breakpoints::BreakpointOptions;
 use crate::context::DebugContext;
 use crate::context::{DebugCommandResult, DebugLocation};
 use crate::foreign_calls::DefaultDebugForeignCallExecutor;
+use crate::watchpoints::{WatchTarget, WatchpointId};
 
 use dap::errors::ServerError;
-use dap::events::StoppedEventBody;
+use dap::events::{OutputEventBody, StoppedEventBody};
 use dap::prelude::Event;
 use dap::requests::{Command, Request, SetBreakpointsArguments};
 use dap::responses::{
-    ContinueResponse, DisassembleResponse, ResponseBody, ScopesResponse, SetBreakpointsResponse,
+    ContinueResponse, DataBreakpointInfoResponse, DisassembleResponse, EvaluateResponse,
+    ResponseBody, ScopesResponse, SetBreakpointsResponse, SetDataBreakpointsResponse,
     SetExceptionBreakpointsResponse, SetInstructionBreakpointsResponse, StackTraceResponse,
     ThreadsResponse, VariablesResponse,
 };
 use dap::server::Server;
 use dap::types::{
-    Breakpoint, DisassembledInstruction, Scope, Source, StackFrame, SteppingGranularity,
-    StoppedEventReason, Thread, Variable,
+    Breakpoint, DisassembledInstruction, OutputEventCategory, Scope, Source, StackFrame,
+    SteppingGranularity, StoppedEventReason, Thread, Variable,
 };
 use noirc_artifacts::debug::DebugArtifact;
 
@@ -38,13 +43,15 @@ pub struct DapSession<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElem
     debug_artifact: &'a DebugArtifact,
     running: bool,
     next_breakpoint_id: BreakpointId,
-    instruction_breakpoints: Vec<(DebugLocation, BreakpointId)>,
-    source_breakpoints: BTreeMap<FileId, Vec<(DebugLocation, BreakpointId)>>,
+    instruction_breakpoints: Vec<(DebugLocation, BreakpointId, BreakpointOptions)>,
+    source_breakpoints: BTreeMap<FileId, Vec<(DebugLocation, BreakpointId, BreakpointOptions)>>,
+    data_breakpoints: Vec<(WatchpointId, BreakpointId)>,
 }
 
 enum ScopeReferences {
     Locals = 1,
     WitnessMap = 2,
+    BrilligMemory = 3,
     InvalidScope = 0,
 }
 
@@ -53,6 +60,7 @@ impl From<i64> for ScopeReferences {
         match value {
             1 => Self::Locals,
             2 => Self::WitnessMap,
+            3 => Self::BrilligMemory,
             _ => Self::InvalidScope,
         }
     }
@@ -86,6 +94,7 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
             next_breakpoint_id: 1,
             instruction_breakpoints: vec![],
             source_breakpoints: BTreeMap::new(),
+            data_breakpoints: vec![],
         }
     }
 
@@ -139,6 +148,12 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                 Command::SetInstructionBreakpoints(_) => {
                     self.handle_set_instruction_breakpoints(req)?;
                 }
+                Command::DataBreakpointInfo(_) => {
+                    self.handle_data_breakpoint_info(req)?;
+                }
+                Command::SetDataBreakpoints(_) => {
+                    self.handle_set_data_breakpoints(req)?;
+                }
                 Command::Threads => {
                     self.server.respond(req.success(ResponseBody::Threads(ThreadsResponse {
                         threads: vec![Thread { id: 0, name: "main".to_string() }],
@@ -177,12 +192,26 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                 Command::Continue(_) => {
                     self.handle_continue(req)?;
                 }
+                Command::StepBack(ref args) => {
+                    let granularity =
+                        args.granularity.as_ref().unwrap_or(&SteppingGranularity::Statement);
+                    match granularity {
+                        SteppingGranularity::Instruction => self.handle_step_back(req)?,
+                        _ => self.handle_next_back(req)?,
+                    }
+                }
+                Command::ReverseContinue(_) => {
+                    self.handle_reverse_continue(req)?;
+                }
                 Command::Scopes(_) => {
                     self.handle_scopes(req)?;
                 }
                 Command::Variables(ref _args) => {
                     self.handle_variables(req)?;
                 }
+                Command::Evaluate(_) => {
+                    self.handle_evaluate(req)?;
+                }
                 _ => {
                     eprintln!("ERROR: unhandled command: {:?}", req.command);
                 }
@@ -324,15 +353,54 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
         self.handle_execution_result(result)
     }
 
+    fn handle_step_back(&mut self, req: Request) -> Result<(), ServerError> {
+        let result = match self.context.step_back_opcode() {
+            Ok(result) => result,
+            Err(error) => {
+                self.server.respond(req.error(&error.to_string()))?;
+                return Ok(());
+            }
+        };
+        eprintln!("INFO: stepped back by instruction with result {result:?}");
+        self.server.respond(req.ack()?)?;
+        self.handle_execution_result(result)
+    }
+
+    fn handle_next_back(&mut self, req: Request) -> Result<(), ServerError> {
+        let result = match self.context.next_back() {
+            Ok(result) => result,
+            Err(error) => {
+                self.server.respond(req.error(&error.to_string()))?;
+                return Ok(());
+            }
+        };
+        eprintln!("INFO: stepped back by statement with result {result:?}");
+        self.server.respond(req.ack()?)?;
+        self.handle_execution_result(result)
+    }
+
+    fn handle_reverse_continue(&mut self, req: Request) -> Result<(), ServerError> {
+        let result = match self.context.reverse_cont() {
+            Ok(result) => result,
+            Err(error) => {
+                self.server.respond(req.error(&error.to_string()))?;
+                return Ok(());
+            }
+        };
+        eprintln!("INFO: reverse continue with result {result:?}");
+        self.server.respond(req.ack()?)?;
+        self.handle_execution_result(result)
+    }
+
     fn find_breakpoints_at_location(&self, debug_location: &DebugLocation) -> Vec<i64> {
         let mut result = vec![];
-        for (location, id) in &self.instruction_breakpoints {
+        for (location, id, _) in &self.instruction_breakpoints {
             if debug_location == location {
                 result.push(*id);
             }
         }
         for breakpoints in self.source_breakpoints.values() {
-            for (location, id) in breakpoints {
+            for (location, id, _) in breakpoints {
                 if debug_location == location {
                     result.push(*id);
                 }
@@ -342,6 +410,13 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
     }
 
     fn handle_execution_result(&mut self, result: DebugCommandResult) -> Result<(), ServerError> {
+        for message in self.context.take_log_messages() {
+            self.server.send_event(Event::Output(OutputEventBody {
+                category: Some(OutputEventCategory::Console),
+                output: format!("{message}\n"),
+                ..OutputEventBody::default()
+            }))?;
+        }
         match result {
             DebugCommandResult::Done => {
                 self.running = false;
@@ -369,6 +444,27 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                     hit_breakpoint_ids: Some(breakpoint_ids),
                 }))?;
             }
+            DebugCommandResult::WatchpointTriggered(hits) => {
+                let breakpoint_ids = hits
+                    .iter()
+                    .filter_map(|hit| {
+                        self.data_breakpoints
+                            .iter()
+                            .find(|(watchpoint_id, _)| *watchpoint_id == hit.id)
+                            .map(|(_, breakpoint_id)| *breakpoint_id)
+                    })
+                    .collect();
+                let text = hits.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+                self.server.send_event(Event::Stopped(StoppedEventBody {
+                    reason: StoppedEventReason::Data,
+                    description: Some(String::from("Paused at data breakpoint")),
+                    thread_id: Some(0),
+                    preserve_focus_hint: Some(false),
+                    text: Some(text),
+                    all_threads_stopped: Some(false),
+                    hit_breakpoint_ids: Some(breakpoint_ids),
+                }))?;
+            }
             DebugCommandResult::Error(err) => {
                 self.server.send_event(Event::Stopped(StoppedEventBody {
                     reason: StoppedEventReason::Exception,
@@ -392,12 +488,12 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
 
     fn reinstall_breakpoints(&mut self) {
         self.context.clear_breakpoints();
-        for (location, _) in &self.instruction_breakpoints {
-            self.context.add_breakpoint(*location);
+        for (location, _, options) in &self.instruction_breakpoints {
+            self.context.add_breakpoint_with_options(*location, options.clone());
         }
         for breakpoints in self.source_breakpoints.values() {
-            for (location, _) in breakpoints {
-                self.context.add_breakpoint(*location);
+            for (location, _, options) in breakpoints {
+                self.context.add_breakpoint_with_options(*location, options.clone());
             }
         }
     }
@@ -408,11 +504,25 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
         };
 
         // compute breakpoints to set and return
-        let mut breakpoints_to_set: Vec<(DebugLocation, i64)> = vec![];
+        let mut breakpoints_to_set: Vec<(DebugLocation, i64, BreakpointOptions)> = vec![];
         let breakpoints: Vec<Breakpoint> = args
             .breakpoints
             .iter()
             .map(|breakpoint| {
+                let options = match BreakpointOptions::parse(
+                    breakpoint.condition.as_deref(),
+                    breakpoint.hit_condition.as_deref(),
+                    None,
+                ) {
+                    Ok(options) => options,
+                    Err(error) => {
+                        return Breakpoint {
+                            verified: false,
+                            message: Some(error.to_string()),
+                            ..Breakpoint::default()
+                        }
+                    }
+                };
                 let offset = breakpoint.offset.unwrap_or(0);
                 let address = breakpoint.instruction_reference.parse::<i64>().unwrap_or(0) + offset;
                 let Ok(address): Result<usize, _> = address.try_into() else {
@@ -434,7 +544,7 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                     };
                 };
                 let id = self.get_next_breakpoint_id();
-                breakpoints_to_set.push((location, id));
+                breakpoints_to_set.push((location, id, options));
                 Breakpoint {
                     id: Some(id),
                     verified: true,
@@ -476,11 +586,25 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
         let Some(ref breakpoints) = &args.breakpoints else {
             return vec![];
         };
-        let mut breakpoints_to_set: Vec<(DebugLocation, i64)> = vec![];
+        let mut breakpoints_to_set: Vec<(DebugLocation, i64, BreakpointOptions)> = vec![];
         let breakpoints = breakpoints
             .iter()
             .map(|breakpoint| {
                 let line = breakpoint.line;
+                let options = match BreakpointOptions::parse(
+                    breakpoint.condition.as_deref(),
+                    breakpoint.hit_condition.as_deref(),
+                    breakpoint.log_message.as_deref(),
+                ) {
+                    Ok(options) => options,
+                    Err(error) => {
+                        return Breakpoint {
+                            verified: false,
+                            message: Some(error.to_string()),
+                            ..Breakpoint::default()
+                        }
+                    }
+                };
                 let Some(location) = self.context.find_opcode_for_source_location(&file_id, line)
                 else {
                     return Breakpoint {
@@ -504,7 +628,7 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                 let breakpoint_address = self.context.debug_location_to_address(&location);
                 let instruction_reference = format!("{}", breakpoint_address);
                 let breakpoint_id = self.get_next_breakpoint_id();
-                breakpoints_to_set.push((location, breakpoint_id));
+                breakpoints_to_set.push((location, breakpoint_id, options));
                 Breakpoint {
                     id: Some(breakpoint_id),
                     verified: true,
@@ -534,6 +658,123 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
         Ok(())
     }
 
+    fn handle_data_breakpoint_info(&mut self, req: Request) -> Result<(), ServerError> {
+        let Command::DataBreakpointInfo(ref args) = req.command else {
+            unreachable!("handle_data_breakpoint_info called on a different request");
+        };
+        let scope: ScopeReferences = args.variables_reference.unwrap_or_default().into();
+        let data_id = match scope {
+            ScopeReferences::Locals => {
+                let frame_index = self.context.get_variables().len().checked_sub(1);
+                frame_index.map(|frame_index| format!("variable:{frame_index}:{}", args.name))
+            }
+            ScopeReferences::WitnessMap => {
+                let index = args.name.strip_prefix('_').and_then(|index| index.parse::<u32>().ok());
+                index
+                    .zip(self.context.get_current_debug_location())
+                    .map(|(index, location)| format!("witness:{}:{index}", location.circuit_id))
+            }
+            ScopeReferences::BrilligMemory => {
+                args.name.parse::<usize>().ok().map(|address| format!("memory:{address}"))
+            }
+            ScopeReferences::InvalidScope => None,
+        };
+        let description = match data_id {
+            Some(_) => format!("Changes to {}", args.name),
+            None => format!("{} cannot be watched", args.name),
+        };
+        self.server.respond(req.success(ResponseBody::DataBreakpointInfo(
+            DataBreakpointInfoResponse {
+                data_id,
+                description,
+                access_types: None,
+                can_persist: Some(false),
+            },
+        )))?;
+        Ok(())
+    }
+
+    fn handle_set_data_breakpoints(&mut self, req: Request) -> Result<(), ServerError> {
+        let Command::SetDataBreakpoints(ref args) = req.command else {
+            unreachable!("handle_set_data_breakpoints called on a different request");
+        };
+
+        // data breakpoints are replaced as a whole on each request
+        for (watchpoint_id, _) in std::mem::take(&mut self.data_breakpoints) {
+            self.context.delete_watchpoint(watchpoint_id);
+        }
+
+        let breakpoints = args
+            .breakpoints
+            .iter()
+            .map(|data_breakpoint| {
+                let options = match BreakpointOptions::parse(
+                    data_breakpoint.condition.as_deref(),
+                    data_breakpoint.hit_condition.as_deref(),
+                    None,
+                ) {
+                    Ok(options) => options,
+                    Err(error) => {
+                        return Breakpoint {
+                            verified: false,
+                            message: Some(error.to_string()),
+                            ..Breakpoint::default()
+                        }
+                    }
+                };
+                let Some(target) = parse_data_id(&data_breakpoint.data_id) else {
+                    return Breakpoint {
+                        verified: false,
+                        message: Some(format!(
+                            "Invalid data breakpoint {}",
+                            data_breakpoint.data_id
+                        )),
+                        ..Breakpoint::default()
+                    };
+                };
+                let watchpoint_id = self.context.add_watchpoint_with_options(target, options);
+                let breakpoint_id = self.get_next_breakpoint_id();
+                self.data_breakpoints.push((watchpoint_id, breakpoint_id));
+                Breakpoint { id: Some(breakpoint_id), verified: true, ..Breakpoint::default() }
+            })
+            .collect();
+
+        self.server.respond(req.success(ResponseBody::SetDataBreakpoints(
+            SetDataBreakpointsResponse { breakpoints },
+        )))?;
+        Ok(())
+    }
+
+    fn handle_evaluate(&mut self, req: Request) -> Result<(), ServerError> {
+        let Command::Evaluate(ref args) = req.command else {
+            unreachable!("handle_evaluate called on a different request");
+        };
+        // Stack frame ids are indices into the call stack, starting from the outermost frame.
+        // Without a frame the expression is evaluated in the innermost one.
+        let Ok(frame_index) = args.frame_id.map(usize::try_from).transpose() else {
+            self.server.respond(req.error("Invalid stack frame id"))?;
+            return Ok(());
+        };
+        match self.context.evaluate(&args.expression, frame_index) {
+            Ok(result) => {
+                self.server.respond(req.success(ResponseBody::Evaluate(EvaluateResponse {
+                    result: result.to_string(),
+                    type_field: Some(result.type_name()),
+                    presentation_hint: None,
+                    variables_reference: 0,
+                    named_variables: None,
+                    indexed_variables: None,
+                    memory_reference: None,
+                })))?;
+            }
+            Err(error) => {
+                let message = error.to_string();
+                self.server.respond(req.error(&message))?;
+            }
+        }
+        Ok(())
+    }
+
     fn handle_scopes(&mut self, req: Request) -> Result<(), ServerError> {
         self.server.respond(req.success(ResponseBody::Scopes(ScopesResponse {
             scopes: vec![
@@ -547,6 +788,11 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
                     variables_reference: ScopeReferences::WitnessMap as i64,
                     ..Scope::default()
                 },
+                Scope {
+                    name: String::from("Brillig Memory"),
+                    variables_reference: ScopeReferences::BrilligMemory as i64,
+                    ..Scope::default()
+                },
             ],
         })))?;
         Ok(())
@@ -584,6 +830,23 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
             .collect()
     }
 
+    fn build_brillig_memory(&self) -> Vec<Variable> {
+        let Some(memory) = self.context.get_brillig_memory() else {
+            return vec![];
+        };
+        memory
+            .iter()
+            .enumerate()
+            // Zero field is the default value, we omit it as in the REPL
+            .filter(|(_, value)| !matches!(value, MemoryValue::Field(field) if field.is_zero()))
+            .map(|(address, value)| Variable {
+                name: address.to_string(),
+                value: value.to_string(),
+                ..Variable::default()
+            })
+            .collect()
+    }
+
     fn handle_variables(&mut self, req: Request) -> Result<(), ServerError> {
         let Command::Variables(ref args) = req.command else {
             unreachable!("handle_variables called on a different request");
@@ -592,6 +855,7 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
         let variables: Vec<_> = match scope {
             ScopeReferences::Locals => self.build_local_variables(),
             ScopeReferences::WitnessMap => self.build_witness_map(),
+            ScopeReferences::BrilligMemory => self.build_brillig_memory(),
             _ => {
                 eprintln!(
                     "handle_variables with an unknown variables_reference {}",
@@ -606,6 +870,26 @@ impl<'a, R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>> DapSession<
     }
 }
 
+/// Parses the data ids handed out in response to `dataBreakpointInfo` requests, which are one of
+/// `witness:<circuit id>:<witness index>`, `memory:<address>` or `variable:<frame index>:<name>`.
+fn parse_data_id(data_id: &str) -> Option<WatchTarget> {
+    let mut parts = data_id.splitn(3, ':');
+    match parts.next()? {
+        "witness" => {
+            let circuit_id = parts.next()?.parse().ok()?;
+            let witness = Witness(parts.next()?.parse().ok()?);
+            Some(WatchTarget::Witness { circuit_id, witness, value: None })
+        }
+        "memory" => Some(WatchTarget::BrilligMemory { address: parts.next()?.parse().ok()? }),
+        "variable" => {
+            let frame_index = parts.next()?.parse().ok()?;
+            let name = parts.next()?.to_string();
+            Some(WatchTarget::Variable { name, frame_index })
+        }
+        _ => None,
+    }
+}
+
 pub fn run_session<R: Read, W: Write, B: BlackBoxFunctionSolver<FieldElement>>(
     server: Server<R, W>,
     solver: &B,
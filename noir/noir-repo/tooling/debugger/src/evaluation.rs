@@ -0,0 +1,767 @@
+//! Evaluation of Noir expressions over the source variables in scope, such as `x + y[2]` or
+//! `point.coords.len()`, backing the REPL `eval` command and DAP `evaluate` requests.
+//!
+//! Expressions are parsed with the Noir parser and evaluated directly over the values tracked by
+//! the debug instrumentation. The comptime interpreter can't be reused for this, since it works on
+//! elaborated programs whereas at a pause point we only know the printable types of variables.
+//! Supported are literals, variables, struct member and tuple element accesses, indexing, unary and
+//! binary operators, casts to numeric types and `len()` on arrays, slices and strings.
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use acvm::{AcirField, FieldElement};
+use noirc_artifacts::debug::StackFrame;
+use noirc_frontend::ast::{
+    BinaryOpKind, Expression, ExpressionKind, Literal, Signedness, UnaryOp, UnresolvedTypeData,
+};
+use noirc_frontend::parser::parse_expression;
+use noirc_printable_type::{PrintableType, PrintableValue, PrintableValueDisplay};
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use thiserror::Error;
+
+/// Integer literals have no type of their own, so arithmetic on them is only
+/// limited to values of up to this many bits.
+const MAX_LITERAL_BITS: u64 = 128;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum ExpressionError {
+    #[error("Invalid expression `{expression}`: {message}")]
+    Parse { expression: String, message: String },
+
+    #[error("No source variables are available at this point")]
+    NoStackFrame,
+
+    #[error("There is no stack frame with id {0}")]
+    UnknownFrame(usize),
+
+    #[error("Variable `{0}` is not in scope")]
+    UnknownVariable(String),
+
+    #[error("`{expression}` has no member `{member}`")]
+    UnknownMember { expression: String, member: String },
+
+    #[error("Index {index} is out of bounds in `{expression}`")]
+    IndexOutOfBounds { expression: String, index: String },
+
+    #[error("Invalid operand types in `{expression}`: {types}")]
+    InvalidOperands { expression: String, types: String },
+
+    #[error("`{0}` does not evaluate to a boolean")]
+    NotABoolean(String),
+
+    #[error("`{0}` overflows its type")]
+    Overflow(String),
+
+    #[error("Division by zero in `{0}`")]
+    DivisionByZero(String),
+
+    #[error("`{0}` is not supported by the debugger")]
+    Unsupported(String),
+}
+
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct EvaluatedValue {
+    pub(super) value: PrintableValue<FieldElement>,
+    pub(super) typ: PrintableType,
+}
+
+impl EvaluatedValue {
+    /// Returns the type of the value as it would be written in Noir.
+    pub(super) fn type_name(&self) -> String {
+        type_name(&self.typ)
+    }
+}
+
+impl std::fmt::Display for EvaluatedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Integers are printed directly since the printable value display doesn't support
+        // 128-bit integers
+        if let Value::Integer(value, _) = Value::from_printable(&self.value, &self.typ) {
+            return write!(f, "{value}");
+        }
+        let display = PrintableValueDisplay::Plain(self.value.clone(), self.typ.clone());
+        // Fall back to the debug representation for values not matching their type
+        let mut output = String::new();
+        if write!(output, "{display}").is_err() {
+            output = format!("{:?}", self.value);
+        }
+        write!(f, "{output}")
+    }
+}
+
+/// An expression which is parsed once and then evaluated every time execution reaches some
+/// point, such as the condition of a breakpoint.
+#[derive(Debug, Clone)]
+pub(super) struct ParsedExpression {
+    source: String,
+    expression: Expression,
+}
+
+impl ParsedExpression {
+    pub(super) fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let source = source.trim().to_string();
+        let expression = parse_expression(&source).map_err(|errors| ExpressionError::Parse {
+            expression: source.clone(),
+            message: errors.first().map(ToString::to_string).unwrap_or_default(),
+        })?;
+        Ok(Self { source, expression })
+    }
+
+    /// Evaluates the expression over the variables of the given stack frame.
+    pub(super) fn evaluate(
+        &self,
+        frame: Option<&StackFrame<FieldElement>>,
+    ) -> Result<EvaluatedValue, ExpressionError> {
+        let (value, typ) = Evaluator { frame }.evaluate(&self.expression)?.into_printable();
+        Ok(EvaluatedValue { value, typ })
+    }
+
+    /// Evaluates the expression, which must be a boolean, over the variables of the given stack frame.
+    pub(super) fn evaluate_bool(
+        &self,
+        frame: Option<&StackFrame<FieldElement>>,
+    ) -> Result<bool, ExpressionError> {
+        match self.evaluate(frame)? {
+            EvaluatedValue { value: PrintableValue::Field(value), typ: PrintableType::Boolean } => {
+                Ok(value.is_one())
+            }
+            _ => Err(ExpressionError::NotABoolean(self.source.clone())),
+        }
+    }
+}
+
+impl std::fmt::Display for ParsedExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Parses and evaluates an expression over the variables of the given stack frame.
+pub(super) fn evaluate_expression(
+    source: &str,
+    frame: Option<&StackFrame<FieldElement>>,
+) -> Result<EvaluatedValue, ExpressionError> {
+    ParsedExpression::parse(source)?.evaluate(frame)
+}
+
+fn type_name(typ: &PrintableType) -> String {
+    match typ {
+        PrintableType::Field => "Field".to_string(),
+        PrintableType::Array { length, typ } => format!("[{}; {length}]", type_name(typ)),
+        PrintableType::Slice { typ } => format!("[{}]", type_name(typ)),
+        PrintableType::Tuple { types } => {
+            let types: Vec<_> = types.iter().map(type_name).collect();
+            format!("({})", types.join(", "))
+        }
+        PrintableType::SignedInteger { width } => format!("i{width}"),
+        PrintableType::UnsignedInteger { width } => format!("u{width}"),
+        PrintableType::Boolean => "bool".to_string(),
+        PrintableType::Struct { name, .. } => name.clone(),
+        PrintableType::String { length } => format!("str<{length}>"),
+        PrintableType::Function { .. } => "fn".to_string(),
+        PrintableType::MutableReference { typ } => format!("&mut {}", type_name(typ)),
+        PrintableType::Unit => "()".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IntegerType {
+    signed: bool,
+    /// Between 1 and 128 bits
+    width: u32,
+}
+
+impl IntegerType {
+    fn from_printable(typ: &PrintableType) -> Option<Self> {
+        let (signed, width) = match typ {
+            PrintableType::SignedInteger { width } => (true, *width),
+            PrintableType::UnsignedInteger { width } => (false, *width),
+            _ => return None,
+        };
+        (1..=128).contains(&width).then_some(IntegerType { signed, width })
+    }
+
+    /// The number of values of the type, `2^width`.
+    fn modulus(&self) -> BigInt {
+        BigInt::one() << self.width
+    }
+
+    fn min(&self) -> BigInt {
+        if self.signed {
+            -(BigInt::one() << (self.width - 1))
+        } else {
+            BigInt::zero()
+        }
+    }
+
+    fn max(&self) -> BigInt {
+        if self.signed {
+            (BigInt::one() << (self.width - 1)) - 1
+        } else {
+            self.modulus() - 1
+        }
+    }
+
+    fn contains(&self, value: &BigInt) -> bool {
+        self.min() <= *value && *value <= self.max()
+    }
+
+    /// Truncates a value to the width of the type, as casts and shifts do.
+    fn wrap(&self, value: &BigInt) -> BigInt {
+        let modulus = self.modulus();
+        let truncated = ((value % &modulus) + &modulus) % &modulus;
+        if self.signed && truncated > self.max() {
+            truncated - modulus
+        } else {
+            truncated
+        }
+    }
+
+    fn printable_type(&self) -> PrintableType {
+        if self.signed {
+            PrintableType::SignedInteger { width: self.width }
+        } else {
+            PrintableType::UnsignedInteger { width: self.width }
+        }
+    }
+}
+
+/// A value computed while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Field(FieldElement),
+    Integer(BigInt, IntegerType),
+    /// An integer literal, whose type is inferred from the values it is combined with
+    Literal(BigInt),
+    Bool(bool),
+    /// Any other value, such as an array, a tuple, a struct or a string
+    Other(PrintableValue<FieldElement>, PrintableType),
+}
+
+impl Value {
+    fn from_printable(value: &PrintableValue<FieldElement>, typ: &PrintableType) -> Self {
+        match (value, typ) {
+            (_, PrintableType::MutableReference { typ }) => Value::from_printable(value, typ),
+            (PrintableValue::Field(field), PrintableType::Field) => Value::Field(*field),
+            (PrintableValue::Field(field), PrintableType::Boolean) => Value::Bool(field.is_one()),
+            (PrintableValue::Field(field), _) => match IntegerType::from_printable(typ) {
+                // Negative values are stored in two's complement relative to the width of the type
+                Some(integer_type) => {
+                    Value::Integer(integer_type.wrap(&bigint_from_field(*field)), integer_type)
+                }
+                None => Value::Other(value.clone(), typ.clone()),
+            },
+            _ => Value::Other(value.clone(), typ.clone()),
+        }
+    }
+
+    fn into_printable(self) -> (PrintableValue<FieldElement>, PrintableType) {
+        match self {
+            Value::Field(field) => (PrintableValue::Field(field), PrintableType::Field),
+            Value::Integer(value, typ) => {
+                // Store negative values in two's complement, as the instrumentation does
+                let stored = if value.is_negative() { value + typ.modulus() } else { value };
+                (PrintableValue::Field(field_from_bigint(&stored)), typ.printable_type())
+            }
+            // As in Noir, integer literals default to fields
+            Value::Literal(value) => {
+                (PrintableValue::Field(field_from_bigint(&value)), PrintableType::Field)
+            }
+            Value::Bool(value) => {
+                (PrintableValue::Field(FieldElement::from(value)), PrintableType::Boolean)
+            }
+            Value::Other(value, typ) => (value, typ),
+        }
+    }
+
+    fn type_name(&self) -> String {
+        match self {
+            Value::Field(_) => "Field".to_string(),
+            Value::Integer(_, typ) => type_name(&typ.printable_type()),
+            Value::Literal(_) => "integer literal".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Other(_, typ) => type_name(typ),
+        }
+    }
+
+    /// Returns the value as an index into an array or a tuple.
+    fn as_index(&self) -> Option<usize> {
+        match self {
+            Value::Integer(value, _) | Value::Literal(value) => value.to_usize(),
+            Value::Field(field) if field.num_bits() <= 64 => usize::try_from(field.to_u128()).ok(),
+            _ => None,
+        }
+    }
+}
+
+fn field_from_bigint(value: &BigInt) -> FieldElement {
+    let (sign, bytes) = value.to_bytes_be();
+    let magnitude = FieldElement::from_be_bytes_reduce(&bytes);
+    if sign == Sign::Minus {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn bigint_from_field(field: FieldElement) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, &field.to_be_bytes())
+}
+
+/// Returns the result of a comparison operator, or `None` for any other operator.
+fn compare(operator: BinaryOpKind, ordering: Ordering) -> Option<bool> {
+    match operator {
+        BinaryOpKind::Equal => Some(ordering.is_eq()),
+        BinaryOpKind::NotEqual => Some(ordering.is_ne()),
+        BinaryOpKind::Less => Some(ordering.is_lt()),
+        BinaryOpKind::LessEqual => Some(ordering.is_le()),
+        BinaryOpKind::Greater => Some(ordering.is_gt()),
+        BinaryOpKind::GreaterEqual => Some(ordering.is_ge()),
+        _ => None,
+    }
+}
+
+struct Evaluator<'frame, 'a> {
+    frame: Option<&'frame StackFrame<'a, FieldElement>>,
+}
+
+impl Evaluator<'_, '_> {
+    fn evaluate(&self, expression: &Expression) -> Result<Value, ExpressionError> {
+        let unsupported = || ExpressionError::Unsupported(expression.to_string());
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => self.evaluate_literal(literal, expression),
+            ExpressionKind::Variable(path) => {
+                let name = path.as_ident().ok_or_else(unsupported)?.0.contents.as_str();
+                let frame = self.frame.ok_or(ExpressionError::NoStackFrame)?;
+                let (_, value, typ) = frame
+                    .variables
+                    .iter()
+                    .find(|(variable_name, ..)| *variable_name == name)
+                    .ok_or_else(|| ExpressionError::UnknownVariable(name.to_string()))?;
+                Ok(Value::from_printable(value, typ))
+            }
+            ExpressionKind::Parenthesized(inner) => self.evaluate(inner),
+            ExpressionKind::Tuple(elements) => {
+                let (values, types) = elements
+                    .iter()
+                    .map(|element| Ok(self.evaluate(element)?.into_printable()))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
+                    .unzip();
+                let value = PrintableValue::Vec { array_elements: values, is_slice: false };
+                Ok(Value::Other(value, PrintableType::Tuple { types }))
+            }
+            ExpressionKind::MemberAccess(access) => {
+                let lhs = self.evaluate(&access.lhs)?;
+                let member = access.rhs.0.contents.as_str();
+                let unknown_member = || ExpressionError::UnknownMember {
+                    expression: access.lhs.to_string(),
+                    member: member.to_string(),
+                };
+                let element = match &lhs {
+                    Value::Other(
+                        PrintableValue::Struct(values),
+                        PrintableType::Struct { fields, .. },
+                    ) => {
+                        let typ =
+                            fields.iter().find(|(name, _)| name == member).map(|(_, typ)| typ);
+                        values.get(member).zip(typ)
+                    }
+                    Value::Other(
+                        PrintableValue::Vec { array_elements, .. },
+                        PrintableType::Tuple { types },
+                    ) => member
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| array_elements.get(index).zip(types.get(index))),
+                    _ => None,
+                };
+                let (value, typ) = element.ok_or_else(unknown_member)?;
+                Ok(Value::from_printable(value, typ))
+            }
+            ExpressionKind::Index(index_expression) => {
+                let collection = self.evaluate(&index_expression.collection)?;
+                let index = self.evaluate(&index_expression.index)?;
+                let Value::Other(
+                    PrintableValue::Vec { array_elements, .. },
+                    PrintableType::Array { typ, .. } | PrintableType::Slice { typ },
+                ) = &collection
+                else {
+                    return Err(ExpressionError::InvalidOperands {
+                        expression: expression.to_string(),
+                        types: format!("{} cannot be indexed", collection.type_name()),
+                    });
+                };
+                let out_of_bounds = || ExpressionError::IndexOutOfBounds {
+                    expression: expression.to_string(),
+                    index: index_expression.index.to_string(),
+                };
+                let position = index.as_index().ok_or_else(out_of_bounds)?;
+                let element = array_elements.get(position).ok_or_else(out_of_bounds)?;
+                Ok(Value::from_printable(element, typ))
+            }
+            ExpressionKind::MethodCall(call) => {
+                if call.method_name.0.contents != "len" || !call.arguments.is_empty() {
+                    return Err(unsupported());
+                }
+                let length = match self.evaluate(&call.object)? {
+                    Value::Other(_, PrintableType::Array { length, .. })
+                    | Value::Other(_, PrintableType::String { length }) => BigInt::from(length),
+                    Value::Other(
+                        PrintableValue::Vec { array_elements, .. },
+                        PrintableType::Slice { .. },
+                    ) => BigInt::from(array_elements.len()),
+                    _ => return Err(unsupported()),
+                };
+                Ok(Value::Integer(length, IntegerType { signed: false, width: 32 }))
+            }
+            ExpressionKind::Prefix(prefix) => {
+                let operand = self.evaluate(&prefix.rhs)?;
+                self.evaluate_prefix(prefix.operator, operand, expression)
+            }
+            ExpressionKind::Infix(infix) => {
+                let lhs = self.evaluate(&infix.lhs)?;
+                let rhs = self.evaluate(&infix.rhs)?;
+                self.evaluate_infix(infix.operator.contents, lhs, rhs, expression)
+            }
+            ExpressionKind::Cast(cast) => {
+                let value = self.evaluate(&cast.lhs)?;
+                let mut target = &cast.r#type.typ;
+                while let UnresolvedTypeData::Parenthesized(inner) = target {
+                    target = &inner.typ;
+                }
+                self.evaluate_cast(value, target, expression)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn evaluate_literal(
+        &self,
+        literal: &Literal,
+        expression: &Expression,
+    ) -> Result<Value, ExpressionError> {
+        match literal {
+            Literal::Bool(value) => Ok(Value::Bool(*value)),
+            Literal::Integer(magnitude, negative) => {
+                if u64::from(magnitude.num_bits()) <= MAX_LITERAL_BITS {
+                    let value = BigInt::from(magnitude.to_u128());
+                    Ok(Value::Literal(if *negative { -value } else { value }))
+                } else {
+                    Ok(Value::Field(if *negative { -*magnitude } else { *magnitude }))
+                }
+            }
+            Literal::Str(string) | Literal::RawStr(string, _) => Ok(Value::Other(
+                PrintableValue::String(string.clone()),
+                PrintableType::String { length: string.len() as u32 },
+            )),
+            Literal::Unit => Ok(Value::Other(PrintableValue::Other, PrintableType::Unit)),
+            _ => Err(ExpressionError::Unsupported(expression.to_string())),
+        }
+    }
+
+    fn evaluate_prefix(
+        &self,
+        operator: UnaryOp,
+        operand: Value,
+        expression: &Expression,
+    ) -> Result<Value, ExpressionError> {
+        let overflow = || ExpressionError::Overflow(expression.to_string());
+        match (operator, operand) {
+            // References are tracked by the value they point to
+            (UnaryOp::Dereference { .. }, operand) => Ok(operand),
+            (UnaryOp::Minus, Value::Field(field)) => Ok(Value::Field(-field)),
+            (UnaryOp::Minus, Value::Literal(value)) => Ok(Value::Literal(-value)),
+            // Negating the minimum value of a signed type, or any non-zero unsigned value, overflows
+            (UnaryOp::Minus, Value::Integer(value, typ)) => {
+                let negated = -value;
+                if typ.contains(&negated) {
+                    Ok(Value::Integer(negated, typ))
+                } else {
+                    Err(overflow())
+                }
+            }
+            (UnaryOp::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+            // Bitwise negation in two's complement
+            (UnaryOp::Not, Value::Literal(value)) => Ok(Value::Literal(-value - 1)),
+            (UnaryOp::Not, Value::Integer(value, typ)) => {
+                Ok(Value::Integer(typ.wrap(&(-value - 1)), typ))
+            }
+            (_, operand) => Err(ExpressionError::InvalidOperands {
+                expression: expression.to_string(),
+                types: operand.type_name(),
+            }),
+        }
+    }
+
+    fn evaluate_infix(
+        &self,
+        operator: BinaryOpKind,
+        lhs: Value,
+        rhs: Value,
+        expression: &Expression,
+    ) -> Result<Value, ExpressionError> {
+        let invalid_operands = |lhs: &Value, rhs: &Value| ExpressionError::InvalidOperands {
+            expression: expression.to_string(),
+            types: format!("{} and {}", lhs.type_name(), rhs.type_name()),
+        };
+        let overflow = || ExpressionError::Overflow(expression.to_string());
+
+        // Integer literals take the type of the other operand
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Value::Literal(value), Value::Field(field)) => {
+                (Value::Field(field_from_bigint(&value)), Value::Field(field))
+            }
+            (Value::Field(field), Value::Literal(value)) => {
+                (Value::Field(field), Value::Field(field_from_bigint(&value)))
+            }
+            (Value::Literal(value), Value::Integer(other, typ)) if typ.contains(&value) => {
+                (Value::Integer(value, typ), Value::Integer(other, typ))
+            }
+            (Value::Integer(other, typ), Value::Literal(value)) if typ.contains(&value) => {
+                (Value::Integer(other, typ), Value::Integer(value, typ))
+            }
+            (lhs, rhs) => (lhs, rhs),
+        };
+
+        match (&lhs, &rhs) {
+            (Value::Field(a), Value::Field(b)) => {
+                if let Some(result) = compare(operator, a.cmp(b)) {
+                    return Ok(Value::Bool(result));
+                }
+                match operator {
+                    BinaryOpKind::Add => Ok(Value::Field(*a + *b)),
+                    BinaryOpKind::Subtract => Ok(Value::Field(*a - *b)),
+                    BinaryOpKind::Multiply => Ok(Value::Field(*a * *b)),
+                    BinaryOpKind::Divide if b.is_zero() => {
+                        Err(ExpressionError::DivisionByZero(expression.to_string()))
+                    }
+                    BinaryOpKind::Divide => Ok(Value::Field(*a / *b)),
+                    _ => Err(invalid_operands(&lhs, &rhs)),
+                }
+            }
+            (Value::Integer(a, typ), Value::Integer(b, other_typ)) if typ == other_typ => {
+                let result = self.evaluate_integer_infix(operator, a, b, Some(*typ), expression)?;
+                match result {
+                    Value::Integer(value, _) if !typ.contains(&value) => Err(overflow()),
+                    result => Ok(result),
+                }
+            }
+            (Value::Literal(a), Value::Literal(b)) => {
+                match self.evaluate_integer_infix(operator, a, b, None, expression)? {
+                    Value::Literal(value) if value.bits() > MAX_LITERAL_BITS => Err(overflow()),
+                    result => Ok(result),
+                }
+            }
+            (Value::Bool(a), Value::Bool(b)) => match operator {
+                BinaryOpKind::And => Ok(Value::Bool(*a & *b)),
+                BinaryOpKind::Or => Ok(Value::Bool(*a | *b)),
+                BinaryOpKind::Xor => Ok(Value::Bool(*a ^ *b)),
+                BinaryOpKind::Equal => Ok(Value::Bool(a == b)),
+                BinaryOpKind::NotEqual => Ok(Value::Bool(a != b)),
+                _ => Err(invalid_operands(&lhs, &rhs)),
+            },
+            (Value::Other(a, typ), Value::Other(b, other_typ)) if typ == other_typ => {
+                match operator {
+                    BinaryOpKind::Equal => Ok(Value::Bool(a == b)),
+                    BinaryOpKind::NotEqual => Ok(Value::Bool(a != b)),
+                    _ => Err(invalid_operands(&lhs, &rhs)),
+                }
+            }
+            _ => Err(invalid_operands(&lhs, &rhs)),
+        }
+    }
+
+    /// Applies a binary operator to two integers of the given type, or two integer literals if no
+    /// type is given. Overflows of the type are left to the caller to check.
+    fn evaluate_integer_infix(
+        &self,
+        operator: BinaryOpKind,
+        a: &BigInt,
+        b: &BigInt,
+        typ: Option<IntegerType>,
+        expression: &Expression,
+    ) -> Result<Value, ExpressionError> {
+        if let Some(result) = compare(operator, a.cmp(b)) {
+            return Ok(Value::Bool(result));
+        }
+        let overflow = || ExpressionError::Overflow(expression.to_string());
+        let division_by_zero = || ExpressionError::DivisionByZero(expression.to_string());
+        let width = typ.map_or(MAX_LITERAL_BITS as u32, |typ| typ.width);
+        // Shifting by the width of the type or more overflows
+        let shift = || b.to_u32().filter(|shift| *shift < width).ok_or_else(overflow);
+        let result = match operator {
+            BinaryOpKind::Add => a + b,
+            BinaryOpKind::Subtract => a - b,
+            BinaryOpKind::Multiply => a * b,
+            BinaryOpKind::Divide | BinaryOpKind::Modulo if b.is_zero() => {
+                return Err(division_by_zero())
+            }
+            BinaryOpKind::Divide => a / b,
+            BinaryOpKind::Modulo => a % b,
+            BinaryOpKind::And => a & b,
+            BinaryOpKind::Or => a | b,
+            BinaryOpKind::Xor => a ^ b,
+            // Bits shifted out of the type are dropped
+            BinaryOpKind::ShiftLeft => match typ {
+                Some(typ) => typ.wrap(&(a << shift()?)),
+                None => a << shift()?,
+            },
+            BinaryOpKind::ShiftRight => a >> shift()?,
+            _ => unreachable!("Comparisons are handled above"),
+        };
+        Ok(match typ {
+            Some(typ) => Value::Integer(result, typ),
+            None => Value::Literal(result),
+        })
+    }
+
+    fn evaluate_cast(
+        &self,
+        value: Value,
+        target: &UnresolvedTypeData,
+        expression: &Expression,
+    ) -> Result<Value, ExpressionError> {
+        let integer_value = match &value {
+            Value::Integer(value, _) | Value::Literal(value) => Some(value.clone()),
+            Value::Bool(value) => Some(BigInt::from(u8::from(*value))),
+            _ => None,
+        };
+        match (target, value, integer_value) {
+            (UnresolvedTypeData::FieldElement, Value::Field(field), _) => Ok(Value::Field(field)),
+            (UnresolvedTypeData::FieldElement, _, Some(integer_value)) => {
+                Ok(Value::Field(field_from_bigint(&integer_value)))
+            }
+            (UnresolvedTypeData::Integer(signedness, bit_size), value, integer_value) => {
+                let typ = IntegerType {
+                    signed: matches!(signedness, Signedness::Signed),
+                    width: u32::from(bit_size.bit_size()),
+                };
+                // Casting to a narrower type truncates the value
+                let truncated = match (value, integer_value) {
+                    (Value::Field(field), _) => typ.wrap(&bigint_from_field(field)),
+                    (_, Some(integer_value)) => typ.wrap(&integer_value),
+                    (value, None) => {
+                        return Err(ExpressionError::InvalidOperands {
+                            expression: expression.to_string(),
+                            types: value.type_name(),
+                        })
+                    }
+                };
+                Ok(Value::Integer(truncated, typ))
+            }
+            (UnresolvedTypeData::Bool, Value::Bool(value), _) => Ok(Value::Bool(value)),
+            _ => Err(ExpressionError::Unsupported(expression.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    fn field(value: u128) -> PrintableValue<FieldElement> {
+        PrintableValue::Field(FieldElement::from(value))
+    }
+
+    #[test]
+    fn evaluates_expressions_over_variables() {
+        let x = field(5);
+        let minus_two = field(0xfe);
+        let values = PrintableValue::Vec {
+            array_elements: vec![field(10), field(20), field(30)],
+            is_slice: false,
+        };
+        let point = PrintableValue::Struct(BTreeMap::from([
+            ("x".to_string(), field(1)),
+            ("y".to_string(), field(2)),
+        ]));
+        let pair =
+            PrintableValue::Vec { array_elements: vec![field(1), field(0)], is_slice: false };
+
+        let u32_type = PrintableType::UnsignedInteger { width: 32 };
+        let i8_type = PrintableType::SignedInteger { width: 8 };
+        let values_type = PrintableType::Array { length: 3, typ: Box::new(u32_type.clone()) };
+        let point_type = PrintableType::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                ("x".to_string(), PrintableType::Field),
+                ("y".to_string(), PrintableType::Field),
+            ],
+        };
+        let pair_type =
+            PrintableType::Tuple { types: vec![PrintableType::Field, PrintableType::Boolean] };
+        let frame = StackFrame {
+            function_name: "main",
+            function_params: vec!["x"],
+            variables: vec![
+                ("x", &x, &u32_type),
+                ("m", &minus_two, &i8_type),
+                ("values", &values, &values_type),
+                ("point", &point, &point_type),
+                ("pair", &pair, &pair_type),
+            ],
+        };
+        let evaluate =
+            |source: &str| evaluate_expression(source, Some(&frame)).map(|value| value.to_string());
+
+        assert_eq!(evaluate("x + values[2]"), Ok("35".to_string()));
+        assert_eq!(evaluate("values[x - 4] * 2"), Ok("40".to_string()));
+        assert_eq!(evaluate("m * 3"), Ok("-6".to_string()));
+        assert_eq!(evaluate("(m as u8) >> 4"), Ok("15".to_string()));
+        assert_eq!(evaluate("point.y - point.x"), Ok("0x01".to_string()));
+        assert_eq!(evaluate("pair.1 | (values.len() == 3)"), Ok("true".to_string()));
+        assert_eq!(evaluate("(x, pair.1)"), Ok("(5, false)".to_string()));
+
+        let value = evaluate_expression("values[0] as u8", Some(&frame)).unwrap();
+        assert_eq!(value.type_name(), "u8");
+
+        assert_eq!(evaluate("y"), Err(ExpressionError::UnknownVariable("y".to_string())));
+        assert!(matches!(evaluate("values[3]"), Err(ExpressionError::IndexOutOfBounds { .. })));
+        assert!(matches!(evaluate("point.z"), Err(ExpressionError::UnknownMember { .. })));
+        assert!(matches!(evaluate("x * 1000000000"), Err(ExpressionError::Overflow(_))));
+        assert!(matches!(evaluate("x / 0"), Err(ExpressionError::DivisionByZero(_))));
+        assert!(matches!(evaluate("x + m"), Err(ExpressionError::InvalidOperands { .. })));
+        assert!(matches!(evaluate("x +"), Err(ExpressionError::Parse { .. })));
+    }
+
+    #[test]
+    fn evaluates_128_bit_integers() {
+        let max = PrintableValue::Field(FieldElement::from(u128::MAX));
+        let min = PrintableValue::Field(FieldElement::from(1u128 << 127));
+        let minus_128 = field(0x80);
+
+        let u128_type = PrintableType::UnsignedInteger { width: 128 };
+        let i128_type = PrintableType::SignedInteger { width: 128 };
+        let i8_type = PrintableType::SignedInteger { width: 8 };
+        let frame = StackFrame {
+            function_name: "main",
+            function_params: vec![],
+            variables: vec![
+                ("max", &max, &u128_type),
+                ("min", &min, &i128_type),
+                ("minus_128", &minus_128, &i8_type),
+            ],
+        };
+        let evaluate =
+            |source: &str| evaluate_expression(source, Some(&frame)).map(|value| value.to_string());
+
+        assert_eq!(evaluate("max - 1"), Ok((u128::MAX - 1).to_string()));
+        assert_eq!(evaluate("max >> 127"), Ok("1".to_string()));
+        assert_eq!(evaluate("max as u8"), Ok("255".to_string()));
+        assert_eq!(evaluate("1 as u128 << 127"), Ok((1u128 << 127).to_string()));
+        assert_eq!(evaluate("min < 0"), Ok("true".to_string()));
+        assert_eq!(evaluate(&format!("min + 1 == -{}", i128::MAX)), Ok("true".to_string()));
+        assert_eq!(evaluate("min"), Ok(i128::MIN.to_string()));
+
+        assert!(matches!(evaluate("max + 1"), Err(ExpressionError::Overflow(_))));
+        assert!(matches!(evaluate("max << 128"), Err(ExpressionError::Overflow(_))));
+        assert!(matches!(evaluate("-min"), Err(ExpressionError::Overflow(_))));
+        assert!(matches!(evaluate("-minus_128"), Err(ExpressionError::Overflow(_))));
+        assert!(matches!(evaluate("-max"), Err(ExpressionError::Overflow(_))));
+    }
+}
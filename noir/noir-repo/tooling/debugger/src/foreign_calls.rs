@@ -42,6 +42,13 @@ impl DebugForeignCall {
 pub trait DebugForeignCallExecutor: ForeignCallExecutor<FieldElement> {
     fn get_variables(&self) -> Vec<StackFrame<FieldElement>>;
     fn current_stack_frame(&self) -> Option<StackFrame<FieldElement>>;
+    /// Returns a copy of the state of the debug variables, so that it can be restored when going
+    /// back in the execution. Executors which don't support it show the variables as they were
+    /// before going back.
+    fn debug_vars_snapshot(&self) -> Option<DebugVars<FieldElement>> {
+        None
+    }
+    fn restore_debug_vars(&mut self, _debug_vars: DebugVars<FieldElement>) {}
 }
 
 pub struct DefaultDebugForeignCallExecutor<'a> {
@@ -81,6 +88,14 @@ impl DebugForeignCallExecutor for DefaultDebugForeignCallExecutor<'_> {
     fn current_stack_frame(&self) -> Option<StackFrame<FieldElement>> {
         self.debug_vars.current_stack_frame()
     }
+
+    fn debug_vars_snapshot(&self) -> Option<DebugVars<FieldElement>> {
+        Some(self.debug_vars.clone())
+    }
+
+    fn restore_debug_vars(&mut self, debug_vars: DebugVars<FieldElement>) {
+        self.debug_vars = debug_vars;
+    }
 }
 
 fn debug_var_id(value: &FieldElement) -> DebugVarId {
@@ -1,9 +1,13 @@
+mod breakpoints;
 mod context;
 mod dap;
 pub mod errors;
+mod evaluation;
 mod foreign_calls;
+mod recording;
 mod repl;
 mod source_code_printer;
+mod watchpoints;
 
 use std::io::{Read, Write};
 
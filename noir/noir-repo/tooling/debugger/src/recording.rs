@@ -0,0 +1,207 @@
+//! Recording of the execution of a program, which allows the debugger to go back in time.
+//!
+//! Rather than keeping the whole execution state after every step, a snapshot of it is taken
+//! every [SNAPSHOT_INTERVAL] steps. Going back to a given step restores the closest snapshot taken
+//! before it and replays the steps in between. The results of foreign calls are recorded, so that
+//! replaying them doesn't execute them again, which would print output twice or advance the
+//! state of oracles.
+//!
+//! At most [MAX_SNAPSHOTS] snapshots are kept. Once the limit is exceeded the oldest snapshot is
+//! evicted along with everything recorded before the next one, so only the latest part of long
+//! executions can be gone back to.
+use std::collections::{BTreeMap, VecDeque};
+
+use acvm::acir::brillig::ForeignCallResult;
+use acvm::acir::native_types::WitnessStack;
+use acvm::pwg::{BrilligSolver, ACVM};
+use acvm::{BlackBoxFunctionSolver, FieldElement};
+use noirc_artifacts::debug::DebugVars;
+use thiserror::Error;
+
+use crate::context::{DebugLocation, ExecutionFrame};
+
+/// Number of steps between two consecutive snapshots, which bounds the number of steps replayed
+/// when going back.
+pub(super) const SNAPSHOT_INTERVAL: usize = 64;
+
+/// Number of snapshots kept, which bounds the memory used by the recording.
+pub(super) const MAX_SNAPSHOTS: usize = 256;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum RecordingError {
+    #[error("Step {step} was not reached yet, the last recorded step is {last_step}")]
+    NotReached { step: usize, last_step: usize },
+
+    #[error("Step {step} is no longer recorded, the earliest recorded step is {first_step}")]
+    Evicted { step: usize, first_step: usize },
+}
+
+/// The state of the execution before a given step, from which execution can be resumed.
+pub(super) struct ExecutionSnapshot<'a, B: BlackBoxFunctionSolver<FieldElement>> {
+    pub(super) acvm: ACVM<'a, FieldElement, B>,
+    pub(super) current_circuit_id: u32,
+    pub(super) brillig_solver: Option<BrilligSolver<'a, FieldElement, B>>,
+    pub(super) witness_stack: WitnessStack<FieldElement>,
+    pub(super) acvm_stack: Vec<ExecutionFrame<'a, B>>,
+    /// State of the debug variables, if the foreign call executor supports snapshotting it
+    pub(super) debug_vars: Option<DebugVars<FieldElement>>,
+    /// Number of recorded foreign call results consumed before the step
+    pub(super) foreign_call_position: usize,
+}
+
+pub(super) struct ExecutionRecording<'a, B: BlackBoxFunctionSolver<FieldElement>> {
+    /// Number of steps executed since the start to reach the current state
+    current_step: usize,
+    /// Earliest step which can still be gone back to, which is the step of the oldest snapshot
+    first_step: usize,
+    /// Debug location after each recorded step from `first_step` onwards, or `None` once
+    /// execution finished
+    locations: VecDeque<Option<DebugLocation>>,
+    snapshots: BTreeMap<usize, ExecutionSnapshot<'a, B>>,
+    snapshot_interval: usize,
+    max_snapshots: usize,
+    /// Results of the foreign calls executed so far, in order, excluding debug instrumentation
+    /// calls which only update the state of the debug variables and the results evicted along
+    /// with the oldest snapshots
+    foreign_call_results: VecDeque<ForeignCallResult<FieldElement>>,
+    /// Number of foreign call results evicted from the start of `foreign_call_results`
+    evicted_foreign_calls: usize,
+    foreign_call_position: usize,
+}
+
+impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ExecutionRecording<'a, B> {
+    pub(super) fn new(initial_location: Option<DebugLocation>) -> Self {
+        Self {
+            current_step: 0,
+            first_step: 0,
+            locations: VecDeque::from([initial_location]),
+            snapshots: BTreeMap::new(),
+            snapshot_interval: SNAPSHOT_INTERVAL,
+            max_snapshots: MAX_SNAPSHOTS,
+            foreign_call_results: VecDeque::new(),
+            evicted_foreign_calls: 0,
+            foreign_call_position: 0,
+        }
+    }
+
+    /// Overrides the default snapshot interval and limit, so that eviction can be tested on short
+    /// executions.
+    #[cfg(test)]
+    pub(super) fn set_snapshot_limits(&mut self, snapshot_interval: usize, max_snapshots: usize) {
+        self.snapshot_interval = snapshot_interval;
+        self.max_snapshots = max_snapshots;
+    }
+
+    pub(super) fn current_step(&self) -> usize {
+        self.current_step
+    }
+
+    /// The earliest step recorded, which is the start of the execution unless snapshots were
+    /// evicted.
+    pub(super) fn first_step(&self) -> usize {
+        self.first_step
+    }
+
+    /// The furthest step recorded, which is ahead of the current one after going back.
+    pub(super) fn last_step(&self) -> usize {
+        self.first_step + self.locations.len() - 1
+    }
+
+    /// Checks that execution can be moved to the given step.
+    pub(super) fn check_step(&self, step: usize) -> Result<(), RecordingError> {
+        if step < self.first_step {
+            Err(RecordingError::Evicted { step, first_step: self.first_step })
+        } else if step > self.last_step() {
+            Err(RecordingError::NotReached { step, last_step: self.last_step() })
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(super) fn location_at(&self, step: usize) -> Option<DebugLocation> {
+        let index = step.checked_sub(self.first_step)?;
+        self.locations.get(index).copied().flatten()
+    }
+
+    pub(super) fn foreign_call_position(&self) -> usize {
+        self.foreign_call_position
+    }
+
+    /// Whether a snapshot should be taken before executing the current step.
+    pub(super) fn needs_snapshot(&self) -> bool {
+        self.current_step % self.snapshot_interval == 0
+            && !self.snapshots.contains_key(&self.current_step)
+    }
+
+    /// Records a snapshot of the state before executing the current step, replacing any other
+    /// snapshot for it, and evicts the oldest snapshots if there are too many.
+    pub(super) fn add_snapshot(&mut self, snapshot: ExecutionSnapshot<'a, B>) {
+        self.snapshots.insert(self.current_step, snapshot);
+        while self.snapshots.len() > self.max_snapshots {
+            self.snapshots.pop_first();
+        }
+
+        // Steps before the oldest snapshot can no longer be replayed
+        let Some((&first_step, first_snapshot)) = self.snapshots.first_key_value() else {
+            return;
+        };
+        self.locations.drain(..first_step - self.first_step);
+        self.first_step = first_step;
+        let foreign_call_position = first_snapshot.foreign_call_position;
+        self.foreign_call_results.drain(..foreign_call_position - self.evicted_foreign_calls);
+        self.evicted_foreign_calls = foreign_call_position;
+    }
+
+    /// Records that a step was executed, reaching the given location.
+    pub(super) fn record_step(&mut self, location: Option<DebugLocation>) {
+        self.current_step += 1;
+        let index = self.current_step - self.first_step;
+        if index < self.locations.len() {
+            // Execution is deterministic, so we're just going through the recording again
+            self.locations[index] = location;
+        } else {
+            self.locations.push_back(location);
+        }
+    }
+
+    /// Returns the recorded result for the next foreign call, if it was already executed.
+    pub(super) fn replay_foreign_call(&mut self) -> Option<ForeignCallResult<FieldElement>> {
+        let index = self.foreign_call_position - self.evicted_foreign_calls;
+        let result = self.foreign_call_results.get(index)?.clone();
+        self.foreign_call_position += 1;
+        Some(result)
+    }
+
+    pub(super) fn record_foreign_call(&mut self, result: ForeignCallResult<FieldElement>) {
+        self.foreign_call_results.push_back(result);
+        self.foreign_call_position += 1;
+    }
+
+    /// Returns the latest snapshot taken at or before the given step, along with its step.
+    pub(super) fn closest_snapshot(
+        &self,
+        step: usize,
+    ) -> Option<(usize, &ExecutionSnapshot<'a, B>)> {
+        self.snapshots.range(..=step).next_back().map(|(step, snapshot)| (*step, snapshot))
+    }
+
+    /// Moves the current position of the recording back to the given snapshot, which must have
+    /// been restored.
+    pub(super) fn rewind_to_snapshot(&mut self, step: usize) {
+        let snapshot = &self.snapshots[&step];
+        self.foreign_call_position = snapshot.foreign_call_position;
+        self.current_step = step;
+    }
+
+    /// Discards the recording after the current step, which can no longer be replayed once the
+    /// state of the execution was modified by the user.
+    ///
+    /// Note that foreign calls which were discarded were already executed, so oracles keeping
+    /// state will not be rolled back.
+    pub(super) fn truncate(&mut self) {
+        self.locations.truncate(self.current_step - self.first_step + 1);
+        let current_step = self.current_step;
+        self.snapshots.retain(|step, _| *step < current_step);
+        self.foreign_call_results.truncate(self.foreign_call_position - self.evicted_foreign_calls);
+    }
+}
@@ -1,4 +1,7 @@
+use crate::breakpoints::{BreakpointOptions, Condition, HitCondition, LogMessage};
 use crate::context::{DebugCommandResult, DebugContext, DebugLocation};
+use crate::recording::RecordingError;
+use crate::watchpoints::{WatchTarget, WatchpointId};
 
 use acvm::acir::brillig::BitSize;
 use acvm::acir::circuit::brillig::{BrilligBytecode, BrilligFunctionId};
@@ -241,9 +244,164 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ReplDebugger<'a, B> {
         }
     }
 
+    /// Updates the options of the breakpoint at the given location, which must already be set.
+    fn update_breakpoint_options(
+        &mut self,
+        location: DebugLocation,
+        update: impl FnOnce(&mut BreakpointOptions),
+    ) {
+        let Some(options) = self.context.get_breakpoint_options_mut(&location) else {
+            println!("Breakpoint at {location} not set");
+            return;
+        };
+        update(options);
+        if options.is_logpoint() {
+            println!("Logpoint at {location}: {options}");
+        } else {
+            println!("Breakpoint at {location}: {options}");
+        }
+    }
+
+    fn set_breakpoint_condition(&mut self, location: DebugLocation, condition: String) {
+        let condition = if condition.trim().is_empty() {
+            None
+        } else {
+            match Condition::parse(&condition) {
+                Ok(condition) => Some(condition),
+                Err(error) => {
+                    println!("{error}");
+                    return;
+                }
+            }
+        };
+        self.update_breakpoint_options(location, |options| options.condition = condition);
+    }
+
+    fn set_breakpoint_hit_condition(&mut self, location: DebugLocation, hit_condition: String) {
+        let hit_condition = if hit_condition.trim().is_empty() {
+            None
+        } else {
+            match HitCondition::parse(&hit_condition) {
+                Ok(hit_condition) => Some(hit_condition),
+                Err(error) => {
+                    println!("{error}");
+                    return;
+                }
+            }
+        };
+        self.update_breakpoint_options(location, |options| options.hit_condition = hit_condition);
+    }
+
+    fn add_logpoint_at(&mut self, location: DebugLocation, message: String) {
+        let log_message = match LogMessage::parse(&message) {
+            Ok(log_message) => log_message,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        if !self.context.is_valid_debug_location(&location) {
+            println!("Invalid location {location}");
+        } else if self.context.is_breakpoint_set(&location) {
+            self.update_breakpoint_options(location, |options| {
+                options.log_message = Some(log_message);
+            });
+        } else {
+            let options =
+                BreakpointOptions { log_message: Some(log_message), ..Default::default() };
+            self.context.add_breakpoint_with_options(location, options);
+            println!("Added logpoint at {location}");
+        }
+    }
+
+    fn show_breakpoints(&self) {
+        let mut breakpoints: Vec<_> = self.context.iterate_breakpoints().collect();
+        if breakpoints.is_empty() {
+            println!("No breakpoints set");
+            return;
+        }
+        breakpoints.sort_by_key(|(location, _)| **location);
+        for (location, breakpoint) in breakpoints {
+            let kind = if breakpoint.options.is_logpoint() { "Logpoint" } else { "Breakpoint" };
+            let hits = breakpoint.hit_count();
+            let options = breakpoint.options.to_string();
+            if options.is_empty() {
+                println!("{kind} at {location} (hit {hits} times)");
+            } else {
+                println!("{kind} at {location}: {options} (hit {hits} times)");
+            }
+        }
+    }
+
+    fn add_watchpoint(&mut self, target: WatchTarget) {
+        let id = self.context.add_watchpoint(target.clone());
+        println!("Added watchpoint #{id} on {target}");
+    }
+
+    fn watch_variable(&mut self, name: String) {
+        let Some(frame_index) = self.context.get_variables().len().checked_sub(1) else {
+            println!("No source variables available at this point");
+            return;
+        };
+        self.add_watchpoint(WatchTarget::Variable { name, frame_index });
+    }
+
+    fn watch_witness(&mut self, index: u32, value: Option<String>) {
+        let value = match value {
+            Some(value) => match FieldElement::try_from_str(&value) {
+                Some(value) => Some(value),
+                None => {
+                    println!("Invalid witness value: {value}");
+                    return;
+                }
+            },
+            None => None,
+        };
+        let Some(location) = self.context.get_current_debug_location() else {
+            println!("Finished execution");
+            return;
+        };
+        let witness = Witness::from(index);
+        self.add_watchpoint(WatchTarget::Witness {
+            circuit_id: location.circuit_id,
+            witness,
+            value,
+        });
+    }
+
+    fn watch_brillig_memory(&mut self, address: usize) {
+        self.add_watchpoint(WatchTarget::BrilligMemory { address });
+    }
+
+    fn delete_watchpoint(&mut self, id: WatchpointId) {
+        if self.context.delete_watchpoint(id) {
+            println!("Watchpoint #{id} deleted");
+        } else {
+            println!("Watchpoint #{id} not set");
+        }
+    }
+
+    fn show_watchpoints(&self) {
+        let mut watchpoints = self.context.iterate_watchpoints().peekable();
+        if watchpoints.peek().is_none() {
+            println!("No watchpoints set");
+            return;
+        }
+        for (id, watchpoint) in watchpoints {
+            let options = watchpoint.breakpoint.options.to_string();
+            let options = if options.is_empty() { options } else { format!(" [{options}]") };
+            match watchpoint.last_value() {
+                Some(value) => println!("#{id}: {}{options} = {value}", watchpoint.target),
+                None => println!("#{id}: {}{options} (no value)", watchpoint.target),
+            }
+        }
+    }
+
     fn validate_in_progress(&self) -> bool {
         match self.last_result {
-            DebugCommandResult::Ok | DebugCommandResult::BreakpointReached(..) => true,
+            DebugCommandResult::Ok
+            | DebugCommandResult::BreakpointReached(..)
+            | DebugCommandResult::WatchpointTriggered(..) => true,
             DebugCommandResult::Done => {
                 println!("Execution finished");
                 false
@@ -257,10 +415,18 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ReplDebugger<'a, B> {
     }
 
     fn handle_debug_command_result(&mut self, result: DebugCommandResult) {
+        for message in self.context.take_log_messages() {
+            println!("{message}");
+        }
         match &result {
             DebugCommandResult::BreakpointReached(location) => {
                 println!("Stopped at breakpoint in opcode {}", location);
             }
+            DebugCommandResult::WatchpointTriggered(hits) => {
+                for hit in hits {
+                    println!("Stopped at {hit}");
+                }
+            }
             DebugCommandResult::Error(error) => {
                 println!("ERROR: {}", error);
             }
@@ -313,8 +479,70 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ReplDebugger<'a, B> {
         }
     }
 
+    fn validate_can_step_back(&self) -> bool {
+        if self.context.can_step_back() {
+            true
+        } else {
+            println!("Already at the earliest recorded step of the execution");
+            false
+        }
+    }
+
+    fn step_back_opcode(&mut self) {
+        if self.validate_can_step_back() {
+            let result = self.context.step_back_opcode();
+            self.handle_recording_result(result);
+        }
+    }
+
+    fn next_back(&mut self) {
+        if self.validate_can_step_back() {
+            let result = self.context.next_back();
+            self.handle_recording_result(result);
+        }
+    }
+
+    fn reverse_cont(&mut self) {
+        if self.validate_can_step_back() {
+            println!("(Continuing execution backwards...)");
+            let result = self.context.reverse_cont();
+            self.handle_recording_result(result);
+        }
+    }
+
+    fn handle_recording_result(&mut self, result: Result<DebugCommandResult, RecordingError>) {
+        match result {
+            Ok(result) => self.handle_debug_command_result(result),
+            Err(error) => println!("{error}"),
+        }
+    }
+
+    fn goto_opcode(&mut self, location: DebugLocation) {
+        if !self.context.is_valid_debug_location(&location) {
+            println!("Invalid location {location}");
+            return;
+        }
+        // Opcodes which were not reached yet require executing forward
+        if self.context.find_past_step_at(&location).is_none() && !self.validate_in_progress() {
+            return;
+        }
+        let result = self.context.goto_location(&location);
+        self.handle_recording_result(result);
+    }
+
     fn restart_session(&mut self) {
-        let breakpoints: Vec<DebugLocation> = self.context.iterate_breakpoints().copied().collect();
+        let breakpoints: Vec<(DebugLocation, BreakpointOptions)> = self
+            .context
+            .iterate_breakpoints()
+            .map(|(location, breakpoint)| (*location, breakpoint.options.clone()))
+            .collect();
+        let watchpoints: Vec<(WatchTarget, BreakpointOptions)> = self
+            .context
+            .iterate_watchpoints()
+            .map(|(_, watchpoint)| {
+                (watchpoint.target.clone(), watchpoint.breakpoint.options.clone())
+            })
+            .collect();
         let foreign_call_executor = Box::new(DefaultDebugForeignCallExecutor::from_artifact(
             PrintOutput::Stdout,
             self.debug_artifact,
@@ -327,8 +555,11 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ReplDebugger<'a, B> {
             foreign_call_executor,
             self.unconstrained_functions,
         );
-        for debug_location in breakpoints {
-            self.context.add_breakpoint(debug_location);
+        for (debug_location, options) in breakpoints {
+            self.context.add_breakpoint_with_options(debug_location, options);
+        }
+        for (target, options) in watchpoints {
+            self.context.add_watchpoint_with_options(target, options);
         }
         self.last_result = DebugCommandResult::Ok;
         println!("Restarted debugging session.");
@@ -414,6 +645,13 @@ impl<'a, B: BlackBoxFunctionSolver<FieldElement>> ReplDebugger<'a, B> {
         }
     }
 
+    pub fn eval(&self, expression: &str) {
+        match self.context.evaluate(expression, None) {
+            Ok(result) => println!("{result}: {}", result.type_name()),
+            Err(error) => println!("{error}"),
+        }
+    }
+
     fn is_solved(&self) -> bool {
         self.context.is_solved()
     }
@@ -504,6 +742,46 @@ pub fn run<B: BlackBoxFunctionSolver<FieldElement>>(
                 }
             },
         )
+        .add(
+            "stepback",
+            command! {
+                "step back to the previous opcode",
+                () => || {
+                    ref_context.borrow_mut().step_back_opcode();
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "back",
+            command! {
+                "step back to the start of the previous source location",
+                () => || {
+                    ref_context.borrow_mut().next_back();
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "rcontinue",
+            command! {
+                "continue execution backwards until the previous breakpoint or the start of the program",
+                () => || {
+                    ref_context.borrow_mut().reverse_cont();
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "goto",
+            command! {
+                "go back to the last time an opcode location was reached, or run until reaching it",
+                (LOCATION:DebugLocation) => |location| {
+                    ref_context.borrow_mut().goto_opcode(location);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
         .add(
             "restart",
             command! {
@@ -544,6 +822,106 @@ pub fn run<B: BlackBoxFunctionSolver<FieldElement>>(
                 }
             },
         )
+        .add(
+            "condition",
+            command! {
+                "only stop at a breakpoint when a condition over source variables holds (\"\" to clear)",
+                (LOCATION:DebugLocation, CONDITION:String) => |location, condition| {
+                    ref_context.borrow_mut().set_breakpoint_condition(location, condition);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "hits",
+            command! {
+                "only stop at a breakpoint when its hit count satisfies a condition such as `3` or `%3`",
+                (LOCATION:DebugLocation, HIT_CONDITION:String) => |location, hit_condition| {
+                    ref_context.borrow_mut().set_breakpoint_hit_condition(location, hit_condition);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "logpoint",
+            command! {
+                "print a message instead of stopping at an opcode location, with `{var}` replaced by its value",
+                (LOCATION:DebugLocation, MESSAGE:String) => |location, message| {
+                    ref_context.borrow_mut().add_logpoint_at(location, message);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "breakpoints",
+            command! {
+                "list breakpoints and logpoints along with their options",
+                () => || {
+                    ref_context.borrow().show_breakpoints();
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "watch",
+            command! {
+                "stop when a source variable of the current function changes",
+                (NAME:String) => |name| {
+                    ref_context.borrow_mut().watch_variable(name);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "watchwitness",
+            command! {
+                "stop when a witness of the current circuit gets solved or changes",
+                (index: u32) => |index| {
+                    ref_context.borrow_mut().watch_witness(index, None);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "watchwitness",
+            command! {
+                "stop when a witness of the current circuit is assigned the given value",
+                (index: u32, value: String) => |index, value| {
+                    ref_context.borrow_mut().watch_witness(index, Some(value));
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "watchmemory",
+            command! {
+                "stop when a Brillig memory cell changes",
+                (index: usize) => |index| {
+                    ref_context.borrow_mut().watch_brillig_memory(index);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "watchpoints",
+            command! {
+                "list watchpoints along with the last value observed",
+                () => || {
+                    ref_context.borrow().show_watchpoints();
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
+        .add(
+            "unwatch",
+            command! {
+                "delete a watchpoint",
+                (id: usize) => |id| {
+                    ref_context.borrow_mut().delete_watchpoint(id);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
         .add(
             "witness",
             command! {
@@ -614,6 +992,16 @@ pub fn run<B: BlackBoxFunctionSolver<FieldElement>>(
                 }
             },
         )
+        .add(
+            "eval",
+            command! {
+                "evaluate an expression over the variables in scope, quoting it if it has spaces",
+                (EXPRESSION:String) => |expression| {
+                    ref_context.borrow().eval(&expression);
+                    Ok(CommandStatus::Done)
+                }
+            },
+        )
         .build()
         .expect("Failed to initialize debugger repl");
 
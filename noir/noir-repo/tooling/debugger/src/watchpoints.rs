@@ -0,0 +1,150 @@
+//! Watchpoints, also known as data breakpoints, stop execution when a watched value changes rather
+//! than when execution reaches a given location.
+//!
+//! Changes are detected by observing every watched value after each step and comparing it with its
+//! previous observation, so writing a value equal to the current one doesn't trigger a watchpoint.
+//! A change only stops execution when the watchpoint's options, a condition and a hit condition
+//! just like the ones of breakpoints, hold.
+use std::fmt::Write;
+
+use acvm::acir::native_types::Witness;
+use acvm::brillig_vm::MemoryValue;
+use acvm::FieldElement;
+use noirc_printable_type::{PrintableType, PrintableValue, PrintableValueDisplay};
+
+use crate::breakpoints::{Breakpoint, BreakpointOptions};
+
+pub(super) type WatchpointId = usize;
+
+/// A value which can be watched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum WatchTarget {
+    /// A witness of the given circuit, which triggers when it gets solved or overwritten.
+    /// If a value is given, the witness must be assigned that value to trigger.
+    Witness { circuit_id: u32, witness: Witness, value: Option<FieldElement> },
+    /// A cell of the memory of the Brillig VM currently executing
+    BrilligMemory { address: usize },
+    /// A source variable of the stack frame at the given index, counting from the outermost one
+    Variable { name: String, frame_index: usize },
+}
+
+impl std::fmt::Display for WatchTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchTarget::Witness { circuit_id, witness, value: None } => {
+                write!(f, "witness {circuit_id}:_{}", witness.witness_index())
+            }
+            WatchTarget::Witness { circuit_id, witness, value: Some(value) } => {
+                write!(f, "witness {circuit_id}:_{} == {value}", witness.witness_index())
+            }
+            WatchTarget::BrilligMemory { address } => write!(f, "memory[{address}]"),
+            WatchTarget::Variable { name, frame_index } => {
+                write!(f, "variable {name} (frame #{frame_index})")
+            }
+        }
+    }
+}
+
+/// An observation of a watched value.
+#[derive(Debug, Clone)]
+pub(super) enum WatchedValue {
+    Field(FieldElement),
+    Memory(MemoryValue<FieldElement>),
+    Variable(PrintableValue<FieldElement>, PrintableType),
+}
+
+impl PartialEq for WatchedValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (WatchedValue::Field(lhs), WatchedValue::Field(rhs)) => lhs == rhs,
+            (WatchedValue::Memory(lhs), WatchedValue::Memory(rhs)) => lhs == rhs,
+            // Types are only kept for display purposes
+            (WatchedValue::Variable(lhs, _), WatchedValue::Variable(rhs, _)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for WatchedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchedValue::Field(value) => write!(f, "{value}"),
+            WatchedValue::Memory(value) => write!(f, "{value}"),
+            WatchedValue::Variable(value, typ) => {
+                let display = PrintableValueDisplay::Plain(value.clone(), typ.clone());
+                // Fall back to the debug representation for values not matching their type
+                let mut output = String::new();
+                if write!(output, "{display}").is_err() {
+                    output = format!("{value:?}");
+                }
+                write!(f, "{output}")
+            }
+        }
+    }
+}
+
+/// Reports a watchpoint which was triggered by the last step.
+#[derive(Debug, Clone)]
+pub(super) struct WatchpointHit {
+    pub(super) id: WatchpointId,
+    pub(super) target: WatchTarget,
+    pub(super) old_value: Option<WatchedValue>,
+    pub(super) new_value: WatchedValue,
+}
+
+impl std::fmt::Display for WatchpointHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Watchpoint #{} on {}: ", self.id, self.target)?;
+        match &self.old_value {
+            Some(old_value) => write!(f, "{old_value} -> {}", self.new_value),
+            None => write!(f, "{}", self.new_value),
+        }
+    }
+}
+
+/// A watched value along with its last observation.
+#[derive(Debug, Clone)]
+pub(super) struct Watchpoint {
+    pub(super) target: WatchTarget,
+    /// Decides whether a change of the watched value stops execution
+    pub(super) breakpoint: Breakpoint,
+    last_value: Option<WatchedValue>,
+}
+
+impl Watchpoint {
+    pub(super) fn new(
+        target: WatchTarget,
+        options: BreakpointOptions,
+        current_value: Option<WatchedValue>,
+    ) -> Self {
+        Self { target, breakpoint: Breakpoint::new(options), last_value: current_value }
+    }
+
+    pub(super) fn last_value(&self) -> Option<&WatchedValue> {
+        self.last_value.as_ref()
+    }
+
+    /// Records a new observation of the watched value, reporting whether it changed in a way
+    /// which may trigger the watchpoint, subject to its options.
+    ///
+    /// A value disappearing, for example because a Brillig call finished or a variable went out
+    /// of scope, is recorded but doesn't trigger the watchpoint.
+    pub(super) fn observe(
+        &mut self,
+        id: WatchpointId,
+        value: Option<WatchedValue>,
+    ) -> Option<WatchpointHit> {
+        if value == self.last_value {
+            return None;
+        }
+        let old_value = std::mem::replace(&mut self.last_value, value.clone());
+        let new_value = value?;
+
+        if let WatchTarget::Witness { value: Some(expected), .. } = &self.target {
+            if new_value != WatchedValue::Field(*expected) {
+                return None;
+            }
+        }
+        Some(WatchpointHit { id, target: self.target.clone(), old_value, new_value })
+    }
+}
@@ -0,0 +1,341 @@
+//! This module defines the coverage feedback used to guide the fuzzer towards inputs which
+//! exercise new behavior of the program being fuzzed.
+//!
+//! Brillig coverage is collected from the profiling samples emitted by the ACVM. These contain the
+//! program counter of every Brillig opcode executed, from which we record each control flow edge
+//! taken along with a rough count of how many times it was taken.
+//!
+//! ACIR has no control flow, so ACIR coverage is collected from the witnesses of a successful run
+//! instead. For each solved ACIR opcode we record the outcome of solving it: whether its predicate
+//! was active, and which products of an `AssertZero` were zero. Branches of constrained code are
+//! flattened into constraints multiplied by their condition, so the latter tells which branches
+//! were taken.
+use std::collections::{HashMap, HashSet};
+
+use acvm::{
+    acir::{
+        circuit::{brillig::BrilligFunctionId, Opcode, OpcodeLocation, Program},
+        native_types::{WitnessMap, WitnessStack},
+    },
+    pwg::{get_value, ProfilingSample},
+    AcirField,
+};
+
+/// The number of products of an `AssertZero` opcode whose outcome is recorded.
+const MAX_RECORDED_PRODUCTS: usize = 64;
+
+/// A control flow edge taken within a single Brillig call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Edge {
+    /// The index of the ACIR opcode which called into Brillig
+    acir_index: usize,
+    brillig_function_id: Option<BrilligFunctionId>,
+    /// The Brillig opcode executed before `to`, or `None` if `to` was the first opcode executed
+    from: Option<usize>,
+    to: usize,
+}
+
+/// An ACIR opcode solved by an execution, along with the outcome of solving it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AcirOpcodeOutcome {
+    /// The index of the ACIR function in the program
+    function_index: u32,
+    opcode_index: usize,
+    /// Whether the predicate of the opcode was active, or `None` if it has no predicate
+    predicate_active: Option<bool>,
+    /// For `AssertZero` opcodes, a bit set for each of the first products which evaluated to zero
+    zero_products: u64,
+}
+
+/// The set of Brillig control flow edges and ACIR opcode outcomes exercised by one or more
+/// executions of a program.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    /// Each edge is paired with the bucket of the number of times it was taken,
+    /// so that iterating a loop a different number of times also counts as new coverage.
+    edges: HashSet<(Edge, u8)>,
+    acir_opcodes: HashSet<AcirOpcodeOutcome>,
+}
+
+impl Coverage {
+    /// Builds the coverage of a single execution from the profiling samples it produced.
+    pub fn from_profiling_samples(samples: &[ProfilingSample]) -> Self {
+        let mut hit_counts = HashMap::<Edge, usize>::new();
+
+        let mut previous: Option<(usize, usize)> = None;
+        for sample in samples {
+            let Some(OpcodeLocation::Brillig { acir_index, brillig_index }) =
+                sample.call_stack.last()
+            else {
+                previous = None;
+                continue;
+            };
+
+            let from = match previous {
+                Some((previous_acir_index, previous_brillig_index))
+                    if previous_acir_index == *acir_index =>
+                {
+                    Some(previous_brillig_index)
+                }
+                _ => None,
+            };
+            let edge = Edge {
+                acir_index: *acir_index,
+                brillig_function_id: sample.brillig_function_id,
+                from,
+                to: *brillig_index,
+            };
+            *hit_counts.entry(edge).or_default() += 1;
+
+            previous = Some((*acir_index, *brillig_index));
+        }
+
+        let edges = hit_counts.into_iter().map(|(edge, count)| (edge, hit_count_bucket(count)));
+        Coverage { edges: edges.collect(), acir_opcodes: HashSet::new() }
+    }
+
+    /// Builds the ACIR coverage of a single successful execution from the witnesses of each
+    /// ACIR function call it made.
+    pub fn from_witness_stack<F: AcirField>(
+        program: &Program<F>,
+        witness_stack: &WitnessStack<F>,
+    ) -> Self {
+        let mut acir_opcodes = HashSet::new();
+
+        let mut witness_stack = witness_stack.clone();
+        while let Some(call) = witness_stack.pop() {
+            let Some(function) = program.functions.get(call.index as usize) else {
+                continue;
+            };
+            for (opcode_index, opcode) in function.opcodes.iter().enumerate() {
+                acir_opcodes.insert(AcirOpcodeOutcome {
+                    function_index: call.index,
+                    opcode_index,
+                    predicate_active: predicate_active(opcode, &call.witness),
+                    zero_products: zero_products(opcode, &call.witness),
+                });
+            }
+        }
+
+        Coverage { edges: HashSet::new(), acir_opcodes }
+    }
+
+    /// The number of distinct edges and hit count buckets, and ACIR opcode outcomes covered.
+    pub fn len(&self) -> usize {
+        self.edges.len() + self.acir_opcodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty() && self.acir_opcodes.is_empty()
+    }
+
+    /// Adds the coverage of `other` to this coverage, returning whether it covered anything new.
+    pub fn merge(&mut self, other: Coverage) -> bool {
+        let previous_len = self.len();
+        self.edges.extend(other.edges);
+        self.acir_opcodes.extend(other.acir_opcodes);
+        self.len() > previous_len
+    }
+}
+
+/// Returns whether the predicate of an opcode was active, or `None` if it has no predicate.
+fn predicate_active<F: AcirField>(opcode: &Opcode<F>, witness: &WitnessMap<F>) -> Option<bool> {
+    let predicate = match opcode {
+        Opcode::BrilligCall { predicate, .. }
+        | Opcode::Call { predicate, .. }
+        | Opcode::MemoryOp { predicate, .. } => predicate.as_ref()?,
+        _ => return None,
+    };
+    get_value(predicate, witness).ok().map(|value| !value.is_zero())
+}
+
+/// Returns a bit set for each of the first products of an `AssertZero` opcode which evaluated
+/// to zero. Products whose witnesses weren't solved are left unset.
+fn zero_products<F: AcirField>(opcode: &Opcode<F>, witness: &WitnessMap<F>) -> u64 {
+    let Opcode::AssertZero(expression) = opcode else {
+        return 0;
+    };
+
+    let mut zero_products = 0;
+    let products = expression.mul_terms.iter().take(MAX_RECORDED_PRODUCTS);
+    for (bit, (coefficient, lhs, rhs)) in products.enumerate() {
+        let (Some(lhs), Some(rhs)) = (witness.get(lhs), witness.get(rhs)) else {
+            continue;
+        };
+        if (*coefficient * *lhs * *rhs).is_zero() {
+            zero_products |= 1 << bit;
+        }
+    }
+    zero_products
+}
+
+/// Groups hit counts into logarithmic buckets, following AFL, so that small changes in the
+/// number of iterations of a hot loop are not each treated as new coverage.
+fn hit_count_bucket(count: usize) -> u8 {
+    match count {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4..=7 => 4,
+        8..=15 => 5,
+        16..=31 => 6,
+        32..=127 => 7,
+        _ => 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use acvm::{
+        acir::{
+            circuit::{brillig::BrilligFunctionId, Circuit, Opcode, OpcodeLocation, Program},
+            native_types::{Expression, Witness, WitnessMap, WitnessStack},
+        },
+        pwg::ProfilingSample,
+        AcirField, FieldElement,
+    };
+
+    use super::{hit_count_bucket, Coverage};
+
+    fn brillig_samples(acir_index: usize, brillig_indices: &[usize]) -> Vec<ProfilingSample> {
+        brillig_indices
+            .iter()
+            .map(|brillig_index| ProfilingSample {
+                call_stack: vec![OpcodeLocation::Brillig {
+                    acir_index,
+                    brillig_index: *brillig_index,
+                }],
+                brillig_function_id: Some(BrilligFunctionId(0)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_each_edge_once() {
+        let coverage = Coverage::from_profiling_samples(&brillig_samples(0, &[0, 1, 2]));
+        // The entry into opcode 0 and the edges 0 -> 1 and 1 -> 2
+        assert_eq!(coverage.len(), 3);
+    }
+
+    #[test]
+    fn acir_opcodes_contribute_no_coverage() {
+        let samples = vec![ProfilingSample {
+            call_stack: vec![OpcodeLocation::Acir(0)],
+            brillig_function_id: None,
+        }];
+        assert!(Coverage::from_profiling_samples(&samples).is_empty());
+    }
+
+    #[test]
+    fn merging_reports_new_edges() {
+        let mut coverage = Coverage::default();
+        assert!(coverage.merge(Coverage::from_profiling_samples(&brillig_samples(0, &[0, 1]))));
+        assert!(!coverage.merge(Coverage::from_profiling_samples(&brillig_samples(0, &[0, 1]))));
+        assert!(coverage.merge(Coverage::from_profiling_samples(&brillig_samples(0, &[0, 2]))));
+
+        // The same opcodes called from a different ACIR opcode are different edges
+        assert!(coverage.merge(Coverage::from_profiling_samples(&brillig_samples(1, &[0, 1]))));
+    }
+
+    #[test]
+    fn loop_iterations_in_a_new_bucket_are_new_coverage() {
+        let mut coverage = Coverage::from_profiling_samples(&brillig_samples(0, &[0, 1, 0, 1]));
+
+        // A third iteration moves the loop's edges into the next bucket
+        let three_iterations = brillig_samples(0, &[0, 1, 0, 1, 0, 1]);
+        assert!(coverage.merge(Coverage::from_profiling_samples(&three_iterations)));
+
+        // Five and six iterations land in the same buckets
+        let five_iterations = brillig_samples(0, &[0, 1, 0, 1, 0, 1, 0, 1, 0, 1]);
+        let six_iterations = brillig_samples(0, &[0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1]);
+        assert!(coverage.merge(Coverage::from_profiling_samples(&five_iterations)));
+        assert!(!coverage.merge(Coverage::from_profiling_samples(&six_iterations)));
+    }
+
+    /// A program with a single ACIR function made of the given opcodes
+    fn acir_program(opcodes: Vec<Opcode<FieldElement>>) -> Program<FieldElement> {
+        let main = Circuit { opcodes, ..Default::default() };
+        Program { functions: vec![main], unconstrained_functions: Vec::new() }
+    }
+
+    fn witness_stack(values: &[u128]) -> WitnessStack<FieldElement> {
+        let values = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (Witness(index as u32), FieldElement::from(*value)));
+        WitnessMap::from(BTreeMap::from_iter(values)).into()
+    }
+
+    #[test]
+    fn constrained_branches_are_new_coverage() {
+        // `if c { assert(x == 5) }` once flattened, with `c` as witness 0 and `x` as witness 1
+        let (c, x) = (Witness(0), Witness(1));
+        let condition_is_boolean = Expression {
+            mul_terms: vec![(FieldElement::one(), c, c)],
+            linear_combinations: vec![(-FieldElement::one(), c)],
+            q_c: FieldElement::zero(),
+        };
+        let assertion_if_condition = Expression {
+            mul_terms: vec![(FieldElement::one(), c, x)],
+            linear_combinations: vec![(-FieldElement::from(5_u128), c)],
+            q_c: FieldElement::zero(),
+        };
+        let program = acir_program(vec![
+            Opcode::AssertZero(condition_is_boolean),
+            Opcode::AssertZero(assertion_if_condition),
+        ]);
+
+        let mut coverage = Coverage::from_witness_stack(&program, &witness_stack(&[0, 3]));
+        assert_eq!(coverage.len(), 2);
+
+        // Another input skipping the branch covers nothing new
+        let skipped = Coverage::from_witness_stack(&program, &witness_stack(&[0, 7]));
+        assert!(!coverage.merge(skipped));
+
+        // Taking the branch does
+        let taken = Coverage::from_witness_stack(&program, &witness_stack(&[1, 5]));
+        assert!(coverage.merge(taken));
+        assert_eq!(coverage.len(), 4);
+    }
+
+    #[test]
+    fn inactive_predicates_are_new_coverage() {
+        let program = acir_program(vec![Opcode::BrilligCall {
+            id: BrilligFunctionId(0),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            predicate: Some(Witness(0).into()),
+        }]);
+
+        let mut coverage = Coverage::from_witness_stack(&program, &witness_stack(&[1]));
+        assert!(!coverage.merge(Coverage::from_witness_stack(&program, &witness_stack(&[2]))));
+        assert!(coverage.merge(Coverage::from_witness_stack(&program, &witness_stack(&[0]))));
+    }
+
+    #[test]
+    fn acir_coverage_is_recorded_for_each_function_called() {
+        let function = Circuit {
+            opcodes: vec![Opcode::AssertZero(Expression::default())],
+            ..Default::default()
+        };
+        let program = Program {
+            functions: vec![function.clone(), function],
+            unconstrained_functions: Vec::new(),
+        };
+
+        let mut stack = witness_stack(&[]);
+        stack.push(1, WitnessMap::default());
+        assert_eq!(Coverage::from_witness_stack(&program, &stack).len(), 2);
+    }
+
+    #[test]
+    fn hit_count_buckets_are_monotonic() {
+        let buckets: Vec<u8> = (0..300).map(hit_count_bucket).collect();
+        assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(buckets[0], 0);
+        assert_eq!(buckets[299], 8);
+    }
+}
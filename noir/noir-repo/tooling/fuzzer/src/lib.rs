@@ -12,12 +12,20 @@ use acvm::{
 };
 use dictionary::build_dictionary_from_program;
 use noirc_abi::InputMap;
-use proptest::test_runner::{TestCaseError, TestError, TestRunner};
+use proptest::{
+    strategy::{Strategy, ValueTree},
+    test_runner::{TestCaseError, TestError, TestRunner},
+};
+use rand::Rng;
 
+mod coverage;
 mod dictionary;
+mod mutation;
+mod shrink;
 mod strategies;
 mod types;
 
+pub use coverage::Coverage;
 use types::{CaseOutcome, CounterExampleOutcome, FuzzOutcome, FuzzTestResult};
 
 use noirc_artifacts::program::ProgramArtifact;
@@ -36,6 +44,9 @@ pub struct FuzzedExecutor<E> {
 
     /// The fuzzer
     runner: TestRunner,
+
+    /// Inputs which are run before any generated ones, such as previously found counterexamples
+    seed_inputs: Vec<InputMap>,
 }
 
 impl<
@@ -47,11 +58,24 @@ impl<
 {
     /// Instantiates a fuzzed executor given a testrunner
     pub fn new(program: ProgramArtifact, executor: E, runner: TestRunner) -> Self {
-        Self { program, executor, runner }
+        Self { program, executor, runner, seed_inputs: Vec::new() }
+    }
+
+    /// Sets inputs to be run before any generated ones. Seeds which fail are reported
+    /// as counterexamples as-is, so they should already be minimal.
+    pub fn with_seed_inputs(mut self, seed_inputs: Vec<InputMap>) -> Self {
+        self.seed_inputs = seed_inputs;
+        self
     }
 
     /// Fuzzes the provided program.
     pub fn fuzz(&self) -> FuzzTestResult {
+        for input_map in &self.seed_inputs {
+            if let Ok(FuzzOutcome::CounterExample(outcome)) = self.single_fuzz(input_map.clone()) {
+                return FuzzTestResult::failure(outcome.exit_reason, outcome.counterexample);
+            }
+        }
+
         let dictionary = build_dictionary_from_program(&self.program.bytecode);
         let strategy = strategies::arb_input_map(&self.program.abi, dictionary);
 
@@ -69,18 +93,16 @@ impl<
             });
 
         match run_result {
-            Ok(()) => FuzzTestResult { success: true, reason: None, counterexample: None },
+            Ok(()) => FuzzTestResult::success(Vec::new()),
 
             Err(TestError::Abort(reason)) => FuzzTestResult {
                 success: false,
                 reason: Some(reason.to_string()),
                 counterexample: None,
+                corpus: Vec::new(),
             },
             Err(TestError::Fail(reason, counterexample)) => {
-                let reason = reason.to_string();
-                let reason = if reason.is_empty() { None } else { Some(reason) };
-
-                FuzzTestResult { success: false, reason, counterexample: Some(counterexample) }
+                FuzzTestResult::failure(reason.to_string(), counterexample)
             }
         }
     }
@@ -102,3 +124,137 @@ impl<
         }
     }
 }
+
+/// The number of randomly generated inputs the corpus is seeded with before mutation starts.
+const INITIAL_CORPUS_SIZE: usize = 8;
+
+/// The percentage of runs which use a freshly generated input rather than mutating one from the corpus.
+const FRESH_INPUT_PERCENTAGE: u32 = 20;
+
+/// An executor for Noir programs which uses coverage feedback to guide fuzzing.
+///
+/// Rather than sampling each input independently, this executor keeps a corpus of the inputs
+/// which reached new coverage and derives most new inputs by mutating an entry of that corpus.
+/// This lets it make progress step by step towards code behind several nested conditions,
+/// which random sampling is unlikely to ever reach.
+///
+/// The number of runs and the random seed are taken from the configuration of the provided
+/// [`TestRunner`]. Counterexamples are minimized greedily rather than with proptest's shrinking.
+pub struct CoverageGuidedFuzzedExecutor<E> {
+    /// The program to be fuzzed
+    program: ProgramArtifact,
+
+    /// A function which executes the program with a given set of inputs,
+    /// returning the coverage it reached
+    executor: E,
+
+    /// The fuzzer
+    runner: TestRunner,
+
+    /// Inputs which are run before any generated ones and start off the corpus
+    seed_inputs: Vec<InputMap>,
+}
+
+impl<E: Fn(&Program<FieldElement>, WitnessMap<FieldElement>) -> Result<Coverage, String>>
+    CoverageGuidedFuzzedExecutor<E>
+{
+    /// Instantiates a coverage guided executor given a testrunner
+    pub fn new(program: ProgramArtifact, executor: E, runner: TestRunner) -> Self {
+        Self { program, executor, runner, seed_inputs: Vec::new() }
+    }
+
+    /// Sets inputs to be run before any generated ones, such as the corpus and
+    /// counterexamples saved by previous runs.
+    pub fn with_seed_inputs(mut self, seed_inputs: Vec<InputMap>) -> Self {
+        self.seed_inputs = seed_inputs;
+        self
+    }
+
+    /// Fuzzes the provided program.
+    pub fn fuzz(&self) -> FuzzTestResult {
+        let dictionary = build_dictionary_from_program(&self.program.bytecode);
+        let mutation_dictionary: Vec<FieldElement> = dictionary.iter().copied().collect();
+        let strategy = strategies::arb_input_map(&self.program.abi, dictionary);
+
+        let mut runner = self.runner.clone();
+        let runs = runner.config().cases as usize;
+
+        let mut corpus: Vec<InputMap> = Vec::new();
+        let mut total_coverage = Coverage::default();
+
+        for input_map in &self.seed_inputs {
+            match self.execute(input_map) {
+                Ok(coverage) => {
+                    total_coverage.merge(coverage);
+                    corpus.push(input_map.clone());
+                }
+                Err(reason) => return self.counterexample(input_map.clone(), reason),
+            }
+        }
+
+        // The inputs which reached new coverage during this run
+        let mut new_corpus_entries = Vec::new();
+
+        for run in 0..runs {
+            let use_fresh_input = run < INITIAL_CORPUS_SIZE
+                || corpus.is_empty()
+                || runner.rng().gen_range(0..100) < FRESH_INPUT_PERCENTAGE;
+
+            let input_map = if use_fresh_input {
+                match strategy.new_tree(&mut runner) {
+                    Ok(tree) => tree.current(),
+                    Err(reason) => {
+                        return FuzzTestResult {
+                            success: false,
+                            reason: Some(reason.to_string()),
+                            counterexample: None,
+                            corpus: new_corpus_entries,
+                        }
+                    }
+                }
+            } else {
+                let parent = &corpus[runner.rng().gen_range(0..corpus.len())];
+                mutation::mutate_input_map(
+                    &self.program.abi,
+                    parent,
+                    &mutation_dictionary,
+                    runner.rng(),
+                )
+            };
+
+            match self.execute(&input_map) {
+                Ok(coverage) => {
+                    // The initial inputs are always kept so that the corpus isn't empty
+                    // before any input reached new coverage.
+                    let is_new_coverage = total_coverage.merge(coverage);
+                    if is_new_coverage {
+                        new_corpus_entries.push(input_map.clone());
+                    }
+                    if is_new_coverage || run < INITIAL_CORPUS_SIZE {
+                        corpus.push(input_map);
+                    }
+                }
+                Err(reason) => {
+                    let mut result = self.counterexample(input_map, reason);
+                    result.corpus = new_corpus_entries;
+                    return result;
+                }
+            }
+        }
+
+        FuzzTestResult::success(new_corpus_entries)
+    }
+
+    fn execute(&self, input_map: &InputMap) -> Result<Coverage, String> {
+        let initial_witness = self.program.abi.encode(input_map, None).unwrap();
+        (self.executor)(&self.program.bytecode, initial_witness)
+    }
+
+    /// Minimizes a failing input and reports it as a counterexample.
+    fn counterexample(&self, input_map: InputMap, reason: String) -> FuzzTestResult {
+        let (counterexample, reason) = shrink::shrink_input_map(input_map, reason, |input_map| {
+            self.execute(input_map).map(|_| ())
+        });
+        FuzzTestResult::failure(reason, counterexample)
+    }
+}
@@ -0,0 +1,234 @@
+//! This module defines how inputs from the fuzzer's corpus are mutated into new inputs.
+//!
+//! Mutations are small, type-aware edits of a single value, such as nudging an integer, flipping
+//! one of its bits or swapping it for a value from the program's dictionary. Every mutated value is
+//! kept within the range of its ABI type so that the input can always be encoded.
+use acvm::{AcirField, FieldElement};
+use noirc_abi::{input_parser::InputValue, Abi, AbiType, InputMap, Sign};
+use rand::{Rng, RngCore};
+
+/// The maximum number of values which are mutated when deriving a new input from an existing one.
+const MAX_MUTATIONS_PER_INPUT: usize = 3;
+
+/// Derives a new input from `input_map` by mutating a few of its values.
+pub(super) fn mutate_input_map(
+    abi: &Abi,
+    input_map: &InputMap,
+    dictionary: &[FieldElement],
+    rng: &mut impl RngCore,
+) -> InputMap {
+    let mut input_map = input_map.clone();
+    if abi.parameters.is_empty() {
+        return input_map;
+    }
+
+    let mutations = rng.gen_range(1..=MAX_MUTATIONS_PER_INPUT);
+    for _ in 0..mutations {
+        let parameter = &abi.parameters[rng.gen_range(0..abi.parameters.len())];
+        if let Some(value) = input_map.get_mut(&parameter.name) {
+            mutate_value(&parameter.typ, value, dictionary, rng);
+        }
+    }
+    input_map
+}
+
+fn mutate_value(
+    typ: &AbiType,
+    value: &mut InputValue,
+    dictionary: &[FieldElement],
+    rng: &mut impl RngCore,
+) {
+    match (typ, value) {
+        (AbiType::Field, InputValue::Field(field)) => {
+            *field = mutate_field(*field, dictionary, rng)
+        }
+        (AbiType::Integer { sign, width }, InputValue::Field(field)) => {
+            *field = mutate_integer(*field, *sign, *width, dictionary, rng);
+        }
+        (AbiType::Boolean, InputValue::Field(field)) => {
+            *field = if field.is_zero() { FieldElement::one() } else { FieldElement::zero() };
+        }
+        (AbiType::String { .. }, InputValue::String(string)) => mutate_string(string, rng),
+        (AbiType::Array { typ, .. }, InputValue::Vec(elements)) => {
+            if !elements.is_empty() {
+                let index = rng.gen_range(0..elements.len());
+                mutate_value(typ, &mut elements[index], dictionary, rng);
+            }
+        }
+        (AbiType::Tuple { fields }, InputValue::Vec(elements)) => {
+            if !elements.is_empty() {
+                let index = rng.gen_range(0..elements.len());
+                mutate_value(&fields[index], &mut elements[index], dictionary, rng);
+            }
+        }
+        (AbiType::Struct { fields, .. }, InputValue::Struct(values)) => {
+            if !fields.is_empty() {
+                let (name, typ) = &fields[rng.gen_range(0..fields.len())];
+                if let Some(value) = values.get_mut(name) {
+                    mutate_value(typ, value, dictionary, rng);
+                }
+            }
+        }
+        // The value doesn't match its type, which can't happen for values produced by the fuzzer.
+        _ => (),
+    }
+}
+
+fn mutate_field(
+    field: FieldElement,
+    dictionary: &[FieldElement],
+    rng: &mut impl RngCore,
+) -> FieldElement {
+    match rng.gen_range(0..4) {
+        0 => field + FieldElement::from(rng.gen_range(1..=16u128)),
+        1 => field - FieldElement::from(rng.gen_range(1..=16u128)),
+        2 if !dictionary.is_empty() => dictionary[rng.gen_range(0..dictionary.len())],
+        _ => {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            FieldElement::from_be_bytes_reduce(&bytes)
+        }
+    }
+}
+
+/// Mutates an integer of the given type, represented as the field element the ABI encodes it as.
+/// Negative signed integers are stored in two's complement, so all arithmetic wraps modulo `2^width`.
+fn mutate_integer(
+    field: FieldElement,
+    sign: Sign,
+    width: u32,
+    dictionary: &[FieldElement],
+    rng: &mut impl RngCore,
+) -> FieldElement {
+    let mask = if width >= 128 { u128::MAX } else { (1u128 << width) - 1 };
+    let value = field.to_u128();
+
+    let mutated = match rng.gen_range(0..5) {
+        0 => value.wrapping_add(rng.gen_range(1..=16)),
+        1 => value.wrapping_sub(rng.gen_range(1..=16)),
+        2 if width > 0 => value ^ (1u128 << rng.gen_range(0..width.min(128))),
+        3 => {
+            let fitting: Vec<_> =
+                dictionary.iter().filter(|value| value.num_bits() <= width).collect();
+            if fitting.is_empty() {
+                rng.gen()
+            } else {
+                fitting[rng.gen_range(0..fitting.len())].to_u128()
+            }
+        }
+        _ => match (sign, rng.gen_range(0..3)) {
+            (_, 0) => 0,
+            // The largest value of the type
+            (Sign::Unsigned, _) => mask,
+            (Sign::Signed, 1) => mask >> 1,
+            // The smallest value of the type
+            (Sign::Signed, _) => (mask >> 1) + 1,
+        },
+    };
+
+    FieldElement::from(mutated & mask)
+}
+
+fn mutate_string(string: &mut String, rng: &mut impl RngCore) {
+    if string.is_empty() {
+        return;
+    }
+    // Strings only allow ASCII characters so each character is a single byte.
+    let mut bytes = std::mem::take(string).into_bytes();
+    let index = rng.gen_range(0..bytes.len());
+    bytes[index] = rng.gen_range(0..128u8);
+    *string = String::from_utf8(bytes).expect("ASCII bytes should always be valid UTF-8");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use acvm::{AcirField, FieldElement};
+    use noirc_abi::{
+        input_parser::InputValue, Abi, AbiParameter, AbiType, AbiVisibility, InputMap, Sign,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{mutate_input_map, mutate_integer, mutate_value};
+
+    const ITERATIONS: usize = 1000;
+
+    fn abi(parameters: Vec<(&str, AbiType)>) -> Abi {
+        let parameters = parameters
+            .into_iter()
+            .map(|(name, typ)| AbiParameter {
+                name: name.to_string(),
+                typ,
+                visibility: AbiVisibility::Private,
+            })
+            .collect();
+        Abi { parameters, return_type: None, error_types: BTreeMap::new() }
+    }
+
+    #[test]
+    fn mutated_integers_stay_within_their_type() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for (sign, width) in [(Sign::Unsigned, 1), (Sign::Unsigned, 8), (Sign::Signed, 8)] {
+            let mut value = FieldElement::zero();
+            for _ in 0..ITERATIONS {
+                value = mutate_integer(value, sign, width, &[], &mut rng);
+                assert!(value.num_bits() <= width, "{value} does not fit in {width} bits");
+            }
+        }
+    }
+
+    #[test]
+    fn mutation_uses_the_dictionary() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let dictionary = [FieldElement::from(42u128), FieldElement::from(1000u128)];
+        let mutated: Vec<_> = (0..ITERATIONS)
+            .map(|_| mutate_integer(FieldElement::zero(), Sign::Unsigned, 8, &dictionary, &mut rng))
+            .collect();
+        assert!(mutated.contains(&FieldElement::from(42u128)));
+        // 1000 doesn't fit in a u8 so it's never used
+        assert!(!mutated.contains(&FieldElement::from(1000u128)));
+    }
+
+    #[test]
+    fn mutates_booleans_by_flipping_them() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut value = InputValue::Field(FieldElement::zero());
+        mutate_value(&AbiType::Boolean, &mut value, &[], &mut rng);
+        assert_eq!(value, InputValue::Field(FieldElement::one()));
+        mutate_value(&AbiType::Boolean, &mut value, &[], &mut rng);
+        assert_eq!(value, InputValue::Field(FieldElement::zero()));
+    }
+
+    #[test]
+    fn mutated_inputs_can_be_encoded() {
+        let abi = abi(vec![
+            ("x", AbiType::Integer { sign: Sign::Signed, width: 16 }),
+            (
+                "xs",
+                AbiType::Array {
+                    length: 2,
+                    typ: Box::new(AbiType::Integer { sign: Sign::Unsigned, width: 32 }),
+                },
+            ),
+            ("s", AbiType::String { length: 3 }),
+            ("b", AbiType::Boolean),
+        ]);
+        let mut input_map = InputMap::from([
+            ("x".to_string(), InputValue::Field(FieldElement::zero())),
+            ("xs".to_string(), InputValue::Vec(vec![InputValue::Field(FieldElement::zero()); 2])),
+            ("s".to_string(), InputValue::String("abc".to_string())),
+            ("b".to_string(), InputValue::Field(FieldElement::zero())),
+        ]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let original = input_map.clone();
+        let mut changed = false;
+        for _ in 0..ITERATIONS {
+            input_map = mutate_input_map(&abi, &input_map, &[], &mut rng);
+            abi.encode(&input_map, None).expect("mutated input should be valid for its ABI");
+            changed |= input_map != original;
+        }
+        assert!(changed);
+    }
+}
@@ -0,0 +1,190 @@
+//! This module defines how counterexamples found by coverage guided fuzzing are minimized.
+//!
+//! Inputs derived by mutation have no proptest value tree to shrink along, so instead we greedily
+//! replace individual numeric values with smaller ones for as long as the program keeps failing.
+use acvm::{AcirField, FieldElement};
+use noirc_abi::{input_parser::InputValue, InputMap};
+
+/// The maximum number of executions spent minimizing a single counterexample.
+const MAX_SHRINK_ITERATIONS: usize = 512;
+
+/// Minimizes a failing input, returning the smallest failing input found along with its failure reason.
+///
+/// `execute` runs the program with the given input, returning the failure reason if it fails.
+pub(super) fn shrink_input_map(
+    input_map: InputMap,
+    reason: String,
+    mut execute: impl FnMut(&InputMap) -> Result<(), String>,
+) -> (InputMap, String) {
+    let mut current = input_map;
+    let mut reason = reason;
+    let mut iterations = 0;
+
+    'shrink: loop {
+        for candidate in shrink_candidates(&current) {
+            if iterations == MAX_SHRINK_ITERATIONS {
+                break 'shrink;
+            }
+            iterations += 1;
+
+            if let Err(candidate_reason) = execute(&candidate) {
+                current = candidate;
+                reason = candidate_reason;
+                continue 'shrink;
+            }
+        }
+        // No simpler input fails so we've reached a local minimum.
+        break;
+    }
+
+    (current, reason)
+}
+
+/// Returns the inputs which are one step simpler than `input_map`, simplest first.
+fn shrink_candidates(input_map: &InputMap) -> Vec<InputMap> {
+    let mut candidates = Vec::new();
+    for (name, value) in input_map {
+        for simpler_value in shrink_value(value) {
+            let mut candidate = input_map.clone();
+            candidate.insert(name.clone(), simpler_value);
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Returns the values which are one step simpler than `value`, changing a single numeric value in it.
+///
+/// Numeric values only ever get smaller, which keeps them within the range of their type.
+fn shrink_value(value: &InputValue) -> Vec<InputValue> {
+    match value {
+        InputValue::Field(field) => {
+            if field.is_zero() {
+                return Vec::new();
+            }
+            let mut candidates = vec![InputValue::Field(FieldElement::zero())];
+            if field.num_bits() <= 128 && field.to_u128() > 1 {
+                let halved = FieldElement::from(field.to_u128() / 2);
+                candidates.push(InputValue::Field(halved));
+            }
+            candidates
+        }
+        InputValue::String(_) => Vec::new(),
+        InputValue::Vec(elements) => {
+            let mut candidates = Vec::new();
+            for (index, element) in elements.iter().enumerate() {
+                for simpler_element in shrink_value(element) {
+                    let mut candidate = elements.clone();
+                    candidate[index] = simpler_element;
+                    candidates.push(InputValue::Vec(candidate));
+                }
+            }
+            candidates
+        }
+        InputValue::Struct(fields) => {
+            let mut candidates = Vec::new();
+            for (name, field) in fields {
+                for simpler_field in shrink_value(field) {
+                    let mut candidate = fields.clone();
+                    candidate.insert(name.clone(), simpler_field);
+                    candidates.push(InputValue::Struct(candidate));
+                }
+            }
+            candidates
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::{AcirField, FieldElement};
+    use noirc_abi::{input_parser::InputValue, InputMap};
+
+    use super::{shrink_input_map, shrink_value, MAX_SHRINK_ITERATIONS};
+
+    fn field(value: u128) -> InputValue {
+        InputValue::Field(FieldElement::from(value))
+    }
+
+    fn input_map(values: Vec<(&str, InputValue)>) -> InputMap {
+        values.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+    }
+
+    fn as_u128(input_map: &InputMap, name: &str) -> u128 {
+        match &input_map[name] {
+            InputValue::Field(field) => field.to_u128(),
+            other => panic!("expected a field but found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn zero_cannot_be_shrunk() {
+        assert!(shrink_value(&field(0)).is_empty());
+        assert!(shrink_value(&InputValue::String("abc".to_string())).is_empty());
+    }
+
+    #[test]
+    fn shrinks_to_the_smallest_failing_value() {
+        let input = input_map(vec![("x", field(1000)), ("y", field(77))]);
+        let (shrunk, reason) = shrink_input_map(input, "x >= 10".to_string(), |input| {
+            if as_u128(input, "x") >= 10 {
+                Err(format!("x is {}", as_u128(input, "x")))
+            } else {
+                Ok(())
+            }
+        });
+
+        // Halving from 1000 stops at the first value below 20
+        let x = as_u128(&shrunk, "x");
+        assert!((10..20).contains(&x), "x shrunk to {x}");
+        assert_eq!(reason, format!("x is {x}"));
+        // `y` doesn't affect the failure so it's shrunk all the way
+        assert_eq!(as_u128(&shrunk, "y"), 0);
+    }
+
+    #[test]
+    fn shrinks_elements_of_arrays_and_structs() {
+        let input = input_map(vec![
+            ("xs", InputValue::Vec(vec![field(5), field(6)])),
+            ("s", InputValue::Struct(input_map(vec![("a", field(3))]))),
+        ]);
+        let (shrunk, _) = shrink_input_map(input, String::new(), |_| Err(String::new()));
+
+        let expected = input_map(vec![
+            ("xs", InputValue::Vec(vec![field(0), field(0)])),
+            ("s", InputValue::Struct(input_map(vec![("a", field(0))]))),
+        ]);
+        assert_eq!(shrunk, expected);
+    }
+
+    #[test]
+    fn keeps_the_original_input_if_nothing_simpler_fails() {
+        let input = input_map(vec![("x", field(8))]);
+        let (shrunk, reason) = shrink_input_map(input.clone(), "original".to_string(), |input| {
+            if as_u128(input, "x") == 8 {
+                Err("x is 8".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(shrunk, input);
+        assert_eq!(reason, "original");
+    }
+
+    #[test]
+    fn stops_after_the_maximum_number_of_executions() {
+        let names = ["a", "b", "c", "d"];
+        let input = input_map(names.iter().map(|name| (*name, field(u128::MAX))).collect());
+        let mut executions = 0;
+        shrink_input_map(input, String::new(), |input| {
+            executions += 1;
+            // Halving each value one step at a time takes far longer than the budget allows
+            if names.iter().all(|name| as_u128(input, name) != 0) {
+                Err(String::new())
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(executions, MAX_SHRINK_ITERATIONS);
+    }
+}
@@ -16,6 +16,21 @@ pub struct FuzzTestResult {
 
     /// Minimal reproduction test case for failing fuzz tests
     pub counterexample: Option<CounterExample>,
+
+    /// Inputs which reached new coverage, worth replaying as seeds in future runs.
+    /// This is only populated by coverage guided fuzzing.
+    pub corpus: Vec<InputMap>,
+}
+
+impl FuzzTestResult {
+    pub(crate) fn success(corpus: Vec<InputMap>) -> Self {
+        Self { success: true, reason: None, counterexample: None, corpus }
+    }
+
+    pub(crate) fn failure(reason: String, counterexample: CounterExample) -> Self {
+        let reason = if reason.is_empty() { None } else { Some(reason) };
+        Self { success: false, reason, counterexample: Some(counterexample), corpus: Vec::new() }
+    }
 }
 
 /// Returned by a single fuzz in the case of a successful run
@@ -0,0 +1,235 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::thread;
+
+use acvm::{
+    acir::circuit::{brillig::BrilligFunctionId, Opcode, OpcodeLocation, Program},
+    FieldElement,
+};
+use async_lsp::ClientSocket;
+use fm::{FileId, FileManager};
+use fxhash::FxHashMap as HashMap;
+use nargo::{ops::transform_program, package::Package, parse_all};
+use noirc_driver::{check_crate, compile_no_check, CompileOptions, DEFAULT_EXPRESSION_WIDTH};
+use noirc_errors::{debug_info::DebugInfo, Location, Span};
+use noirc_frontend::{
+    graph::CrateId,
+    hir::{Context, ParsedFiles},
+    node_interner::FuncId,
+};
+
+/// Compiling can recurse deeply, so the thread computing circuit costs gets the stack size
+/// of a main thread rather than the smaller default of spawned threads.
+const CIRCUIT_COSTS_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// The number of ACIR and Brillig opcodes attributed to some source code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OpcodeCounts {
+    pub(crate) acir_opcodes: usize,
+    pub(crate) brillig_opcodes: usize,
+}
+
+impl OpcodeCounts {
+    pub(crate) fn total(&self) -> usize {
+        self.acir_opcodes + self.brillig_opcodes
+    }
+
+    fn add(&mut self, other: OpcodeCounts) {
+        self.acir_opcodes += other.acir_opcodes;
+        self.brillig_opcodes += other.brillig_opcodes;
+    }
+}
+
+impl Display for OpcodeCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let acir_opcodes = opcodes_string(self.acir_opcodes, "ACIR");
+        let brillig_opcodes = opcodes_string(self.brillig_opcodes, "Brillig");
+        match (self.acir_opcodes, self.brillig_opcodes) {
+            (_, 0) => write!(f, "{acir_opcodes}"),
+            (0, _) => write!(f, "{brillig_opcodes}"),
+            (_, _) => write!(f, "{acir_opcodes}, {brillig_opcodes}"),
+        }
+    }
+}
+
+fn opcodes_string(count: usize, kind: &str) -> String {
+    if count == 1 {
+        format!("1 {kind} opcode")
+    } else {
+        format!("{count} {kind} opcodes")
+    }
+}
+
+/// The opcodes a package compiles to, grouped by the call stack they were generated from.
+/// Like the profiler's opcode flamegraphs, the call stacks come from the programs' `DebugInfo`.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitCosts {
+    call_stacks: HashMap<Vec<Location>, OpcodeCounts>,
+}
+
+impl CircuitCosts {
+    /// Returns the opcodes generated from code in `span`, that is, those whose call stack
+    /// goes through `span`. Opcodes are only counted once even if their call stack goes
+    /// through `span` multiple times (for example in recursive calls).
+    pub(crate) fn opcodes_in_span(&self, file: FileId, span: Span) -> OpcodeCounts {
+        let mut counts = OpcodeCounts::default();
+        for (call_stack, call_stack_counts) in &self.call_stacks {
+            if call_stack
+                .iter()
+                .any(|location| location.file == file && span.contains(&location.span))
+            {
+                counts.add(*call_stack_counts);
+            }
+        }
+        counts
+    }
+
+    /// Forgets the opcodes generated from code in `file`, for example because its text changed
+    /// and the costs would be matched against spans that moved. The opcodes are still attributed
+    /// to the code in other files they were called from.
+    pub(crate) fn remove_file(&mut self, file: FileId) {
+        let call_stacks = std::mem::take(&mut self.call_stacks);
+        for (mut call_stack, counts) in call_stacks {
+            call_stack.retain(|location| location.file != file);
+            if !call_stack.is_empty() {
+                self.add(call_stack, counts);
+            }
+        }
+    }
+
+    fn add_program(&mut self, program: &Program<FieldElement>, debug_infos: &[DebugInfo]) {
+        for (circuit, debug_info) in program.functions.iter().zip(debug_infos) {
+            for (index, opcode) in circuit.opcodes.iter().enumerate() {
+                let call_stack =
+                    debug_info.opcode_location(&OpcodeLocation::Acir(index)).unwrap_or_default();
+                self.add(call_stack.clone(), OpcodeCounts { acir_opcodes: 1, brillig_opcodes: 0 });
+
+                // The Brillig opcodes of an unconstrained function are also attributed to
+                // every place it's called from
+                if let Opcode::BrilligCall { id, .. } = opcode {
+                    if let Some(brillig) = program.unconstrained_functions.get(id.as_usize()) {
+                        let brillig_opcodes = brillig.bytecode.len();
+                        self.add(call_stack, OpcodeCounts { acir_opcodes: 0, brillig_opcodes });
+                    }
+                }
+            }
+        }
+
+        // Brillig opcodes are attributed to the unconstrained code that generated them once,
+        // regardless of how many times the function is called
+        for index in 0..program.unconstrained_functions.len() {
+            let brillig_function_id = BrilligFunctionId(index as u32);
+            let Some(brillig_locations) = debug_infos
+                .iter()
+                .find_map(|debug_info| debug_info.brillig_locations.get(&brillig_function_id))
+            else {
+                continue;
+            };
+
+            for call_stack in brillig_locations.values() {
+                self.add(call_stack.clone(), OpcodeCounts { acir_opcodes: 0, brillig_opcodes: 1 });
+            }
+        }
+    }
+
+    fn add(&mut self, call_stack: Vec<Location>, counts: OpcodeCounts) {
+        self.call_stacks.entry(call_stack).or_default().add(counts);
+    }
+}
+
+/// Emitted once the circuit costs of a package were computed in the background.
+pub(crate) struct CircuitCostsComputed {
+    /// The value of `LspState::circuit_costs_generation` when the computation started.
+    pub(crate) generation: usize,
+    pub(crate) package_root_dir: PathBuf,
+    pub(crate) circuit_costs: Option<CircuitCosts>,
+}
+
+/// Compiles `packages` on another thread, as that's much slower than type-checking them, and
+/// emits a `CircuitCostsComputed` event for each of them. `file_manager` must be the file manager
+/// of their workspace, so that the costs refer to the same files as the cached packages.
+pub(crate) fn spawn_circuit_costs_computation(
+    client: ClientSocket,
+    generation: usize,
+    file_manager: FileManager,
+    packages: Vec<Package>,
+) {
+    let spawned = thread::Builder::new()
+        .name("circuit-costs".to_string())
+        .stack_size(CIRCUIT_COSTS_STACK_SIZE)
+        .spawn(move || {
+            let parsed_files = parse_all(&file_manager);
+            for package in packages {
+                let circuit_costs =
+                    compute_package_circuit_costs(&file_manager, &parsed_files, &package);
+                let event = CircuitCostsComputed {
+                    generation,
+                    package_root_dir: package.root_dir,
+                    circuit_costs,
+                };
+                // The server is shutting down
+                if client.emit(event).is_err() {
+                    break;
+                }
+            }
+        });
+    if let Err(error) = spawned {
+        eprintln!("Could not start computing circuit costs: {error}");
+    }
+}
+
+/// Type-checks and compiles a package, returning how many opcodes each part of its code
+/// compiles to.
+pub(crate) fn compute_package_circuit_costs(
+    file_manager: &FileManager,
+    parsed_files: &ParsedFiles,
+    package: &Package,
+) -> Option<CircuitCosts> {
+    let (mut context, crate_id) = crate::prepare_package(file_manager, parsed_files, package);
+    check_crate(&mut context, crate_id, &Default::default()).ok()?;
+    compute_circuit_costs(&mut context, crate_id, package)
+}
+
+/// Compiles the package's `main` function, or its contract's entry points, and returns how many
+/// opcodes each part of the code compiles to. The package must have type-checked without errors.
+/// Like `nargo compile`, the ACIR is optimized for the package's expression width so that the
+/// costs match the compiled circuits. Entry points that fail to compile are skipped.
+fn compute_circuit_costs(
+    context: &mut Context,
+    crate_id: CrateId,
+    package: &Package,
+) -> Option<CircuitCosts> {
+    let entry_points: Vec<FuncId> = if package.is_binary() {
+        vec![context.get_main_function(&crate_id)?]
+    } else if package.is_contract() {
+        context
+            .get_all_contracts(&crate_id)
+            .into_iter()
+            .flat_map(|contract| contract.functions)
+            .filter(|contract_function| contract_function.is_entry_point)
+            .map(|contract_function| contract_function.function_id)
+            .collect()
+    } else {
+        return None;
+    };
+
+    let options = CompileOptions::default();
+    let expression_width = package.expression_width.unwrap_or(DEFAULT_EXPRESSION_WIDTH);
+    let mut circuit_costs = CircuitCosts::default();
+    for entry_point in entry_points {
+        let force_compile = true;
+        match compile_no_check(context, &options, entry_point, None, force_compile) {
+            Ok(compiled_program) => {
+                let compiled_program = transform_program(compiled_program, expression_width);
+                circuit_costs.add_program(&compiled_program.program, &compiled_program.debug);
+            }
+            Err(error) => {
+                let function_name = context.def_interner.function_name(&entry_point);
+                eprintln!(
+                    "Could not compile {function_name} to compute its circuit costs: {error:?}"
+                );
+            }
+        }
+    }
+    Some(circuit_costs)
+}
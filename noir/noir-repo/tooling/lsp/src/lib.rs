@@ -18,12 +18,15 @@ use async_lsp::{
     router::Router, AnyEvent, AnyNotification, AnyRequest, ClientSocket, Error, LspService,
     ResponseError,
 };
-use fm::{codespan_files as files, FileManager};
+use fm::{codespan_files as files, FileId, FileManager};
 use fxhash::FxHashSet;
 use lsp_types::{
     request::{
-        CodeActionRequest, Completion, DocumentSymbolRequest, HoverRequest, InlayHintRequest,
-        PrepareRenameRequest, References, Rename, SignatureHelpRequest,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
+        CodeActionRequest, Completion, DocumentSymbolRequest, GotoImplementation, HoverRequest,
+        InlayHintRequest, PrepareRenameRequest, References, Rename, SemanticTokensFullRequest,
+        SemanticTokensRangeRequest, SignatureHelpRequest, TypeHierarchyPrepare,
+        TypeHierarchySubtypes, TypeHierarchySupertypes, WorkspaceSymbolRequest,
     },
     CodeLens,
 };
@@ -34,6 +37,7 @@ use nargo::{
 };
 use nargo_toml::{find_file_manifest, resolve_workspace_from_toml, PackageSelection};
 use noirc_driver::{file_manager_with_stdlib, prepare_crate, NOIR_ARTIFACT_VERSION_STRING};
+use noirc_errors::{FileDiagnostic, Location};
 use noirc_frontend::{
     graph::{CrateGraph, CrateId, CrateName},
     hir::{
@@ -47,23 +51,30 @@ use noirc_frontend::{
 };
 use rayon::prelude::*;
 
+use circuit_costs::{CircuitCosts, CircuitCostsComputed};
 use notifications::{
-    on_did_change_configuration, on_did_change_text_document, on_did_close_text_document,
-    on_did_open_text_document, on_did_save_text_document, on_exit, on_initialized,
+    on_circuit_costs_computed, on_did_change_configuration, on_did_change_text_document,
+    on_did_close_text_document, on_did_open_text_document, on_did_save_text_document, on_exit,
+    on_initialized,
 };
 use requests::{
+    on_call_hierarchy_incoming_calls_request, on_call_hierarchy_outgoing_calls_request,
     on_code_action_request, on_code_lens_request, on_completion_request,
     on_document_symbol_request, on_formatting, on_goto_declaration_request,
-    on_goto_definition_request, on_goto_type_definition_request, on_hover_request, on_initialize,
-    on_inlay_hint_request, on_prepare_rename_request, on_references_request, on_rename_request,
+    on_goto_definition_request, on_goto_implementation_request, on_goto_type_definition_request,
+    on_hover_request, on_initialize, on_inlay_hint_request, on_prepare_call_hierarchy_request,
+    on_prepare_rename_request, on_prepare_type_hierarchy_request, on_references_request,
+    on_rename_request, on_semantic_tokens_full_request, on_semantic_tokens_range_request,
     on_shutdown, on_signature_help_request, on_test_run_request, on_tests_request,
-    LspInitializationOptions,
+    on_type_hierarchy_subtypes_request, on_type_hierarchy_supertypes_request,
+    on_workspace_symbol_request, LspInitializationOptions,
 };
 use serde_json::Value as JsonValue;
 use thiserror::Error;
 use tower::Service;
 
 mod attribute_reference_finder;
+mod circuit_costs;
 mod modules;
 mod notifications;
 mod requests;
@@ -103,6 +114,10 @@ pub struct LspState {
 
     // Tracks files that currently have errors, by package root.
     files_with_errors: HashMap<PathBuf, HashSet<Url>>,
+
+    // Incremented when circuit costs start being computed in the background, or when the files of
+    // a workspace change, so that outdated costs are discarded once computed.
+    circuit_costs_generation: usize,
 }
 
 struct WorkspaceCacheData {
@@ -111,10 +126,27 @@ struct WorkspaceCacheData {
 
 struct PackageCacheData {
     crate_id: CrateId,
+    crate_name: CrateName,
     crate_graph: CrateGraph,
     node_interner: NodeInterner,
     def_maps: BTreeMap<CrateId, CrateDefMap>,
     usage_tracker: UsageTracker,
+    dependencies: Option<DependenciesCacheData>,
+    circuit_costs: Option<CircuitCosts>,
+}
+
+/// The dependencies of a package, including the stdlib, as elaborated when the package was last
+/// type-checked. They are reused while only the package's own files change, instead of being
+/// elaborated again.
+struct DependenciesCacheData {
+    node_interner: NodeInterner,
+    def_maps: BTreeMap<CrateId, CrateDefMap>,
+    usage_tracker: UsageTracker,
+    visited_files: BTreeMap<FileId, Location>,
+    /// The files of the dependencies' modules. The cache must be dropped when one of them changes.
+    files: HashSet<FileId>,
+    /// The warnings and errors found in the dependencies
+    diagnostics: Vec<FileDiagnostic>,
 }
 
 impl LspState {
@@ -134,6 +166,7 @@ impl LspState {
             open_documents_count: 0,
             options: Default::default(),
             files_with_errors: HashMap::new(),
+            circuit_costs_generation: 0,
         }
     }
 }
@@ -159,6 +192,7 @@ impl NargoLspService {
             .request::<request::GotoDefinition, _>(on_goto_definition_request)
             .request::<request::GotoDeclaration, _>(on_goto_declaration_request)
             .request::<request::GotoTypeDefinition, _>(on_goto_type_definition_request)
+            .request::<GotoImplementation, _>(on_goto_implementation_request)
             .request::<DocumentSymbolRequest, _>(on_document_symbol_request)
             .request::<References, _>(on_references_request)
             .request::<PrepareRenameRequest, _>(on_prepare_rename_request)
@@ -168,13 +202,23 @@ impl NargoLspService {
             .request::<Completion, _>(on_completion_request)
             .request::<SignatureHelpRequest, _>(on_signature_help_request)
             .request::<CodeActionRequest, _>(on_code_action_request)
+            .request::<WorkspaceSymbolRequest, _>(on_workspace_symbol_request)
+            .request::<SemanticTokensFullRequest, _>(on_semantic_tokens_full_request)
+            .request::<SemanticTokensRangeRequest, _>(on_semantic_tokens_range_request)
+            .request::<CallHierarchyPrepare, _>(on_prepare_call_hierarchy_request)
+            .request::<CallHierarchyIncomingCalls, _>(on_call_hierarchy_incoming_calls_request)
+            .request::<CallHierarchyOutgoingCalls, _>(on_call_hierarchy_outgoing_calls_request)
+            .request::<TypeHierarchyPrepare, _>(on_prepare_type_hierarchy_request)
+            .request::<TypeHierarchySupertypes, _>(on_type_hierarchy_supertypes_request)
+            .request::<TypeHierarchySubtypes, _>(on_type_hierarchy_subtypes_request)
             .notification::<notification::Initialized>(on_initialized)
             .notification::<notification::DidChangeConfiguration>(on_did_change_configuration)
             .notification::<notification::DidOpenTextDocument>(on_did_open_text_document)
             .notification::<notification::DidChangeTextDocument>(on_did_change_text_document)
             .notification::<notification::DidCloseTextDocument>(on_did_close_text_document)
             .notification::<notification::DidSaveTextDocument>(on_did_save_text_document)
-            .notification::<notification::Exit>(on_exit);
+            .notification::<notification::Exit>(on_exit)
+            .event::<CircuitCostsComputed>(on_circuit_costs_computed);
         Self { router }
     }
 }
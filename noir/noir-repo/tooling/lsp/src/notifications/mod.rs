@@ -1,17 +1,21 @@
 use std::collections::HashSet;
 use std::ops::ControlFlow;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::{
-    insert_all_files_for_workspace_into_file_manager, PackageCacheData, WorkspaceCacheData,
+    circuit_costs::{self, CircuitCostsComputed},
+    insert_all_files_for_workspace_into_file_manager, DependenciesCacheData, PackageCacheData,
+    WorkspaceCacheData,
 };
 use async_lsp::{ErrorCode, LanguageClient, ResponseError};
-use fm::{FileId, FileManager, FileMap};
+use fm::{FileId, FileManager, FileMap, NormalizePath};
 use fxhash::FxHashMap as HashMap;
 use lsp_types::{DiagnosticRelatedInformation, DiagnosticTag, Url};
-use noirc_driver::check_crate;
+use nargo::package::Package;
+use noirc_driver::{check_crate, check_crate_dependencies};
 use noirc_errors::reporter::CustomLabel;
 use noirc_errors::{DiagnosticKind, FileDiagnostic, Location};
+use noirc_frontend::{graph::CrateId, hir::Context};
 
 use crate::types::{
     notification, Diagnostic, DiagnosticSeverity, DidChangeConfigurationParams,
@@ -21,7 +25,7 @@ use crate::types::{
 
 use crate::{
     byte_span_to_range, get_package_tests_in_crate, parse_diff, resolve_workspace_for_source_path,
-    LspState,
+    utils, LspState,
 };
 
 pub(super) fn on_initialized(
@@ -60,13 +64,21 @@ pub(super) fn on_did_change_text_document(
     state: &mut LspState,
     params: DidChangeTextDocumentParams,
 ) -> ControlFlow<Result<(), async_lsp::Error>> {
-    let text = params.content_changes.into_iter().next().unwrap().text;
-    state.input_files.insert(params.text_document.uri.to_string(), text.clone());
+    let source = state.input_files.entry(params.text_document.uri.to_string()).or_default();
+    for change in params.content_changes {
+        utils::apply_text_document_change(source, change);
+    }
 
     let document_uri = params.text_document.uri;
-    let output_diagnostics = false;
+    let output_diagnostics = state.options.enable_diagnostics_on_change;
 
-    match process_workspace_for_noir_document(state, document_uri, output_diagnostics) {
+    // Only the packages containing the changed document are type-checked again, reusing the
+    // elaboration of their dependencies and the stdlib. Packages depending on them are
+    // type-checked again when the document is saved.
+    let recheck_all_packages = false;
+
+    match process_workspace_packages(state, document_uri, output_diagnostics, recheck_all_packages)
+    {
         Ok(_) => ControlFlow::Continue(()),
         Err(err) => ControlFlow::Break(Err(err)),
     }
@@ -115,6 +127,20 @@ pub(crate) fn process_workspace_for_noir_document(
     state: &mut LspState,
     document_uri: Url,
     output_diagnostics: bool,
+) -> Result<(), async_lsp::Error> {
+    let recheck_all_packages = true;
+    process_workspace_packages(state, document_uri, output_diagnostics, recheck_all_packages)
+}
+
+// Like `process_workspace_for_noir_document`, but unless `recheck_all_packages` is true
+// only the packages whose sources contain the given document are type-checked again, reusing
+// the cached elaboration of their dependencies: the cached data of the other packages is kept
+// until the workspace is processed again.
+fn process_workspace_packages(
+    state: &mut LspState,
+    document_uri: Url,
+    output_diagnostics: bool,
+    recheck_all_packages: bool,
 ) -> Result<(), async_lsp::Error> {
     let file_path = document_uri.to_file_path().map_err(|_| {
         ResponseError::new(ErrorCode::REQUEST_FAILED, "URI is not a valid file path")
@@ -134,15 +160,66 @@ pub(crate) fn process_workspace_for_noir_document(
 
     let parsed_files = parse_diff(&workspace_file_manager, state);
 
+    // Compiling is much slower than type-checking, so circuit costs are only computed when
+    // the whole workspace is processed (when a document is opened or saved), not on every change.
+    let compute_circuit_costs = state.options.enable_circuit_costs && recheck_all_packages;
+
+    // Cached packages refer to files by FileId, so they can only be kept if the files
+    // in the workspace are still the same (for example no file was added or removed).
+    let same_files =
+        state.workspace_cache.get(&workspace.root_dir).map_or(false, |workspace_cache_data| {
+            have_same_files(&workspace_cache_data.file_manager, &workspace_file_manager)
+        });
+    let recheck_all_packages = recheck_all_packages || !same_files;
+    if !same_files {
+        // Circuit costs being computed refer to the previous files
+        state.circuit_costs_generation += 1;
+    } else if let Some(file_id) = workspace_file_manager.name_to_id(file_path.clone()) {
+        // Circuit costs being computed refer to the previous text of the document
+        state.circuit_costs_generation += 1;
+
+        // The dependencies of packages which depend on the document are elaborated again
+        // the next time those packages are type-checked, and their circuit costs no longer
+        // apply to the document until they are computed again
+        for package in workspace.into_iter() {
+            if let Some(package_cache) = state.package_cache.get_mut(&package.root_dir) {
+                if let Some(circuit_costs) = &mut package_cache.circuit_costs {
+                    circuit_costs.remove_file(file_id);
+                }
+                if package_cache
+                    .dependencies
+                    .as_ref()
+                    .is_some_and(|dependencies| dependencies.files.contains(&file_id))
+                {
+                    package_cache.dependencies = None;
+                }
+            }
+        }
+    }
+
+    let mut packages_to_compile = Vec::new();
+
     for package in workspace.into_iter() {
-        let (mut context, crate_id) =
-            crate::prepare_package(&workspace_file_manager, &parsed_files, package);
+        if !recheck_all_packages
+            && state.package_cache.contains_key(&package.root_dir)
+            && !package_contains_file(package, &file_path)
+        {
+            continue;
+        }
 
-        let file_diagnostics = match check_crate(&mut context, crate_id, &Default::default()) {
-            Ok(((), warnings)) => warnings,
-            Err(errors_and_warnings) => errors_and_warnings,
+        let (dependencies, circuit_costs) = match state.package_cache.remove(&package.root_dir) {
+            Some(package_cache) => (package_cache.dependencies, package_cache.circuit_costs),
+            None => (None, None),
         };
 
+        // Opening or saving a document elaborates the dependencies again, in case files
+        // which aren't open changed
+        let dependencies = dependencies.filter(|_| !recheck_all_packages);
+
+        let (mut context, crate_id) =
+            crate::prepare_package(&workspace_file_manager, &parsed_files, package);
+        let (file_diagnostics, dependencies) = check_package(&mut context, crate_id, dependencies);
+
         // We don't add test headings for a package if it contains no `#[test]` functions
         if let Some(tests) = get_package_tests_in_crate(&context, &crate_id, &package.name) {
             let _ = state.client.notify::<notification::NargoUpdateTests>(NargoPackageTests {
@@ -151,6 +228,15 @@ pub(crate) fn process_workspace_for_noir_document(
             });
         }
 
+        let has_errors = file_diagnostics.iter().any(|diagnostic| diagnostic.diagnostic.is_error());
+        if compute_circuit_costs && !has_errors {
+            packages_to_compile.push(package.clone());
+        }
+
+        // The previous circuit costs of other documents are kept until new ones are computed in
+        // the background. They can't be kept if the files changed, as they refer to files by FileId.
+        let circuit_costs = circuit_costs.filter(|_| same_files);
+
         let collected_lenses = crate::requests::collect_lenses_for_package(
             &context,
             crate_id,
@@ -163,10 +249,13 @@ pub(crate) fn process_workspace_for_noir_document(
             package.root_dir.clone(),
             PackageCacheData {
                 crate_id,
+                crate_name: package.name.clone(),
                 crate_graph: context.crate_graph,
                 node_interner: context.def_interner,
                 def_maps: context.def_maps,
                 usage_tracker: context.usage_tracker,
+                dependencies: Some(dependencies),
+                circuit_costs,
             },
         );
 
@@ -178,6 +267,16 @@ pub(crate) fn process_workspace_for_noir_document(
         }
     }
 
+    if !packages_to_compile.is_empty() {
+        state.circuit_costs_generation += 1;
+        circuit_costs::spawn_circuit_costs_computation(
+            state.client.clone(),
+            state.circuit_costs_generation,
+            workspace_file_manager.clone(),
+            packages_to_compile,
+        );
+    }
+
     state.workspace_cache.insert(
         workspace.root_dir.clone(),
         WorkspaceCacheData { file_manager: workspace_file_manager },
@@ -186,6 +285,80 @@ pub(crate) fn process_workspace_for_noir_document(
     Ok(())
 }
 
+/// Type-checks a package, reusing the given elaboration of its dependencies if any.
+/// Returns the warnings and errors found in the package and its dependencies, along with the
+/// elaboration of the dependencies to reuse the next time the package is type-checked.
+fn check_package(
+    context: &mut Context,
+    crate_id: CrateId,
+    dependencies: Option<DependenciesCacheData>,
+) -> (Vec<FileDiagnostic>, DependenciesCacheData) {
+    let dependencies = match dependencies {
+        Some(dependencies) => {
+            context.def_interner = dependencies.node_interner.clone();
+            context.def_maps = dependencies.def_maps.clone();
+            context.usage_tracker = dependencies.usage_tracker.clone();
+            context.visited_files = dependencies.visited_files.clone();
+            dependencies
+        }
+        None => {
+            let diagnostics = check_crate_dependencies(context, crate_id, &Default::default());
+            let files = context
+                .def_maps
+                .values()
+                .flat_map(|def_map| {
+                    def_map.modules().iter().map(|(_, module)| module.location.file)
+                })
+                .collect();
+            DependenciesCacheData {
+                node_interner: context.def_interner.clone(),
+                def_maps: context.def_maps.clone(),
+                usage_tracker: context.usage_tracker.clone(),
+                visited_files: context.visited_files.clone(),
+                files,
+                diagnostics,
+            }
+        }
+    };
+
+    let mut file_diagnostics = dependencies.diagnostics.clone();
+    match check_crate(context, crate_id, &Default::default()) {
+        Ok(((), warnings)) => file_diagnostics.extend(warnings),
+        Err(errors_and_warnings) => file_diagnostics.extend(errors_and_warnings),
+    }
+    (file_diagnostics, dependencies)
+}
+
+pub(crate) fn on_circuit_costs_computed(
+    state: &mut LspState,
+    event: CircuitCostsComputed,
+) -> ControlFlow<Result<(), async_lsp::Error>> {
+    // Newer costs are being computed, or these refer to files that changed since
+    if event.generation != state.circuit_costs_generation {
+        return ControlFlow::Continue(());
+    }
+
+    if let Some(package_cache) = state.package_cache.get_mut(&event.package_root_dir) {
+        package_cache.circuit_costs = event.circuit_costs;
+    }
+    ControlFlow::Continue(())
+}
+
+/// Returns true if `file_path` is part of the sources of `package`, not counting its dependencies.
+fn package_contains_file(package: &Package, file_path: &Path) -> bool {
+    file_path.starts_with(package.root_dir.normalize())
+}
+
+/// Returns true if both file managers have the same files with the same FileIds.
+fn have_same_files(file_manager: &FileManager, other_file_manager: &FileManager) -> bool {
+    let files = file_manager.as_file_map();
+    let other_files = other_file_manager.as_file_map();
+    files.all_file_ids().count() == other_files.all_file_ids().count()
+        && files
+            .all_file_ids()
+            .all(|file_id| file_manager.path(*file_id) == other_file_manager.path(*file_id))
+}
+
 fn publish_diagnostics(
     state: &mut LspState,
     package_root_dir: &PathBuf,
@@ -336,6 +509,7 @@ mod notification_tests {
         TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
         WorkDoneProgressParams,
     };
+    use noirc_errors::{CustomDiagnostic, Span};
     use tokio::test;
 
     #[test]
@@ -403,4 +577,215 @@ mod notification_tests {
             panic!("Expected InlayHintLabel::LabelParts, got {:?}", inlay_hint.label);
         }
     }
+
+    #[test]
+    async fn test_applies_incremental_changes() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("inlay_hints").await;
+
+        state
+            .input_files
+            .insert(noir_text_document.to_string(), "fn main() {\n    let x = 1;\n}\n".to_string());
+
+        let change = |start: Position, end: Position, text: &str| TextDocumentContentChangeEvent {
+            range: Some(Range { start, end }),
+            range_length: None,
+            text: text.to_string(),
+        };
+
+        let _ = on_did_change_text_document(
+            &mut state,
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier {
+                    uri: noir_text_document.clone(),
+                    version: 1,
+                },
+                // Changes are applied in order, each one to the result of the previous one
+                content_changes: vec![
+                    change(Position::new(1, 12), Position::new(1, 13), "true"),
+                    change(Position::new(1, 4), Position::new(1, 4), "let y = 2;\n    "),
+                ],
+            },
+        );
+
+        assert_eq!(
+            state.input_files[&noir_text_document.to_string()],
+            "fn main() {\n    let y = 2;\n    let x = true;\n}\n"
+        );
+    }
+
+    #[test]
+    async fn test_package_contains_only_its_own_files() {
+        let (_state, noir_text_document) = test_utils::init_lsp_server("workspace").await;
+
+        // noir_text_document is always `src/main.nr` in the workspace directory, so let's go to the workspace dir
+        let noir_text_document = noir_text_document.to_file_path().unwrap();
+        let workspace_dir = noir_text_document.parent().unwrap().parent().unwrap();
+        let one_lib = workspace_dir.join("one/src/lib.nr");
+        let two_lib = workspace_dir.join("two/src/lib.nr");
+
+        let workspace =
+            resolve_workspace_for_source_path(&workspace_dir.join("Nargo.toml")).unwrap();
+        let packages: Vec<_> = workspace.into_iter().collect();
+        let [one, two] = packages[..] else {
+            panic!("Expected two packages in the workspace");
+        };
+        assert_eq!(one.name.to_string(), "one");
+        assert_eq!(two.name.to_string(), "two");
+
+        // "two" depends on "one", but a change in "one" only makes "one" be type-checked again
+        assert!(package_contains_file(one, &one_lib));
+        assert!(!package_contains_file(two, &one_lib));
+
+        assert!(!package_contains_file(one, &two_lib));
+        assert!(package_contains_file(two, &two_lib));
+    }
+
+    fn change_whole_document(state: &mut LspState, uri: &Url, text: String) {
+        let _ = on_did_change_text_document(
+            state,
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier { uri: uri.clone(), version: 1 },
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text,
+                }],
+            },
+        );
+    }
+
+    #[test]
+    async fn test_reuses_dependencies_on_change() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("workspace").await;
+
+        // noir_text_document is always `src/main.nr` in the workspace directory, so let's go to the workspace dir
+        let noir_text_document = noir_text_document.to_file_path().unwrap();
+        let workspace_dir = noir_text_document.parent().unwrap().parent().unwrap();
+        let one_lib = workspace_dir.join("one/src/lib.nr");
+        let two_lib = workspace_dir.join("two/src/lib.nr");
+        let one_lib_uri = Url::from_file_path(&one_lib).unwrap();
+        let two_lib_uri = Url::from_file_path(&two_lib).unwrap();
+
+        let workspace =
+            resolve_workspace_for_source_path(&workspace_dir.join("Nargo.toml")).unwrap();
+        let packages: Vec<_> = workspace.into_iter().collect();
+        let [one, two] = packages[..] else {
+            panic!("Expected two packages in the workspace");
+        };
+
+        let output_diagnostics = false;
+        process_workspace_for_noir_document(&mut state, two_lib_uri.clone(), output_diagnostics)
+            .unwrap();
+
+        let file_manager = &state.workspace_cache[&workspace.root_dir].file_manager;
+        let one_lib_id = file_manager.name_to_id(one_lib.clone()).unwrap();
+        let two_lib_id = file_manager.name_to_id(two_lib.clone()).unwrap();
+        let dependencies = state.package_cache[&two.root_dir].dependencies.as_ref().unwrap();
+        assert!(dependencies.files.contains(&one_lib_id));
+        assert!(!dependencies.files.contains(&two_lib_id));
+
+        // Mark the cached dependencies of "two" to tell whether they are reused
+        let marker = CustomDiagnostic::from_message("marker").in_file(one_lib_id);
+        let package_cache = state.package_cache.get_mut(&two.root_dir).unwrap();
+        package_cache.dependencies.as_mut().unwrap().diagnostics.push(marker.clone());
+
+        let two_text = std::fs::read_to_string(&two_lib).unwrap();
+        change_whole_document(&mut state, &two_lib_uri, format!("{two_text}\n"));
+        let dependencies = state.package_cache[&two.root_dir].dependencies.as_ref().unwrap();
+        assert!(dependencies.diagnostics.contains(&marker));
+
+        // "two" depends on "one", so a change in "one" drops the dependencies cached for "two"
+        let one_text = std::fs::read_to_string(&one_lib).unwrap();
+        change_whole_document(&mut state, &one_lib_uri, format!("{one_text}\n"));
+        assert!(state.package_cache[&one.root_dir].dependencies.is_some());
+        assert!(state.package_cache[&two.root_dir].dependencies.is_none());
+
+        // They are elaborated again the next time "two" is type-checked
+        change_whole_document(&mut state, &two_lib_uri, two_text);
+        let dependencies = state.package_cache[&two.root_dir].dependencies.as_ref().unwrap();
+        assert!(!dependencies.diagnostics.contains(&marker));
+    }
+
+    #[test]
+    async fn test_drops_circuit_costs_of_changed_document() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("circuit_costs").await;
+        state.options.enable_circuit_costs = true;
+
+        let file_path = noir_text_document.to_file_path().unwrap();
+        let package_root_dir = file_path.parent().unwrap().parent().unwrap().to_path_buf();
+        let text = std::fs::read_to_string(&file_path).unwrap();
+
+        let _ = on_did_open_text_document(
+            &mut state,
+            DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: noir_text_document.clone(),
+                    language_id: "noir".to_string(),
+                    version: 0,
+                    text: text.clone(),
+                },
+            },
+        );
+        test_utils::compute_circuit_costs(&mut state, &noir_text_document);
+
+        let workspace = resolve_workspace_for_source_path(&file_path).unwrap();
+        let file_id = state.workspace_cache[&workspace.root_dir]
+            .file_manager
+            .name_to_id(file_path.clone())
+            .unwrap();
+        let whole_file = Span::from(0..text.len() as u32);
+        let circuit_costs = state.package_cache[&package_root_dir].circuit_costs.as_ref().unwrap();
+        assert!(circuit_costs.opcodes_in_span(file_id, whole_file).total() > 0);
+
+        let _ = on_did_change_text_document(
+            &mut state,
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier {
+                    uri: noir_text_document.clone(),
+                    version: 1,
+                },
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: format!("\n{text}"),
+                }],
+            },
+        );
+        let circuit_costs = state.package_cache[&package_root_dir].circuit_costs.as_ref().unwrap();
+        assert_eq!(circuit_costs.opcodes_in_span(file_id, whole_file).total(), 0);
+    }
+
+    #[test]
+    async fn test_discards_outdated_circuit_costs() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("circuit_costs").await;
+        state.options.enable_circuit_costs = true;
+
+        let file_path = noir_text_document.to_file_path().unwrap();
+        let package_root_dir = file_path.parent().unwrap().parent().unwrap().to_path_buf();
+
+        let output_diagnostics = false;
+        process_workspace_for_noir_document(&mut state, noir_text_document, output_diagnostics)
+            .unwrap();
+
+        // The workspace was processed again since these costs started being computed
+        let _ = on_circuit_costs_computed(
+            &mut state,
+            CircuitCostsComputed {
+                generation: state.circuit_costs_generation - 1,
+                package_root_dir: package_root_dir.clone(),
+                circuit_costs: Some(Default::default()),
+            },
+        );
+        assert!(state.package_cache[&package_root_dir].circuit_costs.is_none());
+
+        let _ = on_circuit_costs_computed(
+            &mut state,
+            CircuitCostsComputed {
+                generation: state.circuit_costs_generation,
+                package_root_dir: package_root_dir.clone(),
+                circuit_costs: Some(Default::default()),
+            },
+        );
+        assert!(state.package_cache[&package_root_dir].circuit_costs.is_some());
+    }
 }
@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    future::{self, Future},
+};
+
+use async_lsp::ResponseError;
+use fm::{FileId, FileMap};
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams, Range,
+    SymbolKind, TextDocumentIdentifier, TextDocumentPositionParams,
+};
+use noirc_errors::{Location, Span};
+use noirc_frontend::node_interner::{DefinitionKind, FuncId, NodeInterner, ReferenceId};
+
+use crate::LspState;
+
+use super::{process_request, to_lsp_location};
+
+pub(crate) fn on_prepare_call_hierarchy_request(
+    state: &mut LspState,
+    params: CallHierarchyPrepareParams,
+) -> impl Future<Output = Result<Option<Vec<CallHierarchyItem>>, ResponseError>> {
+    let result = process_request(state, params.text_document_position_params, |args| {
+        let func_id = referenced_function(args.interner, args.location)?;
+        let item = call_hierarchy_item(func_id, args.interner, args.files)?;
+        Some(vec![item])
+    });
+    future::ready(result)
+}
+
+pub(crate) fn on_call_hierarchy_incoming_calls_request(
+    state: &mut LspState,
+    params: CallHierarchyIncomingCallsParams,
+) -> impl Future<Output = Result<Option<Vec<CallHierarchyIncomingCall>>, ResponseError>> {
+    let result = process_request(state, item_position(&params.item), |args| {
+        let func_id = referenced_function(args.interner, args.location)?;
+        let function_location = args.interner.reference_location(ReferenceId::Function(func_id));
+
+        // Callers can be in any package of the workspace, and all interners in a workspace share
+        // the same FileManager so locations can be compared across them. Packages of other
+        // workspaces are skipped as their FileIds refer to other files.
+        let workspace_interners = args
+            .workspace
+            .into_iter()
+            .filter_map(|package| args.package_cache.get(&package.root_dir))
+            .map(|cache_data| &cache_data.node_interner);
+        let interners = std::iter::once(args.interner).chain(workspace_interners);
+
+        let mut calls = CallsCollector::default();
+        for interner in interners {
+            let Some(references) = interner.find_all_references(function_location, false, false)
+            else {
+                continue;
+            };
+
+            let mut functions_per_file = HashMap::new();
+            for reference in references {
+                let functions = functions_per_file
+                    .entry(reference.file)
+                    .or_insert_with(|| functions_in_file(interner, reference.file));
+                let Some(caller) = enclosing_function(functions, reference.span) else {
+                    continue;
+                };
+                let Some(item) = call_hierarchy_item(caller, interner, args.files) else {
+                    continue;
+                };
+                let Some(from) = to_lsp_location(args.files, reference.file, reference.span) else {
+                    continue;
+                };
+                calls.add(item, from.range);
+            }
+        }
+
+        let calls = calls
+            .into_sorted_calls()
+            .into_iter()
+            .map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+            .collect();
+        Some(calls)
+    });
+    future::ready(result)
+}
+
+pub(crate) fn on_call_hierarchy_outgoing_calls_request(
+    state: &mut LspState,
+    params: CallHierarchyOutgoingCallsParams,
+) -> impl Future<Output = Result<Option<Vec<CallHierarchyOutgoingCall>>, ResponseError>> {
+    let result = process_request(state, item_position(&params.item), |args| {
+        let interner = args.interner;
+        let func_id = referenced_function(interner, args.location)?;
+        let file = interner.function_modifiers(&func_id).name_location.file;
+        let body_span = function_body_span(interner, func_id)?;
+
+        let mut calls = CallsCollector::default();
+        for (span, referenced, is_declaration) in interner.find_all_referenced_in_file(file) {
+            if is_declaration || !body_span.contains(&span) {
+                continue;
+            }
+            let Some(callee) = function_reference(interner, referenced) else {
+                continue;
+            };
+            let Some(item) = call_hierarchy_item(callee, interner, args.files) else {
+                continue;
+            };
+            let Some(from) = to_lsp_location(args.files, file, span) else {
+                continue;
+            };
+            calls.add(item, from.range);
+        }
+
+        let calls = calls
+            .calls
+            .into_iter()
+            .map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+            .collect();
+        Some(calls)
+    });
+    future::ready(result)
+}
+
+/// Calls grouped by the function on the other end of the call, in the order they were found.
+#[derive(Default)]
+struct CallsCollector {
+    calls: Vec<(CallHierarchyItem, Vec<Range>)>,
+}
+
+impl CallsCollector {
+    fn add(&mut self, item: CallHierarchyItem, range: Range) {
+        let existing_call = self.calls.iter_mut().find(|(existing_item, _)| {
+            existing_item.uri == item.uri && existing_item.selection_range == item.selection_range
+        });
+        match existing_call {
+            Some((_, ranges)) => {
+                if !ranges.contains(&range) {
+                    ranges.push(range);
+                }
+            }
+            None => self.calls.push((item, vec![range])),
+        }
+    }
+
+    fn into_sorted_calls(mut self) -> Vec<(CallHierarchyItem, Vec<Range>)> {
+        self.calls.sort_by_key(|(item, _)| {
+            (
+                item.uri.to_string(),
+                item.selection_range.start.line,
+                item.selection_range.start.character,
+            )
+        });
+        self.calls
+    }
+}
+
+/// The position of an item's name, which is where we look the item up again when the client
+/// asks for its calls.
+fn item_position(item: &CallHierarchyItem) -> TextDocumentPositionParams {
+    TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier { uri: item.uri.clone() },
+        position: item.selection_range.start,
+    }
+}
+
+fn referenced_function(interner: &NodeInterner, location: Location) -> Option<FuncId> {
+    function_reference(interner, interner.find_referenced(location)?)
+}
+
+fn function_reference(interner: &NodeInterner, referenced: ReferenceId) -> Option<FuncId> {
+    match referenced {
+        ReferenceId::Function(func_id) => Some(func_id),
+        ReferenceId::Local(definition_id) => match interner.definition(definition_id).kind {
+            DefinitionKind::Function(func_id) => Some(func_id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn call_hierarchy_item(
+    func_id: FuncId,
+    interner: &NodeInterner,
+    files: &FileMap,
+) -> Option<CallHierarchyItem> {
+    let modifiers = interner.function_modifiers(&func_id);
+    let name_location = modifiers.name_location;
+    let selection = to_lsp_location(files, name_location.file, name_location.span)?;
+
+    // The range of the item spans from its name to the end of its body
+    let span = function_body_span(interner, func_id)
+        .map_or(name_location.span, |body_span| name_location.span.merge(body_span));
+    let range = to_lsp_location(files, name_location.file, span)?.range;
+
+    let func_meta = interner.function_meta(&func_id);
+    let kind = if func_meta.struct_id.is_some()
+        || func_meta.trait_id.is_some()
+        || func_meta.trait_impl.is_some()
+    {
+        SymbolKind::METHOD
+    } else {
+        SymbolKind::FUNCTION
+    };
+
+    // Show whether the function is unconstrained or an oracle, which is usually what we are
+    // looking for when following calls.
+    let mut detail = Vec::new();
+    if modifiers.is_comptime {
+        detail.push("comptime");
+    }
+    if modifiers.is_unconstrained {
+        detail.push("unconstrained");
+    }
+    if modifiers.attributes.function().map_or(false, |attribute| attribute.is_oracle()) {
+        detail.push("oracle");
+    }
+    let detail = if detail.is_empty() { None } else { Some(detail.join(" ")) };
+
+    Some(CallHierarchyItem {
+        name: modifiers.name.clone(),
+        kind,
+        tags: None,
+        detail,
+        uri: selection.uri,
+        range,
+        selection_range: selection.range,
+        data: None,
+    })
+}
+
+fn function_body_span(interner: &NodeInterner, func_id: FuncId) -> Option<Span> {
+    let body = interner.function(&func_id).try_as_expr()?;
+    Some(interner.expr_span(&body))
+}
+
+/// The functions declared in a file, together with the span of their body.
+pub(super) fn functions_in_file(interner: &NodeInterner, file: FileId) -> Vec<(FuncId, Span)> {
+    interner
+        .find_all_referenced_in_file(file)
+        .into_iter()
+        .filter_map(|(_, referenced, is_declaration)| match referenced {
+            ReferenceId::Function(func_id) if is_declaration => {
+                Some((func_id, function_body_span(interner, func_id)?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the innermost function whose body contains `span`.
+fn enclosing_function(functions: &[(FuncId, Span)], span: Span) -> Option<FuncId> {
+    functions
+        .iter()
+        .filter(|(_, body_span)| body_span.contains(&span))
+        .min_by_key(|(_, body_span)| body_span.end() - body_span.start())
+        .map(|(func_id, _)| *func_id)
+}
+
+#[cfg(test)]
+mod call_hierarchy_tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{notifications::on_did_open_text_document, test_utils};
+    use lsp_types::{
+        DidOpenTextDocumentParams, PartialResultParams, Position, TextDocumentItem, Url,
+        WorkDoneProgressParams,
+    };
+    use tokio::test;
+
+    async fn prepare_call_hierarchy(
+        state: &mut LspState,
+        uri: &Url,
+        position: Position,
+    ) -> CallHierarchyItem {
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+        };
+        let mut items = on_prepare_call_hierarchy_request(state, params)
+            .await
+            .expect("Could not execute on_prepare_call_hierarchy_request")
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        items.remove(0)
+    }
+
+    fn open_document(state: &mut LspState, uri: &Url, text: String) {
+        let _ = on_did_open_text_document(
+            state,
+            DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "noir".to_string(),
+                    version: 0,
+                    text,
+                },
+            },
+        );
+    }
+
+    async fn incoming_calls(state: &mut LspState, item: CallHierarchyItem) -> Vec<String> {
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        let calls = on_call_hierarchy_incoming_calls_request(state, params)
+            .await
+            .expect("Could not execute on_call_hierarchy_incoming_calls_request")
+            .unwrap();
+        calls.into_iter().map(|call| call.from.name).collect()
+    }
+
+    fn range(line: u32, character: u32, length: u32) -> Range {
+        Range {
+            start: Position { line, character },
+            end: Position { line, character: character + length },
+        }
+    }
+
+    #[test]
+    async fn test_prepare_call_hierarchy_from_call() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        // `lookup_owner(id)` inside `lookup`
+        let item =
+            prepare_call_hierarchy(&mut state, &noir_text_document, Position::new(34, 15)).await;
+        assert_eq!(item.name, "lookup_owner");
+        assert_eq!(item.kind, SymbolKind::FUNCTION);
+        assert_eq!(item.detail, Some("unconstrained oracle".to_string()));
+        assert_eq!(item.selection_range, range(31, 17, 12));
+    }
+
+    #[test]
+    async fn test_call_hierarchy_incoming_calls() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        let item =
+            prepare_call_hierarchy(&mut state, &noir_text_document, Position::new(33, 3)).await;
+        assert_eq!(item.name, "lookup");
+
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        let calls = on_call_hierarchy_incoming_calls_request(&mut state, params)
+            .await
+            .expect("Could not execute on_call_hierarchy_incoming_calls_request")
+            .unwrap();
+
+        let calls: Vec<_> =
+            calls.iter().map(|call| (call.from.name.as_str(), call.from_ranges.clone())).collect();
+        assert_eq!(calls, vec![("owner", vec![range(20, 8, 6)]), ("main", vec![range(39, 26, 6)])]);
+    }
+
+    #[test]
+    async fn test_call_hierarchy_incoming_calls_ignores_other_workspaces() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+        let file_path = noir_text_document.to_file_path().unwrap();
+        let package_root_dir = file_path.parent().unwrap().parent().unwrap().to_path_buf();
+        let text = std::fs::read_to_string(&file_path).unwrap();
+
+        // Cache a package with another caller of `lookup` as if it belonged to another
+        // workspace, whose FileIds happen to refer to a file with the same spans
+        let other_text = format!("{text}\nfn other_caller(x: Field) -> Field {{ lookup(x) }}\n");
+        open_document(&mut state, &noir_text_document, other_text);
+        let other_package_cache = state.package_cache.remove(&package_root_dir).unwrap();
+        state.package_cache.insert(PathBuf::from("/other_workspace"), other_package_cache);
+
+        open_document(&mut state, &noir_text_document, text);
+        let item =
+            prepare_call_hierarchy(&mut state, &noir_text_document, Position::new(33, 3)).await;
+        assert_eq!(incoming_calls(&mut state, item).await, vec!["owner", "main"]);
+    }
+
+    #[test]
+    async fn test_call_hierarchy_outgoing_calls() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        let item =
+            prepare_call_hierarchy(&mut state, &noir_text_document, Position::new(33, 3)).await;
+
+        let params = CallHierarchyOutgoingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        let calls = on_call_hierarchy_outgoing_calls_request(&mut state, params)
+            .await
+            .expect("Could not execute on_call_hierarchy_outgoing_calls_request")
+            .unwrap();
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].to.name, "lookup_owner");
+        assert_eq!(calls[0].from_ranges, vec![range(34, 13, 12)]);
+    }
+}
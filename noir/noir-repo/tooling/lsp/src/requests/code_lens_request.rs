@@ -1,4 +1,5 @@
 use std::future::{self, Future};
+use std::path::Path;
 
 use async_lsp::{ErrorCode, ResponseError};
 
@@ -6,6 +7,8 @@ use nargo::{package::Package, workspace::Workspace};
 use noirc_driver::check_crate;
 use noirc_frontend::hir::FunctionNameMatch;
 
+use super::call_hierarchy::functions_in_file;
+
 use crate::{
     byte_span_to_range, prepare_source, resolve_workspace_for_source_path,
     types::{CodeLens, CodeLensParams, CodeLensResult, Command},
@@ -52,17 +55,36 @@ fn on_code_lens_request_inner(
         ResponseError::new(ErrorCode::REQUEST_FAILED, "URI is not a valid file path")
     })?;
 
-    if let Some(collected_lenses) = state.cached_lenses.get(&params.text_document.uri.to_string()) {
-        return Ok(Some(collected_lenses.clone()));
+    let mut collected_lenses = if let Some(collected_lenses) =
+        state.cached_lenses.get(&params.text_document.uri.to_string())
+    {
+        collected_lenses.clone()
+    } else {
+        collect_and_cache_lenses(state, &params, &file_path)?
+    };
+
+    // Circuit costs are computed in the background, so their lenses aren't cached
+    collected_lenses.extend(collect_circuit_cost_lenses(state, &file_path));
+
+    if collected_lenses.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(collected_lenses))
     }
+}
 
-    let source_string = std::fs::read_to_string(&file_path).map_err(|_| {
+fn collect_and_cache_lenses(
+    state: &mut LspState,
+    params: &CodeLensParams,
+    file_path: &Path,
+) -> Result<Vec<CodeLens>, ResponseError> {
+    let source_string = std::fs::read_to_string(file_path).map_err(|_| {
         ResponseError::new(ErrorCode::REQUEST_FAILED, "Could not read file from disk")
     })?;
 
-    let workspace = resolve_workspace_for_source_path(file_path.as_path()).unwrap();
+    let workspace = resolve_workspace_for_source_path(file_path).unwrap();
 
-    let package = crate::workspace_package_for_file(&workspace, &file_path).ok_or_else(|| {
+    let package = crate::workspace_package_for_file(&workspace, file_path).ok_or_else(|| {
         ResponseError::new(ErrorCode::REQUEST_FAILED, "Could not find package for file")
     })?;
 
@@ -76,13 +98,12 @@ fn on_code_lens_request_inner(
 
     if collected_lenses.is_empty() {
         state.cached_lenses.remove(&params.text_document.uri.to_string());
-        Ok(None)
     } else {
         state
             .cached_lenses
             .insert(params.text_document.uri.to_string().clone(), collected_lenses.clone());
-        Ok(Some(collected_lenses))
     }
+    Ok(collected_lenses)
 }
 
 pub(crate) fn collect_lenses_for_package(
@@ -215,3 +236,109 @@ pub(crate) fn collect_lenses_for_package(
 
     lenses
 }
+
+/// Shows how many opcodes each function in `file_path` compiles to, once the circuit costs of its
+/// package were computed.
+fn collect_circuit_cost_lenses(state: &LspState, file_path: &Path) -> Vec<CodeLens> {
+    if !state.options.enable_circuit_costs {
+        return Vec::new();
+    }
+
+    let Ok(workspace) = resolve_workspace_for_source_path(file_path) else {
+        return Vec::new();
+    };
+    let Some(package) = crate::workspace_package_for_file(&workspace, file_path) else {
+        return Vec::new();
+    };
+    let Some(package_cache) = state.package_cache.get(&package.root_dir) else {
+        return Vec::new();
+    };
+    let Some(circuit_costs) = &package_cache.circuit_costs else {
+        return Vec::new();
+    };
+    let Some(workspace_cache) = state.workspace_cache.get(&workspace.root_dir) else {
+        return Vec::new();
+    };
+
+    let fm = &workspace_cache.file_manager;
+    let files = fm.as_file_map();
+    let Some(file_id) = fm.name_to_id(file_path.to_path_buf()) else {
+        return Vec::new();
+    };
+
+    let interner = &package_cache.node_interner;
+    let mut lenses = Vec::new();
+    for (func_id, body_span) in functions_in_file(interner, file_id) {
+        let opcode_counts = circuit_costs.opcodes_in_span(file_id, body_span);
+        if opcode_counts.total() == 0 {
+            continue;
+        }
+
+        let name_location = interner.function_modifiers(&func_id).name_location;
+        let range =
+            byte_span_to_range(files, file_id, name_location.span.into()).unwrap_or_default();
+
+        let info_command = Command {
+            title: opcode_counts.to_string(),
+            command: INFO_COMMAND.into(),
+            arguments: Some(package_selection_args(&workspace, package)),
+        };
+
+        lenses.push(CodeLens { range, command: Some(info_command), data: None });
+    }
+    lenses
+}
+
+#[cfg(test)]
+mod code_lens_tests {
+    use super::*;
+    use crate::{notifications, test_utils};
+    use lsp_types::{
+        PartialResultParams, Position, TextDocumentIdentifier, WorkDoneProgressParams,
+    };
+    use tokio::test;
+
+    #[test]
+    async fn test_circuit_cost_lenses() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("circuit_costs").await;
+        state.options.enable_circuit_costs = true;
+
+        let output_diagnostics = false;
+        notifications::process_workspace_for_noir_document(
+            &mut state,
+            noir_text_document.clone(),
+            output_diagnostics,
+        )
+        .unwrap();
+        test_utils::compute_circuit_costs(&mut state, &noir_text_document);
+
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri: noir_text_document },
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        let lenses = on_code_lens_request(&mut state, params)
+            .await
+            .expect("Could not execute on_code_lens_request")
+            .unwrap();
+
+        let cost_title = |line: u32, character: u32| {
+            lenses
+                .iter()
+                .filter(|lens| lens.range.start == Position { line, character })
+                .filter_map(|lens| lens.command.as_ref())
+                .find(|command| command.title.contains(" opcode"))
+                .map(|command| command.title.clone())
+                .unwrap_or_else(|| panic!("Expected a circuit cost lens at {line}:{character}"))
+        };
+
+        // `main` calls the unconstrained `double`, so both kinds of opcodes are attributed to it
+        let main_title = cost_title(0, 3);
+        assert!(main_title.contains("ACIR opcode"));
+        assert!(main_title.contains("Brillig opcode"));
+
+        let double_title = cost_title(11, 17);
+        assert!(!double_title.contains("ACIR"));
+        assert!(double_title.ends_with("Brillig opcodes"));
+    }
+}
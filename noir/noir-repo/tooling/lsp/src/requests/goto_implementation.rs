@@ -0,0 +1,145 @@
+use std::future::{self, Future};
+
+use async_lsp::ResponseError;
+use lsp_types::request::{GotoImplementationParams, GotoImplementationResponse};
+use noirc_errors::Location;
+use noirc_frontend::{
+    hir_def::traits::TraitImpl,
+    node_interner::{NodeInterner, ReferenceId},
+};
+
+use crate::LspState;
+
+use super::{process_request, to_lsp_location, type_hierarchy::implements_struct};
+
+pub(crate) fn on_goto_implementation_request(
+    state: &mut LspState,
+    params: GotoImplementationParams,
+) -> impl Future<Output = Result<Option<GotoImplementationResponse>, ResponseError>> {
+    let result = process_request(state, params.text_document_position_params, |args| {
+        let locations = implementation_locations(args.interner, args.location)?;
+        let mut locations: Vec<_> = locations
+            .into_iter()
+            .filter_map(|location| to_lsp_location(args.files, location.file, location.span))
+            .collect();
+        if locations.is_empty() {
+            return None;
+        }
+
+        locations.sort_by_key(|location| {
+            (location.uri.to_string(), location.range.start.line, location.range.start.character)
+        });
+        Some(GotoImplementationResponse::Array(locations))
+    });
+    future::ready(result)
+}
+
+/// Returns the locations of the implementations of the item referenced at `location`:
+/// - for a trait, its `impl` blocks
+/// - for a trait method, the methods implementing it
+/// - for a struct, the `impl` blocks of the traits it implements
+fn implementation_locations(interner: &NodeInterner, location: Location) -> Option<Vec<Location>> {
+    let trait_impls = interner.get_trait_implementations().map(|trait_impl| trait_impl.borrow());
+
+    match interner.find_referenced(location)? {
+        ReferenceId::Trait(trait_id) => Some(
+            trait_impls
+                .filter(|trait_impl| trait_impl.trait_id == trait_id)
+                .map(|trait_impl| impl_location(&trait_impl))
+                .collect(),
+        ),
+        ReferenceId::Struct(struct_id) => Some(
+            trait_impls
+                .filter(|trait_impl| implements_struct(trait_impl, struct_id))
+                .map(|trait_impl| impl_location(&trait_impl))
+                .collect(),
+        ),
+        ReferenceId::Function(func_id) => {
+            // Only trait methods have implementations, and methods in trait impls don't
+            let func_meta = interner.function_meta(&func_id);
+            let trait_id = func_meta.trait_id?;
+            if func_meta.trait_impl.is_some() {
+                return None;
+            }
+
+            let name = interner.function_name(&func_id);
+            Some(
+                trait_impls
+                    .filter(|trait_impl| trait_impl.trait_id == trait_id)
+                    .filter_map(|trait_impl| {
+                        trait_impl
+                            .methods
+                            .iter()
+                            .find(|method_id| interner.function_name(method_id) == name)
+                            .map(|method_id| interner.function_modifiers(method_id).name_location)
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The location of an `impl` block is the name of the trait it implements.
+fn impl_location(trait_impl: &TraitImpl) -> Location {
+    Location::new(trait_impl.ident.span(), trait_impl.file)
+}
+
+#[cfg(test)]
+mod goto_implementation_tests {
+    use super::*;
+    use crate::test_utils;
+    use lsp_types::{
+        PartialResultParams, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams,
+        WorkDoneProgressParams,
+    };
+    use tokio::test;
+
+    async fn get_implementation_ranges(line: u32, character: u32) -> Vec<Range> {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        let params = GotoImplementationParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: noir_text_document },
+                position: Position { line, character },
+            },
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+
+        let response = on_goto_implementation_request(&mut state, params)
+            .await
+            .expect("Could not execute on_goto_implementation_request")
+            .unwrap();
+
+        let GotoImplementationResponse::Array(locations) = response else {
+            panic!("Expected response to be an array");
+        };
+        locations.iter().map(|location| location.range).collect()
+    }
+
+    fn range(line: u32, character: u32, length: u32) -> Range {
+        Range {
+            start: Position { line, character },
+            end: Position { line, character: character + length },
+        }
+    }
+
+    #[test]
+    async fn test_goto_implementation_of_trait() {
+        let ranges = get_implementation_ranges(0, 6).await;
+        assert_eq!(ranges, vec![range(12, 5, 6), range(24, 5, 6)]);
+    }
+
+    #[test]
+    async fn test_goto_implementation_of_trait_method() {
+        let ranges = get_implementation_ranges(1, 7).await;
+        assert_eq!(ranges, vec![range(13, 7, 4), range(25, 7, 4)]);
+    }
+
+    #[test]
+    async fn test_goto_implementation_of_struct() {
+        let ranges = get_implementation_ranges(8, 7).await;
+        assert_eq!(ranges, vec![range(12, 5, 6), range(18, 5, 3)]);
+    }
+}
@@ -1,7 +1,10 @@
-use std::future::{self, Future};
+use std::{
+    collections::HashSet,
+    future::{self, Future},
+};
 
 use async_lsp::ResponseError;
-use fm::{FileId, FileMap, PathString};
+use fm::{codespan_files::Files, FileId, FileMap, PathString};
 use lsp_types::{
     InlayHint, InlayHintKind, InlayHintLabel, InlayHintLabelPart, InlayHintParams, Position, Range,
     TextDocumentPositionParams, TextEdit,
@@ -20,7 +23,7 @@ use noirc_frontend::{
     Kind, Type, TypeBinding, TypeVariable,
 };
 
-use crate::{utils, LspState};
+use crate::{circuit_costs::CircuitCosts, utils, LspState};
 
 use super::{process_request, to_lsp_location, InlayHintsOptions};
 
@@ -45,8 +48,14 @@ pub(crate) fn on_inlay_hint_request(
             let span = utils::range_to_byte_span(args.files, file_id, &params.range)
                 .map(|range| Span::from(range.start as u32..range.end as u32));
 
-            let mut collector =
-                InlayHintCollector::new(args.files, file_id, args.interner, span, options);
+            let mut collector = InlayHintCollector::new(
+                args.files,
+                file_id,
+                args.interner,
+                args.circuit_costs,
+                span,
+                options,
+            );
             parsed_moduled.accept(&mut collector);
             collector.inlay_hints
         })
@@ -58,9 +67,13 @@ pub(crate) struct InlayHintCollector<'a> {
     files: &'a FileMap,
     file_id: FileId,
     interner: &'a NodeInterner,
+    circuit_costs: Option<&'a CircuitCosts>,
     span: Option<Span>,
     options: InlayHintsOptions,
     inlay_hints: Vec<InlayHint>,
+    /// Lines that already have a circuit cost hint. Statements are visited before the statements
+    /// nested in them, so a line only shows the cost of the outermost statement ending there.
+    circuit_cost_hint_lines: HashSet<u32>,
 }
 
 impl<'a> InlayHintCollector<'a> {
@@ -68,10 +81,20 @@ impl<'a> InlayHintCollector<'a> {
         files: &'a FileMap,
         file_id: FileId,
         interner: &'a NodeInterner,
+        circuit_costs: Option<&'a CircuitCosts>,
         span: Option<Span>,
         options: InlayHintsOptions,
     ) -> InlayHintCollector<'a> {
-        InlayHintCollector { files, file_id, interner, span, options, inlay_hints: Vec::new() }
+        InlayHintCollector {
+            files,
+            file_id,
+            interner,
+            circuit_costs,
+            span,
+            options,
+            inlay_hints: Vec::new(),
+            circuit_cost_hint_lines: HashSet::new(),
+        }
     }
 
     fn collect_in_ident(&mut self, ident: &Ident, editable: bool) {
@@ -265,6 +288,43 @@ impl<'a> InlayHintCollector<'a> {
             }
         }
     }
+
+    /// Shows how many opcodes a statement compiles to at the end of the line where it ends,
+    /// if it's at least `min_opcodes`.
+    fn show_circuit_cost_hint(&mut self, span: Span) {
+        let Some(circuit_costs) = self.circuit_costs else {
+            return;
+        };
+        if !self.options.circuit_cost_hints.enabled {
+            return;
+        }
+
+        let opcode_counts = circuit_costs.opcodes_in_span(self.file_id, span);
+        if opcode_counts.total() < self.options.circuit_cost_hints.min_opcodes {
+            return;
+        }
+
+        let Some(lsp_location) = to_lsp_location(self.files, self.file_id, span) else {
+            return;
+        };
+        let line = lsp_location.range.end.line;
+        if !self.circuit_cost_hint_lines.insert(line) {
+            return;
+        }
+
+        let Some(position) = self.end_of_line_position(line) else {
+            return;
+        };
+        self.push_text_hint(position, format!(" {opcode_counts}"));
+    }
+
+    fn end_of_line_position(&self, line: u32) -> Option<Position> {
+        let source = self.files.source(self.file_id).ok()?;
+        let line_span = self.files.line_range(self.file_id, line as usize).ok()?;
+        let line_str = source.get(line_span)?.trim_end();
+        let character = line_str.chars().map(|char| char.len_utf16() as u32).sum();
+        Some(Position { line, character })
+    }
 }
 
 impl<'a> Visitor for InlayHintCollector<'a> {
@@ -305,7 +365,13 @@ impl<'a> Visitor for InlayHintCollector<'a> {
     }
 
     fn visit_statement(&mut self, statement: &Statement) -> bool {
-        self.intersects_span(statement.span)
+        if !self.intersects_span(statement.span) {
+            return false;
+        }
+
+        self.show_circuit_cost_hint(statement.span);
+
+        true
     }
 
     fn visit_let_statement(&mut self, let_statement: &LetStatement) -> bool {
@@ -540,7 +606,10 @@ fn get_expression_name(expression: &Expression) -> Option<String> {
 #[cfg(test)]
 mod inlay_hints_tests {
     use crate::{
-        requests::{ClosingBraceHintsOptions, ParameterHintsOptions, TypeHintsOptions},
+        requests::{
+            CircuitCostHintsOptions, ClosingBraceHintsOptions, ParameterHintsOptions,
+            TypeHintsOptions,
+        },
         test_utils,
     };
 
@@ -577,6 +646,7 @@ mod inlay_hints_tests {
             type_hints: TypeHintsOptions { enabled: false },
             parameter_hints: ParameterHintsOptions { enabled: false },
             closing_brace_hints: ClosingBraceHintsOptions { enabled: false, min_lines: 25 },
+            circuit_cost_hints: CircuitCostHintsOptions { enabled: false, min_opcodes: 100 },
         }
     }
 
@@ -585,6 +655,7 @@ mod inlay_hints_tests {
             type_hints: TypeHintsOptions { enabled: true },
             parameter_hints: ParameterHintsOptions { enabled: false },
             closing_brace_hints: ClosingBraceHintsOptions { enabled: false, min_lines: 25 },
+            circuit_cost_hints: CircuitCostHintsOptions { enabled: false, min_opcodes: 100 },
         }
     }
 
@@ -593,6 +664,7 @@ mod inlay_hints_tests {
             type_hints: TypeHintsOptions { enabled: false },
             parameter_hints: ParameterHintsOptions { enabled: true },
             closing_brace_hints: ClosingBraceHintsOptions { enabled: false, min_lines: 25 },
+            circuit_cost_hints: CircuitCostHintsOptions { enabled: false, min_opcodes: 100 },
         }
     }
 
@@ -601,6 +673,7 @@ mod inlay_hints_tests {
             type_hints: TypeHintsOptions { enabled: false },
             parameter_hints: ParameterHintsOptions { enabled: false },
             closing_brace_hints: ClosingBraceHintsOptions { enabled: true, min_lines },
+            circuit_cost_hints: CircuitCostHintsOptions { enabled: false, min_opcodes: 100 },
         }
     }
 
@@ -955,4 +1028,48 @@ mod inlay_hints_tests {
             panic!("Expected InlayHintLabel::String, got {:?}", inlay_hint.label);
         }
     }
+
+    #[test]
+    async fn test_shows_circuit_cost_inlay_hints() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("circuit_costs").await;
+        state.options.enable_circuit_costs = true;
+        state.options.inlay_hints = no_hints();
+        state.options.inlay_hints.circuit_cost_hints =
+            CircuitCostHintsOptions { enabled: true, min_opcodes: 1 };
+
+        let output_diagnostics = false;
+        crate::notifications::process_workspace_for_noir_document(
+            &mut state,
+            noir_text_document.clone(),
+            output_diagnostics,
+        )
+        .unwrap();
+        test_utils::compute_circuit_costs(&mut state, &noir_text_document);
+
+        let inlay_hints = on_inlay_hint_request(
+            &mut state,
+            InlayHintParams {
+                work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+                text_document: TextDocumentIdentifier { uri: noir_text_document },
+                range: Range {
+                    start: Position { line: 3, character: 0 },
+                    end: Position { line: 4, character: 0 },
+                },
+            },
+        )
+        .await
+        .expect("Could not execute on_inlay_hint_request")
+        .unwrap();
+
+        // `let double = unsafe { double(x) };` gets the cost of calling `double`
+        assert_eq!(inlay_hints.len(), 1);
+
+        let inlay_hint = &inlay_hints[0];
+        assert_eq!(inlay_hint.position, Position { line: 3, character: 38 });
+        if let InlayHintLabel::String(label) = &inlay_hint.label {
+            assert!(label.ends_with("Brillig opcodes"));
+        } else {
+            panic!("Expected InlayHintLabel::String, got {:?}", inlay_hint.label);
+        }
+    }
 }
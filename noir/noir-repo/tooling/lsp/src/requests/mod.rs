@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::{collections::HashMap, future::Future};
 
+use crate::circuit_costs::CircuitCosts;
 use crate::{insert_all_files_for_workspace_into_file_manager, parse_diff, PackageCacheData};
 use crate::{
     resolve_workspace_for_source_path,
@@ -10,10 +11,12 @@ use crate::{
 use async_lsp::{ErrorCode, ResponseError};
 use fm::{codespan_files::Error, FileMap, PathString};
 use lsp_types::{
-    CodeActionKind, DeclarationCapability, Location, Position, TextDocumentPositionParams,
+    CallHierarchyServerCapability, CodeActionKind, DeclarationCapability,
+    ImplementationProviderCapability, Location, Position, TextDocumentPositionParams,
     TextDocumentSyncCapability, TextDocumentSyncKind, TypeDefinitionProviderCapability, Url,
     WorkDoneProgressOptions,
 };
+use nargo::workspace::Workspace;
 use nargo_fmt::Config;
 
 use noirc_frontend::graph::CrateId;
@@ -37,29 +40,43 @@ use crate::{
 // They are not attached to the `NargoLspService` struct so they can be unit tested with only `LspState`
 // and params passed in.
 
+mod call_hierarchy;
 mod code_action;
 mod code_lens_request;
 mod completion;
 mod document_symbol;
 mod goto_declaration;
 mod goto_definition;
+mod goto_implementation;
 mod hover;
 mod inlay_hint;
 mod references;
 mod rename;
+mod semantic_tokens;
 mod signature_help;
 mod test_run;
 mod tests;
+mod type_hierarchy;
+mod workspace_symbol;
 
 pub(crate) use {
-    code_action::on_code_action_request, code_lens_request::collect_lenses_for_package,
-    code_lens_request::on_code_lens_request, completion::on_completion_request,
-    document_symbol::on_document_symbol_request, goto_declaration::on_goto_declaration_request,
-    goto_definition::on_goto_definition_request, goto_definition::on_goto_type_definition_request,
-    hover::on_hover_request, inlay_hint::on_inlay_hint_request, references::on_references_request,
+    call_hierarchy::on_call_hierarchy_incoming_calls_request,
+    call_hierarchy::on_call_hierarchy_outgoing_calls_request,
+    call_hierarchy::on_prepare_call_hierarchy_request, code_action::on_code_action_request,
+    code_lens_request::collect_lenses_for_package, code_lens_request::on_code_lens_request,
+    completion::on_completion_request, document_symbol::on_document_symbol_request,
+    goto_declaration::on_goto_declaration_request, goto_definition::on_goto_definition_request,
+    goto_definition::on_goto_type_definition_request,
+    goto_implementation::on_goto_implementation_request, hover::on_hover_request,
+    inlay_hint::on_inlay_hint_request, references::on_references_request,
     rename::on_prepare_rename_request, rename::on_rename_request,
-    signature_help::on_signature_help_request, test_run::on_test_run_request,
-    tests::on_tests_request,
+    semantic_tokens::on_semantic_tokens_full_request,
+    semantic_tokens::on_semantic_tokens_range_request, signature_help::on_signature_help_request,
+    test_run::on_test_run_request, tests::on_tests_request,
+    type_hierarchy::on_prepare_type_hierarchy_request,
+    type_hierarchy::on_type_hierarchy_subtypes_request,
+    type_hierarchy::on_type_hierarchy_supertypes_request,
+    workspace_symbol::on_workspace_symbol_request,
 };
 
 /// LSP client will send initialization request after the server has started.
@@ -74,6 +91,19 @@ pub(crate) struct LspInitializationOptions {
     #[serde(rename = "enableParsingCache", default = "default_enable_parsing_cache")]
     pub(crate) enable_parsing_cache: bool,
 
+    /// Controls whether diagnostics are published while a document is being edited, rather than
+    /// only when it's opened or saved. By default this will be set to true (enabled).
+    #[serde(
+        rename = "enableDiagnosticsOnChange",
+        default = "default_enable_diagnostics_on_change"
+    )]
+    pub(crate) enable_diagnostics_on_change: bool,
+
+    /// Controls whether packages are compiled when a document is opened or saved, to show
+    /// how many opcodes each function compiles to. By default this is disabled.
+    #[serde(rename = "enableCircuitCosts", default = "default_enable_circuit_costs")]
+    pub(crate) enable_circuit_costs: bool,
+
     #[serde(rename = "inlayHints", default = "default_inlay_hints")]
     pub(crate) inlay_hints: InlayHintsOptions,
 }
@@ -88,6 +118,9 @@ pub(crate) struct InlayHintsOptions {
 
     #[serde(rename = "closingBraceHints", default = "default_closing_brace_hints")]
     pub(crate) closing_brace_hints: ClosingBraceHintsOptions,
+
+    #[serde(rename = "circuitCostHints", default = "default_circuit_cost_hints")]
+    pub(crate) circuit_cost_hints: CircuitCostHintsOptions,
 }
 
 #[derive(Debug, Deserialize, Serialize, Copy, Clone)]
@@ -111,6 +144,16 @@ pub(crate) struct ClosingBraceHintsOptions {
     pub(crate) min_lines: u32,
 }
 
+/// Circuit cost hints are only shown if `enableCircuitCosts` is set.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone)]
+pub(crate) struct CircuitCostHintsOptions {
+    #[serde(rename = "enabled", default = "default_circuit_cost_hints_enabled")]
+    pub(crate) enabled: bool,
+
+    #[serde(rename = "minOpcodes", default = "default_circuit_cost_min_opcodes")]
+    pub(crate) min_opcodes: usize,
+}
+
 fn default_enable_code_lens() -> bool {
     true
 }
@@ -119,11 +162,20 @@ fn default_enable_parsing_cache() -> bool {
     true
 }
 
+fn default_enable_diagnostics_on_change() -> bool {
+    true
+}
+
+fn default_enable_circuit_costs() -> bool {
+    false
+}
+
 fn default_inlay_hints() -> InlayHintsOptions {
     InlayHintsOptions {
         type_hints: default_type_hints(),
         parameter_hints: default_parameter_hints(),
         closing_brace_hints: default_closing_brace_hints(),
+        circuit_cost_hints: default_circuit_cost_hints(),
     }
 }
 
@@ -158,11 +210,28 @@ fn default_closing_brace_min_lines() -> u32 {
     25
 }
 
+fn default_circuit_cost_hints() -> CircuitCostHintsOptions {
+    CircuitCostHintsOptions {
+        enabled: default_circuit_cost_hints_enabled(),
+        min_opcodes: default_circuit_cost_min_opcodes(),
+    }
+}
+
+fn default_circuit_cost_hints_enabled() -> bool {
+    true
+}
+
+fn default_circuit_cost_min_opcodes() -> usize {
+    100
+}
+
 impl Default for LspInitializationOptions {
     fn default() -> Self {
         Self {
             enable_code_lens: default_enable_code_lens(),
             enable_parsing_cache: default_enable_parsing_cache(),
+            enable_diagnostics_on_change: default_enable_diagnostics_on_change(),
+            enable_circuit_costs: default_enable_circuit_costs(),
             inlay_hints: default_inlay_hints(),
         }
     }
@@ -180,7 +249,8 @@ pub(crate) fn on_initialize(
     state.options = initialization_options;
 
     async move {
-        let text_document_sync = TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL);
+        let text_document_sync =
+            TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL);
 
         let code_lens = if initialization_options.enable_code_lens {
             Some(CodeLensOptions { resolve_provider: Some(false) })
@@ -205,6 +275,9 @@ pub(crate) fn on_initialize(
                 definition_provider: Some(lsp_types::OneOf::Left(true)),
                 declaration_provider: Some(DeclarationCapability::Simple(true)),
                 type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+                implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                type_hierarchy_provider: Some(true),
                 rename_provider: Some(lsp_types::OneOf::Right(lsp_types::RenameOptions {
                     prepare_provider: Some(true),
                     work_done_progress_options: WorkDoneProgressOptions {
@@ -264,6 +337,26 @@ pub(crate) fn on_initialize(
                     },
                     resolve_provider: None,
                 })),
+                workspace_symbol_provider: Some(lsp_types::OneOf::Right(
+                    lsp_types::WorkspaceSymbolOptions {
+                        work_done_progress_options: WorkDoneProgressOptions {
+                            work_done_progress: None,
+                        },
+                        resolve_provider: None,
+                    },
+                )),
+                semantic_tokens_provider: Some(
+                    lsp_types::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp_types::SemanticTokensOptions {
+                            work_done_progress_options: WorkDoneProgressOptions {
+                                work_done_progress: None,
+                            },
+                            legend: semantic_tokens::semantic_tokens_legend(),
+                            range: Some(true),
+                            full: Some(lsp_types::SemanticTokensFullOptions::Bool(true)),
+                        },
+                    ),
+                ),
             },
             server_info: None,
         })
@@ -411,11 +504,13 @@ pub(crate) struct ProcessRequestCallbackArgs<'a> {
     files: &'a FileMap,
     interner: &'a NodeInterner,
     package_cache: &'a HashMap<PathBuf, PackageCacheData>,
+    workspace: &'a Workspace,
     crate_id: CrateId,
     crate_name: String,
     dependencies: &'a Vec<Dependency>,
     def_maps: &'a BTreeMap<CrateId, CrateDefMap>,
     usage_tracker: &'a UsageTracker,
+    circuit_costs: Option<&'a CircuitCosts>,
 }
 
 pub(crate) fn process_request<F, T>(
@@ -469,11 +564,13 @@ where
         files,
         interner,
         package_cache: &state.package_cache,
+        workspace: &workspace,
         crate_id,
         crate_name: package.name.to_string(),
         dependencies: &crate_graph[crate_id].dependencies,
         def_maps,
         usage_tracker,
+        circuit_costs: package_cache_data.circuit_costs.as_ref(),
     }))
 }
 
@@ -509,10 +606,12 @@ where
     let interner;
     let def_maps;
     let usage_tracker;
+    let mut circuit_costs = None;
     if let Some(package_cache) = state.package_cache.get(&package.root_dir) {
         interner = &package_cache.node_interner;
         def_maps = &package_cache.def_maps;
         usage_tracker = &package_cache.usage_tracker;
+        circuit_costs = package_cache.circuit_costs.as_ref();
     } else {
         // We ignore the warnings and errors produced by compilation while resolving the definition
         let _ = noirc_driver::check_crate(&mut context, crate_id, &Default::default());
@@ -534,11 +633,13 @@ where
         files,
         interner,
         package_cache: &state.package_cache,
+        workspace: &workspace,
         crate_id,
         crate_name: package.name.to_string(),
         dependencies: &context.crate_graph[crate_id].dependencies,
         def_maps,
         usage_tracker,
+        circuit_costs,
     }))
 }
 
@@ -638,7 +739,7 @@ mod initialization {
             response.capabilities,
             ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL
+                    TextDocumentSyncKind::INCREMENTAL
                 )),
                 code_lens_provider: Some(CodeLensOptions { resolve_provider: Some(false) }),
                 document_formatting_provider: true,
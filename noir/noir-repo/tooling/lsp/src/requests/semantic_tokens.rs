@@ -0,0 +1,509 @@
+use std::{
+    collections::HashSet,
+    future::{self, Future},
+};
+
+use async_lsp::ResponseError;
+use fm::{FileId, FileMap, PathString};
+use lsp_types::{
+    Position, Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensLegend, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult, TextDocumentIdentifier,
+    TextDocumentPositionParams, Url,
+};
+use noirc_errors::Span;
+use noirc_frontend::{
+    node_interner::{DefinitionId, DefinitionKind, FuncId, GlobalId, NodeInterner, ReferenceId},
+    token::SecondaryAttribute,
+};
+
+use crate::{utils, LspState};
+
+use super::process_request;
+
+/// The token types the server reports, in the order of their index in the legend.
+const TOKEN_TYPES: [SemanticTokenType; 10] = [
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::STRUCT,
+    SemanticTokenType::INTERFACE,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::TYPE_PARAMETER,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+];
+
+/// The token modifiers the server reports, in the order of their bit in a modifiers bitset.
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 8] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::STATIC,
+    SemanticTokenModifier::DEPRECATED,
+    SemanticTokenModifier::new("mutable"),
+    SemanticTokenModifier::new("comptime"),
+    SemanticTokenModifier::new("unconstrained"),
+    SemanticTokenModifier::new("oracle"),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TokenType {
+    Namespace,
+    Struct,
+    Interface,
+    Type,
+    TypeParameter,
+    Function,
+    Method,
+    Property,
+    Variable,
+    Parameter,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TokenModifier {
+    Declaration,
+    Readonly,
+    Static,
+    Deprecated,
+    Mutable,
+    Comptime,
+    Unconstrained,
+    Oracle,
+}
+
+impl TokenModifier {
+    fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+pub(crate) fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+pub(crate) fn on_semantic_tokens_full_request(
+    state: &mut LspState,
+    params: SemanticTokensParams,
+) -> impl Future<Output = Result<Option<SemanticTokensResult>, ResponseError>> {
+    let result = semantic_tokens(state, params.text_document.uri, None)
+        .map(|tokens| tokens.map(SemanticTokensResult::Tokens));
+    future::ready(result)
+}
+
+pub(crate) fn on_semantic_tokens_range_request(
+    state: &mut LspState,
+    params: SemanticTokensRangeParams,
+) -> impl Future<Output = Result<Option<SemanticTokensRangeResult>, ResponseError>> {
+    let result = semantic_tokens(state, params.text_document.uri, Some(params.range))
+        .map(|tokens| tokens.map(SemanticTokensRangeResult::Tokens));
+    future::ready(result)
+}
+
+/// Returns `None` for documents that aren't files, as their tokens can't be found.
+fn semantic_tokens(
+    state: &mut LspState,
+    uri: Url,
+    range: Option<Range>,
+) -> Result<Option<SemanticTokens>, ResponseError> {
+    let Ok(file_path) = uri.to_file_path() else {
+        return Ok(None);
+    };
+
+    let text_document_position_params = TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier { uri: uri.clone() },
+        position: Position { line: 0, character: 0 },
+    };
+
+    process_request(state, text_document_position_params, |args| {
+        let path = PathString::from_path(file_path);
+        args.files.get_file_id(&path).map(|file_id| {
+            let span = range
+                .and_then(|range| utils::range_to_byte_span(args.files, file_id, &range))
+                .map(|range| Span::from(range.start as u32..range.end as u32));
+
+            let collector = SemanticTokenCollector::new(args.files, file_id, args.interner);
+            SemanticTokens { result_id: None, data: collector.collect(span) }
+        })
+    })
+}
+
+struct SemanticTokenCollector<'a> {
+    files: &'a FileMap,
+    file_id: FileId,
+    interner: &'a NodeInterner,
+}
+
+impl<'a> SemanticTokenCollector<'a> {
+    fn new(files: &'a FileMap, file_id: FileId, interner: &'a NodeInterner) -> Self {
+        Self { files, file_id, interner }
+    }
+
+    /// Collects the tokens of the file, or only those inside `span` if given, encoded relative to
+    /// each other as the LSP specification requires.
+    ///
+    /// Tokens spanning multiple lines are dropped: clients only support them through the
+    /// `multilineTokenSupport` capability, and references are names which rarely span lines.
+    fn collect(&self, span: Option<Span>) -> Vec<SemanticToken> {
+        let references = self.interner.find_all_referenced_in_file(self.file_id);
+
+        // Parameters are locals like any other, so we find them through the functions declared
+        // in this file.
+        let parameters: HashSet<DefinitionId> = references
+            .iter()
+            .filter_map(|(_, referenced, is_declaration)| match referenced {
+                ReferenceId::Function(func_id) if *is_declaration => Some(func_id),
+                _ => None,
+            })
+            .filter_map(|func_id| self.interner.try_function_meta(func_id))
+            .flat_map(|func_meta| func_meta.parameter_idents.iter().map(|ident| ident.id))
+            .collect();
+
+        let mut tokens = Vec::new();
+        let mut previous_position = Position { line: 0, character: 0 };
+        for (reference_span, referenced, is_declaration) in references {
+            if let Some(span) = span {
+                if reference_span.end() <= span.start() || reference_span.start() >= span.end() {
+                    continue;
+                }
+            }
+
+            let Some((token_type, mut modifiers)) = self.classify(referenced, &parameters) else {
+                continue;
+            };
+            if is_declaration {
+                modifiers |= TokenModifier::Declaration.bit();
+            }
+
+            let Some(range) = crate::byte_span_to_range(
+                self.files,
+                self.file_id,
+                reference_span.start() as usize..reference_span.end() as usize,
+            ) else {
+                continue;
+            };
+
+            // Tokens can't span multiple lines, see above
+            if range.start.line != range.end.line {
+                continue;
+            }
+
+            let delta_line = range.start.line - previous_position.line;
+            let delta_start = if delta_line == 0 {
+                range.start.character - previous_position.character
+            } else {
+                range.start.character
+            };
+            tokens.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length: range.end.character - range.start.character,
+                token_type: token_type as u32,
+                token_modifiers_bitset: modifiers,
+            });
+            previous_position = range.start;
+        }
+        tokens
+    }
+
+    fn classify(
+        &self,
+        referenced: ReferenceId,
+        parameters: &HashSet<DefinitionId>,
+    ) -> Option<(TokenType, u32)> {
+        match referenced {
+            ReferenceId::Module(_) => Some((TokenType::Namespace, 0)),
+            ReferenceId::Struct(struct_id) => {
+                let attributes = self.interner.struct_attributes(&struct_id);
+                Some((TokenType::Struct, deprecated_modifier(attributes)))
+            }
+            ReferenceId::StructMember(..) => Some((TokenType::Property, 0)),
+            ReferenceId::Trait(_) => Some((TokenType::Interface, 0)),
+            ReferenceId::Alias(_) => Some((TokenType::Type, 0)),
+            ReferenceId::Function(func_id) => Some(self.function(func_id)),
+            ReferenceId::Global(global_id) => Some(self.global(global_id)),
+            ReferenceId::Local(definition_id) => {
+                let definition = self.interner.definition(definition_id);
+                match &definition.kind {
+                    DefinitionKind::Function(func_id) => Some(self.function(*func_id)),
+                    DefinitionKind::Global(global_id) => Some(self.global(*global_id)),
+                    DefinitionKind::NumericGeneric(..) => Some((TokenType::TypeParameter, 0)),
+                    DefinitionKind::Local(_) => {
+                        let token_type = if parameters.contains(&definition_id) {
+                            TokenType::Parameter
+                        } else {
+                            TokenType::Variable
+                        };
+                        let mut modifiers = 0;
+                        if definition.mutable {
+                            modifiers |= TokenModifier::Mutable.bit();
+                        }
+                        if definition.comptime {
+                            modifiers |= TokenModifier::Comptime.bit();
+                        }
+                        Some((token_type, modifiers))
+                    }
+                }
+            }
+            ReferenceId::Reference(..) => None,
+        }
+    }
+
+    fn function(&self, func_id: FuncId) -> (TokenType, u32) {
+        let func_meta = self.interner.function_meta(&func_id);
+        let token_type = if func_meta.struct_id.is_some()
+            || func_meta.trait_id.is_some()
+            || func_meta.trait_impl.is_some()
+        {
+            TokenType::Method
+        } else {
+            TokenType::Function
+        };
+
+        let modifiers = self.interner.function_modifiers(&func_id);
+        let mut bitset = deprecated_modifier(&modifiers.attributes.secondary);
+        if modifiers.is_comptime {
+            bitset |= TokenModifier::Comptime.bit();
+        }
+        if modifiers.is_unconstrained {
+            bitset |= TokenModifier::Unconstrained.bit();
+        }
+        if modifiers.attributes.function().map_or(false, |attribute| attribute.is_oracle()) {
+            bitset |= TokenModifier::Oracle.bit();
+        }
+        (token_type, bitset)
+    }
+
+    fn global(&self, global_id: GlobalId) -> (TokenType, u32) {
+        let global = self.interner.get_global(global_id);
+        let mut modifiers = TokenModifier::Readonly.bit() | TokenModifier::Static.bit();
+        modifiers |= deprecated_modifier(self.interner.global_attributes(&global_id));
+        if self.interner.definition(global.definition_id).comptime {
+            modifiers |= TokenModifier::Comptime.bit();
+        }
+        (TokenType::Variable, modifiers)
+    }
+}
+
+fn deprecated_modifier(attributes: &[SecondaryAttribute]) -> u32 {
+    if attributes.iter().any(|attribute| matches!(attribute, SecondaryAttribute::Deprecated(_))) {
+        TokenModifier::Deprecated.bit()
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod semantic_tokens_tests {
+    use super::*;
+    use crate::test_utils;
+    use lsp_types::{PartialResultParams, WorkDoneProgressParams};
+    use tokio::test;
+
+    /// A decoded token: its line, start character, length, type and modifiers.
+    type Token = (u32, u32, u32, TokenType, u32);
+
+    async fn get_semantic_tokens(range: Option<Range>) -> Vec<Token> {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("semantic_tokens").await;
+        let text_document = TextDocumentIdentifier { uri: noir_text_document };
+        let work_done_progress_params = WorkDoneProgressParams { work_done_token: None };
+        let partial_result_params = PartialResultParams { partial_result_token: None };
+
+        let tokens = match range {
+            Some(range) => {
+                let params = SemanticTokensRangeParams {
+                    work_done_progress_params,
+                    partial_result_params,
+                    text_document,
+                    range,
+                };
+                let result = on_semantic_tokens_range_request(&mut state, params)
+                    .await
+                    .expect("Could not execute on_semantic_tokens_range_request")
+                    .unwrap();
+                let SemanticTokensRangeResult::Tokens(tokens) = result else {
+                    panic!("Expected tokens");
+                };
+                tokens
+            }
+            None => {
+                let params = SemanticTokensParams {
+                    work_done_progress_params,
+                    partial_result_params,
+                    text_document,
+                };
+                let result = on_semantic_tokens_full_request(&mut state, params)
+                    .await
+                    .expect("Could not execute on_semantic_tokens_full_request")
+                    .unwrap();
+                let SemanticTokensResult::Tokens(tokens) = result else {
+                    panic!("Expected tokens");
+                };
+                tokens
+            }
+        };
+
+        decode(&tokens.data)
+    }
+
+    fn decode(tokens: &[SemanticToken]) -> Vec<Token> {
+        let token_types = [
+            TokenType::Namespace,
+            TokenType::Struct,
+            TokenType::Interface,
+            TokenType::Type,
+            TokenType::TypeParameter,
+            TokenType::Function,
+            TokenType::Method,
+            TokenType::Property,
+            TokenType::Variable,
+            TokenType::Parameter,
+        ];
+
+        let mut line = 0;
+        let mut character = 0;
+        tokens
+            .iter()
+            .map(|token| {
+                if token.delta_line == 0 {
+                    character += token.delta_start;
+                } else {
+                    line += token.delta_line;
+                    character = token.delta_start;
+                }
+                let token_type = token_types[token.token_type as usize];
+                (line, character, token.length, token_type, token.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    fn find_token(tokens: &[Token], line: u32, character: u32) -> Token {
+        *tokens
+            .iter()
+            .find(|token| token.0 == line && token.1 == character)
+            .unwrap_or_else(|| panic!("Expected a token at {line}:{character}"))
+    }
+
+    #[test]
+    async fn test_legend_matches_token_types() {
+        let legend = semantic_tokens_legend();
+        assert_eq!(legend.token_types[TokenType::Parameter as usize], SemanticTokenType::PARAMETER);
+        assert_eq!(
+            legend.token_modifiers[TokenModifier::Oracle as usize],
+            SemanticTokenModifier::new("oracle")
+        );
+    }
+
+    #[test]
+    async fn test_semantic_tokens_for_declarations() {
+        let tokens = get_semantic_tokens(None).await;
+        let declaration = TokenModifier::Declaration.bit();
+
+        assert_eq!(find_token(&tokens, 0, 7), (0, 7, 5, TokenType::Struct, declaration));
+        assert_eq!(find_token(&tokens, 1, 4), (1, 4, 1, TokenType::Property, declaration));
+        assert_eq!(find_token(&tokens, 4, 6), (4, 6, 5, TokenType::Interface, declaration));
+        assert_eq!(
+            find_token(&tokens, 14, 7),
+            (
+                14,
+                7,
+                5,
+                TokenType::Variable,
+                declaration | TokenModifier::Readonly.bit() | TokenModifier::Static.bit()
+            )
+        );
+        assert_eq!(
+            find_token(&tokens, 17, 3),
+            (17, 3, 3, TokenType::Function, declaration | TokenModifier::Deprecated.bit())
+        );
+        assert_eq!(
+            find_token(&tokens, 20, 17),
+            (
+                20,
+                17,
+                9,
+                TokenType::Function,
+                declaration | TokenModifier::Unconstrained.bit() | TokenModifier::Oracle.bit()
+            )
+        );
+        assert_eq!(
+            find_token(&tokens, 26, 12),
+            (26, 12, 8, TokenType::Function, declaration | TokenModifier::Comptime.bit())
+        );
+        assert_eq!(find_token(&tokens, 22, 24), (22, 24, 5, TokenType::Parameter, declaration));
+    }
+
+    #[test]
+    async fn test_semantic_tokens_for_references() {
+        let tokens = get_semantic_tokens(None).await;
+
+        // let mut point = Point { x };
+        assert_eq!(
+            find_token(&tokens, 29, 12),
+            (
+                29,
+                12,
+                5,
+                TokenType::Variable,
+                TokenModifier::Declaration.bit() | TokenModifier::Mutable.bit()
+            )
+        );
+        assert_eq!(find_token(&tokens, 29, 20).3, TokenType::Struct);
+
+        // point.x = LIMIT;
+        assert_eq!(find_token(&tokens, 30, 10).3, TokenType::Property);
+        assert_eq!(
+            find_token(&tokens, 30, 14),
+            (
+                30,
+                14,
+                5,
+                TokenType::Variable,
+                TokenModifier::Readonly.bit() | TokenModifier::Static.bit()
+            )
+        );
+
+        // old();
+        assert_eq!(
+            find_token(&tokens, 31, 4),
+            (31, 4, 3, TokenType::Function, TokenModifier::Deprecated.bit())
+        );
+
+        // let _ = unsafe { helper(point.area()) };
+        assert_eq!(
+            find_token(&tokens, 32, 21),
+            (32, 21, 6, TokenType::Function, TokenModifier::Unconstrained.bit())
+        );
+        assert_eq!(find_token(&tokens, 32, 34).3, TokenType::Method);
+    }
+
+    #[test]
+    async fn test_semantic_tokens_in_range() {
+        let range = Range {
+            start: Position { line: 31, character: 0 },
+            end: Position { line: 32, character: 0 },
+        };
+        let tokens = get_semantic_tokens(Some(range)).await;
+        assert_eq!(tokens, vec![(31, 4, 3, TokenType::Function, TokenModifier::Deprecated.bit())]);
+    }
+
+    #[test]
+    async fn test_semantic_tokens_for_non_file_uri() {
+        let (mut state, _noir_text_document) = test_utils::init_lsp_server("semantic_tokens").await;
+        let params = SemanticTokensParams {
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+            text_document: TextDocumentIdentifier {
+                uri: Url::parse("untitled:Untitled-1").unwrap(),
+            },
+        };
+        let result = on_semantic_tokens_full_request(&mut state, params)
+            .await
+            .expect("Could not execute on_semantic_tokens_full_request");
+        assert!(result.is_none());
+    }
+}
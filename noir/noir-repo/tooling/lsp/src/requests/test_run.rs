@@ -3,7 +3,7 @@ use std::future::{self, Future};
 use crate::insert_all_files_for_workspace_into_file_manager;
 use async_lsp::{ErrorCode, ResponseError};
 use nargo::{
-    ops::{run_test, TestStatus},
+    ops::{run_test, FuzzingOptions, TestStatus},
     PrintOutput,
 };
 use nargo_toml::{find_package_manifest, resolve_workspace_from_toml, PackageSelection};
@@ -92,6 +92,7 @@ fn on_test_run_request_inner(
                 Some(workspace.root_dir.clone()),
                 Some(package.name.to_string()),
                 &CompileOptions::default(),
+                &FuzzingOptions::default(),
             );
             let result = match test_result {
                 TestStatus::Pass => NargoTestRunResult {
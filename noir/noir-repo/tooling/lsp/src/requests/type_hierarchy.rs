@@ -0,0 +1,296 @@
+use std::future::{self, Future};
+
+use async_lsp::ResponseError;
+use fm::FileMap;
+use lsp_types::{
+    SymbolKind, TextDocumentIdentifier, TextDocumentPositionParams, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams,
+};
+use noirc_errors::Location;
+use noirc_frontend::{
+    hir_def::traits::TraitImpl,
+    node_interner::{NodeInterner, ReferenceId, StructId, TraitId},
+    Type,
+};
+use serde_json::Value as JsonValue;
+
+use crate::LspState;
+
+use super::{process_request, to_lsp_location};
+
+/// Implementations for types other than structs (for example `impl Trait for Field`) are shown
+/// as items located at the trait name of the `impl`. The data of such items is this value, so
+/// we can tell them apart from the trait itself.
+const IMPL_ITEM_DATA: &str = "impl";
+
+pub(crate) fn on_prepare_type_hierarchy_request(
+    state: &mut LspState,
+    params: TypeHierarchyPrepareParams,
+) -> impl Future<Output = Result<Option<Vec<TypeHierarchyItem>>, ResponseError>> {
+    let result = process_request(state, params.text_document_position_params, |args| {
+        let item = match args.interner.find_referenced(args.location)? {
+            ReferenceId::Trait(trait_id) => trait_item(trait_id, args.interner, args.files),
+            ReferenceId::Struct(struct_id) => struct_item(struct_id, args.interner, args.files),
+            _ => None,
+        }?;
+        Some(vec![item])
+    });
+    future::ready(result)
+}
+
+pub(crate) fn on_type_hierarchy_supertypes_request(
+    state: &mut LspState,
+    params: TypeHierarchySupertypesParams,
+) -> impl Future<Output = Result<Option<Vec<TypeHierarchyItem>>, ResponseError>> {
+    let is_impl_item = is_impl_item(&params.item);
+    let result = process_request(state, item_position(&params.item), |args| {
+        let interner = args.interner;
+        let items = match interner.find_referenced(args.location)? {
+            // The supertype of an implementation is the implemented trait
+            ReferenceId::Trait(trait_id) if is_impl_item => {
+                vec![trait_item(trait_id, interner, args.files)?]
+            }
+            ReferenceId::Trait(trait_id) => {
+                let trait_bounds = &interner.get_trait(trait_id).trait_bounds;
+                let trait_ids = trait_bounds.iter().map(|trait_bound| trait_bound.trait_id);
+                trait_items(trait_ids, interner, args.files)
+            }
+            ReferenceId::Struct(struct_id) => {
+                let trait_ids = interner
+                    .get_trait_implementations()
+                    .filter(|trait_impl| implements_struct(&trait_impl.borrow(), struct_id))
+                    .map(|trait_impl| trait_impl.borrow().trait_id);
+                trait_items(trait_ids, interner, args.files)
+            }
+            _ => return None,
+        };
+        Some(items)
+    });
+    future::ready(result)
+}
+
+pub(crate) fn on_type_hierarchy_subtypes_request(
+    state: &mut LspState,
+    params: TypeHierarchySubtypesParams,
+) -> impl Future<Output = Result<Option<Vec<TypeHierarchyItem>>, ResponseError>> {
+    let is_impl_item = is_impl_item(&params.item);
+    let result = process_request(state, item_position(&params.item), |args| {
+        let interner = args.interner;
+        let referenced = interner.find_referenced(args.location)?;
+
+        // Structs and implementations have no subtypes
+        let ReferenceId::Trait(trait_id) = referenced else {
+            return Some(Vec::new());
+        };
+        if is_impl_item {
+            return Some(Vec::new());
+        }
+
+        // Traits that have this trait as a supertrait
+        let subtrait_ids = interner
+            .get_traits()
+            .filter(|the_trait| {
+                the_trait.trait_bounds.iter().any(|trait_bound| trait_bound.trait_id == trait_id)
+            })
+            .map(|the_trait| the_trait.id);
+        let mut items = trait_items(subtrait_ids, interner, args.files);
+
+        // Types that implement this trait
+        let mut implementation_items: Vec<TypeHierarchyItem> = interner
+            .get_trait_implementations()
+            .filter(|trait_impl| trait_impl.borrow().trait_id == trait_id)
+            .filter_map(|trait_impl| {
+                let trait_impl = trait_impl.borrow();
+                match &trait_impl.typ {
+                    Type::Struct(struct_type, _) => {
+                        struct_item(struct_type.borrow().id, interner, args.files)
+                    }
+                    _ => impl_item(&trait_impl, args.files),
+                }
+            })
+            .collect();
+        sort_and_dedup_items(&mut implementation_items);
+        items.extend(implementation_items);
+
+        Some(items)
+    });
+    future::ready(result)
+}
+
+fn is_impl_item(item: &TypeHierarchyItem) -> bool {
+    item.data.as_ref().and_then(|data| data.as_str()) == Some(IMPL_ITEM_DATA)
+}
+
+/// The position of an item's name, which is where we look the item up again when the client
+/// asks for its supertypes or subtypes.
+fn item_position(item: &TypeHierarchyItem) -> TextDocumentPositionParams {
+    TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier { uri: item.uri.clone() },
+        position: item.selection_range.start,
+    }
+}
+
+pub(super) fn implements_struct(trait_impl: &TraitImpl, struct_id: StructId) -> bool {
+    matches!(&trait_impl.typ, Type::Struct(struct_type, _) if struct_type.borrow().id == struct_id)
+}
+
+fn trait_items(
+    trait_ids: impl Iterator<Item = TraitId>,
+    interner: &NodeInterner,
+    files: &FileMap,
+) -> Vec<TypeHierarchyItem> {
+    let mut items: Vec<_> =
+        trait_ids.filter_map(|trait_id| trait_item(trait_id, interner, files)).collect();
+    sort_and_dedup_items(&mut items);
+    items
+}
+
+fn trait_item(
+    trait_id: TraitId,
+    interner: &NodeInterner,
+    files: &FileMap,
+) -> Option<TypeHierarchyItem> {
+    let the_trait = interner.get_trait(trait_id);
+    let location = interner.reference_location(ReferenceId::Trait(trait_id));
+    type_hierarchy_item(the_trait.name.to_string(), SymbolKind::INTERFACE, location, None, files)
+}
+
+fn struct_item(
+    struct_id: StructId,
+    interner: &NodeInterner,
+    files: &FileMap,
+) -> Option<TypeHierarchyItem> {
+    let struct_type = interner.get_struct(struct_id);
+    let name = struct_type.borrow().name.to_string();
+    let location = interner.reference_location(ReferenceId::Struct(struct_id));
+    type_hierarchy_item(name, SymbolKind::STRUCT, location, None, files)
+}
+
+fn impl_item(trait_impl: &TraitImpl, files: &FileMap) -> Option<TypeHierarchyItem> {
+    let location = Location::new(trait_impl.ident.span(), trait_impl.file);
+    let data = Some(JsonValue::String(IMPL_ITEM_DATA.to_string()));
+    type_hierarchy_item(trait_impl.typ.to_string(), SymbolKind::STRUCT, location, data, files)
+}
+
+fn type_hierarchy_item(
+    name: String,
+    kind: SymbolKind,
+    location: Location,
+    data: Option<JsonValue>,
+    files: &FileMap,
+) -> Option<TypeHierarchyItem> {
+    let location = to_lsp_location(files, location.file, location.span)?;
+    Some(TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail: None,
+        uri: location.uri,
+        range: location.range,
+        selection_range: location.range,
+        data,
+    })
+}
+
+fn sort_and_dedup_items(items: &mut Vec<TypeHierarchyItem>) {
+    items.sort_by_key(|item| {
+        (
+            item.uri.to_string(),
+            item.selection_range.start.line,
+            item.selection_range.start.character,
+        )
+    });
+    items.dedup_by(|item1, item2| {
+        item1.uri == item2.uri && item1.selection_range == item2.selection_range
+    });
+}
+
+#[cfg(test)]
+mod type_hierarchy_tests {
+    use super::*;
+    use crate::test_utils;
+    use lsp_types::{PartialResultParams, Position, Url, WorkDoneProgressParams};
+    use tokio::test;
+
+    async fn prepare_type_hierarchy(
+        state: &mut LspState,
+        uri: &Url,
+        position: Position,
+    ) -> TypeHierarchyItem {
+        let params = TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+        };
+        let mut items = on_prepare_type_hierarchy_request(state, params)
+            .await
+            .expect("Could not execute on_prepare_type_hierarchy_request")
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        items.remove(0)
+    }
+
+    async fn supertypes(state: &mut LspState, item: TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+        let params = TypeHierarchySupertypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        on_type_hierarchy_supertypes_request(state, params)
+            .await
+            .expect("Could not execute on_type_hierarchy_supertypes_request")
+            .unwrap()
+    }
+
+    async fn subtypes(state: &mut LspState, item: TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+        let params = TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+            partial_result_params: PartialResultParams { partial_result_token: None },
+        };
+        on_type_hierarchy_subtypes_request(state, params)
+            .await
+            .expect("Could not execute on_type_hierarchy_subtypes_request")
+            .unwrap()
+    }
+
+    fn names(items: &[TypeHierarchyItem]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    async fn test_trait_subtypes() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        let item =
+            prepare_type_hierarchy(&mut state, &noir_text_document, Position::new(0, 6)).await;
+        assert_eq!(item.name, "Animal");
+        assert_eq!(item.kind, SymbolKind::INTERFACE);
+
+        let items = subtypes(&mut state, item).await;
+        assert_eq!(names(&items), vec!["Pet", "Dog", "Field"]);
+
+        // `impl Animal for Field` has no subtypes, and its supertype is the trait
+        let field_item = items[2].clone();
+        assert!(subtypes(&mut state, field_item.clone()).await.is_empty());
+        assert_eq!(names(&supertypes(&mut state, field_item).await), vec!["Animal"]);
+    }
+
+    #[test]
+    async fn test_struct_and_trait_supertypes() {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("hierarchy").await;
+
+        // `struct Dog`
+        let item =
+            prepare_type_hierarchy(&mut state, &noir_text_document, Position::new(8, 7)).await;
+        assert_eq!(item.kind, SymbolKind::STRUCT);
+        assert_eq!(names(&supertypes(&mut state, item).await), vec!["Animal", "Pet"]);
+
+        // `trait Pet: Animal`
+        let item =
+            prepare_type_hierarchy(&mut state, &noir_text_document, Position::new(4, 6)).await;
+        assert_eq!(names(&supertypes(&mut state, item).await), vec!["Animal"]);
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashSet;
+use std::future::{self, Future};
+use std::path::Path;
+
+use async_lsp::ResponseError;
+use fm::FileMap;
+use lsp_types::{SymbolInformation, SymbolKind, WorkspaceSymbolParams, WorkspaceSymbolResponse};
+use noirc_frontend::{
+    hir::def_map::{ModuleDefId, ModuleId},
+    node_interner::{FuncId, NodeInterner, ReferenceId},
+};
+
+use crate::{
+    modules::{get_parent_module, module_def_id_to_reference_id, module_full_path},
+    LspState, PackageCacheData,
+};
+
+use super::to_lsp_location;
+
+/// The maximum number of symbols returned for a query, so that short queries stay responsive.
+const MAX_WORKSPACE_SYMBOLS: usize = 256;
+
+/// Symbols come from the packages that were type-checked when their documents were opened or
+/// saved, so no symbols are returned before a document is opened.
+pub(crate) fn on_workspace_symbol_request(
+    state: &mut LspState,
+    params: WorkspaceSymbolParams,
+) -> impl Future<Output = Result<Option<WorkspaceSymbolResponse>, ResponseError>> {
+    let mut symbols = Vec::new();
+    let mut seen_locations = HashSet::new();
+    // Packages are visited in a fixed order so that a symbol shared by several packages is always
+    // reported with the same container name.
+    let mut package_caches: Vec<_> = state.package_cache.iter().collect();
+    package_caches.sort_by_key(|(package_root_dir, _)| *package_root_dir);
+    for (package_root_dir, package_cache) in package_caches {
+        let Some(files) = workspace_files(state, package_root_dir) else {
+            continue;
+        };
+
+        for symbol in package_workspace_symbols(package_cache, files, &params.query) {
+            // Packages of a workspace share their dependencies, so the same symbol can show up
+            // in the interner of every package.
+            let location = &symbol.1.location;
+            if seen_locations.insert((location.uri.clone(), location.range.start)) {
+                symbols.push(symbol);
+            }
+        }
+    }
+
+    symbols.sort_by(|(score1, symbol1), (score2, symbol2)| {
+        score1
+            .cmp(score2)
+            .then_with(|| symbol1.name.len().cmp(&symbol2.name.len()))
+            .then_with(|| symbol1.name.cmp(&symbol2.name))
+            .then_with(|| symbol1.location.uri.as_str().cmp(symbol2.location.uri.as_str()))
+            .then_with(|| symbol1.location.range.start.cmp(&symbol2.location.range.start))
+    });
+    symbols.truncate(MAX_WORKSPACE_SYMBOLS);
+
+    let symbols = symbols.into_iter().map(|(_, symbol)| symbol).collect();
+    future::ready(Ok(Some(WorkspaceSymbolResponse::Flat(symbols))))
+}
+
+/// Returns the files of the workspace a package belongs to. All packages of a workspace share
+/// the same `FileManager`, so file IDs in a package's interner refer to these files.
+fn workspace_files<'a>(state: &'a LspState, package_root_dir: &Path) -> Option<&'a FileMap> {
+    let file_manager = state
+        .workspace_cache
+        .iter()
+        .filter(|(workspace_root_dir, _)| package_root_dir.starts_with(workspace_root_dir))
+        .max_by_key(|(workspace_root_dir, _)| workspace_root_dir.as_os_str().len())
+        .map(|(_, workspace_cache)| &workspace_cache.file_manager)?;
+    Some(file_manager.as_file_map())
+}
+
+/// Returns the symbols of a package and its dependencies matching `query`, along with their
+/// match score (lower is better).
+fn package_workspace_symbols(
+    package_cache: &PackageCacheData,
+    files: &FileMap,
+    query: &str,
+) -> Vec<(u32, SymbolInformation)> {
+    let mut collector =
+        WorkspaceSymbolCollector { package_cache, files, query, symbols: Vec::new() };
+    collector.collect_module_definitions();
+    collector.collect_functions();
+    collector.symbols
+}
+
+struct WorkspaceSymbolCollector<'a> {
+    package_cache: &'a PackageCacheData,
+    files: &'a FileMap,
+    query: &'a str,
+    symbols: Vec<(u32, SymbolInformation)>,
+}
+
+impl WorkspaceSymbolCollector<'_> {
+    /// Collects the items defined in the modules of every crate, except functions: they are
+    /// collected along with methods by `collect_functions`.
+    fn collect_module_definitions(&mut self) {
+        let package_cache = self.package_cache;
+        let interner = &package_cache.node_interner;
+        for def_map in package_cache.def_maps.values() {
+            for (_, module) in def_map.modules().iter() {
+                let definitions = module.definitions();
+                for (name, scope) in definitions.types().iter().chain(definitions.values()) {
+                    for (module_def_id, _visibility, _is_prelude) in scope.values() {
+                        if let ModuleDefId::FunctionId(_) = module_def_id {
+                            continue;
+                        }
+
+                        let reference_id = module_def_id_to_reference_id(*module_def_id);
+                        let container_name = get_parent_module(interner, *module_def_id)
+                            .and_then(|parent_module| self.module_path(parent_module));
+                        self.push_symbol(
+                            &name.0.contents,
+                            module_def_id_symbol_kind(*module_def_id),
+                            reference_id,
+                            container_name,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collects every function, including the methods of impls and traits which aren't part of
+    /// any module definitions.
+    fn collect_functions(&mut self) {
+        let package_cache = self.package_cache;
+        let interner = &package_cache.node_interner;
+        for func_id in interner.function_ids() {
+            if is_default_method_copy(interner, func_id) {
+                continue;
+            }
+
+            let kind = if interner.function_meta(&func_id).self_type.is_some() {
+                SymbolKind::METHOD
+            } else {
+                SymbolKind::FUNCTION
+            };
+            let container_name = self.function_container_name(func_id);
+            self.push_symbol(
+                interner.function_name(&func_id),
+                kind,
+                ReferenceId::Function(func_id),
+                container_name,
+            );
+        }
+    }
+
+    fn push_symbol(
+        &mut self,
+        name: &str,
+        kind: SymbolKind,
+        reference_id: ReferenceId,
+        container_name: Option<String>,
+    ) {
+        let Some(score) = symbol_match_score(name, self.query) else {
+            return;
+        };
+
+        let location = self.package_cache.node_interner.reference_location(reference_id);
+        let Some(location) = to_lsp_location(self.files, location.file, location.span) else {
+            return;
+        };
+
+        #[allow(deprecated)]
+        self.symbols.push((
+            score,
+            SymbolInformation {
+                name: name.to_string(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location,
+                container_name,
+            },
+        ));
+    }
+
+    /// Returns the path of the module a function is defined in, followed by the name of the
+    /// trait or struct it's a method of, if any.
+    fn function_container_name(&self, func_id: FuncId) -> Option<String> {
+        let interner = &self.package_cache.node_interner;
+        let func_meta = interner.function_meta(&func_id);
+        let (parent_module, type_name) = if let Some(trait_id) = func_meta.trait_id {
+            let trait_name = interner.get_trait(trait_id).name.0.contents.clone();
+            (get_parent_module(interner, ModuleDefId::TraitId(trait_id)), Some(trait_name))
+        } else if let Some(struct_id) = func_meta.struct_id {
+            let struct_name = interner.get_struct(struct_id).borrow().name.0.contents.clone();
+            (get_parent_module(interner, ModuleDefId::TypeId(struct_id)), Some(struct_name))
+        } else {
+            (get_parent_module(interner, ModuleDefId::FunctionId(func_id)), None)
+        };
+
+        let module_path = parent_module.and_then(|parent_module| self.module_path(parent_module));
+        match (module_path, type_name) {
+            (Some(module_path), Some(type_name)) => Some(format!("{module_path}::{type_name}")),
+            (module_path, type_name) => module_path.or(type_name),
+        }
+    }
+
+    fn module_path(&self, module_id: ModuleId) -> Option<String> {
+        let package_cache = self.package_cache;
+        let crate_id = package_cache.crate_id;
+        let path = module_full_path(
+            &module_id,
+            &package_cache.node_interner,
+            crate_id,
+            &package_cache.crate_name.to_string(),
+            &package_cache.crate_graph[crate_id].dependencies,
+        );
+        (!path.is_empty()).then_some(path)
+    }
+}
+
+/// Trait impls that don't override a method with a default implementation get a copy of that
+/// implementation, whose name has the span of the default implementation but the file of the
+/// impl. These copies aren't symbols of their own.
+fn is_default_method_copy(interner: &NodeInterner, func_id: FuncId) -> bool {
+    let Some(trait_impl_id) = interner.function_meta(&func_id).trait_impl else {
+        return false;
+    };
+
+    let trait_id = interner.get_trait_implementation(trait_impl_id).borrow().trait_id;
+    let name = interner.function_name(&func_id);
+    let name_span = interner.function_modifiers(&func_id).name_location.span;
+    interner.get_trait(trait_id).methods.iter().any(|method| {
+        method.name.0.contents == name
+            && method
+                .default_impl
+                .as_ref()
+                .is_some_and(|default_impl| default_impl.name_ident().span() == name_span)
+    })
+}
+
+fn module_def_id_symbol_kind(module_def_id: ModuleDefId) -> SymbolKind {
+    match module_def_id {
+        ModuleDefId::ModuleId(_) => SymbolKind::MODULE,
+        ModuleDefId::FunctionId(_) => SymbolKind::FUNCTION,
+        ModuleDefId::TypeId(_) => SymbolKind::STRUCT,
+        ModuleDefId::TypeAliasId(_) => SymbolKind::TYPE_PARAMETER,
+        ModuleDefId::TraitId(_) => SymbolKind::INTERFACE,
+        ModuleDefId::GlobalId(_) => SymbolKind::CONSTANT,
+    }
+}
+
+/// Returns how well `name` matches `query`, or `None` if it doesn't match at all.
+/// From best to worst: an exact match, a case-insensitive exact match, a case-insensitive
+/// prefix, a case-insensitive substring, and the characters of the query appearing in order
+/// in the name (so `bvec` matches `BoundedVec`).
+fn symbol_match_score(name: &str, query: &str) -> Option<u32> {
+    if query.is_empty() {
+        return Some(4);
+    }
+    if name == query {
+        return Some(0);
+    }
+
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name == query {
+        Some(1)
+    } else if name.starts_with(&query) {
+        Some(2)
+    } else if name.contains(&query) {
+        Some(3)
+    } else {
+        let mut name_chars = name.chars();
+        let is_subsequence = query.chars().all(|query_char| name_chars.any(|c| c == query_char));
+        is_subsequence.then_some(4)
+    }
+}
+
+#[cfg(test)]
+mod workspace_symbol_tests {
+    use super::*;
+    use crate::{notifications, test_utils};
+    use lsp_types::{PartialResultParams, Url, WorkDoneProgressParams};
+    use tokio::test;
+
+    async fn workspace_symbols(state: &mut LspState, query: &str) -> Vec<SymbolInformation> {
+        let response = on_workspace_symbol_request(
+            state,
+            WorkspaceSymbolParams {
+                query: query.to_string(),
+                work_done_progress_params: WorkDoneProgressParams { work_done_token: None },
+                partial_result_params: PartialResultParams { partial_result_token: None },
+            },
+        )
+        .await
+        .expect("Could not execute on_workspace_symbol_request")
+        .unwrap();
+
+        let WorkspaceSymbolResponse::Flat(symbols) = response else {
+            panic!("Expected response to be flat");
+        };
+        symbols
+    }
+
+    async fn get_workspace_symbols(query: &str) -> Vec<SymbolInformation> {
+        let (mut state, noir_text_document) = test_utils::init_lsp_server("workspace").await;
+
+        // noir_text_document is always `src/main.nr` in the workspace directory, so let's go to the workspace dir
+        let noir_text_document = noir_text_document.to_file_path().unwrap();
+        let workspace_dir = noir_text_document.parent().unwrap().parent().unwrap();
+        let one_lib = Url::from_file_path(workspace_dir.join("one/src/lib.nr")).unwrap();
+
+        // We call this to open the document, so that the entire workspace is analyzed
+        let output_diagnostics = false;
+        notifications::process_workspace_for_noir_document(&mut state, one_lib, output_diagnostics)
+            .unwrap();
+
+        workspace_symbols(&mut state, query).await
+    }
+
+    fn find_symbol<'a>(
+        symbols: &'a [SymbolInformation],
+        name: &str,
+        kind: SymbolKind,
+    ) -> &'a SymbolInformation {
+        symbols
+            .iter()
+            .find(|symbol| symbol.name == name && symbol.kind == kind)
+            .unwrap_or_else(|| panic!("Expected a symbol named {name} of kind {kind:?}"))
+    }
+
+    #[test]
+    async fn test_symbol_match_score() {
+        assert_eq!(symbol_match_score("foo", "foo"), Some(0));
+        assert_eq!(symbol_match_score("Foo", "foo"), Some(1));
+        assert_eq!(symbol_match_score("foo_bar", "foo"), Some(2));
+        assert_eq!(symbol_match_score("bar_foo", "foo"), Some(3));
+        assert_eq!(symbol_match_score("BoundedVec", "bvec"), Some(4));
+        assert_eq!(symbol_match_score("foo", "bar"), None);
+    }
+
+    #[test]
+    async fn test_workspace_symbol_across_packages() {
+        let symbols = get_workspace_symbols("function_").await;
+        let names: Vec<_> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert!(names.contains(&"function_one"));
+        assert!(names.contains(&"function_two"));
+
+        let function_one = find_symbol(&symbols, "function_one", SymbolKind::FUNCTION);
+        assert!(function_one.location.uri.path().ends_with("workspace/one/src/lib.nr"));
+        assert_eq!(function_one.location.range.start.line, 0);
+    }
+
+    #[test]
+    async fn test_workspace_symbol_ranks_exact_matches_first() {
+        let symbols = get_workspace_symbols("SubOneStruct").await;
+        assert_eq!(symbols[0].name, "SubOneStruct");
+        assert_eq!(symbols[0].kind, SymbolKind::STRUCT);
+        assert_eq!(symbols[0].container_name, Some("one::subone".to_string()));
+    }
+
+    #[test]
+    async fn test_workspace_symbol_includes_impl_and_trait_methods() {
+        let symbols = get_workspace_symbols("foo").await;
+        let method = find_symbol(&symbols, "foo", SymbolKind::METHOD);
+        assert_eq!(method.container_name, Some("one::subone::SubOneStruct".to_string()));
+        assert!(method.location.uri.path().ends_with("workspace/one/src/lib.nr"));
+
+        let symbols = get_workspace_symbols("bar_stuff").await;
+        let bar_stuff: Vec<_> =
+            symbols.iter().filter(|symbol| symbol.name == "bar_stuff").collect();
+        assert_eq!(bar_stuff.len(), 3);
+
+        let function = find_symbol(&symbols, "bar_stuff", SymbolKind::FUNCTION);
+        assert_eq!(function.container_name, Some("two".to_string()));
+
+        let mut method_containers: Vec<_> = bar_stuff
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::METHOD)
+            .map(|symbol| symbol.container_name.clone().unwrap())
+            .collect();
+        method_containers.sort();
+        assert_eq!(method_containers, vec!["two::Bar", "two::Foo"]);
+    }
+
+    #[test]
+    async fn test_workspace_symbol_without_opened_documents() {
+        let (mut state, _noir_text_document) = test_utils::init_lsp_server("workspace").await;
+        assert!(workspace_symbols(&mut state, "function_").await.is_empty());
+        assert!(state.package_cache.is_empty());
+    }
+}
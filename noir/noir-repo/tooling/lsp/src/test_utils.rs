@@ -1,4 +1,8 @@
-use crate::LspState;
+use crate::{
+    circuit_costs::{compute_package_circuit_costs, CircuitCostsComputed},
+    notifications::on_circuit_costs_computed,
+    resolve_workspace_for_source_path, LspState,
+};
 use acvm::blackbox_solver::StubbedBlackBoxSolver;
 use async_lsp::ClientSocket;
 use lsp_types::{Position, Range, Url};
@@ -38,6 +42,27 @@ pub(crate) async fn init_lsp_server(directory: &str) -> (LspState, Url) {
     (state, noir_text_document)
 }
 
+/// Computes the circuit costs of the packages in the workspace of a processed document, like the
+/// background computation started when it's opened or saved does.
+pub(crate) fn compute_circuit_costs(state: &mut LspState, noir_text_document: &Url) {
+    let file_path = noir_text_document.to_file_path().unwrap();
+    let workspace = resolve_workspace_for_source_path(&file_path).unwrap();
+    let file_manager = &state.workspace_cache[&workspace.root_dir].file_manager;
+    let parsed_files = nargo::parse_all(file_manager);
+
+    let events: Vec<_> = workspace
+        .into_iter()
+        .map(|package| CircuitCostsComputed {
+            generation: state.circuit_costs_generation,
+            package_root_dir: package.root_dir.clone(),
+            circuit_costs: compute_package_circuit_costs(file_manager, &parsed_files, package),
+        })
+        .collect();
+    for event in events {
+        let _ = on_circuit_costs_computed(state, event);
+    }
+}
+
 /// Searches for all instances of `search_string` in file `file_name` and returns a list of their locations.
 pub(crate) fn search_in_file(filename: &str, search_string: &str) -> Vec<Range> {
     let file_contents = std::fs::read_to_string(filename)
@@ -1,7 +1,8 @@
 use lsp_types::{
-    CodeActionOptions, CompletionOptions, DeclarationCapability, DefinitionOptions,
-    DocumentSymbolOptions, HoverOptions, InlayHintOptions, OneOf, ReferencesOptions, RenameOptions,
-    SignatureHelpOptions, TypeDefinitionProviderCapability,
+    CallHierarchyServerCapability, CodeActionOptions, CompletionOptions, DeclarationCapability,
+    DefinitionOptions, DocumentSymbolOptions, HoverOptions, ImplementationProviderCapability,
+    InlayHintOptions, OneOf, ReferencesOptions, RenameOptions, SemanticTokensServerCapabilities,
+    SignatureHelpOptions, TypeDefinitionProviderCapability, WorkspaceSymbolOptions,
 };
 use noirc_frontend::graph::CrateName;
 use serde::{Deserialize, Serialize};
@@ -114,6 +115,18 @@ pub(crate) struct ServerCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) type_definition_provider: Option<TypeDefinitionProviderCapability>,
 
+    /// The server provides goto implementation support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) implementation_provider: Option<ImplementationProviderCapability>,
+
+    /// The server provides call hierarchy support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) call_hierarchy_provider: Option<CallHierarchyServerCapability>,
+
+    /// The server provides type hierarchy support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) type_hierarchy_provider: Option<bool>,
+
     /// The server provides code lens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) code_lens_provider: Option<CodeLensOptions>,
@@ -156,6 +169,14 @@ pub(crate) struct ServerCapabilities {
     /// The server provides code action support.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) code_action_provider: Option<OneOf<bool, CodeActionOptions>>,
+
+    /// The server provides workspace symbol support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) workspace_symbol_provider: Option<OneOf<bool, WorkspaceSymbolOptions>>,
+
+    /// The server provides semantic tokens support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) semantic_tokens_provider: Option<SemanticTokensServerCapabilities>,
 }
 
 #[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
@@ -57,3 +57,35 @@ pub(crate) fn character_to_line_offset(line: &str, character: u32) -> Option<usi
         None
     }
 }
+
+/// Applies a change sent by the client to the source of an open document.
+/// A change without a range replaces the whole document.
+pub(crate) fn apply_text_document_change(
+    source: &mut String,
+    change: lsp_types::TextDocumentContentChangeEvent,
+) {
+    let Some(range) = change.range else {
+        *source = change.text;
+        return;
+    };
+
+    let start = position_to_source_offset(source, &range.start);
+    let end = position_to_source_offset(source, &range.end).max(start);
+    source.replace_range(start..end, &change.text);
+}
+
+/// Like `position_to_byte_index` but works on a document's source instead of a file in a FileMap.
+/// Positions past the end of a line, or past the end of the document, are clamped to it.
+fn position_to_source_offset(source: &str, position: &lsp_types::Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match source[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return source.len(),
+        }
+    }
+
+    let line_end = source[line_start..].find('\n').map_or(source.len(), |index| line_start + index);
+    let line = &source[line_start..line_end];
+    line_start + character_to_line_offset(line, position.character).unwrap_or(line.len())
+}
@@ -9,6 +9,8 @@ pub const SRC_DIR: &str = "src";
 pub const TARGET_DIR: &str = "target";
 /// The directory to store serialized ACIR representations of exported library functions.
 pub const EXPORT_DIR: &str = "export";
+/// The directory, within the target directory, to store inputs found by fuzzing tests.
+pub const FUZZ_DIR: &str = "fuzz";
 
 // Files
 /// The file from which Nargo pulls prover inputs
@@ -0,0 +1,159 @@
+//! Persistence of the inputs found while fuzzing a test, so that later runs can replay them.
+//!
+//! Each input is stored as a `Prover.toml`-style file named after a hash of its contents,
+//! with counterexamples and corpus entries distinguished by their file name prefix.
+use std::path::{Path, PathBuf};
+
+use noirc_abi::{input_parser::Format, Abi, InputMap};
+use sha2::{Digest, Sha256};
+
+const COUNTEREXAMPLE_PREFIX: &str = "counterexample-";
+const CORPUS_PREFIX: &str = "corpus-";
+
+/// The kind of a persisted fuzzing input
+#[derive(Debug, Clone, Copy)]
+pub(super) enum InputKind {
+    /// An input which made the test fail
+    Counterexample,
+    /// An input which reached new coverage
+    Corpus,
+}
+
+impl InputKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            InputKind::Counterexample => COUNTEREXAMPLE_PREFIX,
+            InputKind::Corpus => CORPUS_PREFIX,
+        }
+    }
+}
+
+/// Loads every input stored in `corpus_dir`, with counterexamples first.
+///
+/// Inputs which no longer match the test's parameters, for example because its
+/// signature changed since they were saved, are skipped.
+pub(super) fn load_inputs(corpus_dir: &Path, abi: &Abi) -> Vec<InputMap> {
+    let Ok(entries) = std::fs::read_dir(corpus_dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == Format::Toml.ext()))
+        .collect();
+    paths.sort_by_key(|path| {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        (!file_name.starts_with(COUNTEREXAMPLE_PREFIX), file_name)
+    });
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let contents = std::fs::read_to_string(path).ok()?;
+            Format::Toml.parse(&contents, abi).ok()
+        })
+        .collect()
+}
+
+/// Saves an input to `corpus_dir`, returning the path it was written to.
+///
+/// Inputs are named after the SHA-256 hash of their contents so saving the same input twice is
+/// a no-op, including across toolchain versions.
+pub(super) fn save_input(
+    corpus_dir: &Path,
+    kind: InputKind,
+    input_map: &InputMap,
+    abi: &Abi,
+) -> std::io::Result<PathBuf> {
+    let contents = Format::Toml
+        .serialize(input_map, abi)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    let hash = hex::encode(Sha256::digest(contents.as_bytes()));
+    let file_name = format!("{}{}.{}", kind.prefix(), &hash[..16], Format::Toml.ext());
+
+    std::fs::create_dir_all(corpus_dir)?;
+    let path = corpus_dir.join(file_name);
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Formats an input the same way it is persisted, for display in test output.
+pub(super) fn display_input(input_map: &InputMap, abi: &Abi) -> String {
+    Format::Toml.serialize(input_map, abi).unwrap_or_else(|_| format!("{input_map:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::FieldElement;
+    use noirc_abi::{
+        input_parser::InputValue, Abi, AbiParameter, AbiType, AbiVisibility, InputMap, Sign,
+    };
+
+    use sha2::{Digest, Sha256};
+
+    use super::{load_inputs, save_input, InputKind};
+
+    fn abi() -> Abi {
+        let parameters = vec![AbiParameter {
+            name: "x".to_string(),
+            typ: AbiType::Integer { sign: Sign::Unsigned, width: 32 },
+            visibility: AbiVisibility::Private,
+        }];
+        Abi { parameters, return_type: None, error_types: Default::default() }
+    }
+
+    fn input(x: u128) -> InputMap {
+        InputMap::from([("x".to_string(), InputValue::Field(FieldElement::from(x)))])
+    }
+
+    #[test]
+    fn files_are_named_after_the_sha256_of_their_contents() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        let path = save_input(corpus_dir.path(), InputKind::Corpus, &input(1), &abi()).unwrap();
+
+        let contents = std::fs::read(&path).unwrap();
+        let hash = hex::encode(Sha256::digest(contents));
+        assert_eq!(
+            path.file_name().unwrap().to_string_lossy(),
+            format!("corpus-{}.toml", &hash[..16])
+        );
+    }
+
+    #[test]
+    fn saving_the_same_input_twice_is_a_no_op() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        let first = save_input(corpus_dir.path(), InputKind::Corpus, &input(1), &abi()).unwrap();
+        let second = save_input(corpus_dir.path(), InputKind::Corpus, &input(1), &abi()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(std::fs::read_dir(corpus_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn loads_counterexamples_first() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        for x in [1, 2, 3] {
+            save_input(corpus_dir.path(), InputKind::Corpus, &input(x), &abi()).unwrap();
+        }
+        save_input(corpus_dir.path(), InputKind::Counterexample, &input(4), &abi()).unwrap();
+
+        let inputs = load_inputs(corpus_dir.path(), &abi());
+        assert_eq!(inputs.len(), 4);
+        assert_eq!(inputs[0], input(4));
+    }
+
+    #[test]
+    fn skips_inputs_which_no_longer_match_the_abi() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        save_input(corpus_dir.path(), InputKind::Corpus, &input(1), &abi()).unwrap();
+        std::fs::write(corpus_dir.path().join("corpus-stale.toml"), "y = 1\n").unwrap();
+
+        assert_eq!(load_inputs(corpus_dir.path(), &abi()), vec![input(1)]);
+    }
+
+    #[test]
+    fn loading_a_missing_directory_returns_no_inputs() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        assert!(load_inputs(&corpus_dir.path().join("missing"), &abi()).is_empty());
+    }
+}
@@ -7,11 +7,13 @@ pub use self::execute::{execute_program, execute_program_with_profiling};
 pub use self::optimize::{optimize_contract, optimize_program};
 pub use self::transform::{transform_contract, transform_program};
 
-pub use self::test::{run_test, TestStatus};
+pub use self::test::{run_test, FuzzingOptions, TestStatus};
 
 mod check;
 mod compile;
 mod execute;
+#[cfg(not(target_arch = "wasm32"))]
+mod fuzz_corpus;
 mod optimize;
 mod test;
 mod transform;
@@ -29,6 +29,18 @@ use crate::{
 
 use super::execute_program;
 
+/// Options controlling how tests which take parameters are fuzzed
+#[derive(Debug, Clone, Default)]
+pub struct FuzzingOptions {
+    /// Use the coverage reached by each input to guide the generation of new inputs,
+    /// rather than sampling every input independently
+    pub coverage_guided: bool,
+
+    /// The directory in which the counterexamples and corpus found while fuzzing the test
+    /// are saved. Any inputs already in it are replayed before new inputs are generated.
+    pub corpus_dir: Option<PathBuf>,
+}
+
 #[derive(Debug)]
 pub enum TestStatus {
     Pass,
@@ -53,6 +65,7 @@ pub fn run_test<B: BlackBoxFunctionSolver<FieldElement>>(
     root_path: Option<PathBuf>,
     package_name: Option<String>,
     config: &CompileOptions,
+    fuzzing_options: &FuzzingOptions,
 ) -> TestStatus {
     let test_function_has_no_arguments = context
         .def_interner
@@ -118,8 +131,10 @@ pub fn run_test<B: BlackBoxFunctionSolver<FieldElement>>(
 
                 #[cfg(not(target_arch = "wasm32"))]
                 {
+                    use super::execute_program_with_profiling;
+                    use super::fuzz_corpus::{self, InputKind};
                     use acvm::acir::circuit::Program;
-                    use noir_fuzzer::FuzzedExecutor;
+                    use noir_fuzzer::{Coverage, CoverageGuidedFuzzedExecutor, FuzzedExecutor};
                     use proptest::test_runner::Config;
                     use proptest::test_runner::TestRunner;
 
@@ -129,47 +144,103 @@ pub fn run_test<B: BlackBoxFunctionSolver<FieldElement>>(
                     let abi = compiled_program.abi.clone();
                     let debug = compiled_program.debug.clone();
 
-                    let executor =
-                        |program: &Program<FieldElement>,
-                         initial_witness: WitnessMap<FieldElement>|
-                         -> Result<WitnessStack<FieldElement>, String> {
-                            let circuit_execution = execute_program(
+                    let corpus_dir = fuzzing_options.corpus_dir.as_deref();
+                    let seed_inputs = corpus_dir
+                        .map(|corpus_dir| fuzz_corpus::load_inputs(corpus_dir, &abi))
+                        .unwrap_or_default();
+
+                    let check_status = |circuit_execution: &_| {
+                        fuzzing_result(test_function, &abi, &debug, circuit_execution)
+                    };
+                    let new_foreign_call_executor = || {
+                        TestForeignCallExecutor::<FieldElement>::new(
+                            PrintOutput::None,
+                            foreign_call_resolver_url,
+                            root_path.clone(),
+                            package_name.clone(),
+                        )
+                    };
+
+                    let result = if fuzzing_options.coverage_guided {
+                        let executor = |program: &Program<FieldElement>,
+                                        initial_witness: WitnessMap<FieldElement>|
+                         -> Result<Coverage, String> {
+                            let circuit_execution = execute_program_with_profiling(
                                 program,
                                 initial_witness,
                                 blackbox_solver,
-                                &mut TestForeignCallExecutor::<FieldElement>::new(
-                                    PrintOutput::None,
-                                    foreign_call_resolver_url,
-                                    root_path.clone(),
-                                    package_name.clone(),
-                                ),
+                                &mut new_foreign_call_executor(),
                             );
+                            let coverage = match &circuit_execution {
+                                Ok((witness_stack, profiling_samples)) => {
+                                    let mut coverage =
+                                        Coverage::from_profiling_samples(profiling_samples);
+                                    coverage.merge(Coverage::from_witness_stack(
+                                        program,
+                                        witness_stack,
+                                    ));
+                                    coverage
+                                }
+                                Err(_) => Coverage::default(),
+                            };
+
+                            let circuit_execution =
+                                circuit_execution.map(|(witness_stack, _)| witness_stack);
+                            check_status(&circuit_execution).map(|()| coverage)
+                        };
 
-                            let status = test_status_program_compile_pass(
-                                test_function,
-                                &abi,
-                                &debug,
-                                &circuit_execution,
-                            );
+                        CoverageGuidedFuzzedExecutor::new(compiled_program.into(), executor, runner)
+                            .with_seed_inputs(seed_inputs)
+                            .fuzz()
+                    } else {
+                        let executor =
+                            |program: &Program<FieldElement>,
+                             initial_witness: WitnessMap<FieldElement>|
+                             -> Result<WitnessStack<FieldElement>, String> {
+                                let circuit_execution = execute_program(
+                                    program,
+                                    initial_witness,
+                                    blackbox_solver,
+                                    &mut new_foreign_call_executor(),
+                                );
 
-                            if let TestStatus::Fail { message, error_diagnostic: _ } = status {
-                                Err(message)
-                            } else {
                                 // The fuzzer doesn't care about the actual result.
-                                Ok(WitnessStack::default())
-                            }
-                        };
+                                check_status(&circuit_execution).map(|()| WitnessStack::default())
+                            };
 
-                    let fuzzer = FuzzedExecutor::new(compiled_program.into(), executor, runner);
+                        FuzzedExecutor::new(compiled_program.into(), executor, runner)
+                            .with_seed_inputs(seed_inputs)
+                            .fuzz()
+                    };
+
+                    if let Some(corpus_dir) = corpus_dir {
+                        // Saving inputs is best effort: failing to do so shouldn't fail the test.
+                        let corpus = result.corpus.iter().map(|input| (InputKind::Corpus, input));
+                        let counterexample = result
+                            .counterexample
+                            .iter()
+                            .map(|input| (InputKind::Counterexample, input));
+                        for (kind, input_map) in corpus.chain(counterexample) {
+                            if let Err(error) =
+                                fuzz_corpus::save_input(corpus_dir, kind, input_map, &abi)
+                            {
+                                tracing::warn!(
+                                    "Failed to save fuzzing input to {}: {error}",
+                                    corpus_dir.display()
+                                );
+                            }
+                        }
+                    }
 
-                    let result = fuzzer.fuzz();
                     if result.success {
                         TestStatus::Pass
                     } else {
-                        TestStatus::Fail {
-                            message: result.reason.unwrap_or_default(),
-                            error_diagnostic: None,
+                        let mut message = result.reason.unwrap_or_default();
+                        if let Some(counterexample) = &result.counterexample {
+                            let input = fuzz_corpus::display_input(counterexample, &abi);
+                            message.push_str(&format!("\nFailing input:\n{input}"));
                         }
+                        TestStatus::Fail { message, error_diagnostic: None }
                     }
                 }
             }
@@ -178,6 +249,23 @@ pub fn run_test<B: BlackBoxFunctionSolver<FieldElement>>(
     }
 }
 
+/// Converts the outcome of executing a fuzzed test with a single input into the
+/// result the fuzzer expects, which is an error message if the test failed.
+#[cfg(not(target_arch = "wasm32"))]
+fn fuzzing_result(
+    test_function: &TestFunction,
+    abi: &Abi,
+    debug: &[DebugInfo],
+    circuit_execution: &Result<WitnessStack<FieldElement>, NargoError<FieldElement>>,
+) -> Result<(), String> {
+    let status = test_status_program_compile_pass(test_function, abi, debug, circuit_execution);
+    if let TestStatus::Fail { message, error_diagnostic: _ } = status {
+        Err(message)
+    } else {
+        Ok(())
+    }
+}
+
 /// Test function failed to compile
 ///
 /// Note: This could be because the compiler was able to deduce
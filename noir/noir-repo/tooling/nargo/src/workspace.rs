@@ -13,7 +13,7 @@ use fm::FileManager;
 use noirc_driver::file_manager_with_stdlib;
 
 use crate::{
-    constants::{CONTRACT_DIR, EXPORT_DIR, PROOFS_DIR, TARGET_DIR},
+    constants::{CONTRACT_DIR, EXPORT_DIR, FUZZ_DIR, PROOFS_DIR, TARGET_DIR},
     package::Package,
 };
 
@@ -50,6 +50,15 @@ impl Workspace {
         self.root_dir.join(EXPORT_DIR)
     }
 
+    /// Returns the directory in which the counterexamples and corpus found by fuzzing
+    /// the given test of a package are stored.
+    pub fn fuzz_corpus_directory_path(&self, package: &Package, test_name: &str) -> PathBuf {
+        let name: String = package.name.clone().into();
+        let package_dir = self.target_directory_path().join(FUZZ_DIR).join(name);
+        // Test names are paths such as `foo::test_bar`, each segment of which becomes a directory.
+        test_name.split("::").fold(package_dir, |path, segment| path.join(segment))
+    }
+
     /// Returns a new `FileManager` for the root directory of this workspace.
     /// If the root directory is not the standard library, the standard library
     /// is added to the returned `FileManager`.
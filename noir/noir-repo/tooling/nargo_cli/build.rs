@@ -205,7 +205,7 @@ fn test_{test_name}(force_brillig: ForceBrillig, inliner_aggressiveness: Inliner
     ).expect("failed to lock Nargo.toml");
 
     let mut nargo = Command::cargo_bin("nargo").unwrap();
-    nargo.arg("--program-dir").arg(test_program_dir);
+    nargo.arg("--program-dir").arg(&test_program_dir);
     nargo.arg("{test_command}").arg("--force");
     nargo.arg("--inliner-aggressiveness").arg(inliner_aggressiveness.0.to_string());
 
@@ -247,6 +247,10 @@ fn generate_execution_success_tests(test_file: &mut File, test_data_dir: &Path)
             &test_dir,
             "execute",
             r#"
+                // Opt-in check that every SSA pass preserves the program's result on its inputs
+                if std::env::var("NOIR_CHECK_SSA_PASSES").is_ok() {
+                    nargo.arg("--check-ssa-passes").arg(test_program_dir.join("Prover.toml"));
+                }
                 nargo.assert().success();
             "#,
             &MatrixConfig {
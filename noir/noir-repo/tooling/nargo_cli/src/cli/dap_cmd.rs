@@ -147,6 +147,12 @@ fn loop_uninitialized_dap<R: Read, W: Write>(
         match req.command {
             Command::Initialize(_) => {
                 let rsp = req.success(ResponseBody::Initialize(Capabilities {
+                    supports_conditional_breakpoints: Some(true),
+                    supports_hit_conditional_breakpoints: Some(true),
+                    supports_log_points: Some(true),
+                    supports_data_breakpoints: Some(true),
+                    supports_evaluate_for_hovers: Some(true),
+                    supports_step_back: Some(true),
                     supports_disassemble_request: Some(true),
                     supports_instruction_breakpoints: Some(true),
                     supports_stepping_granularity: Some(true),
@@ -14,8 +14,12 @@ use clap::Args;
 use fm::FileManager;
 use formatters::{Formatter, JsonFormatter, PrettyFormatter, TerseFormatter};
 use nargo::{
-    insert_all_files_for_workspace_into_file_manager, ops::TestStatus, package::Package, parse_all,
-    prepare_package, workspace::Workspace, PrintOutput,
+    insert_all_files_for_workspace_into_file_manager,
+    ops::{FuzzingOptions, TestStatus},
+    package::Package,
+    parse_all, prepare_package,
+    workspace::Workspace,
+    PrintOutput,
 };
 use nargo_toml::{get_package_manifest, resolve_workspace_from_toml};
 use noirc_driver::{check_crate, CompileOptions, NOIR_ARTIFACT_VERSION_STRING};
@@ -63,6 +67,10 @@ pub(crate) struct TestCommand {
     /// Display one character per test instead of one line
     #[clap(short = 'q', long = "quiet")]
     quiet: bool,
+
+    /// Use coverage feedback to guide the inputs generated for tests with parameters
+    #[clap(long)]
+    fuzz_coverage_guided: bool,
 }
 
 #[derive(Debug, Copy, Clone, clap::ValueEnum)]
@@ -498,6 +506,10 @@ impl<'a> TestRunner<'a> {
             root_path,
             Some(package_name),
             &self.args.compile_options,
+            &FuzzingOptions {
+                coverage_guided: self.args.fuzz_coverage_guided,
+                corpus_dir: Some(self.workspace.fuzz_corpus_directory_path(package, fn_name)),
+            },
         );
         (test_status, output_string)
     }
@@ -9,7 +9,7 @@ use std::io::Write;
 use std::{collections::BTreeMap, path::PathBuf};
 
 use nargo::{
-    ops::{report_errors, run_test, TestStatus},
+    ops::{report_errors, run_test, FuzzingOptions, TestStatus},
     package::{Package, PackageType},
     parse_all, prepare_package,
 };
@@ -92,6 +92,7 @@ fn run_stdlib_tests(force_brillig: bool, inliner_aggressiveness: i64) {
                 Some(dummy_package.root_dir.clone()),
                 Some(dummy_package.name.to_string()),
                 &CompileOptions { force_brillig, inliner_aggressiveness, ..Default::default() },
+                &FuzzingOptions::default(),
             );
             (test_name, status)
         })
@@ -0,0 +1,17 @@
+use clap::Args;
+use color_eyre::eyre;
+use noirc_evaluator::ssa::primary_passes;
+
+use super::PassOptions;
+
+/// List the SSA passes run by `nargo compile`, in the order they are run
+#[derive(Debug, Clone, Args)]
+pub(crate) struct ListPassesCommand;
+
+pub(crate) fn run(_args: ListPassesCommand) -> eyre::Result<()> {
+    let options = PassOptions::default().evaluator_options();
+    for pass in primary_passes(&options) {
+        println!("{}", pass.msg());
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use acir::circuit::ExpressionWidth;
+use clap::{Args, Parser, Subcommand};
+use color_eyre::eyre;
+use const_format::formatcp;
+use noirc_driver::parse_expression_width;
+use noirc_evaluator::ssa::{SsaEvaluatorOptions, SsaLogging};
+
+mod list_passes_cmd;
+mod transform_cmd;
+
+const SSA_CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+static VERSION_STRING: &str = formatcp!("version = {}\n", SSA_CLI_VERSION,);
+
+#[derive(Parser, Debug)]
+#[command(name="Noir SSA", author, version=VERSION_STRING, about, long_about = None)]
+struct SsaCli {
+    #[command(subcommand)]
+    command: SsaCommand,
+}
+
+#[non_exhaustive]
+#[derive(Subcommand, Clone, Debug)]
+enum SsaCommand {
+    Transform(transform_cmd::TransformCommand),
+    ListPasses(list_passes_cmd::ListPassesCommand),
+}
+
+pub(crate) fn start_cli() -> eyre::Result<()> {
+    let SsaCli { command } = SsaCli::parse();
+
+    match command {
+        SsaCommand::Transform(args) => transform_cmd::run(args),
+        SsaCommand::ListPasses(args) => list_passes_cmd::run(args),
+    }
+}
+
+/// Options which change the behavior of individual passes, with the same defaults as `nargo compile`.
+#[derive(Debug, Clone, Args)]
+pub(crate) struct PassOptions {
+    /// Specify the backend expression width that should be targeted when generating ACIR.
+    /// By default ACIR is generated without a bound.
+    #[arg(long, value_parser = parse_expression_width)]
+    expression_width: Option<ExpressionWidth>,
+
+    /// Setting to decide on an inlining strategy for Brillig functions.
+    #[arg(long, allow_hyphen_values = true, default_value_t = i64::MAX)]
+    inliner_aggressiveness: i64,
+
+    /// Setting the maximum acceptable increase in Brillig bytecode size due to
+    /// unrolling small loops.
+    #[arg(long, allow_hyphen_values = true)]
+    max_bytecode_increase_percent: Option<i32>,
+}
+
+impl Default for PassOptions {
+    fn default() -> Self {
+        Self {
+            expression_width: None,
+            inliner_aggressiveness: i64::MAX,
+            max_bytecode_increase_percent: None,
+        }
+    }
+}
+
+impl PassOptions {
+    pub(crate) fn evaluator_options(&self) -> SsaEvaluatorOptions {
+        SsaEvaluatorOptions {
+            ssa_logging: SsaLogging::None,
+            enable_brillig_logging: false,
+            force_brillig_output: false,
+            print_codegen_timings: false,
+            expression_width: self.expression_width.unwrap_or(ExpressionWidth::Unbounded),
+            emit_ssa: None,
+            skip_underconstrained_check: true,
+            skip_brillig_constraints_check: true,
+            inliner_aggressiveness: self.inliner_aggressiveness,
+            max_bytecode_increase_percent: self.max_bytecode_increase_percent,
+            interpreter_check_inputs: None,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use color_eyre::eyre::{self, eyre, Context};
+use noirc_evaluator::ssa::{
+    create_program_from_ssa, primary_passes, ssa_gen::Ssa, SsaPass, SsaProgramArtifact,
+};
+
+use super::PassOptions;
+
+/// Parse an SSA program, run the given passes over it and print the result
+#[derive(Debug, Clone, Args)]
+pub(crate) struct TransformCommand {
+    /// The path to the SSA file. The SSA is read from stdin if this is omitted.
+    #[clap(long, short)]
+    source_path: Option<PathBuf>,
+
+    /// The name of a pass to run, as shown by `list-passes` and `--show-ssa`.
+    /// Can be repeated to run several passes in order.
+    #[clap(long = "pass", short = 'p')]
+    passes: Vec<String>,
+
+    /// Run every pass `nargo compile` runs, before any passes given with `--pass`
+    #[clap(long, action)]
+    all_passes: bool,
+
+    /// Print the SSA after every pass instead of only after the last one
+    #[clap(long, action)]
+    show_intermediate: bool,
+
+    /// Lower the final SSA to ACIR and Brillig and print the opcode count of each function
+    #[clap(long, action)]
+    opcode_counts: bool,
+
+    #[clap(flatten)]
+    pass_options: PassOptions,
+}
+
+pub(crate) fn run(args: TransformCommand) -> eyre::Result<()> {
+    let src = read_source(args.source_path.as_deref())?;
+    let mut ssa = Ssa::from_str(&src).map_err(|error| eyre!("Failed to parse SSA:{error:?}"))?;
+
+    let options = args.pass_options.evaluator_options();
+    let available_passes = primary_passes(&options);
+
+    let mut passes: Vec<&SsaPass> = Vec::new();
+    if args.all_passes {
+        passes.extend(&available_passes);
+    }
+    for name in &args.passes {
+        let pass = find_pass(&available_passes, name).ok_or_else(|| {
+            eyre!("Unknown SSA pass `{name}`. Run `noir-ssa list-passes` to see all passes.")
+        })?;
+        passes.push(pass);
+    }
+
+    for pass in passes {
+        ssa = pass.run(ssa).map_err(|error| eyre!("{} failed: {error}", pass.msg()))?;
+        if args.show_intermediate {
+            ssa.normalize_ids();
+            println!("After {}:\n{ssa}", pass.msg());
+        }
+    }
+
+    if !args.show_intermediate {
+        ssa.normalize_ids();
+        println!("{ssa}");
+    }
+
+    if args.opcode_counts {
+        let program = create_program_from_ssa(ssa, &options)
+            .map_err(|error| eyre!("Failed to generate ACIR: {error}"))?;
+        print_opcode_counts(&program);
+    }
+
+    Ok(())
+}
+
+fn read_source(path: Option<&Path>) -> eyre::Result<String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display())),
+        None => {
+            let mut src = String::new();
+            std::io::stdin().read_to_string(&mut src).wrap_err("Failed to read from stdin")?;
+            Ok(src)
+        }
+    }
+}
+
+/// Finds the pass with the given name.
+///
+/// Names are matched the same way as `--show-ssa-pass-name`: case insensitively and
+/// ignoring any "After" prefix or trailing colon. Passes which run more than once,
+/// such as "Mem2Reg (1st)" and "Mem2Reg (2nd)", can also be selected without the suffix.
+fn find_pass<'a>(passes: &'a [SsaPass<'a>], name: &str) -> Option<&'a SsaPass<'a>> {
+    let name = normalize_pass_name(name);
+    passes.iter().find(|pass| normalize_pass_name(pass.msg()) == name).or_else(|| {
+        passes.iter().find(|pass| strip_occurrence(&normalize_pass_name(pass.msg())) == name)
+    })
+}
+
+fn normalize_pass_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let name = name.strip_prefix("after ").unwrap_or(&name);
+    let name = name.strip_suffix(':').unwrap_or(name);
+    name.trim().to_string()
+}
+
+/// Strips an occurrence suffix such as " (1st)" from a normalized pass name.
+fn strip_occurrence(name: &str) -> &str {
+    match name.rfind(" (") {
+        Some(index) if name.ends_with(')') => &name[..index],
+        _ => name,
+    }
+}
+
+fn print_opcode_counts(program: &SsaProgramArtifact) {
+    for (name, circuit) in program.names.iter().zip(&program.program.functions) {
+        println!("ACIR opcodes for `{name}`: {}", circuit.opcodes.len());
+    }
+    for (name, function) in
+        program.brillig_names.iter().zip(&program.program.unconstrained_functions)
+    {
+        println!("Brillig opcodes for `{name}`: {}", function.bytecode.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use noirc_evaluator::ssa::primary_passes;
+
+    use super::{find_pass, PassOptions};
+
+    #[test]
+    fn finds_passes_by_name() {
+        let options = PassOptions::default().evaluator_options();
+        let passes = primary_passes(&options);
+
+        let find = |name| find_pass(&passes, name).map(|pass| pass.msg());
+        assert_eq!(find("Flattening"), Some("Flattening"));
+        assert_eq!(find("constant folding"), Some("Constant Folding"));
+        assert_eq!(find("Mem2Reg"), Some("Mem2Reg (1st)"));
+        assert_eq!(find("Mem2Reg (2nd)"), Some("Mem2Reg (2nd)"));
+        assert_eq!(find("Removing Bit Shifts"), Some("After Removing Bit Shifts"));
+        assert_eq!(find("Simplifying"), Some("Simplifying:"));
+        assert_eq!(find("Not a pass"), None);
+    }
+}